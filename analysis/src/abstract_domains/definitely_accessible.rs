@@ -0,0 +1,201 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::PointwiseState;
+use crate::abstract_domains::DefinitelyInitializedState;
+use crate::abstract_domains::place_utils::*;
+use rustc_middle::mir;
+use rustc_middle::ty::TyCtxt;
+use std::collections::{HashMap, HashSet, BTreeSet};
+use std::mem;
+use serde::{Serialize, Serializer};
+use serde::ser::{SerializeMap, SerializeStruct};
+
+/// Borrow information needed to compute the definitely accessible places.
+///
+/// This is usually provided by the Polonius facts of the analyzed body.
+pub trait BorrowedPlaces<'tcx> {
+    /// Returns the places borrowed by the loans that are alive at the start of `location`,
+    /// together with the mutability of each loan.
+    fn get_borrowed_places(&self, location: mir::Location)
+        -> Vec<(mir::Place<'tcx>, mir::Mutability)>;
+}
+
+/// The MIR places that are definitely accessible at a program point
+///
+/// A place can be read if it is definitely initialized and it is not mutably borrowed. It can
+/// also be written if it is not borrowed at all.
+///
+/// Invariant: as in `DefinitelyInitializedState`, we never have a place and any of its
+/// descendants in the same set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DefinitelyAccessibleState<'tcx> {
+    /// Places that are definitely initialized and not mutably borrowed
+    definitely_accessible: HashSet<mir::Place<'tcx>>,
+    /// Places that are definitely initialized and not borrowed
+    definitely_owned: HashSet<mir::Place<'tcx>>,
+}
+
+impl<'tcx> Serialize for DefinitelyAccessibleState<'tcx> {
+    fn serialize<Se: Serializer>(&self, serializer: Se) -> Result<Se::Ok, Se::Error> {
+        let mut state = serializer.serialize_struct("DefinitelyAccessibleState", 2)?;
+        let ordered_accessible: BTreeSet<_> = self.definitely_accessible.iter()
+            .map(|place| format!("{:?}", place))
+            .collect();
+        state.serialize_field("accessible", &ordered_accessible)?;
+        let ordered_owned: BTreeSet<_> = self.definitely_owned.iter()
+            .map(|place| format!("{:?}", place))
+            .collect();
+        state.serialize_field("owned", &ordered_owned)?;
+        state.end()
+    }
+}
+
+impl<'tcx> DefinitelyAccessibleState<'tcx> {
+    /// The places that can be read at the program point
+    pub fn get_read_places(&self) -> &HashSet<mir::Place<'tcx>> {
+        &self.definitely_accessible
+    }
+
+    /// The places that can be written at the program point
+    pub fn get_write_places(&self) -> &HashSet<mir::Place<'tcx>> {
+        &self.definitely_owned
+    }
+}
+
+/// Records the definitely accessible places before every statement and terminator of `mir`.
+pub struct DefinitelyAccessibleAnalysisResult<'a, 'tcx: 'a> {
+    state_before: HashMap<mir::Location, DefinitelyAccessibleState<'tcx>>,
+    // Needed for translation of location to statement/terminator in serialization.
+    mir: &'a mir::Body<'tcx>,
+}
+
+impl<'a, 'tcx: 'a> DefinitelyAccessibleAnalysisResult<'a, 'tcx> {
+    /// Look up the state before the `location`.
+    /// The `location` can point to a statement or terminator.
+    pub fn lookup_before(&self, location: mir::Location) -> Option<&DefinitelyAccessibleState<'tcx>> {
+        self.state_before.get(&location)
+    }
+}
+
+impl<'a, 'tcx: 'a> Serialize for DefinitelyAccessibleAnalysisResult<'a, 'tcx> {
+    /// Serialize the result in the same layout used by `PointwiseState`, without the states on
+    /// the CFG edges.
+    fn serialize<Se: Serializer>(&self, serializer: Se) -> Result<Se::Ok, Se::Error> {
+        let mut map = serializer.serialize_map(Some(self.mir.basic_blocks().len()))?;
+
+        for bb in self.mir.basic_blocks().indices() {
+            let mir::BasicBlockData { ref statements, .. } = self.mir[bb];
+            let mut stmt_vec: Vec<_> = Vec::new();
+            for (statement_index, stmt) in statements.iter().enumerate() {
+                let location = mir::Location {
+                    block: bb,
+                    statement_index,
+                };
+                let state = self.lookup_before(location);
+                stmt_vec.push(("state:", state, format!("statement: {:?}", stmt)));
+            }
+
+            let term_location = self.mir.terminator_loc(bb);
+            let state_before = self.lookup_before(term_location);
+
+            let terminator_str = format!("terminator: {:?}", self.mir[bb].terminator().kind);
+
+            map.serialize_entry(
+                &format!("{:?}", bb),
+                &(
+                    stmt_vec,
+                    "state before terminator:",
+                    state_before,
+                    terminator_str,
+                )
+            )?;
+        }
+        map.end()
+    }
+}
+
+/// Returns the prefix of `place` that ends before its first projection that is not a field or
+/// a downcast. Borrowing through a reference or an index blocks the whole prefix.
+fn get_owned_prefix<'tcx>(tcx: TyCtxt<'tcx>, place: &mir::Place<'tcx>) -> mir::Place<'tcx> {
+    let prefix_len = place.projection.iter()
+        .position(|elem| !matches!(
+            elem,
+            mir::ProjectionElem::Field(..) | mir::ProjectionElem::Downcast(..)
+        ))
+        .unwrap_or(place.projection.len());
+    mir::Place {
+        local: place.local,
+        projection: tcx.intern_place_elems(&place.projection[..prefix_len]),
+    }
+}
+
+/// Removes from `places` all the memory locations of `removed_place`, expanding the places of
+/// which `removed_place` is only a part (see `place_utils::expand`).
+fn remove_place<'tcx>(
+    mir: &mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    places: &mut HashSet<mir::Place<'tcx>>,
+    removed_place: &mir::Place<'tcx>,
+) {
+    let old_places = mem::take(places);
+    for old_place in old_places {
+        if is_prefix(removed_place, &old_place) {
+            // Only a part of `old_place` is removed.
+            places.extend(expand(mir, tcx, &old_place, removed_place));
+        } else if !is_prefix(&old_place, removed_place) {
+            places.insert(old_place);
+        }
+    }
+}
+
+/// Computes the definitely accessible places at every program point of `mir` by removing the
+/// places borrowed by the alive loans from the definitely initialized places.
+pub fn compute_definitely_accessible<'a, 'tcx: 'a, B: BorrowedPlaces<'tcx>>(
+    mir: &'a mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    def_init: &PointwiseState<'a, 'tcx, DefinitelyInitializedState<'a, 'tcx>>,
+    borrowed_places: &B,
+) -> DefinitelyAccessibleAnalysisResult<'a, 'tcx> {
+    let mut state_before = HashMap::new();
+    for (bb, bb_data) in mir.basic_blocks().iter_enumerated() {
+        for statement_index in 0..=bb_data.statements.len() {
+            let location = mir::Location {
+                block: bb,
+                statement_index,
+            };
+            // Unreachable locations have no initialization state.
+            let def_init_state = if let Some(state) = def_init.lookup_before(location) {
+                state
+            } else {
+                continue;
+            };
+            let mut definitely_accessible = def_init_state.get_def_init_places().clone();
+            let mut shared_borrowed = vec![];
+            for (place, mutability) in borrowed_places.get_borrowed_places(location) {
+                let blocked_place = get_owned_prefix(tcx, &place);
+                match mutability {
+                    mir::Mutability::Mut => {
+                        remove_place(mir, tcx, &mut definitely_accessible, &blocked_place)
+                    }
+                    mir::Mutability::Not => shared_borrowed.push(blocked_place),
+                }
+            }
+            let mut definitely_owned = definitely_accessible.clone();
+            for blocked_place in &shared_borrowed {
+                remove_place(mir, tcx, &mut definitely_owned, blocked_place);
+            }
+            state_before.insert(location, DefinitelyAccessibleState {
+                definitely_accessible,
+                definitely_owned,
+            });
+        }
+    }
+    DefinitelyAccessibleAnalysisResult {
+        state_before,
+        mir,
+    }
+}
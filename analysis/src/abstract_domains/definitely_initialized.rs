@@ -16,6 +16,18 @@ use std::fmt;
 use serde::{Serialize, Serializer};
 use serde::ser::SerializeSeq;
 
+/// Number of times a basic block is traversed before its state gets widened
+const WIDENING_THRESHOLD: u32 = 5;
+
+/// Maximum length of the projection of the places kept by widening, unless overridden by the
+/// `ANALYSIS_MAX_PLACE_DEPTH` environment variable
+const DEFAULT_MAX_PLACE_DEPTH: usize = 8;
+
+fn get_max_place_depth() -> usize {
+    std::env::var("ANALYSIS_MAX_PLACE_DEPTH").ok()
+        .and_then(|depth| depth.parse().ok())
+        .unwrap_or(DEFAULT_MAX_PLACE_DEPTH)
+}
 
 /// A set of MIR places that are definitely initialized at a program point
 ///
@@ -220,8 +232,10 @@ impl<'a, 'tcx: 'a> AbstractState<'a, 'tcx> for DefinitelyInitializedState<'a, 't
         }
     }
 
-    fn need_to_widen(_counter: &u32) -> bool {
-        false   //TODO: check
+    /// Places can get arbitrarily deep (e.g. when following a chain of boxes in a loop),
+    /// therefore the place sets are widened after a few iterations
+    fn need_to_widen(counter: &u32) -> bool {
+        *counter > WIDENING_THRESHOLD
     }
 
     /// The lattice join intersects the two place sets
@@ -254,6 +268,17 @@ impl<'a, 'tcx: 'a> AbstractState<'a, 'tcx> for DefinitelyInitializedState<'a, 't
         let other_places = &other.def_init_places;
         propagate_places_fn(self_places, other_places);
         propagate_places_fn(other_places, self_places);
+        // Downcasts of the same enum place to different variants are not
+        // prefixes of each other, but both of them mean that the enum place
+        // is initialized. Places under different downcasts (e.g. the fields
+        // of different variants) are instead not kept at all.
+        for place1 in self_places.iter() {
+            for place2 in other_places.iter() {
+                if let Some(enum_place) = get_common_enum_place(self.tcx, place1, place2) {
+                    intersection.insert(enum_place);
+                }
+            }
+        }
         self.def_init_places = intersection;
 
         if cfg!(debug_assertions) {
@@ -261,13 +286,27 @@ impl<'a, 'tcx: 'a> AbstractState<'a, 'tcx> for DefinitelyInitializedState<'a, 't
         }
     }
 
-    fn widen(&mut self, _previous: &Self) {
-        unimplemented!()
+    /// Joins `previous` and removes the places with a projection longer than the maximum place
+    /// depth. The deeper places cannot be replaced by their prefix, because the other fields of
+    /// the prefix are not known to be initialized.
+    fn widen(&mut self, previous: &Self) {
+        self.join(previous);
+        let max_place_depth = get_max_place_depth();
+        self.def_init_places.retain(|place| place.projection.len() <= max_place_depth);
+
+        if cfg!(debug_assertions) {
+            self.check_invariant();
+        }
     }
 
     fn apply_statement_effect(&mut self, location: mir::Location)-> Result<(), AnalysisError> {
         let statement = &self.mir[location.block].statements[location.statement_index];
         match statement.kind {
+            // The shallow borrows of the guards of `match` expressions are only read by
+            // `FakeRead` statements and do not initialize their target for the verifier.
+            mir::StatementKind::Assign(
+                box (_, mir::Rvalue::Ref(_, mir::BorrowKind::Shallow, _))
+            ) => {}
             mir::StatementKind::Assign(box (ref target, ref source)) => {
                 match source {
                     mir::Rvalue::Repeat(ref operand, _)
@@ -0,0 +1,365 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{AbstractState, AnalysisError};
+use crate::abstract_domains::place_utils::*;
+use rustc_middle::mir;
+use std::collections::{HashSet, BTreeSet};
+use rustc_middle::ty::TyCtxt;
+use rustc_middle::ich::StableHashingContextProvider;
+use rustc_data_structures::{fingerprint::Fingerprint, stable_hasher::{HashStable, StableHasher}};
+use std::mem;
+use std::fmt;
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeSeq;
+
+
+/// A set of MIR places that might be uninitialized at a program point
+///
+/// This is the dual of `DefinitelyInitializedState`: a place that is not
+/// (a part of) a place in the set is definitely initialized.
+///
+/// Invariant: we never have a place and any of its descendants in the
+/// set at the same time. For example, having `x.f` and `x.f.g` in the
+/// set at the same time is illegal.
+#[derive(Clone)]
+pub struct MaybeUninitializedState<'a, 'tcx: 'a> {
+    maybe_uninit_places: HashSet<mir::Place<'tcx>>,
+    mir: &'a mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+}
+
+impl<'a, 'tcx: 'a> fmt::Debug for MaybeUninitializedState<'a, 'tcx> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // ignore tcx & mir
+        f.debug_struct("MaybeUninitializedState")
+            .field("maybe_uninit_places", &self.maybe_uninit_places)
+            .finish()
+    }
+}
+
+impl<'a, 'tcx: 'a> PartialEq for MaybeUninitializedState<'a, 'tcx> {
+    fn eq(&self, other: &Self) -> bool {
+        debug_assert_eq!(
+            {
+                let mut stable_hasher = StableHasher::new();
+                self.mir.hash_stable(
+                    &mut self.tcx.get_stable_hashing_context(),
+                    &mut stable_hasher,
+                );
+                stable_hasher.finish::<Fingerprint>()
+            },
+            {
+                let mut stable_hasher = StableHasher::new();
+                other.mir.hash_stable(
+                    &mut other.tcx.get_stable_hashing_context(),
+                    &mut stable_hasher,
+                );
+                stable_hasher.finish::<Fingerprint>()
+            },
+        );
+        self.maybe_uninit_places == other.maybe_uninit_places
+    }
+}
+
+impl<'a, 'tcx: 'a> Eq for MaybeUninitializedState<'a, 'tcx> {}
+
+impl<'a, 'tcx: 'a> Serialize for MaybeUninitializedState<'a, 'tcx> {
+    fn serialize<Se: Serializer>(&self, serializer: Se) -> Result<Se::Ok, Se::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.maybe_uninit_places.len()))?;
+        let ordered_place_set: BTreeSet<_> = self.maybe_uninit_places.iter().collect();
+        for place in ordered_place_set {
+            seq.serialize_element(&format!("{:?}", place))?;
+        }
+        seq.end()
+    }
+}
+
+
+impl<'a, 'tcx: 'a>  MaybeUninitializedState<'a, 'tcx>  {
+    pub fn get_maybe_uninit_places(&self) -> &HashSet<mir::Place<'tcx>> {
+        &self.maybe_uninit_places
+    }
+
+    /// The top element of the lattice contains all places, meaning all
+    /// locals (which includes all their fields)
+    pub fn new_top(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        let mut places = HashSet::new();
+        for local in mir.local_decls.indices() {
+            places.insert(local.into());
+        }
+        Self {
+            maybe_uninit_places: places,
+            mir,
+            tcx
+        }
+    }
+
+    pub fn is_top(&self) -> bool {
+        if self.maybe_uninit_places.len() == self.mir.local_decls.len() {
+            self.mir.local_decls.indices()
+                .all(|local| self.maybe_uninit_places.contains(&local.into()))
+        } else {
+            false
+        }
+    }
+
+    pub fn check_invariant(&self) {
+        for place1 in self.maybe_uninit_places.iter() {
+            for place2 in self.maybe_uninit_places.iter() {
+                if place1 != place2 {
+                    debug_assert!(
+                        !is_prefix(place1, place2),
+                        "The place {:?} is a prefix of the place {:?}",
+                        place2,
+                        place1
+                    );
+                    debug_assert!(
+                        !is_prefix(place2, place1),
+                        "The place {:?} is a prefix of the place {:?}",
+                        place1,
+                        place2
+                    );
+                }
+            }
+        }
+    }
+
+    /// Sets `place` as (possibly) uninitialized
+    fn set_place_uninitialised(&mut self, place: &mir::Place<'tcx>) {
+        if cfg!(debug_assertions) {
+            self.check_invariant();
+        }
+
+        // First, check that the place is not already marked as
+        // maybe uninitialized.
+        if !self.maybe_uninit_places.iter().any(|current| is_prefix(place, current)) {
+            // To maintain the invariant that we do not have a place and its
+            // prefix in the set, we remove all places for which the given
+            // one is a prefix.
+            self.maybe_uninit_places.retain(|current| !is_prefix(current, place));
+            self.maybe_uninit_places.insert(*place);
+            // If all fields of a struct are maybe uninitialized, just keep
+            // info that the struct is maybe uninitialized.
+            collapse(self.mir, self.tcx, &mut self.maybe_uninit_places, place);
+        }
+
+        if cfg!(debug_assertions) {
+            self.check_invariant();
+        }
+    }
+
+    /// Sets `place` as definitely initialized
+    fn set_place_initialised(&mut self, place: &mir::Place<'tcx>) {
+        if cfg!(debug_assertions) {
+            self.check_invariant();
+        }
+
+        let old_places = mem::take(&mut self.maybe_uninit_places);
+        for old_place in old_places {
+            if is_prefix(place, &old_place) {
+                // We are initializing a field of the place `old_place`.
+                self.maybe_uninit_places.extend(expand(self.mir, self.tcx, &old_place, place));
+            } else if is_prefix(&old_place, place) {
+                // We are initializing a place of which only some fields
+                // are uninitialized. Just remove all uninitialized fields.
+            } else {
+                self.maybe_uninit_places.insert(old_place);
+            }
+        }
+
+        // Check that place is properly removed
+        for place1 in self.maybe_uninit_places.iter() {
+            assert!(
+                !is_prefix(place1, place) && !is_prefix(place, place1),
+                "Bug: failed to ensure that there are no prefixes: place={:?} place1={:?}",
+                place,
+                place1
+            );
+        }
+
+        if cfg!(debug_assertions) {
+            self.check_invariant();
+        }
+    }
+
+    /// If the operand is move, make the place uninitialized
+    fn apply_operand_effect(&mut self, operand: &mir::Operand<'tcx>) {
+        if let mir::Operand::Move(place) = operand {
+            self.set_place_uninitialised(place);
+        }
+    }
+}
+
+impl<'a, 'tcx: 'a> AbstractState<'a, 'tcx> for MaybeUninitializedState<'a, 'tcx> {
+    /// The bottom element of the lattice contains no places
+    fn new_bottom(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        Self {
+            maybe_uninit_places: HashSet::new(),
+            mir,
+            tcx
+        }
+    }
+
+    fn is_bottom(&self) -> bool {
+        self.maybe_uninit_places.is_empty()
+    }
+
+    fn new_initial(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        // All locals except for the arguments are uninitialized
+        // they are guaranteed to be disjoint and not prefixes of each other,
+        // therefore insert them directly
+        let mut places = HashSet::new();
+        places.insert(mir::RETURN_PLACE.into());
+        for local in mir.vars_and_temps_iter() {
+            places.insert(local.into());
+        }
+        Self {
+            maybe_uninit_places: places,
+            mir,
+            tcx
+        }
+    }
+
+    fn need_to_widen(_counter: &u32) -> bool {
+        false
+    }
+
+    /// The lattice join unions the two place sets
+    fn join(&mut self, other: &Self) {
+        if cfg!(debug_assertions) {
+            self.check_invariant();
+            other.check_invariant();
+        }
+
+        for place in other.maybe_uninit_places.iter() {
+            self.set_place_uninitialised(place);
+        }
+
+        if cfg!(debug_assertions) {
+            self.check_invariant();
+        }
+    }
+
+    fn widen(&mut self, _previous: &Self) {
+        unimplemented!()
+    }
+
+    fn apply_statement_effect(&mut self, location: mir::Location)-> Result<(), AnalysisError> {
+        let statement = &self.mir[location.block].statements[location.statement_index];
+        match statement.kind {
+            mir::StatementKind::Assign(box (ref target, ref source)) => {
+                match source {
+                    mir::Rvalue::Repeat(ref operand, _)
+                    | mir::Rvalue::Cast(_, ref operand, _)
+                    | mir::Rvalue::UnaryOp(_, ref operand)
+                    | mir::Rvalue::Use(ref operand) => {
+                        self.apply_operand_effect(operand);
+                    }
+                    mir::Rvalue::BinaryOp(_, box (ref operand1, ref operand2))
+                    | mir::Rvalue::CheckedBinaryOp(_, box (ref operand1, ref operand2)) => {
+                        self.apply_operand_effect(operand1);
+                        self.apply_operand_effect(operand2);
+                    }
+                    mir::Rvalue::Aggregate(_, ref operands) => {
+                        for operand in operands.iter() {
+                            self.apply_operand_effect(operand);
+                        }
+                    }
+                    _ => {}
+                }
+
+                self.set_place_initialised(target);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn apply_terminator_effect(&self, location: mir::Location)
+        -> Result<Vec<(mir::BasicBlock, Self)>, AnalysisError> {
+
+        let mut new_state = self.clone();
+        let mut res_vec = Vec::new();
+        let terminator = self.mir[location.block].terminator();
+        match terminator.kind {
+            mir::TerminatorKind::SwitchInt { ref discr, .. } => {
+                // only operand has an effect on maybe uninitialized places, all successors
+                // get the same state
+                new_state.apply_operand_effect(discr);
+
+                for &bb in terminator.successors() {
+                    res_vec.push((bb, new_state.clone()));
+                }
+            }
+            mir::TerminatorKind::Drop { ref place, target, unwind } => {
+                new_state.set_place_uninitialised(place);
+                res_vec.push((target, new_state));
+
+                if let Some(bb) = unwind {
+                    // imprecision for error states
+                    res_vec.push((bb, Self::new_top(self.mir, self.tcx)));
+                }
+            }
+            mir::TerminatorKind::DropAndReplace { ref place, ref value, target, unwind } => {
+                new_state.set_place_uninitialised(place);
+                new_state.apply_operand_effect(value);
+                new_state.set_place_initialised(place);
+                res_vec.push((target, new_state));
+
+                if let Some(bb) = unwind {
+                    // imprecision for error states
+                    res_vec.push((bb, Self::new_top(self.mir, self.tcx)));
+                }
+            }
+            mir::TerminatorKind::Call { ref func, ref args, ref destination, cleanup, .. } => {
+                for arg in args.iter() {
+                    new_state.apply_operand_effect(arg);
+                }
+                new_state.apply_operand_effect(func);
+                if let Some((place, bb)) = destination {
+                    new_state.set_place_initialised(place);
+                    res_vec.push((*bb, new_state));
+                }
+
+                if let Some(bb) = cleanup {
+                    // imprecision for error states
+                    res_vec.push((bb, Self::new_top(self.mir, self.tcx)));
+                }
+            }
+            mir::TerminatorKind::Assert { ref cond, target, cleanup, .. } => {
+                new_state.apply_operand_effect(cond);
+                res_vec.push((target, new_state));
+
+                if let Some(bb) = cleanup {
+                    // imprecision for error states
+                    res_vec.push((bb, Self::new_top(self.mir, self.tcx)));
+                }
+            }
+            mir::TerminatorKind::Yield { ref value, resume, drop, .. } => { // TODO: resume_arg?
+                new_state.apply_operand_effect(value);
+                res_vec.push((resume, new_state));
+
+                if let Some(bb) = drop {
+                    // imprecision for error states
+                    res_vec.push((bb, Self::new_top(self.mir, self.tcx)));
+                }
+            }
+            mir::TerminatorKind::InlineAsm { .. } =>
+                return Err(AnalysisError::UnsupportedStatement(location)),
+
+            _ => {
+                for &bb in terminator.successors() {
+                    // no operation -> no change of state
+                    res_vec.push((bb, self.clone()));
+                }
+            }
+        }
+
+        Ok(res_vec)
+    }
+}
@@ -5,10 +5,19 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 mod reaching_definitions;
+mod place_reaching_definitions;
 mod definitely_initialized;
+mod maybe_uninitialized;
+mod definitely_accessible;
 mod pcs;
 mod place_utils;
 
 pub use reaching_definitions::ReachingDefsState;
+pub use place_reaching_definitions::{PlaceReachingDefsState, PlaceDef};
 pub use definitely_initialized::DefinitelyInitializedState;
+pub use maybe_uninitialized::MaybeUninitializedState;
+pub use definitely_accessible::{
+    DefinitelyAccessibleState, DefinitelyAccessibleAnalysisResult, BorrowedPlaces,
+    compute_definitely_accessible,
+};
 pub use pcs::PCSState;
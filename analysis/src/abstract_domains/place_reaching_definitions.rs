@@ -0,0 +1,292 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{AbstractState, AnalysisError};
+use crate::abstract_domains::place_utils::*;
+use crate::serialization_utils::location_to_stmt_str;
+use rustc_middle::mir;
+use rustc_middle::ty::TyCtxt;
+use rustc_middle::ich::StableHashingContextProvider;
+use rustc_data_structures::{fingerprint::Fingerprint, stable_hasher::{HashStable, StableHasher}};
+use std::collections::{HashMap, HashSet, BTreeMap, BTreeSet};
+use std::fmt;
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeMap;
+
+#[derive(Hash, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PlaceDef {
+    /// The place was assigned at the location
+    Assignment(mir::Location),
+    /// The place was moved out at the location
+    Move(mir::Location),
+}
+
+/// A set of definition locations per MIR place, meaning that the place
+/// might still have the value which was assigned or moved out at the location.
+///
+/// Unlike `ReachingDefsState`, the definitions are tracked for places and
+/// not only for locals, and moves are definitions as well. The definitions
+/// of a place that has no entry are those of its longest prefix that has one.
+/// An empty set means that the place still has the value it had at the
+/// beginning of the function.
+#[derive(Clone)]
+pub struct PlaceReachingDefsState<'a, 'tcx: 'a> {
+    reaching_defs: HashMap<mir::Place<'tcx>, HashSet<PlaceDef>>,
+    mir: &'a mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+}
+
+impl<'a, 'tcx: 'a> fmt::Debug for PlaceReachingDefsState<'a, 'tcx> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // ignore tcx & mir
+        f.debug_struct("PlaceReachingDefsState")
+            .field("reaching_defs", &self.reaching_defs)
+            .finish()
+    }
+}
+
+impl<'a, 'tcx: 'a> PartialEq for PlaceReachingDefsState<'a, 'tcx> {
+    fn eq(&self, other: &Self) -> bool {
+        debug_assert_eq!(
+            {
+                let mut stable_hasher = StableHasher::new();
+                self.mir.hash_stable(
+                    &mut self.tcx.get_stable_hashing_context(),
+                    &mut stable_hasher,
+                );
+                stable_hasher.finish::<Fingerprint>()
+            },
+            {
+                let mut stable_hasher = StableHasher::new();
+                other.mir.hash_stable(
+                    &mut other.tcx.get_stable_hashing_context(),
+                    &mut stable_hasher,
+                );
+                stable_hasher.finish::<Fingerprint>()
+            },
+        );
+        self.reaching_defs == other.reaching_defs
+    }
+}
+
+impl<'a, 'tcx: 'a> Eq for PlaceReachingDefsState<'a, 'tcx> {}
+
+impl<'a, 'tcx: 'a> Serialize for PlaceReachingDefsState<'a, 'tcx> {
+    fn serialize<Se: Serializer>(&self, serializer: Se) -> Result<Se::Ok, Se::Error> {
+        let mut map = serializer.serialize_map(Some(self.reaching_defs.len()))?;
+        let ordered_defs_map: BTreeMap<_, _> = self.reaching_defs.iter().collect();
+        for (place, def_set) in ordered_defs_map {
+            let ordered_def_set: BTreeSet<_> = def_set.iter().collect();
+            let mut def_vec = Vec::new();
+            for def in ordered_def_set {
+                match def {
+                    PlaceDef::Assignment(l) => {
+                        let stmt = location_to_stmt_str(*l, self.mir);
+                        def_vec.push(format!("assigned at {:?}: {}", l, stmt));
+                    }
+                    PlaceDef::Move(l) => {
+                        let stmt = location_to_stmt_str(*l, self.mir);
+                        def_vec.push(format!("moved at {:?}: {}", l, stmt));
+                    }
+                }
+            }
+            map.serialize_entry(&format!("{:?}", place), &def_vec)?;
+        }
+        map.end()
+    }
+}
+
+impl<'a, 'tcx: 'a> PlaceReachingDefsState<'a, 'tcx> {
+    /// The definitions that may reach `place`: those of its longest prefix
+    /// (including itself) that has an entry.
+    fn get_place_defs(&self, place: &mir::Place<'tcx>) -> HashSet<PlaceDef> {
+        self.reaching_defs.iter()
+            .filter(|(current, _)| is_prefix(place, current))
+            .max_by_key(|(current, _)| current.projection.len())
+            .map(|(_, defs)| defs.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the locations at which `place`, or a part of it, may have
+    /// been moved out without being assigned again.
+    pub fn get_reaching_moves(&self, place: &mir::Place<'tcx>) -> BTreeSet<mir::Location> {
+        let mut defs = self.get_place_defs(place);
+        for (current, current_defs) in self.reaching_defs.iter() {
+            if is_prefix(current, place) {
+                defs.extend(current_defs);
+            }
+        }
+        defs.into_iter()
+            .filter_map(|def| match def {
+                PlaceDef::Move(location) => Some(location),
+                PlaceDef::Assignment(_) => None,
+            })
+            .collect()
+    }
+
+    /// Sets `def` as the only definition of `place` and of all its fields
+    fn set_place_def(&mut self, place: &mir::Place<'tcx>, def: PlaceDef) {
+        self.reaching_defs.retain(|current, _| !is_prefix(current, place));
+        let mut def_set = HashSet::new();
+        def_set.insert(def);
+        self.reaching_defs.insert(*place, def_set);
+    }
+
+    /// If the operand is move, record the move of the place at `location`
+    fn apply_operand_effect(&mut self, operand: &mir::Operand<'tcx>, location: mir::Location) {
+        if let mir::Operand::Move(place) = operand {
+            self.set_place_def(place, PlaceDef::Move(location));
+        }
+    }
+}
+
+impl<'a, 'tcx: 'a> AbstractState<'a, 'tcx> for PlaceReachingDefsState<'a, 'tcx> {
+    /// The bottom element of the lattice contains no definitions
+    fn new_bottom(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        Self {
+            reaching_defs: HashMap::new(),
+            mir,
+            tcx,
+        }
+    }
+
+    fn is_bottom(&self) -> bool {
+        self.reaching_defs.iter().all(|(_, set)| set.is_empty())
+    }
+
+    /// At the beginning of the function no place has been assigned or moved
+    fn new_initial(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        Self::new_bottom(mir, tcx)
+    }
+
+    fn need_to_widen(_counter: &u32) -> bool {
+        // only consider static information (locations) => no lattice of infinite height
+        false
+    }
+
+    /// The lattice join unions the definitions of each place. A place that
+    /// has an entry in only one of the states gets the definitions of its
+    /// longest prefix in the other one.
+    fn join(&mut self, other: &Self) {
+        let places: HashSet<_> = self.reaching_defs.keys()
+            .chain(other.reaching_defs.keys())
+            .cloned()
+            .collect();
+        let mut reaching_defs = HashMap::new();
+        for place in places {
+            let mut def_set = self.get_place_defs(&place);
+            def_set.extend(other.get_place_defs(&place));
+            reaching_defs.insert(place, def_set);
+        }
+        self.reaching_defs = reaching_defs;
+    }
+
+    fn widen(&mut self, _previous: &Self) {
+        // locations are static info => cannot grow infinitely => widening should not be needed
+        unimplemented!()
+    }
+
+    fn apply_statement_effect(&mut self, location: mir::Location)
+        -> Result<(), AnalysisError> {
+
+        let statement = &self.mir[location.block].statements[location.statement_index];
+        if let mir::StatementKind::Assign(box (ref target, ref source)) = statement.kind {
+            match source {
+                mir::Rvalue::Repeat(ref operand, _)
+                | mir::Rvalue::Cast(_, ref operand, _)
+                | mir::Rvalue::UnaryOp(_, ref operand)
+                | mir::Rvalue::Use(ref operand) => {
+                    self.apply_operand_effect(operand, location);
+                }
+                mir::Rvalue::BinaryOp(_, box (ref operand1, ref operand2))
+                | mir::Rvalue::CheckedBinaryOp(_, box (ref operand1, ref operand2)) => {
+                    self.apply_operand_effect(operand1, location);
+                    self.apply_operand_effect(operand2, location);
+                }
+                mir::Rvalue::Aggregate(_, ref operands) => {
+                    for operand in operands.iter() {
+                        self.apply_operand_effect(operand, location);
+                    }
+                }
+                _ => {}
+            }
+            self.set_place_def(target, PlaceDef::Assignment(location));
+        }
+
+        Ok(())
+    }
+
+    fn apply_terminator_effect(&self, location: mir::Location)
+        -> Result<Vec<(mir::BasicBlock, Self)>, AnalysisError> {
+
+        let mut new_state = self.clone();
+        let mut res_vec = Vec::new();
+        let terminator = self.mir[location.block].terminator();
+        match terminator.kind {
+            mir::TerminatorKind::SwitchInt { ref discr, .. } => {
+                new_state.apply_operand_effect(discr, location);
+                for &bb in terminator.successors() {
+                    res_vec.push((bb, new_state.clone()));
+                }
+            }
+            mir::TerminatorKind::DropAndReplace { ref place, ref value, target, unwind } => {
+                new_state.apply_operand_effect(value, location);
+                if let Some(bb) = unwind {
+                    // error state -> be conservative & keep the old definitions
+                    res_vec.push((bb, new_state.clone()));
+                }
+                new_state.set_place_def(place, PlaceDef::Assignment(location));
+                res_vec.push((target, new_state));
+            }
+            mir::TerminatorKind::Call { ref func, ref args, ref destination, cleanup, .. } => {
+                for arg in args.iter() {
+                    new_state.apply_operand_effect(arg, location);
+                }
+                new_state.apply_operand_effect(func, location);
+                if let Some(bb) = cleanup {
+                    // error state -> be conservative & add destination as possible reaching def
+                    // while keeping all others
+                    let mut cleanup_state = new_state.clone();
+                    if let Some((place, _)) = destination {
+                        let mut def_set = cleanup_state.get_place_defs(place);
+                        def_set.insert(PlaceDef::Assignment(location));
+                        cleanup_state.reaching_defs.insert(*place, def_set);
+                    }
+                    res_vec.push((bb, cleanup_state));
+                }
+                if let Some((place, bb)) = destination {
+                    new_state.set_place_def(place, PlaceDef::Assignment(location));
+                    res_vec.push((*bb, new_state));
+                }
+            }
+            mir::TerminatorKind::Assert { ref cond, target, cleanup, .. } => {
+                new_state.apply_operand_effect(cond, location);
+                res_vec.push((target, new_state.clone()));
+                if let Some(bb) = cleanup {
+                    res_vec.push((bb, new_state));
+                }
+            }
+            mir::TerminatorKind::Yield { ref value, resume, drop, .. } => {
+                new_state.apply_operand_effect(value, location);
+                res_vec.push((resume, new_state.clone()));
+                if let Some(bb) = drop {
+                    res_vec.push((bb, new_state));
+                }
+            }
+            mir::TerminatorKind::InlineAsm { .. } => {
+                return Err(AnalysisError::UnsupportedStatement(location));
+            }
+            _ => {
+                for &bb in terminator.successors() {
+                    // no assignment or move -> no change of state
+                    res_vec.push((bb, self.clone()));
+                }
+            }
+        }
+
+        Ok(res_vec)
+    }
+}
@@ -27,9 +27,10 @@ pub(crate) fn is_prefix(place: &mir::Place, potential_prefix: &mir::Place) -> bo
 }
 
 /// Expands a place `x.f.g` of type struct into a vector of places for
-/// each of the struct's fields `{x.f.g.f, x.f.g.g, x.f.g.h}`. If
-/// `without_field` is not `None`, then omits that field from the final
-/// vector.
+/// each of the struct's fields `{x.f.g.f, x.f.g.g, x.f.g.h}`. A downcast
+/// place `(x.f.g as V)` of an enum is expanded into the fields of the
+/// variant `V`. If `without_field` is not `None`, then omits that field
+/// from the final vector.
 pub(crate) fn expand_struct_place<'tcx>(
     place: &mir::Place<'tcx>,
     mir: &mir::Body<'tcx>,
@@ -38,49 +39,50 @@ pub(crate) fn expand_struct_place<'tcx>(
 ) -> Vec<mir::Place<'tcx>> {
     let mut places = Vec::new();
     let typ = place.ty(mir, tcx);
-    if typ.variant_index.is_some() {
-        // Downcast is a no-op.
-    } else {
-        match typ.ty.kind() {
-            ty::Adt(def, substs) => {
-                assert!(
-                    def.is_struct(),
-                    "Only structs can be expanded. Got def={:?}.",
-                    def
-                );
-                let variant = def.non_enum_variant();
-                for (index, field_def) in variant.fields.iter().enumerate() {
-                    if Some(index) != without_field {
-                        let field = mir::Field::from_usize(index);
-                        let field_place = tcx.mk_place_field(*place, field, field_def.ty(tcx, substs));
-                        places.push(field_place);
-                    }
-                }
-            }
-            ty::Tuple(slice) => {
-                for (index, arg) in slice.iter().enumerate() {
-                    if Some(index) != without_field {
-                        let field = mir::Field::from_usize(index);
-                        let field_place = tcx.mk_place_field(*place, field, arg.expect_ty());
-                        places.push(field_place);
-                    }
-                }
-            },
-            ty::Ref(_region, _ty, _) => match without_field {
-                Some(without_field) => {
-                    assert_eq!(
-                        without_field, 0,
-                        "References have only a single “field”."
+    match typ.ty.kind() {
+        ty::Adt(def, substs) => {
+            let variant = match typ.variant_index {
+                Some(variant_index) => &def.variants[variant_index],
+                None => {
+                    assert!(
+                        def.is_struct(),
+                        "Only structs can be expanded. Got def={:?}.",
+                        def
                     );
+                    def.non_enum_variant()
                 }
-                None => {
-                    places.push(tcx.mk_place_deref(*place));
+            };
+            for (index, field_def) in variant.fields.iter().enumerate() {
+                if Some(index) != without_field {
+                    let field = mir::Field::from_usize(index);
+                    let field_place = tcx.mk_place_field(*place, field, field_def.ty(tcx, substs));
+                    places.push(field_place);
                 }
-            },
-            ref ty => {
-                unimplemented!("ty={:?}", ty);
             }
         }
+        ty::Tuple(slice) => {
+            for (index, arg) in slice.iter().enumerate() {
+                if Some(index) != without_field {
+                    let field = mir::Field::from_usize(index);
+                    let field_place = tcx.mk_place_field(*place, field, arg.expect_ty());
+                    places.push(field_place);
+                }
+            }
+        },
+        ty::Ref(_region, _ty, _) => match without_field {
+            Some(without_field) => {
+                assert_eq!(
+                    without_field, 0,
+                    "References have only a single “field”."
+                );
+            }
+            None => {
+                places.push(tcx.mk_place_deref(*place));
+            }
+        },
+        ref ty => {
+            unimplemented!("ty={:?}", ty);
+        }
     }
     places
 }
@@ -103,6 +105,8 @@ pub(crate) fn expand_one_level<'tcx>(
             (new_current_place, places)
         }
         mir::ProjectionElem::Downcast(_symbol, variant) => {
+            // The other variants do not result in other places: they share
+            // the memory of the enum.
             let kind = &current_place.ty(mir, tcx).ty.kind();
             if let ty::TyKind::Adt(adt, _) = kind {
                 (tcx.mk_place_downcast(current_place, adt, variant), Vec::new())
@@ -160,7 +164,9 @@ pub(crate) fn expand<'tcx>(
 
 /// Try to collapse all places in `places` by following the
 /// `guide_place`. This function is basically the reverse of
-/// `expand_struct_place`.
+/// `expand_struct_place`. A downcast place `(x as V)` is collapsed into
+/// the enum place `x`, which also removes the places under the other
+/// variants of `x`.
 pub(crate) fn collapse<'tcx>(
     mir: &mir::Body<'tcx>,
     tcx: TyCtxt<'tcx>,
@@ -183,9 +189,7 @@ pub(crate) fn collapse<'tcx>(
             recurse(mir, tcx, places, new_current_place, guide_place);
             expansion.push(new_current_place);
             if expansion.iter().all(|place| places.contains(place)) {
-                for place in expansion {
-                    places.remove(&place);
-                }
+                places.retain(|place| !is_prefix(place, &current_place));
                 places.insert(current_place);
             } else {
                 return;
@@ -194,3 +198,25 @@ pub(crate) fn collapse<'tcx>(
     }
     recurse(mir, tcx, places, guide_place.local.into(), guide_place);
 }
+
+/// If `place1` and `place2` are downcasts of the same place to different
+/// variants, returns that place. For example, the result for `(x.f as Some)`
+/// and `(x.f as None)` is `x.f`.
+pub(crate) fn get_common_enum_place<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    place1: &mir::Place<'tcx>,
+    place2: &mir::Place<'tcx>,
+) -> Option<mir::Place<'tcx>> {
+    match (place1.projection.split_last(), place2.projection.split_last()) {
+        (
+            Some((mir::ProjectionElem::Downcast(_, variant1), base1)),
+            Some((mir::ProjectionElem::Downcast(_, variant2), base2)),
+        ) if place1.local == place2.local && base1 == base2 && variant1 != variant2 => {
+            Some(mir::Place {
+                local: place1.local,
+                projection: tcx.intern_place_elems(base1),
+            })
+        }
+        _ => None,
+    }
+}
@@ -72,7 +72,10 @@ impl<'a, 'tcx: 'a> Analyzer<'tcx> {
                     block: bb,
                     statement_index: 0,
                 };
-                state_before_block.widen(p_state.lookup_before(location).unwrap())
+                // the state of the previous iteration is missing if the block was never reached
+                if let Some(previous) = p_state.lookup_before(location) {
+                    state_before_block.widen(previous);
+                }
             }
 
             let statements = &mir[bb].statements;
@@ -1,4 +1,5 @@
 #![feature(rustc_private)]
+#![feature(box_patterns)]
 
 /// Source: https://github.com/rust-lang/miri/blob/master/benches/helpers/miri_helper.rs
 
@@ -10,19 +11,188 @@ extern crate rustc_interface;
 extern crate rustc_session;
 
 use rustc_ast::ast;
-use rustc_middle::ty;
+use rustc_middle::{mir, ty};
 use rustc_hir::def_id::DefId;
 use rustc_driver::Compilation;
 use rustc_interface::{interface, Queries};
 use rustc_session::Attribute;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use polonius_engine::{AllFacts, Algorithm, Atom, FactTypes, Output};
 
 use analysis::Analyzer;
-use analysis::abstract_domains::{ReachingDefsState, DefinitelyInitializedState};
+use analysis::abstract_domains::{
+    ReachingDefsState, PlaceReachingDefsState, DefinitelyInitializedState, MaybeUninitializedState,
+    BorrowedPlaces, compute_definitely_accessible,
+};
 
 struct OurCompilerCalls {
     args: Vec<String>,
 }
 
+/// Declares an index type for the interned Polonius atoms.
+macro_rules! atom_type {
+    ($typ:ident) => {
+        #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Copy, Hash, Debug)]
+        struct $typ(usize);
+
+        impl From<usize> for $typ {
+            fn from(index: usize) -> $typ {
+                $typ(index)
+            }
+        }
+
+        impl From<$typ> for usize {
+            fn from(atom: $typ) -> usize {
+                atom.0
+            }
+        }
+
+        impl Atom for $typ {
+            fn index(self) -> usize {
+                self.0
+            }
+        }
+    };
+}
+
+atom_type!(Origin);
+atom_type!(Loan);
+atom_type!(Point);
+atom_type!(Variable);
+atom_type!(MovePath);
+
+#[derive(Debug, Clone, Copy)]
+struct PoloniusFactTypes;
+
+impl FactTypes for PoloniusFactTypes {
+    type Origin = Origin;
+    type Loan = Loan;
+    type Point = Point;
+    type Variable = Variable;
+    type Path = MovePath;
+}
+
+/// The directory in which rustc dumps the Polonius input facts (see `-Znll-facts-dir`).
+fn get_nll_facts_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("analysis-driver-nll-facts-{}", std::process::id()))
+}
+
+/// Reads the tab-separated facts of `facts_dir/<facts_type>.facts`, interning every atom.
+fn load_facts(
+    interner: &mut HashMap<String, usize>,
+    facts_dir: &Path,
+    facts_type: &str,
+) -> Vec<Vec<usize>> {
+    let facts_file = facts_dir.join(format!("{}.facts", facts_type));
+    let content = fs::read_to_string(&facts_file)
+        .unwrap_or_else(|err| panic!("failed to read file {:?} with err: {}", facts_file, err));
+    content.lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.split('\t').map(|atom| {
+            let atom = atom.trim_matches('"').to_owned();
+            let next_index = interner.len();
+            *interner.entry(atom).or_insert(next_index)
+        }).collect())
+        .collect()
+}
+
+/// The loans computed by Polonius from the facts dumped by rustc for a function.
+struct PoloniusLoans<'a, 'tcx> {
+    mir: &'a mir::Body<'tcx>,
+    /// The loans alive at each interned point.
+    borrow_live_at: HashMap<usize, Vec<usize>>,
+    /// The location at which each loan is created.
+    loan_location: HashMap<usize, mir::Location>,
+    interner: HashMap<String, usize>,
+}
+
+impl<'a, 'tcx> PoloniusLoans<'a, 'tcx> {
+    fn new(mir: &'a mir::Body<'tcx>, facts_dir: &Path) -> Self {
+        let mut interner = HashMap::new();
+        let mut facts = AllFacts::<PoloniusFactTypes>::default();
+        macro_rules! load {
+            ($field:ident, 2) => {
+                facts.$field = load_facts(&mut interner, facts_dir, stringify!($field))
+                    .into_iter().map(|a| (a[0].into(), a[1].into())).collect();
+            };
+            ($field:ident, 3) => {
+                facts.$field = load_facts(&mut interner, facts_dir, stringify!($field))
+                    .into_iter().map(|a| (a[0].into(), a[1].into(), a[2].into())).collect();
+            };
+        }
+        load!(borrow_region, 3);
+        facts.universal_region = load_facts(&mut interner, facts_dir, "universal_region")
+            .into_iter().map(|a| a[0].into()).collect();
+        load!(cfg_edge, 2);
+        load!(killed, 2);
+        load!(outlives, 3);
+        load!(invalidates, 2);
+        load!(var_used_at, 2);
+        load!(var_defined_at, 2);
+        load!(var_dropped_at, 2);
+        load!(use_of_var_derefs_origin, 2);
+        load!(drop_of_var_derefs_origin, 2);
+        load!(child_path, 2);
+        load!(path_is_var, 2);
+        load!(path_assigned_at_base, 2);
+        load!(path_moved_at_base, 2);
+        load!(path_accessed_at_base, 2);
+        load!(known_subset, 2);
+        load!(placeholder, 2);
+
+        let output = Output::compute(&facts, Algorithm::Naive, false);
+        let borrow_live_at = output.borrow_live_at.iter()
+            .map(|(point, loans)| (point.index(), loans.iter().map(|loan| loan.index()).collect()))
+            .collect();
+
+        let mut mid_point_location = HashMap::new();
+        for (bb, bb_data) in mir.basic_blocks().iter_enumerated() {
+            for statement_index in 0..=bb_data.statements.len() {
+                let location = mir::Location { block: bb, statement_index };
+                if let Some(&point) = interner.get(&format!("Mid({:?})", location)) {
+                    mid_point_location.insert(point, location);
+                }
+            }
+        }
+        let loan_location = facts.borrow_region.iter()
+            .filter_map(|(_, loan, point)| {
+                mid_point_location.get(&point.index()).map(|&location| (loan.index(), location))
+            })
+            .collect();
+
+        PoloniusLoans { mir, borrow_live_at, loan_location, interner }
+    }
+}
+
+impl<'a, 'tcx> BorrowedPlaces<'tcx> for PoloniusLoans<'a, 'tcx> {
+    fn get_borrowed_places(&self, location: mir::Location)
+        -> Vec<(mir::Place<'tcx>, mir::Mutability)>
+    {
+        let loans = self.interner.get(&format!("Start({:?})", location))
+            .and_then(|point| self.borrow_live_at.get(point));
+        let mut borrowed_places = vec![];
+        for loan in loans.into_iter().flatten() {
+            let loan_location = if let Some(loan_location) = self.loan_location.get(loan) {
+                *loan_location
+            } else {
+                continue;
+            };
+            let statements = &self.mir[loan_location.block].statements;
+            if loan_location.statement_index >= statements.len() {
+                continue;
+            }
+            if let mir::StatementKind::Assign(
+                box (_, mir::Rvalue::Ref(_, kind, place))
+            ) = &statements[loan_location.statement_index].kind {
+                borrowed_places.push((*place, kind.to_mutbl_lossy()));
+            }
+        }
+        borrowed_places
+    }
+}
+
 fn get_attribute<'tcx>(tcx: ty::TyCtxt<'tcx>, def_id: DefId, segment1: &str, segment2: &str) -> Option<&'tcx Attribute> {
     tcx.get_attrs(def_id).iter().find(|attr| match &attr.kind {
         ast::AttrKind::Normal(ast::AttrItem {
@@ -80,6 +250,13 @@ impl rustc_driver::Callbacks for OurCompilerCalls {
                             Err(e) => eprintln!("{}", e.to_pretty_str(&body))
                         }
                     },
+                    "PlaceReachingDefsState" => {
+                        let result = analyzer.run_fwd_analysis::<PlaceReachingDefsState>(&body);
+                        match result {
+                            Ok(state) => print!("{}", serde_json::to_string_pretty(&state).unwrap()),
+                            Err(e) => eprintln!("{}", e.to_pretty_str(&body))
+                        }
+                    },
                     "DefinitelyInitializedState" => {
                         let result = analyzer.run_fwd_analysis::<DefinitelyInitializedState>(&body);
                         match result {
@@ -87,6 +264,27 @@ impl rustc_driver::Callbacks for OurCompilerCalls {
                             Err(e) => eprintln!("{}", e.to_pretty_str(&body))
                         }
                     },
+                    "MaybeUninitializedState" => {
+                        let result = analyzer.run_fwd_analysis::<MaybeUninitializedState>(&body);
+                        match result {
+                            Ok(state) => print!("{}", serde_json::to_string_pretty(&state).unwrap()),
+                            Err(e) => eprintln!("{}", e.to_pretty_str(&body))
+                        }
+                    },
+                    "DefinitelyAccessibleState" => {
+                        let result = analyzer.run_fwd_analysis::<DefinitelyInitializedState>(&body);
+                        match result {
+                            Ok(def_init) => {
+                                let facts_dir = get_nll_facts_dir().join(
+                                    tcx.def_path(local_def_id.to_def_id()).to_filename_friendly_no_crate()
+                                );
+                                let loans = PoloniusLoans::new(&body, &facts_dir);
+                                let state = compute_definitely_accessible(&body, tcx, &def_init, &loans);
+                                print!("{}", serde_json::to_string_pretty(&state).unwrap())
+                            },
+                            Err(e) => eprintln!("{}", e.to_pretty_str(&body))
+                        }
+                    },
                     _ => panic!("Unknown domain argument: {}", abstract_domain)
                 }
             }
@@ -102,7 +300,9 @@ impl rustc_driver::Callbacks for OurCompilerCalls {
 ///
 /// Give arguments to the analyzer by prefixing them with '--AD'
 /// A abstract domain has to be provided by using '--ADdomain=' (without spaces), e.g.:
-/// --ADdomain=ReachingDefsState or --ADdomain=DefinitelyInitializedState
+/// --ADdomain=ReachingDefsState, --ADdomain=PlaceReachingDefsState,
+/// --ADdomain=DefinitelyInitializedState, --ADdomain=MaybeUninitializedState or
+/// --ADdomain=DefinitelyAccessibleState
 fn main() {
     let mut compiler_args= Vec::new();
     let mut callback_args= Vec::new();
@@ -118,10 +318,21 @@ fn main() {
     compiler_args.push("-Zcrate-attr=feature(register_tool)".to_owned());
     compiler_args.push("-Zcrate-attr=register_tool(analyzer)".to_owned());
 
+    // The definitely accessible places are computed from the Polonius facts
+    let nll_facts_dir = get_nll_facts_dir();
+    let needs_nll_facts = callback_args.iter().any(|a| a == "--ADdomain=DefinitelyAccessibleState");
+    if needs_nll_facts {
+        compiler_args.push("-Znll-facts".to_owned());
+        compiler_args.push(format!("-Znll-facts-dir={}", nll_facts_dir.display()));
+    }
+
     let mut callbacks = OurCompilerCalls { args: callback_args };
     // Invoke compiler, and handle return code.
     let exit_code = rustc_driver::catch_with_exit_code(move || {
         rustc_driver::RunCompiler::new(&compiler_args, &mut callbacks).run()
     });
+    if needs_nll_facts {
+        let _ = fs::remove_dir_all(&nll_facts_dir);
+    }
     std::process::exit(exit_code)
 }
@@ -70,5 +70,8 @@ fn test_runner(_tests: &[&()]) {
     env::set_var("RUST_BACKTRACE", "1");
 
     run_tests("ui", "tests/test_cases/reaching_definitions", vec!["--ADdomain=ReachingDefsState".into()]);
+    run_tests("ui", "tests/test_cases/place_reaching_definitions", vec!["--ADdomain=PlaceReachingDefsState".into()]);
     run_tests("ui", "tests/test_cases/definitely_initialized", vec!["--ADdomain=DefinitelyInitializedState".into()]);
+    run_tests("ui", "tests/test_cases/maybe_uninitialized", vec!["--ADdomain=MaybeUninitializedState".into()]);
+    run_tests("ui", "tests/test_cases/definitely_accessible", vec!["--ADdomain=DefinitelyAccessibleState".into()]);
 }
\ No newline at end of file
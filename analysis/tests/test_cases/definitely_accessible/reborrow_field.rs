@@ -0,0 +1,12 @@
+struct T {
+    f: u32,
+    g: u32,
+}
+
+#[analyzer::run]
+fn main() {
+    let mut t = T { f: 1, g: 2 };
+    let x = &mut t.f;
+    let y = t.g;
+    *x = y;
+}
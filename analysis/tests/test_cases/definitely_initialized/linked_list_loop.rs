@@ -0,0 +1,11 @@
+struct Node {
+    next: Option<Box<Node>>,
+}
+
+#[analyzer::run]
+fn main() {
+    let mut list = Node { next: None };
+    loop {
+        list = Node { next: Some(Box::new(list)) };
+    }
+}
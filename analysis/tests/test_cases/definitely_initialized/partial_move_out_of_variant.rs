@@ -0,0 +1,16 @@
+struct T {
+    f: u32,
+}
+
+enum E {
+    A(T, T),
+    B,
+}
+
+#[analyzer::run]
+fn main() {
+    let mut e = E::A(T { f: 1 }, T { f: 2 });
+    if let E::A(_t, _) = e {
+        e = E::B;
+    }
+}
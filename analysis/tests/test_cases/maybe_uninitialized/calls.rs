@@ -0,0 +1,12 @@
+#[analyzer::run]
+fn main() {
+    let a = f(-1);
+    let b = f(1);
+
+    let c = a + b;
+    let d = f(c);
+}
+
+fn f(x: i32) -> i32 {
+    return x;
+}
\ No newline at end of file
@@ -0,0 +1,7 @@
+#[analyzer::run]
+fn main() {
+    let x = Box::new(1);
+    let y = x;
+    drop(y);
+    let _z = Box::new(2);
+}
@@ -0,0 +1,7 @@
+#[analyzer::run]
+fn main() {
+    let x = 123;
+    let y: u32;
+    y = x;
+}
+
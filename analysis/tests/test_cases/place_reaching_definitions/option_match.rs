@@ -0,0 +1,12 @@
+struct T {
+    f: u32,
+}
+
+#[analyzer::run]
+fn main() {
+    let x = Some((T { f: 1 }, T { f: 2 }));
+    let _a = match x {
+        Some((y, _)) => y.f,
+        None => 0,
+    };
+}
@@ -17,13 +17,18 @@ use serde::Deserialize;
 pub struct Optimizations {
     pub inline_constant_functions: bool,
     pub delete_unused_predicates: bool,
+    pub delete_unused_items: bool,
     pub optimize_folding: bool,
+    pub simplify_expressions: bool,
     pub remove_empty_if: bool,
     pub purify_vars: bool,
     pub fix_quantifiers: bool,
+    pub remove_dead_assignments: bool,
     pub remove_unused_vars: bool,
     pub remove_trivial_assertions: bool,
+    pub remove_unreachable_blocks: bool,
     pub clean_cfg: bool,
+    pub inline_pure_functions: bool,
 }
 
 impl Optimizations {
@@ -31,13 +36,18 @@ impl Optimizations {
         Optimizations {
             inline_constant_functions: false,
             delete_unused_predicates: false,
+            delete_unused_items: false,
             optimize_folding: false,
+            simplify_expressions: false,
             remove_empty_if: false,
             purify_vars: false,
             fix_quantifiers: false,
+            remove_dead_assignments: false,
             remove_unused_vars: false,
             remove_trivial_assertions: false,
+            remove_unreachable_blocks: false,
             clean_cfg: false,
+            inline_pure_functions: false,
         }
     }
 
@@ -45,13 +55,20 @@ impl Optimizations {
         Optimizations{
             inline_constant_functions: true,
             delete_unused_predicates: true,
+            delete_unused_items: true,
             optimize_folding: true,
+            simplify_expressions: true,
             remove_empty_if: true,
             purify_vars: true,
             fix_quantifiers: true,
+            remove_dead_assignments: true,
             remove_unused_vars: true,
             remove_trivial_assertions: true,
+            // pruning dead code has to be enabled explicitly
+            remove_unreachable_blocks: false,
             clean_cfg: true,
+            // inlining pure functions has to be enabled explicitly
+            inline_pure_functions: false,
         }
     }
 }
@@ -67,6 +84,7 @@ lazy_static! {
         settings.set_default("check_foldunfold_state", false).unwrap();
         settings.set_default("check_overflows", false).unwrap();
         settings.set_default("check_panics", true).unwrap();
+        settings.set_default("panic_free", false).unwrap();
         settings.set_default("encode_unsigned_num_constraint", false).unwrap();
         settings.set_default("simplify_encoding", true).unwrap();
         settings.set_default("log_dir", "./log/").unwrap();
@@ -77,6 +95,7 @@ lazy_static! {
         settings.set_default("dump_reborrowing_dag_in_debug_info", false).unwrap();
         settings.set_default("dump_borrowck_info", false).unwrap();
         settings.set_default("dump_viper_program", false).unwrap();
+        settings.set_default("dump_viper_per_fn", false).unwrap();
         settings.set_default("foldunfold_state_filter", "").unwrap();
         settings.set_default("contracts_lib", "").unwrap();
         settings.set_default::<Vec<String>>("extra_jvm_args", vec![]).unwrap();
@@ -84,20 +103,34 @@ lazy_static! {
         settings.set_default("quiet", false).unwrap();
         settings.set_default("assert_timeout", 10_000).unwrap();
         settings.set_default("use_more_complete_exhale", true).unwrap();
+        settings.set_default("counterexample", false).unwrap();
+        settings.set_default("force_full_verification", false).unwrap();
         settings.set_default("skip_unsupported_features", false).unwrap();
         settings.set_default("allow_unreachable_unsupported_code", false).unwrap();
+        settings.set_default("report_dead_specs", false).unwrap();
+        settings.set_default("deny_dead_specs", false).unwrap();
         settings.set_default("no_verify", false).unwrap();
+        settings.set_default("check_specs_only", false).unwrap();
         settings.set_default("full_compilation", false).unwrap();
+        settings.set_default("export_specs", false).unwrap();
         settings.set_default("json_communication", false).unwrap();
         settings.set_default("json_communication", false).unwrap();
+        settings.set_default("verify_parallel_jobs", 1).unwrap();
+        settings.set_default("verification_timeout_per_method_secs", 0).unwrap();
+        settings.set_default("allow_timeouts", false).unwrap();
         settings.set_default("optimizations","all").unwrap();
         settings.set_default("intern_names", true).unwrap();
         settings.set_default("enable_purification_optimization", false).unwrap();
+        settings.set_default("inline_pure_functions_max_calls", 1).unwrap();
+        settings.set_default("enable_local_snapshot_purification", false).unwrap();
         settings.set_default("enable_manual_axiomatization", false).unwrap();
+        settings.set_default("default_fuel", 2).unwrap();
 
         settings.set_default("print_desugared_specs", false).unwrap();
         settings.set_default("print_typeckd_specs", false).unwrap();
         settings.set_default("print_collected_verification_items", false).unwrap();
+        settings.set_default("verify_only_def_paths", "").unwrap();
+        settings.set_default("verify_only_files", "").unwrap();
         settings.set_default("hide_uuids", false).unwrap();
 
         // Flags for debugging Prusti that can change verification results.
@@ -168,7 +201,9 @@ pub fn check_foldunfold_state() -> bool {
     read_setting("check_foldunfold_state")
 }
 
-/// The Viper backend that should be used for the verification
+/// The Viper backend that should be used for the verification, either
+/// `Silicon` or `Carbon`. The encoding avoids the features of Viper that the
+/// selected backend does not support (see `vir::BackendCapabilities`).
 pub fn viper_backend() -> String {
     read_setting::<String>("viper_backend")
         .to_lowercase()
@@ -181,6 +216,13 @@ pub fn check_panics() -> bool {
     read_setting("check_panics")
 }
 
+/// Should we check that all functions are free of panics, as if they had the
+/// `#[panic_free]` attribute? This also checks the panics of the library
+/// methods that are called, like `Option::unwrap`, and overrides `check_panics`.
+pub fn panic_free() -> bool {
+    read_setting("panic_free")
+}
+
 /// Should we simplify the encoding before passing it to Viper?
 pub fn simplify_encoding() -> bool {
     read_setting("simplify_encoding")
@@ -222,6 +264,12 @@ pub fn dump_viper_program() -> bool {
     read_setting("dump_viper_program")
 }
 
+/// Should we dump the encoding of each method and function in its own file, with comments
+/// pointing to the Rust code and a table of the positions?
+pub fn dump_viper_per_fn() -> bool {
+    read_setting("dump_viper_per_fn")
+}
+
 /// The Viper backend that should be used for the verification
 pub fn foldunfold_state_filter() -> String {
     read_setting("foldunfold_state_filter")
@@ -272,6 +320,54 @@ pub fn use_more_complete_exhale() -> bool {
     read_setting("use_more_complete_exhale")
 }
 
+/// Should Prusti ask the verifier for a counterexample and report it along
+/// with verification errors.
+pub fn counterexample() -> bool {
+    read_setting("counterexample")
+}
+
+/// The file in which Prusti stores the hashes of the procedures that have
+/// been verified successfully, so that they are not verified again as long as
+/// their encoding does not change. If not set, every procedure is verified.
+pub fn cache_path() -> Option<String> {
+    read_optional_setting("cache_path")
+}
+
+/// Should Prusti verify all procedures, ignoring the results stored in the
+/// verification cache (see `cache_path`). The cache is still updated.
+pub fn force_full_verification() -> bool {
+    read_setting("force_full_verification")
+}
+
+/// The file to which Prusti writes a JSON report with the result of each
+/// verified item, its errors and the time spent on it. If not set, no report
+/// is written.
+pub fn json_report() -> Option<String> {
+    read_optional_setting("json_report")
+}
+
+/// Verify only the procedures whose def path (e.g. `my_crate::module::function`)
+/// is in this comma-separated list. The contracts of the other procedures are
+/// still used when they are called. If empty, this filter is disabled.
+pub fn verify_only_def_paths() -> Vec<String> {
+    split_list(read_setting("verify_only_def_paths"))
+}
+
+/// Verify only the procedures defined in the files of this comma-separated
+/// list. This is used by `cargo prusti --diff` to verify only the files
+/// changed since the last run. If empty, this filter is disabled.
+pub fn verify_only_files() -> Vec<String> {
+    split_list(read_setting("verify_only_files"))
+}
+
+fn split_list(list: String) -> Vec<String> {
+    list.split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| item.to_string())
+        .collect()
+}
+
 /// Should Prusti print the items collected for verification.
 pub fn print_collected_verification_items() -> bool {
     read_setting("print_collected_verification_items")
@@ -323,6 +419,29 @@ pub fn json_communication() -> bool {
     read_setting("json_communication")
 }
 
+/// The number of verification requests that Prusti submits concurrently to
+/// the server (see `server_address`). If greater than one, each Viper method
+/// is verified by a separate request. Without a server, the setting is
+/// ignored.
+pub fn verify_parallel_jobs() -> usize {
+    read_setting("verify_parallel_jobs")
+}
+
+/// The time limit (in seconds) for the verification of each Viper method, or 0
+/// for no limit. When set, each method is verified by a separate request, so
+/// that the methods that exceed the limit are reported as timed out while the
+/// other methods are still verified. Only Silicon supports the limit.
+pub fn verification_timeout_per_method_secs() -> u64 {
+    read_setting("verification_timeout_per_method_secs")
+}
+
+/// When enabled, the methods whose verification timed out (see
+/// `verification_timeout_per_method_secs`) are reported with warnings instead
+/// of errors, so that they do not make the verification fail.
+pub fn allow_timeouts() -> bool {
+    read_setting("allow_timeouts")
+}
+
 /// Disable mangling of generated Viper names.
 ///
 /// **Note:** This is very likely to result in invalid programs being
@@ -367,13 +486,18 @@ pub fn optimizations() -> Optimizations {
             "all" => opt = Optimizations::all_enabled(),
             "inline_constant_functions" => opt.inline_constant_functions = true,
             "delete_unused_predicates" => opt.delete_unused_predicates = true,
+            "delete_unused_items" => opt.delete_unused_items = true,
             "optimize_folding" => opt.optimize_folding = true,
+            "simplify_expressions" => opt.simplify_expressions = true,
             "remove_empty_if" => opt.remove_empty_if = true,
             "purify_vars" => opt.purify_vars = true,
             "fix_quantifiers" => opt.fix_quantifiers = true,
+            "remove_dead_assignments" => opt.remove_dead_assignments = true,
             "remove_unused_vars" => opt.remove_unused_vars = true,
             "remove_trivial_assertions" => opt.remove_trivial_assertions = true,
+            "remove_unreachable_blocks" => opt.remove_unreachable_blocks = true,
             "clean_cfg" => opt.clean_cfg = true,
+            "inline_pure_functions" => opt.inline_pure_functions = true,
             _ => warn!("Ignoring Unkown optimization '{}'", trimmed)
         }
     }
@@ -386,6 +510,18 @@ pub fn enable_purification_optimization() -> bool {
     read_setting("enable_purification_optimization")
 }
 
+/// The maximum number of calls of a pure function for which the `inline_pure_functions`
+/// optimization inlines its body.
+pub fn inline_pure_functions_max_calls() -> usize {
+    read_setting("inline_pure_functions_max_calls")
+}
+
+/// Replace local variables of struct or tuple type that are only read by pure
+/// snapshot variables, removing their permissions and fold/unfold statements.
+pub fn enable_local_snapshot_purification() -> bool {
+    read_setting("enable_local_snapshot_purification")
+}
+
 /// Enable manual axiomatization of pure functions.
 ///
 /// **Note:** this is currently very incomplete and may introduce unsoudnesses.
@@ -393,6 +529,13 @@ pub fn enable_manual_axiomatization() -> bool {
     read_setting("enable_manual_axiomatization")
 }
 
+/// How many times the definition of a recursive pure function can be unfolded starting from
+/// each of its calls, unless the function specifies its own fuel with `#[fuel(n)]`.
+/// A fuel of zero disables the fuel-based encoding of recursive pure functions.
+pub fn default_fuel() -> u32 {
+    read_setting("default_fuel")
+}
+
 /// Replace the given basic blocks with ``assume false``.
 pub fn delete_basic_blocks() -> Vec<String> {
     read_setting("delete_basic_blocks")
@@ -409,16 +552,44 @@ pub fn allow_unreachable_unsupported_code() -> bool {
     read_setting("allow_unreachable_unsupported_code")
 }
 
+/// Should we warn about the specifications that are never used, i.e. the
+/// specifications of trusted functions that are never called, the predicates
+/// that are never referenced and the external specifications that do not
+/// specify any function?
+pub fn report_dead_specs() -> bool {
+    read_setting::<bool>("report_dead_specs") || deny_dead_specs()
+}
+
+/// Should the specifications that are never used be reported as errors instead
+/// of warnings? This implies `report_dead_specs`.
+pub fn deny_dead_specs() -> bool {
+    read_setting("deny_dead_specs")
+}
+
 /// Skip the verification
 pub fn no_verify() -> bool {
     read_setting("no_verify")
 }
 
+/// Only check the specifications, without verifying the bodies of the
+/// functions: the refinement checks of trait method implementations are
+/// verified, and the functions whose precondition is unsatisfiable are
+/// reported with warnings.
+pub fn check_specs_only() -> bool {
+    read_setting("check_specs_only")
+}
+
 /// Continue the compilation and generate the binary after Prusti terminates
 pub fn full_compilation() -> bool {
     read_setting("full_compilation")
 }
 
+/// Export the specifications of a library crate to a file next to its compiled
+/// metadata, so that the crates depending on it can use them.
+pub fn export_specs() -> bool {
+    read_setting("export_specs")
+}
+
 /// Intern Viper identifiers to shorten them when possible.
 pub fn intern_names() -> bool {
     read_setting("intern_names")
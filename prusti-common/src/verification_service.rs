@@ -32,12 +32,29 @@ impl Default for ViperBackendConfig {
                 if config::use_more_complete_exhale() {
                     verifier_args.push("--enableMoreCompleteExhale".to_string());
                 }
+                if config::counterexample() {
+                    verifier_args.extend(vec![
+                        "--counterexample".to_string(),
+                        "variables".to_string(),
+                    ]);
+                }
                 verifier_args.extend(vec![
                     "--assertTimeout".to_string(),
                     config::assert_timeout().to_string(),
                     "--logLevel".to_string(),
                     "ERROR".to_string(),
+                    // Report all errors of a method, not only the first ones.
+                    "--numberOfErrorsToReport".to_string(),
+                    "0".to_string(),
                 ]);
+                let timeout = config::verification_timeout_per_method_secs();
+                if timeout > 0 {
+                    // The requests verify one method each (see `verify_in_parallel`)
+                    verifier_args.extend(vec![
+                        "--timeout".to_string(),
+                        timeout.to_string(),
+                    ]);
+                }
             }
             VerificationBackend::Carbon => {
                 verifier_args.extend(vec![
@@ -37,6 +37,8 @@ pub enum Expr {
     Cond(Box<Expr>, Box<Expr>, Box<Expr>, Position),
     /// ForAll: variables, triggers, body
     ForAll(Vec<LocalVar>, Vec<Trigger>, Box<Expr>, Position),
+    /// Exists: variables, triggers, body
+    Exists(Vec<LocalVar>, Vec<Trigger>, Box<Expr>, Position),
     /// let variable == (expr) in body
     LetExpr(LocalVar, Box<Expr>, Box<Expr>, Position),
     /// FuncApp: function_name, args, formal_args, return_type, Viper position
@@ -159,6 +161,20 @@ impl fmt::Display for Expr {
                     .join(", "),
                 body.to_string()
             ),
+            Expr::Exists(ref vars, ref triggers, ref body, ref _pos) => write!(
+                f,
+                "exists {} {} :: {}",
+                vars.iter()
+                    .map(|x| format!("{:?}", x))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                triggers
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                body.to_string()
+            ),
             Expr::LetExpr(ref var, ref expr, ref body, ref _pos) => write!(
                 f,
                 "(let {:?} == ({}) in {})",
@@ -282,6 +298,7 @@ impl Expr {
             | Expr::Unfolding(_, _, _, _, _, p)
             | Expr::Cond(_, _, _, p)
             | Expr::ForAll(_, _, _, p)
+            | Expr::Exists(_, _, _, p)
             | Expr::LetExpr(_, _, _, p)
             | Expr::FuncApp(_, _, _, _, p)
             | Expr::DomainFuncApp(_, _, p)
@@ -312,6 +329,7 @@ impl Expr {
             },
             Expr::Cond(x, y, z, _) => Expr::Cond(x, y, z, pos),
             Expr::ForAll(x, y, z, _) => Expr::ForAll(x, y, z, pos),
+            Expr::Exists(x, y, z, _) => Expr::Exists(x, y, z, pos),
             Expr::LetExpr(x, y, z, _) => Expr::LetExpr(x, y, z, pos),
             Expr::FuncApp(x, y, z, k, _) => Expr::FuncApp(x, y, z, k, pos),
             Expr::DomainFuncApp(x,y,_) => Expr::DomainFuncApp(x,y,pos),
@@ -416,6 +434,25 @@ impl Expr {
         Expr::BinOp(BinOpKind::Mod, box left, box right, Position::default())
     }
 
+    /// Encode Rust division, which truncates towards zero. This is *not* Viper
+    /// division, which rounds the quotient so that the remainder is not negative.
+    pub fn rust_div(left: Expr, right: Expr) -> Self {
+        Expr::ite(
+            Expr::or(
+                Expr::ge_cmp(left.clone(), 0.into()),
+                Expr::eq_cmp(Expr::modulo(left.clone(), right.clone()), 0.into()),
+            ),
+            // positive value or left % right == 0
+            Expr::div(left.clone(), right.clone()),
+            // negative value
+            Expr::ite(
+                Expr::gt_cmp(right.clone(), 0.into()),
+                Expr::add(Expr::div(left.clone(), right.clone()), 1.into()),
+                Expr::sub(Expr::div(left, right), 1.into()),
+            ),
+        )
+    }
+
     /// Encode Rust reminder. This is *not* Viper modulo.
     pub fn rem(left: Expr, right: Expr) -> Self {
         let abs_right = Expr::ite(
@@ -455,6 +492,10 @@ impl Expr {
         Expr::ForAll(vars, triggers, box body, Position::default())
     }
 
+    pub fn exists(vars: Vec<LocalVar>, triggers: Vec<Trigger>, body: Expr) -> Self {
+        Expr::Exists(vars, triggers, box body, Position::default())
+    }
+
     pub fn ite(guard: Expr, left: Expr, right: Expr) -> Self {
         Expr::Cond(box guard, box left, box right, Position::default())
     }
@@ -1038,7 +1079,7 @@ impl Expr {
                 assert_eq!(typ1, typ2, "expr: {:?}", self);
                 typ1
             }
-            Expr::ForAll(..) => {
+            Expr::ForAll(..) | Expr::Exists(..) => {
                 &Type::Bool
             }
             Expr::MagicWand(..) |
@@ -1067,7 +1108,8 @@ impl Expr {
                 Expr::Const(Const::Bool(_), _) |
                 Expr::UnaryOp(UnaryOpKind::Not, _, _) |
                 Expr::FuncApp(_, _, _, Type::Bool, _) |
-                Expr::ForAll(..) => {
+                Expr::ForAll(..) |
+                Expr::Exists(..) => {
                     true
                 },
                 Expr::BinOp(kind, _, _, _) => {
@@ -1195,6 +1237,29 @@ impl Expr {
                     )
                 }
             }
+
+            fn fold_exists(
+                &mut self,
+                vars: Vec<LocalVar>,
+                triggers: Vec<Trigger>,
+                body: Box<Expr>,
+                pos: Position,
+            ) -> Expr {
+                if vars.contains(&self.target.get_base()) {
+                    // Do nothing
+                    Expr::Exists(vars, triggers, body, pos)
+                } else {
+                    Expr::Exists(
+                        vars,
+                        triggers
+                            .into_iter()
+                            .map(|x| x.replace_place(self.target, self.replacement))
+                            .collect(),
+                        self.fold_boxed(body),
+                        pos,
+                    )
+                }
+            }
         }
         let typaram_substs = match (&target, &replacement) {
             (Expr::Local(tv, _), Expr::Local(rv, _)) => {
@@ -1325,6 +1390,33 @@ impl Expr {
                     pos,
                 )
             }
+
+            fn fold_exists(
+                &mut self,
+                vars: Vec<LocalVar>,
+                triggers: Vec<Trigger>,
+                body: Box<Expr>,
+                pos: Position,
+            ) -> Expr {
+                for (src, dst) in self.replacements.iter() {
+                    if vars.contains(&src.get_base()) || vars.contains(&dst.get_base()) {
+                        unimplemented!(
+                            "replace_multiple_places doesn't handle replacements that conflict \
+                            with quantified variables"
+                        )
+                    }
+                }
+
+                Expr::Exists(
+                    vars,
+                    triggers
+                        .into_iter()
+                        .map(|x| x.replace_multiple_places(self.replacements))
+                        .collect(),
+                    self.fold_boxed(body),
+                    pos,
+                )
+            }
         }
         let typaram_substs = replacements.iter().map(
             |(target, replacement)| {
@@ -1403,6 +1495,7 @@ impl Expr {
                     | Expr::AddrOf(..)
                     | Expr::LabelledOld(..)
                     | Expr::ForAll(..)
+                    | Expr::Exists(..)
                     | Expr::LetExpr(..)
                     | Expr::FuncApp(..)
                     | Expr::DomainFuncApp(..)
@@ -1571,6 +1664,10 @@ impl PartialEq for Expr {
                 Expr::ForAll(ref self_vars, ref self_triggers, box ref self_expr, _),
                 Expr::ForAll(ref other_vars, ref other_triggers, box ref other_expr, _),
             ) => (self_vars, self_triggers, self_expr) == (other_vars, other_triggers, other_expr),
+            (
+                Expr::Exists(ref self_vars, ref self_triggers, box ref self_expr, _),
+                Expr::Exists(ref other_vars, ref other_triggers, box ref other_expr, _),
+            ) => (self_vars, self_triggers, self_expr) == (other_vars, other_triggers, other_expr),
             (
                 Expr::LetExpr(ref self_var, box ref self_def, box ref self_expr, _),
                 Expr::LetExpr(ref other_var, box ref other_def, box ref other_expr, _),
@@ -1628,7 +1725,8 @@ impl Hash for Expr {
             Expr::Cond(box ref cond, box ref then_expr, box ref else_expr, _) => {
                 (cond, then_expr, else_expr).hash(state)
             }
-            Expr::ForAll(ref vars, ref triggers, box ref expr, _) => {
+            Expr::ForAll(ref vars, ref triggers, box ref expr, _)
+            | Expr::Exists(ref vars, ref triggers, box ref expr, _) => {
                 (vars, triggers, expr).hash(state)
             }
             Expr::LetExpr(ref var, box ref def, box ref expr, _) => (var, def, expr).hash(state),
@@ -170,6 +170,15 @@ pub trait ExprFolder: Sized {
     ) -> Expr {
         Expr::ForAll(x, y, self.fold_boxed(z), p)
     }
+    fn fold_exists(
+        &mut self,
+        x: Vec<LocalVar>,
+        y: Vec<Trigger>,
+        z: Box<Expr>,
+        p: Position,
+    ) -> Expr {
+        Expr::Exists(x, y, self.fold_boxed(z), p)
+    }
     fn fold_let_expr(
         &mut self,
         var: LocalVar,
@@ -278,6 +287,7 @@ pub fn default_fold_expr<T: ExprFolder>(this: &mut T, e: Expr) -> Expr {
         },
         Expr::Cond(x, y, z, p) => this.fold_cond(x, y, z, p),
         Expr::ForAll(x, y, z, p) => this.fold_forall(x, y, z, p),
+        Expr::Exists(x, y, z, p) => this.fold_exists(x, y, z, p),
         Expr::LetExpr(x, y, z, p) => this.fold_let_expr(x, y, z, p),
         Expr::FuncApp(x, y, z, k, p) => this.fold_func_app(x, y, z, k, p),
         Expr::DomainFuncApp(x, y, p) => this.fold_domain_func_app(x,y,p),
@@ -376,6 +386,18 @@ pub trait ExprWalker: Sized {
         }
         self.walk(body);
     }
+    fn walk_exists(
+        &mut self,
+        vars: &Vec<LocalVar>,
+        _triggers: &Vec<Trigger>,
+        body: &Expr,
+        _pos: &Position
+    ) {
+        for var in vars {
+            self.walk_local_var(var);
+        }
+        self.walk(body);
+    }
     fn walk_let_expr(&mut self, bound_var: &LocalVar, expr: &Expr, body: &Expr, _pos: &Position) {
         self.walk_local_var(bound_var);
         self.walk(expr);
@@ -455,6 +477,7 @@ pub fn default_walk_expr<T: ExprWalker>(this: &mut T, e: &Expr) {
         },
         Expr::Cond(ref x, ref y, ref z, ref p) => this.walk_cond(x, y, z, p),
         Expr::ForAll(ref x, ref y, ref z, ref p) => this.walk_forall(x, y, z, p),
+        Expr::Exists(ref x, ref y, ref z, ref p) => this.walk_exists(x, y, z, p),
         Expr::LetExpr(ref x, ref y, ref z, ref p) => this.walk_let_expr(x, y, z, p),
         Expr::FuncApp(ref x, ref y, ref z, ref k, ref p) => this.walk_func_app(x, y, z, k, p),
         Expr::DomainFuncApp(ref x, ref y,ref p) => this.walk_domain_func_app(x,y,p),
@@ -595,6 +618,15 @@ pub trait FallibleExprFolder: Sized {
     ) -> Result<Expr, Self::Error> {
         Ok(Expr::ForAll(x, y, self.fallible_fold_boxed(z)?, p))
     }
+    fn fallible_fold_exists(
+        &mut self,
+        x: Vec<LocalVar>,
+        y: Vec<Trigger>,
+        z: Box<Expr>,
+        p: Position,
+    ) -> Result<Expr, Self::Error> {
+        Ok(Expr::Exists(x, y, self.fallible_fold_boxed(z)?, p))
+    }
     fn fallible_fold_let_expr(
         &mut self,
         var: LocalVar,
@@ -719,6 +751,7 @@ pub fn default_fallible_fold_expr<U, T: FallibleExprFolder<Error=U>>(
         },
         Expr::Cond(x, y, z, p) => this.fallible_fold_cond(x, y, z, p),
         Expr::ForAll(x, y, z, p) => this.fallible_fold_forall(x, y, z, p),
+        Expr::Exists(x, y, z, p) => this.fallible_fold_exists(x, y, z, p),
         Expr::LetExpr(x, y, z, p) => this.fallible_fold_let_expr(x, y, z, p),
         Expr::FuncApp(x, y, z, k, p) => this.fallible_fold_func_app(x, y, z, k, p),
         Expr::DomainFuncApp(x, y, p) => this.fallible_fold_domain_func_app(x,y,p),
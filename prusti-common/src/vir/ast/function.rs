@@ -20,6 +20,25 @@ pub struct Function {
 
 impl fmt::Display for Function {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_with_comments(f, None)
+    }
+}
+
+impl Function {
+    /// Like `to_string`, but before each pre- and postcondition and before the body prints the
+    /// comment returned by `comment`, if any.
+    pub fn to_string_with_comments<F: Fn(&Expr) -> Option<String>>(&self, comment: F) -> String {
+        let mut result = String::new();
+        self.write_with_comments(&mut result, Some(&comment)).unwrap();
+        result
+    }
+
+    fn write_with_comments(
+        &self,
+        f: &mut dyn fmt::Write,
+        comment: Option<&dyn Fn(&Expr) -> Option<String>>,
+    ) -> fmt::Result {
+        let comment_of = |expr: &Expr| comment.and_then(|comment| comment(expr));
         write!(f, "function {}(", self.name)?;
         let mut first = true;
         for arg in &self.formal_args {
@@ -31,13 +50,22 @@ impl fmt::Display for Function {
         }
         writeln!(f, "): {}", self.return_type)?;
         for pre in &self.pres {
+            if let Some(text) = comment_of(pre) {
+                writeln!(f, "  // {}", text)?;
+            }
             writeln!(f, "  requires {}", pre)?;
         }
         for post in &self.posts {
+            if let Some(text) = comment_of(post) {
+                writeln!(f, "  // {}", text)?;
+            }
             writeln!(f, "  ensures {}", post)?;
         }
         if let Some(ref body) = self.body {
             writeln!(f, "{{")?;
+            if let Some(text) = comment_of(body) {
+                writeln!(f, "\t// {}", text)?;
+            }
             writeln!(f, "\t{}", body)?;
             write!(f, "}}")?;
         }
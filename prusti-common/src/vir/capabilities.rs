@@ -0,0 +1,28 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use viper::VerificationBackend;
+
+/// The features of the Viper language that the encoding may use, which depend
+/// on the backend that verifies the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackendCapabilities {
+    /// Whether the triggers of quantifiers may contain `unfolding` expressions.
+    pub unfolding_in_triggers: bool,
+}
+
+impl BackendCapabilities {
+    pub fn of(backend: VerificationBackend) -> Self {
+        match backend {
+            VerificationBackend::Silicon => BackendCapabilities {
+                unfolding_in_triggers: true,
+            },
+            VerificationBackend::Carbon => BackendCapabilities {
+                unfolding_in_triggers: false,
+            },
+        }
+    }
+}
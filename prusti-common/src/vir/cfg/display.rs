@@ -5,10 +5,29 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::fmt;
+use crate::vir::ast::Stmt;
 use crate::vir::cfg::method::*;
 
 impl fmt::Display for CfgMethod {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_with_comments(f, None)
+    }
+}
+
+impl CfgMethod {
+    /// Like `to_string`, but before each statement prints the comment returned by `comment`,
+    /// if any.
+    pub fn to_string_with_comments<F: Fn(&Stmt) -> Option<String>>(&self, comment: F) -> String {
+        let mut result = String::new();
+        self.write_with_comments(&mut result, Some(&comment)).unwrap();
+        result
+    }
+
+    fn write_with_comments(
+        &self,
+        f: &mut dyn fmt::Write,
+        comment: Option<&dyn Fn(&Stmt) -> Option<String>>,
+    ) -> fmt::Result {
         writeln!(
             f,
             "method {}({} args)",
@@ -35,6 +54,9 @@ impl fmt::Display for CfgMethod {
                 self.basic_blocks_labels[index], index
             )?;
             for stmt in &block.stmts {
+                if let Some(text) = comment.and_then(|comment| comment(stmt)) {
+                    writeln!(f, "    // {}", text)?;
+                }
                 writeln!(f, "    {}", stmt)?;
             }
             writeln!(f, "    {:?}", block.successor)?;
@@ -69,3 +91,30 @@ impl fmt::Display for CfgBlockIndex {
         write!(f, "cfg:{}", self.block_index)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{Expr, Position};
+
+    #[test]
+    fn print_comments_before_statements() {
+        let mut method = CfgMethod::new("test".to_string(), 0, vec![], vec![], vec![]);
+        let block = method.add_block("start", vec![
+            Stmt::Comment("first".to_string()),
+            Stmt::Assert(Expr::from(true), Position::new(3, 5, 1)),
+        ]);
+        method.set_successor(block, Successor::Return);
+
+        let text = method.to_string_with_comments(|stmt| match stmt {
+            Stmt::Assert(_, pos) => Some(format!("test.rs:{}", pos.line())),
+            _ => None,
+        });
+
+        let lines: Vec<_> = text.lines().map(|line| line.trim()).collect();
+        let comment_index = lines.iter().position(|line| *line == "// test.rs:3").unwrap();
+        assert!(lines[comment_index + 1].starts_with("assert"));
+        assert_eq!(lines.iter().filter(|line| line.starts_with("// test.rs")).count(), 1);
+        assert_eq!(method.to_string_with_comments(|_| None), method.to_string());
+    }
+}
@@ -30,7 +30,7 @@ pub struct CfgMethod {
     #[serde(skip)]
     pub(super) reserved_labels: HashSet<String>,
     pub basic_blocks: Vec<CfgBlock>, // FIXME: Hack, should be pub(super).
-    pub(super) basic_blocks_labels: Vec<String>,
+    pub(in super::super) basic_blocks_labels: Vec<String>,
     #[serde(skip)]
     fresh_var_index: i32,
     #[serde(skip)]
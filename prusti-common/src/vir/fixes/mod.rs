@@ -7,5 +7,7 @@
 //! Fix the potentially broken encoding.
 
 pub use self::ghost_vars::fix_ghost_vars;
+pub use self::triggers::{fix_function_triggers, fix_triggers};
 
 mod ghost_vars;
+mod triggers;
@@ -0,0 +1,123 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Fix the triggers of quantifiers that the backend does not support.
+
+use super::super::ast;
+use super::super::cfg;
+use super::super::BackendCapabilities;
+use std::mem;
+
+/// Fold-unfold may wrap the terms of the triggers in `unfolding` expressions,
+/// which Silicon accepts but Carbon rejects. When the backend does not support
+/// them, we replace each `unfolding P(..) in e` in a trigger with `e`; the
+/// term matches the same instances, because the unfolding does not change
+/// the value of `e`.
+pub fn fix_triggers(
+    mut method: cfg::CfgMethod,
+    capabilities: &BackendCapabilities,
+) -> cfg::CfgMethod {
+    if capabilities.unfolding_in_triggers {
+        return method;
+    }
+    let mut fixer = TriggerFixer;
+    let mut sentinel_stmt = ast::Stmt::Comment(String::from("moved out stmt"));
+    for block in &mut method.basic_blocks {
+        for stmt in &mut block.stmts {
+            mem::swap(&mut sentinel_stmt, stmt);
+            sentinel_stmt = ast::StmtFolder::fold(&mut fixer, sentinel_stmt);
+            mem::swap(&mut sentinel_stmt, stmt);
+        }
+        if let cfg::Successor::GotoSwitch(ref mut guarded_targets, _) = block.successor {
+            for (guard, _) in guarded_targets {
+                let old_guard = mem::replace(guard, true.into());
+                *guard = ast::ExprFolder::fold(&mut fixer, old_guard);
+            }
+        }
+    }
+    method
+}
+
+/// Like `fix_triggers`, for the specification and the body of a function.
+pub fn fix_function_triggers(
+    mut function: ast::Function,
+    capabilities: &BackendCapabilities,
+) -> ast::Function {
+    if capabilities.unfolding_in_triggers {
+        return function;
+    }
+    let mut fixer = TriggerFixer;
+    function.pres = function.pres.into_iter()
+        .map(|expr| ast::ExprFolder::fold(&mut fixer, expr))
+        .collect();
+    function.posts = function.posts.into_iter()
+        .map(|expr| ast::ExprFolder::fold(&mut fixer, expr))
+        .collect();
+    function.body = function.body.map(|expr| ast::ExprFolder::fold(&mut fixer, expr));
+    function
+}
+
+struct TriggerFixer;
+
+impl TriggerFixer {
+    fn fix(&mut self, triggers: Vec<ast::Trigger>) -> Vec<ast::Trigger> {
+        triggers.into_iter()
+            .map(|trigger| {
+                ast::Trigger::new(
+                    trigger.elements().iter()
+                        .cloned()
+                        .map(|term| ast::ExprFolder::fold(&mut UnfoldingRemover, term))
+                        .collect()
+                )
+            })
+            .collect()
+    }
+}
+
+impl ast::StmtFolder for TriggerFixer {
+    fn fold_expr(&mut self, expr: ast::Expr) -> ast::Expr {
+        ast::ExprFolder::fold(self, expr)
+    }
+}
+
+impl ast::ExprFolder for TriggerFixer {
+    fn fold_forall(
+        &mut self,
+        vars: Vec<ast::LocalVar>,
+        triggers: Vec<ast::Trigger>,
+        body: Box<ast::Expr>,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        ast::Expr::ForAll(vars, self.fix(triggers), self.fold_boxed(body), pos)
+    }
+
+    fn fold_exists(
+        &mut self,
+        vars: Vec<ast::LocalVar>,
+        triggers: Vec<ast::Trigger>,
+        body: Box<ast::Expr>,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        ast::Expr::Exists(vars, self.fix(triggers), self.fold_boxed(body), pos)
+    }
+}
+
+/// Replace `unfolding P(..) in e` with `e`.
+struct UnfoldingRemover;
+
+impl ast::ExprFolder for UnfoldingRemover {
+    fn fold_unfolding(
+        &mut self,
+        _name: String,
+        _args: Vec<ast::Expr>,
+        expr: Box<ast::Expr>,
+        _perm: ast::PermAmount,
+        _variant: ast::MaybeEnumVariantIndex,
+        _pos: ast::Position,
+    ) -> ast::Expr {
+        self.fold(*expr)
+    }
+}
@@ -5,12 +5,14 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 pub use self::ast::*;
+pub use self::capabilities::*;
 pub use self::cfg::*;
 pub use self::conversions::*;
 pub use self::to_viper::*;
 pub use self::program::*;
 
 mod ast;
+mod capabilities;
 pub mod borrows;
 mod cfg;
 mod conversions;
@@ -0,0 +1,11 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A module that contains optimizations for expressions.
+
+mod simplifier;
+
+pub use self::simplifier::{simplify_expr, simplify_function, simplify_method};
@@ -0,0 +1,329 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Simplifier of boolean and arithmetic expressions.
+
+use crate::vir::{
+    cfg, BinOpKind, Const, Expr, ExprFolder, ExprWalker, Function, Position, StmtFolder,
+    UnaryOpKind,
+};
+
+/// Simplify all expressions of a method, including the guards of its
+/// branches.
+pub fn simplify_method(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
+    let mut simplifier = ExprSimplifier;
+    for block in &mut method.basic_blocks {
+        block.stmts = block.stmts.drain(..)
+            .map(|stmt| StmtFolder::fold(&mut simplifier, stmt))
+            .collect();
+        if let cfg::Successor::GotoSwitch(ref mut guarded_targets, _) = block.successor {
+            for (guard, _) in guarded_targets.iter_mut() {
+                let old_guard = std::mem::replace(guard, true.into());
+                *guard = simplify_expr(old_guard);
+            }
+        }
+    }
+    method
+}
+
+/// Simplify the preconditions, postconditions and body of a function.
+pub fn simplify_function(mut function: Function) -> Function {
+    function.pres = function.pres.into_iter().map(simplify_expr).collect();
+    function.posts = function.posts.into_iter().map(simplify_expr).collect();
+    function.body = function.body.map(simplify_expr);
+    function
+}
+
+/// Perform local simplifications of an expression:
+/// * constant folding of boolean and integer operations on constants;
+/// * removal of identity elements (`true && e`, `e + 0`, `e * 1`, ...);
+/// * removal of double negations and of nested `old[l1](old[l2](e))`;
+/// * flattening of nested conjunctions to a right-nested chain.
+///
+/// A subexpression is only dropped if its evaluation cannot fail, so that
+/// no well-definedness check is lost. When a node is replaced by one of its
+/// operands, the operand inherits the position of the replaced node, unless
+/// the replaced node has no position.
+pub fn simplify_expr(expr: Expr) -> Expr {
+    ExprFolder::fold(&mut ExprSimplifier, expr)
+}
+
+struct ExprSimplifier;
+
+impl ExprFolder for ExprSimplifier {
+    fn fold(&mut self, e: Expr) -> Expr {
+        let folded_expr = crate::vir::default_fold_expr(self, e);
+        simplify_node(folded_expr)
+    }
+}
+
+impl StmtFolder for ExprSimplifier {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        ExprFolder::fold(self, expr)
+    }
+}
+
+/// Replace the node at position `pos` by `expr`, which inherits the position so that
+/// the errors reported on it are mapped back to the replaced node. If the replaced
+/// node has no position, `expr` keeps its own one.
+fn replace_by(expr: Expr, pos: Position) -> Expr {
+    if pos.is_default() {
+        expr
+    } else {
+        expr.set_pos(pos)
+    }
+}
+
+/// Returns true if the evaluation of the expression cannot fail and does not
+/// depend on permissions, so that it can be dropped.
+fn is_droppable(expr: &Expr) -> bool {
+    struct DivisionFinder {
+        found: bool,
+    }
+    impl ExprWalker for DivisionFinder {
+        fn walk_bin_op(&mut self, op: BinOpKind, arg1: &Expr, arg2: &Expr, _pos: &Position) {
+            if op == BinOpKind::Div || op == BinOpKind::Mod {
+                self.found = true;
+            }
+            self.walk(arg1);
+            self.walk(arg2);
+        }
+    }
+    if expr.is_heap_dependent() {
+        return false;
+    }
+    let mut finder = DivisionFinder { found: false };
+    finder.walk(expr);
+    !finder.found
+}
+
+/// Simplify the outermost node of an expression whose operands are already
+/// simplified.
+fn simplify_node(expr: Expr) -> Expr {
+    match expr {
+        Expr::UnaryOp(UnaryOpKind::Not, box operand, pos) => match operand {
+            Expr::Const(Const::Bool(b), _) => Expr::Const(Const::Bool(!b), pos),
+            Expr::UnaryOp(UnaryOpKind::Not, box inner, _) => replace_by(inner, pos),
+            Expr::BinOp(BinOpKind::EqCmp, left, right, _) => {
+                Expr::BinOp(BinOpKind::NeCmp, left, right, pos)
+            }
+            Expr::BinOp(BinOpKind::NeCmp, left, right, _) => {
+                Expr::BinOp(BinOpKind::EqCmp, left, right, pos)
+            }
+            operand => Expr::UnaryOp(UnaryOpKind::Not, box operand, pos),
+        },
+        Expr::UnaryOp(UnaryOpKind::Minus, box operand, pos) => match operand {
+            Expr::Const(Const::Int(i), _) if i != i64::MIN => Expr::Const(Const::Int(-i), pos),
+            Expr::UnaryOp(UnaryOpKind::Minus, box inner, _) => replace_by(inner, pos),
+            operand => Expr::UnaryOp(UnaryOpKind::Minus, box operand, pos),
+        },
+        Expr::BinOp(op, box left, box right, pos) => simplify_bin_op(op, left, right, pos),
+        Expr::Cond(box guard, box then_expr, box else_expr, pos) => match guard {
+            Expr::Const(Const::Bool(true), _) => replace_by(then_expr, pos),
+            Expr::Const(Const::Bool(false), _) => replace_by(else_expr, pos),
+            guard if then_expr == else_expr && is_droppable(&guard) => {
+                replace_by(then_expr, pos)
+            }
+            guard => Expr::Cond(box guard, box then_expr, box else_expr, pos),
+        },
+        // `old[l1](old[l2](e))` evaluates `e` in the state of `l2`
+        Expr::LabelledOld(label, box body, pos) => match body {
+            Expr::LabelledOld(..) | Expr::Const(..) => replace_by(body, pos),
+            body => Expr::LabelledOld(label, box body, pos),
+        },
+        expr => expr,
+    }
+}
+
+fn simplify_bin_op(op: BinOpKind, left: Expr, right: Expr, pos: Position) -> Expr {
+    use self::BinOpKind::*;
+    match (op, left, right) {
+        // constant folding
+        (And, Expr::Const(Const::Bool(l), _), Expr::Const(Const::Bool(r), _)) => {
+            Expr::Const(Const::Bool(l && r), pos)
+        }
+        (Or, Expr::Const(Const::Bool(l), _), Expr::Const(Const::Bool(r), _)) => {
+            Expr::Const(Const::Bool(l || r), pos)
+        }
+        (Implies, Expr::Const(Const::Bool(l), _), Expr::Const(Const::Bool(r), _)) => {
+            Expr::Const(Const::Bool(!l || r), pos)
+        }
+        (EqCmp, Expr::Const(Const::Bool(l), _), Expr::Const(Const::Bool(r), _)) => {
+            Expr::Const(Const::Bool(l == r), pos)
+        }
+        (NeCmp, Expr::Const(Const::Bool(l), _), Expr::Const(Const::Bool(r), _)) => {
+            Expr::Const(Const::Bool(l != r), pos)
+        }
+        (op, Expr::Const(Const::Int(l), l_pos), Expr::Const(Const::Int(r), r_pos)) => {
+            let value = match op {
+                EqCmp => Some(Const::Bool(l == r)),
+                NeCmp => Some(Const::Bool(l != r)),
+                GtCmp => Some(Const::Bool(l > r)),
+                GeCmp => Some(Const::Bool(l >= r)),
+                LtCmp => Some(Const::Bool(l < r)),
+                LeCmp => Some(Const::Bool(l <= r)),
+                Add => l.checked_add(r).map(Const::Int),
+                Sub => l.checked_sub(r).map(Const::Int),
+                Mul => l.checked_mul(r).map(Const::Int),
+                // Viper and Rust disagree on the rounding of negative numbers
+                _ => None,
+            };
+            match value {
+                Some(value) => Expr::Const(value, pos),
+                None => Expr::BinOp(
+                    op,
+                    box Expr::Const(Const::Int(l), l_pos),
+                    box Expr::Const(Const::Int(r), r_pos),
+                    pos,
+                ),
+            }
+        }
+
+        // conjunctions
+        (And, Expr::Const(Const::Bool(true), _), e)
+        | (And, e, Expr::Const(Const::Bool(true), _)) => replace_by(e, pos),
+        (And, Expr::Const(Const::Bool(false), _), _) => Expr::Const(Const::Bool(false), pos),
+        (And, Expr::BinOp(And, box inner_left, box inner_right, inner_pos), right) => {
+            // `(a && b) && c` becomes `a && (b && c)`
+            let inner_pos = if inner_pos.is_default() { pos } else { inner_pos };
+            Expr::BinOp(
+                And,
+                box inner_left,
+                box simplify_bin_op(And, inner_right, right, inner_pos),
+                pos,
+            )
+        }
+
+        // disjunctions
+        (Or, Expr::Const(Const::Bool(false), _), e)
+        | (Or, e, Expr::Const(Const::Bool(false), _)) => replace_by(e, pos),
+        (Or, Expr::Const(Const::Bool(true), _), _) => Expr::Const(Const::Bool(true), pos),
+
+        // implications
+        (Implies, Expr::Const(Const::Bool(true), _), e) => replace_by(e, pos),
+        (Implies, Expr::Const(Const::Bool(false), _), _) => Expr::Const(Const::Bool(true), pos),
+        (Implies, guard, Expr::Const(Const::Bool(true), r_pos)) => {
+            if is_droppable(&guard) {
+                Expr::Const(Const::Bool(true), pos)
+            } else {
+                Expr::BinOp(Implies, box guard, box Expr::Const(Const::Bool(true), r_pos), pos)
+            }
+        }
+
+        // arithmetic identities
+        (Add, Expr::Const(Const::Int(0), _), e)
+        | (Add, e, Expr::Const(Const::Int(0), _))
+        | (Sub, e, Expr::Const(Const::Int(0), _))
+        | (Mul, Expr::Const(Const::Int(1), _), e)
+        | (Mul, e, Expr::Const(Const::Int(1), _)) => replace_by(e, pos),
+
+        (op, left, right) => Expr::BinOp(op, box left, box right, pos),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{LocalVar, Type};
+
+    fn local(name: &str, typ: Type) -> Expr {
+        Expr::local(LocalVar::new(name, typ))
+    }
+
+    fn pos(id: u64) -> Position {
+        Position::new(1, 1, id)
+    }
+
+    #[test]
+    fn conjunction_identity() {
+        let a = local("a", Type::Bool);
+        assert_eq!(simplify_expr(Expr::and(true.into(), a.clone())), a);
+        assert_eq!(simplify_expr(Expr::and(a.clone(), true.into())), a);
+        assert_eq!(simplify_expr(Expr::and(false.into(), a)), false.into());
+    }
+
+    #[test]
+    fn implication_with_true_conclusion() {
+        let a = local("a", Type::Bool);
+        assert_eq!(simplify_expr(Expr::implies(a, true.into())), true.into());
+        let read = Expr::field(
+            local("r", Type::TypedRef("i32".to_string())),
+            crate::vir::Field::new("val_int", Type::Int),
+        );
+        let guard = Expr::gt_cmp(read, 0.into());
+        assert_eq!(
+            simplify_expr(Expr::implies(guard.clone(), true.into())),
+            Expr::implies(guard, true.into()),
+        );
+    }
+
+    #[test]
+    fn double_negation() {
+        let a = local("a", Type::Bool);
+        assert_eq!(simplify_expr(Expr::not(Expr::not(a.clone()))), a);
+        let x = local("x", Type::Int);
+        assert_eq!(
+            simplify_expr(Expr::not(Expr::eq_cmp(x.clone(), 1.into()))),
+            Expr::ne_cmp(x, 1.into()),
+        );
+    }
+
+    #[test]
+    fn constant_folding() {
+        let sum = Expr::add(2.into(), 3.into());
+        assert_eq!(simplify_expr(Expr::eq_cmp(sum, 5.into())), true.into());
+        let x = local("x", Type::Int);
+        assert_eq!(
+            simplify_expr(Expr::mul(Expr::add(x.clone(), 0.into()), 1.into())),
+            x,
+        );
+        let division = Expr::div(7.into(), 2.into());
+        assert_eq!(simplify_expr(division.clone()), division);
+    }
+
+    #[test]
+    fn nested_old() {
+        let a = local("a", Type::Bool);
+        let inner = Expr::labelled_old("l2", a);
+        assert_eq!(simplify_expr(Expr::labelled_old("l1", inner.clone())), inner);
+    }
+
+    #[test]
+    fn flatten_conjunctions() {
+        let a = local("a", Type::Bool);
+        let b = local("b", Type::Bool);
+        let c = local("c", Type::Bool);
+        assert_eq!(
+            simplify_expr(Expr::and(Expr::and(a.clone(), b.clone()), c.clone())),
+            Expr::and(a, Expr::and(b, c)),
+        );
+    }
+
+    #[test]
+    fn inherit_position() {
+        let a = local("a", Type::Bool);
+        let expr = Expr::BinOp(
+            BinOpKind::And,
+            box true.into(),
+            box a.clone(),
+            pos(42),
+        );
+        assert_eq!(simplify_expr(expr).pos(), pos(42));
+        let expr = Expr::BinOp(
+            BinOpKind::And,
+            box true.into(),
+            box a.clone().set_pos(pos(7)),
+            pos(42),
+        );
+        assert_eq!(simplify_expr(expr).pos(), pos(42));
+        let expr = Expr::not(Expr::not(a.clone().set_pos(pos(7))).set_pos(pos(8)))
+            .set_pos(pos(42));
+        assert_eq!(simplify_expr(expr).pos(), pos(42));
+        // Without a position of the replaced node, the operand keeps its own
+        let expr = Expr::and(true.into(), a.set_pos(pos(7)));
+        assert_eq!(simplify_expr(expr).pos(), pos(7));
+    }
+}
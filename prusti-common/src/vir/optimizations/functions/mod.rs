@@ -7,7 +7,9 @@
 //! A module that contains optimizations for functions.
 
 mod inliner;
+mod pure_inliner;
 mod simplifier;
 
 pub use self::inliner::inline_constant_functions;
+pub use self::pure_inliner::inline_pure_functions;
 pub use self::simplifier::Simplifier;
@@ -0,0 +1,352 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Inliner of functions encoding Rust pure functions that are called only a few times.
+
+use super::super::super::ast;
+use super::super::super::cfg;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+/// Inline the body of the functions in `pure_functions` that are called at most `max_calls`
+/// times in the program, and drop them. Inlining removes the axioms of the functions, which
+/// slow down the verifier when the functions are only helpers of a specification.
+///
+/// A function is inlined only if it:
+/// * has a body;
+/// * has a precondition made only of permissions and no postcondition, so that inlining does
+///   not lose any check nor assumption;
+/// * is not recursive, directly or through other functions;
+/// * is called only with places as arguments, and never from predicates, domains or
+///   the guards of a CFG.
+///
+/// The positions of the inlined body are kept, so that errors still point to the Rust code of
+/// the pure function. Parts of the body without a position get the position of the call site.
+pub fn inline_pure_functions(
+    methods: Vec<cfg::CfgMethod>,
+    functions: Vec<ast::Function>,
+    domains: &[ast::Domain],
+    pure_functions: &HashSet<String>,
+    max_calls: usize,
+) -> (Vec<cfg::CfgMethod>, Vec<ast::Function>) {
+    trace!("[enter] inline_pure_functions(max_calls={})", max_calls);
+
+    // Collect the candidates
+    let mut candidates: HashMap<String, &ast::Function> = functions.iter()
+        .filter(|function| pure_functions.contains(&function.name))
+        .filter(|function| function.body.is_some())
+        .filter(|function| {
+            function.pres.iter().all(|cond| cond.is_only_permissions())
+                && function.posts.is_empty()
+        })
+        .map(|function| (function.name.clone(), function))
+        .collect();
+
+    // Remove recursive functions
+    let callees: HashMap<String, HashSet<String>> = candidates.iter()
+        .map(|(name, function)| {
+            let mut collector = CallCollector::default();
+            ast::ExprWalker::walk(&mut collector, function.body.as_ref().unwrap());
+            (name.clone(), collector.calls.into_iter().map(|(callee, _)| callee).collect())
+        })
+        .collect();
+    candidates.retain(|name, _| !is_recursive(name, &callees));
+
+    // Count the calls of the candidates
+    let mut collector = CallCollector::default();
+    for method in &methods {
+        for block in &method.basic_blocks {
+            for stmt in &block.stmts {
+                ast::StmtWalker::walk(&mut collector, stmt);
+            }
+        }
+    }
+    for function in &functions {
+        for expr in function.pres.iter().chain(function.posts.iter()).chain(function.body.iter()) {
+            ast::ExprWalker::walk(&mut collector, expr);
+        }
+    }
+    let mut blocked = HashSet::new();
+    for method in &methods {
+        for block in &method.basic_blocks {
+            if let cfg::Successor::GotoSwitch(ref guarded_targets, _) = block.successor {
+                for (guard, _) in guarded_targets {
+                    blocked.extend(collect_called_functions(guard));
+                }
+            }
+        }
+    }
+    for domain in domains {
+        for axiom in &domain.axioms {
+            blocked.extend(collect_called_functions(&axiom.expr));
+        }
+    }
+    let mut num_calls: HashMap<String, usize> = HashMap::new();
+    for (name, args) in collector.calls {
+        if !args.iter().all(|arg| arg.is_place()) {
+            blocked.insert(name.clone());
+        }
+        *num_calls.entry(name).or_insert(0) += 1;
+    }
+    candidates.retain(|name, _| {
+        let calls = num_calls.get(name).copied().unwrap_or(0);
+        calls > 0 && calls <= max_calls && !blocked.contains(name)
+    });
+    debug!("Inlining functions {:?}", candidates.keys());
+    if candidates.is_empty() {
+        return (methods, functions);
+    }
+
+    // Inline the selected functions and drop them
+    let inlined_functions: HashMap<String, ast::Function> = candidates.into_iter()
+        .map(|(name, function)| (name, function.clone()))
+        .collect();
+    let mut inliner = PureFunctionInliner {
+        functions: &inlined_functions,
+    };
+    let functions = functions.into_iter()
+        .filter(|function| !inlined_functions.contains_key(&function.name))
+        .map(|mut function| {
+            function.pres = function.pres.into_iter()
+                .map(|expr| ast::ExprFolder::fold(&mut inliner, expr))
+                .collect();
+            function.posts = function.posts.into_iter()
+                .map(|expr| ast::ExprFolder::fold(&mut inliner, expr))
+                .collect();
+            function.body = function.body.map(|expr| ast::ExprFolder::fold(&mut inliner, expr));
+            function
+        })
+        .collect();
+    let methods = methods.into_iter()
+        .map(|mut method| {
+            let mut sentinel_stmt = ast::Stmt::Comment(String::from("moved out stmt"));
+            for block in &mut method.basic_blocks {
+                for stmt in &mut block.stmts {
+                    mem::swap(&mut sentinel_stmt, stmt);
+                    sentinel_stmt = ast::StmtFolder::fold(&mut inliner, sentinel_stmt);
+                    mem::swap(&mut sentinel_stmt, stmt);
+                }
+            }
+            method
+        })
+        .collect();
+    (methods, functions)
+}
+
+/// Is `name` reachable from itself in the call graph `callees`?
+fn is_recursive(name: &str, callees: &HashMap<String, HashSet<String>>) -> bool {
+    let mut visited = HashSet::new();
+    let mut to_visit: Vec<&str> = vec![name];
+    while let Some(current) = to_visit.pop() {
+        for callee in callees.get(current).into_iter().flatten() {
+            if callee == name {
+                return true;
+            }
+            if visited.insert(callee.as_str()) {
+                to_visit.push(callee);
+            }
+        }
+    }
+    false
+}
+
+fn collect_called_functions(expr: &ast::Expr) -> HashSet<String> {
+    let mut collector = CallCollector::default();
+    ast::ExprWalker::walk(&mut collector, expr);
+    collector.calls.into_iter().map(|(name, _)| name).collect()
+}
+
+/// Collect the function calls, together with their arguments.
+#[derive(Default)]
+struct CallCollector {
+    calls: Vec<(String, Vec<ast::Expr>)>,
+}
+
+impl ast::ExprWalker for CallCollector {
+    fn walk_func_app(
+        &mut self,
+        name: &str,
+        args: &Vec<ast::Expr>,
+        _formal_args: &Vec<ast::LocalVar>,
+        _return_type: &ast::Type,
+        _pos: &ast::Position,
+    ) {
+        self.calls.push((name.to_string(), args.clone()));
+        for arg in args {
+            self.walk(arg);
+        }
+    }
+}
+
+impl ast::StmtWalker for CallCollector {
+    fn walk_expr(&mut self, expr: &ast::Expr) {
+        ast::ExprWalker::walk(self, expr);
+    }
+}
+
+/// Replace the calls of the given functions with their bodies.
+struct PureFunctionInliner<'a> {
+    functions: &'a HashMap<String, ast::Function>,
+}
+
+impl<'a> ast::StmtFolder for PureFunctionInliner<'a> {
+    fn fold_expr(&mut self, expr: ast::Expr) -> ast::Expr {
+        ast::ExprFolder::fold(self, expr)
+    }
+}
+
+impl<'a> ast::ExprFolder for PureFunctionInliner<'a> {
+    fn fold_func_app(
+        &mut self,
+        name: String,
+        args: Vec<ast::Expr>,
+        formal_args: Vec<ast::LocalVar>,
+        return_type: ast::Type,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        let args: Vec<_> = args.into_iter().map(|e| self.fold(e)).collect();
+        if let Some(function) = self.functions.get(&name) {
+            let replacements: Vec<_> = function.formal_args.iter()
+                .cloned()
+                .map(ast::Expr::local)
+                .zip(args.into_iter())
+                .collect();
+            let body = function.body.clone().unwrap()
+                .replace_multiple_places(&replacements)
+                .set_default_pos(pos);
+            // The body might call other inlined functions
+            self.fold(body)
+        } else {
+            ast::Expr::FuncApp(name, args, formal_args, return_type, pos)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{Const, Expr, Function, LocalVar, Position, Stmt, Type};
+
+    fn function(name: &str, body: Expr) -> Function {
+        Function {
+            name: name.to_string(),
+            formal_args: vec![LocalVar::new("_1", Type::Int)],
+            return_type: Type::Int,
+            pres: vec![],
+            posts: vec![],
+            body: Some(body),
+        }
+    }
+
+    fn call(name: &str, arg: Expr, pos: Position) -> Expr {
+        Expr::func_app(
+            name.to_string(),
+            vec![arg],
+            vec![LocalVar::new("_1", Type::Int)],
+            Type::Int,
+            pos,
+        )
+    }
+
+    fn method(stmts: Vec<Stmt>) -> cfg::CfgMethod {
+        let mut method = cfg::CfgMethod::new(
+            "caller".to_string(),
+            0,
+            vec![],
+            vec![LocalVar::new("x", Type::Int)],
+            vec![],
+        );
+        let block = method.add_block("start", stmts);
+        method.set_successor(block, cfg::Successor::Return);
+        method
+    }
+
+    fn pure_functions(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn inline_single_use() {
+        let body_pos = Position::new(10, 1, 1);
+        let call_pos = Position::new(20, 1, 2);
+        let formal = Expr::local(LocalVar::new("_1", Type::Int));
+        let helper = function("helper", Expr::add(formal, Expr::Const(Const::Int(1), body_pos)));
+        let x = Expr::local(LocalVar::new("x", Type::Int));
+        let methods = vec![method(vec![
+            Stmt::Assert(Expr::gt_cmp(call("helper", x.clone(), call_pos), 0.into()), call_pos),
+        ])];
+
+        let (methods, functions) = inline_pure_functions(
+            methods, vec![helper], &[], &pure_functions(&["helper"]), 1,
+        );
+
+        assert!(functions.is_empty());
+        let expected = Expr::gt_cmp(
+            Expr::add(x, Expr::Const(Const::Int(1), body_pos)).set_default_pos(call_pos),
+            0.into(),
+        );
+        assert_eq!(
+            methods[0].basic_blocks[0].stmts,
+            vec![Stmt::Assert(expected, call_pos)],
+        );
+    }
+
+    #[test]
+    fn keep_body_positions() {
+        let body_pos = Position::new(10, 1, 1);
+        let call_pos = Position::new(20, 1, 2);
+        let formal = Expr::local(LocalVar::new("_1", Type::Int));
+        let helper = function("helper", Expr::Const(Const::Int(1), body_pos));
+        let caller = function("caller", call("helper", formal, call_pos));
+
+        let (_, functions) = inline_pure_functions(
+            vec![], vec![helper, caller], &[], &pure_functions(&["helper"]), 1,
+        );
+
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].name, "caller");
+        assert_eq!(functions[0].body, Some(Expr::Const(Const::Int(1), body_pos)));
+    }
+
+    #[test]
+    fn keep_functions_called_too_often() {
+        let formal = Expr::local(LocalVar::new("_1", Type::Int));
+        let helper = function("helper", formal.clone());
+        let caller = function("caller", Expr::add(
+            call("helper", formal.clone(), Position::default()),
+            call("helper", formal, Position::default()),
+        ));
+
+        let (_, functions) = inline_pure_functions(
+            vec![], vec![helper, caller.clone()], &[], &pure_functions(&["helper"]), 1,
+        );
+
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions[1], caller);
+    }
+
+    #[test]
+    fn keep_recursive_and_non_pure_functions() {
+        let formal = Expr::local(LocalVar::new("_1", Type::Int));
+        let recursive = function("recursive", call("recursive", formal.clone(), Position::default()));
+        let other = function("other", formal.clone());
+        let caller = function("caller", Expr::add(
+            call("recursive", formal.clone(), Position::default()),
+            call("other", formal, Position::default()),
+        ));
+
+        let (_, functions) = inline_pure_functions(
+            vec![],
+            vec![recursive, other, caller.clone()],
+            &[],
+            &pure_functions(&["recursive"]),
+            1,
+        );
+
+        assert_eq!(functions.len(), 3);
+        assert_eq!(functions[2], caller);
+    }
+}
@@ -124,6 +124,18 @@ impl ExprSimplifier {
                     true.into()
                 }
             },
+            ast::Expr::Cond(
+                box ast::Expr::Const(ast::Const::Bool(b), _),
+                box then_expr,
+                box else_expr,
+                _pos,
+            ) => {
+                if b {
+                    then_expr
+                } else {
+                    else_expr
+                }
+            },
             ast::Expr::BinOp(ast::BinOpKind::And, box op1, box op2, pos) => {
                 ast::Expr::BinOp(
                     ast::BinOpKind::And,
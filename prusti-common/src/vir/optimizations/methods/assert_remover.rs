@@ -4,23 +4,173 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-//! Optimization that removes unused temporary variables.
+//! Optimization that removes trivial assertions and the statements that
+//! become useless without them.
 
-use crate::vir::{cfg, Const, Expr, Stmt};
+use std::collections::HashSet;
+use std::mem;
 
-/// Remove trivial assertions:
+use crate::vir::{cfg, Const, Expr, ExprWalker, Position, Stmt};
+
+/// Remove trivial assertions and clean up the statements around them:
 /// * `assert true`
 /// * `exhale true`
 /// * `inhale true`
+/// * `if (...) {}`, once the branches are cleaned, also if they only contain comments
+/// * labels that are never used by an `old[label](...)` expression
+///
+/// Consecutive comments are merged into a single comment. The jumps of the
+/// CFG target the labels of the basic blocks, which are not statements, so
+/// they are not affected.
 pub fn remove_trivial_assertions(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
-    method.retain_stmts(|stmt| {
-        // Remove those statements marked with `false`
-        match stmt {
-            Stmt::Assert(Expr::Const(Const::Bool(true), _), _) => false,
-            Stmt::Exhale(Expr::Const(Const::Bool(true), _), _) => false,
-            Stmt::Inhale(Expr::Const(Const::Bool(true), _)) => false,
-            _ => true, // Keep the rest
-        }
-    });
+    let mut collector = UsedLabelsCollector {
+        labels: HashSet::new(),
+    };
+    method.walk_expressions(|expr| collector.walk(expr));
+    let used_labels = collector.labels;
+    for block in &mut method.basic_blocks {
+        let stmts = mem::replace(&mut block.stmts, vec![]);
+        block.stmts = clean_stmts(stmts, &used_labels);
+    }
     method
 }
+
+/// Collect the labels used in `old[label](...)` expressions.
+struct UsedLabelsCollector {
+    labels: HashSet<String>,
+}
+
+impl ExprWalker for UsedLabelsCollector {
+    fn walk_labelled_old(&mut self, label: &str, body: &Expr, _pos: &Position) {
+        self.labels.insert(label.to_string());
+        self.walk(body);
+    }
+}
+
+fn clean_stmts(stmts: Vec<Stmt>, used_labels: &HashSet<String>) -> Vec<Stmt> {
+    let mut cleaned: Vec<Stmt> = vec![];
+    for stmt in stmts {
+        let stmt = match stmt {
+            Stmt::Assert(Expr::Const(Const::Bool(true), _), _) |
+            Stmt::Exhale(Expr::Const(Const::Bool(true), _), _) |
+            Stmt::Inhale(Expr::Const(Const::Bool(true), _)) => continue,
+            Stmt::Label(ref label) if !used_labels.contains(label) => continue,
+            Stmt::If(guard, then_stmts, else_stmts) => {
+                let then_stmts = clean_stmts(then_stmts, used_labels);
+                let else_stmts = clean_stmts(else_stmts, used_labels);
+                if is_empty_body(&then_stmts) && is_empty_body(&else_stmts) {
+                    continue;
+                }
+                Stmt::If(guard, then_stmts, else_stmts)
+            }
+            Stmt::PackageMagicWand(wand, body, label, vars, pos) => {
+                let body = clean_stmts(body, used_labels);
+                Stmt::PackageMagicWand(wand, body, label, vars, pos)
+            }
+            stmt => stmt,
+        };
+        if let Stmt::Comment(ref comment) = stmt {
+            if let Some(Stmt::Comment(previous)) = cleaned.last_mut() {
+                previous.push_str("; ");
+                previous.push_str(comment);
+                continue;
+            }
+        }
+        cleaned.push(stmt);
+    }
+    cleaned
+}
+
+fn is_empty_body(stmts: &[Stmt]) -> bool {
+    stmts.iter().all(|stmt| matches!(stmt, Stmt::Comment(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{AssignKind, LocalVar, Type};
+
+    fn new_method(stmts: Vec<Stmt>) -> cfg::CfgMethod {
+        let mut method = cfg::CfgMethod::new("test".to_string(), 0, vec![], vec![], vec![]);
+        let start = method.add_block("start", stmts);
+        method.set_successor(start, cfg::Successor::Return);
+        method
+    }
+
+    fn local(name: &str, typ: Type) -> Expr {
+        Expr::local(LocalVar::new(name, typ))
+    }
+
+    fn cleaned_stmts(stmts: Vec<Stmt>) -> Vec<Stmt> {
+        let method = remove_trivial_assertions(new_method(stmts));
+        method.basic_blocks[0].stmts.clone()
+    }
+
+    #[test]
+    fn removes_trivial_assertions() {
+        let a = local("a", Type::Bool);
+        let stmts = cleaned_stmts(vec![
+            Stmt::Assert(true.into(), Position::default()),
+            Stmt::Exhale(true.into(), Position::default()),
+            Stmt::Inhale(true.into()),
+            Stmt::Inhale(a.clone()),
+        ]);
+        assert_eq!(stmts, vec![Stmt::Inhale(a)]);
+    }
+
+    #[test]
+    fn removes_conditionals_that_become_empty() {
+        let a = local("a", Type::Bool);
+        let b = local("b", Type::Bool);
+        let stmts = cleaned_stmts(vec![
+            Stmt::If(
+                a.clone(),
+                vec![Stmt::Assert(true.into(), Position::default())],
+                vec![Stmt::If(b.clone(), vec![Stmt::Inhale(true.into())], vec![])],
+            ),
+            Stmt::If(
+                a.clone(),
+                vec![Stmt::Comment("then".to_string())],
+                vec![Stmt::Exhale(b.clone(), Position::default())],
+            ),
+        ]);
+        assert_eq!(stmts, vec![Stmt::If(
+            a,
+            vec![Stmt::Comment("then".to_string())],
+            vec![Stmt::Exhale(b, Position::default())],
+        )]);
+    }
+
+    #[test]
+    fn merges_consecutive_comments() {
+        let x = local("x", Type::Int);
+        let assign = Stmt::Assign(x, 1.into(), AssignKind::Copy);
+        let stmts = cleaned_stmts(vec![
+            Stmt::Comment("first".to_string()),
+            Stmt::Assert(true.into(), Position::default()),
+            Stmt::Comment("second".to_string()),
+            assign.clone(),
+            Stmt::Comment("third".to_string()),
+        ]);
+        assert_eq!(stmts, vec![
+            Stmt::Comment("first; second".to_string()),
+            assign,
+            Stmt::Comment("third".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn removes_unused_labels() {
+        let x = local("x", Type::Int);
+        let old_x = Expr::labelled_old("used", x.clone());
+        let stmts = cleaned_stmts(vec![
+            Stmt::Label("unused".to_string()),
+            Stmt::Label("used".to_string()),
+            Stmt::Assert(Expr::eq_cmp(x, old_x.clone()), Position::default()),
+        ]);
+        assert_eq!(stmts, vec![
+            Stmt::Label("used".to_string()),
+            Stmt::Assert(Expr::eq_cmp(local("x", Type::Int), old_x), Position::default()),
+        ]);
+    }
+}
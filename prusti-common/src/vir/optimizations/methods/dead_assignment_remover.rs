@@ -0,0 +1,349 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Optimization that removes the assignments to temporary variables that
+//! are never read.
+
+use std::collections::HashSet;
+use crate::vir::{ast, borrows::Borrow, cfg, BinOpKind, Expr, LocalVar, Position, Stmt};
+
+/// The prefix of the names of the temporary variables generated by
+/// `CfgMethod::add_fresh_local_var`.
+const TEMPORARY_PREFIX: &str = "__t";
+
+/// Remove the assignments to temporary variables that are never read, and
+/// the declarations of the temporaries that are no longer mentioned:
+/// * an assignment is only removed if its right-hand side cannot fail, i.e.
+///   it has no function applications, permissions or divisions;
+/// * removing an assignment can make the temporaries read by its right-hand
+///   side unused, so the removal is repeated until a fixpoint is reached;
+/// * the variables of magic wand packages and the variables with the name
+///   of a label are never touched.
+pub fn remove_dead_assignments(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
+    let mut protected_vars = ProtectedVarCollector {
+        protected_vars: HashSet::new(),
+    };
+    method.walk_statements(|stmt| ast::StmtWalker::walk(&mut protected_vars, stmt));
+    let protected_vars = protected_vars.protected_vars;
+
+    let mut removed_vars = HashSet::new();
+    loop {
+        let read_vars = collect_read_vars(&method);
+        let is_dead = |var: &LocalVar| {
+            var.name.starts_with(TEMPORARY_PREFIX)
+                && !read_vars.contains(&var.name)
+                && !protected_vars.contains(&var.name)
+        };
+        let mut changed = false;
+        for block in &mut method.basic_blocks {
+            block.stmts.retain(|stmt| match stmt {
+                Stmt::Assign(Expr::Local(var, _), source, _)
+                    if is_dead(var) && !may_fail(source) =>
+                {
+                    removed_vars.insert(var.name.clone());
+                    changed = true;
+                    false
+                }
+                _ => true,
+            });
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    if !removed_vars.is_empty() {
+        let mut mentioned_vars = MentionedVarCollector {
+            mentioned_vars: HashSet::new(),
+        };
+        method.walk_statements(|stmt| ast::StmtWalker::walk(&mut mentioned_vars, stmt));
+        method.walk_expressions(|expr| ast::ExprWalker::walk(&mut mentioned_vars, expr));
+        let mentioned_vars = mentioned_vars.mentioned_vars;
+        method.local_vars.retain(|var| {
+            !removed_vars.contains(&var.name) || mentioned_vars.contains(&var.name)
+        });
+    }
+    method
+}
+
+/// Collects the variables that are read in the statements and successors of the
+/// method. The targets of assignments to local variables are not reads.
+fn collect_read_vars(method: &cfg::CfgMethod) -> HashSet<String> {
+    let mut collector = ReadVarCollector {
+        read_vars: HashSet::new(),
+    };
+    method.walk_statements(|stmt| ast::StmtWalker::walk(&mut collector, stmt));
+    method.walk_successors(|successor| match successor {
+        cfg::Successor::Undefined |
+        cfg::Successor::Return |
+        cfg::Successor::Goto(_) => {}
+        cfg::Successor::GotoSwitch(conditional_targets, _) => {
+            for (expr, _) in conditional_targets {
+                ast::ExprWalker::walk(&mut collector, expr);
+            }
+        }
+    });
+    collector.read_vars
+}
+
+/// Returns true if the evaluation of the expression might fail, or has an effect
+/// on the permissions.
+fn may_fail(expr: &Expr) -> bool {
+    let mut finder = FallibleExprFinder { may_fail: false };
+    ast::ExprWalker::walk(&mut finder, expr);
+    finder.may_fail
+}
+
+struct ReadVarCollector {
+    read_vars: HashSet<String>,
+}
+
+impl ast::ExprWalker for ReadVarCollector {
+    fn walk_local_var(&mut self, local_var: &LocalVar) {
+        self.read_vars.insert(local_var.name.clone());
+    }
+}
+
+impl ast::StmtWalker for ReadVarCollector {
+    fn walk_expr(&mut self, expr: &Expr) {
+        ast::ExprWalker::walk(self, expr);
+    }
+    fn walk_local_var(&mut self, local_var: &LocalVar) {
+        self.read_vars.insert(local_var.name.clone());
+    }
+    fn walk_assign(&mut self, target: &Expr, expr: &Expr, _kind: &ast::AssignKind) {
+        if !target.is_local() {
+            self.walk_expr(target);
+        }
+        self.walk_expr(expr);
+    }
+}
+
+/// Collects the variables of magic wand packages and the names of labels.
+struct ProtectedVarCollector {
+    protected_vars: HashSet<String>,
+}
+
+impl ast::StmtWalker for ProtectedVarCollector {
+    fn walk_label(&mut self, label: &str) {
+        self.protected_vars.insert(label.to_string());
+    }
+    fn walk_package_magic_wand(
+        &mut self,
+        _wand: &Expr,
+        body: &Vec<Stmt>,
+        label: &str,
+        vars: &[LocalVar],
+        _pos: &Position,
+    ) {
+        self.protected_vars.insert(label.to_string());
+        for var in vars {
+            self.protected_vars.insert(var.name.clone());
+        }
+        for statement in body {
+            self.walk(statement);
+        }
+    }
+    fn walk_if(&mut self, _guard: &Expr, then_stmts: &Vec<Stmt>, else_stmts: &Vec<Stmt>) {
+        for statement in then_stmts.iter().chain(else_stmts) {
+            self.walk(statement);
+        }
+    }
+}
+
+/// Collects all the variables mentioned in the method, including the targets of
+/// assignments.
+struct MentionedVarCollector {
+    mentioned_vars: HashSet<String>,
+}
+
+impl ast::ExprWalker for MentionedVarCollector {
+    fn walk_local_var(&mut self, local_var: &LocalVar) {
+        self.mentioned_vars.insert(local_var.name.clone());
+    }
+}
+
+impl ast::StmtWalker for MentionedVarCollector {
+    fn walk_expr(&mut self, expr: &Expr) {
+        ast::ExprWalker::walk(self, expr);
+    }
+    fn walk_local_var(&mut self, local_var: &LocalVar) {
+        self.mentioned_vars.insert(local_var.name.clone());
+    }
+}
+
+struct FallibleExprFinder {
+    may_fail: bool,
+}
+
+impl ast::ExprWalker for FallibleExprFinder {
+    fn walk_func_app(
+        &mut self,
+        _name: &str,
+        _args: &Vec<Expr>,
+        _formal_args: &Vec<LocalVar>,
+        _return_type: &ast::Type,
+        _pos: &Position,
+    ) {
+        self.may_fail = true;
+    }
+    fn walk_magic_wand(
+        &mut self,
+        _lhs: &Expr,
+        _rhs: &Expr,
+        _borrow: &Option<Borrow>,
+        _pos: &Position,
+    ) {
+        self.may_fail = true;
+    }
+    fn walk_predicate_access_predicate(
+        &mut self,
+        _name: &str,
+        _arg: &Expr,
+        _perm_amount: ast::PermAmount,
+        _pos: &Position,
+    ) {
+        self.may_fail = true;
+    }
+    fn walk_field_access_predicate(
+        &mut self,
+        _receiver: &Expr,
+        _perm_amount: ast::PermAmount,
+        _pos: &Position,
+    ) {
+        self.may_fail = true;
+    }
+    fn walk_unfolding(
+        &mut self,
+        _name: &str,
+        _args: &Vec<Expr>,
+        _body: &Expr,
+        _perm: ast::PermAmount,
+        _variant: &ast::MaybeEnumVariantIndex,
+        _pos: &Position,
+    ) {
+        self.may_fail = true;
+    }
+    fn walk_inhale_exhale(&mut self, _inhale_expr: &Expr, _exhale_expr: &Expr, _pos: &Position) {
+        self.may_fail = true;
+    }
+    fn walk_bin_op(&mut self, op: BinOpKind, arg1: &Expr, arg2: &Expr, _pos: &Position) {
+        if matches!(op, BinOpKind::Div | BinOpKind::Mod) {
+            self.may_fail = true;
+        }
+        ast::ExprWalker::walk(self, arg1);
+        ast::ExprWalker::walk(self, arg2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{AssignKind, Type};
+
+    fn new_method(local_vars: Vec<LocalVar>, stmts: Vec<Stmt>) -> cfg::CfgMethod {
+        let mut method = cfg::CfgMethod::new("test".to_string(), 0, vec![], local_vars, vec![]);
+        let start = method.add_block("start", stmts);
+        method.set_successor(start, cfg::Successor::Return);
+        method
+    }
+
+    fn var(name: &str) -> LocalVar {
+        LocalVar::new(name, Type::Int)
+    }
+
+    fn local(name: &str) -> Expr {
+        Expr::local(var(name))
+    }
+
+    fn assign(name: &str, source: Expr) -> Stmt {
+        Stmt::Assign(local(name), source, AssignKind::Copy)
+    }
+
+    #[test]
+    fn removes_unread_temporaries() {
+        let method = new_method(
+            vec![var("x"), var("__t0"), var("__t1")],
+            vec![
+                assign("__t0", local("x")),
+                assign("__t1", 1.into()),
+                Stmt::Assert(Expr::eq_cmp(local("__t1"), 1.into()), Position::default()),
+            ],
+        );
+        let method = remove_dead_assignments(method);
+        assert_eq!(method.basic_blocks[0].stmts, vec![
+            assign("__t1", 1.into()),
+            Stmt::Assert(Expr::eq_cmp(local("__t1"), 1.into()), Position::default()),
+        ]);
+        assert_eq!(method.local_vars, vec![var("x"), var("__t1")]);
+    }
+
+    #[test]
+    fn removes_chains_of_dead_assignments() {
+        let method = new_method(
+            vec![var("__t0"), var("__t1"), var("__t2")],
+            vec![
+                assign("__t0", 1.into()),
+                assign("__t1", Expr::add(local("__t0"), 1.into())),
+                assign("__t2", local("__t1")),
+            ],
+        );
+        assert_eq!(method.basic_blocks[0].stmts.len(), 3);
+        let method = remove_dead_assignments(method);
+        assert_eq!(method.basic_blocks[0].stmts.len(), 0);
+        assert!(method.local_vars.is_empty());
+    }
+
+    #[test]
+    fn keeps_fallible_and_non_temporary_assignments() {
+        let division = Expr::BinOp(
+            BinOpKind::Div,
+            box local("x"),
+            box local("y"),
+            Position::default(),
+        );
+        let stmts = vec![
+            assign("__t0", division),
+            assign("__t1", Expr::func_app(
+                "f".to_string(),
+                vec![local("x")],
+                vec![var("x")],
+                Type::Int,
+                Position::default(),
+            )),
+            assign("x", 1.into()),
+        ];
+        let method = new_method(
+            vec![var("x"), var("y"), var("__t0"), var("__t1")],
+            stmts.clone(),
+        );
+        let method = remove_dead_assignments(method);
+        assert_eq!(method.basic_blocks[0].stmts, stmts);
+        assert_eq!(method.local_vars.len(), 4);
+    }
+
+    #[test]
+    fn keeps_magic_wand_variables() {
+        let package = Stmt::PackageMagicWand(
+            Expr::magic_wand(true.into(), true.into(), None),
+            vec![],
+            "l0".to_string(),
+            vec![var("__t1")],
+            Position::default(),
+        );
+        let method = new_method(
+            vec![var("__t0"), var("__t1")],
+            vec![
+                assign("__t0", 1.into()),
+                assign("__t1", 2.into()),
+                package.clone(),
+            ],
+        );
+        let method = remove_dead_assignments(method);
+        assert_eq!(method.basic_blocks[0].stmts, vec![assign("__t1", 2.into()), package]);
+        assert_eq!(method.local_vars, vec![var("__t1")]);
+    }
+}
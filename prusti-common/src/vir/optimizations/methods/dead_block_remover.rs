@@ -0,0 +1,364 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Optimization that removes basic blocks whose entry condition is
+//! statically `false`.
+
+use std::collections::{HashMap, VecDeque};
+use crate::vir::{cfg, BinOpKind, Const, Expr, Stmt, UnaryOpKind};
+
+/// The places that are known to hold a constant value at a program point.
+type ConstantState = HashMap<Expr, Const>;
+
+/// Remove the basic blocks that cannot be reached:
+/// * the statements following an `assert false` or `inhale false` are
+///   removed, together with the outgoing edges of their block;
+/// * the branches of a switch whose guard is `false` after constant
+///   propagation over the CFG are removed;
+/// * the blocks that are no longer reachable from the start block are
+///   removed.
+///
+/// The statements of the retained blocks, and thus their positions, are
+/// left untouched.
+pub fn remove_unreachable_blocks(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
+    if method.basic_blocks.is_empty() {
+        return method;
+    }
+
+    for block in &mut method.basic_blocks {
+        if let Some(index) = block.stmts.iter().position(is_false_assumption) {
+            block.stmts.truncate(index + 1);
+            block.successor = cfg::Successor::Return;
+        }
+    }
+
+    let exit_states = propagate_constants(&method);
+    for (block, exit_state) in method.basic_blocks.iter_mut().zip(exit_states) {
+        if let Some(state) = exit_state {
+            let successor = std::mem::replace(&mut block.successor, cfg::Successor::Undefined);
+            block.successor = prune_successor(successor, &state);
+        }
+    }
+
+    remove_unreachable(method)
+}
+
+/// After an `assert false` or `inhale false` the rest of the block is unreachable.
+fn is_false_assumption(stmt: &Stmt) -> bool {
+    matches!(
+        stmt,
+        Stmt::Assert(Expr::Const(Const::Bool(false), _), _)
+            | Stmt::Inhale(Expr::Const(Const::Bool(false), _))
+    )
+}
+
+/// Compute the constant state at the end of each basic block, by a forward
+/// analysis whose join keeps the places that hold the same constant on all
+/// incoming edges. Blocks that are never reached get `None`.
+fn propagate_constants(method: &cfg::CfgMethod) -> Vec<Option<ConstantState>> {
+    let block_count = method.basic_blocks.len();
+    let mut entry_states: Vec<Option<ConstantState>> = vec![None; block_count];
+    let mut exit_states: Vec<Option<ConstantState>> = vec![None; block_count];
+    entry_states[0] = Some(HashMap::new());
+    let mut to_visit = VecDeque::new();
+    to_visit.push_back(0);
+    while let Some(index) = to_visit.pop_front() {
+        let mut state = entry_states[index].clone().unwrap();
+        for stmt in &method.basic_blocks[index].stmts {
+            apply_stmt(&mut state, stmt);
+        }
+        if exit_states[index].as_ref() == Some(&state) {
+            continue;
+        }
+        let successor = prune_successor(method.basic_blocks[index].successor.clone(), &state);
+        for target in successor.get_following() {
+            let target_index = target.index();
+            let joined = match entry_states[target_index].take() {
+                None => state.clone(),
+                Some(mut target_state) => {
+                    target_state.retain(|place, value| state.get(place) == Some(value));
+                    target_state
+                }
+            };
+            entry_states[target_index] = Some(joined);
+            if !to_visit.contains(&target_index) {
+                to_visit.push_back(target_index);
+            }
+        }
+        exit_states[index] = Some(state);
+    }
+    exit_states
+}
+
+/// Update the constant state with the effect of a statement. Statements that
+/// may change the value of an unknown set of places forget everything.
+fn apply_stmt(state: &mut ConstantState, stmt: &Stmt) {
+    match stmt {
+        Stmt::Comment(_)
+        | Stmt::Label(_)
+        | Stmt::Assert(..)
+        | Stmt::Fold(..)
+        | Stmt::Unfold(..)
+        | Stmt::Obtain(..)
+        | Stmt::BeginFrame
+        | Stmt::EndFrame
+        | Stmt::Downcast(..) => {}
+        Stmt::Assign(target, source, _) if target.is_place() => {
+            let value = evaluate(source, state);
+            forget_aliases(state, target);
+            if let Some(value) = value {
+                state.insert(target.clone(), value);
+            }
+        }
+        Stmt::MethodCall(_, _, targets) => {
+            for target in targets {
+                forget_aliases(state, &Expr::local(target.clone()));
+            }
+            // the callee might modify the heap
+            state.retain(|place, _| place.is_local());
+        }
+        _ => state.clear(),
+    }
+}
+
+/// Forget the value of the places that might be modified by an assignment to
+/// `target`: its extensions, and the places that might alias it.
+fn forget_aliases(state: &mut ConstantState, target: &Expr) {
+    state.retain(|place, _| {
+        if place.has_prefix(target) {
+            return false;
+        }
+        match (place, target) {
+            (Expr::Field(_, place_field, _), Expr::Field(_, target_field, _)) => {
+                place_field != target_field
+            }
+            _ => true,
+        }
+    });
+}
+
+/// Evaluate an expression to a constant, if possible.
+fn evaluate(expr: &Expr, state: &ConstantState) -> Option<Const> {
+    match expr {
+        Expr::Const(value, _) => Some(value.clone()),
+        Expr::Local(..) | Expr::Field(..) => state.get(expr).cloned(),
+        Expr::UnaryOp(UnaryOpKind::Not, box operand, _) => match evaluate(operand, state)? {
+            Const::Bool(value) => Some(Const::Bool(!value)),
+            _ => None,
+        },
+        Expr::UnaryOp(UnaryOpKind::Minus, box operand, _) => match evaluate(operand, state)? {
+            Const::Int(value) => value.checked_neg().map(Const::Int),
+            _ => None,
+        },
+        Expr::BinOp(BinOpKind::And, box left, box right, _) => {
+            match (evaluate(left, state), evaluate(right, state)) {
+                (Some(Const::Bool(false)), _) | (_, Some(Const::Bool(false))) => {
+                    Some(Const::Bool(false))
+                }
+                (Some(Const::Bool(true)), Some(Const::Bool(true))) => Some(Const::Bool(true)),
+                _ => None,
+            }
+        }
+        Expr::BinOp(BinOpKind::Or, box left, box right, _) => {
+            match (evaluate(left, state), evaluate(right, state)) {
+                (Some(Const::Bool(true)), _) | (_, Some(Const::Bool(true))) => {
+                    Some(Const::Bool(true))
+                }
+                (Some(Const::Bool(false)), Some(Const::Bool(false))) => Some(Const::Bool(false)),
+                _ => None,
+            }
+        }
+        Expr::BinOp(op, box left, box right, _) => {
+            match (op, evaluate(left, state)?, evaluate(right, state)?) {
+                (BinOpKind::EqCmp, Const::Bool(l), Const::Bool(r)) => Some(Const::Bool(l == r)),
+                (BinOpKind::NeCmp, Const::Bool(l), Const::Bool(r)) => Some(Const::Bool(l != r)),
+                (op, Const::Int(l), Const::Int(r)) => match op {
+                    BinOpKind::EqCmp => Some(Const::Bool(l == r)),
+                    BinOpKind::NeCmp => Some(Const::Bool(l != r)),
+                    BinOpKind::GtCmp => Some(Const::Bool(l > r)),
+                    BinOpKind::GeCmp => Some(Const::Bool(l >= r)),
+                    BinOpKind::LtCmp => Some(Const::Bool(l < r)),
+                    BinOpKind::LeCmp => Some(Const::Bool(l <= r)),
+                    BinOpKind::Add => l.checked_add(r).map(Const::Int),
+                    BinOpKind::Sub => l.checked_sub(r).map(Const::Int),
+                    BinOpKind::Mul => l.checked_mul(r).map(Const::Int),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Remove the targets of a switch whose guard is statically `false`. If a
+/// guard is statically `true`, its target is always taken.
+fn prune_successor(successor: cfg::Successor, state: &ConstantState) -> cfg::Successor {
+    match successor {
+        cfg::Successor::GotoSwitch(guarded_targets, default_target) => {
+            let mut retained_targets = vec![];
+            for (guard, target) in guarded_targets {
+                match evaluate(&guard, state) {
+                    Some(Const::Bool(false)) => {}
+                    Some(Const::Bool(true)) if retained_targets.is_empty() => {
+                        return cfg::Successor::Goto(target);
+                    }
+                    Some(Const::Bool(true)) => {
+                        return cfg::Successor::GotoSwitch(retained_targets, target);
+                    }
+                    _ => retained_targets.push((guard, target)),
+                }
+            }
+            if retained_targets.is_empty() {
+                cfg::Successor::Goto(default_target)
+            } else {
+                cfg::Successor::GotoSwitch(retained_targets, default_target)
+            }
+        }
+        successor => successor,
+    }
+}
+
+/// Remove the blocks that are not reachable from the start block.
+fn remove_unreachable(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
+    let block_count = method.basic_blocks.len();
+    let mut reachable = vec![false; block_count];
+    reachable[0] = true;
+    let mut to_visit = vec![0];
+    while let Some(index) = to_visit.pop() {
+        for target in method.basic_blocks[index].successor.get_following() {
+            if !reachable[target.index()] {
+                reachable[target.index()] = true;
+                to_visit.push(target.index());
+            }
+        }
+    }
+
+    let mut new_indices = HashMap::new();
+    let mut new_basic_blocks = Vec::new();
+    let mut new_basic_blocks_labels = Vec::new();
+    let basic_blocks = method.basic_blocks.into_iter().zip(method.basic_blocks_labels);
+    for (index, (basic_block, label)) in basic_blocks.enumerate() {
+        if reachable[index] {
+            new_indices.insert(index, new_basic_blocks.len());
+            new_basic_blocks.push(basic_block);
+            new_basic_blocks_labels.push(label);
+        }
+    }
+    for basic_block in &mut new_basic_blocks {
+        match &mut basic_block.successor {
+            cfg::Successor::Undefined | cfg::Successor::Return => {},
+            cfg::Successor::Goto(target) => {
+                target.block_index = new_indices[&target.block_index];
+            }
+            cfg::Successor::GotoSwitch(conditional_targets, default_target) => {
+                default_target.block_index = new_indices[&default_target.block_index];
+                for (_, target) in conditional_targets {
+                    target.block_index = new_indices[&target.block_index];
+                }
+            }
+        }
+    }
+    method.basic_blocks = new_basic_blocks;
+    method.basic_blocks_labels = new_basic_blocks_labels;
+    method
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{AssignKind, LocalVar, Position, Type};
+
+    fn new_method() -> cfg::CfgMethod {
+        cfg::CfgMethod::new("test".to_string(), 0, vec![], vec![], vec![])
+    }
+
+    fn local(name: &str, typ: Type) -> Expr {
+        Expr::local(LocalVar::new(name, typ))
+    }
+
+    #[test]
+    fn removes_blocks_after_inhale_false() {
+        let mut method = new_method();
+        let start = method.add_block("start", vec![Stmt::Inhale(false.into())]);
+        let dead = method.add_block("dead", vec![Stmt::Assert(false.into(), Position::default())]);
+        method.set_successor(start, cfg::Successor::Goto(dead));
+        method.set_successor(dead, cfg::Successor::Return);
+        assert_eq!(method.basic_blocks.len(), 2);
+
+        let method = remove_unreachable_blocks(method);
+        assert_eq!(method.basic_blocks.len(), 1);
+        assert_eq!(method.basic_blocks_labels, vec!["start".to_string()]);
+    }
+
+    #[test]
+    fn removes_branches_on_constants() {
+        let mut method = new_method();
+        let discriminant = local("discriminant", Type::Int);
+        let start = method.add_block("start", vec![Stmt::Assign(
+            discriminant.clone(),
+            1.into(),
+            AssignKind::Copy,
+        )]);
+        let variant_0 = method.add_block("variant_0", vec![]);
+        let variant_1 = method.add_block("variant_1", vec![]);
+        let variant_2 = method.add_block("variant_2", vec![]);
+        let end = method.add_block("end", vec![]);
+        method.set_successor(start, cfg::Successor::GotoSwitch(
+            vec![
+                (Expr::eq_cmp(discriminant.clone(), 0.into()), variant_0),
+                (Expr::eq_cmp(discriminant, 1.into()), variant_1),
+            ],
+            variant_2,
+        ));
+        method.set_successor(variant_0, cfg::Successor::Goto(end));
+        method.set_successor(variant_1, cfg::Successor::Goto(end));
+        method.set_successor(variant_2, cfg::Successor::Goto(end));
+        method.set_successor(end, cfg::Successor::Return);
+        assert_eq!(method.basic_blocks.len(), 5);
+
+        let method = remove_unreachable_blocks(method);
+        assert_eq!(method.basic_blocks.len(), 3);
+        assert_eq!(
+            method.basic_blocks_labels,
+            vec!["start".to_string(), "variant_1".to_string(), "end".to_string()],
+        );
+    }
+
+    #[test]
+    fn keeps_branches_on_unknown_values() {
+        let mut method = new_method();
+        let flag = local("flag", Type::Bool);
+        let start = method.add_block("start", vec![Stmt::Assign(
+            flag.clone(),
+            true.into(),
+            AssignKind::Copy,
+        )]);
+        let join = method.add_block("join", vec![]);
+        let havoc = method.add_block("havoc", vec![Stmt::MethodCall(
+            "havoc_bool".to_string(),
+            vec![],
+            vec![LocalVar::new("flag", Type::Bool)],
+        )]);
+        let then_block = method.add_block("then_block", vec![]);
+        let else_block = method.add_block("else_block", vec![]);
+        method.set_successor(start, cfg::Successor::GotoSwitch(
+            vec![(local("unknown", Type::Bool), havoc)],
+            join,
+        ));
+        method.set_successor(havoc, cfg::Successor::Goto(join));
+        method.set_successor(join, cfg::Successor::GotoSwitch(
+            vec![(flag, then_block)],
+            else_block,
+        ));
+        method.set_successor(then_block, cfg::Successor::Return);
+        method.set_successor(else_block, cfg::Successor::Return);
+        assert_eq!(method.basic_blocks.len(), 5);
+
+        let method = remove_unreachable_blocks(method);
+        assert_eq!(method.basic_blocks.len(), 5);
+    }
+}
@@ -7,9 +7,11 @@
 //! A module that contains optimizations for methods.
 
 mod cfg_cleaner;
+mod dead_block_remover;
 mod empty_if_remover;
 mod assert_remover;
 mod var_remover;
+mod dead_assignment_remover;
 mod purifier;
 mod quantifier_fixer;
 
@@ -18,9 +20,11 @@ use crate::vir::cfg::CfgMethod;
 use super::log_method;
 
 use self::cfg_cleaner::clean_cfg;
+use self::dead_block_remover::remove_unreachable_blocks;
 use self::empty_if_remover::remove_empty_if;
 use self::assert_remover::remove_trivial_assertions;
 use self::var_remover::remove_unused_vars;
+use self::dead_assignment_remover::remove_dead_assignments;
 use self::purifier::purify_vars;
 use self::quantifier_fixer::fix_quantifiers;
 
@@ -40,8 +44,10 @@ pub fn optimize_method_encoding(cfg: CfgMethod, source_file_name: &str, optimiza
     let cfg = apply!(purify_vars, cfg);
     let cfg = apply!(fix_quantifiers, cfg);
     let cfg = apply!(remove_empty_if, cfg);
+    let cfg = apply!(remove_dead_assignments, cfg);
     let cfg = apply!(remove_unused_vars, cfg);
     let cfg = apply!(remove_trivial_assertions, cfg);
+    let cfg = apply!(remove_unreachable_blocks, cfg);
     let cfg = apply!(clean_cfg, cfg);
 
     cfg
@@ -184,6 +184,19 @@ impl ast::ExprWalker for VarCollector {
             self.all_vars.remove(var);
         }
     }
+    fn walk_exists(
+        &mut self,
+        vars: &Vec<ast::LocalVar>,
+        _triggers: &Vec<ast::Trigger>,
+        body: &ast::Expr,
+        _pos: &ast::Position,
+    ) {
+        self.walk(body);
+        for var in vars {
+            // TODO: This is not bullet proof against name collisions.
+            self.all_vars.remove(var);
+        }
+    }
 }
 
 impl ast::StmtWalker for VarCollector {
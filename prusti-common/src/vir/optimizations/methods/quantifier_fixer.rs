@@ -16,6 +16,8 @@ use log::debug;
 /// 3.  Replace all arithmetic expressions inside `forall` that do not depend on bound variables
 ///     with `let tmp == (...) in forall ..`.
 ///
+/// The same is done for `exists ..` quantifiers.
+///
 /// Note: this seems to be required to workaround some Silicon incompleteness.
 pub fn fix_quantifiers(cfg: vir::CfgMethod) -> vir::CfgMethod {
     let mut optimizer = Optimizer::new();
@@ -26,6 +28,10 @@ struct Optimizer {
     counter: u32,
 }
 
+/// Constructor of a quantifier expression, i.e. `vir::Expr::ForAll` or `vir::Expr::Exists`.
+type QuantifierConstructor =
+    fn(Vec<vir::LocalVar>, Vec<vir::Trigger>, Box<vir::Expr>, vir::Position) -> vir::Expr;
+
 impl Optimizer {
     fn new() -> Self {
         Self { counter: 0 }
@@ -61,6 +67,34 @@ impl Optimizer {
         use self::vir::ExprFolder;
         unfolding_extractor.fold(expr)
     }
+
+    fn fold_quantifier(
+        &mut self,
+        constructor: QuantifierConstructor,
+        variables: Vec<vir::LocalVar>,
+        triggers: Vec<vir::Trigger>,
+        body: Box<vir::Expr>,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        use self::vir::ExprFolder;
+        debug!("original body: {}", body);
+        let folded_body = self.fold_boxed(body);
+        debug!("Folded body: {}", folded_body);
+        let old_counter = self.counter;
+        let mut replacer = Replacer::new(&variables, &mut self.counter);
+        let replaced_body = replacer.fold_boxed(folded_body);
+        debug!("replaced body: {}", replaced_body);
+        let mut quantifier = constructor(variables, triggers, replaced_body, pos);
+
+        if *replacer.counter > old_counter {
+            for (expr, variable) in replacer.map {
+                quantifier = vir::Expr::LetExpr(variable, box expr, box quantifier, pos);
+            }
+            debug!("replaced quantifier: {}", quantifier);
+        }
+
+        quantifier
+    }
 }
 
 impl vir::StmtFolder for Optimizer {
@@ -91,23 +125,16 @@ impl vir::ExprFolder for Optimizer {
         body: Box<vir::Expr>,
         pos: vir::Position,
     ) -> vir::Expr {
-        debug!("original body: {}", body);
-        let folded_body = self.fold_boxed(body);
-        debug!("Folded body: {}", folded_body);
-        let old_counter = self.counter;
-        let mut replacer = Replacer::new(&variables, &mut self.counter);
-        let replaced_body = replacer.fold_boxed(folded_body);
-        debug!("replaced body: {}", replaced_body);
-        let mut forall = vir::Expr::ForAll(variables, triggers, replaced_body, pos);
-
-        if *replacer.counter > old_counter {
-            for (expr, variable) in replacer.map {
-                forall = vir::Expr::LetExpr(variable, box expr, box forall, pos);
-            }
-            debug!("replaced quantifier: {}", forall);
-        }
-
-        forall
+        self.fold_quantifier(vir::Expr::ForAll, variables, triggers, body, pos)
+    }
+    fn fold_exists(
+        &mut self,
+        variables: Vec<vir::LocalVar>,
+        triggers: Vec<vir::Trigger>,
+        body: Box<vir::Expr>,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        self.fold_quantifier(vir::Expr::Exists, variables, triggers, body, pos)
     }
 }
 
@@ -209,6 +236,15 @@ impl<'a> vir::ExprFolder for Replacer<'a> {
     ) -> vir::Expr {
         vir::Expr::ForAll(variables, triggers, body, pos)
     }
+    fn fold_exists(
+        &mut self,
+        variables: Vec<vir::LocalVar>,
+        triggers: Vec<vir::Trigger>,
+        body: Box<vir::Expr>,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        vir::Expr::Exists(variables, triggers, body, pos)
+    }
 }
 
 struct UnfoldingExtractor {
@@ -219,14 +255,16 @@ struct UnfoldingExtractor {
     in_quantifier: bool,
 }
 
-impl vir::ExprFolder for UnfoldingExtractor {
-    fn fold_forall(
+impl UnfoldingExtractor {
+    fn fold_quantifier(
         &mut self,
+        constructor: QuantifierConstructor,
         variables: Vec<vir::LocalVar>,
         triggers: Vec<vir::Trigger>,
         body: Box<vir::Expr>,
         pos: vir::Position,
     ) -> vir::Expr {
+        use self::vir::ExprFolder;
         assert!(
             self.unfoldings.is_empty(),
             "Nested quantifiers are not supported."
@@ -237,17 +275,38 @@ impl vir::ExprFolder for UnfoldingExtractor {
         let replaced_body = self.fold_boxed(body);
         self.in_quantifier = false;
 
-        let mut forall = vir::Expr::ForAll(variables, triggers, replaced_body, pos);
+        let mut quantifier = constructor(variables, triggers, replaced_body, pos);
 
         let unfoldings = mem::replace(&mut self.unfoldings, HashMap::new());
 
         for ((name, args), (perm_amount, variant, _)) in unfoldings {
-            forall =
-                vir::Expr::Unfolding(name, args, box forall, perm_amount, variant, pos);
+            quantifier =
+                vir::Expr::Unfolding(name, args, box quantifier, perm_amount, variant, pos);
         }
-        debug!("replaced quantifier: {}", forall);
+        debug!("replaced quantifier: {}", quantifier);
 
-        forall
+        quantifier
+    }
+}
+
+impl vir::ExprFolder for UnfoldingExtractor {
+    fn fold_forall(
+        &mut self,
+        variables: Vec<vir::LocalVar>,
+        triggers: Vec<vir::Trigger>,
+        body: Box<vir::Expr>,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        self.fold_quantifier(vir::Expr::ForAll, variables, triggers, body, pos)
+    }
+    fn fold_exists(
+        &mut self,
+        variables: Vec<vir::LocalVar>,
+        triggers: Vec<vir::Trigger>,
+        body: Box<vir::Expr>,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        self.fold_quantifier(vir::Expr::Exists, variables, triggers, body, pos)
     }
     fn fold_unfolding(
         &mut self,
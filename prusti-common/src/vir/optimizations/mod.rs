@@ -8,11 +8,14 @@
 
 use crate::vir::{CfgMethod, Program};
 use crate::config::{self, optimizations, Optimizations};
+use std::collections::HashSet;
 
+pub mod expressions;
 pub mod folding;
 pub mod functions;
 pub mod methods;
 pub mod predicates;
+pub mod program;
 pub mod purification;
 
 fn log_method(
@@ -47,7 +50,9 @@ fn log_methods(
 }
 
 impl Program {
-    pub fn optimized(mut self, source_file_name: &str) -> Self {
+    /// Optimize the program. `pure_functions` are the names of the functions that encode Rust
+    /// pure functions.
+    pub fn optimized(mut self, source_file_name: &str, pure_functions: &HashSet<String>) -> Self {
         let optimizations = config::optimizations();
         info!("Enabled optimisations: {:?}", optimizations);
 
@@ -70,6 +75,29 @@ impl Program {
                 true
             );
         }
+        if optimizations.inline_pure_functions {
+            log_methods(
+                source_file_name,
+                &self.methods,
+                "inline_pure_functions",
+                false
+            );
+            let (new_methods, new_functions) = functions::inline_pure_functions(
+                self.methods,
+                self.functions,
+                &self.domains,
+                pure_functions,
+                config::inline_pure_functions_max_calls(),
+            );
+            self.methods = new_methods;
+            self.functions = new_functions;
+            log_methods(
+                source_file_name,
+                &self.methods,
+                "inline_pure_functions",
+                true
+            );
+        }
         if optimizations.optimize_folding {
             log_methods(
                 source_file_name,
@@ -94,6 +122,28 @@ impl Program {
                 true
             );
         }
+        if optimizations.simplify_expressions {
+            log_methods(
+                source_file_name,
+                &self.methods,
+                "simplify_expressions",
+                false
+            );
+            self.methods = self.methods
+                .into_iter()
+                .map(expressions::simplify_method)
+                .collect();
+            self.functions = self.functions
+                .into_iter()
+                .map(expressions::simplify_function)
+                .collect();
+            log_methods(
+                source_file_name,
+                &self.methods,
+                "simplify_expressions",
+                true
+            );
+        }
         self.methods = self.methods.into_iter().map(|method| {
             methods::optimize_method_encoding(method, source_file_name, &optimizations)
         }).collect();
@@ -104,6 +154,9 @@ impl Program {
                 self.viper_predicates,
             );
         }
+        if optimizations.delete_unused_items {
+            self = program::delete_unused_items(self);
+        }
 
         if config::enable_purification_optimization() {
             self.methods=purification::purify_methods(self.methods, &self.viper_predicates);
@@ -0,0 +1,416 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Removal of the functions, predicates and domains that cannot be reached from the methods.
+
+use crate::vir::{
+    ast::*,
+    cfg::CfgMethod,
+    utils::walk_methods,
+    Program,
+};
+use std::collections::{BTreeSet, HashMap};
+
+/// Delete the functions, predicates and domains of `program` that are not reachable from its
+/// methods, builtin methods and fields.
+///
+/// An item is reachable if it is referenced by the methods or by the signature, the
+/// specification or the body of a reachable item. Functions are referenced by calls, predicates
+/// by accesses, folds and unfoldings, and domains by the types of variables, fields and
+/// functions and by the calls of their functions. All the axioms of a reachable domain are kept,
+/// and the items that they reference are reachable too.
+pub fn delete_unused_items(mut program: Program) -> Program {
+    let mut collector = UsedItemCollector::default();
+    walk_methods(&program.methods, &mut collector);
+    for method in &program.methods {
+        collector.walk_method_signature(method);
+    }
+    for method in &program.builtin_methods {
+        for var in method.formal_args.iter().chain(method.formal_returns.iter()) {
+            collector.add_type(&var.typ);
+        }
+    }
+    for field in &program.fields {
+        collector.add_type(&field.typ);
+    }
+    // DeadBorrowToken$ is a used predicate but it does not appear in VIR
+    // becaue it is only created when viper code is created from VIR
+    collector.predicates.insert("DeadBorrowToken$".to_string());
+
+    let functions: HashMap<&str, &Function> = program.functions.iter()
+        .map(|function| (function.name.as_str(), function))
+        .collect();
+    let predicates: HashMap<&str, &Predicate> = program.viper_predicates.iter()
+        .map(|predicate| (predicate.name(), predicate))
+        .collect();
+    let domains: HashMap<&str, &Domain> = program.domains.iter()
+        .map(|domain| (domain.name.as_str(), domain))
+        .collect();
+
+    // Visit the reachable items until a fixpoint is reached
+    let mut visited = UsedItemCollector::default();
+    loop {
+        let new_functions: Vec<_> = collector.functions.difference(&visited.functions)
+            .cloned()
+            .collect();
+        let new_predicates: Vec<_> = collector.predicates.difference(&visited.predicates)
+            .cloned()
+            .collect();
+        let new_domains: Vec<_> = collector.domains.difference(&visited.domains)
+            .cloned()
+            .collect();
+        if new_functions.is_empty() && new_predicates.is_empty() && new_domains.is_empty() {
+            break;
+        }
+        for name in new_functions {
+            if let Some(function) = functions.get(name.as_str()) {
+                collector.walk_function(function);
+            }
+            visited.functions.insert(name);
+        }
+        for name in new_predicates {
+            if let Some(predicate) = predicates.get(name.as_str()) {
+                collector.walk_predicate(predicate);
+            }
+            visited.predicates.insert(name);
+        }
+        for name in new_domains {
+            if let Some(domain) = domains.get(name.as_str()) {
+                collector.walk_domain(domain);
+            }
+            visited.domains.insert(name);
+        }
+    }
+
+    debug!("The reachable functions are {:?}", &visited.functions);
+    debug!("The reachable predicates are {:?}", &visited.predicates);
+    debug!("The reachable domains are {:?}", &visited.domains);
+
+    program.functions.retain(|function| visited.functions.contains(&function.name));
+    program.viper_predicates.retain(|predicate| visited.predicates.contains(predicate.name()));
+    program.domains.retain(|domain| visited.domains.contains(&domain.name));
+    program
+}
+
+/// Collect the names of the functions, predicates and domains used by the walked items.
+#[derive(Default)]
+struct UsedItemCollector {
+    functions: BTreeSet<String>,
+    predicates: BTreeSet<String>,
+    domains: BTreeSet<String>,
+}
+
+impl UsedItemCollector {
+    /// Record the domain that encodes `typ`, if any.
+    fn add_type(&mut self, typ: &Type) {
+        match typ {
+            Type::Domain(name) => {
+                self.domains.insert(name.clone());
+            }
+            Type::Snapshot(name) => {
+                self.domains.insert(format!("Snap${}", name));
+            }
+            Type::Int | Type::Bool | Type::TypedRef(_) => {}
+        }
+    }
+
+    fn walk_method_signature(&mut self, method: &CfgMethod) {
+        for var in method.formal_returns.iter().chain(method.local_vars.iter()) {
+            self.add_type(&var.typ);
+        }
+    }
+
+    fn walk_function(&mut self, function: &Function) {
+        for var in &function.formal_args {
+            self.add_type(&var.typ);
+        }
+        self.add_type(&function.return_type);
+        for expr in function.pres.iter().chain(function.posts.iter()).chain(function.body.iter()) {
+            ExprWalker::walk(self, expr);
+        }
+    }
+
+    fn walk_struct_predicate(&mut self, predicate: &StructPredicate) {
+        self.add_type(&predicate.this.typ);
+        if let Some(body) = &predicate.body {
+            ExprWalker::walk(self, body);
+        }
+    }
+
+    fn walk_predicate(&mut self, predicate: &Predicate) {
+        match predicate {
+            Predicate::Struct(predicate) => self.walk_struct_predicate(predicate),
+            Predicate::Enum(predicate) => {
+                self.add_type(&predicate.this.typ);
+                self.add_type(&predicate.discriminant_field.typ);
+                ExprWalker::walk(self, &predicate.discriminant_bounds);
+                for (guard, _, variant) in &predicate.variants {
+                    ExprWalker::walk(self, guard);
+                    self.walk_struct_predicate(variant);
+                }
+            }
+            Predicate::Bodyless(_, this) => self.add_type(&this.typ),
+        }
+    }
+
+    fn walk_domain(&mut self, domain: &Domain) {
+        for function in &domain.functions {
+            for var in &function.formal_args {
+                self.add_type(&var.typ);
+            }
+            self.add_type(&function.return_type);
+        }
+        for axiom in &domain.axioms {
+            ExprWalker::walk(self, &axiom.expr);
+        }
+    }
+}
+
+impl ExprWalker for UsedItemCollector {
+    fn walk_local_var(&mut self, var: &LocalVar) {
+        self.add_type(&var.typ);
+    }
+
+    fn walk_variant(&mut self, base: &Expr, variant: &Field, _pos: &Position) {
+        self.add_type(&variant.typ);
+        ExprWalker::walk(self, base);
+    }
+
+    fn walk_field(&mut self, receiver: &Expr, field: &Field, _pos: &Position) {
+        self.add_type(&field.typ);
+        ExprWalker::walk(self, receiver);
+    }
+
+    fn walk_addr_of(&mut self, receiver: &Expr, typ: &Type, _pos: &Position) {
+        self.add_type(typ);
+        ExprWalker::walk(self, receiver);
+    }
+
+    fn walk_predicate_access_predicate(
+        &mut self,
+        name: &str,
+        arg: &Expr,
+        _perm_amount: PermAmount,
+        _pos: &Position
+    ) {
+        self.predicates.insert(name.to_string());
+        ExprWalker::walk(self, arg);
+    }
+
+    fn walk_unfolding(
+        &mut self,
+        name: &str,
+        args: &Vec<Expr>,
+        body: &Expr,
+        _perm: PermAmount,
+        _variant: &MaybeEnumVariantIndex,
+        _pos: &Position
+    ) {
+        self.predicates.insert(name.to_string());
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+        ExprWalker::walk(self, body);
+    }
+
+    fn walk_func_app(
+        &mut self,
+        name: &str,
+        args: &Vec<Expr>,
+        formal_args: &Vec<LocalVar>,
+        return_type: &Type,
+        _pos: &Position
+    ) {
+        self.functions.insert(name.to_string());
+        self.add_type(return_type);
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+        for arg in formal_args {
+            ExprWalker::walk_local_var(self, arg);
+        }
+    }
+
+    fn walk_domain_func_app(&mut self, func: &DomainFunc, args: &Vec<Expr>, _pos: &Position) {
+        self.domains.insert(func.domain_name.clone());
+        self.add_type(&func.return_type);
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+        for arg in &func.formal_args {
+            ExprWalker::walk_local_var(self, arg);
+        }
+    }
+}
+
+impl StmtWalker for UsedItemCollector {
+    fn walk_expr(&mut self, expr: &Expr) {
+        ExprWalker::walk(self, expr);
+    }
+
+    fn walk_local_var(&mut self, var: &LocalVar) {
+        self.add_type(&var.typ);
+    }
+
+    fn walk_fold(
+        &mut self,
+        predicate_name: &str,
+        args: &Vec<Expr>,
+        _perm: &PermAmount,
+        _variant: &MaybeEnumVariantIndex,
+        _pos: &Position
+    ) {
+        self.predicates.insert(predicate_name.to_string());
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+    }
+
+    fn walk_unfold(
+        &mut self,
+        predicate_name: &str,
+        args: &Vec<Expr>,
+        _perm: &PermAmount,
+        _variant: &MaybeEnumVariantIndex,
+    ) {
+        self.predicates.insert(predicate_name.to_string());
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{cfg, Stmt};
+
+    fn domain_func(domain_name: &str, name: &str, return_type: Type) -> DomainFunc {
+        DomainFunc {
+            name: name.to_string(),
+            formal_args: vec![LocalVar::new("_1", Type::Int)],
+            return_type,
+            unique: false,
+            domain_name: domain_name.to_string(),
+        }
+    }
+
+    fn domain(name: &str, functions: Vec<DomainFunc>, axioms: Vec<Expr>) -> Domain {
+        Domain {
+            name: name.to_string(),
+            functions,
+            axioms: axioms.into_iter()
+                .enumerate()
+                .map(|(index, expr)| DomainAxiom {
+                    name: format!("{}$axiom${}", name, index),
+                    expr,
+                    domain_name: name.to_string(),
+                })
+                .collect(),
+            type_vars: vec![],
+        }
+    }
+
+    fn function(name: &str, formal_args: Vec<LocalVar>, body: Expr) -> Function {
+        Function {
+            name: name.to_string(),
+            formal_args,
+            return_type: Type::Int,
+            pres: vec![],
+            posts: vec![],
+            body: Some(body),
+        }
+    }
+
+    fn program(stmts: Vec<Stmt>, domains: Vec<Domain>, functions: Vec<Function>) -> Program {
+        let mut method = cfg::CfgMethod::new(
+            "main".to_string(),
+            0,
+            vec![],
+            vec![LocalVar::new("x", Type::Int)],
+            vec![],
+        );
+        let block = method.add_block("start", stmts);
+        method.set_successor(block, cfg::Successor::Return);
+        Program {
+            domains,
+            fields: vec![],
+            builtin_methods: vec![],
+            methods: vec![method],
+            functions,
+            viper_predicates: vec![],
+        }
+    }
+
+    fn domain_names(program: &Program) -> Vec<&str> {
+        program.domains.iter().map(|domain| domain.name.as_str()).collect()
+    }
+
+    fn x() -> Expr {
+        Expr::local(LocalVar::new("x", Type::Int))
+    }
+
+    #[test]
+    fn delete_orphan_domain() {
+        let used_func = domain_func("Used", "used$f", Type::Int);
+        let orphan_func = domain_func("Orphan", "orphan$f", Type::Int);
+        let program = program(
+            vec![Stmt::Assert(
+                Expr::gt_cmp(used_func.apply(vec![x()]), 0.into()),
+                Position::default(),
+            )],
+            vec![
+                domain("Used", vec![used_func], vec![]),
+                domain("Orphan", vec![orphan_func], vec![]),
+            ],
+            vec![],
+        );
+
+        let program = delete_unused_items(program);
+
+        assert_eq!(domain_names(&program), vec!["Used"]);
+    }
+
+    #[test]
+    fn keep_domains_used_by_axioms_and_types() {
+        let used_func = domain_func("Used", "used$f", Type::Int);
+        let axiom_func = domain_func("FromAxiom", "from_axiom$f", Type::Int);
+        let snapshot_arg = LocalVar::new("_1", Type::Snapshot("T".to_string()));
+        let program = program(
+            vec![Stmt::Assert(
+                Expr::gt_cmp(
+                    Expr::func_app(
+                        "snap_user".to_string(),
+                        vec![x()],
+                        vec![snapshot_arg.clone()],
+                        Type::Int,
+                        Position::default(),
+                    ),
+                    used_func.apply(vec![x()]),
+                ),
+                Position::default(),
+            )],
+            vec![
+                domain("Used", vec![used_func], vec![
+                    Expr::eq_cmp(axiom_func.apply(vec![0.into()]), 0.into()),
+                ]),
+                domain("FromAxiom", vec![axiom_func], vec![]),
+                domain("Snap$T", vec![], vec![]),
+                domain("Orphan", vec![], vec![]),
+            ],
+            vec![
+                function("snap_user", vec![snapshot_arg], 0.into()),
+                function("unused", vec![], 0.into()),
+            ],
+        );
+
+        let program = delete_unused_items(program);
+
+        assert_eq!(domain_names(&program), vec!["Used", "FromAxiom", "Snap$T"]);
+        assert_eq!(program.functions.len(), 1);
+        assert_eq!(program.functions[0].name, "snap_user");
+    }
+}
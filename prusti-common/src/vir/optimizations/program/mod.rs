@@ -0,0 +1,11 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Optimizations of a whole VIR program.
+
+mod delete_unused_items;
+
+pub use self::delete_unused_items::delete_unused_items;
@@ -9,3 +9,24 @@ pub struct Program {
     pub functions: Vec<Function>,
     pub viper_predicates: Vec<Predicate>,
 }
+
+impl Program {
+    /// Splits the program into one program per method, which can be verified
+    /// independently. Each program keeps all the domains, fields, functions,
+    /// and predicates of the original one. The positions of the methods are
+    /// not changed, so that the errors of all the programs can be reported
+    /// with the same error manager.
+    pub fn split_by_method(mut self) -> Vec<Program> {
+        if self.methods.len() <= 1 {
+            return vec![self];
+        }
+        let methods = std::mem::take(&mut self.methods);
+        methods
+            .into_iter()
+            .map(|method| Program {
+                methods: vec![method],
+                ..self.clone()
+            })
+            .collect()
+    }
+}
@@ -118,6 +118,13 @@ macro_rules! vir {
             vir!($body),
         )
     };
+    (exists $($name: ident : $type: tt),+ :: {$($triggers: tt),*} $body: tt) => {
+        $crate::vir::Expr::exists(
+            vec![$($crate::vir_local!($name: $type)),+],
+            vec![$($crate::vir::Trigger::new(vec![vir!($triggers)])),*],
+            vir!($body),
+        )
+    };
     ([ $e: expr ]) => { $e.clone() };
     (( $($tokens: tt)+ )) => { vir!($($tokens)+) }
 }
@@ -140,6 +147,19 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn exists() {
+        let expected = Expr::Exists(
+            vec![vir_local!(i: Int)],
+            vec![Trigger::new(vec![vir!{ true }])],
+            Box::new(vir!{ false }),
+            Position::default());
+
+        let actual = vir!{ exists i: Int :: {true} false };
+
+        assert_eq!(expected, actual);
+    }
+
     #[test]
     fn expr_passthrough() {
         let expr = vir!{ assert true };
@@ -33,11 +33,96 @@ pub fn trusted(attr: TokenStream, tokens: TokenStream) -> TokenStream {
     rewrite_prusti_attributes(SpecAttributeKind::Trusted, attr.into(), tokens.into()).into()
 }
 
+#[proc_macro_attribute]
+pub fn allow_spec_warnings(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    rewrite_prusti_attributes(SpecAttributeKind::AllowSpecWarnings, attr.into(), tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn decreases(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    rewrite_prusti_attributes(SpecAttributeKind::Decreases, attr.into(), tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn bitvector(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    rewrite_prusti_attributes(SpecAttributeKind::Bitvector, attr.into(), tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn trusted_unsafe(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    rewrite_prusti_attributes(SpecAttributeKind::TrustedUnsafe, attr.into(), tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn refine_spec(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    rewrite_prusti_attributes(SpecAttributeKind::RefineSpec, attr.into(), tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn no_invariant(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    rewrite_prusti_attributes(SpecAttributeKind::NoInvariant, attr.into(), tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn panic_free(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    rewrite_prusti_attributes(SpecAttributeKind::PanicFree, attr.into(), tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn fuel(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    rewrite_prusti_attributes(SpecAttributeKind::Fuel, attr.into(), tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn assigns(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    rewrite_prusti_attributes(SpecAttributeKind::Assigns, attr.into(), tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn bounded(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    rewrite_prusti_attributes(SpecAttributeKind::Bounded, attr.into(), tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn invariant(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    prusti_specs::invariant(attr.into(), tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn history_invariant(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    prusti_specs::history_invariant(attr.into(), tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn derive_spec_eq(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    prusti_specs::derive_spec_eq(attr.into(), tokens.into()).into()
+}
+
 #[proc_macro]
 pub fn body_invariant(tokens: TokenStream) -> TokenStream {
     prusti_specs::body_invariant(tokens.into()).into()
 }
 
+#[proc_macro]
+pub fn prusti_assert(tokens: TokenStream) -> TokenStream {
+    prusti_specs::prusti_assertion(tokens.into()).into()
+}
+
+#[proc_macro]
+pub fn prusti_assume(tokens: TokenStream) -> TokenStream {
+    prusti_specs::prusti_assumption(tokens.into()).into()
+}
+
+#[proc_macro]
+pub fn label(tokens: TokenStream) -> TokenStream {
+    prusti_specs::label(tokens.into()).into()
+}
+
+#[proc_macro]
+pub fn ghost(tokens: TokenStream) -> TokenStream {
+    prusti_specs::ghost(tokens.into()).into()
+}
+
 #[proc_macro]
 pub fn closure(tokens: TokenStream) -> TokenStream {
     prusti_specs::closure(tokens.into(), false).into()
@@ -19,3 +19,34 @@ pub fn test4() {
         body_invariant!(true)
     }
 }
+
+pub fn test5() {
+    prusti_assert!(true);
+    prusti_assume!(true);
+}
+
+pub fn test6() {
+    ghost! {
+        let g = 1;
+    }
+    prusti_assert!(g == 1);
+}
+
+macro_rules! generate_test7 {
+    ($name:ident) => {
+        #[requires(x < 100)]
+        #[ensures(result == x + 1)]
+        pub fn $name(x: u32) -> u32 {
+            x + 1
+        }
+    };
+}
+
+generate_test7!(test7);
+
+#[ensures(result == x)]
+#[cfg_attr(prusti, requires(x > 0))]
+#[cfg_attr(feature = "prusti", ensures(result > 0))]
+pub fn test8(x: u32) -> u32 {
+    x
+}
@@ -20,9 +20,71 @@ mod private {
     /// A macro for marking a function as trusted.
     pub use prusti_contracts_impl::trusted;
 
+    /// A macro for suppressing specification warnings of a function.
+    pub use prusti_contracts_impl::allow_spec_warnings;
+
+    /// A macro for annotating a function with a termination measure.
+    pub use prusti_contracts_impl::decreases;
+
+    /// A macro for encoding the integers of a function with their bit width.
+    pub use prusti_contracts_impl::bitvector;
+
+    /// A macro for trusting the `unsafe` blocks of a function.
+    pub use prusti_contracts_impl::trusted_unsafe;
+
+    /// A macro for writing specifications of a function that only hold for
+    /// the instantiations of its generics that satisfy some trait bounds.
+    pub use prusti_contracts_impl::refine_spec;
+
+    /// A macro for writing an invariant of a struct or enum, which holds for
+    /// the values passed to and returned from functions.
+    pub use prusti_contracts_impl::invariant;
+
+    /// A macro for writing a history invariant of a struct or enum, which
+    /// relates `old(self)` and `self` in every method taking `&mut self`.
+    pub use prusti_contracts_impl::history_invariant;
+
+    /// A macro for encoding the comparisons with the derived `PartialEq`
+    /// implementation of a struct or enum as the equality of specifications.
+    pub use prusti_contracts_impl::derive_spec_eq;
+
+    /// A macro for allowing a function to temporarily break the invariants of
+    /// the values that it takes and returns.
+    pub use prusti_contracts_impl::no_invariant;
+
+    /// A macro for requiring a function to be proven free of panics, including
+    /// the ones of the library methods that it calls (e.g. `Option::unwrap`).
+    pub use prusti_contracts_impl::panic_free;
+
+    /// A macro for setting how many times the definition of a recursive pure
+    /// function can be unfolded from each of its calls.
+    pub use prusti_contracts_impl::fuel;
+
+    /// A macro for listing the places behind `&mut` parameters that a
+    /// function may modify; all the other places are unchanged.
+    pub use prusti_contracts_impl::assigns;
+
+    /// A macro for verifying a function only for the executions in which each
+    /// loop does at most the given number of iterations.
+    pub use prusti_contracts_impl::bounded;
+
     /// A macro for writing a loop body invariant.
     pub use prusti_contracts_impl::body_invariant;
 
+    /// A macro for writing an assertion in specification syntax.
+    pub use prusti_contracts_impl::prusti_assert;
+
+    /// A macro for writing an assumption in specification syntax.
+    pub use prusti_contracts_impl::prusti_assume;
+
+    /// A macro for naming a program point, whose state can be referred to
+    /// by `labelled_old(..)` expressions.
+    pub use prusti_contracts_impl::label;
+
+    /// A macro for writing ghost code, which is only executed by Prusti and
+    /// whose variables can be used in specifications.
+    pub use prusti_contracts_impl::ghost;
+
     /// A macro for defining a closure with a specification.
     pub use prusti_contracts_impl::closure;
 
@@ -57,9 +119,71 @@ mod private {
     /// A macro for marking a function as trusted.
     pub use prusti_contracts_internal::trusted;
 
+    /// A macro for suppressing specification warnings of a function.
+    pub use prusti_contracts_internal::allow_spec_warnings;
+
+    /// A macro for annotating a function with a termination measure.
+    pub use prusti_contracts_internal::decreases;
+
+    /// A macro for encoding the integers of a function with their bit width.
+    pub use prusti_contracts_internal::bitvector;
+
+    /// A macro for trusting the `unsafe` blocks of a function.
+    pub use prusti_contracts_internal::trusted_unsafe;
+
+    /// A macro for writing specifications of a function that only hold for
+    /// the instantiations of its generics that satisfy some trait bounds.
+    pub use prusti_contracts_internal::refine_spec;
+
+    /// A macro for writing an invariant of a struct or enum, which holds for
+    /// the values passed to and returned from functions.
+    pub use prusti_contracts_internal::invariant;
+
+    /// A macro for writing a history invariant of a struct or enum, which
+    /// relates `old(self)` and `self` in every method taking `&mut self`.
+    pub use prusti_contracts_internal::history_invariant;
+
+    /// A macro for encoding the comparisons with the derived `PartialEq`
+    /// implementation of a struct or enum as the equality of specifications.
+    pub use prusti_contracts_internal::derive_spec_eq;
+
+    /// A macro for allowing a function to temporarily break the invariants of
+    /// the values that it takes and returns.
+    pub use prusti_contracts_internal::no_invariant;
+
+    /// A macro for requiring a function to be proven free of panics, including
+    /// the ones of the library methods that it calls (e.g. `Option::unwrap`).
+    pub use prusti_contracts_internal::panic_free;
+
+    /// A macro for setting how many times the definition of a recursive pure
+    /// function can be unfolded from each of its calls.
+    pub use prusti_contracts_internal::fuel;
+
+    /// A macro for listing the places behind `&mut` parameters that a
+    /// function may modify; all the other places are unchanged.
+    pub use prusti_contracts_internal::assigns;
+
+    /// A macro for verifying a function only for the executions in which each
+    /// loop does at most the given number of iterations.
+    pub use prusti_contracts_internal::bounded;
+
     /// A macro for writing a loop body invariant.
     pub use prusti_contracts_internal::body_invariant;
 
+    /// A macro for writing an assertion in specification syntax.
+    pub use prusti_contracts_internal::prusti_assert;
+
+    /// A macro for writing an assumption in specification syntax.
+    pub use prusti_contracts_internal::prusti_assume;
+
+    /// A macro for naming a program point, whose state can be referred to
+    /// by `labelled_old(..)` expressions.
+    pub use prusti_contracts_internal::label;
+
+    /// A macro for writing ghost code, which is only executed by Prusti and
+    /// whose variables can be used in specifications.
+    pub use prusti_contracts_internal::ghost;
+
     /// A macro for defining a closure with a specification.
     pub use prusti_contracts_internal::closure;
 
@@ -87,4 +211,42 @@ pub fn old<T>(arg: T) -> T {
     arg
 }
 
+/// This function is used to evaluate an expression in the context just
+/// before the enclosing loop is entered. It can only be used in loop
+/// invariants.
+pub fn old_loop<T>(arg: T) -> T {
+    arg
+}
+
+/// This function is used to evaluate an expression in the state at the
+/// program point named by a `label!(..)` statement, which has to precede
+/// every use of the expression.
+pub fn labelled_old<T>(_label: &str, arg: T) -> T {
+    arg
+}
+
+/// This function is used to state that the value of an expression is the
+/// same as just before the enclosing loop is entered, i.e. that it is not
+/// changed by any iteration. It can only be used in loop invariants.
+pub fn unchanged<T>(_arg: T) -> bool {
+    true
+}
+
+/// This function returns the number of iterations of the enclosing loop
+/// that have been executed so far. It can only be used in loop invariants.
+pub fn loop_iterations() -> usize {
+    0
+}
+
+/// This trait is used to name the return type of a function in the generated
+/// specifications, which is needed for the never type `!`.
+#[doc(hidden)]
+pub trait FnOutput {
+    type Output;
+}
+
+impl<T> FnOutput for fn() -> T {
+    type Output = T;
+}
+
 pub use private::*;
@@ -25,6 +25,18 @@ fn test4() {
     }
 }
 
+fn test5() {
+    prusti_assert!(true);
+    prusti_assume!(true);
+}
+
+fn test6() {
+    ghost! {
+        let g = 1;
+    }
+    prusti_assert!(g == 1);
+}
+
 #[predicate]
 fn pred_ok() -> bool {
     true
@@ -6,7 +6,7 @@ use log::{trace, debug};
 use rustc_hir::def_id::DefId;
 use rustc_span::Span;
 use rustc_middle::ty::TypeckResults;
-use crate::utils::has_spec_only_attr;
+use crate::utils::{has_spec_only_attr, is_test_harness_code};
 
 pub struct CollectClosureDefsVisitor<'env, 'tcx: 'env> {
     env: &'env Environment<'tcx>,
@@ -37,7 +37,8 @@ impl<'env, 'tcx> Visitor<'tcx> for CollectClosureDefsVisitor<'env, 'tcx> {
     fn visit_expr(&mut self, expr: &'tcx hir::Expr<'tcx>) {
         match expr.kind {
             hir::ExprKind::Closure(_, _, _, _, _) => {
-                if !has_spec_only_attr(self.map.attrs(expr.hir_id)) {
+                // Skip the closures generated by `#[test]`, which wrap the test functions
+                if !has_spec_only_attr(self.map.attrs(expr.hir_id)) && !is_test_harness_code(expr.span) {
                     let _tcx = self.env.tcx();
                     let def_id = self.map.local_def_id(expr.hir_id).to_def_id();
                     let item_def_path = self.env.get_item_def_path(def_id);
@@ -14,7 +14,7 @@ use std::collections::HashSet;
 use std::iter::FromIterator;
 use log::{trace, debug};
 use rustc_ast::ast;
-use crate::utils::{has_spec_only_attr, has_extern_spec_attr};
+use crate::utils::{has_spec_only_attr, has_extern_spec_attr, is_test_harness_code};
 
 pub struct CollectPrustiSpecVisitor<'a, 'tcx: 'a> {
     env: &'a Environment<'tcx>,
@@ -41,6 +41,10 @@ impl<'a, 'tcx> ItemLikeVisitor<'tcx> for CollectPrustiSpecVisitor<'a, 'tcx> {
         if has_spec_only_attr(&attrs) || has_extern_spec_attr(&attrs) {
             return;
         }
+        // Skip the `main` function generated for `--test` builds
+        if is_test_harness_code(item.span) {
+            return;
+        }
         if let hir::ItemKind::Fn(..) = item.kind {
             let def_id = self.tcx.hir().local_def_id(item.hir_id()).to_def_id();
             let item_def_path = self.env.get_item_def_path(def_id);
@@ -495,7 +495,7 @@ impl<'a, 'tcx> MirInfoPrinter<'a, 'tcx> {
 
             let definitely_initalised_paths = self.initialization.get_before_block(bb);
             // FIXME: this computes the wrong loop invariant permission
-            let (write_leaves, mut_borrow_leaves, read_leaves) = self
+            let (write_leaves, mut_borrow_leaves, read_leaves, shared_borrow_leaves) = self
                 .loops
                 .compute_read_and_write_leaves(bb, self.mir, Some(&definitely_initalised_paths));
             // Construct the permission forest.
@@ -505,6 +505,7 @@ impl<'a, 'tcx> MirInfoPrinter<'a, 'tcx> {
                 &write_leaves,
                 &mut_borrow_leaves,
                 &read_leaves,
+                &shared_borrow_leaves,
                 &definitely_initalised_paths,
             );
 
@@ -526,9 +527,25 @@ impl<'a, 'tcx> MirInfoPrinter<'a, 'tcx> {
             );
             write_graph!(self, "</tr>");
 
+            write_graph!(self, "<tr>");
+            write_graph!(self, "<td colspan=\"2\">Shared borrowed paths (A3):</td>");
+            write_graph!(
+                self,
+                "<td colspan=\"8\">{}</td>",
+                to_sorted_string!(shared_borrow_leaves)
+            );
+            write_graph!(self, "</tr>");
+
             write_graph!(self, "<tr>");
             write_graph!(self, "<td colspan=\"2\">Invariant:</td>");
-            write_graph!(self, "<td colspan=\"8\">{}</td>", to_html_display!(forest));
+            match forest {
+                Ok(forest) => {
+                    write_graph!(self, "<td colspan=\"8\">{}</td>", to_html_display!(forest));
+                }
+                Err(error) => {
+                    write_graph!(self, "<td colspan=\"8\">{}</td>", to_html!(error));
+                }
+            }
             write_graph!(self, "</tr>");
 
             if let Some(ref magic_wands) = self.polonius_info.loop_magic_wands.get(&bb) {
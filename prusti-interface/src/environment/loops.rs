@@ -475,6 +475,7 @@ impl ProcedureLoops {
         Vec<mir::Place<'tcx>>,
         Vec<mir::Place<'tcx>>,
         Vec<mir::Place<'tcx>>,
+        Vec<mir::Place<'tcx>>,
     ) {
         // 1.  Let ``A1`` be a set of pairs ``(p, t)`` where ``p`` is a prefix
         //     accessed in the loop body and ``t`` is the type of access (read,
@@ -561,6 +562,25 @@ impl ProcedureLoops {
         }
         debug!("mut_borrow_leaves = {:?}", write_leaves);
 
+        // Paths that are only shared-borrowed, and thus only read, in the loop.
+        let mut shared_borrow_leaves: Vec<mir::Place> = Vec::new();
+        for (place, kind) in accesses_pairs.iter() {
+            if *kind == PlaceAccessKind::SharedBorrow {
+                let has_prefix = accesses_pairs.iter().any(|(potential_prefix, _kind)| {
+                    place != potential_prefix && utils::is_prefix(place, potential_prefix)
+                });
+                let is_written = accesses_pairs.iter().any(|(other_place, kind)| {
+                    (kind.is_write_access() || *kind == PlaceAccessKind::MutableBorrow)
+                        && (utils::is_prefix(place, other_place)
+                            || utils::is_prefix(other_place, place))
+                });
+                if !has_prefix && !is_written && !shared_borrow_leaves.contains(place) {
+                    shared_borrow_leaves.push((*place).clone());
+                }
+            }
+        }
+        debug!("shared_borrow_leaves = {:?}", shared_borrow_leaves);
+
         // Paths to whose leaves we need read permissions.
         let mut read_leaves: Vec<mir::Place> = Vec::new();
         for (place, kind) in accesses_pairs.iter() {
@@ -572,6 +592,7 @@ impl ProcedureLoops {
                     && !read_leaves.contains(place)
                     && !write_leaves.contains(place)
                     && !mut_borrow_leaves.contains(place)
+                    && !shared_borrow_leaves.contains(place)
                 {
                     read_leaves.push((*place).clone());
                 }
@@ -579,6 +600,6 @@ impl ProcedureLoops {
         }
         debug!("read_leaves = {:?}", read_leaves);
 
-        (write_leaves, mut_borrow_leaves, read_leaves)
+        (write_leaves, mut_borrow_leaves, read_leaves, shared_borrow_leaves)
     }
 }
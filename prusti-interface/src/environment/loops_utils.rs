@@ -29,6 +29,9 @@ pub enum PermissionKind {
     /// Give no permission to this node and the entire subtree. This
     /// must be a leaf node.
     None,
+    /// Marks the root of a borrowed tree in the list of permissions. It
+    /// gives no permission by itself.
+    Borrowed,
 }
 
 impl PermissionKind {
@@ -40,7 +43,8 @@ impl PermissionKind {
             | PermissionKind::ReadSubtree
             | PermissionKind::WriteNode
             | PermissionKind::WriteSubtree
-            | PermissionKind::WriteNodeAndSubtree => false,
+            | PermissionKind::WriteNodeAndSubtree
+            | PermissionKind::Borrowed => false,
         }
     }
 }
@@ -68,7 +72,8 @@ pub enum PermissionNode<'tcx> {
         kind: PermissionKind,
         children: Vec<PermissionNode<'tcx>>,
     },
-    // TODO: Make this the type only of the root node.
+    /// The root of a tree of places that are shared-borrowed, which can
+    /// only be read. The `child` is the dereferenced `place`.
     BorrowedNode {
         place: mir::Place<'tcx>,
         kind: PermissionKind,
@@ -89,12 +94,10 @@ impl<'tcx> PermissionNode<'tcx> {
 
     pub fn set_permission_kind(&mut self, permission_kind: PermissionKind) {
         match self {
-            PermissionNode::OwnedNode { ref mut kind, .. } => {
+            PermissionNode::OwnedNode { ref mut kind, .. }
+            | PermissionNode::BorrowedNode { ref mut kind, .. } => {
                 *kind = permission_kind;
             }
-            PermissionNode::BorrowedNode { .. } => {
-                unimplemented!();
-            }
         }
     }
 
@@ -106,16 +109,19 @@ impl<'tcx> PermissionNode<'tcx> {
         }
     }
 
+    /// Get the child with the given `place`, creating it with the given
+    /// `kind` if needed. A `BorrowedNode` has a single child, so `place`
+    /// must be (an extension of) the place of that child.
     pub fn get_or_create_child(
         &mut self,
         place: &mir::Place<'tcx>,
         kind: PermissionKind,
-    ) -> &mut Self {
+    ) -> Result<&mut Self, PermissionForestError<'tcx>> {
         match self {
             PermissionNode::OwnedNode { children, .. } => {
                 let index = children.iter().position(|child| child.get_place() == place);
                 if let Some(index) = index {
-                    return &mut children[index];
+                    return Ok(&mut children[index]);
                 }
                 let child = PermissionNode::OwnedNode {
                     place: place.clone(),
@@ -124,11 +130,19 @@ impl<'tcx> PermissionNode<'tcx> {
                 };
                 children.push(child);
                 let len = children.len();
-                &mut children[len - 1]
+                Ok(&mut children[len - 1])
             }
-            PermissionNode::BorrowedNode { .. } => {
-                unimplemented!(); // TODO: Change code so that we do not
-                                  // have to deal with this case.
+            PermissionNode::BorrowedNode { place: ref borrowed_place, box ref mut child, .. } => {
+                if child.get_place() == place {
+                    Ok(child)
+                } else if utils::is_prefix(place, child.get_place()) {
+                    child.get_or_create_child(place, kind)
+                } else {
+                    Err(PermissionForestError::UnexpectedBorrowedChild {
+                        borrowed_place: borrowed_place.clone(),
+                        place: place.clone(),
+                    })
+                }
             }
         }
     }
@@ -141,9 +155,10 @@ impl<'tcx> PermissionNode<'tcx> {
                     return Some(&children[index]);
                 }
             }
-            PermissionNode::BorrowedNode { .. } => {
-                unimplemented!(); // TODO: Change code so that we do not
-                                  // have to deal with this case.
+            PermissionNode::BorrowedNode { box ref child, .. } => {
+                if child.get_place() == place {
+                    return Some(child);
+                }
             }
         }
         None
@@ -156,6 +171,47 @@ impl<'tcx> PermissionNode<'tcx> {
             PermissionNode::BorrowedNode { box ref child, .. } => vec![child],
         }
     }
+
+    /// Get the permissions given by the tree rooted at this node. The root
+    /// of every borrowed tree is marked with `PermissionKind::Borrowed`.
+    pub fn get_permissions(&self) -> Vec<(PermissionKind, mir::Place<'tcx>)> {
+        let mut visited = vec![];
+        let mut to_visit = vec![self];
+        while let Some(node) = to_visit.pop() {
+            if let PermissionNode::BorrowedNode { place, .. } = node {
+                visited.push((PermissionKind::Borrowed, place.clone()));
+            }
+            let kind = node.get_permission_kind();
+            for child in node.get_children().iter() {
+                to_visit.push(child);
+                if child.get_permission_kind() == PermissionKind::WriteNodeAndSubtree {
+                    visited.push((PermissionKind::WriteNode, child.get_place().clone()));
+                    continue;
+                }
+                match kind {
+                    PermissionKind::ReadNode | PermissionKind::WriteNode => {
+                        visited.push((kind, child.get_place().clone()));
+                    }
+                    _ => {
+                        unreachable!();
+                    }
+                }
+            }
+            match kind {
+                PermissionKind::ReadSubtree => {
+                    visited.push((kind, node.get_place().clone()));
+                }
+                PermissionKind::WriteNodeAndSubtree | PermissionKind::WriteSubtree => {
+                    visited.push((PermissionKind::WriteSubtree, node.get_place().clone()));
+                }
+                PermissionKind::ReadNode
+                | PermissionKind::WriteNode
+                | PermissionKind::None
+                | PermissionKind::Borrowed => {}
+            }
+        }
+        visited
+    }
 }
 
 impl<'tcx> fmt::Display for PermissionNode<'tcx> {
@@ -171,8 +227,18 @@ impl<'tcx> fmt::Display for PermissionNode<'tcx> {
                     write!(f, " && {}", child)?;
                 }
             }
-            PermissionNode::BorrowedNode { .. } => {
-                unimplemented!();
+            PermissionNode::BorrowedNode {
+                place,
+                kind,
+                child,
+                may_borrow_from,
+            } => {
+                write!(f, "acc({:?}, {:?})", place, kind)?;
+                if !may_borrow_from.is_empty() {
+                    let loans: Vec<_> = may_borrow_from.iter().map(|loan| loan.id).collect();
+                    write!(f, " borrowing {:?}", loans)?;
+                }
+                write!(f, " && {}", child)?;
             }
         }
         Ok(())
@@ -259,6 +325,30 @@ impl<'a, 'tcx: 'a> PermissionTree<'a, 'tcx> {
         Self { mir, tcx, root: node }
     }
 
+    /// Create a permission tree for a place that is shared-borrowed. The
+    /// `place` is of kind `ReadSubtree` and all steps from the root are of
+    /// kind `ReadNode`. If the root has a child, the root is a
+    /// `BorrowedNode`.
+    pub fn new_shared_borrowed(
+        mir: &'a mir::Body<'tcx>,
+        tcx: TyCtxt<'tcx>,
+        place: &mir::Place<'tcx>,
+    ) -> Self {
+        let tree = Self::new(mir, tcx, place, place, TargetType::Read);
+        let root = match tree.root {
+            PermissionNode::OwnedNode { place, kind, mut children } if !children.is_empty() => {
+                PermissionNode::BorrowedNode {
+                    place,
+                    kind,
+                    child: box children.pop().unwrap(),
+                    may_borrow_from: Vec::new(),
+                }
+            }
+            root => root,
+        };
+        Self { mir, tcx, root }
+    }
+
     /// Add a new place by following the same rules as described in the
     /// comment for the `new`.
     pub fn add(
@@ -266,7 +356,7 @@ impl<'a, 'tcx: 'a> PermissionTree<'a, 'tcx> {
         place: &mir::Place<'tcx>,
         _target_place: &mir::Place<'tcx>,
         target_type: TargetType,
-    ) {
+    ) -> Result<(), PermissionForestError<'tcx>> {
         let place = utils::VecPlace::new(self.mir, self.tcx, place);
         let mut place_iter = place.iter();
         place_iter.next(); // Drop the root.
@@ -283,14 +373,14 @@ impl<'a, 'tcx: 'a> PermissionTree<'a, 'tcx> {
                 !target_type.is_write(),
                 "Adding a write root node to an existing tree."
             );
-            return;
+            return Ok(());
         }
         let mut current_parent_node = &mut self.root;
         while component_count > 1 {
             let component = place_iter.next().unwrap();
             component_count -= 1;
             let current_node = current_parent_node
-                .get_or_create_child(component.get_mir_place(), PermissionKind::ReadNode);
+                .get_or_create_child(component.get_mir_place(), PermissionKind::ReadNode)?;
             if target_type.is_write() {
                 current_node.set_permission_kind(PermissionKind::WriteNode);
             }
@@ -298,8 +388,8 @@ impl<'a, 'tcx: 'a> PermissionTree<'a, 'tcx> {
         }
         let component = place_iter.next().unwrap();
         let kind = target_type.to_permission_kind();
-        let mut _current_node =
-            current_parent_node.get_or_create_child(component.get_mir_place(), kind);
+        current_parent_node.get_or_create_child(component.get_mir_place(), kind)?;
+        Ok(())
     }
 
     pub fn get_root_place(&self) -> &mir::Place {
@@ -312,39 +402,46 @@ impl<'a, 'tcx: 'a> PermissionTree<'a, 'tcx> {
 
     pub fn get_permissions(&self) -> Vec<(PermissionKind, mir::Place<'tcx>)> {
         trace!("[enter] get_permissions self={:?}", self);
-        let mut visited = vec![];
-        let mut to_visit = vec![&self.root];
-        while let Some(node) = to_visit.pop() {
-            let kind = node.get_permission_kind();
-            for child in node.get_children().iter() {
-                to_visit.push(child);
-                if child.get_permission_kind() == PermissionKind::WriteNodeAndSubtree {
-                    visited.push((PermissionKind::WriteNode, child.get_place().clone()));
-                    continue;
-                }
-                match kind {
-                    PermissionKind::ReadNode | PermissionKind::WriteNode => {
-                        visited.push((kind, child.get_place().clone()));
-                    }
-                    _ => {
-                        unreachable!();
-                    }
-                }
-            }
-            match kind {
-                PermissionKind::ReadSubtree => {
-                    visited.push((kind, node.get_place().clone()));
-                }
-                PermissionKind::WriteNodeAndSubtree | PermissionKind::WriteSubtree => {
-                    visited.push((PermissionKind::WriteSubtree, node.get_place().clone()));
-                }
-                PermissionKind::ReadNode | PermissionKind::WriteNode | PermissionKind::None => {}
-            }
-        }
+        let visited = self.root.get_permissions();
         trace!("[exit] get_permissions visited={:?}", visited);
         visited
     }
 
+    /// Does the tree give at least read permission to `place`?
+    fn gives_read_permission(&self, place: &mir::Place<'tcx>) -> bool {
+        self.get_permissions().iter().any(|(kind, tree_place)| match kind {
+            PermissionKind::ReadSubtree | PermissionKind::WriteSubtree => {
+                utils::is_prefix(place, tree_place)
+            }
+            PermissionKind::ReadNode | PermissionKind::WriteNode => place == tree_place,
+            PermissionKind::WriteNodeAndSubtree
+            | PermissionKind::None
+            | PermissionKind::Borrowed => false,
+        })
+    }
+
+    /// Get the permissions of this tree that are not already given by
+    /// `other`. Only read permissions are subtracted: the places that are
+    /// written must still be exhaled so that their values get havocked.
+    pub fn difference(
+        &self,
+        other: &PermissionTree<'a, 'tcx>,
+    ) -> Vec<(PermissionKind, mir::Place<'tcx>)> {
+        trace!("[enter] difference self={:?} other={:?}", self, other);
+        let difference: Vec<_> = self
+            .get_permissions()
+            .into_iter()
+            .filter(|(kind, place)| match kind {
+                PermissionKind::ReadNode | PermissionKind::ReadSubtree => {
+                    !other.gives_read_permission(place)
+                }
+                _ => true,
+            })
+            .collect();
+        trace!("[exit] difference {:?}", difference);
+        difference
+    }
+
     pub fn get_children(&self, parent_place: &mir::Place<'tcx>) -> Vec<&mir::Place<'tcx>> {
         trace!("[enter] get_children self={:?} parent_place={:?}", self, parent_place);
         let mut current_parent_node = &self.root;
@@ -380,6 +477,22 @@ impl<'a, 'tcx> fmt::Display for PermissionTree<'a, 'tcx> {
     }
 }
 
+#[derive(Clone, Debug)]
+pub enum PermissionForestError<'tcx> {
+    /// The loop writes to (or mutably borrows) `place`, which overlaps with
+    /// `borrowed_place` that is shared-borrowed.
+    WriteToSharedBorrowedPlace {
+        place: mir::Place<'tcx>,
+        borrowed_place: mir::Place<'tcx>,
+    },
+    /// `place` was added below the borrowed node of `borrowed_place`, but it
+    /// is not inside the subtree of the single borrowed child.
+    UnexpectedBorrowedChild {
+        borrowed_place: mir::Place<'tcx>,
+        place: mir::Place<'tcx>,
+    },
+}
+
 #[derive(Debug)]
 pub struct PermissionForest<'a, 'tcx> {
     trees: Vec<PermissionTree<'a, 'tcx>>,
@@ -390,26 +503,46 @@ impl<'a, 'tcx> PermissionForest<'a, 'tcx> {
     /// +   `mut_borrowed_paths` – paths that are roots of trees to
     ///     which we hsould have write permission.
     /// +   `read_paths` – paths to whose leaves we should have read permission.
+    /// +   `shared_borrowed_paths` – paths that are shared-borrowed, to whose
+    ///     leaves we should have read permission. Read paths inside them are
+    ///     subsumed, while writing to them is an error.
     pub fn new(
         mir: &'a mir::Body<'tcx>,
         tcx: TyCtxt<'tcx>,
         write_paths: &Vec<mir::Place<'tcx>>,
         mut_borrowed_paths: &Vec<mir::Place<'tcx>>,
         read_paths: &Vec<mir::Place<'tcx>>,
+        shared_borrowed_paths: &Vec<mir::Place<'tcx>>,
         all_places: &PlaceSet<'tcx>,
-    ) -> Self {
+    ) -> Result<Self, PermissionForestError<'tcx>> {
         trace!(
             "[enter] PermissionForest::new(\
              write_paths={:?}, \
              mut_borrowed_paths={:?}, \
              read_paths={:?}, \
+             shared_borrowed_paths={:?}, \
              all_places={:?})",
             write_paths,
             mut_borrowed_paths,
             read_paths,
+            shared_borrowed_paths,
             all_places
         );
 
+        for borrowed_place in shared_borrowed_paths.iter() {
+            let written_place = write_paths.iter()
+                .chain(mut_borrowed_paths.iter())
+                .find(|place| {
+                    utils::is_prefix(place, borrowed_place) || utils::is_prefix(borrowed_place, place)
+                });
+            if let Some(place) = written_place {
+                return Err(PermissionForestError::WriteToSharedBorrowedPlace {
+                    place: place.clone(),
+                    borrowed_place: borrowed_place.clone(),
+                });
+            }
+        }
+
         let mut trees: Vec<PermissionTree> = Vec::new();
 
         /// Take the intended place to add and compute the set of places
@@ -449,7 +582,7 @@ impl<'a, 'tcx> PermissionForest<'a, 'tcx> {
             trees: &mut Vec<PermissionTree<'a, 'tcx>>,
             target_type: TargetType,
             all_places: &PlaceSet<'tcx>,
-        ) {
+        ) -> Result<(), PermissionForestError<'tcx>> {
             for place in paths.iter() {
                 let mut found = false;
                 let places_to_add = compute_places_to_add(place, all_places);
@@ -457,7 +590,7 @@ impl<'a, 'tcx> PermissionForest<'a, 'tcx> {
                     if utils::is_prefix(place, tree.get_root_place()) {
                         found = true;
                         for (actual_place, target_place) in places_to_add.iter() {
-                            tree.add(actual_place, target_place, target_type);
+                            tree.add(actual_place, target_place, target_type)?;
                         }
                     }
                 }
@@ -468,8 +601,9 @@ impl<'a, 'tcx> PermissionForest<'a, 'tcx> {
                     }
                 }
             }
+            Ok(())
         }
-        add_paths(mir, tcx, write_paths, &mut trees, TargetType::WriteNode, all_places);
+        add_paths(mir, tcx, write_paths, &mut trees, TargetType::WriteNode, all_places)?;
         add_paths(
             mir,
             tcx,
@@ -477,15 +611,49 @@ impl<'a, 'tcx> PermissionForest<'a, 'tcx> {
             &mut trees,
             TargetType::WriteContents,
             all_places,
-        );
-        add_paths(mir, tcx, read_paths, &mut trees, TargetType::Read, all_places);
-        Self { trees: trees }
+        )?;
+
+        // The shared-borrowed places are only readable, so the read paths
+        // inside them do not need a tree of their own.
+        let read_paths: Vec<_> = read_paths.iter()
+            .filter(|place| {
+                !shared_borrowed_paths.iter()
+                    .any(|borrowed_place| utils::is_prefix(place, borrowed_place))
+            })
+            .cloned()
+            .collect();
+        add_paths(mir, tcx, &read_paths, &mut trees, TargetType::Read, all_places)?;
+
+        for place in shared_borrowed_paths.iter() {
+            let is_subsumed = read_paths.iter()
+                .chain(shared_borrowed_paths.iter())
+                .any(|other_place| other_place != place && utils::is_prefix(place, other_place));
+            if is_subsumed {
+                continue;
+            }
+            let mut found = false;
+            for tree in trees.iter_mut() {
+                if utils::is_prefix(place, tree.get_root_place()) {
+                    found = true;
+                    tree.add(place, place, TargetType::Read)?;
+                }
+            }
+            if !found {
+                trees.push(PermissionTree::new_shared_borrowed(mir, tcx, place));
+            }
+        }
+        Ok(Self { trees: trees })
     }
 
     pub fn get_trees(&self) -> &[PermissionTree<'a, 'tcx>] {
         &self.trees
     }
 
+    /// Get the tree rooted at `root_place`, if any.
+    pub fn get_tree(&self, root_place: &mir::Place) -> Option<&PermissionTree<'a, 'tcx>> {
+        self.trees.iter().find(|tree| tree.get_root_place() == root_place)
+    }
+
     pub fn get_children(&self, parent_place: &mir::Place<'tcx>) -> Vec<&mir::Place<'tcx>> {
         for tree in &self.trees {
             if utils::is_prefix(parent_place, tree.get_root_place()) {
@@ -510,3 +678,75 @@ impl<'a, 'tcx> fmt::Display for PermissionForest<'a, 'tcx> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_index::vec::Idx;
+
+    fn local_place<'tcx>(index: usize) -> mir::Place<'tcx> {
+        mir::Place::from(mir::Local::new(index))
+    }
+
+    /// A borrowed `_1` whose dereferenced place is modelled by `_2`.
+    fn borrowed_root<'tcx>(may_borrow_from: Vec<Loan<'tcx>>) -> PermissionNode<'tcx> {
+        PermissionNode::BorrowedNode {
+            place: local_place(1),
+            kind: PermissionKind::ReadNode,
+            child: box PermissionNode::OwnedNode {
+                place: local_place(2),
+                kind: PermissionKind::ReadSubtree,
+                children: Vec::new(),
+            },
+            may_borrow_from,
+        }
+    }
+
+    #[test]
+    fn test_borrowed_node_permissions() {
+        let root = borrowed_root(Vec::new());
+        assert_eq!(
+            root.get_permissions(),
+            vec![
+                (PermissionKind::Borrowed, local_place(1)),
+                (PermissionKind::ReadNode, local_place(2)),
+                (PermissionKind::ReadSubtree, local_place(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_borrowed_node_display() {
+        let root = borrowed_root(Vec::new());
+        assert_eq!(format!("{}", root), "acc(_1, ReadNode) && acc(_2, ReadSubtree)");
+
+        let loan = Loan {
+            id: facts::Loan::from(1),
+            location: mir::Location::START,
+            place: local_place(1),
+        };
+        let root = borrowed_root(vec![loan]);
+        assert_eq!(
+            format!("{}", root),
+            "acc(_1, ReadNode) borrowing [L1] && acc(_2, ReadSubtree)"
+        );
+    }
+
+    #[test]
+    fn test_borrowed_node_get_or_create_child() {
+        let mut root = borrowed_root(Vec::new());
+        let child = root
+            .get_or_create_child(&local_place(2), PermissionKind::ReadNode)
+            .unwrap();
+        assert_eq!(child.get_place(), &local_place(2));
+        assert_eq!(child.get_permission_kind(), PermissionKind::ReadSubtree);
+
+        match root.get_or_create_child(&local_place(3), PermissionKind::ReadNode) {
+            Err(PermissionForestError::UnexpectedBorrowedChild { borrowed_place, place }) => {
+                assert_eq!(borrowed_place, local_place(1));
+                assert_eq!(place, local_place(3));
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
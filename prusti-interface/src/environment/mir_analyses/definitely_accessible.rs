@@ -0,0 +1,63 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! This module provides the definitely accessible analysis for MIR.
+//!
+//! A place is definitely accessible for reading if it is definitely
+//! initialized and not mutably borrowed by a loan that is alive at that
+//! program point. It is accessible for writing if it is not borrowed at
+//! all. The alive loans are taken from the Polonius facts.
+
+use prusti_common::Stopwatch;
+use crate::environment::borrowck::facts;
+use crate::environment::polonius_info::PoloniusInfo;
+use rustc_middle::ty::TyCtxt;
+use rustc_middle::mir;
+use analysis::Analyzer;
+use analysis::abstract_domains::{
+    BorrowedPlaces, DefinitelyInitializedState, DefinitelyAccessibleAnalysisResult,
+};
+
+impl<'a, 'tcx: 'a> BorrowedPlaces<'tcx> for PoloniusInfo<'a, 'tcx> {
+    fn get_borrowed_places(&self, location: mir::Location)
+        -> Vec<(mir::Place<'tcx>, mir::Mutability)>
+    {
+        let start_point = self.get_point(location, facts::PointType::Start);
+        let loans = self.borrowck_out_facts.borrow_live_at.get(&start_point);
+        let mut borrowed_places = vec![];
+        for loan in loans.into_iter().flatten() {
+            // Fake loans created for moves of references do not borrow a place.
+            if let Ok(Some(loan_places)) = self.get_loan_places(loan) {
+                if let mir::Rvalue::Ref(_, kind, place) = loan_places.source {
+                    borrowed_places.push((place, kind.to_mutbl_lossy()));
+                }
+            }
+        }
+        borrowed_places
+    }
+}
+
+/// Computes the places that are definitely accessible at every program point of `body`.
+/// Use `lookup_before` on the result to get the read and write place sets at a MIR location.
+pub fn compute_definitely_accessible<'a, 'tcx: 'a>(
+    body: &'a mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    polonius_info: &PoloniusInfo<'a, 'tcx>,
+) -> DefinitelyAccessibleAnalysisResult<'a, 'tcx> {
+    let stopwatch = Stopwatch::start("prusti-client", "definitely accessible analysis");
+    let analyzer = Analyzer::new(tcx);
+    let def_init = analyzer.run_fwd_analysis::<DefinitelyInitializedState>(&body)
+        .map_err(|e| panic!("Error while analyzing function at {:?}: {}", body.span, e.to_pretty_str(body)))
+        .unwrap();
+    let result = analysis::abstract_domains::compute_definitely_accessible(
+        body,
+        tcx,
+        &def_init,
+        polonius_info,
+    );
+    stopwatch.finish();
+    result
+}
@@ -7,5 +7,7 @@
 //! This module defines various MIR analyses.
 
 pub mod common;
+pub mod definitely_accessible;
 pub mod initialization;
 pub mod liveness;
+pub mod reaching_moves;
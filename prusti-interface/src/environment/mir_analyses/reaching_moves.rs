@@ -0,0 +1,96 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! This module provides the reaching moves of MIR places, which the encoder
+//! uses to point at the moves that may explain an error about a place.
+
+use prusti_common::Stopwatch;
+use rustc_middle::ty::TyCtxt;
+use rustc_middle::mir;
+use std::collections::{BTreeSet, HashMap};
+use analysis::Analyzer;
+use analysis::abstract_domains::PlaceReachingDefsState;
+
+/// The locations at which (parts of) the places of a procedure may have been
+/// moved out, per basic block.
+pub struct ReachingMovesAnalysisResult<'tcx> {
+    /// The moves that may reach the beginning of each basic block.
+    before_block: HashMap<mir::BasicBlock, HashMap<mir::Place<'tcx>, BTreeSet<mir::Location>>>,
+    /// The moves that may reach the terminator of each basic block.
+    before_terminator: HashMap<mir::BasicBlock, HashMap<mir::Place<'tcx>, BTreeSet<mir::Location>>>,
+}
+
+impl<'tcx> ReachingMovesAnalysisResult<'tcx> {
+    /// Returns the locations at which `place`, or a part of it, may have been
+    /// moved out without being assigned again when reaching the beginning of `bb`.
+    pub fn get_moves_before_block(
+        &self,
+        bb: mir::BasicBlock,
+        place: &mir::Place<'tcx>,
+    ) -> Vec<mir::Location> {
+        lookup_moves(&self.before_block, bb, place)
+    }
+
+    /// Returns the locations at which `place`, or a part of it, may have been
+    /// moved out without being assigned again when reaching the terminator of `bb`.
+    pub fn get_moves_before_terminator(
+        &self,
+        bb: mir::BasicBlock,
+        place: &mir::Place<'tcx>,
+    ) -> Vec<mir::Location> {
+        lookup_moves(&self.before_terminator, bb, place)
+    }
+}
+
+fn lookup_moves<'tcx>(
+    moves: &HashMap<mir::BasicBlock, HashMap<mir::Place<'tcx>, BTreeSet<mir::Location>>>,
+    bb: mir::BasicBlock,
+    place: &mir::Place<'tcx>,
+) -> Vec<mir::Location> {
+    moves.get(&bb)
+        .and_then(|place_moves| place_moves.get(place))
+        .map(|locations| locations.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Computes the moves of the given `places` that may reach the beginning and
+/// the terminator of each basic block of `body`.
+pub fn compute_reaching_moves<'a, 'tcx: 'a>(
+    body: &'a mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    places: &[mir::Place<'tcx>],
+) -> ReachingMovesAnalysisResult<'tcx> {
+    let stopwatch = Stopwatch::start("prusti-client", "reaching moves analysis");
+    let analyzer = Analyzer::new(tcx);
+    let pointwise_state = analyzer.run_fwd_analysis::<PlaceReachingDefsState>(&body)
+        .map_err(|e| panic!("Error while analyzing function at {:?}: {}", body.span, e.to_pretty_str(body)))
+        .unwrap();
+
+    let mut analysis_result = ReachingMovesAnalysisResult {
+        before_block: HashMap::new(),
+        before_terminator: HashMap::new(),
+    };
+    for (bb, bb_data) in body.basic_blocks().iter_enumerated() {
+        let terminator_location = mir::Location {
+            block: bb,
+            statement_index: bb_data.statements.len(),
+        };
+        for (location, result) in vec![
+            (bb.start_location(), &mut analysis_result.before_block),
+            (terminator_location, &mut analysis_result.before_terminator),
+        ] {
+            // Unreachable blocks have no state
+            if let Some(state) = pointwise_state.lookup_before(location) {
+                let place_moves = places.iter()
+                    .map(|place| (*place, state.get_reaching_moves(place)))
+                    .collect();
+                result.insert(bb, place_moves);
+            }
+        }
+    }
+    stopwatch.finish();
+    analysis_result
+}
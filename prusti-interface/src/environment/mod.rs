@@ -14,7 +14,8 @@ use rustc_hir::def_id::{DefId, LocalDefId};
 use rustc_middle::ty::{self, TyCtxt, ParamEnv, WithOptConstParam};
 use std::path::PathBuf;
 use std::cell::Ref;
-use rustc_span::{Span, MultiSpan, symbol::Symbol};
+use std::ops::Deref;
+use rustc_span::{FileName, Span, MultiSpan, symbol::{sym, Symbol}};
 use std::collections::HashSet;
 use log::debug;
 
@@ -24,11 +25,13 @@ mod collect_closure_defs_visitor;
 mod dump_borrowck_info;
 mod loops;
 mod loops_utils;
+mod mut_ref_writes;
 pub mod mir_analyses;
 pub mod mir_utils;
 pub mod place_set;
 pub mod polonius_info;
 mod procedure;
+mod unsafe_regions;
 
 use self::collect_prusti_spec_visitor::CollectPrustiSpecVisitor;
 use self::collect_closure_defs_visitor::CollectClosureDefsVisitor;
@@ -36,6 +39,8 @@ use rustc_hir::intravisit::Visitor;
 pub use self::loops::{PlaceAccess, PlaceAccessKind, ProcedureLoops};
 pub use self::loops_utils::*;
 pub use self::procedure::{BasicBlockIndex, Procedure};
+pub use self::unsafe_regions::UnsafeRegions;
+pub use self::mut_ref_writes::find_writes_through_mut_ref_args;
 // use config;
 use crate::data::ProcedureDefId;
 // use syntax::codemap::CodeMap;
@@ -43,6 +48,69 @@ use crate::data::ProcedureDefId;
 // use utils::get_attr_value;
 use rustc_span::source_map::SourceMap;
 
+/// The MIR body of a local or of an external procedure.
+pub enum MirBody<'a, 'tcx> {
+    Local(Ref<'a, mir::Body<'tcx>>),
+    External(&'a mir::Body<'tcx>),
+}
+
+impl<'a, 'tcx> Deref for MirBody<'a, 'tcx> {
+    type Target = mir::Body<'tcx>;
+
+    fn deref(&self) -> &mir::Body<'tcx> {
+        match self {
+            MirBody::Local(body) => body,
+            MirBody::External(body) => body,
+        }
+    }
+}
+
+/// A method of `Option` or `Result` that has a built-in pure encoding, so that it can be used
+/// in specifications without an external specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinPureMethod {
+    /// `Option::is_some` and `Result::is_ok`
+    IsValue,
+    /// `Option::is_none` and `Result::is_err`
+    IsNotValue,
+    /// `Option::unwrap` and `Result::unwrap`, which require the value to be present
+    Unwrap,
+    /// `Option::unwrap_or` and `Result::unwrap_or`
+    UnwrapOr,
+}
+
+/// A method of `Vec` that is encoded with a built-in model of the vector as a sequence of
+/// its elements, so that it can be used without a trusted wrapper.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinVecMethod {
+    /// `Vec::new`
+    New,
+    /// `Vec::len`
+    Len,
+    /// `Vec::push`
+    Push,
+    /// `Vec::pop`
+    Pop,
+    /// `Index::index` of `Vec` with a `usize` index
+    Index,
+    /// `IndexMut::index_mut` of `Vec` with a `usize` index
+    IndexMut,
+}
+
+/// A method of the primitive integer types whose semantics on overflow is encoded exactly.
+/// The operation is one of `mir::BinOp::Add`, `mir::BinOp::Sub` and `mir::BinOp::Mul`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinIntMethod {
+    /// `checked_*`, which returns `None` if the result does not fit into the type
+    Checked(mir::BinOp),
+    /// `wrapping_*`, which wraps the result around the bounds of the type
+    Wrapping(mir::BinOp),
+    /// `saturating_*`, which clamps the result to the bounds of the type
+    Saturating(mir::BinOp),
+    /// `overflowing_*`, which returns the wrapped result and whether it overflowed
+    Overflowing(mir::BinOp),
+}
+
 /// Facade to the Rust compiler.
 // #[derive(Copy, Clone)]
 pub struct Environment<'tcx> {
@@ -115,7 +183,8 @@ impl<'tcx> Environment<'tcx> {
         sp: S,
         msg: &str,
         help: &Option<String>,
-        note: &Option<(String, S)>
+        note: &Option<(String, S)>,
+        notes: &[String],
     ) {
         let mut diagnostic = self.tcx.sess.struct_err(msg);
         diagnostic.set_span(sp);
@@ -125,6 +194,9 @@ impl<'tcx> Environment<'tcx> {
         if let Some((note_msg, note_sp)) = note {
             diagnostic.span_note(note_sp.clone(), note_msg);
         }
+        for note_msg in notes {
+            diagnostic.note(note_msg);
+        }
         diagnostic.emit();
     }
 
@@ -134,7 +206,8 @@ impl<'tcx> Environment<'tcx> {
         sp: S,
         msg: &str,
         help: &Option<String>,
-        note: &Option<(String, S)>
+        note: &Option<(String, S)>,
+        notes: &[String],
     ) {
         let mut diagnostic = self.tcx.sess.struct_warn(msg);
         diagnostic.set_span(sp);
@@ -144,6 +217,9 @@ impl<'tcx> Environment<'tcx> {
         if let Some((note_msg, note_sp)) = note {
             diagnostic.span_note(note_sp.clone(), note_msg);
         }
+        for note_msg in notes {
+            diagnostic.note(note_msg);
+        }
         diagnostic.emit();
     }
 
@@ -195,6 +271,16 @@ impl<'tcx> Environment<'tcx> {
         self.tcx.hir().span_if_local(def_id).unwrap()
     }
 
+    /// Returns the path of the source file that contains the item, if the item
+    /// is defined in a local file.
+    pub fn get_item_source_path(&self, def_id: DefId) -> Option<PathBuf> {
+        let span = self.get_item_span(def_id);
+        match self.codemap().span_to_filename(span) {
+            FileName::Real(real_name) => real_name.local_path().map(|path| path.to_path_buf()),
+            _ => None,
+        }
+    }
+
     pub fn get_absolute_item_name(&self, def_id: DefId) -> String {
         self.tcx.def_path_str(def_id)
     }
@@ -221,6 +307,15 @@ impl<'tcx> Environment<'tcx> {
         self.tcx().optimized_mir(def_id)
     }
 
+    /// Get the MIR body of a local procedure, or of a procedure of a dependency,
+    /// whose MIR is encoded in the metadata of the dependency.
+    pub fn mir<'a>(&self, def_id: DefId) -> MirBody<'a, 'tcx> {
+        match def_id.as_local() {
+            Some(local_id) => MirBody::Local(self.local_mir(local_id)),
+            None => MirBody::External(self.external_mir(def_id)),
+        }
+    }
+
     /// Get all relevant trait declarations for some type.
     pub fn get_traits_decls_for_type(&self, ty: &ty::Ty<'tcx>) -> HashSet<DefId> {
         let mut res = HashSet::new();
@@ -242,6 +337,171 @@ impl<'tcx> Environment<'tcx> {
         self.tcx().associated_items(id).filter_by_name_unhygienic(name).next().cloned()
     }
 
+    /// Get the trait method that is implemented by the method `impl_method_def_id` of a trait
+    /// impl. Returns `None` if the method is not part of a trait impl.
+    pub fn find_trait_method(&self, impl_method_def_id: DefId) -> Option<DefId> {
+        let impl_def_id = self.tcx().impl_of_method(impl_method_def_id)?;
+        let trait_def_id = self.tcx().trait_id_of_impl(impl_def_id)?;
+        let name = self.tcx().item_name(impl_method_def_id);
+        self.get_assoc_item(trait_def_id, name).map(|assoc_item| assoc_item.def_id)
+    }
+
+    /// Get the `deref` method of the `Deref` trait, which is called by the implicit
+    /// dereferences of smart pointers.
+    pub fn get_deref_method(&self) -> Option<DefId> {
+        let deref_trait = self.tcx.lang_items().deref_trait()?;
+        self.get_assoc_item(deref_trait, sym::deref).map(|assoc_item| assoc_item.def_id)
+    }
+
+    /// Returns true if `def_id` is the `deref` method of the `Deref` trait, or the one of
+    /// an implementation of the trait.
+    pub fn is_deref_method(&self, def_id: DefId) -> bool {
+        match self.get_deref_method() {
+            Some(deref_method) => {
+                def_id == deref_method || self.find_trait_method(def_id) == Some(deref_method)
+            }
+            None => false,
+        }
+    }
+
+    /// Returns true if `callee` might directly or indirectly call `caller`, i.e. if a call from
+    /// `caller` to `callee` is (mutually) recursive. Only the bodies of procedures of the local
+    /// crate are inspected.
+    pub fn is_recursive_call(&self, caller: DefId, callee: DefId) -> bool {
+        let mut visited = HashSet::new();
+        let mut to_visit = vec![callee];
+        while let Some(def_id) = to_visit.pop() {
+            if def_id == caller {
+                return true;
+            }
+            if !visited.insert(def_id) {
+                continue;
+            }
+            if let Some(local_def_id) = def_id.as_local() {
+                if self.tcx().is_mir_available(def_id) {
+                    to_visit.extend(self.get_called_procedures(local_def_id));
+                }
+            }
+        }
+        false
+    }
+
+    /// Get the procedures that are called in the body of a local procedure.
+    fn get_called_procedures(&self, def_id: LocalDefId) -> Vec<DefId> {
+        let mir = self.local_mir(def_id);
+        mir.basic_blocks()
+            .iter()
+            .filter_map(|bb_data| match bb_data.terminator().kind {
+                mir::TerminatorKind::Call {
+                    func: mir::Operand::Constant(box mir::Constant { literal, .. }),
+                    ..
+                } => match literal.ty().kind() {
+                    ty::TyKind::FnDef(called_def_id, _) => Some(*called_def_id),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Checks whether `def_id` is the item at `path` (e.g. `["mem", "swap"]`)
+    /// of the standard library. The path in the defining crate is used, so
+    /// that items of `core` and `alloc` are also found through their
+    /// re-exports in `std`.
+    pub fn is_std_item(&self, def_id: DefId, path: &[&str]) -> bool {
+        let crate_name = self.tcx.crate_name(def_id.krate);
+        if !matches!(&*crate_name.as_str(), "core" | "alloc" | "std") {
+            return false;
+        }
+        let def_path = self.tcx.def_path(def_id);
+        def_path.data.len() == path.len()
+            && def_path.data.iter().zip(path).all(|(component, name)| {
+                component.data.get_opt_name().map_or(false, |symbol| symbol.as_str() == *name)
+            })
+    }
+
+    /// Replaces the opaque types (i.e. `impl Trait` return types) in `ty` by
+    /// the hidden concrete types that the defining functions return.
+    pub fn reveal_opaque_types(&self, ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
+        use rustc_middle::ty::fold::{TypeFolder, TypeFoldable};
+        use rustc_middle::ty::subst::Subst;
+        struct OpaqueTypeRevealer<'tcx> {
+            tcx: TyCtxt<'tcx>,
+        }
+        impl<'tcx> TypeFolder<'tcx> for OpaqueTypeRevealer<'tcx> {
+            fn tcx(&self) -> TyCtxt<'tcx> {
+                self.tcx
+            }
+            fn fold_ty(&mut self, ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
+                match ty.kind() {
+                    ty::TyKind::Opaque(def_id, substs) => {
+                        self.tcx.type_of(*def_id).subst(self.tcx, substs).fold_with(self)
+                    }
+                    _ => ty.super_fold_with(self),
+                }
+            }
+        }
+        if !ty.has_opaque_types() {
+            return ty;
+        }
+        ty.fold_with(&mut OpaqueTypeRevealer { tcx: self.tcx })
+    }
+
+    /// Normalizes the projections (i.e. uses of associated types) in `ty` whose
+    /// self types are known, as happens at the monomorphic call sites of
+    /// generic functions. Projections on type parameters are left unchanged.
+    pub fn normalize_projections(&self, ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
+        use rustc_middle::ty::fold::{TypeFolder, TypeFoldable};
+        struct ProjectionNormalizer<'tcx> {
+            tcx: TyCtxt<'tcx>,
+        }
+        impl<'tcx> TypeFolder<'tcx> for ProjectionNormalizer<'tcx> {
+            fn tcx(&self) -> TyCtxt<'tcx> {
+                self.tcx
+            }
+            fn fold_ty(&mut self, ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
+                if let ty::TyKind::Projection(_) = ty.kind() {
+                    let erased_ty = self.tcx.erase_regions(ty);
+                    if !erased_ty.needs_subst() {
+                        return self.tcx.normalize_erasing_regions(ParamEnv::reveal_all(), erased_ty);
+                    }
+                }
+                ty.super_fold_with(self)
+            }
+        }
+        if !ty.has_projections() {
+            return ty;
+        }
+        ty.fold_with(&mut ProjectionNormalizer { tcx: self.tcx })
+    }
+
+    /// Resolves a call of the trait method `called_def_id` with the given
+    /// substitutions to the method of the implementing type, if the type is
+    /// known. Returns `None` if the call cannot be resolved statically.
+    pub fn resolve_trait_method_call(
+        &self,
+        called_def_id: DefId,
+        call_substs: ty::subst::SubstsRef<'tcx>,
+    ) -> Option<DefId> {
+        self.tcx.trait_of_item(called_def_id)?;
+        let call_substs = self.tcx.mk_substs(call_substs.iter().map(|arg| {
+            match arg.unpack() {
+                ty::subst::GenericArgKind::Type(ty) => self.reveal_opaque_types(ty).into(),
+                _ => arg,
+            }
+        }));
+        let instance = ty::Instance::resolve(
+            self.tcx,
+            ParamEnv::reveal_all(),
+            called_def_id,
+            call_substs,
+        ).ok()??;
+        match instance.def {
+            ty::InstanceDef::Item(def) if def.did != called_def_id => Some(def.did),
+            _ => None,
+        }
+    }
+
     /// Get a trait method declaration by name for type.
     pub fn get_trait_method_decl_for_type(&self, typ: ty::Ty<'tcx>, trait_id: DefId, name: Symbol) -> Vec<ty::AssocItem> {
         let mut result = Vec::new();
@@ -254,6 +514,146 @@ impl<'tcx> Environment<'tcx> {
         result
     }
 
+    /// Returns the built-in pure encoding of a method of `Option` or `Result`, together with
+    /// the index of the variant that holds the value (i.e. `Some` or `Ok`).
+    pub fn get_builtin_pure_method(&self, def_id: DefId) -> Option<(BuiltinPureMethod, usize)> {
+        let impl_def_id = self.tcx.impl_of_method(def_id)?;
+        if self.tcx.trait_id_of_impl(impl_def_id).is_some() {
+            return None;
+        }
+        let adt_def = match self.tcx.type_of(impl_def_id).kind() {
+            ty::TyKind::Adt(adt_def, _) => adt_def,
+            _ => return None,
+        };
+        let is_option = self.tcx.is_diagnostic_item(sym::option_type, adt_def.did);
+        let is_result = self.tcx.is_diagnostic_item(sym::result_type, adt_def.did);
+        let method = match &*self.tcx.item_name(def_id).as_str() {
+            "is_some" if is_option => BuiltinPureMethod::IsValue,
+            "is_none" if is_option => BuiltinPureMethod::IsNotValue,
+            "is_ok" if is_result => BuiltinPureMethod::IsValue,
+            "is_err" if is_result => BuiltinPureMethod::IsNotValue,
+            "unwrap" if is_option || is_result => BuiltinPureMethod::Unwrap,
+            "unwrap_or" if is_option || is_result => BuiltinPureMethod::UnwrapOr,
+            _ => return None,
+        };
+        let value_variant_name = if is_option { sym::Some } else { sym::Ok };
+        let value_variant = adt_def.variants.iter()
+            .position(|variant| variant.ident.name == value_variant_name)?;
+        Some((method, value_variant))
+    }
+
+    /// Returns the built-in encoding of an inherent method of a primitive integer type.
+    pub fn get_builtin_int_method(&self, def_id: DefId) -> Option<BuiltinIntMethod> {
+        let impl_def_id = self.tcx.impl_of_method(def_id)?;
+        if self.tcx.trait_id_of_impl(impl_def_id).is_some()
+            || !self.tcx.type_of(impl_def_id).is_integral()
+        {
+            return None;
+        }
+        let item_name = self.tcx.item_name(def_id).as_str();
+        let (kind, op) = item_name.split_once('_')?;
+        let op = match op {
+            "add" => mir::BinOp::Add,
+            "sub" => mir::BinOp::Sub,
+            "mul" => mir::BinOp::Mul,
+            _ => return None,
+        };
+        Some(match kind {
+            "checked" => BuiltinIntMethod::Checked(op),
+            "wrapping" => BuiltinIntMethod::Wrapping(op),
+            "saturating" => BuiltinIntMethod::Saturating(op),
+            "overflowing" => BuiltinIntMethod::Overflowing(op),
+            _ => return None,
+        })
+    }
+
+    /// Returns the type of the elements of `ty`, if it is a `Vec`.
+    pub fn get_vec_elem_type(&self, ty: ty::Ty<'tcx>) -> Option<ty::Ty<'tcx>> {
+        match ty.kind() {
+            ty::TyKind::Adt(adt_def, substs)
+                if self.tcx.is_diagnostic_item(sym::vec_type, adt_def.did) => {
+                Some(substs.type_at(0))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the type of the bounds of `ty`, if it is a `std::ops::Range` of integers.
+    pub fn get_integer_range_elem_type(&self, ty: ty::Ty<'tcx>) -> Option<ty::Ty<'tcx>> {
+        match ty.kind() {
+            ty::TyKind::Adt(adt_def, substs)
+                if Some(adt_def.did) == self.tcx.lang_items().range_struct()
+                    && substs.type_at(0).is_integral() => {
+                Some(substs.type_at(0))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the built-in encoding of a method of `Vec`. The types of the arguments of the
+    /// call are needed to recognise the implementations of `Index` and `IndexMut` for `Vec`.
+    pub fn get_builtin_vec_method(
+        &self,
+        def_id: DefId,
+        arg_tys: &[ty::Ty<'tcx>],
+    ) -> Option<BuiltinVecMethod> {
+        let trait_id = self.tcx.trait_of_item(def_id).or_else(|| {
+            self.tcx.impl_of_method(def_id)
+                .and_then(|impl_def_id| self.tcx.trait_id_of_impl(impl_def_id))
+        });
+        if let Some(trait_id) = trait_id {
+            let lang_items = self.tcx.lang_items();
+            let method = if Some(trait_id) == lang_items.index_trait() {
+                BuiltinVecMethod::Index
+            } else if Some(trait_id) == lang_items.index_mut_trait() {
+                BuiltinVecMethod::IndexMut
+            } else {
+                return None;
+            };
+            // Ranges and other index types are not modelled
+            let is_vec = arg_tys.first()
+                .and_then(|ty| self.get_vec_elem_type(ty.peel_refs()))
+                .is_some();
+            let is_usize = matches!(
+                arg_tys.get(1).map(|ty| ty.kind()),
+                Some(ty::TyKind::Uint(ty::UintTy::Usize))
+            );
+            return if is_vec && is_usize { Some(method) } else { None };
+        }
+        let impl_def_id = self.tcx.impl_of_method(def_id)?;
+        self.get_vec_elem_type(self.tcx.type_of(impl_def_id))?;
+        match &*self.tcx.item_name(def_id).as_str() {
+            "new" => Some(BuiltinVecMethod::New),
+            "len" => Some(BuiltinVecMethod::Len),
+            "push" => Some(BuiltinVecMethod::Push),
+            "pop" => Some(BuiltinVecMethod::Pop),
+            _ => None,
+        }
+    }
+
+    /// Checks whether the trait bound `trait_ref` holds in the parameter environment of
+    /// `param_env_def_id`, which provides the bounds of the type parameters in `trait_ref`.
+    pub fn trait_bound_holds(&self, trait_ref: ty::TraitRef<'tcx>, param_env_def_id: DefId) -> bool {
+        self.tcx.type_implements_trait((
+            trait_ref.def_id,
+            trait_ref.self_ty(),
+            self.tcx.mk_substs(trait_ref.substs.iter().skip(1)),
+            self.tcx.param_env(param_env_def_id),
+        ))
+    }
+
+    /// Returns true if the `PartialEq` implementation of the struct or enum `adt_def_id` is
+    /// derived, which is the case iff the type implements `StructuralPartialEq`.
+    pub fn has_derived_partial_eq(&self, adt_def_id: DefId) -> bool {
+        let structural_peq_trait = match self.tcx.lang_items().structural_peq_trait() {
+            Some(trait_def_id) => trait_def_id,
+            None => return false,
+        };
+        self.tcx.all_impls(structural_peq_trait).any(|impl_def_id| {
+            self.tcx.type_of(impl_def_id).ty_adt_def().map(|adt_def| adt_def.did) == Some(adt_def_id)
+        })
+    }
+
     pub fn type_is_copy(&self, ty: ty::Ty<'tcx>) -> bool {
         let copy_trait = self.tcx.lang_items().copy_trait();
         if let Some(copy_trait_def_id) = copy_trait {
@@ -341,6 +741,14 @@ impl<'tcx> Environment<'tcx> {
             ty::TyKind::Ref(_, ref_ty, _) => {
                 self.type_implements_trait(ref_ty, trait_def_id)
             }
+            ty::TyKind::Array(..) | ty::TyKind::Slice(..) => {
+                self.tcx.type_implements_trait((
+                    trait_def_id,
+                    ty,
+                    ty::List::empty(),
+                    ParamEnv::empty()
+                ))
+            }
             _ => {
                 unimplemented!() // none of the remaining types should be supported yet
             }
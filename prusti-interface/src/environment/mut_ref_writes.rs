@@ -0,0 +1,121 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use rustc_middle::mir;
+use rustc_middle::mir::visit::{MutatingUseContext, PlaceContext, Visitor};
+use rustc_middle::ty;
+use std::collections::HashSet;
+use log::{debug, trace};
+
+/// Returns the locations of the statements and terminators of `mir` that write
+/// to the target of a mutable reference argument, e.g. `self.field = value` in a
+/// method with a `&mut self` receiver, sorted by location.
+///
+/// The writes through the mutable references that are (re)borrowed from the
+/// arguments are included. Borrowing the target of an argument is not a write,
+/// because the borrow can only be passed to functions that are checked on their
+/// own.
+pub fn find_writes_through_mut_ref_args<'tcx>(mir: &mir::Body<'tcx>) -> Vec<mir::Location> {
+    let mut mut_refs: HashSet<mir::Local> = mir.args_iter()
+        .filter(|&arg| matches!(
+            mir.local_decls[arg].ty.kind(),
+            ty::TyKind::Ref(_, _, mir::Mutability::Mut)
+        ))
+        .collect();
+    if mut_refs.is_empty() {
+        return vec![];
+    }
+
+    // Collect the locals that may point into the targets of the arguments, until
+    // a fixpoint is reached
+    loop {
+        let mut collector = MutRefCollector {
+            mut_refs: &mut_refs,
+            new_mut_refs: HashSet::new(),
+        };
+        collector.visit_body(mir);
+        let new_mut_refs = collector.new_mut_refs;
+        if new_mut_refs.is_empty() {
+            break;
+        }
+        mut_refs.extend(new_mut_refs);
+    }
+    debug!("References to the targets of the arguments: {:?}", mut_refs);
+
+    let mut finder = WriteFinder {
+        mut_refs: &mut_refs,
+        writes: vec![],
+    };
+    finder.visit_body(mir);
+    let mut writes = finder.writes;
+    writes.sort();
+    writes.dedup();
+    writes
+}
+
+/// Returns true if `place` is the target of one of `mut_refs`, or is inside it.
+fn is_behind_mut_ref(place: &mir::Place, mut_refs: &HashSet<mir::Local>) -> bool {
+    mut_refs.contains(&place.local)
+        && place.projection.iter().any(|elem| matches!(elem, mir::ProjectionElem::Deref))
+}
+
+/// A visitor that collects the locals that are assigned a mutable reference into
+/// the target of one of the known references, or one of the known references.
+struct MutRefCollector<'b> {
+    mut_refs: &'b HashSet<mir::Local>,
+    new_mut_refs: HashSet<mir::Local>,
+}
+
+impl<'b, 'tcx> Visitor<'tcx> for MutRefCollector<'b> {
+    fn visit_assign(
+        &mut self,
+        place: &mir::Place<'tcx>,
+        rvalue: &mir::Rvalue<'tcx>,
+        location: mir::Location,
+    ) {
+        let points_into_target = match rvalue {
+            mir::Rvalue::Ref(_, mir::BorrowKind::Mut { .. }, source)
+            | mir::Rvalue::AddressOf(mir::Mutability::Mut, source) => {
+                is_behind_mut_ref(source, self.mut_refs)
+            }
+            mir::Rvalue::Use(mir::Operand::Copy(source))
+            | mir::Rvalue::Use(mir::Operand::Move(source)) => {
+                self.mut_refs.contains(&source.local)
+            }
+            _ => false,
+        };
+        if points_into_target && !self.mut_refs.contains(&place.local) {
+            trace!("{:?} points into the target of an argument at {:?}", place.local, location);
+            self.new_mut_refs.insert(place.local);
+        }
+    }
+}
+
+/// A visitor that finds the assignments to the targets of the known references.
+struct WriteFinder<'b> {
+    mut_refs: &'b HashSet<mir::Local>,
+    writes: Vec<mir::Location>,
+}
+
+impl<'b, 'tcx> Visitor<'tcx> for WriteFinder<'b> {
+    fn visit_place(
+        &mut self,
+        place: &mir::Place<'tcx>,
+        context: PlaceContext,
+        location: mir::Location,
+    ) {
+        let is_write = matches!(
+            context,
+            PlaceContext::MutatingUse(MutatingUseContext::Store)
+                | PlaceContext::MutatingUse(MutatingUseContext::AsmOutput)
+                | PlaceContext::MutatingUse(MutatingUseContext::Call)
+        );
+        if is_write && is_behind_mut_ref(place, self.mut_refs) {
+            trace!("Place {:?} is written at {:?}", place, location);
+            self.writes.push(location);
+        }
+    }
+}
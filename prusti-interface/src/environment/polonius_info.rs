@@ -526,6 +526,35 @@ fn add_fake_facts<'a, 'tcx: 'a>(
     Ok((reference_moves, argument_moves, incompatible_loans))
 }
 
+/// Remove the loans of the shallow borrows that the compiler introduces for the guards of
+/// `match` expressions. These fake borrows only forbid the guards to modify the matched place,
+/// they are used only by `FakeRead` statements and are not encoded.
+fn remove_fake_borrows(
+    all_facts: &mut facts::AllInputFacts,
+    interner: &facts::Interner,
+    mir: &mir::Body,
+) {
+    let fake_loans: HashSet<facts::Loan> = all_facts.borrow_region
+        .iter()
+        .filter(|&&(_, _, point)| {
+            let location = interner.get_point(point).location;
+            let statements = &mir[location.block].statements;
+            statements.get(location.statement_index).map_or(false, |statement| matches!(
+                statement.kind,
+                mir::StatementKind::Assign(box (_, mir::Rvalue::Ref(_, mir::BorrowKind::Shallow, _)))
+            ))
+        })
+        .map(|&(_, loan, _)| loan)
+        .collect();
+    if fake_loans.is_empty() {
+        return;
+    }
+    debug!("remove fake loans: {:?}", fake_loans);
+    all_facts.borrow_region.retain(|(_, loan, _)| !fake_loans.contains(loan));
+    all_facts.killed.retain(|(loan, _)| !fake_loans.contains(loan));
+    all_facts.invalidates.retain(|(_, loan)| !fake_loans.contains(loan));
+}
+
 /// Remove back edges to make MIR uncyclic so that we can compute reborrowing dags at the end of
 /// the loop body.
 fn remove_back_edges(
@@ -699,6 +728,7 @@ impl<'a, 'tcx: 'a> PoloniusInfo<'a, 'tcx> {
         let mut call_magic_wands = HashMap::new();
 
         let mut all_facts = facts_loader.facts;
+        remove_fake_borrows(&mut all_facts, &facts_loader.interner, mir);
 
         let real_edges = RealEdges::new(&mir);
         let loop_info = loops::ProcedureLoops::new(&mir, &real_edges);
@@ -14,6 +14,7 @@ use std::collections::{HashSet, HashMap};
 use rustc_span::Span;
 use log::{trace, debug};
 use rustc_middle::mir::StatementKind;
+use rustc_middle::mir::visit::{PlaceContext, Visitor};
 use rustc_hir::def_id;
 use std::iter::FromIterator;
 use crate::environment::mir_utils::RealEdges;
@@ -139,11 +140,174 @@ impl<'a, 'tcx> Procedure<'a, 'tcx> {
             .collect()
     }
 
+    /// Returns the name of the user variable stored in `local`, as recorded in
+    /// the debug information of the MIR. Temporaries and the variables
+    /// introduced by desugarings (e.g. the iterator of a `for` loop) have no name.
+    pub fn get_var_name(&self, local: mir::Local) -> Option<String> {
+        self.mir.var_debug_info.iter()
+            .find(|info| match info.value {
+                mir::VarDebugInfoContents::Place(place) => place.as_local() == Some(local),
+                _ => false,
+            })
+            .filter(|info| info.source_info.span.desugaring_kind().is_none())
+            .map(|info| info.name.to_ident_string())
+    }
+
     /// Check whether the block is used for typechecking the specification
     pub fn is_spec_block(&self, bbi: BasicBlockIndex) -> bool {
         !self.nonspec_basic_blocks.contains(&bbi)
     }
 
+    /// Returns the closures of the `prusti_assert!(..)` and `prusti_assume!(..)`
+    /// ghost statements that are type-checked by the specification blocks
+    /// starting at `bbi`, together with the span of the closure definition.
+    pub fn get_ghost_statements(&self, bbi: BasicBlockIndex) -> Vec<(def_id::DefId, Span)> {
+        let mut ghost_statements = vec![];
+        let mut visited: HashSet<BasicBlock> = HashSet::new();
+        let mut to_visit = vec![bbi];
+        while let Some(curr) = to_visit.pop() {
+            if !self.is_spec_block(curr) || !visited.insert(curr) {
+                continue;
+            }
+            for stmt in &self.mir[curr].statements {
+                if let StatementKind::Assign(box (
+                    _,
+                    Rvalue::Aggregate(box AggregateKind::Closure(def_id, _), _),
+                )) = &stmt.kind {
+                    if is_ghost_statement_closure(*def_id, &self.tcx) {
+                        ghost_statements.push((*def_id, stmt.source_info.span));
+                    }
+                }
+            }
+            to_visit.extend(self.real_edges.successors(curr).iter().cloned());
+        }
+        ghost_statements
+    }
+
+    /// Returns the blocks whose terminator enters the specification blocks of a
+    /// `body_invariant!(..)`, together with the span of the macro call.
+    pub fn get_loop_invariant_entries(&self) -> Vec<(BasicBlockIndex, Span)> {
+        let mut entries = vec![];
+        for bbi in self.get_reachable_nonspec_cfg_blocks() {
+            for &target in self.real_edges.successors(bbi) {
+                if self.is_spec_block(target) && !self.is_ghost_statement_block(target) {
+                    let span = self.mir[target].terminator().source_info.span.source_callsite();
+                    entries.push((bbi, span));
+                }
+            }
+        }
+        entries
+    }
+
+    /// Returns the name of the label if the closure belongs to a `label!(..)` ghost statement.
+    pub fn get_ghost_label(&self, cl_def_id: def_id::DefId) -> Option<String> {
+        get_ghost_label_name(cl_def_id, &self.tcx)
+    }
+
+    /// Returns the labels defined by `label!(..)` ghost statements, together with the block
+    /// whose terminator enters the specification block of the label and the span of the label.
+    pub fn get_ghost_labels(&self) -> Vec<(String, BasicBlockIndex, Span)> {
+        let mut labels = vec![];
+        for bbi in self.get_reachable_nonspec_cfg_blocks() {
+            for &target in self.real_edges.successors(bbi) {
+                if !self.is_spec_block(target) {
+                    continue;
+                }
+                for (cl_def_id, span) in self.get_ghost_statements(target) {
+                    if let Some(label) = get_ghost_label_name(cl_def_id, &self.tcx) {
+                        labels.push((label, bbi, span));
+                    }
+                }
+            }
+        }
+        labels
+    }
+
+    /// Check whether the closure only marks the beginning or the end of a `ghost! { .. }` block.
+    pub fn is_ghost_block_marker(&self, cl_def_id: def_id::DefId) -> bool {
+        let attrs = self.tcx.get_attrs(cl_def_id);
+        crate::utils::has_prusti_attr(attrs, "ghost_block_begin")
+            || crate::utils::has_prusti_attr(attrs, "ghost_block_end")
+    }
+
+    /// Returns the reachable basic blocks that execute the code of `ghost! { .. }` blocks.
+    pub fn get_ghost_code_blocks(&self) -> HashSet<BasicBlock> {
+        let mut begins = vec![];
+        let mut ends = HashSet::new();
+        for bbi in self.get_reachable_nonspec_cfg_blocks() {
+            for &target in self.real_edges.successors(bbi) {
+                if !self.is_spec_block(target) {
+                    continue;
+                }
+                for (cl_def_id, _) in self.get_ghost_statements(target) {
+                    let attrs = self.tcx.get_attrs(cl_def_id);
+                    if let Some(id) = crate::utils::read_prusti_attr("ghost_block_begin", attrs) {
+                        begins.push((bbi, id));
+                    } else if let Some(id) = crate::utils::read_prusti_attr("ghost_block_end", attrs) {
+                        ends.insert((bbi, id));
+                    }
+                }
+            }
+        }
+
+        // The ghost code starts right after the block entering the first marker and ends with
+        // the block entering the second marker.
+        let mut ghost_blocks = HashSet::new();
+        for (begin, id) in begins {
+            let mut to_visit: Vec<BasicBlock> = self.real_edges.successors(begin).to_vec();
+            while let Some(curr) = to_visit.pop() {
+                if self.is_spec_block(curr)
+                    || self.mir[curr].is_cleanup
+                    || !ghost_blocks.insert(curr)
+                {
+                    continue;
+                }
+                if !ends.contains(&(curr, id.clone())) {
+                    to_visit.extend(self.real_edges.successors(curr).iter().cloned());
+                }
+            }
+        }
+        ghost_blocks
+    }
+
+    /// Returns the span of a use of a variable declared in a `ghost! { .. }` block by the
+    /// code outside of the ghost blocks and of the specifications, if any.
+    pub fn find_ghost_variable_use(&self) -> Option<Span> {
+        let ghost_blocks = self.get_ghost_code_blocks();
+        let mut ghost_locals = HashSet::new();
+        for &bbi in &ghost_blocks {
+            for stmt in &self.mir[bbi].statements {
+                if let StatementKind::StorageLive(local) = stmt.kind {
+                    if self.mir.local_decls[local].is_user_variable() {
+                        ghost_locals.insert(local);
+                    }
+                }
+            }
+        }
+        if ghost_locals.is_empty() {
+            return None;
+        }
+        let mut finder = GhostVariableUseFinder {
+            procedure: self,
+            ghost_blocks: &ghost_blocks,
+            ghost_locals: &ghost_locals,
+            found: None,
+        };
+        finder.visit_body(&self.mir);
+        finder.found
+    }
+
+    /// Check whether every execution reaching `bbi` went through `dominator` before.
+    pub fn dominates(&self, dominator: BasicBlockIndex, bbi: BasicBlockIndex) -> bool {
+        self.mir.dominators().is_dominated_by(bbi, dominator)
+    }
+
+    /// Check whether the specification blocks starting at `bbi` belong to a
+    /// ghost statement instead of, e.g., a loop invariant.
+    pub fn is_ghost_statement_block(&self, bbi: BasicBlockIndex) -> bool {
+        !self.get_ghost_statements(bbi).is_empty()
+    }
+
     /// Check whether the block is reachable
     pub fn is_reachable_block(&self, bbi: BasicBlockIndex) -> bool {
         self.reachable_basic_blocks.contains(&bbi)
@@ -184,6 +348,31 @@ impl<'a, 'tcx> Procedure<'a, 'tcx> {
     }
 }
 
+/// A visitor that looks for a read of a ghost variable in the real code of a procedure.
+struct GhostVariableUseFinder<'b, 'a, 'tcx> {
+    procedure: &'b Procedure<'a, 'tcx>,
+    ghost_blocks: &'b HashSet<BasicBlock>,
+    ghost_locals: &'b HashSet<mir::Local>,
+    found: Option<Span>,
+}
+
+impl<'b, 'a, 'tcx> Visitor<'tcx> for GhostVariableUseFinder<'b, 'a, 'tcx> {
+    fn visit_local(&mut self, local: &mir::Local, context: PlaceContext, location: mir::Location) {
+        if self.found.is_some()
+            || !self.ghost_locals.contains(local)
+            || self.ghost_blocks.contains(&location.block)
+            || !self.procedure.is_reachable_block(location.block)
+            || self.procedure.is_spec_block(location.block)
+        {
+            return;
+        }
+        // Writing to a ghost variable or dropping it does not leak its value
+        if matches!(context, PlaceContext::NonMutatingUse(_)) || context.is_borrow() {
+            self.found = Some(self.procedure.mir.source_info(location).span);
+        }
+    }
+}
+
 /// Returns the set of basic blocks that are not used as part of the typechecking of Prusti specifications
 fn build_reachable_basic_blocks(mir: &Mir, real_edges: &RealEdges) -> HashSet<BasicBlock> {
     let mut reachable_basic_blocks: HashSet<BasicBlock> = HashSet::new();
@@ -215,6 +404,19 @@ fn is_spec_closure(def_id: def_id::DefId, tcx: &TyCtxt) -> bool {
     crate::utils::has_spec_only_attr(tcx.get_attrs(def_id))
 }
 
+fn is_ghost_statement_closure(def_id: def_id::DefId, tcx: &TyCtxt) -> bool {
+    let attrs = tcx.get_attrs(def_id);
+    crate::utils::has_prusti_attr(attrs, "prusti_assertion_spec")
+        || crate::utils::has_prusti_attr(attrs, "prusti_assumption_spec")
+        || crate::utils::has_prusti_attr(attrs, "ghost_label")
+        || crate::utils::has_prusti_attr(attrs, "ghost_block_begin")
+        || crate::utils::has_prusti_attr(attrs, "ghost_block_end")
+}
+
+fn get_ghost_label_name(def_id: def_id::DefId, tcx: &TyCtxt) -> Option<String> {
+    crate::utils::read_prusti_attr("ghost_label", tcx.get_attrs(def_id))
+}
+
 fn is_spec_basic_block(bb_data: &BasicBlockData, tcx: &TyCtxt) -> bool {
     for stmt in &bb_data.statements {
         if let StatementKind::Assign(box (_, rvalue)) = &stmt.kind {
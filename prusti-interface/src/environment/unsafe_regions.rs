@@ -0,0 +1,141 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use rustc_middle::mir;
+use rustc_middle::mir::visit::{PlaceContext, Visitor};
+use rustc_index::vec::Idx;
+use std::collections::HashSet;
+use log::{debug, trace};
+
+/// The `unsafe` blocks of a procedure, which are trusted by the encoding of
+/// procedures annotated with `#[trusted_unsafe]`.
+///
+/// The statements and terminators of the blocks are not encoded. Instead, the
+/// places that they may modify are havocked right after leaving a block.
+#[derive(Debug)]
+pub struct UnsafeRegions<'tcx> {
+    /// The locations of the statements and terminators inside `unsafe` blocks.
+    locations: HashSet<mir::Location>,
+    /// The first locations outside of the `unsafe` blocks that are reachable
+    /// from a location inside them.
+    exits: HashSet<mir::Location>,
+    /// The places, used also outside of the `unsafe` blocks, that may be
+    /// modified by them.
+    write_set: Vec<mir::Place<'tcx>>,
+}
+
+impl<'tcx> UnsafeRegions<'tcx> {
+    pub fn new(mir: &mir::Body<'tcx>) -> Self {
+        let mut locations = HashSet::new();
+        for (bbi, bb_data) in mir.basic_blocks().iter_enumerated() {
+            for statement_index in 0..=bb_data.statements.len() {
+                let location = mir::Location { block: bbi, statement_index };
+                if is_in_unsafe_block(mir, mir.source_info(location)) {
+                    locations.insert(location);
+                }
+            }
+        }
+
+        let mut exits = HashSet::new();
+        for (bbi, bb_data) in mir.basic_blocks().iter_enumerated() {
+            for statement_index in 0..=bb_data.statements.len() {
+                let location = mir::Location { block: bbi, statement_index };
+                if locations.contains(&location) {
+                    continue;
+                }
+                let leaves_region = if statement_index > 0 {
+                    locations.contains(&mir::Location { block: bbi, statement_index: statement_index - 1 })
+                } else {
+                    mir.predecessors()[bbi].iter().any(|&pred| {
+                        locations.contains(&mir.terminator_loc(pred))
+                    })
+                };
+                if leaves_region {
+                    exits.insert(location);
+                }
+            }
+        }
+
+        let mut collector = WriteSetCollector {
+            locations: &locations,
+            written_places: vec![],
+            used_outside: HashSet::new(),
+        };
+        collector.visit_body(mir);
+        let WriteSetCollector { written_places, used_outside, .. } = collector;
+        let write_set = written_places
+            .into_iter()
+            .filter(|place| {
+                let local = place.local;
+                // The return value and the arguments are used by the specification
+                (local.index() <= mir.arg_count || used_outside.contains(&local))
+                    // The targets of raw pointers are not known
+                    && !mir.local_decls[local].ty.is_unsafe_ptr()
+            })
+            .collect();
+
+        let regions = UnsafeRegions { locations, exits, write_set };
+        debug!("Unsafe regions: {:?}", regions);
+        regions
+    }
+
+    /// Returns `true` iff the procedure has no `unsafe` blocks.
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    /// Is the statement or terminator at `location` inside an `unsafe` block?
+    pub fn contains(&self, location: mir::Location) -> bool {
+        self.locations.contains(&location)
+    }
+
+    /// Is `location` the first location after leaving an `unsafe` block?
+    pub fn is_exit(&self, location: mir::Location) -> bool {
+        self.exits.contains(&location)
+    }
+
+    /// The places, used also outside of the `unsafe` blocks, that are
+    /// assigned or borrowed inside them.
+    pub fn write_set(&self) -> &[mir::Place<'tcx>] {
+        &self.write_set
+    }
+}
+
+fn is_in_unsafe_block(mir: &mir::Body, source_info: &mir::SourceInfo) -> bool {
+    match mir.source_scopes[source_info.scope].local_data {
+        mir::ClearCrossCrate::Set(ref scope_data) => {
+            matches!(scope_data.safety, mir::Safety::ExplicitUnsafe(_))
+        }
+        mir::ClearCrossCrate::Clear => false,
+    }
+}
+
+/// A visitor that collects the places that are modified inside of the
+/// `unsafe` blocks and the locals that are used outside of them.
+struct WriteSetCollector<'b, 'tcx> {
+    locations: &'b HashSet<mir::Location>,
+    written_places: Vec<mir::Place<'tcx>>,
+    used_outside: HashSet<mir::Local>,
+}
+
+impl<'b, 'tcx> Visitor<'tcx> for WriteSetCollector<'b, 'tcx> {
+    fn visit_place(
+        &mut self,
+        place: &mir::Place<'tcx>,
+        context: PlaceContext,
+        location: mir::Location,
+    ) {
+        if !context.is_use() {
+            return;
+        }
+        if !self.locations.contains(&location) {
+            self.used_outside.insert(place.local);
+        } else if context.is_mutating_use() && !self.written_places.contains(place) {
+            trace!("Place {:?} is modified at {:?} ({:?})", place, location, context);
+            self.written_places.push(*place);
+        }
+    }
+}
@@ -29,6 +29,7 @@ extern crate rustc_middle;
 extern crate rustc_span;
 extern crate rustc_ast;
 extern crate rustc_attr;
+extern crate rustc_session;
 
 // extern crate csv;
 // extern crate datafrog;
@@ -4,7 +4,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use rustc_span::{Span, MultiSpan};
+use rustc_span::MultiSpan;
 use crate::environment::Environment;
 use prusti_common::config;
 use ::log::warn;
@@ -25,6 +25,7 @@ pub struct PrustiError {
     span: MultiSpan,
     help: Option<String>,
     note: Option<(String, MultiSpan)>,
+    notes: Vec<String>,
 }
 
 impl PrustiError {
@@ -36,6 +37,7 @@ impl PrustiError {
             span,
             help: None,
             note: None,
+            notes: vec![],
         }
     }
 
@@ -70,6 +72,18 @@ impl PrustiError {
         )
     }
 
+    /// Report a likely mistake in the specification of the verified Rust code (e.g. a
+    /// comparison that is trivially true). This is always reported as a warning.
+    pub fn warning<S: ToString>(message: S, span: MultiSpan) -> Self {
+        check_message(message.to_string());
+        let mut warning = PrustiError::new(
+            format!("[Prusti: warning] {}", message.to_string()),
+            span
+        );
+        warning.set_warning();
+        warning
+    }
+
     /// Report an internal error of Prusti (e.g. failure of the fold-unfold)
     pub fn internal<S: ToString>(message: S, span: MultiSpan) -> Self {
         check_message(message.to_string());
@@ -88,13 +102,34 @@ impl PrustiError {
         self.is_error
     }
 
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn span(&self) -> &MultiSpan {
+        &self.span
+    }
+
+    /// Checks whether `other` reports the same problem at the same location.
+    pub fn is_duplicate_of(&self, other: &PrustiError) -> bool {
+        self.is_error == other.is_error
+            && self.message == other.message
+            && self.span.primary_spans() == other.span.primary_spans()
+    }
+
     pub fn set_help<S: ToString>(mut self, message: S) -> Self {
         self.help = Some(message.to_string());
         self
     }
 
-    pub fn set_note<S: ToString>(mut self, note: S, note_span: Span) -> Self {
-        self.note = Some((note.to_string(), MultiSpan::from_span(note_span)));
+    pub fn set_note<S: ToString, Sp: Into<MultiSpan>>(mut self, note: S, note_span: Sp) -> Self {
+        self.note = Some((note.to_string(), note_span.into()));
+        self
+    }
+
+    /// Add a note without a span, reported after all the other messages.
+    pub fn add_note<S: ToString>(mut self, note: S) -> Self {
+        self.notes.push(note.to_string());
         self
     }
 
@@ -106,6 +141,7 @@ impl PrustiError {
                 &self.message,
                 &self.help,
                 &self.note,
+                &self.notes,
             );
         } else {
             env.span_warn_with_help_and_note(
@@ -113,6 +149,7 @@ impl PrustiError {
                 &self.message,
                 &self.help,
                 &self.note,
+                &self.notes,
             );
         }
     }
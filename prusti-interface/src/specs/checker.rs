@@ -1,29 +1,66 @@
 use log::{debug, info};
+use prusti_common::config;
+use rustc_ast::ast;
 use rustc_hir::{
     self as hir,
     def_id::{DefId, LocalDefId},
     intravisit::{self, Visitor},
     itemlikevisit::ItemLikeVisitor,
 };
-use rustc_middle::{hir::map::Map, ty::TyCtxt};
-use rustc_span::{MultiSpan, Span};
+use rustc_hir::def::{DefKind, Res};
+use rustc_middle::{hir::map::Map, ty::{self, adjustment::Adjust, subst::Subst, TyCtxt}};
+use rustc_session::config::CrateType;
+use rustc_span::{hygiene::{ExpnKind, MacroKind}, MultiSpan, Span, DUMMY_SP};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
-    environment::Environment,
-    utils::{has_prusti_attr, has_spec_only_attr},
+    environment::{BuiltinVecMethod, Environment},
+    specs::typed,
+    utils::{
+        has_extern_spec_attr, has_prusti_attr, has_spec_only_attr, read_prusti_attr,
+        read_prusti_attrs,
+    },
     PrustiError,
 };
 
-/// Checker visitor for the specifications. Currently checks that `#[predicate]`
-/// functions are never used from non-specification code, but more checks may follow.
+/// Checker visitor for the specifications. Checks that `#[predicate]` functions
+/// are never used from non-specification code and are not recursive, looks
+/// for specifications that only hold because arithmetic in specifications does
+/// not overflow, for triggers that cannot be used by the verifier, for
+/// `#[derive_spec_eq]` types whose equality is not derived, and for
+/// specifications that are never used.
 pub struct SpecChecker {
     /// Map of the `DefID`s to the `Span`s of `#[predicate]` functions found in the first pass.
     predicates: HashMap<DefId, Span>,
 
+    /// Map of the specification IDs of the predicate bodies to the `DefID`s of the
+    /// predicates, collected in the first pass.
+    pred_spec_ids: HashMap<String, DefId>,
+
     /// Span of use and definition of predicates used outside of specifications, collected in the second pass.
     pred_usages: Vec<(Span, Span)>,
+
+    /// Span of the definition of a recursive predicate and of the call that closes the
+    /// cycle, collected in the third pass.
+    recursive_predicates: Vec<(Span, Span)>,
+
+    /// Spans of arithmetic in specifications and of the comparisons that are trivially
+    /// true or false because of it.
+    unbounded_arithmetic: Vec<(Span, Span)>,
+
+    /// Calls in specifications that cannot be encoded, with the errors to report.
+    impure_calls: Vec<(Span, PrustiError)>,
+
+    /// Trigger terms that cannot be used by the verifier, with the errors to report.
+    invalid_triggers: Vec<(Span, PrustiError)>,
+
+    /// Specifications that are never used, with the warnings (or errors) to report.
+    dead_specs: Vec<(Span, PrustiError)>,
+
+    /// Types annotated with `#[derive_spec_eq]` whose `PartialEq` implementation is not
+    /// derived, with the errors to report.
+    invalid_spec_eq_types: Vec<(Span, PrustiError)>,
 }
 
 /// First predicate checks visitor: collect all function items that originate
@@ -32,6 +69,7 @@ struct CollectPredicatesVisitor<'v, 'tcx> {
     tcx: TyCtxt<'tcx>,
 
     predicates: &'v mut HashMap<DefId, Span>,
+    pred_spec_ids: &'v mut HashMap<String, DefId>,
 }
 
 impl<'v, 'tcx> intravisit::Visitor<'tcx> for CollectPredicatesVisitor<'v, 'tcx> {
@@ -51,9 +89,10 @@ impl<'v, 'tcx> intravisit::Visitor<'tcx> for CollectPredicatesVisitor<'v, 'tcx>
     ) {
         // collect this fn's DefId if predicate function
         let attrs = self.tcx.hir().attrs(id);
-        if has_prusti_attr(attrs, "pred_spec_id_ref") {
+        if let Some(spec_id) = read_prusti_attr("pred_spec_id_ref", attrs) {
             let def_id = self.tcx.hir().local_def_id(id).to_def_id();
             self.predicates.insert(def_id, s);
+            self.pred_spec_ids.insert(spec_id, def_id);
         }
 
         intravisit::walk_fn(self, fk, fd, b, s, id);
@@ -122,11 +161,600 @@ impl<'v, 'tcx> Visitor<'tcx> for CheckPredicatesVisitor<'v, 'tcx> {
     }
 }
 
+/// Third predicate checks visitor: collect the calls between predicates, i.e. the
+/// references to predicate functions from the bodies of predicates
+struct CollectPredicateCallsVisitor<'v, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+
+    predicates: &'v HashMap<DefId, Span>,
+    pred_spec_ids: &'v HashMap<String, DefId>,
+    /// The predicate whose body is being visited.
+    current_predicate: Option<DefId>,
+    /// For each predicate, the called predicates and the spans of the calls.
+    pred_calls: HashMap<DefId, Vec<(DefId, Span)>>,
+}
+
+impl<'v, 'tcx> Visitor<'tcx> for CollectPredicateCallsVisitor<'v, 'tcx> {
+    type Map = Map<'tcx>;
+
+    fn nested_visit_map(&mut self) -> intravisit::NestedVisitorMap<Self::Map> {
+        intravisit::NestedVisitorMap::All(self.tcx.hir())
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx hir::Expr<'tcx>) {
+        if let (Some(caller), hir::ExprKind::Path(ref path)) = (self.current_predicate, &ex.kind) {
+            let res = self.tcx.typeck(ex.hir_id.owner).qpath_res(path, ex.hir_id);
+            if let hir::def::Res::Def(_, def_id) = res {
+                if self.predicates.contains_key(&def_id) {
+                    self.pred_calls.entry(caller).or_default().push((def_id, ex.span));
+                }
+            }
+        }
+
+        intravisit::walk_expr(self, ex);
+    }
+
+    fn visit_fn(
+        &mut self,
+        fk: intravisit::FnKind<'tcx>,
+        fd: &'tcx hir::FnDecl<'tcx>,
+        b: hir::BodyId,
+        s: Span,
+        id: hir::HirId,
+    ) {
+        // The body of a predicate is the specification function with its spec ID;
+        // the closures nested in it belong to the same predicate
+        let attrs = self.tcx.hir().attrs(id);
+        let predicate = read_prusti_attr("spec_id", attrs)
+            .and_then(|spec_id| self.pred_spec_ids.get(&spec_id).copied());
+        if let Some(def_id) = predicate {
+            let outer_predicate = self.current_predicate.replace(def_id);
+            intravisit::walk_fn(self, fk, fd, b, s, id);
+            self.current_predicate = outer_predicate;
+        } else {
+            intravisit::walk_fn(self, fk, fd, b, s, id);
+        }
+    }
+}
+
+impl<'v, 'tcx> CollectPredicateCallsVisitor<'v, 'tcx> {
+    /// Depth-first search for cycles of calls starting from `predicate`. Each call
+    /// to a predicate that is still on the stack closes a cycle.
+    fn find_cycles(
+        &self,
+        predicate: DefId,
+        stack: &mut Vec<DefId>,
+        visited: &mut HashSet<DefId>,
+        cycles: &mut Vec<(Span, Span)>,
+    ) {
+        if !visited.insert(predicate) {
+            return;
+        }
+        stack.push(predicate);
+        for &(callee, call_span) in self.pred_calls.get(&predicate).into_iter().flatten() {
+            if stack.contains(&callee) {
+                cycles.push((self.predicates[&callee], call_span));
+            } else {
+                self.find_cycles(callee, stack, visited, cycles);
+            }
+        }
+        stack.pop();
+    }
+}
+
+/// Dead specifications visitor: collect the items that are referenced anywhere in
+/// the crate, either by a path (e.g. a call or a function pointer) or by a method
+/// call or an overloaded operator. The references from specifications are included.
+struct CollectReferencesVisitor<'tcx> {
+    tcx: TyCtxt<'tcx>,
+
+    referenced: HashSet<DefId>,
+}
+
+impl<'tcx> Visitor<'tcx> for CollectReferencesVisitor<'tcx> {
+    type Map = Map<'tcx>;
+
+    fn nested_visit_map(&mut self) -> intravisit::NestedVisitorMap<Self::Map> {
+        intravisit::NestedVisitorMap::All(self.tcx.hir())
+    }
+
+    fn visit_item(&mut self, i: &'tcx hir::Item<'tcx>) {
+        // Only the items with bodies can contain references
+        use hir::ItemKind::*;
+
+        match i.kind {
+            Static(_, _, _) | Const(_, _) | Fn(_, _, _) | Mod(_) | Trait(..) | Impl { .. } => {
+                intravisit::walk_item(self, i);
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx hir::Expr<'tcx>) {
+        let typeck_results = self.tcx.typeck(ex.hir_id.owner);
+        match ex.kind {
+            hir::ExprKind::Path(ref path) => {
+                if let hir::def::Res::Def(_, def_id) = typeck_results.qpath_res(path, ex.hir_id) {
+                    self.referenced.insert(def_id);
+                }
+            }
+            hir::ExprKind::MethodCall(..)
+            | hir::ExprKind::Binary(..)
+            | hir::ExprKind::AssignOp(..)
+            | hir::ExprKind::Unary(..)
+            | hir::ExprKind::Index(..) => {
+                if let Some(def_id) = typeck_results.type_dependent_def_id(ex.hir_id) {
+                    self.referenced.insert(def_id);
+                }
+            }
+            _ => {}
+        }
+
+        intravisit::walk_expr(self, ex);
+    }
+}
+
+/// Spec equality checks visitor: find the structs and enums annotated with
+/// `#[derive_spec_eq]` whose `PartialEq` implementation is not derived, and which
+/// thus might not be equivalent to the snapshot equality.
+struct CheckSpecEqTypesVisitor<'v, 'tcx> {
+    env: &'v Environment<'tcx>,
+
+    invalid_spec_eq_types: &'v mut Vec<(Span, PrustiError)>,
+}
+
+impl<'v, 'tcx> ItemLikeVisitor<'tcx> for CheckSpecEqTypesVisitor<'v, 'tcx> {
+    fn visit_item(&mut self, item: &'tcx hir::Item<'tcx>) {
+        if !matches!(item.kind, hir::ItemKind::Struct(..) | hir::ItemKind::Enum(..)) {
+            return;
+        }
+        let def_id = item.def_id.to_def_id();
+        let tcx = self.env.tcx();
+        if has_prusti_attr(tcx.get_attrs(def_id), "derive_spec_eq")
+            && !self.env.has_derived_partial_eq(def_id)
+        {
+            let error = PrustiError::incorrect(
+                "the `#[derive_spec_eq]` attribute requires a derived `PartialEq` \
+                implementation".to_string(),
+                MultiSpan::from_span(item.span),
+            ).set_help(
+                "add `#[derive(PartialEq)]` to the type; only derived implementations are \
+                known to compare values structurally"
+            );
+            self.invalid_spec_eq_types.push((item.span, error));
+        }
+    }
+
+    fn visit_trait_item(&mut self, _trait_item: &'tcx hir::TraitItem<'tcx>) {}
+
+    fn visit_impl_item(&mut self, _impl_item: &'tcx hir::ImplItem<'tcx>) {}
+
+    fn visit_foreign_item(&mut self, _foreign_item: &'tcx hir::ForeignItem<'tcx>) {}
+}
+
+/// Unbounded arithmetic checks visitor: find comparisons such as `x + 1 > x` in the
+/// body of a specification expression. With unbounded integers these are trivially
+/// true (or false), while with the machine integers of Rust they might not be.
+struct CheckUnboundedArithmeticVisitor<'v, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    typeck_results: &'tcx ty::TypeckResults<'tcx>,
+
+    unbounded_arithmetic: &'v mut Vec<(Span, Span)>,
+}
+
+impl<'v, 'tcx> CheckUnboundedArithmeticVisitor<'v, 'tcx> {
+    /// Returns true if `expr` is `base + c` or `base - c`, for a non-zero integer
+    /// literal `c`.
+    fn is_offset_of(&self, expr: &hir::Expr<'tcx>, base: &hir::Expr<'tcx>) -> bool {
+        if let hir::ExprKind::Binary(op, lhs, rhs) = expr.kind {
+            let is_offset = matches!(op.node, hir::BinOpKind::Add | hir::BinOpKind::Sub)
+                && matches!(
+                    rhs.kind,
+                    hir::ExprKind::Lit(hir::Lit { node: ast::LitKind::Int(value, _), .. })
+                        if value != 0
+                );
+            is_offset
+                && self.typeck_results.expr_ty(expr).is_integral()
+                && self.is_same_expr(lhs, base)
+        } else {
+            false
+        }
+    }
+
+    /// Syntactic equality of two expressions, ignoring whitespace.
+    fn is_same_expr(&self, a: &hir::Expr<'tcx>, b: &hir::Expr<'tcx>) -> bool {
+        let source_map = self.tcx.sess.source_map();
+        match (source_map.span_to_snippet(a.span), source_map.span_to_snippet(b.span)) {
+            (Ok(a), Ok(b)) => a.split_whitespace().eq(b.split_whitespace()),
+            _ => false,
+        }
+    }
+}
+
+impl<'v, 'tcx> Visitor<'tcx> for CheckUnboundedArithmeticVisitor<'v, 'tcx> {
+    type Map = Map<'tcx>;
+
+    fn nested_visit_map(&mut self) -> intravisit::NestedVisitorMap<Self::Map> {
+        // Nested specification expressions are checked on their own
+        intravisit::NestedVisitorMap::None
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx hir::Expr<'tcx>) {
+        if let hir::ExprKind::Binary(op, lhs, rhs) = ex.kind {
+            if op.node.is_comparison() {
+                if self.is_offset_of(lhs, rhs) {
+                    self.unbounded_arithmetic.push((lhs.span, ex.span));
+                } else if self.is_offset_of(rhs, lhs) {
+                    self.unbounded_arithmetic.push((rhs.span, ex.span));
+                }
+            }
+        }
+
+        intravisit::walk_expr(self, ex);
+    }
+}
+
+/// Impure calls visitor: find the calls in the body of a specification expression
+/// that cannot be encoded, because the called function is not pure. Method calls
+/// and overloaded operators are resolved to the called implementation, if possible.
+struct CheckSpecCallsVisitor<'v, 'tcx> {
+    env: &'v Environment<'tcx>,
+    def_spec: &'v typed::DefSpecificationMap<'tcx>,
+    typeck_results: &'tcx ty::TypeckResults<'tcx>,
+
+    impure_calls: &'v mut Vec<(Span, PrustiError)>,
+}
+
+impl<'v, 'tcx> CheckSpecCallsVisitor<'v, 'tcx> {
+    fn is_pure(&self, def_id: DefId) -> bool {
+        self.def_spec.get(&def_id).map_or(false, |spec| spec.expect_procedure().pure)
+    }
+
+    /// Returns true if a call of the function can be encoded in a specification.
+    fn is_supported_callee(&self, def_id: DefId, substs: ty::subst::SubstsRef<'tcx>) -> bool {
+        let tcx = self.env.tcx();
+        if matches!(tcx.def_kind(def_id), DefKind::Ctor(..)) {
+            // Constructors of tuple structs and enum variants are not calls in MIR
+            return true;
+        }
+        if tcx.crate_name(def_id.krate).as_str() == "prusti_contracts" {
+            return true;
+        }
+        // Functions with a dedicated encoding
+        let def_path = tcx.def_path_str(def_id);
+        if matches!(
+            def_path.as_str(),
+            "core::slice::<impl [T]>::len"
+                | "core::str::<impl str>::len"
+                | "std::cmp::PartialEq::eq"
+                | "core::cmp::PartialEq::eq"
+                | "std::cmp::PartialEq::ne"
+                | "core::cmp::PartialEq::ne"
+        ) || self.env.get_builtin_pure_method(def_id).is_some() {
+            return true;
+        }
+        let arg_tys = tcx.fn_sig(def_id).subst(tcx, substs).skip_binder().inputs();
+        if matches!(
+            self.env.get_builtin_vec_method(def_id, arg_tys),
+            Some(BuiltinVecMethod::Len) | Some(BuiltinVecMethod::Index)
+        ) {
+            return true;
+        }
+        self.is_pure(def_id)
+            || self.env.resolve_trait_method_call(def_id, substs)
+                .map_or(false, |impl_def_id| self.is_pure(impl_def_id))
+    }
+
+    /// Returns true if the function could be marked as `#[pure]`, i.e. it has no
+    /// mutable reference arguments and a sized return type.
+    fn can_be_pure(&self, def_id: DefId) -> bool {
+        let tcx = self.env.tcx();
+        let sig = tcx.liberate_late_bound_regions(def_id, tcx.fn_sig(def_id));
+        let has_mut_ref_arg = sig.inputs().iter().any(|ty| {
+            matches!(ty.kind(), ty::TyKind::Ref(_, _, hir::Mutability::Mut))
+        });
+        !has_mut_ref_arg && sig.output().is_sized(tcx.at(DUMMY_SP), tcx.param_env(def_id))
+    }
+
+    fn check_call(&mut self, call_span: Span, def_id: DefId, substs: ty::subst::SubstsRef<'tcx>) {
+        if self.is_supported_callee(def_id, substs) {
+            return;
+        }
+        // Report the implementation of a trait method, which is the one that
+        // would have to be pure
+        let def_id = self.env.resolve_trait_method_call(def_id, substs).unwrap_or(def_id);
+        let tcx = self.env.tcx();
+        let name = self.env.get_item_name(def_id);
+        let mut error = PrustiError::incorrect(
+            format!("use of impure function \"{}\" in pure code is not allowed", name),
+            MultiSpan::from_span(call_span),
+        );
+        if let Some(help) = self.purity_help(def_id) {
+            error = error.set_help(help);
+        }
+        error = error.set_note("the function is defined here", tcx.def_span(def_id));
+        self.impure_calls.push((call_span, error));
+    }
+
+    /// Suggests to mark the function as `#[pure]`, if it could be.
+    fn purity_help(&self, def_id: DefId) -> Option<String> {
+        if !self.can_be_pure(def_id) {
+            return None;
+        }
+        let name = self.env.get_item_name(def_id);
+        Some(if def_id.is_local() {
+            format!("consider marking \"{}\" as `#[pure]`", name)
+        } else {
+            format!(
+                "consider marking \"{}\" as `#[pure]` in an external specification \
+                (`#[extern_spec]`)",
+                name,
+            )
+        })
+    }
+
+    /// Checks the call of `Deref::deref` of an implicit dereference of a value of type
+    /// `source_ty`, e.g. of `w` in `w.len()` when `w` is a smart pointer.
+    fn check_overloaded_deref(&mut self, span: Span, source_ty: ty::Ty<'tcx>) {
+        let deref_method = match self.env.get_deref_method() {
+            Some(deref_method) => deref_method,
+            None => return,
+        };
+        let tcx = self.env.tcx();
+        let substs = tcx.mk_substs_trait(source_ty, &[]);
+        if self.is_supported_callee(deref_method, substs) {
+            return;
+        }
+        let def_id = self.env.resolve_trait_method_call(deref_method, substs)
+            .unwrap_or(deref_method);
+        let mut error = PrustiError::incorrect(
+            format!(
+                "dereferencing `{}` in pure code uses the impure function \"{}\"",
+                source_ty,
+                self.env.get_item_name(def_id),
+            ),
+            MultiSpan::from_span(span),
+        );
+        if let Some(help) = self.purity_help(def_id) {
+            error = error.set_help(help);
+        }
+        error = error.set_note(
+            "the dereference calls the `deref` method implemented here",
+            tcx.def_span(def_id),
+        );
+        self.impure_calls.push((span, error));
+    }
+
+    fn report_closure_call(&mut self, call_span: Span) {
+        let error = PrustiError::incorrect(
+            "closures cannot be called in specifications".to_string(),
+            MultiSpan::from_span(call_span),
+        )
+        .set_help(
+            "the behavior of a closure can be specified with a specification entailment, \
+            e.g. `f |= |x: i32| [requires(..), ensures(..)]`"
+        );
+        self.impure_calls.push((call_span, error));
+    }
+}
+
+impl<'v, 'tcx> Visitor<'tcx> for CheckSpecCallsVisitor<'v, 'tcx> {
+    type Map = Map<'tcx>;
+
+    fn nested_visit_map(&mut self) -> intravisit::NestedVisitorMap<Self::Map> {
+        // Nested specification expressions are checked on their own
+        intravisit::NestedVisitorMap::None
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx hir::Expr<'tcx>) {
+        // The calls generated by macros such as `panic!` are not written by the user
+        let from_macro = ex.span.from_expansion() && matches!(
+            ex.span.ctxt().outer_expn_data().kind,
+            ExpnKind::Macro(MacroKind::Bang, _)
+        );
+        if !from_macro {
+            match ex.kind {
+                hir::ExprKind::Call(callee, _) => {
+                    match *self.typeck_results.expr_ty(callee).kind() {
+                        ty::TyKind::FnDef(def_id, substs) => self.check_call(ex.span, def_id, substs),
+                        _ => self.report_closure_call(ex.span),
+                    }
+                }
+                hir::ExprKind::MethodCall(..)
+                | hir::ExprKind::Binary(..)
+                | hir::ExprKind::Unary(..)
+                | hir::ExprKind::Index(..) => {
+                    // Operators are only method calls if they are overloaded
+                    if let Some(def_id) = self.typeck_results.type_dependent_def_id(ex.hir_id) {
+                        let substs = self.typeck_results.node_substs(ex.hir_id);
+                        self.check_call(ex.span, def_id, substs);
+                    }
+                }
+                _ => {}
+            }
+            // The implicit dereferences of smart pointers call `Deref::deref`
+            let mut source_ty = self.typeck_results.expr_ty(ex);
+            for adjustment in self.typeck_results.expr_adjustments(ex) {
+                if let Adjust::Deref(Some(_)) = adjustment.kind {
+                    self.check_overloaded_deref(ex.span, source_ty);
+                }
+                source_ty = adjustment.target;
+            }
+        }
+
+        intravisit::walk_expr(self, ex);
+    }
+}
+
+/// Trigger terms visitor: find the arithmetic operations in the body of a trigger term,
+/// which the verifier cannot match, and whether the term mentions any of the variables
+/// bound by its quantifier.
+struct CheckTriggerTermVisitor<'v, 'tcx> {
+    typeck_results: &'tcx ty::TypeckResults<'tcx>,
+    bound_vars: &'v HashSet<hir::HirId>,
+
+    /// Whether the visited expression is an operand of an arithmetic operation.
+    in_arithmetic: bool,
+    mentions_bound_var: bool,
+    arithmetic: Vec<Span>,
+}
+
+impl<'v, 'tcx> Visitor<'tcx> for CheckTriggerTermVisitor<'v, 'tcx> {
+    type Map = Map<'tcx>;
+
+    fn nested_visit_map(&mut self) -> intravisit::NestedVisitorMap<Self::Map> {
+        intravisit::NestedVisitorMap::None
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx hir::Expr<'tcx>) {
+        let is_arithmetic = match ex.kind {
+            hir::ExprKind::Binary(op, ..) => matches!(
+                op.node,
+                hir::BinOpKind::Add
+                    | hir::BinOpKind::Sub
+                    | hir::BinOpKind::Mul
+                    | hir::BinOpKind::Div
+                    | hir::BinOpKind::Rem
+                    | hir::BinOpKind::BitXor
+                    | hir::BinOpKind::BitAnd
+                    | hir::BinOpKind::BitOr
+                    | hir::BinOpKind::Shl
+                    | hir::BinOpKind::Shr
+            ) && self.typeck_results.expr_ty(ex).is_numeric(),
+            hir::ExprKind::Unary(hir::UnOp::Neg, _) => true,
+            hir::ExprKind::Path(hir::QPath::Resolved(None, path)) => {
+                if let Res::Local(hir_id) = path.res {
+                    self.mentions_bound_var |= self.bound_vars.contains(&hir_id);
+                }
+                false
+            }
+            _ => false,
+        };
+        // Only the outermost arithmetic operation of a term is reported
+        if is_arithmetic && !self.in_arithmetic {
+            self.arithmetic.push(ex.span);
+        }
+
+        let in_arithmetic = self.in_arithmetic;
+        self.in_arithmetic |= is_arithmetic;
+        intravisit::walk_expr(self, ex);
+        self.in_arithmetic = in_arithmetic;
+    }
+}
+
+/// Collects the trigger terms of the quantifiers of an assertion, including the ones
+/// of nested assertions.
+fn collect_trigger_terms<'a, 'tcx>(
+    assertion: &'a typed::Assertion<'tcx>,
+    terms: &mut Vec<&'a typed::Expression>,
+) {
+    match *assertion.kind {
+        typed::AssertionKind::And(ref assertions) => {
+            for assertion in assertions {
+                collect_trigger_terms(assertion, terms);
+            }
+        }
+        typed::AssertionKind::Implies(ref lhs, ref rhs) => {
+            collect_trigger_terms(lhs, terms);
+            collect_trigger_terms(rhs, terms);
+        }
+        typed::AssertionKind::TypeCond(_, ref body) => collect_trigger_terms(body, terms),
+        typed::AssertionKind::ForAll(_, ref trigger_set, ref body)
+        | typed::AssertionKind::Exists(_, ref trigger_set, ref body) => {
+            terms.extend(trigger_set.triggers().iter().flat_map(|t| t.terms()));
+            collect_trigger_terms(body, terms);
+        }
+        typed::AssertionKind::SpecEntailment { ref pres, ref posts, .. } => {
+            for assertion in pres.iter().chain(posts) {
+                collect_trigger_terms(assertion, terms);
+            }
+        }
+        typed::AssertionKind::Expr(_)
+        | typed::AssertionKind::Matches(..)
+        | typed::AssertionKind::Measure(_) => {}
+    }
+}
+
+/// Collects the expressions of an assertion, including the ones of nested assertions.
+pub fn collect_expressions<'a, 'tcx>(
+    assertion: &'a typed::Assertion<'tcx>,
+    expressions: &mut Vec<&'a typed::Expression>,
+) {
+    match *assertion.kind {
+        typed::AssertionKind::Expr(ref expression) => expressions.push(expression),
+        typed::AssertionKind::And(ref assertions) => {
+            for assertion in assertions {
+                collect_expressions(assertion, expressions);
+            }
+        }
+        typed::AssertionKind::Implies(ref lhs, ref rhs) => {
+            collect_expressions(lhs, expressions);
+            collect_expressions(rhs, expressions);
+        }
+        typed::AssertionKind::TypeCond(_, ref body) => collect_expressions(body, expressions),
+        typed::AssertionKind::ForAll(_, ref trigger_set, ref body)
+        | typed::AssertionKind::Exists(_, ref trigger_set, ref body) => {
+            expressions.extend(trigger_set.triggers().iter().flat_map(|t| t.terms()));
+            collect_expressions(body, expressions);
+        }
+        typed::AssertionKind::SpecEntailment { ref pres, ref posts, .. } => {
+            for assertion in pres.iter().chain(posts) {
+                collect_expressions(assertion, expressions);
+            }
+        }
+        typed::AssertionKind::Matches(ref expression, _) => expressions.push(expression),
+        typed::AssertionKind::Measure(ref components) => expressions.extend(components),
+    }
+}
+
+/// Returns the assertions of a specification set, together with the item that owns
+/// them. Loop invariants and ghost statements are owned by their function.
+fn spec_assertions<'a, 'tcx>(
+    tcx: TyCtxt<'tcx>,
+    local_id: LocalDefId,
+    spec_set: &'a typed::SpecificationSet<'tcx>,
+) -> Option<(LocalDefId, Vec<&'a typed::Assertion<'tcx>>)> {
+    let mut assertions = vec![];
+    let owner_id = match spec_set {
+        typed::SpecificationSet::Procedure(spec) => {
+            assertions.extend(spec.pres.iter());
+            assertions.extend(spec.posts.iter());
+            for pledge in &spec.pledges {
+                assertions.extend(pledge.lhs.iter());
+                assertions.push(&pledge.rhs);
+            }
+            assertions.extend(spec.predicate_body.iter());
+            local_id
+        }
+        typed::SpecificationSet::Loop(spec) => {
+            assertions.extend(spec.invariant.iter());
+            // Loop invariants are closures in the body of the function
+            let hir_id = tcx.hir().local_def_id_to_hir_id(local_id);
+            tcx.hir().local_def_id(tcx.hir().get_parent_item(hir_id))
+        }
+        typed::SpecificationSet::Ghost(spec) => {
+            assertions.push(&spec.assertion);
+            // Ghost statements are closures in the body of the function
+            let hir_id = tcx.hir().local_def_id_to_hir_id(local_id);
+            tcx.hir().local_def_id(tcx.hir().get_parent_item(hir_id))
+        }
+        typed::SpecificationSet::Struct(_) => return None,
+    };
+    Some((owner_id, assertions))
+}
+
 impl<'tcx> SpecChecker {
     pub fn new() -> Self {
         Self {
             predicates: HashMap::new(),
+            pred_spec_ids: HashMap::new(),
             pred_usages: Vec::new(),
+            recursive_predicates: Vec::new(),
+            unbounded_arithmetic: Vec::new(),
+            impure_calls: Vec::new(),
+            invalid_triggers: Vec::new(),
+            dead_specs: Vec::new(),
+            invalid_spec_eq_types: Vec::new(),
         }
     }
 
@@ -134,6 +762,7 @@ impl<'tcx> SpecChecker {
         let mut collect = CollectPredicatesVisitor {
             tcx,
             predicates: &mut self.predicates,
+            pred_spec_ids: &mut self.pred_spec_ids,
         };
         intravisit::walk_crate(&mut collect, krate);
 
@@ -144,8 +773,323 @@ impl<'tcx> SpecChecker {
         };
         intravisit::walk_crate(&mut visit, krate);
 
+        let mut calls = CollectPredicateCallsVisitor {
+            tcx,
+            predicates: &self.predicates,
+            pred_spec_ids: &self.pred_spec_ids,
+            current_predicate: None,
+            pred_calls: HashMap::new(),
+        };
+        intravisit::walk_crate(&mut calls, krate);
+
+        // Visit the predicates in the order of their definition, so that the errors
+        // are reported in a deterministic order
+        let mut predicates: Vec<_> = self.predicates.iter().collect();
+        predicates.sort_by_key(|&(_, span)| *span);
+        let mut visited = HashSet::new();
+        for (&def_id, _) in predicates {
+            calls.find_cycles(def_id, &mut vec![], &mut visited, &mut self.recursive_predicates);
+        }
+
         debug!("Predicate funcs: {:?}", self.predicates);
         debug!("Predicate usages: {:?}", self.pred_usages);
+        debug!("Predicate calls: {:?}", calls.pred_calls);
+        debug!("Recursive predicates: {:?}", self.recursive_predicates);
+    }
+
+    /// Look for comparisons in specifications that are trivially true or false because
+    /// the arithmetic in specifications is unbounded, which is only the case when overflow
+    /// checks are disabled. Functions annotated with
+    /// `#[allow_spec_warnings(unbounded_arithmetic)]` are skipped.
+    pub fn check_unbounded_arithmetic(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        def_spec: &typed::DefSpecificationMap<'tcx>,
+    ) {
+        if config::check_overflows() {
+            return;
+        }
+
+        for (local_id, spec_set) in def_spec.specs.iter() {
+            let (owner_id, assertions) = match spec_assertions(tcx, *local_id, spec_set) {
+                Some(owner_and_assertions) => owner_and_assertions,
+                None => continue,
+            };
+
+            let allowed_warnings = read_prusti_attrs(
+                "allow_spec_warning",
+                tcx.get_attrs(owner_id.to_def_id()),
+            );
+            if allowed_warnings.iter().any(|warning| warning == "unbounded_arithmetic") {
+                continue;
+            }
+
+            let mut expressions = vec![];
+            for assertion in assertions {
+                collect_expressions(assertion, &mut expressions);
+            }
+            for expression in expressions {
+                // The specifications of this crate only contain local expressions
+                let expr_id = expression.expr.expect_local();
+                let hir_id = tcx.hir().local_def_id_to_hir_id(expr_id);
+                let body = tcx.hir().body(tcx.hir().body_owned_by(hir_id));
+                let mut visit = CheckUnboundedArithmeticVisitor {
+                    tcx,
+                    typeck_results: tcx.typeck(expr_id),
+                    unbounded_arithmetic: &mut self.unbounded_arithmetic,
+                };
+                visit.visit_body(body);
+            }
+        }
+
+        // Report the warnings in a deterministic order
+        self.unbounded_arithmetic.sort();
+        self.unbounded_arithmetic.dedup();
+        debug!("Unbounded arithmetic in specifications: {:?}", self.unbounded_arithmetic);
+    }
+
+    /// Look for calls of functions that are not pure in all specifications, so that
+    /// all of them are reported before the encoding starts.
+    pub fn check_spec_calls(
+        &mut self,
+        env: &Environment<'tcx>,
+        def_spec: &typed::DefSpecificationMap<'tcx>,
+    ) {
+        let tcx = env.tcx();
+        for (local_id, spec_set) in def_spec.specs.iter() {
+            let assertions = match spec_assertions(tcx, *local_id, spec_set) {
+                Some((_, assertions)) => assertions,
+                None => continue,
+            };
+            let mut expressions = vec![];
+            for assertion in assertions {
+                collect_expressions(assertion, &mut expressions);
+            }
+            for expression in expressions {
+                let expr_id = expression.expr.expect_local();
+                let hir_id = tcx.hir().local_def_id_to_hir_id(expr_id);
+                let body = tcx.hir().body(tcx.hir().body_owned_by(hir_id));
+                let mut visit = CheckSpecCallsVisitor {
+                    env,
+                    def_spec,
+                    typeck_results: tcx.typeck(expr_id),
+                    impure_calls: &mut self.impure_calls,
+                };
+                visit.visit_body(body);
+            }
+        }
+
+        // Report the errors in a deterministic order
+        self.impure_calls.sort_by_key(|&(span, _)| span);
+        self.impure_calls.dedup_by_key(|&mut (span, _)| span);
+        debug!("Impure calls in specifications: {:?}", self.impure_calls.iter()
+            .map(|(span, _)| span).collect::<Vec<_>>());
+    }
+
+    /// Look for triggers that the verifier cannot use: trigger terms with arithmetic
+    /// operations, and trigger terms that do not mention any of the variables bound
+    /// by their quantifier. Triggers only matter for the verification, so they are
+    /// not checked when `no_verify` is enabled.
+    pub fn check_triggers(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        def_spec: &typed::DefSpecificationMap<'tcx>,
+    ) {
+        if config::no_verify() {
+            return;
+        }
+
+        for (local_id, spec_set) in def_spec.specs.iter() {
+            let assertions = match spec_assertions(tcx, *local_id, spec_set) {
+                Some((_, assertions)) => assertions,
+                None => continue,
+            };
+            let mut terms = vec![];
+            for assertion in assertions {
+                collect_trigger_terms(assertion, &mut terms);
+            }
+            for term in terms {
+                let term_id = term.expr.expect_local();
+                let hir_id = tcx.hir().local_def_id_to_hir_id(term_id);
+                // The closure of a trigger term is nested in the closure of its
+                // quantifier, whose parameters are the bound variables
+                let quantifier_id = tcx.hir().enclosing_body_owner(hir_id);
+                let quantifier_body = tcx.hir().body(tcx.hir().body_owned_by(quantifier_id));
+                let bound_vars: HashSet<_> = quantifier_body.params.iter()
+                    .map(|param| param.pat.hir_id)
+                    .collect();
+
+                let mut visit = CheckTriggerTermVisitor {
+                    typeck_results: tcx.typeck(term_id),
+                    bound_vars: &bound_vars,
+                    in_arithmetic: false,
+                    mentions_bound_var: false,
+                    arithmetic: vec![],
+                };
+                visit.visit_body(tcx.hir().body(tcx.hir().body_owned_by(hir_id)));
+
+                for span in visit.arithmetic {
+                    let error = PrustiError::incorrect(
+                        "arithmetic operations are not allowed in triggers".to_string(),
+                        MultiSpan::from_span(span),
+                    ).set_help(
+                        "the verifier cannot match terms with arithmetic operations; use \
+                        calls of pure functions on the quantified variables, e.g. `f(i)` \
+                        instead of `f(i + 1)`"
+                    );
+                    self.invalid_triggers.push((span, error));
+                }
+                if !visit.mentions_bound_var {
+                    let span = tcx.def_span(term_id);
+                    let error = PrustiError::incorrect(
+                        "the trigger term does not mention any of the quantified variables"
+                            .to_string(),
+                        MultiSpan::from_span(span),
+                    ).set_help(
+                        "the quantifier is instantiated with the values that the matched terms \
+                        give to the quantified variables, so each term of a trigger must \
+                        mention at least one of them"
+                    );
+                    self.invalid_triggers.push((span, error));
+                }
+            }
+        }
+
+        // Report the errors in a deterministic order
+        self.invalid_triggers.sort_by_key(|&(span, _)| span);
+        self.invalid_triggers.dedup_by_key(|&mut (span, _)| span);
+        debug!("Invalid triggers: {:?}", self.invalid_triggers.iter()
+            .map(|(span, _)| span).collect::<Vec<_>>());
+    }
+
+    /// Look for the types annotated with `#[derive_spec_eq]` whose `PartialEq`
+    /// implementation is not derived. The equality of the other annotated types is
+    /// encoded as the snapshot equality.
+    pub fn check_spec_eq_types(&mut self, env: &Environment<'tcx>) {
+        let mut visit = CheckSpecEqTypesVisitor {
+            env,
+            invalid_spec_eq_types: &mut self.invalid_spec_eq_types,
+        };
+        env.tcx().hir().krate().visit_all_item_likes(&mut visit);
+
+        // Report the errors in a deterministic order
+        self.invalid_spec_eq_types.sort_by_key(|&(span, _)| span);
+        debug!("Invalid spec equality types: {:?}", self.invalid_spec_eq_types.iter()
+            .map(|(span, _)| span).collect::<Vec<_>>());
+    }
+
+    /// Look for specifications that are never used, when `report_dead_specs` is
+    /// enabled: the specifications of trusted functions that are never called (and
+    /// thus never encoded), the predicates that are never referenced, and the external
+    /// specifications that do not resolve to any function. The items that can be used
+    /// from other crates, the trait methods and their implementations are skipped.
+    /// The specifications of items removed by `cfg` are removed with them, so they
+    /// cannot be found.
+    pub fn check_dead_specs(
+        &mut self,
+        env: &Environment<'tcx>,
+        def_spec: &typed::DefSpecificationMap<'tcx>,
+    ) {
+        if !config::report_dead_specs() {
+            return;
+        }
+
+        let tcx = env.tcx();
+        let mut visit = CollectReferencesVisitor {
+            tcx,
+            referenced: HashSet::new(),
+        };
+        intravisit::walk_crate(&mut visit, tcx.hir().krate());
+        let referenced = visit.referenced;
+
+        let is_executable = tcx.sess.crate_types().iter().all(|ty| *ty == CrateType::Executable);
+        let is_used = |def_id: DefId| {
+            referenced.contains(&def_id)
+                || (!is_executable && tcx.visibility(def_id) == ty::Visibility::Public)
+                || tcx.trait_of_item(def_id).is_some()
+                || tcx.impl_of_method(def_id)
+                    .map_or(false, |impl_id| tcx.trait_id_of_impl(impl_id).is_some())
+                || tcx.def_path_str(def_id) == "main"
+        };
+        let extern_spec_ids: HashSet<_> = def_spec.extern_specs.values().copied().collect();
+        let new_error = |message: String, span: Span| if config::deny_dead_specs() {
+            PrustiError::incorrect(message, MultiSpan::from_span(span))
+        } else {
+            PrustiError::warning(message, MultiSpan::from_span(span))
+        };
+
+        for (&def_id, &span) in self.predicates.iter() {
+            if !is_used(def_id) {
+                let error = new_error(
+                    format!("predicate \"{}\" is never used", env.get_item_name(def_id)),
+                    span,
+                ).set_help("remove the predicate if it is no longer needed");
+                self.dead_specs.push((span, error));
+            }
+        }
+
+        for (&local_id, spec_set) in def_spec.specs.iter() {
+            let spec = match spec_set {
+                typed::SpecificationSet::Procedure(spec) => spec,
+                _ => continue,
+            };
+            let def_id = local_id.to_def_id();
+            if !matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn) {
+                continue;
+            }
+            let span = env.get_item_span(def_id);
+            if has_extern_spec_attr(tcx.get_attrs(def_id)) {
+                if !extern_spec_ids.contains(&local_id) {
+                    let error = new_error(
+                        "the external specification does not specify any function".to_string(),
+                        span,
+                    ).set_help(
+                        "the body of an external specification must be a call of the \
+                        specified function"
+                    );
+                    self.dead_specs.push((span, error));
+                }
+            } else if spec.trusted && spec.predicate_body.is_none() && !is_used(def_id) {
+                let error = new_error(
+                    format!(
+                        "the specification of \"{}\" is never used, because the function \
+                        is trusted and never called",
+                        env.get_item_name(def_id),
+                    ),
+                    span,
+                ).set_help("remove the specification, or the function if it is no longer needed");
+                self.dead_specs.push((span, error));
+            }
+        }
+
+        // Report the warnings in a deterministic order
+        self.dead_specs.sort_by_key(|&(span, _)| span);
+        debug!("Dead specifications: {:?}", self.dead_specs.iter()
+            .map(|(span, _)| span).collect::<Vec<_>>());
+    }
+
+    pub fn report_dead_specs(&mut self, env: &Environment<'tcx>) {
+        for (_, error) in self.dead_specs.drain(..) {
+            error.emit(env);
+        }
+    }
+
+    pub fn report_spec_call_errors(&mut self, env: &Environment<'tcx>) {
+        for (_, error) in self.impure_calls.drain(..) {
+            error.emit(env);
+        }
+    }
+
+    pub fn report_trigger_errors(&mut self, env: &Environment<'tcx>) {
+        for (_, error) in self.invalid_triggers.drain(..) {
+            error.emit(env);
+        }
+    }
+
+    pub fn report_spec_eq_errors(&mut self, env: &Environment<'tcx>) {
+        for (_, error) in self.invalid_spec_eq_types.drain(..) {
+            error.emit(env);
+        }
     }
 
     pub fn report_errors(&self, env: &Environment<'tcx>) {
@@ -157,5 +1101,29 @@ impl<'tcx> SpecChecker {
             .set_note("this is a specification-only predicate function", def_span)
             .emit(env);
         }
+        for &(def_span, call_span) in &self.recursive_predicates {
+            PrustiError::incorrect(
+                "recursive predicates are not supported".to_string(),
+                MultiSpan::from_span(def_span),
+            )
+            .set_note("the predicate is called recursively here", call_span)
+            .emit(env);
+        }
+    }
+
+    pub fn report_warnings(&self, env: &Environment<'tcx>) {
+        for &(arithmetic_span, comparison_span) in &self.unbounded_arithmetic {
+            PrustiError::warning(
+                "this comparison is trivially true or false, because arithmetic in \
+                specifications cannot overflow when overflow checks are disabled".to_string(),
+                MultiSpan::from_span(comparison_span),
+            )
+            .set_note("this arithmetic expression can overflow at runtime", arithmetic_span)
+            .set_help(
+                "enable overflow checks with `check_overflows`, or suppress this warning with \
+                `#[allow_spec_warnings(unbounded_arithmetic)]`"
+            )
+            .emit(env);
+        }
     }
 }
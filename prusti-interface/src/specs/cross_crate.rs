@@ -0,0 +1,342 @@
+//! Export of the specifications of a library crate, and import of the
+//! specifications of the dependencies of the crate being verified.
+//!
+//! The spec items of a crate are compiled together with it and their MIR is
+//! encoded in its metadata, but the assertions that tie them to the specified
+//! procedures are only known while the crate is compiled. They are written to a
+//! `.prusti-specs` file next to the metadata of the crate, in which the items
+//! are identified by their index and the hash of their definition path. A
+//! crate that depends on it then uses the imported contracts at the calls,
+//! without verifying the dependency again.
+
+use super::{typed, SpecCollector};
+use super::typed::StructuralToTyped;
+use crate::environment::Environment;
+use crate::PrustiError;
+use prusti_specs::specifications::common::{SpecIdRef, SpecificationId};
+use prusti_specs::specifications::json::Assertion as JsonAssertion;
+use rustc_hir::def_id::{CrateNum, DefId, DefIndex, LOCAL_CRATE};
+use rustc_middle::ty::TyCtxt;
+use rustc_session::config::CrateType;
+use rustc_session::output::filename_for_metadata;
+use rustc_span::MultiSpan;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use log::debug;
+
+/// The version of the format of the exported specifications, to be increased
+/// whenever the format or the meaning of the assertions changes.
+const FORMAT_VERSION: u32 = 1;
+
+const SPECS_FILE_EXTENSION: &str = "prusti-specs";
+
+/// The first field of the exported specifications, which is read before the
+/// rest of the file because the format of the rest might be different.
+#[derive(Serialize, Deserialize)]
+struct FormatHeader {
+    format_version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CrateSpecs {
+    format_version: u32,
+    /// The hash of the crate the specifications were exported from, which
+    /// must match the hash of the crate found by the compiler.
+    crate_hash: u64,
+    procedures: Vec<ExportedProcedureSpec>,
+}
+
+/// The specification of a procedure. Pledges are not exported, so the callers
+/// in other crates cannot rely on them, and termination measures are only
+/// needed to verify the crate of the procedure.
+#[derive(Serialize, Deserialize)]
+struct ExportedProcedureSpec {
+    item: ExportedDefId,
+    pres: Vec<ExportedAssertion>,
+    posts: Vec<ExportedAssertion>,
+    predicate_body: Option<ExportedAssertion>,
+    pure: bool,
+    trusted: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedAssertion {
+    /// The assertion in the JSON format generated by prusti-specs.
+    json: String,
+    /// The spec items of the expressions of the assertion, keyed like the
+    /// typed expressions of the collector.
+    expressions: Vec<(String, ExportedDefId)>,
+    /// The variants tested by the `matches!` patterns of the assertion, which
+    /// can only be resolved in the crate that defines the patterns.
+    variants: Vec<(String, usize)>,
+}
+
+/// An item of the exporting crate.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct ExportedDefId {
+    index: u32,
+    def_path_hash: (u64, u64),
+}
+
+impl ExportedDefId {
+    fn new(tcx: TyCtxt, def_id: DefId) -> Self {
+        ExportedDefId {
+            index: def_id.index.as_u32(),
+            def_path_hash: tcx.def_path_hash(def_id).0.as_value(),
+        }
+    }
+
+    /// Finds the item in the crate `krate`, which must be the exporting crate.
+    fn resolve(&self, tcx: TyCtxt, krate: CrateNum) -> Option<DefId> {
+        let def_id = DefId { krate, index: DefIndex::from_u32(self.index) };
+        if tcx.def_path_hash(def_id).0.as_value() == self.def_path_hash {
+            Some(def_id)
+        } else {
+            None
+        }
+    }
+}
+
+pub(super) fn export_specs(collector: &SpecCollector, env: &Environment) {
+    let tcx = env.tcx();
+    if !tcx.sess.crate_types().contains(&CrateType::Rlib) {
+        return;
+    }
+
+    let mut procedures = vec![];
+    for (local_id, refs) in &collector.procedure_specs {
+        let def_id = local_id.to_def_id();
+        // The specifications of closures can only be used in their crate
+        if tcx.is_closure(def_id) {
+            continue;
+        }
+        let mut spec = ExportedProcedureSpec {
+            item: ExportedDefId::new(tcx, def_id),
+            pres: vec![],
+            posts: vec![],
+            predicate_body: None,
+            pure: refs.pure,
+            trusted: refs.trusted,
+        };
+        for spec_id_ref in &refs.spec_id_refs {
+            match spec_id_ref {
+                SpecIdRef::Precondition(spec_id) => {
+                    spec.pres.push(export_assertion(collector, spec_id));
+                }
+                SpecIdRef::Postcondition(spec_id) => {
+                    spec.posts.push(export_assertion(collector, spec_id));
+                }
+                SpecIdRef::Predicate(spec_id) => {
+                    spec.predicate_body = Some(export_assertion(collector, spec_id));
+                }
+                SpecIdRef::Pledge { .. } | SpecIdRef::Termination(_) => {}
+            }
+        }
+        procedures.push(spec);
+    }
+
+    let crate_specs = CrateSpecs {
+        format_version: FORMAT_VERSION,
+        crate_hash: tcx.crate_hash(LOCAL_CRATE).as_u64(),
+        procedures,
+    };
+    let path = filename_for_metadata(
+        tcx.sess,
+        &tcx.crate_name(LOCAL_CRATE).as_str(),
+        &tcx.output_filenames(()),
+    ).with_extension(SPECS_FILE_EXTENSION);
+    debug!("Exporting the specifications of {} procedures to {}", crate_specs.procedures.len(), path.display());
+    let result = serde_json::to_string(&crate_specs)
+        .map_err(|error| error.to_string())
+        .and_then(|json| fs::write(&path, json).map_err(|error| error.to_string()));
+    if let Err(error) = result {
+        PrustiError::internal(
+            format!("failed to export the specifications to {}: {}", path.display(), error),
+            MultiSpan::new(),
+        ).emit(env);
+    }
+}
+
+fn export_assertion(collector: &SpecCollector, spec_id: &SpecificationId) -> ExportedAssertion {
+    let tcx = collector.tcx;
+    let prefix = format!("{}_", spec_id);
+    let expressions = collector.typed_expressions.iter()
+        .filter(|(key, _)| key.starts_with(&prefix))
+        .map(|(key, &def_id)| (key.clone(), ExportedDefId::new(tcx, def_id)))
+        .collect();
+    let mut patterns = vec![];
+    collect_variant_patterns(&collector.typed_specs[spec_id], &mut patterns);
+    let variants = patterns.into_iter()
+        .filter_map(|pattern| pattern.variant_index.map(
+            |variant_index| (format!("{}_{}", pattern.spec_id, pattern.id), variant_index)
+        ))
+        .collect();
+    ExportedAssertion {
+        json: collector.json_specs[spec_id].clone(),
+        expressions,
+        variants,
+    }
+}
+
+/// Adds the specifications exported by the dependencies of the crate to
+/// `def_spec`. The dependencies that were not compiled by Prusti have no
+/// exported specifications and are skipped.
+pub(super) fn import_specs<'tcx>(
+    def_spec: &mut typed::DefSpecificationMap<'tcx>,
+    env: &Environment<'tcx>,
+) {
+    let tcx = env.tcx();
+    for &krate in tcx.crates(()) {
+        let source = tcx.used_crate_source(krate);
+        let specs_path = match source.rmeta.as_ref().or(source.rlib.as_ref()) {
+            Some((path, _)) => path.with_extension(SPECS_FILE_EXTENSION),
+            None => continue,
+        };
+        if !specs_path.exists() {
+            continue;
+        }
+        match import_crate_specs(&specs_path, tcx, krate) {
+            Ok(specs) => {
+                debug!("Imported {} specifications from {}", specs.len(), specs_path.display());
+                def_spec.imported_specs.extend(specs);
+            }
+            Err(error) => error.emit(env),
+        }
+    }
+}
+
+fn import_crate_specs<'tcx>(
+    path: &Path,
+    tcx: TyCtxt<'tcx>,
+    krate: CrateNum,
+) -> Result<Vec<(DefId, typed::SpecificationSet<'tcx>)>, PrustiError> {
+    let crate_name = tcx.crate_name(krate);
+    let mismatch_error = || PrustiError::incorrect(
+        format!(
+            "the specifications of the crate `{}` in {} do not match the compiled crate",
+            crate_name,
+            path.display(),
+        ),
+        MultiSpan::new(),
+    ).set_help(format!(
+        "the crate `{}` might have been compiled without Prusti after the \
+        specifications were exported; compile it again with `cargo prusti`",
+        crate_name,
+    ));
+
+    let content = fs::read_to_string(path).map_err(|error| PrustiError::internal(
+        format!("failed to read the specifications of the crate `{}`: {}", crate_name, error),
+        MultiSpan::new(),
+    ))?;
+    let header: FormatHeader = serde_json::from_str(&content).map_err(|_| mismatch_error())?;
+    if header.format_version != FORMAT_VERSION {
+        return Err(PrustiError::incorrect(
+            format!(
+                "the specifications of the crate `{}` were exported by an incompatible \
+                version of Prusti (format version {}, expected {})",
+                crate_name,
+                header.format_version,
+                FORMAT_VERSION,
+            ),
+            MultiSpan::new(),
+        ).set_help(format!(
+            "compile the crate `{}` again with this version of Prusti",
+            crate_name,
+        )));
+    }
+    let crate_specs: CrateSpecs = serde_json::from_str(&content).map_err(|_| mismatch_error())?;
+    if crate_specs.crate_hash != tcx.crate_hash(krate).as_u64() {
+        return Err(mismatch_error());
+    }
+
+    let import_assertion = |assertion: &ExportedAssertion| -> Option<typed::Assertion<'tcx>> {
+        let mut typed_expressions = HashMap::new();
+        for (key, item) in &assertion.expressions {
+            typed_expressions.insert(key.clone(), item.resolve(tcx, krate)?);
+        }
+        let mut typed_assertion = JsonAssertion::from_json_string(&assertion.json)
+            .to_typed(&typed_expressions, tcx);
+        let variants = assertion.variants.iter().cloned().collect();
+        restore_variant_indices(&mut typed_assertion, &variants);
+        Some(typed_assertion)
+    };
+    let mut specs = vec![];
+    for procedure in &crate_specs.procedures {
+        let import_all = |assertions: &[ExportedAssertion]| -> Option<Vec<_>> {
+            assertions.iter().map(|assertion| import_assertion(assertion)).collect()
+        };
+        let def_id = procedure.item.resolve(tcx, krate).ok_or_else(mismatch_error)?;
+        let pres = import_all(&procedure.pres).ok_or_else(mismatch_error)?;
+        let posts = import_all(&procedure.posts).ok_or_else(mismatch_error)?;
+        let predicate_body = match procedure.predicate_body {
+            Some(ref body) => Some(import_assertion(body).ok_or_else(mismatch_error)?),
+            None => None,
+        };
+        specs.push((def_id, typed::SpecificationSet::Procedure(typed::ProcedureSpecification {
+            pres,
+            posts,
+            pledges: vec![],
+            predicate_body,
+            decreases: None,
+            pure: procedure.pure,
+            trusted: procedure.trusted,
+        })));
+    }
+    Ok(specs)
+}
+
+fn collect_variant_patterns<'a, 'tcx>(
+    assertion: &'a typed::Assertion<'tcx>,
+    patterns: &mut Vec<&'a typed::VariantPattern>,
+) {
+    match *assertion.kind {
+        typed::AssertionKind::Matches(_, ref pattern) => patterns.push(pattern),
+        typed::AssertionKind::And(ref assertions) => {
+            for assertion in assertions {
+                collect_variant_patterns(assertion, patterns);
+            }
+        }
+        typed::AssertionKind::Implies(ref lhs, ref rhs) => {
+            collect_variant_patterns(lhs, patterns);
+            collect_variant_patterns(rhs, patterns);
+        }
+        typed::AssertionKind::TypeCond(_, ref body)
+        | typed::AssertionKind::ForAll(_, _, ref body)
+        | typed::AssertionKind::Exists(_, _, ref body) => collect_variant_patterns(body, patterns),
+        typed::AssertionKind::SpecEntailment { ref pres, ref posts, .. } => {
+            for assertion in pres.iter().chain(posts) {
+                collect_variant_patterns(assertion, patterns);
+            }
+        }
+        typed::AssertionKind::Expr(_) | typed::AssertionKind::Measure(_) => {}
+    }
+}
+
+fn restore_variant_indices(assertion: &mut typed::Assertion, variants: &HashMap<String, usize>) {
+    match *assertion.kind {
+        typed::AssertionKind::Matches(_, ref mut pattern) => {
+            let key = format!("{}_{}", pattern.spec_id, pattern.id);
+            pattern.variant_index = variants.get(&key).cloned();
+        }
+        typed::AssertionKind::And(ref mut assertions) => {
+            for assertion in assertions {
+                restore_variant_indices(assertion, variants);
+            }
+        }
+        typed::AssertionKind::Implies(ref mut lhs, ref mut rhs) => {
+            restore_variant_indices(lhs, variants);
+            restore_variant_indices(rhs, variants);
+        }
+        typed::AssertionKind::TypeCond(_, ref mut body)
+        | typed::AssertionKind::ForAll(_, _, ref mut body)
+        | typed::AssertionKind::Exists(_, _, ref mut body) => restore_variant_indices(body, variants),
+        typed::AssertionKind::SpecEntailment { ref mut pres, ref mut posts, .. } => {
+            for assertion in pres.iter_mut().chain(posts) {
+                restore_variant_indices(assertion, variants);
+            }
+        }
+        typed::AssertionKind::Expr(_) | typed::AssertionKind::Measure(_) => {}
+    }
+}
@@ -1,10 +1,10 @@
 use rustc_hir::intravisit::{self, Visitor};
 use rustc_hir::def_id::DefId;
 use rustc_middle::hir::map::Map;
-use rustc_middle::ty::TyCtxt;
+use rustc_middle::ty::{self, subst::{GenericArgKind, Subst, SubstsRef}, TyCtxt};
 use rustc_span::{Span, MultiSpan};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use crate::environment::Environment;
 use crate::PrustiError;
 
@@ -22,6 +22,16 @@ pub struct ExternSpecResolver<'tcx> {
     /// Duplicate specifications detected, keyed by the `DefId` of the function
     /// to be specified.
     spec_duplicates: HashMap<DefId, Vec<(DefId, Span)>>,
+
+    /// Specifications whose signature does not match the signature of the
+    /// specified function, as pairs of the `DefId` of the real function and
+    /// the `DefId` of the specification.
+    signature_mismatches: Vec<(DefId, DefId)>,
+
+    /// Specifications whose generic parameters do not correspond to the ones
+    /// of the specified function, as pairs of the `DefId` of the real function
+    /// and the `DefId` of the specification.
+    generics_mismatches: Vec<(DefId, DefId)>,
 }
 
 impl<'tcx> ExternSpecResolver<'tcx> {
@@ -30,6 +40,8 @@ impl<'tcx> ExternSpecResolver<'tcx> {
             tcx: tcx,
             extern_fn_map: HashMap::new(),
             spec_duplicates: HashMap::new(),
+            signature_mismatches: Vec::new(),
+            generics_mismatches: Vec::new(),
         }
     }
 
@@ -54,7 +66,12 @@ impl<'tcx> ExternSpecResolver<'tcx> {
         };
         visitor.visit_fn(fn_kind, fn_decl, body_id, span, id);
         let current_def_id = self.tcx.hir().local_def_id(id).to_def_id();
-        if let Some((def_id, impl_ty, span)) = visitor.spec_found {
+        if let Some((def_id, impl_ty, span, substs)) = visitor.spec_found {
+            if !self.has_matching_generics(current_def_id, def_id, substs) {
+                self.generics_mismatches.push((def_id, current_def_id));
+            } else if !self.has_matching_signature(current_def_id, def_id, substs) {
+                self.signature_mismatches.push((def_id, current_def_id));
+            }
             match self.extern_fn_map.get(&def_id) {
                 Some((existing_impl_ty, _)) if existing_impl_ty == &impl_ty => {
                     match self.spec_duplicates.get_mut(&def_id) {
@@ -74,16 +91,116 @@ impl<'tcx> ExternSpecResolver<'tcx> {
         }
     }
 
+    /// Checks that the signature of the specification is the signature of the
+    /// specified function, instantiated with the generic arguments of the call
+    /// in the body of the specification. The call alone does not ensure this,
+    /// because the arguments and the result of the call can be coerced (e.g.
+    /// from `&mut T` to `&T`).
+    fn has_matching_signature(
+        &self,
+        spec_def_id: DefId,
+        real_def_id: DefId,
+        substs: SubstsRef<'tcx>,
+    ) -> bool {
+        let param_env = self.tcx.param_env(spec_def_id);
+        let normalize = |sig: ty::PolyFnSig<'tcx>| {
+            let sig = self.tcx.erase_late_bound_regions(sig);
+            self.tcx.normalize_erasing_regions(param_env, sig)
+        };
+        let spec_sig = normalize(self.tcx.fn_sig(spec_def_id));
+        let real_sig = normalize(self.tcx.fn_sig(real_def_id).subst(self.tcx, substs));
+        spec_sig.inputs() == real_sig.inputs() && spec_sig.output() == real_sig.output()
+    }
+
+    /// Checks that each generic parameter of the specification is passed to the
+    /// generic parameter of the specified function with the same name and
+    /// position, e.g. `T` to `T` in `impl<T> Option<T>`. At the call sites, the
+    /// specification is instantiated with the generic arguments of the call of
+    /// the specified function, so its parameters must be the same. Parameters
+    /// of the specified function can also be instantiated with concrete types,
+    /// as in `impl Vec<i32>`. The generics of trait methods are resolved at the
+    /// call sites and are not checked.
+    fn has_matching_generics(
+        &self,
+        spec_def_id: DefId,
+        real_def_id: DefId,
+        substs: SubstsRef<'tcx>,
+    ) -> bool {
+        if self.tcx.trait_of_item(real_def_id).is_some() {
+            return true;
+        }
+        let real_params = ty::List::identity_for_item(self.tcx, real_def_id);
+        if real_params.len() != substs.len() {
+            return false;
+        }
+        let mut passed_params = HashSet::new();
+        for (real_param, arg) in real_params.iter().zip(substs.iter()) {
+            if let GenericArgKind::Type(ty) = arg.unpack() {
+                if let ty::TyKind::Param(_) = ty.kind() {
+                    if arg != real_param {
+                        return false;
+                    }
+                    passed_params.insert(arg);
+                }
+            }
+        }
+        ty::List::identity_for_item(self.tcx, spec_def_id)
+            .iter()
+            .filter(|arg| matches!(arg.unpack(), GenericArgKind::Type(_)))
+            .all(|arg| passed_params.contains(&arg))
+    }
+
     /// Report errors for duplicate specifications found during specification
     /// collection.
     pub fn check_duplicates(&self, env: &Environment<'tcx>) {
         for (&def_id, specs) in self.spec_duplicates.iter() {
             let function_name = env.get_item_name(def_id);
-            PrustiError::incorrect(
+            let mut error = PrustiError::incorrect(
                 format!("duplicate specification for {}", function_name),
                 MultiSpan::from_spans(specs.iter()
                     .map(|s| s.1)
                     .collect())
+            );
+            if let Some((_, first_spec_id)) = self.extern_fn_map.get(&def_id) {
+                error = error.set_note(
+                    "the first specification is here",
+                    env.get_item_span(*first_spec_id),
+                );
+            }
+            error.emit(env);
+        }
+    }
+
+    /// Report errors for specifications whose signature does not match the
+    /// signature of the specified function.
+    pub fn check_signatures(&self, env: &Environment<'tcx>) {
+        for &(real_def_id, spec_def_id) in self.signature_mismatches.iter() {
+            PrustiError::incorrect(
+                format!(
+                    "the signature of the external specification does not match the \
+                    signature of {}",
+                    env.get_item_name(real_def_id),
+                ),
+                MultiSpan::from_span(env.get_item_span(spec_def_id)),
+            ).set_note(
+                "the specified function is declared here",
+                env.tcx().def_span(real_def_id),
+            ).emit(env);
+        }
+        for &(real_def_id, spec_def_id) in self.generics_mismatches.iter() {
+            PrustiError::incorrect(
+                format!(
+                    "the generic parameters of the external specification do not match \
+                    the generic parameters of {}",
+                    env.get_item_name(real_def_id),
+                ),
+                MultiSpan::from_span(env.get_item_span(spec_def_id)),
+            ).set_help(
+                "declare the same generic parameters as the specified function, with the \
+                same names and in the same order",
+            ).set_note(
+                "the specified function is declared here",
+                env.tcx().def_span(real_def_id),
             ).emit(env);
         }
     }
@@ -97,7 +214,7 @@ impl<'tcx> ExternSpecResolver<'tcx> {
 /// accomplished by a nested match rather than a full visitor?
 struct ExternSpecVisitor<'tcx> {
     tcx: TyCtxt<'tcx>,
-    spec_found: Option<(DefId, Option<DefId>, Span)>,
+    spec_found: Option<(DefId, Option<DefId>, Span, SubstsRef<'tcx>)>,
 }
 
 /// Gets the `DefId` from the given path.
@@ -128,9 +245,11 @@ impl<'tcx> Visitor<'tcx> for ExternSpecVisitor<'tcx> {
         }
         if let rustc_hir::ExprKind::Call(ref callee_expr, ref _arguments) = ex.kind {
             if let rustc_hir::ExprKind::Path(ref qself) = callee_expr.kind {
-                let res = self.tcx.typeck(callee_expr.hir_id.owner).qpath_res(qself, callee_expr.hir_id);
+                let typeck_results = self.tcx.typeck(callee_expr.hir_id.owner);
+                let res = typeck_results.qpath_res(qself, callee_expr.hir_id);
                 if let rustc_hir::def::Res::Def(_, def_id) = res {
-                    self.spec_found = Some((def_id, get_impl_type(qself), ex.span));
+                    let substs = typeck_results.node_substs(callee_expr.hir_id);
+                    self.spec_found = Some((def_id, get_impl_type(qself), ex.span, substs));
                     return;
                 }
             }
@@ -18,6 +18,7 @@ use log::debug;
 pub mod external;
 pub mod typed;
 pub mod checker;
+mod cross_crate;
 
 use typed::StructuralToTyped;
 use typed::SpecIdRef;
@@ -29,7 +30,8 @@ struct SpecItem {
     spec_id: typed::SpecificationId,
     #[allow(dead_code)]
     spec_type: SpecType,
-    specification: JsonAssertion,
+    /// The assertion, serialized as JSON.
+    specification: String,
 }
 
 impl fmt::Debug for SpecItem {
@@ -57,14 +59,19 @@ pub struct SpecCollector<'tcx> {
     /// Collected assertions before deserialisation.
     spec_items: Vec<SpecItem>,
 
-    typed_expressions: HashMap<String, LocalDefId>,
+    typed_expressions: HashMap<String, DefId>,
 
     /// Collected, deserialised assertions, keyed by their specification id.
     typed_specs: typed::SpecificationMap<'tcx>,
 
+    /// Collected assertions serialized as JSON, keyed by their specification id.
+    json_specs: HashMap<SpecificationId, String>,
+
     /// Resolved specifications.
     procedure_specs: HashMap<LocalDefId, ProcedureSpecRef>,
     loop_specs: HashMap<LocalDefId, Vec<SpecificationId>>,
+    ghost_specs: HashMap<LocalDefId, (SpecType, SpecificationId)>,
+    struct_specs: HashMap<LocalDefId, Vec<(SpecType, SpecificationId)>>,
 }
 
 impl<'tcx> SpecCollector<'tcx> {
@@ -73,8 +80,11 @@ impl<'tcx> SpecCollector<'tcx> {
             tcx: tcx,
             spec_items: Vec::new(),
             typed_specs: HashMap::new(),
+            json_specs: HashMap::new(),
             procedure_specs: HashMap::new(),
             loop_specs: HashMap::new(),
+            ghost_specs: HashMap::new(),
+            struct_specs: HashMap::new(),
             typed_expressions: HashMap::new(),
             extern_resolver: ExternSpecResolver::new(tcx),
         }
@@ -82,32 +92,41 @@ impl<'tcx> SpecCollector<'tcx> {
 
     fn prepare_typed_procedure_specs(&mut self) {
         let spec_items = std::mem::replace(&mut self.spec_items, vec![]);
-        self.typed_specs = spec_items
-            .into_iter()
-            .map(|spec_item| {
-                let assertion = reconstruct_typed_assertion(
-                    spec_item.specification,
-                    &self.typed_expressions,
-                    self.tcx
-                );
-                (spec_item.spec_id, assertion)
-            })
-            .collect()
+        for spec_item in spec_items {
+            let assertion = reconstruct_typed_assertion(
+                JsonAssertion::from_json_string(&spec_item.specification),
+                &self.typed_expressions,
+                self.tcx
+            );
+            self.typed_specs.insert(spec_item.spec_id, assertion);
+            self.json_specs.insert(spec_item.spec_id, spec_item.specification);
+        }
     }
 
-    pub fn build_def_specs(mut self, env: &Environment<'tcx>) -> typed::DefSpecificationMap<'tcx> {
+    pub fn build_def_specs(&mut self, env: &Environment<'tcx>) -> typed::DefSpecificationMap<'tcx> {
         self.prepare_typed_procedure_specs();
 
         let mut def_spec = typed::DefSpecificationMap::new();
         self.determine_procedure_specs(&mut def_spec);
         self.determine_extern_specs(&mut def_spec, env);
         self.determine_loop_specs(&mut def_spec);
+        self.determine_ghost_specs(&mut def_spec);
         self.determine_struct_specs(&mut def_spec);
+        cross_crate::import_specs(&mut def_spec, env);
         def_spec
     }
 
+    /// Writes the specifications of the procedures of the crate next to its
+    /// metadata, so that they can be used to verify the crates that depend on
+    /// it. This does nothing if the crate is not a library. Must be called
+    /// after [build_def_specs].
+    pub fn export_specs(&self, env: &Environment<'tcx>) {
+        cross_crate::export_specs(self, env);
+    }
+
     fn determine_extern_specs(&self, def_spec: &mut typed::DefSpecificationMap<'tcx>, env: &Environment<'tcx>) {
         self.extern_resolver.check_duplicates(env);
+        self.extern_resolver.check_signatures(env);
         // TODO: do something with the traits
         for (real_id, (_, spec_id)) in self.extern_resolver.extern_fn_map.iter() {
             if let Some(local_id) = real_id.as_local() {
@@ -116,6 +135,9 @@ impl<'tcx> SpecCollector<'tcx> {
                         format!("external specification provided for {}, which already has a specification",
                             env.get_item_name(*real_id)),
                         MultiSpan::from_span(env.get_item_span(*spec_id)),
+                    ).set_note(
+                        "the existing specification is attached here",
+                        env.get_item_span(*real_id),
                     ).emit(env);
                 }
             }
@@ -131,6 +153,7 @@ impl<'tcx> SpecCollector<'tcx> {
             let mut posts = Vec::new();
             let mut pledges = Vec::new();
             let mut predicate_body = None;
+            let mut decreases = None;
             for spec_id_ref in &refs.spec_id_refs {
                 match spec_id_ref {
                     SpecIdRef::Precondition(spec_id) => {
@@ -149,6 +172,9 @@ impl<'tcx> SpecCollector<'tcx> {
                     SpecIdRef::Predicate(spec_id) => {
                         predicate_body = Some(self.typed_specs.get(&spec_id).unwrap().clone());
                     }
+                    SpecIdRef::Termination(spec_id) => {
+                        decreases = Some(self.typed_specs.get(&spec_id).unwrap().clone());
+                    }
                 }
             }
             def_spec.specs.insert(
@@ -158,6 +184,7 @@ impl<'tcx> SpecCollector<'tcx> {
                     posts,
                     pledges,
                     predicate_body,
+                    decreases,
                     pure: refs.pure,
                     trusted: refs.trusted,
                 })
@@ -167,17 +194,36 @@ impl<'tcx> SpecCollector<'tcx> {
 
     fn determine_loop_specs(&self, def_spec: &mut typed::DefSpecificationMap<'tcx>) {
         for (local_id, spec_ids) in self.loop_specs.iter() {
-            let specs = spec_ids.iter()
+            let (variants, invariants): (Vec<_>, Vec<_>) = spec_ids.iter()
                 .map(|spec_id| self.typed_specs.get(&spec_id).unwrap().clone())
-                .collect();
+                .partition(|spec| matches!(*spec.kind, typed::AssertionKind::Measure(_)));
             def_spec.specs.insert(*local_id, typed::SpecificationSet::Loop(typed::LoopSpecification {
-                invariant: specs
+                invariant: invariants,
+                variant: variants.into_iter().next(),
             }));
         }
     }
 
-    // TODO: struct specs
-    fn determine_struct_specs(&self, _def_spec: &mut typed::DefSpecificationMap<'tcx>) {}
+    fn determine_ghost_specs(&self, def_spec: &mut typed::DefSpecificationMap<'tcx>) {
+        for (local_id, (spec_type, spec_id)) in self.ghost_specs.iter() {
+            def_spec.specs.insert(*local_id, typed::SpecificationSet::Ghost(typed::Specification {
+                typ: *spec_type,
+                assertion: self.typed_specs.get(&spec_id).unwrap().clone(),
+            }));
+        }
+    }
+
+    fn determine_struct_specs(&self, def_spec: &mut typed::DefSpecificationMap<'tcx>) {
+        for (local_id, spec_ids) in self.struct_specs.iter() {
+            let invariants = spec_ids.iter()
+                .map(|(spec_type, spec_id)| typed::Specification {
+                    typ: *spec_type,
+                    assertion: self.typed_specs.get(&spec_id).unwrap().clone(),
+                })
+                .collect();
+            def_spec.specs.insert(*local_id, typed::SpecificationSet::Struct(invariants));
+        }
+    }
 }
 
 fn get_procedure_spec_ids(def_id: DefId, attrs: &[ast::Attribute]) -> Option<ProcedureSpecRef> {
@@ -220,6 +266,11 @@ fn get_procedure_spec_ids(def_id: DefId, attrs: &[ast::Attribute]) -> Option<Pro
             |raw_spec_id| SpecIdRef::Predicate(parse_spec_id(raw_spec_id))
         )
     );
+    spec_id_refs.extend(
+        read_prusti_attr("decreases_spec_id_ref", attrs).map(
+            |raw_spec_id| SpecIdRef::Termination(parse_spec_id(raw_spec_id))
+        )
+    );
     debug!("Function {:?} has specification ids {:?}", def_id, spec_id_refs);
 
     let pure = has_prusti_attr(attrs, "pure");
@@ -238,16 +289,15 @@ fn get_procedure_spec_ids(def_id: DefId, attrs: &[ast::Attribute]) -> Option<Pro
 
 fn reconstruct_typed_assertion<'tcx>(
     assertion: JsonAssertion,
-    typed_expressions: &HashMap<String, LocalDefId>,
+    typed_expressions: &HashMap<String, DefId>,
     tcx: TyCtxt<'tcx>
 ) -> typed::Assertion<'tcx> {
     assertion.to_typed(typed_expressions, tcx)
 }
 
-fn deserialize_spec_from_attrs(attrs: &[ast::Attribute]) -> JsonAssertion {
-    let json_string = read_prusti_attr("assertion", attrs)
-        .expect("could not find prusti::assertion");
-    JsonAssertion::from_json_string(&json_string)
+fn read_spec_from_attrs(attrs: &[ast::Attribute]) -> String {
+    read_prusti_attr("assertion", attrs)
+        .expect("could not find prusti::assertion")
 }
 
 impl<'tcx> intravisit::Visitor<'tcx> for SpecCollector<'tcx> {
@@ -258,6 +308,32 @@ impl<'tcx> intravisit::Visitor<'tcx> for SpecCollector<'tcx> {
         intravisit::NestedVisitorMap::All(map)
     }
 
+    fn visit_item(
+        &mut self,
+        item: &'tcx rustc_hir::Item,
+    ) {
+        intravisit::walk_item(self, item);
+
+        // Collect type invariants and history invariants
+        if matches!(item.kind, ItemKind::Struct(..) | ItemKind::Enum(..)) {
+            let attrs = self.tcx.hir().attrs(item.hir_id());
+            let mut spec_ids = vec![];
+            for &(attr_name, spec_type) in &[
+                ("inv_spec_id_ref", SpecType::Invariant),
+                ("hist_inv_spec_id_ref", SpecType::HistoryInvariant),
+            ] {
+                spec_ids.extend(read_prusti_attrs(attr_name, attrs).into_iter().map(
+                    |raw_spec_id| (spec_type, raw_spec_id.try_into().expect(
+                        "failed conversion to SpecificationId"
+                    ))
+                ));
+            }
+            if !spec_ids.is_empty() {
+                self.struct_specs.insert(item.def_id, spec_ids);
+            }
+        }
+    }
+
     fn visit_trait_item(
         &mut self,
         ti: &'tcx rustc_hir::TraitItem,
@@ -301,14 +377,14 @@ impl<'tcx> intravisit::Visitor<'tcx> for SpecCollector<'tcx> {
 
         // Collect a typed expression
         if let Some(expr_id) = read_prusti_attr("expr_id", attrs) {
-            self.typed_expressions.insert(expr_id, local_id);
+            self.typed_expressions.insert(expr_id, def_id);
         }
 
         // Collect a specification id and its assertion
         if let Some(raw_spec_id) = read_prusti_attr("spec_id", attrs) {
             let spec_id: SpecificationId = raw_spec_id.try_into()
                 .expect("failed conversion to SpecificationId");
-            let specification = deserialize_spec_from_attrs(attrs);
+            let specification = read_spec_from_attrs(attrs);
 
             // Detect the kind of specification
             // FIXME: (minor) there is some redundancy here: the type of the
@@ -318,13 +394,17 @@ impl<'tcx> intravisit::Visitor<'tcx> for SpecCollector<'tcx> {
             // for postconditions and invariants.
             let spec_type = if has_prusti_attr(attrs, "loop_body_invariant_spec") {
                 SpecType::Invariant
+            } else if has_prusti_attr(attrs, "prusti_assertion_spec") {
+                SpecType::GhostAssertion
+            } else if has_prusti_attr(attrs, "prusti_assumption_spec") {
+                SpecType::GhostAssumption
             } else {
                 let fn_name = match fn_kind {
                     intravisit::FnKind::ItemFn(ref ident, ..) |
                     intravisit::FnKind::Method(ref ident, ..) => ident.name.to_ident_string(),
                     intravisit::FnKind::Closure => unreachable!(
-                        "a closure is annotated with prusti::spec_id but not with \
-                        prusti::loop_body_invariant_spec"
+                        "a closure is annotated with prusti::spec_id but is neither \
+                        a loop invariant nor a ghost statement"
                     ),
                 };
                 if fn_name.starts_with("prusti_pre_item_")
@@ -335,6 +415,12 @@ impl<'tcx> intravisit::Visitor<'tcx> for SpecCollector<'tcx> {
                     SpecType::Postcondition
                 } else if fn_name.starts_with("prusti_pred_item_") {
                     SpecType::Predicate
+                } else if fn_name.starts_with("prusti_term_item_") {
+                    SpecType::Termination
+                } else if fn_name.starts_with("prusti_inv_item_") {
+                    SpecType::Invariant
+                } else if fn_name.starts_with("prusti_hist_inv_item_") {
+                    SpecType::HistoryInvariant
                 } else {
                     unreachable!()
                 }
@@ -344,12 +430,17 @@ impl<'tcx> intravisit::Visitor<'tcx> for SpecCollector<'tcx> {
             self.spec_items.push(spec_item);
 
             // Collect loop invariant
-            if spec_type == SpecType::Invariant {
+            if has_prusti_attr(attrs, "loop_body_invariant_spec") {
                 self.loop_specs
                     .entry(local_id)
                     .or_insert(vec![])
                     .push(spec_id);
             }
+
+            // Collect ghost statements
+            if spec_type == SpecType::GhostAssertion || spec_type == SpecType::GhostAssumption {
+                self.ghost_specs.insert(local_id, (spec_type, spec_id));
+            }
         }
     }
 
@@ -1,6 +1,6 @@
 use prusti_specs::specifications::common;
 use prusti_specs::specifications::json;
-use rustc_hir::BodyId;
+use rustc_hir::{self as hir, BodyId};
 use rustc_hir::def_id::{DefId, LocalDefId};
 use rustc_middle::{mir, ty::{self, TyCtxt}};
 use rustc_span::Span;
@@ -11,36 +11,43 @@ use crate::data::ProcedureDefId;
 
 // FIXME: these comments are not terribly useful and are a copy of the untyped ones...
 /// A specification that has no types associated with it.
-pub type Specification<'tcx> = common::Specification<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type Specification<'tcx> = common::Specification<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
 /// A set of untyped specifications associated with a single element.
-pub type SpecificationSet<'tcx> = common::SpecificationSet<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type SpecificationSet<'tcx> = common::SpecificationSet<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
 /// A set of untyped specifications associated with a loop.
-pub type LoopSpecification<'tcx> = common::LoopSpecification<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type LoopSpecification<'tcx> = common::LoopSpecification<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
 /// A set of untyped specifications associated with a procedure.
-pub type ProcedureSpecification<'tcx> = common::ProcedureSpecification<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type ProcedureSpecification<'tcx> = common::ProcedureSpecification<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
 /// A map of untyped specifications for a specific crate.
 pub type SpecificationMap<'tcx> = HashMap<common::SpecificationId, Assertion<'tcx>>;
 /// An assertion that has no types associated with it.
-pub type Assertion<'tcx> = common::Assertion<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type Assertion<'tcx> = common::Assertion<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
 /// An assertion kind that has no types associated with it.
-pub type AssertionKind<'tcx> = common::AssertionKind<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type AssertionKind<'tcx> = common::AssertionKind<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
 /// An expression that has no types associated with it.
-pub type Expression = common::Expression<ExpressionId, LocalDefId>;
+pub type Expression = common::Expression<ExpressionId, DefId>;
 /// A trigger set that has no types associated with it.
-pub type TriggerSet = common::TriggerSet<ExpressionId, LocalDefId>;
+pub type TriggerSet = common::TriggerSet<ExpressionId, DefId>;
 /// For all variables that have no types associated with it.
 pub type ForAllVars<'tcx> = common::ForAllVars<ExpressionId, (mir::Local, ty::Ty<'tcx>)>;
 /// Specification entailment variables that have no types associated.
 pub type SpecEntailmentVars<'tcx> = common::SpecEntailmentVars<ExpressionId, (mir::Local, ty::Ty<'tcx>)>;
 /// A trigger that has no types associated with it.
-pub type Trigger = common::Trigger<ExpressionId, LocalDefId>;
+pub type Trigger = common::Trigger<ExpressionId, DefId>;
 /// A pledge in the postcondition.
-pub type Pledge<'tcx> = common::Pledge<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type Pledge<'tcx> = common::Pledge<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
+/// The pattern of an enum variant test, with the tested variant resolved.
+pub type VariantPattern = common::VariantPattern<ExpressionId, DefId>;
+/// The trait bounds of a type-conditional assertion.
+pub type TypeCondition = common::TypeCondition<ExpressionId, DefId>;
 
 /// A map of specifications keyed by crate-local DefIds.
 pub struct DefSpecificationMap<'tcx> {
     pub specs: HashMap<LocalDefId, SpecificationSet<'tcx>>,
     pub extern_specs: HashMap<DefId, LocalDefId>,
+    /// Specifications of the items of dependencies, which were exported when
+    /// the dependencies were compiled.
+    pub imported_specs: HashMap<DefId, SpecificationSet<'tcx>>,
 }
 
 impl<'tcx> DefSpecificationMap<'tcx> {
@@ -48,13 +55,16 @@ impl<'tcx> DefSpecificationMap<'tcx> {
         Self {
             specs: HashMap::new(),
             extern_specs: HashMap::new(),
+            imported_specs: HashMap::new(),
         }
     }
     pub fn get(&self, def_id: &DefId) -> Option<&SpecificationSet<'tcx>> {
         let id = if let Some(spec_id) = self.extern_specs.get(def_id) {
             *spec_id
+        } else if let Some(local_id) = def_id.as_local() {
+            local_id
         } else {
-            def_id.as_local()?
+            return self.imported_specs.get(def_id);
         };
         self.specs.get(&id)
     }
@@ -101,7 +111,8 @@ impl<'tcx> Spanned<'tcx> for Assertion<'tcx> {
                 spans.extend(rhs.get_spans(mir_body, tcx));
                 spans
             }
-            AssertionKind::ForAll(ref vars, ref trigger_set, ref body) => {
+            AssertionKind::ForAll(ref vars, ref trigger_set, ref body)
+            | AssertionKind::Exists(ref vars, ref trigger_set, ref body) => {
                 let mut spans = vars.get_spans(mir_body, tcx);
                 spans.extend(trigger_set
                     .triggers()
@@ -112,11 +123,7 @@ impl<'tcx> Spanned<'tcx> for Assertion<'tcx> {
                 spans.extend(body.get_spans(mir_body, tcx));
                 spans
             }
-            AssertionKind::TypeCond(ref vars, ref body) => {
-                let mut spans = vars.get_spans(mir_body, tcx);
-                spans.extend(body.get_spans(mir_body, tcx));
-                spans
-            }
+            AssertionKind::TypeCond(_, ref body) => body.get_spans(mir_body, tcx),
             AssertionKind::SpecEntailment {
                 ref closure,
                 ref pres,
@@ -134,27 +141,38 @@ impl<'tcx> Spanned<'tcx> for Assertion<'tcx> {
                     .collect::<Vec<Span>>());
                 spans
             }
+            AssertionKind::Matches(ref expression, ref pattern) => {
+                let mut spans = expression.get_spans(mir_body, tcx);
+                spans.push(tcx.def_span(pattern.test));
+                spans
+            }
+            AssertionKind::Measure(ref components) => {
+                components
+                    .iter()
+                    .flat_map(|component| component.get_spans(mir_body, tcx))
+                    .collect()
+            }
         }
     }
 }
 
 pub trait StructuralToTyped<'tcx, Target> {
-    fn to_typed(self, typed_expressions: &HashMap<String, LocalDefId>, tcx: TyCtxt<'tcx>) -> Target;
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, tcx: TyCtxt<'tcx>) -> Target;
 }
 
 impl<'tcx> StructuralToTyped<'tcx, Expression> for json::Expression {
-    fn to_typed(self, typed_expressions: &HashMap<String, LocalDefId>, _tcx: TyCtxt<'tcx>) -> Expression {
-        let local_id = typed_expressions[&format!("{}_{}", self.spec_id, self.expr_id)];
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, _tcx: TyCtxt<'tcx>) -> Expression {
+        let def_id = typed_expressions[&format!("{}_{}", self.spec_id, self.expr_id)];
         Expression {
             spec_id: self.spec_id,
             id: self.expr_id,
-            expr: local_id,
+            expr: def_id,
         }
     }
 }
 
 impl<'tcx> StructuralToTyped<'tcx, TriggerSet> for json::TriggerSet {
-    fn to_typed(self, typed_expressions: &HashMap<String, LocalDefId>, tcx: TyCtxt<'tcx>) -> TriggerSet {
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, tcx: TyCtxt<'tcx>) -> TriggerSet {
         common::TriggerSet(
             self.0
                 .into_iter()
@@ -165,7 +183,7 @@ impl<'tcx> StructuralToTyped<'tcx, TriggerSet> for json::TriggerSet {
 }
 
 impl<'tcx> StructuralToTyped<'tcx, Trigger> for json::Trigger {
-    fn to_typed(self, typed_expressions: &HashMap<String, LocalDefId>, tcx: TyCtxt<'tcx>) -> Trigger {
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, tcx: TyCtxt<'tcx>) -> Trigger {
         common::Trigger(
             self.0
                 .into_iter()
@@ -175,25 +193,21 @@ impl<'tcx> StructuralToTyped<'tcx, Trigger> for json::Trigger {
     }
 }
 
-impl<'tcx> StructuralToTyped<'tcx, ForAllVars<'tcx>> for json::ForAllVars {
-    fn to_typed(self, typed_expressions: &HashMap<String, LocalDefId>, tcx: TyCtxt<'tcx>) -> ForAllVars<'tcx> {
-        let local_id = typed_expressions[&format!("{}_{}", self.spec_id, self.expr_id)];
-        let (body, _) = tcx.mir_promoted(ty::WithOptConstParam::unknown(local_id));
-        let body = body.borrow();
-
-        // the first argument to the node is the closure itself and the
-        // following ones are the variables; therefore, we need to skip
-        // the first one
-        let vars: Vec<(mir::Local, ty::Ty)> = body
-            .args_iter()
-            .skip(1)
-            .map(|arg| (arg, body.local_decls
-                           .get(arg)
-                           .unwrap()
-                           .ty))
-            .collect();
+impl<'tcx> StructuralToTyped<'tcx, TypeCondition> for json::TypeCondition {
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, _tcx: TyCtxt<'tcx>) -> TypeCondition {
+        let def_id = typed_expressions[&format!("{}_{}", self.spec_id, self.expr_id)];
+        TypeCondition {
+            spec_id: self.spec_id,
+            id: self.expr_id,
+            item: def_id,
+        }
+    }
+}
 
-        assert!(body.arg_count-1 == self.count);
+impl<'tcx> StructuralToTyped<'tcx, ForAllVars<'tcx>> for json::ForAllVars {
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, tcx: TyCtxt<'tcx>) -> ForAllVars<'tcx> {
+        let def_id = typed_expressions[&format!("{}_{}", self.spec_id, self.expr_id)];
+        let vars = closure_args(def_id, tcx);
         assert_eq!(vars.len(), self.count);
         return ForAllVars {
             spec_id: self.spec_id,
@@ -204,35 +218,14 @@ impl<'tcx> StructuralToTyped<'tcx, ForAllVars<'tcx>> for json::ForAllVars {
 }
 
 impl<'tcx> StructuralToTyped<'tcx, SpecEntailmentVars<'tcx>> for json::SpecEntailmentVars {
-    fn to_typed(self, typed_expressions: &HashMap<String, LocalDefId>, tcx: TyCtxt<'tcx>) -> SpecEntailmentVars<'tcx> {
-        let local_pre_id = typed_expressions[&format!("{}_{}", self.spec_id, self.pre_expr_id)];
-        let local_post_id = typed_expressions[&format!("{}_{}", self.spec_id, self.post_expr_id)];
-        let (pre_body, _) = tcx.mir_promoted(ty::WithOptConstParam::unknown(local_pre_id));
-        let (post_body, _) = tcx.mir_promoted(ty::WithOptConstParam::unknown(local_post_id));
-        let pre_body = pre_body.borrow();
-        let post_body = post_body.borrow();
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, tcx: TyCtxt<'tcx>) -> SpecEntailmentVars<'tcx> {
+        let pre_id = typed_expressions[&format!("{}_{}", self.spec_id, self.pre_expr_id)];
+        let post_id = typed_expressions[&format!("{}_{}", self.spec_id, self.post_expr_id)];
+        let pre_args = closure_args(pre_id, tcx);
+        let post_args = closure_args(post_id, tcx);
 
-        let pre_args: Vec<(mir::Local, ty::Ty)> = pre_body
-            .args_iter()
-            .skip(1)
-            .map(|arg| (arg, pre_body.local_decls
-                                     .get(arg)
-                                     .unwrap()
-                                     .ty))
-            .collect();
-        let post_args: Vec<(mir::Local, ty::Ty)> = post_body
-            .args_iter()
-            .skip(1)
-            .map(|arg| (arg, post_body.local_decls
-                                      .get(arg)
-                                      .unwrap()
-                                      .ty))
-            .collect();
-
-        assert!(pre_body.arg_count - 1 == self.arg_count);
-        assert!(post_body.arg_count - 1 == self.arg_count + 1); // arguments + "result"
         assert_eq!(pre_args.len(), self.arg_count);
-        assert_eq!(post_args.len(), self.arg_count + 1);
+        assert_eq!(post_args.len(), self.arg_count + 1); // arguments + "result"
         return SpecEntailmentVars {
             spec_id: self.spec_id,
             pre_id: self.pre_expr_id,
@@ -243,8 +236,74 @@ impl<'tcx> StructuralToTyped<'tcx, SpecEntailmentVars<'tcx>> for json::SpecEntai
     }
 }
 
+impl<'tcx> StructuralToTyped<'tcx, VariantPattern> for json::VariantPattern {
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, tcx: TyCtxt<'tcx>) -> VariantPattern {
+        let def_id = typed_expressions[&format!("{}_{}", self.spec_id, self.expr_id)];
+        VariantPattern {
+            spec_id: self.spec_id,
+            id: self.expr_id,
+            test: def_id,
+            // The variants of the patterns of the dependencies cannot be resolved
+            // here; they are restored from the exported specifications.
+            variant_index: def_id.as_local().and_then(|test| resolve_variant_index(test, tcx)),
+        }
+    }
+}
+
+/// Returns the arguments of the closure of a quantifier or of a specification
+/// entailment, skipping the first argument, which is the closure itself.
+fn closure_args<'tcx>(closure: DefId, tcx: TyCtxt<'tcx>) -> Vec<(mir::Local, ty::Ty<'tcx>)> {
+    let collect_args = |body: &mir::Body<'tcx>| -> Vec<(mir::Local, ty::Ty<'tcx>)> {
+        body.args_iter()
+            .skip(1)
+            .map(|arg| (arg, body.local_decls[arg].ty))
+            .collect()
+    };
+    if let Some(local_id) = closure.as_local() {
+        let (body, _) = tcx.mir_promoted(ty::WithOptConstParam::unknown(local_id));
+        let body = body.borrow();
+        collect_args(&body)
+    } else {
+        collect_args(tcx.optimized_mir(closure))
+    }
+}
+
+/// Resolves the variant tested by the type-checked `matches!(expr, Pattern)`
+/// closure `test`. Returns `None` if the pattern is a binding, which matches
+/// every value, or if the matched type is not an ADT.
+fn resolve_variant_index(test: LocalDefId, tcx: TyCtxt) -> Option<usize> {
+    let hir_id = tcx.hir().local_def_id_to_hir_id(test);
+    let body = tcx.hir().body(tcx.hir().body_owned_by(hir_id));
+
+    // `matches!` expands to `match expr { Pattern => true, _ => false }`
+    let mut expr = &body.value;
+    while let hir::ExprKind::Block(block, _) = expr.kind {
+        expr = block.expr.expect("variant test without a match");
+    }
+    let pattern = match expr.kind {
+        hir::ExprKind::Match(_, arms, _) => arms[0].pat,
+        ref kind => unreachable!("variant test is not a match: {:?}", kind),
+    };
+    let qpath = match pattern.kind {
+        hir::PatKind::Path(ref qpath)
+        | hir::PatKind::TupleStruct(ref qpath, ..)
+        | hir::PatKind::Struct(ref qpath, ..) => qpath,
+        _ => return None,
+    };
+
+    let typeck_results = tcx.typeck(test);
+    match typeck_results.pat_ty(pattern).kind() {
+        ty::TyKind::Adt(adt_def, _) => {
+            let res = typeck_results.qpath_res(qpath, pattern.hir_id);
+            let variant = adt_def.variant_of_res(res);
+            Some(adt_def.variant_index_with_id(variant.def_id).as_usize())
+        }
+        _ => None,
+    }
+}
+
 impl<'tcx> StructuralToTyped<'tcx, AssertionKind<'tcx>> for json::AssertionKind {
-    fn to_typed(self, typed_expressions: &HashMap<String, LocalDefId>, tcx: TyCtxt<'tcx>) -> AssertionKind<'tcx> {
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, tcx: TyCtxt<'tcx>) -> AssertionKind<'tcx> {
         use json::AssertionKind::*;
         match self {
             Expr(expr) => AssertionKind::Expr(expr.to_typed(typed_expressions, tcx)),
@@ -257,11 +316,20 @@ impl<'tcx> StructuralToTyped<'tcx, AssertionKind<'tcx>> for json::AssertionKind
                 lhs.to_typed(typed_expressions, tcx),
                 rhs.to_typed(typed_expressions, tcx)
             ),
+            TypeCond(type_cond, body) => AssertionKind::TypeCond(
+                type_cond.to_typed(typed_expressions, tcx),
+                body.to_typed(typed_expressions, tcx)
+            ),
             ForAll(vars, body, triggers) => AssertionKind::ForAll(
                 vars.to_typed(typed_expressions, tcx),
                 triggers.to_typed(typed_expressions, tcx),
                 body.to_typed(typed_expressions, tcx),
             ),
+            Exists(vars, body, triggers) => AssertionKind::Exists(
+                vars.to_typed(typed_expressions, tcx),
+                triggers.to_typed(typed_expressions, tcx),
+                body.to_typed(typed_expressions, tcx),
+            ),
             SpecEntailment {closure, arg_binders, pres, posts} => AssertionKind::SpecEntailment {
                 closure: closure.to_typed(typed_expressions, tcx),
                 arg_binders: arg_binders.to_typed(typed_expressions, tcx),
@@ -272,12 +340,21 @@ impl<'tcx> StructuralToTyped<'tcx, AssertionKind<'tcx>> for json::AssertionKind
                     .map(|post| post.to_typed(typed_expressions, tcx))
                     .collect(),
             },
+            Matches(expr, pattern) => AssertionKind::Matches(
+                expr.to_typed(typed_expressions, tcx),
+                pattern.to_typed(typed_expressions, tcx),
+            ),
+            Measure(components) => AssertionKind::Measure(
+                components.into_iter()
+                    .map(|component| component.to_typed(typed_expressions, tcx))
+                    .collect()
+            ),
         }
     }
 }
 
 impl<'tcx> StructuralToTyped<'tcx, Assertion<'tcx>> for json::Assertion {
-    fn to_typed(self, typed_expressions: &HashMap<String, LocalDefId>, tcx: TyCtxt<'tcx>) -> Assertion<'tcx> {
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, tcx: TyCtxt<'tcx>) -> Assertion<'tcx> {
         Assertion {
             kind: box self.kind.to_typed(typed_expressions, tcx),
         }
@@ -11,6 +11,8 @@ use rustc_middle::ty::{self, TyCtxt};
 use rustc_index::vec::Idx;
 use std::collections::HashSet;
 use rustc_ast::ast;
+use rustc_span::{Span, symbol::sym};
+use rustc_span::hygiene::{AstPass, ExpnKind, MacroKind};
 use log::trace;
 
 /// Check if the place `potential_prefix` is a prefix of `place`. For example:
@@ -294,8 +296,26 @@ pub fn has_extern_spec_attr(attrs: &[ast::Attribute]) -> bool {
     has_prusti_attr(attrs, "extern_spec")
 }
 
+/// Check if the code at `span` has been generated by the test harness, i.e.
+/// by the `main` function of a `--test` build or by the expansion of `#[test]`.
+/// Such code is not written by the user, so it is not verified.
+pub fn is_test_harness_code(span: Span) -> bool {
+    match span.ctxt().outer_expn_data().kind {
+        ExpnKind::AstPass(AstPass::TestHarness) => true,
+        ExpnKind::Macro(MacroKind::Attr, name) => name == sym::test,
+        _ => false,
+    }
+}
+
 /// Read the value stored in a Prusti attribute (e.g. `prusti::<attr_name>="...")`.
 pub fn read_prusti_attrs(attr_name: &str, attrs: &[ast::Attribute]) -> Vec<String> {
+    read_prusti_attrs_with_spans(attr_name, attrs).into_iter()
+        .map(|(string, _)| string)
+        .collect()
+}
+
+/// Read the value stored in a Prusti attribute, together with the span of the attribute.
+pub fn read_prusti_attrs_with_spans(attr_name: &str, attrs: &[ast::Attribute]) -> Vec<(String, Span)> {
     let mut strings = vec![];
     for attr in attrs {
         if let ast::AttrKind::Normal(ast::AttrItem {
@@ -324,7 +344,7 @@ pub fn read_prusti_attrs(attr_name: &str, attrs: &[ast::Attribute]) -> Vec<Strin
                     x => unreachable!("{:?}", x),
                 }
             }
-            strings.push(extract_string(token));
+            strings.push((extract_string(token), attr.span));
         };
     }
     strings
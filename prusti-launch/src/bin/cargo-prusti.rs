@@ -4,7 +4,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::SystemTime;
 use prusti_launch::get_rust_toolchain_channel;
 
 fn main(){
@@ -26,23 +29,162 @@ fn process<I>(args: I) -> Result<(), i32>
 
     // Remove the leading "prusti" argument when `cargo-prusti` is invocated
     // as `cargo prusti` (note the space)
-    let clean_args = args.skip_while(|x| x == "prusti");
+    let mut clean_args: Vec<String> = args.skip_while(|x| x == "prusti").collect();
 
+    // With `--diff`, verify only the functions in the files that changed
+    // since the last successful run.
+    let diff_mode = clean_args.iter().any(|arg| arg == "--diff");
+    clean_args.retain(|arg| arg != "--diff");
+
+    // As for `cargo check`, only the library and binary targets are verified by
+    // default. The `--tests`, `--examples` and `--all-targets` flags are
+    // forwarded to cargo to verify the other targets too. Test targets are
+    // compiled with `--cfg test`, so the specifications in `#[cfg(test)]` code
+    // are collected and verified; the library itself is still compiled and its
+    // specifications exported as usual.
     let cargo_path = std::env::var("CARGO_PATH").unwrap_or("cargo".to_string());
 
-    let exit_status = Command::new(cargo_path)
-        .arg("check")
+    // Store the verification cache in the target directory, unless the user
+    // chose a different location.
+    let cache_path = std::env::var("PRUSTI_CACHE_PATH").unwrap_or_else(|_| {
+        let target_dir = std::env::var("CARGO_TARGET_DIR").unwrap_or("target".to_string());
+        let cache_path = PathBuf::from(target_dir).join("prusti").join("verification.cache");
+        std::env::current_dir()
+            .map(|dir| dir.join(&cache_path))
+            .unwrap_or(cache_path)
+            .to_string_lossy()
+            .to_string()
+    });
+
+    let diff_state_path = PathBuf::from(&cache_path).with_file_name("diff.state");
+
+    let mut cmd = Command::new(cargo_path);
+    cmd.arg("check")
         .args(clean_args)
         .env("RUST_TOOLCHAIN", get_rust_toolchain_channel())
         .env("PRUSTI_QUIET", "true")
         .env("PRUSTI_FULL_COMPILATION", "true")
-        .env("RUSTC_WRAPPER", prusti_rustc_path)
-        .status()
-        .expect("could not run cargo");
+        .env("PRUSTI_EXPORT_SPECS", "true")
+        .env("PRUSTI_CACHE_PATH", cache_path)
+        .env("RUSTC_WRAPPER", prusti_rustc_path);
+
+    if diff_mode {
+        // Without the state of a previous run, everything is verified.
+        if let Some(changed_files) = compute_changed_files(&diff_state_path) {
+            if changed_files.is_empty() {
+                eprintln!("Prusti: no files changed since the last run");
+                cmd.env("PRUSTI_NO_VERIFY", "true");
+            } else {
+                let changed_files: Vec<_> = changed_files.iter()
+                    .map(|path| path.to_string_lossy().to_string())
+                    .collect();
+                cmd.env("PRUSTI_VERIFY_ONLY_FILES", changed_files.join(","));
+            }
+        }
+    }
+
+    let exit_status = cmd.status().expect("could not run cargo");
 
     if exit_status.success() {
+        if diff_mode {
+            store_diff_state(&diff_state_path);
+        }
         Ok(())
     } else {
         Err(exit_status.code().unwrap_or(-1))
     }
 }
+
+/// Run git in the current directory, returning its standard output on success.
+fn run_git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        None
+    }
+}
+
+/// Is the current directory tracked by git? It is not if it is inside an
+/// ignored folder of a repository.
+fn is_tracked_by_git() -> bool {
+    run_git(&["rev-parse", "--is-inside-work-tree"]).map(|out| out == "true").unwrap_or(false)
+        && run_git(&["check-ignore", "-q", "."]).is_none()
+}
+
+/// The state of a successful `--diff` run: the current git commit, if any.
+/// The modification time of the state file is used when git is unavailable.
+fn store_diff_state(diff_state_path: &Path) {
+    let commit = if is_tracked_by_git() {
+        run_git(&["rev-parse", "HEAD"]).unwrap_or_default()
+    } else {
+        String::new()
+    };
+    if let Some(parent) = diff_state_path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Err(error) = fs::write(diff_state_path, commit) {
+        eprintln!(
+            "Prusti: failed to store the state of the run in {}: {}",
+            diff_state_path.display(),
+            error
+        );
+    }
+}
+
+/// Compute the absolute paths of the Rust files changed since the run stored in
+/// `diff_state_path`. Returns `None` if there is no previous run to compare with.
+fn compute_changed_files(diff_state_path: &Path) -> Option<Vec<PathBuf>> {
+    let last_commit = fs::read_to_string(diff_state_path).ok()?;
+    let last_commit = last_commit.trim();
+    let changed_files = if last_commit.is_empty() {
+        None
+    } else {
+        compute_git_changed_files(last_commit)
+    };
+    let changed_files = match changed_files {
+        Some(files) => files,
+        None => {
+            let last_run = fs::metadata(diff_state_path).and_then(|m| m.modified()).ok()?;
+            compute_modified_files(last_run)
+        }
+    };
+    Some(changed_files.into_iter()
+        .filter(|path| path.extension().map(|ext| ext == "rs").unwrap_or(false))
+        .collect())
+}
+
+/// The files that differ from `last_commit`, including the uncommitted and the
+/// untracked ones.
+fn compute_git_changed_files(last_commit: &str) -> Option<Vec<PathBuf>> {
+    let root = PathBuf::from(run_git(&["rev-parse", "--show-toplevel"])?);
+    let changed = run_git(&["diff", "--name-only", last_commit])?;
+    let untracked = run_git(&["ls-files", "--others", "--exclude-standard", "--full-name"])?;
+    Some(changed.lines()
+        .chain(untracked.lines())
+        .filter(|line| !line.is_empty())
+        .map(|line| root.join(line))
+        .collect())
+}
+
+/// The files in the current directory modified after `last_run`, skipping the
+/// target directory.
+fn compute_modified_files(last_run: SystemTime) -> Vec<PathBuf> {
+    let current_dir = std::env::current_dir().expect("current directory invalid");
+    let target_dir = current_dir.join(
+        std::env::var("CARGO_TARGET_DIR").unwrap_or("target".to_string())
+    );
+    walkdir::WalkDir::new(&current_dir)
+        .into_iter()
+        .filter_entry(|entry| entry.path() != target_dir)
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| {
+            entry.metadata()
+                .and_then(|metadata| metadata.modified())
+                .map(|modified| modified > last_run)
+                .unwrap_or(false)
+        })
+        .map(|entry| entry.into_path())
+        .collect()
+}
@@ -6,9 +6,9 @@
 
 use glob::glob;
 use std::process::{Command, ExitStatus, Stdio, Child};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::io::{BufReader, BufRead};
-use std::env;
+use std::{env, fs};
 use prusti_launch::find_java_home;
 use std::collections::HashMap;
 
@@ -108,6 +108,126 @@ fn test_prusti_rustc() {
     });
 }
 
+#[test]
+fn test_prusti_rustc_dump_viper_per_fn() {
+    let prusti_rustc = find_executable_path("prusti-rustc");
+    let program = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/pass/with_specs.rs");
+    let log_dir = env::temp_dir().join("prusti-test-dump-viper-per-fn");
+    let _ = fs::remove_dir_all(&log_dir);
+
+    println!("Running {:?} on {:?}...", prusti_rustc.display(), program.display());
+    let exit_status = Command::new(&prusti_rustc)
+        .arg("--edition=2018")
+        .arg(&program)
+        .env_clear()
+        .env("RUST_BACKTRACE", "1")
+        .env("PRUSTI_DUMP_VIPER_PER_FN", "true")
+        .env("PRUSTI_LOG_DIR", &log_dir)
+        .status()
+        .expect("failed to execute prusti-rustc");
+    assert!(exit_status.success(), "Test case {:?} unexpectedly failed.", program);
+
+    // The method that encodes `test` is dumped together with its table of positions
+    let dump_dir = log_dir.join("viper_program_per_fn");
+    let dump_path = fs::read_dir(&dump_dir)
+        .expect("failed to read the directory of the dumps")
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            let file_name = path.file_name().unwrap().to_str().unwrap();
+            file_name.starts_with("with_specs.rs.")
+                && file_name.contains("test")
+                && file_name.ends_with(".vpr")
+        })
+        .expect("the encoding of `test` was not dumped");
+    let dump = fs::read_to_string(&dump_path).unwrap();
+    let comments: Vec<&str> = dump.lines()
+        .map(|line| line.trim())
+        .filter(|line| line.starts_with("// ") && line.contains("with_specs.rs:"))
+        .collect();
+    assert!(!comments.is_empty(), "no source comments in {:?}:\n{}", dump_path, dump);
+    // The `assert!` in `test` is on line 14
+    assert!(
+        comments.iter().any(|comment| comment.ends_with("with_specs.rs:14")),
+        "no comment for the `assert!` in {:?}:\n{}", dump_path, dump
+    );
+
+    let map_path = dump_path.with_extension("map.json");
+    let map = fs::read_to_string(&map_path)
+        .unwrap_or_else(|_| panic!("the table of positions {:?} was not dumped", map_path));
+    assert!(map.contains("with_specs.rs"), "unexpected table of positions:\n{}", map);
+    assert!(map.contains("\"line\": 14"), "unexpected table of positions:\n{}", map);
+
+    let _ = fs::remove_dir_all(&log_dir);
+}
+
+/// Verify `program` with the given optimizations, and count the assignments and the
+/// declarations of local variables in the dumped Viper program.
+fn count_dumped_statements(program: &Path, optimizations: &str) -> usize {
+    let prusti_rustc = find_executable_path("prusti-rustc");
+    let log_dir = env::temp_dir().join(format!(
+        "prusti-test-optimizations-{}",
+        optimizations.replace(',', "-")
+    ));
+    let _ = fs::remove_dir_all(&log_dir);
+
+    println!("Running {:?} on {:?}...", prusti_rustc.display(), program.display());
+    let exit_status = Command::new(&prusti_rustc)
+        .arg("--edition=2018")
+        .arg(program)
+        .env_clear()
+        .env("RUST_BACKTRACE", "1")
+        .env("PRUSTI_DUMP_VIPER_PROGRAM", "true")
+        .env("PRUSTI_OPTIMIZATIONS", optimizations)
+        .env("PRUSTI_LOG_DIR", &log_dir)
+        .status()
+        .expect("failed to execute prusti-rustc");
+    assert!(exit_status.success(), "Test case {:?} unexpectedly failed.", program);
+
+    let mut count = 0;
+    for entry in fs::read_dir(log_dir.join("viper_program"))
+        .expect("failed to read the directory of the dumps")
+    {
+        let dump = fs::read_to_string(entry.unwrap().path()).unwrap();
+        count += dump.lines()
+            .map(|line| line.trim())
+            .filter(|line| line.contains(" := ") || line.starts_with("var "))
+            .count();
+    }
+    let _ = fs::remove_dir_all(&log_dir);
+    count
+}
+
+#[test]
+fn test_prusti_rustc_remove_dead_assignments() {
+    let program = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../prusti-tests/tests/verify/pass/no-annotations/multiple-branches.rs");
+    // All the default optimizations, with and without `remove_dead_assignments`
+    let optimizations = [
+        "inline_constant_functions",
+        "delete_unused_predicates",
+        "delete_unused_items",
+        "optimize_folding",
+        "simplify_expressions",
+        "remove_empty_if",
+        "purify_vars",
+        "fix_quantifiers",
+        "remove_unused_vars",
+        "remove_trivial_assertions",
+        "clean_cfg",
+    ].join(",");
+    let before = count_dumped_statements(&program, &optimizations);
+    let after = count_dumped_statements(
+        &program,
+        &format!("{},remove_dead_assignments", optimizations),
+    );
+    // At least the flags recording that a block has been executed are never read
+    assert!(
+        after < before,
+        "removing the dead assignments did not remove any statement ({} before, {} after)",
+        before, after
+    );
+}
+
 #[test]
 fn test_prusti_rustc_with_server() {
     let prusti_rustc = find_executable_path("prusti-rustc");
@@ -19,12 +19,14 @@ extern crate tokio;
 #[macro_use]
 extern crate serde;
 
+mod parallel_verification;
 mod service;
 mod verifier_runner;
 mod verifier_thread;
 
 use futures::Future;
 use prusti_common::{verification_context::VerifierBuilder, verification_service::*, Stopwatch};
+pub use parallel_verification::*;
 pub use service::*;
 use std::{
     collections::VecDeque,
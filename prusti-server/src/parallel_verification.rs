@@ -0,0 +1,153 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use prusti_common::verification_service::*;
+use std::{
+    collections::VecDeque,
+    panic,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+use viper::VerificationResult;
+
+/// Verifies each method of the program of the request with a separate
+/// request, submitting at most `jobs` requests to the service at the same
+/// time. The results are merged as if the whole program had been verified
+/// by a single request, except that the methods whose verification timed
+/// out are named in the merged result.
+pub fn verify_in_parallel<S>(
+    service: Arc<S>,
+    request: VerificationRequest,
+    jobs: usize,
+) -> VerificationResult
+where
+    S: VerificationService + Send + Sync + 'static,
+{
+    let VerificationRequest {
+        program,
+        program_name,
+        backend_config,
+    } = request;
+    let programs = program.split_by_method();
+    let method_names: Vec<Vec<String>> = programs
+        .iter()
+        .map(|program| program.methods.iter().map(|method| method.name()).collect())
+        .collect();
+    let requests: VecDeque<_> = programs
+        .into_iter()
+        .map(|program| {
+            let program_name = match program.methods.as_slice() {
+                [method] => format!("{}#{}", program_name, method.name()),
+                _ => program_name.clone(),
+            };
+            VerificationRequest {
+                program,
+                program_name,
+                backend_config: backend_config.clone(),
+            }
+        })
+        .enumerate()
+        .collect();
+    let request_count = requests.len();
+    info!(
+        "Verifying {} with {} requests, {} at a time",
+        program_name, request_count, jobs
+    );
+
+    let requests = Arc::new(Mutex::new(requests));
+    let (sender, receiver) = mpsc::channel();
+    let workers: Vec<_> = (0..jobs.max(1).min(request_count))
+        .map(|_| {
+            let service = service.clone();
+            let requests = requests.clone();
+            let sender = sender.clone();
+            thread::spawn(move || loop {
+                let next_request = requests.lock().unwrap().pop_front();
+                match next_request {
+                    Some((index, request)) => {
+                        sender.send((index, service.verify(request))).unwrap();
+                    }
+                    None => break,
+                }
+            })
+        })
+        .collect();
+    drop(sender);
+
+    let mut results: Vec<_> = receiver.iter().collect();
+    for worker in workers {
+        if let Err(payload) = worker.join() {
+            panic::resume_unwind(payload);
+        }
+    }
+    debug_assert_eq!(results.len(), request_count);
+    results.sort_by_key(|(index, _)| *index);
+    merge_results(results.into_iter().map(|(index, result)| match result {
+        VerificationResult::Timeout { errors, methods } if methods.is_empty() => {
+            VerificationResult::Timeout {
+                errors,
+                methods: method_names[index].clone(),
+            }
+        }
+        result => result,
+    }))
+}
+
+/// Merges the results of the verification of parts of a program. A Java
+/// exception or consistency errors take precedence over timeouts, which take
+/// precedence over verification errors. Errors reported by more than one part,
+/// e.g. the ones of the functions that are shared by all parts, are reported
+/// only once.
+fn merge_results<I>(results: I) -> VerificationResult
+where
+    I: IntoIterator<Item = VerificationResult>,
+{
+    let mut verification_errors = vec![];
+    let mut consistency_errors = vec![];
+    let mut timed_out_methods = vec![];
+    let mut timed_out = false;
+    for result in results {
+        match result {
+            VerificationResult::Success() => {}
+            VerificationResult::Failure(errors) => {
+                for error in errors {
+                    if !verification_errors.contains(&error) {
+                        verification_errors.push(error);
+                    }
+                }
+            }
+            VerificationResult::Timeout { errors, methods } => {
+                for error in errors {
+                    if !verification_errors.contains(&error) {
+                        verification_errors.push(error);
+                    }
+                }
+                timed_out_methods.extend(methods);
+                timed_out = true;
+            }
+            VerificationResult::ConsistencyErrors(errors) => {
+                for error in errors {
+                    if !consistency_errors.contains(&error) {
+                        consistency_errors.push(error);
+                    }
+                }
+            }
+            VerificationResult::JavaException(_) => return result,
+        }
+    }
+    if !consistency_errors.is_empty() {
+        VerificationResult::ConsistencyErrors(consistency_errors)
+    } else if timed_out {
+        VerificationResult::Timeout {
+            errors: verification_errors,
+            methods: timed_out_methods,
+        }
+    } else if !verification_errors.is_empty() {
+        VerificationResult::Failure(verification_errors)
+    } else {
+        VerificationResult::Success()
+    }
+}
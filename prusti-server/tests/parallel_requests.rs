@@ -0,0 +1,85 @@
+extern crate prusti_common;
+extern crate prusti_server;
+extern crate viper;
+
+use prusti_common::{
+    verification_service::{VerificationRequest, VerificationService},
+    vir::*,
+};
+use prusti_server::{verify_in_parallel, PrustiServerConnection, ServerSideService};
+use std::sync::{Arc, Mutex};
+use viper::VerificationResult;
+
+/// Forwards the requests to the server, recording the name and the methods
+/// of the programs.
+struct RecordingService {
+    connection: PrustiServerConnection,
+    requests: Mutex<Vec<(String, Vec<String>)>>,
+}
+
+impl VerificationService for RecordingService {
+    fn verify(&self, request: VerificationRequest) -> VerificationResult {
+        let method_names = request.program.methods.iter().map(|m| m.name()).collect();
+        self.requests
+            .lock()
+            .unwrap()
+            .push((request.program_name.clone(), method_names));
+        self.connection.verify(request)
+    }
+}
+
+fn method_asserting(name: &str, value: bool, pos: Position) -> CfgMethod {
+    let mut method = CfgMethod::new(name.to_string(), 0, vec![], vec![], vec![]);
+    let start = method.add_block(
+        "start",
+        vec![Stmt::Assert(Expr::Const(Const::Bool(value), pos), pos)],
+    );
+    method.set_successor(start, Successor::Return);
+    method
+}
+
+#[test]
+fn one_request_per_method() {
+    let server_address = ServerSideService::spawn_off_thread();
+    let service = Arc::new(RecordingService {
+        connection: PrustiServerConnection::new(server_address)
+            .expect("Could not connect to server!"),
+        requests: Mutex::new(vec![]),
+    });
+
+    let program = Program {
+        domains: vec![],
+        fields: vec![],
+        builtin_methods: vec![],
+        methods: vec![
+            method_asserting("verified", true, Position::new(1, 1, 41)),
+            method_asserting("failing", false, Position::new(2, 1, 42)),
+        ],
+        functions: vec![],
+        viper_predicates: vec![],
+    };
+    let request = VerificationRequest {
+        program,
+        program_name: "dummy".to_string(),
+        backend_config: Default::default(),
+    };
+
+    let result = verify_in_parallel(service.clone(), request, 2);
+
+    let mut requests = service.requests.lock().unwrap().clone();
+    requests.sort();
+    assert_eq!(
+        requests,
+        vec![
+            ("dummy#failing".to_string(), vec!["failing".to_string()]),
+            ("dummy#verified".to_string(), vec!["verified".to_string()]),
+        ]
+    );
+    match result {
+        VerificationResult::Failure(errors) => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].pos_id, Some("42".to_string()));
+        }
+        other => panic!("the failing method was not reported, instead found {:?}", other),
+    }
+}
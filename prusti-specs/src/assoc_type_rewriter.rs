@@ -0,0 +1,55 @@
+use syn::visit_mut::VisitMut;
+
+/// Qualify the uses of the associated types of a trait impl (e.g. `Self::Item`)
+/// with the implemented trait (e.g. `<Self as Iterator>::Item`).
+///
+/// The specification items of a trait impl are generated in an inherent impl
+/// of the same type, where the associated types of the trait cannot be
+/// referred to without naming the trait.
+pub struct AssocTypeRewriter {
+    trait_path: syn::Path,
+    assoc_types: Vec<syn::Ident>,
+}
+
+impl AssocTypeRewriter {
+    pub fn new(trait_path: syn::Path, assoc_types: Vec<syn::Ident>) -> Self {
+        AssocTypeRewriter {
+            trait_path,
+            assoc_types,
+        }
+    }
+
+    fn rewrite_path(&self, qself: &mut Option<syn::QSelf>, path: &mut syn::Path) {
+        if qself.is_some() || path.leading_colon.is_some() || path.segments.len() < 2 {
+            return;
+        }
+        let self_segment = &path.segments[0];
+        if self_segment.ident != "Self" || !self_segment.arguments.is_empty()
+            || !self.assoc_types.contains(&path.segments[1].ident) {
+            return;
+        }
+        let span = self_segment.ident.span();
+        let mut qualified_path = self.trait_path.clone();
+        qualified_path.segments.extend(path.segments.iter().skip(1).cloned());
+        *qself = Some(syn::QSelf {
+            lt_token: syn::Token![<](span),
+            ty: Box::new(parse_quote_spanned! {span=> Self }),
+            position: self.trait_path.segments.len(),
+            as_token: Some(syn::Token![as](span)),
+            gt_token: syn::Token![>](span),
+        });
+        *path = qualified_path;
+    }
+}
+
+impl VisitMut for AssocTypeRewriter {
+    fn visit_type_path_mut(&mut self, type_path: &mut syn::TypePath) {
+        self.rewrite_path(&mut type_path.qself, &mut type_path.path);
+        syn::visit_mut::visit_type_path_mut(self, type_path);
+    }
+
+    fn visit_expr_path_mut(&mut self, expr_path: &mut syn::ExprPath) {
+        self.rewrite_path(&mut expr_path.qself, &mut expr_path.path);
+        syn::visit_mut::visit_expr_path_mut(self, expr_path);
+    }
+}
@@ -0,0 +1,123 @@
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use syn::spanned::Spanned;
+
+/// Expand the `#[cfg_attr(..)]` attributes whose predicate only depends on whether the
+/// code is verified by Prusti, i.e. on the `prusti` cfg.
+///
+/// The specification macros receive the annotated item before the compiler expands the
+/// `cfg_attr` attributes that follow them, so a specification attribute nested in such
+/// a `cfg_attr` would otherwise not be rewritten together with the other ones. The
+/// macros only generate specification items when verifying, so the `prusti` cfg is set.
+/// The `cfg_attr` attributes with other predicates, including the ones on features, are
+/// left to the compiler.
+pub fn expand_cfg_attrs(attrs: &mut Vec<syn::Attribute>) -> syn::Result<()> {
+    let mut expanded_attrs = Vec::with_capacity(attrs.len());
+    for attr in attrs.drain(..) {
+        if !attr.path.is_ident("cfg_attr") {
+            expanded_attrs.push(attr);
+            continue;
+        }
+        let (predicate, mut nested_attrs) = parse_cfg_attr(&attr)?;
+        match eval_predicate(&predicate) {
+            Some(true) => {
+                // The nested attributes can be `cfg_attr` attributes themselves
+                expand_cfg_attrs(&mut nested_attrs)?;
+                expanded_attrs.extend(nested_attrs);
+            }
+            Some(false) => {}
+            None => expanded_attrs.push(attr),
+        }
+    }
+    *attrs = expanded_attrs;
+    Ok(())
+}
+
+/// The `#[cfg(..)]` attributes among `attrs`. The specification items generated for an
+/// item get the same attributes, so that they are removed together with it.
+pub fn cfg_attrs(attrs: &[syn::Attribute]) -> Vec<syn::Attribute> {
+    attrs.iter()
+        .filter(|attr| attr.path.is_ident("cfg"))
+        .cloned()
+        .collect()
+}
+
+/// Split `#[cfg_attr(predicate, attr1, attr2, ..)]` into the predicate and the attributes.
+/// The nested attributes are not parsed as meta items, because specifications do not
+/// follow the meta item syntax.
+fn parse_cfg_attr(attr: &syn::Attribute) -> syn::Result<(syn::NestedMeta, Vec<syn::Attribute>)> {
+    let group = match attr.tokens.clone().into_iter().next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => group,
+        _ => return Err(syn::Error::new(attr.span(), "malformed `cfg_attr` attribute")),
+    };
+    let mut parts = vec![TokenStream::new()];
+    for token in group.stream() {
+        match token {
+            TokenTree::Punct(ref punct) if punct.as_char() == ',' => {
+                parts.push(TokenStream::new());
+            }
+            token => parts.last_mut().unwrap().extend(std::iter::once(token)),
+        }
+    }
+    if parts.len() > 1 && parts.last().unwrap().is_empty() {
+        // trailing comma
+        parts.pop();
+    }
+    let mut parts = parts.into_iter();
+    let predicate = syn::parse2(parts.next().unwrap())?;
+    let nested_attrs = parts
+        .map(|tokens| parse_nested_attr(attr, tokens))
+        .collect::<syn::Result<_>>()?;
+    Ok((predicate, nested_attrs))
+}
+
+/// Parse one of the attributes of a `cfg_attr`, which is an attribute path followed
+/// by arbitrary tokens.
+fn parse_nested_attr(cfg_attr: &syn::Attribute, tokens: TokenStream) -> syn::Result<syn::Attribute> {
+    let parser = |input: syn::parse::ParseStream| {
+        let path = input.call(syn::Path::parse_mod_style)?;
+        let tokens: TokenStream = input.parse()?;
+        Ok((path, tokens))
+    };
+    let (path, tokens) = syn::parse::Parser::parse2(parser, tokens)?;
+    Ok(syn::Attribute {
+        pound_token: cfg_attr.pound_token,
+        style: cfg_attr.style,
+        bracket_token: cfg_attr.bracket_token,
+        path,
+        tokens,
+    })
+}
+
+/// Evaluate a `cfg` predicate when verifying, if it only depends on `prusti`. Returns
+/// `None` if the value of the predicate is not known. The features are not known, the
+/// driver does not enable any.
+fn eval_predicate(predicate: &syn::NestedMeta) -> Option<bool> {
+    match predicate {
+        syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("prusti") => Some(true),
+        syn::NestedMeta::Meta(syn::Meta::List(list)) => {
+            let values: Vec<_> = list.nested.iter().map(eval_predicate).collect();
+            if list.path.is_ident("not") && values.len() == 1 {
+                values[0].map(|value| !value)
+            } else if list.path.is_ident("all") {
+                if values.contains(&Some(false)) {
+                    Some(false)
+                } else if values.iter().all(|value| *value == Some(true)) {
+                    Some(true)
+                } else {
+                    None
+                }
+            } else if list.path.is_ident("any") {
+                if values.contains(&Some(true)) {
+                    Some(true)
+                } else if values.iter().all(|value| *value == Some(false)) {
+                    Some(false)
+                } else {
+                    None
+                }
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
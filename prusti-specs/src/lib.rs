@@ -7,9 +7,12 @@
 #[macro_use]
 mod parse_quote_spanned;
 mod span_overrider;
+mod assoc_type_rewriter;
+mod cfg_attr_expander;
 mod extern_spec_rewriter;
 mod rewriter;
 mod parse_closure_macro;
+mod parse_refine_spec;
 mod spec_attribute_kind;
 pub mod specifications;
 
@@ -18,8 +21,11 @@ use quote::{quote, quote_spanned, ToTokens};
 use syn::spanned::Spanned;
 use std::convert::{TryFrom, TryInto};
 
+use specifications::common::SpecType;
 use specifications::untyped;
 use parse_closure_macro::ClosureWithSpec;
+use parse_refine_spec::RefineSpec;
+use assoc_type_rewriter::AssocTypeRewriter;
 pub use spec_attribute_kind::SpecAttributeKind;
 
 macro_rules! handle_result {
@@ -43,7 +49,13 @@ fn extract_prusti_attributes<'a>(item: &'a mut untyped::AnyFnItem) -> impl Itera
                     SpecAttributeKind::Requires
                     | SpecAttributeKind::Ensures
                     | SpecAttributeKind::AfterExpiry
-                    | SpecAttributeKind::AfterExpiryIf => {
+                    | SpecAttributeKind::AfterExpiryIf
+                    | SpecAttributeKind::AllowSpecWarnings
+                    | SpecAttributeKind::Decreases
+                    | SpecAttributeKind::RefineSpec
+                    | SpecAttributeKind::Fuel
+                    | SpecAttributeKind::Assigns
+                    | SpecAttributeKind::Bounded => {
                         // We need to drop the surrounding parenthesis to make the
                         // tokens identical to the ones passed by the native procedural
                         // macro call.
@@ -59,7 +71,11 @@ fn extract_prusti_attributes<'a>(item: &'a mut untyped::AnyFnItem) -> impl Itera
                     // Nothing to do for attributes without arguments.
                     SpecAttributeKind::Pure
                     | SpecAttributeKind::Trusted
-                    | SpecAttributeKind::Predicate => {
+                    | SpecAttributeKind::Predicate
+                    | SpecAttributeKind::Bitvector
+                    | SpecAttributeKind::TrustedUnsafe
+                    | SpecAttributeKind::NoInvariant
+                    | SpecAttributeKind::PanicFree => {
                         assert!(attr.tokens.is_empty(), "Unexpected shape of an attribute.");
                         attr.tokens
                     }
@@ -87,6 +103,7 @@ pub fn rewrite_prusti_attributes(
     ];
 
     // Collect the remaining Prusti attributes, removing them from `item`.
+    handle_result!(cfg_attr_expander::expand_cfg_attrs(item.attrs_mut()));
     prusti_attributes.extend(extract_prusti_attributes(&mut item));
 
     // make sure to also update the check in the #[predicate] handling method
@@ -100,6 +117,18 @@ pub fn rewrite_prusti_attributes(
         ).to_compile_error();
     }
 
+    if let Some((_, tokens)) = prusti_attributes
+        .iter()
+        .filter(|(ak, _)| ak == &SpecAttributeKind::Decreases)
+        .nth(1)
+    {
+        let span = tokens.clone().into_iter().next().map_or(item.span(), |t| t.span());
+        return syn::Error::new(
+            span,
+            "a function can have at most one `#[decreases]` attribute",
+        ).to_compile_error();
+    }
+
     let (generated_spec_items, generated_attributes) = handle_result!(
         generate_spec_and_assertions(prusti_attributes, &item)
     );
@@ -120,8 +149,10 @@ fn generate_spec_and_assertions(
 ) -> GeneratedResult {
     let mut generated_items = vec![];
     let mut generated_attributes = vec![];
+    let mut is_bitvector = false;
 
     for (attr_kind, attr_tokens) in prusti_attributes.drain(..) {
+        is_bitvector |= attr_kind == SpecAttributeKind::Bitvector;
         let rewriting_result = match attr_kind {
             SpecAttributeKind::Requires => generate_for_requires(attr_tokens, item),
             SpecAttributeKind::Ensures => generate_for_ensures(attr_tokens, item),
@@ -129,6 +160,18 @@ fn generate_spec_and_assertions(
             SpecAttributeKind::AfterExpiryIf => generate_for_after_expiry_if(attr_tokens, item),
             SpecAttributeKind::Pure => generate_for_pure(attr_tokens, item),
             SpecAttributeKind::Trusted => generate_for_trusted(attr_tokens, item),
+            SpecAttributeKind::AllowSpecWarnings => {
+                generate_for_allow_spec_warnings(attr_tokens, item)
+            }
+            SpecAttributeKind::Decreases => generate_for_decreases(attr_tokens, item),
+            SpecAttributeKind::Bitvector => generate_for_bitvector(attr_tokens, item),
+            SpecAttributeKind::TrustedUnsafe => generate_for_trusted_unsafe(attr_tokens, item),
+            SpecAttributeKind::RefineSpec => generate_for_refine_spec(attr_tokens, item),
+            SpecAttributeKind::NoInvariant => generate_for_no_invariant(attr_tokens, item),
+            SpecAttributeKind::PanicFree => generate_for_panic_free(attr_tokens, item),
+            SpecAttributeKind::Fuel => generate_for_fuel(attr_tokens, item),
+            SpecAttributeKind::Assigns => generate_for_assigns(attr_tokens, item),
+            SpecAttributeKind::Bounded => generate_for_bounded(attr_tokens, item),
             // Predicates are handled separately below; the entry in the SpecAttributeKind enum
             // only exists so we successfully parse it and emit an error in
             // `check_incompatible_attrs`; so we'll never reach here.
@@ -139,6 +182,25 @@ fn generate_spec_and_assertions(
         generated_attributes.extend(new_attributes);
     }
 
+    // The specification items are removed together with the function
+    let cfg_attrs = cfg_attr_expander::cfg_attrs(item.attrs());
+    for generated_item in &mut generated_items {
+        if let syn::Item::Fn(spec_item) = generated_item {
+            spec_item.attrs.extend(cfg_attrs.iter().cloned());
+        }
+    }
+
+    // The specifications of a function use the same integer encoding as its body
+    if is_bitvector {
+        for generated_item in &mut generated_items {
+            if let syn::Item::Fn(spec_item) = generated_item {
+                spec_item.attrs.push(parse_quote_spanned! {item.span()=>
+                    #[prusti::bitvector]
+                });
+            }
+        }
+    }
+
     Ok((generated_items, generated_attributes))
 }
 
@@ -182,6 +244,64 @@ fn generate_for_ensures(attr: TokenStream, item: &untyped::AnyFnItem) -> Generat
     ))
 }
 
+/// Generate spec items and attributes to typecheck and later retrieve "decreases" annotations.
+fn generate_for_decreases(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    let mut rewriter = rewriter::AstRewriter::new();
+    let spec_id = rewriter.generate_spec_id();
+    let spec_id_str = spec_id.to_string();
+    let measure = rewriter.parse_measure(spec_id, attr)?;
+    let spec_item = rewriter.generate_spec_item_fn(
+        rewriter::SpecItemType::Termination,
+        spec_id,
+        measure,
+        &item
+    )?;
+    Ok((
+        vec![spec_item],
+        vec![parse_quote_spanned! {item.span()=>
+            #[prusti::decreases_spec_id_ref = #spec_id_str]
+        }],
+    ))
+}
+
+/// Generate spec items and attributes to typecheck and later retrieve "refine_spec" annotations,
+/// whose specifications only hold for the instantiations of the generics that satisfy the bounds.
+fn generate_for_refine_spec(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    let refine_spec: RefineSpec = syn::parse2(attr)?;
+    let mut generated_items = vec![];
+    let mut generated_attributes = vec![];
+    for (kind, tokens) in refine_spec.specs {
+        let mut rewriter = rewriter::AstRewriter::new();
+        let spec_id = rewriter.generate_spec_id();
+        let spec_id_str = spec_id.to_string();
+        let spec_type = if kind == "requires" {
+            generated_attributes.push(parse_quote_spanned! {item.span()=>
+                #[prusti::pre_spec_id_ref = #spec_id_str]
+            });
+            rewriter::SpecItemType::Precondition
+        } else if kind == "ensures" {
+            generated_attributes.push(parse_quote_spanned! {item.span()=>
+                #[prusti::post_spec_id_ref = #spec_id_str]
+            });
+            rewriter::SpecItemType::Postcondition
+        } else {
+            return Err(syn::Error::new(
+                kind.span(),
+                "only `requires` and `ensures` specifications can be refined",
+            ));
+        };
+        let assertion = rewriter.parse_assertion(spec_id, tokens)?;
+        generated_items.push(rewriter.generate_type_cond_spec_item_fn(
+            spec_type,
+            spec_id,
+            assertion,
+            &refine_spec.where_clause,
+            &item
+        )?);
+    }
+    Ok((generated_items, generated_attributes))
+}
+
 /// Check if the given expression is identifier `result`.
 fn check_is_result(reference: &Option<untyped::Expression>) -> syn::Result<()> {
     if let Some(untyped::Expression { expr, ..}) = reference {
@@ -288,10 +408,225 @@ fn generate_for_trusted(attr: TokenStream, item: &untyped::AnyFnItem) -> Generat
     ))
 }
 
+/// Generate attributes to later retrieve "bitvector" annotations.
+fn generate_for_bitvector(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    if !attr.is_empty() {
+        return Err(syn::Error::new(
+            attr.span(),
+            "the `#[bitvector]` attribute does not take parameters"
+        ));
+    }
+
+    Ok((
+        vec![],
+        vec![parse_quote_spanned! {item.span()=>
+            #[prusti::bitvector]
+        }],
+    ))
+}
+
+/// Generate attributes to later retrieve "trusted_unsafe" annotations.
+fn generate_for_trusted_unsafe(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    if !attr.is_empty() {
+        return Err(syn::Error::new(
+            attr.span(),
+            "the `#[trusted_unsafe]` attribute does not take parameters"
+        ));
+    }
+
+    Ok((
+        vec![],
+        vec![parse_quote_spanned! {item.span()=>
+            #[prusti::trusted_unsafe]
+        }],
+    ))
+}
+
+/// Generate attributes to later retrieve "no_invariant" annotations.
+fn generate_for_no_invariant(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    if !attr.is_empty() {
+        return Err(syn::Error::new(
+            attr.span(),
+            "the `#[no_invariant]` attribute does not take parameters"
+        ));
+    }
+
+    Ok((
+        vec![],
+        vec![parse_quote_spanned! {item.span()=>
+            #[prusti::no_invariant]
+        }],
+    ))
+}
+
+/// Generate attributes to later retrieve "panic_free" annotations.
+fn generate_for_panic_free(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    if !attr.is_empty() {
+        return Err(syn::Error::new(
+            attr.span(),
+            "the `#[panic_free]` attribute does not take parameters"
+        ));
+    }
+
+    Ok((
+        vec![],
+        vec![parse_quote_spanned! {item.span()=>
+            #[prusti::panic_free]
+        }],
+    ))
+}
+
+/// Generate attributes to later retrieve "fuel" annotations.
+fn generate_for_fuel(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    let fuel: syn::LitInt = syn::parse2(attr).map_err(|err| syn::Error::new(
+        err.span(),
+        "the `#[fuel(..)]` attribute expects an integer literal"
+    ))?;
+    let fuel_str = fuel.base10_parse::<u32>()?.to_string();
+
+    Ok((
+        vec![],
+        vec![parse_quote_spanned! {item.span()=>
+            #[prusti::fuel = #fuel_str]
+        }],
+    ))
+}
+
+/// Generate attributes to later retrieve "bounded" annotations.
+fn generate_for_bounded(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    let bound: syn::LitInt = syn::parse2(attr).map_err(|err| syn::Error::new(
+        err.span(),
+        "the `#[bounded(..)]` attribute expects an integer literal"
+    ))?;
+    let bound_value = bound.base10_parse::<u32>()?;
+    if bound_value == 0 {
+        return Err(syn::Error::new(
+            bound.span(),
+            "the loop bound of `#[bounded(..)]` has to be positive"
+        ));
+    }
+    let bound_str = bound_value.to_string();
+
+    Ok((
+        vec![],
+        vec![parse_quote_spanned! {item.span()=>
+            #[prusti::bounded = #bound_str]
+        }],
+    ))
+}
+
+/// Generate attributes to later retrieve "assigns" annotations. Each place is
+/// recorded as the dot-separated path of fields from the parameter it is
+/// rooted at (e.g. `s.counter`), which must be a `&mut` parameter.
+fn generate_for_assigns(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    let parser = syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated;
+    let places = syn::parse::Parser::parse2(parser, attr)?;
+    if places.is_empty() {
+        return Err(syn::Error::new(
+            item.span(),
+            "the `#[assigns]` attribute expects at least one place"
+        ));
+    }
+
+    let mut generated_attributes = vec![];
+    for place in places {
+        let path = parse_assigned_place(&place)?;
+        if !is_mut_ref_param(item, &path[0]) {
+            return Err(syn::Error::new(
+                place.span(),
+                format!("the assigned place must be rooted at a `&mut` parameter, \
+                    but `{}` is not one", path[0])
+            ));
+        }
+        let path_str = path.join(".");
+        // The span of the place is used to report errors about it
+        generated_attributes.push(parse_quote_spanned! {place.span()=>
+            #[prusti::assigns = #path_str]
+        });
+    }
+    Ok((vec![], generated_attributes))
+}
+
+/// Returns the name of the root of an assigned place, followed by the names of
+/// the fields that are accessed, e.g. `["s", "inner", "0"]` for `s.inner.0`.
+fn parse_assigned_place(place: &syn::Expr) -> syn::Result<Vec<String>> {
+    match place {
+        syn::Expr::Path(syn::ExprPath { qself: None, path, .. }) if path.get_ident().is_some() => {
+            Ok(vec![path.get_ident().unwrap().to_string()])
+        }
+        syn::Expr::Field(syn::ExprField { base, member, .. }) => {
+            let mut path = parse_assigned_place(base)?;
+            path.push(match member {
+                syn::Member::Named(ident) => ident.to_string(),
+                syn::Member::Unnamed(index) => index.index.to_string(),
+            });
+            Ok(path)
+        }
+        // Fields are accessed through the reference anyway, so `(*s).f` is the same as `s.f`
+        syn::Expr::Paren(syn::ExprParen { expr, .. }) => parse_assigned_place(expr),
+        syn::Expr::Unary(syn::ExprUnary { op: syn::UnOp::Deref(_), expr, .. })
+            if matches!(**expr, syn::Expr::Path(_)) => parse_assigned_place(expr),
+        _ => Err(syn::Error::new(
+            place.span(),
+            "only a parameter or a (nested) field of a parameter can be assigned"
+        )),
+    }
+}
+
+/// Checks if the function has a parameter called `name` of type `&mut T`.
+fn is_mut_ref_param(item: &untyped::AnyFnItem, name: &str) -> bool {
+    item.sig().inputs.iter().any(|input| match input {
+        syn::FnArg::Receiver(receiver) => {
+            name == "self" && receiver.reference.is_some() && receiver.mutability.is_some()
+        }
+        syn::FnArg::Typed(pat_type) => {
+            matches!(&*pat_type.pat, syn::Pat::Ident(pat_ident) if pat_ident.ident == name)
+                && matches!(
+                    &*pat_type.ty,
+                    syn::Type::Reference(syn::TypeReference { mutability: Some(_), .. })
+                )
+        }
+    })
+}
+
+/// The specification warnings that can be suppressed with `#[allow_spec_warnings(..)]`.
+const SPEC_WARNINGS: &[&str] = &["unbounded_arithmetic", "missing_loop_invariant"];
+
+/// Generate attributes to later retrieve "allow_spec_warnings" annotations.
+fn generate_for_allow_spec_warnings(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    let parser = syn::punctuated::Punctuated::<syn::Ident, syn::Token![,]>::parse_terminated;
+    let warnings = syn::parse::Parser::parse2(parser, attr)?;
+    if warnings.is_empty() {
+        return Err(syn::Error::new(
+            item.span(),
+            "the `#[allow_spec_warnings]` attribute expects at least one warning name"
+        ));
+    }
+
+    let mut generated_attributes = vec![];
+    for warning in warnings {
+        let warning_str = warning.to_string();
+        if !SPEC_WARNINGS.contains(&warning_str.as_str()) {
+            return Err(syn::Error::new(
+                warning.span(),
+                format!("unknown specification warning `{}`", warning_str)
+            ));
+        }
+        generated_attributes.push(parse_quote_spanned! {item.span()=>
+            #[prusti::allow_spec_warning = #warning_str]
+        });
+    }
+    Ok((vec![], generated_attributes))
+}
+
 pub fn body_invariant(tokens: TokenStream) -> TokenStream {
     let mut rewriter = rewriter::AstRewriter::new();
     let spec_id = rewriter.generate_spec_id();
-    let invariant = handle_result!(rewriter.parse_assertion(spec_id, tokens));
+    let invariant = if let Some(measure_tokens) = extract_loop_measure(&tokens) {
+        handle_result!(rewriter.parse_measure(spec_id, measure_tokens))
+    } else {
+        handle_result!(rewriter.parse_assertion(spec_id, tokens))
+    };
     let check = rewriter.generate_spec_loop(spec_id, invariant);
     let callsite_span = Span::call_site();
     quote_spanned! {callsite_span=>
@@ -302,6 +637,86 @@ pub fn body_invariant(tokens: TokenStream) -> TokenStream {
     }
 }
 
+pub fn prusti_assertion(tokens: TokenStream) -> TokenStream {
+    generate_ghost_statement(tokens, "prusti_assertion_spec")
+}
+
+pub fn prusti_assumption(tokens: TokenStream) -> TokenStream {
+    generate_ghost_statement(tokens, "prusti_assumption_spec")
+}
+
+/// Desugars `prusti_assert!(..)` and `prusti_assume!(..)`, which differ only in
+/// the attribute used to mark the generated closure.
+fn generate_ghost_statement(tokens: TokenStream, kind_attr: &str) -> TokenStream {
+    let mut rewriter = rewriter::AstRewriter::new();
+    let spec_id = rewriter.generate_spec_id();
+    let assertion = handle_result!(rewriter.parse_assertion(spec_id, tokens));
+    let check = rewriter.generate_spec_ghost_statement(spec_id, assertion, kind_attr);
+    let callsite_span = Span::call_site();
+    quote_spanned! {callsite_span=>
+        #[allow(unused_must_use, unused_variables)]
+        if false {
+            #check
+        }
+    }
+}
+
+/// Desugars `label!("name")` to a closure in a specification block, which marks the program
+/// point whose state is used by `labelled_old("name", ..)` expressions.
+pub fn label(tokens: TokenStream) -> TokenStream {
+    let label: syn::LitStr = handle_result!(syn::parse2(tokens));
+    let label_name = handle_result!(specifications::preparser::parse_label_name(&label));
+    let callsite_span = Span::call_site();
+    quote_spanned! {callsite_span=>
+        #[allow(unused_must_use, unused_variables)]
+        if false {
+            #[prusti::spec_only]
+            #[prusti::ghost_label = #label_name]
+            || {};
+        }
+    }
+}
+
+/// Desugars `ghost! { .. }` to the given statements, delimited by two closures in
+/// specification blocks that mark the beginning and the end of the ghost code. Unlike the
+/// specification blocks, the ghost code is executed by Prusti, so that the ghost variables
+/// that it declares can be used by the following specifications.
+pub fn ghost(tokens: TokenStream) -> TokenStream {
+    let mut rewriter = rewriter::AstRewriter::new();
+    let spec_id = rewriter.generate_spec_id();
+    let spec_id_str = spec_id.to_string();
+    let callsite_span = Span::call_site();
+    quote_spanned! {callsite_span=>
+        #[allow(unused_must_use, unused_variables)]
+        if false {
+            #[prusti::spec_only]
+            #[prusti::ghost_block_begin = #spec_id_str]
+            || {};
+        }
+        #tokens
+        #[allow(unused_must_use, unused_variables)]
+        if false {
+            #[prusti::spec_only]
+            #[prusti::ghost_block_end = #spec_id_str]
+            || {};
+        }
+    }
+}
+
+/// Returns the tokens of the measure if `tokens` has the shape `decreases(..)`, which is used
+/// to give the termination measure of a loop.
+fn extract_loop_measure(tokens: &TokenStream) -> Option<TokenStream> {
+    let mut iter = tokens.clone().into_iter();
+    match (iter.next(), iter.next(), iter.next()) {
+        (Some(TokenTree::Ident(ident)), Some(TokenTree::Group(group)), None)
+            if ident == "decreases" && group.delimiter() == proc_macro2::Delimiter::Parenthesis =>
+        {
+            Some(group.stream())
+        }
+        _ => None,
+    }
+}
+
 /// Unlike the functions above, which are only called from
 /// prusti-contracts-internal, this function also needs to be called
 /// from prusti-contracts-impl, because we still need to parse the
@@ -390,21 +805,135 @@ pub fn closure(tokens: TokenStream, drop_spec: bool) -> TokenStream {
     }
 }
 
+/// Desugars `#[invariant(..)]` on a struct or enum to a method of the type that typechecks
+/// the invariant, and to an attribute of the type that refers to it.
+pub fn invariant(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    generate_for_type_spec(SpecType::Invariant, attr, tokens)
+}
+
+/// Desugars `#[history_invariant(..)]` on a struct or enum like `#[invariant(..)]`, except
+/// that the generated method takes `&mut self` so that the invariant can refer to `old(self)`.
+pub fn history_invariant(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    generate_for_type_spec(SpecType::HistoryInvariant, attr, tokens)
+}
+
+/// Desugars `#[derive_spec_eq]` on a struct or enum to an attribute of the type, which
+/// makes the verifier encode the comparisons with its derived `PartialEq` implementation
+/// as the snapshot equality.
+pub fn derive_spec_eq(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    let mut item: syn::Item = handle_result!(syn::parse2(tokens));
+    let item_span = item.span();
+    if !attr.is_empty() {
+        return syn::Error::new(
+            attr.span(),
+            "the `#[derive_spec_eq]` attribute does not take parameters",
+        ).to_compile_error();
+    }
+    let attrs = match &mut item {
+        syn::Item::Struct(item_struct) => &mut item_struct.attrs,
+        syn::Item::Enum(item_enum) => &mut item_enum.attrs,
+        _ => {
+            return syn::Error::new(
+                item_span,
+                "the `#[derive_spec_eq]` attribute can only be attached to structs and enums",
+            ).to_compile_error();
+        }
+    };
+    attrs.push(parse_quote_spanned! {item_span=>
+        #[prusti::derive_spec_eq]
+    });
+    quote_spanned! {item_span=>
+        #item
+    }
+}
+
+fn generate_for_type_spec(
+    spec_type: SpecType,
+    attr: TokenStream,
+    tokens: TokenStream,
+) -> TokenStream {
+    let mut item: syn::Item = handle_result!(syn::parse2(tokens));
+    let item_span = item.span();
+    let attr_name = match spec_type {
+        SpecType::Invariant => "invariant",
+        SpecType::HistoryInvariant => "history_invariant",
+        _ => unreachable!(),
+    };
+    let (ident, generics, attrs) = match &mut item {
+        syn::Item::Struct(item_struct) => {
+            (item_struct.ident.clone(), item_struct.generics.clone(), &mut item_struct.attrs)
+        }
+        syn::Item::Enum(item_enum) => {
+            (item_enum.ident.clone(), item_enum.generics.clone(), &mut item_enum.attrs)
+        }
+        _ => {
+            return syn::Error::new(
+                item_span,
+                format!(
+                    "the `#[{}]` attribute can only be attached to structs and enums",
+                    attr_name,
+                ),
+            ).to_compile_error();
+        }
+    };
+
+    let mut rewriter = rewriter::AstRewriter::new();
+    let spec_id = rewriter.generate_spec_id();
+    let spec_id_str = spec_id.to_string();
+    let assertion = handle_result!(rewriter.parse_assertion(spec_id, attr));
+    let spec_item = match spec_type {
+        SpecType::Invariant => {
+            attrs.push(parse_quote_spanned! {item_span=>
+                #[prusti::inv_spec_id_ref = #spec_id_str]
+            });
+            rewriter.generate_spec_invariant_fn(spec_id, assertion, &ident)
+        }
+        SpecType::HistoryInvariant => {
+            attrs.push(parse_quote_spanned! {item_span=>
+                #[prusti::hist_inv_spec_id_ref = #spec_id_str]
+            });
+            rewriter.generate_spec_history_invariant_fn(spec_id, assertion, &ident)
+        }
+        _ => unreachable!(),
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote_spanned! {item_span=>
+        #item
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #spec_item
+        }
+    }
+}
+
 pub fn refine_trait_spec(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
     let mut impl_block: syn::ItemImpl = handle_result!(syn::parse2(tokens));
     let mut new_items = Vec::new();
     let mut generated_spec_items = Vec::new();
+    // The spec items are moved to an inherent impl, where `Self::Item` has to
+    // name the trait to refer to the associated types of the implementation.
+    let assoc_types = impl_block.items.iter().filter_map(|item| match item {
+        syn::ImplItem::Type(assoc_type) => Some(assoc_type.ident.clone()),
+        _ => None,
+    }).collect();
+    let mut assoc_type_rewriter = impl_block.trait_.as_ref().map(|(_, trait_path, _)| {
+        AssocTypeRewriter::new(trait_path.clone(), assoc_types)
+    });
     for item in impl_block.items {
         match item {
             syn::ImplItem::Method(method) => {
                 let mut method_item = untyped::AnyFnItem::ImplMethod(method);
+                handle_result!(cfg_attr_expander::expand_cfg_attrs(method_item.attrs_mut()));
                 let prusti_attributes: Vec<_> = extract_prusti_attributes(&mut method_item).collect();
                 let (spec_items, generated_attributes) = handle_result!(
                     generate_spec_and_assertions(prusti_attributes, &method_item)
                 );
                 generated_spec_items.extend(spec_items.into_iter().map(|spec_item| {
                     match spec_item {
-                        syn::Item::Fn(spec_item_fn) => {
+                        syn::Item::Fn(mut spec_item_fn) => {
+                            if let Some(rewriter) = assoc_type_rewriter.as_mut() {
+                                syn::visit_mut::visit_item_fn_mut(rewriter, &mut spec_item_fn);
+                            }
                             syn::ImplItem::Method(syn::ImplItemMethod {
                                 attrs: spec_item_fn.attrs,
                                 vis: spec_item_fn.vis,
@@ -422,7 +951,8 @@ pub fn refine_trait_spec(_attr: TokenStream, tokens: TokenStream) -> TokenStream
                 };
                 new_items.push(new_item);
             }
-            _ => {}
+            // Associated constants and types are kept, specifications can refer to them
+            item => new_items.push(item),
         }
     }
     impl_block.items = new_items;
@@ -500,6 +1030,7 @@ pub fn predicate(attr: TokenStream, tokens: TokenStream) -> TokenStream {
 
     // check for other attributes -- #[predicate] is incompatible with all others currently
     // make sure to also update the check in `rewrite_prusti_attributes`
+    handle_result!(cfg_attr_expander::expand_cfg_attrs(item.attrs_mut()));
     let other_prusti_attrs = extract_prusti_attributes(&mut item);
     if other_prusti_attrs.count() != 0 {
         return syn::Error::new(
@@ -0,0 +1,58 @@
+use proc_macro2::TokenStream;
+use syn::parse::{Parse, ParseStream};
+
+/// The arguments of `#[refine_spec(where T: A, U: B, [requires(..), ensures(..)])]`.
+pub(crate) struct RefineSpec {
+    pub where_clause: syn::WhereClause,
+    /// The kind (e.g. `requires`) and the arguments of each specification.
+    pub specs: Vec<(syn::Ident, TokenStream)>,
+}
+
+impl Parse for RefineSpec {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // `syn::WhereClause` cannot be parsed directly because it would try to
+        // parse the bracketed specifications as a bound.
+        let mut where_clause = syn::WhereClause {
+            where_token: input.parse()?,
+            predicates: syn::punctuated::Punctuated::new(),
+        };
+        while !input.peek(syn::token::Bracket) {
+            where_clause.predicates.push_value(input.parse()?);
+            where_clause.predicates.push_punct(input.parse()?);
+        }
+        if where_clause.predicates.is_empty() {
+            return Err(syn::Error::new(
+                input.span(),
+                "`#[refine_spec]` expects at least one trait bound",
+            ));
+        }
+
+        let content;
+        let brackets = syn::bracketed!(content in input);
+        let mut specs = vec![];
+        while !content.is_empty() {
+            let kind: syn::Ident = content.parse()?;
+            let arguments;
+            syn::parenthesized!(arguments in content);
+            specs.push((kind, arguments.parse()?));
+            if !content.is_empty() {
+                content.parse::<syn::Token![,]>()?;
+            }
+        }
+        if specs.is_empty() {
+            return Err(syn::Error::new(
+                brackets.span,
+                "`#[refine_spec]` expects at least one specification",
+            ));
+        }
+
+        if !input.is_empty() {
+            return Err(syn::Error::new(input.span(), "unexpected tokens after the specifications"));
+        }
+
+        Ok(RefineSpec {
+            where_clause,
+            specs,
+        })
+    }
+}
@@ -15,6 +15,7 @@ pub enum SpecItemType {
     Precondition,
     Postcondition,
     Predicate,
+    Termination,
 }
 
 impl std::fmt::Display for SpecItemType {
@@ -23,6 +24,7 @@ impl std::fmt::Display for SpecItemType {
             SpecItemType::Precondition => write!(f, "pre"),
             SpecItemType::Postcondition => write!(f, "post"),
             SpecItemType::Predicate => write!(f, "pred"),
+            SpecItemType::Termination => write!(f, "term"),
         }
     }
 }
@@ -48,6 +50,15 @@ impl AstRewriter {
         untyped::Assertion::parse(tokens, spec_id, &mut self.expr_id_generator)
     }
 
+    /// Parse a termination measure.
+    pub fn parse_measure(
+        &mut self,
+        spec_id: untyped::SpecificationId,
+        tokens: TokenStream,
+    ) -> syn::Result<untyped::Assertion> {
+        untyped::Assertion::parse_measure(tokens, spec_id, &mut self.expr_id_generator)
+    }
+
     /// Parse a pledge.
     pub fn parse_pledge(
         &mut self,
@@ -77,9 +88,15 @@ impl AstRewriter {
         None
     }
     fn generate_result_arg(&self, item: &untyped::AnyFnItem) -> syn::FnArg {
-        let item_span = item.span();
+        // `result` gets the syntax context of the `fn` token, so that it can be used by the
+        // specifications of functions produced by a `macro_rules!` macro
+        let item_span = item.sig().fn_token.span;
         let output_ty = match &item.sig().output {
             syn::ReturnType::Default => parse_quote_spanned!(item_span=> ()),
+            // The never type can only be named as the return type of a function
+            syn::ReturnType::Type(_, box syn::Type::Never(_)) => {
+                parse_quote_spanned!(item_span=> <fn() -> ! as ::prusti_contracts::FnOutput>::Output)
+            }
             syn::ReturnType::Type(_, ty) => ty.clone(),
         };
         let fn_arg = syn::FnArg::Typed(
@@ -93,9 +110,10 @@ impl AstRewriter {
         fn_arg
     }
 
-    /// Generate a dummy function for checking the given precondition, postcondition or predicate.
+    /// Generate a dummy function for checking the given precondition, postcondition, predicate
+    /// or termination measure.
     ///
-    /// `spec_type` should be either `"pre"`, `"post"` or `"pred"`.
+    /// `spec_type` should be either `"pre"`, `"post"`, `"pred"` or `"term"`.
     pub fn generate_spec_item_fn(
         &mut self,
         spec_type: SpecItemType,
@@ -103,6 +121,61 @@ impl AstRewriter {
         assertion: untyped::Assertion,
         item: &untyped::AnyFnItem,
     ) -> syn::Result<syn::Item> {
+        let assertion_json = crate::specifications::json::to_json_string(&assertion);
+        let spec_item = self.generate_spec_item_fn_with_json(
+            spec_type,
+            spec_id,
+            &assertion,
+            assertion_json,
+            item,
+        )?;
+        Ok(syn::Item::Fn(spec_item))
+    }
+
+    /// Generate a dummy function for checking the given precondition or postcondition, which
+    /// only holds for the instantiations of the generics of `item` that satisfy the bounds of
+    /// `where_clause`. The bounds are added to the generics of the dummy function, which is
+    /// the item that the type condition of the specification refers to.
+    pub fn generate_type_cond_spec_item_fn(
+        &mut self,
+        spec_type: SpecItemType,
+        spec_id: untyped::SpecificationId,
+        assertion: untyped::Assertion,
+        where_clause: &syn::WhereClause,
+        item: &untyped::AnyFnItem,
+    ) -> syn::Result<syn::Item> {
+        let type_cond_id = self.expr_id_generator.generate();
+        let assertion_json = crate::specifications::json::to_type_cond_json_string(
+            spec_id,
+            type_cond_id,
+            &assertion,
+        );
+        let mut spec_item = self.generate_spec_item_fn_with_json(
+            spec_type,
+            spec_id,
+            &assertion,
+            assertion_json,
+            item,
+        )?;
+        let type_cond_id_str = format!("{}_{}", spec_id, type_cond_id);
+        spec_item.attrs.push(parse_quote_spanned! {item.span()=>
+            #[prusti::expr_id = #type_cond_id_str]
+        });
+        spec_item.sig.generics
+            .make_where_clause()
+            .predicates
+            .extend(where_clause.predicates.iter().cloned());
+        Ok(syn::Item::Fn(spec_item))
+    }
+
+    fn generate_spec_item_fn_with_json(
+        &mut self,
+        spec_type: SpecItemType,
+        spec_id: untyped::SpecificationId,
+        assertion: &untyped::Assertion,
+        assertion_json: String,
+        item: &untyped::AnyFnItem,
+    ) -> syn::Result<syn::ItemFn> {
         if let Some(span) = self.check_contains_keyword_in_params(item, "result") {
             return Err(syn::Error::new(
                 span,
@@ -117,7 +190,6 @@ impl AstRewriter {
         let mut statements = TokenStream::new();
         assertion.encode_type_check(&mut statements);
         let spec_id_str = spec_id.to_string();
-        let assertion_json = crate::specifications::json::to_json_string(&assertion);
 
         let mut spec_item: syn::ItemFn = parse_quote_spanned! {item_span=>
             #[allow(unused_must_use, unused_variables, dead_code)]
@@ -134,7 +206,65 @@ impl AstRewriter {
             let fn_arg = self.generate_result_arg(item);
             spec_item.sig.inputs.push(fn_arg);
         }
-        Ok(syn::Item::Fn(spec_item))
+        Ok(spec_item)
+    }
+
+    /// Generate a method for checking the given type invariant. The method takes the value
+    /// of the type as `self`, which is what the invariant refers to.
+    pub fn generate_spec_invariant_fn(
+        &mut self,
+        spec_id: untyped::SpecificationId,
+        assertion: untyped::Assertion,
+        type_ident: &syn::Ident,
+    ) -> syn::ImplItemMethod {
+        let item_span = type_ident.span();
+        let item_name = syn::Ident::new(
+            &format!("prusti_inv_item_{}_{}", type_ident, spec_id),
+            item_span,
+        );
+        let receiver: syn::FnArg = parse_quote_spanned!(item_span=> self);
+        self.generate_spec_type_fn(spec_id, assertion, item_name, receiver)
+    }
+
+    /// Generate a method for checking the given history invariant. The method takes the value
+    /// of the type as `&mut self`, so that the invariant can relate `self` to `old(self)`
+    /// like the postcondition of a method that takes `&mut self`.
+    pub fn generate_spec_history_invariant_fn(
+        &mut self,
+        spec_id: untyped::SpecificationId,
+        assertion: untyped::Assertion,
+        type_ident: &syn::Ident,
+    ) -> syn::ImplItemMethod {
+        let item_span = type_ident.span();
+        let item_name = syn::Ident::new(
+            &format!("prusti_hist_inv_item_{}_{}", type_ident, spec_id),
+            item_span,
+        );
+        let receiver: syn::FnArg = parse_quote_spanned!(item_span=> &mut self);
+        self.generate_spec_type_fn(spec_id, assertion, item_name, receiver)
+    }
+
+    fn generate_spec_type_fn(
+        &mut self,
+        spec_id: untyped::SpecificationId,
+        assertion: untyped::Assertion,
+        item_name: syn::Ident,
+        receiver: syn::FnArg,
+    ) -> syn::ImplItemMethod {
+        let item_span = item_name.span();
+        let mut statements = TokenStream::new();
+        assertion.encode_type_check(&mut statements);
+        let spec_id_str = spec_id.to_string();
+        let assertion_json = crate::specifications::json::to_json_string(&assertion);
+        parse_quote_spanned! {item_span=>
+            #[allow(unused_must_use, unused_variables, dead_code)]
+            #[prusti::spec_only]
+            #[prusti::spec_id = #spec_id_str]
+            #[prusti::assertion = #assertion_json]
+            fn #item_name(#receiver) {
+                #statements
+            }
+        }
     }
 
     /// Generate statements for checking the given loop invariant.
@@ -162,6 +292,34 @@ impl AstRewriter {
         }
     }
 
+    /// Generate statements for checking a ghost assertion or assumption. The
+    /// generated closure is marked with `kind_attr`.
+    pub fn generate_spec_ghost_statement(
+        &mut self,
+        spec_id: untyped::SpecificationId,
+        assertion: untyped::Assertion,
+        kind_attr: &str,
+    ) -> TokenStream {
+        let mut statements = TokenStream::new();
+        assertion.encode_type_check(&mut statements);
+        let spec_id_str = spec_id.to_string();
+        let assertion_json = crate::specifications::json::to_json_string(&assertion);
+        let callsite_span = Span::call_site();
+        let kind_attr = format_ident!("{}", kind_attr);
+        quote_spanned! {callsite_span=>
+            #[allow(unused_must_use, unused_variables)]
+            {
+                #[prusti::spec_only]
+                #[prusti::#kind_attr]
+                #[prusti::spec_id = #spec_id_str]
+                #[prusti::assertion = #assertion_json]
+                || {
+                    #statements
+                };
+            }
+        }
+    }
+
     /// Generate statements for checking a closure specification.
     /// TODO: arguments, result (types are typically not known yet after parsing...)
     pub fn generate_cl_spec(
@@ -10,6 +10,16 @@ pub enum SpecAttributeKind {
     Pure,
     Trusted,
     Predicate,
+    AllowSpecWarnings,
+    Decreases,
+    Bitvector,
+    TrustedUnsafe,
+    RefineSpec,
+    NoInvariant,
+    PanicFree,
+    Fuel,
+    Assigns,
+    Bounded,
 }
 
 impl TryFrom<String> for SpecAttributeKind {
@@ -24,6 +34,16 @@ impl TryFrom<String> for SpecAttributeKind {
             "pure" => Ok(SpecAttributeKind::Pure),
             "trusted" => Ok(SpecAttributeKind::Trusted),
             "predicate" => Ok(SpecAttributeKind::Predicate),
+            "allow_spec_warnings" => Ok(SpecAttributeKind::AllowSpecWarnings),
+            "decreases" => Ok(SpecAttributeKind::Decreases),
+            "bitvector" => Ok(SpecAttributeKind::Bitvector),
+            "trusted_unsafe" => Ok(SpecAttributeKind::TrustedUnsafe),
+            "refine_spec" => Ok(SpecAttributeKind::RefineSpec),
+            "no_invariant" => Ok(SpecAttributeKind::NoInvariant),
+            "panic_free" => Ok(SpecAttributeKind::PanicFree),
+            "fuel" => Ok(SpecAttributeKind::Fuel),
+            "assigns" => Ok(SpecAttributeKind::Assigns),
+            "bounded" => Ok(SpecAttributeKind::Bounded),
             _ => Err(name),
         }
     }
@@ -6,6 +6,7 @@
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 use std::fmt::{Display, Debug};
+use std::sync::atomic::{AtomicU64, Ordering};
 use uuid::Uuid;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -17,8 +18,16 @@ pub enum SpecType {
     Postcondition,
     /// Loop invariant or struct invariant
     Invariant,
+    /// History invariant of a struct, preserved by the methods that take `&mut self`
+    HistoryInvariant,
     /// Predicate
     Predicate,
+    /// Termination measure of a procedure
+    Termination,
+    /// Ghost assertion given by `prusti_assert!(..)`
+    GhostAssertion,
+    /// Ghost assumption given by `prusti_assume!(..)`
+    GhostAssumption,
 }
 
 #[derive(Debug)]
@@ -37,7 +46,11 @@ impl<'a> TryFrom<&'a str> for SpecType {
             "requires" => Ok(SpecType::Precondition),
             "ensures" => Ok(SpecType::Postcondition),
             "invariant" => Ok(SpecType::Invariant),
+            "history_invariant" => Ok(SpecType::HistoryInvariant),
             "predicate" => Ok(SpecType::Predicate),
+            "decreases" => Ok(SpecType::Termination),
+            "prusti_assert" => Ok(SpecType::GhostAssertion),
+            "prusti_assume" => Ok(SpecType::GhostAssumption),
             _ => Err(TryFromStringError::UnknownSpecificationType),
         }
     }
@@ -60,6 +73,7 @@ pub enum SpecIdRef {
         rhs: SpecificationId,
     },
     Predicate(SpecificationId),
+    Termination(SpecificationId),
 }
 
 impl Display for SpecificationId {
@@ -85,6 +99,15 @@ impl SpecificationId {
     }
 }
 
+/// Returns a fresh UUID. The UUIDs are taken from a counter shared by all
+/// generators of the process instead of being random, so that expanding the
+/// same crate twice results in the same identifiers (and in the same Viper
+/// program). As a consequence, they are only unique within a crate.
+fn next_uuid() -> Uuid {
+    static NEXT_UUID: AtomicU64 = AtomicU64::new(1);
+    Uuid::from_u128(NEXT_UUID.fetch_add(1, Ordering::Relaxed) as u128)
+}
+
 pub(crate) struct SpecificationIdGenerator {}
 
 impl SpecificationIdGenerator {
@@ -92,7 +115,7 @@ impl SpecificationIdGenerator {
         Self {}
     }
     pub(crate) fn generate(&mut self) -> SpecificationId {
-        SpecificationId(Uuid::new_v4())
+        SpecificationId(next_uuid())
     }
 }
 
@@ -143,13 +166,13 @@ impl NameGenerator {
                 return Err("expected a path".to_string());
             }
         };
-        let uuid = Uuid::new_v4().to_simple();
+        let uuid = next_uuid().to_simple();
 
         Ok(format!("PrustiStruct{}{}", path_str, uuid))
     }
 
     pub(crate) fn generate_mod_name(&self, ident: &syn::Ident) -> String {
-        let uuid = Uuid::new_v4().to_simple();
+        let uuid = next_uuid().to_simple();
         format!("{}{}", ident.to_string(), uuid)
     }
 }
@@ -219,7 +242,20 @@ impl<EID, ET> IntoIterator for TriggerSet<EID, ET> {
 }
 
 #[derive(Debug, Clone)]
-/// Variables used in a forall.
+/// The pattern of an enum variant test `matches!(expr, Pattern)`.
+pub struct VariantPattern<EID, ET> {
+    /// Identifier of the specification to which this pattern belongs.
+    pub spec_id: SpecificationId,
+    /// Unique identifier.
+    pub id: EID,
+    /// The whole variant test, which is type-checked to resolve the pattern.
+    pub test: ET,
+    /// Index of the tested variant, known only after type-checking.
+    pub variant_index: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+/// Variables used in a forall or exists.
 pub struct ForAllVars<EID, AT> {
     /// Identifier of the specification to which these variables belongs.
     pub spec_id: SpecificationId,
@@ -229,6 +265,17 @@ pub struct ForAllVars<EID, AT> {
     pub vars: Vec<AT>,
 }
 
+#[derive(Debug, Clone)]
+/// The trait bounds of a type-conditional assertion.
+pub struct TypeCondition<EID, ET> {
+    /// Identifier of the specification to which this condition belongs.
+    pub spec_id: SpecificationId,
+    /// Unique identifier.
+    pub id: EID,
+    /// The item whose where clause contains the bounds.
+    pub item: ET,
+}
+
 #[derive(Debug, Clone)]
 /// Variables used in a specification entailment.
 pub struct SpecEntailmentVars<EID, AT> {
@@ -252,14 +299,21 @@ pub enum AssertionKind<EID, ET, AT> {
     And(Vec<Assertion<EID, ET, AT>>),
     /// Implication ==>
     Implies(Assertion<EID, ET, AT>, Assertion<EID, ET, AT>),
-    /// TODO < Even > ==> x % 2 == 0
-    TypeCond(ForAllVars<EID, AT>, Assertion<EID, ET, AT>),
+    /// Type-conditional assertion, which holds only for the instantiations of the generics
+    /// that satisfy some trait bounds (see `#[refine_spec(where T: A, [..])]`)
+    TypeCond(TypeCondition<EID, ET>, Assertion<EID, ET, AT>),
     /// Quantifier
     ForAll(
         ForAllVars<EID, AT>,
         TriggerSet<EID, ET>,
         Assertion<EID, ET, AT>,
     ),
+    /// Existential quantifier
+    Exists(
+        ForAllVars<EID, AT>,
+        TriggerSet<EID, ET>,
+        Assertion<EID, ET, AT>,
+    ),
     /// Specification entailment
     SpecEntailment {
         closure: Expression<EID, ET>,
@@ -267,6 +321,11 @@ pub enum AssertionKind<EID, ET, AT> {
         pres: Vec<Assertion<EID, ET, AT>>,
         posts: Vec<Assertion<EID, ET, AT>>,
     },
+    /// Enum variant test `matches!(expr, Pattern)`
+    Matches(Expression<EID, ET>, VariantPattern<EID, ET>),
+    /// Termination measure `decreases(e1, .., en)`, compared lexicographically. This is not
+    /// a boolean assertion; it is only used as the body of a `#[decreases]` specification.
+    Measure(Vec<Expression<EID, ET>>),
 }
 
 #[derive(Debug, Clone)]
@@ -295,17 +354,19 @@ pub struct Specification<EID, ET, AT> {
 pub struct LoopSpecification<EID, ET, AT> {
     /// Loop invariant.
     pub invariant: Vec<Assertion<EID, ET, AT>>,
+    /// Termination measure given by `body_invariant!(decreases(..))`.
+    pub variant: Option<Assertion<EID, ET, AT>>,
 }
 
 impl<EID, ET, AT> LoopSpecification<EID, ET, AT> {
     pub fn new(invariant: Vec<Assertion<EID, ET, AT>>) -> Self {
-        Self { invariant }
+        Self { invariant, variant: None }
     }
     pub fn empty() -> Self {
         Self::new(Vec::new())
     }
     pub fn is_empty(&self) -> bool {
-        self.invariant.is_empty()
+        self.invariant.is_empty() && self.variant.is_none()
     }
 }
 
@@ -324,6 +385,9 @@ pub struct ProcedureSpecification<EID, ET, AT> {
     /// otherwise.
     pub predicate_body: Option<Assertion<EID, ET, AT>>,
 
+    /// Termination measure given by a `#[decreases]` attribute.
+    pub decreases: Option<Assertion<EID, ET, AT>>,
+
     pub pure: bool,
     pub trusted: bool,
 }
@@ -340,6 +404,7 @@ impl<EID, ET, AT> ProcedureSpecification<EID, ET, AT> {
             posts,
             pledges,
             predicate_body,
+            decreases: None,
             pure: false,
             trusted: false,
         }
@@ -382,11 +447,17 @@ impl<EID: Clone + Debug, ET: Clone + Debug, AT: Clone + Debug> ProcedureSpecific
         } else {
             other.predicate_body.clone()
         };
+        let decreases = if other.decreases.is_none() {
+            self.decreases.clone()
+        } else {
+            other.decreases.clone()
+        };
         Self {
             pres,
             posts,
             pledges,
             predicate_body,
+            decreases,
             pure: other.pure,
             trusted: other.trusted,
         }
@@ -402,6 +473,8 @@ pub enum SpecificationSet<EID, ET, AT> {
     Loop(LoopSpecification<EID, ET, AT>),
     /// Struct invariant.
     Struct(Vec<Specification<EID, ET, AT>>),
+    /// Ghost statement in the body of a procedure.
+    Ghost(Specification<EID, ET, AT>),
 }
 
 impl<EID, ET, AT> SpecificationSet<EID, ET, AT> {
@@ -410,6 +483,7 @@ impl<EID, ET, AT> SpecificationSet<EID, ET, AT> {
             SpecificationSet::Procedure(spec) => spec.is_empty(),
             SpecificationSet::Loop(ref invs) => invs.is_empty(),
             SpecificationSet::Struct(ref invs) => invs.is_empty(),
+            SpecificationSet::Ghost(_) => false,
         }
     }
 }
@@ -446,4 +520,12 @@ impl<EID: Clone + Debug, ET: Clone + Debug, AT: Clone + Debug> SpecificationSet<
         }
         unreachable!("expected Struct: {:?}", self);
     }
+
+    #[track_caller]
+    pub fn expect_ghost(&self) -> &Specification<EID, ET, AT> {
+        if let SpecificationSet::Ghost(spec) = self {
+            return spec;
+        }
+        unreachable!("expected Ghost: {:?}", self);
+    }
 }
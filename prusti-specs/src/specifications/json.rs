@@ -12,13 +12,17 @@ pub enum AssertionKind {
     Expr(Expression),
     And(Vec<Assertion>),
     Implies(Assertion, Assertion),
+    TypeCond(TypeCondition, Assertion),
     ForAll(ForAllVars, Assertion, TriggerSet),
+    Exists(ForAllVars, Assertion, TriggerSet),
     SpecEntailment {
         closure: Expression,
         arg_binders: SpecEntailmentVars,
         pres: Vec<Assertion>,
         posts: Vec<Assertion>,
     },
+    Matches(Expression, VariantPattern),
+    Measure(Vec<Expression>),
 }
 
 #[derive(Serialize, Deserialize)]
@@ -29,6 +33,18 @@ pub struct Expression {
     pub expr_id: untyped::ExpressionId,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct VariantPattern {
+    pub spec_id: untyped::SpecificationId,
+    pub expr_id: untyped::ExpressionId,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TypeCondition {
+    pub spec_id: untyped::SpecificationId,
+    pub expr_id: untyped::ExpressionId,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ForAllVars {
     pub spec_id: untyped::SpecificationId,
@@ -59,6 +75,15 @@ impl untyped::Expression {
     }
 }
 
+impl untyped::VariantPattern {
+    fn to_structure(&self) -> VariantPattern {
+        VariantPattern {
+            spec_id: self.spec_id,
+            expr_id: self.id,
+        }
+    }
+}
+
 impl common::ForAllVars<untyped::ExpressionId, untyped::Arg> {
     fn to_structure(&self) -> ForAllVars {
         ForAllVars {
@@ -122,12 +147,24 @@ impl untyped::AssertionKind {
                 body.to_structure(),
                 triggers.to_structure(),
             ),
+            Exists(vars, triggers, body) => AssertionKind::Exists(
+                vars.to_structure(),
+                body.to_structure(),
+                triggers.to_structure(),
+            ),
             SpecEntailment {closure, arg_binders, pres, posts} => AssertionKind::SpecEntailment {
                 closure: closure.to_structure(),
                 arg_binders: arg_binders.to_structure(),
                 pres: pres.iter().map(|pre| pre.to_structure()).collect(),
                 posts: posts.iter().map(|post| post.to_structure()).collect(),
             },
+            Matches(expr, pattern) => AssertionKind::Matches(
+                expr.to_structure(),
+                pattern.to_structure(),
+            ),
+            Measure(components) => AssertionKind::Measure(
+                components.iter().map(|component| component.to_structure()).collect()
+            ),
             x => {
                 unimplemented!("{:?}", x);
             }
@@ -147,6 +184,23 @@ pub fn to_json_string(assertion: &untyped::Assertion) -> String {
     serde_json::to_string(&assertion.to_structure()).unwrap()
 }
 
+/// Serializes `assertion` wrapped in a type condition, whose bounds are those of the spec
+/// item with the expression id `type_cond_id`.
+pub fn to_type_cond_json_string(
+    spec_id: untyped::SpecificationId,
+    type_cond_id: untyped::ExpressionId,
+    assertion: &untyped::Assertion,
+) -> String {
+    let type_cond = TypeCondition {
+        spec_id,
+        expr_id: type_cond_id,
+    };
+    let assertion = Assertion {
+        kind: box AssertionKind::TypeCond(type_cond, assertion.to_structure()),
+    };
+    serde_json::to_string(&assertion).unwrap()
+}
+
 impl Assertion {
     pub fn from_json_string(json: &str) -> Self {
         serde_json::from_str(&json).unwrap()
@@ -1,5 +1,5 @@
 /// Each atomic Prusti assertion (`A`) is a Rust expression (`E`)
-/// or a `forall` expression. Atomic Prusti assertions can be joined together
+/// or a quantifier (`forall` or `exists`) expression. Atomic Prusti assertions can be joined together
 /// using the following two operators, forming Prusti assertions:
 /// - `A && A` (conjunction)
 /// - `A ==> A` (implication)
@@ -14,6 +14,8 @@
 /// `forall(|NAME1: TYPE1, NAME2: TYPE2, ...| A)`
 /// `forall(|NAME1: TYPE1, NAME2: TYPE2, ...| A, triggers=[(E, ...), ...])`
 ///
+/// `exists` expressions have the same syntax, with `exists` in place of `forall`.
+///
 /// Prusti assertions can only be joined together by `&&` and `==>`, for example
 /// the following is not allowed, since `(E ==> E)` is a Prusti assertion:
 /// `(E ==> E) || E`
@@ -2,7 +2,7 @@
 /// parses the resulting Rust expressions, and then assembles the composite
 /// Prusti assertion.
 
-use proc_macro2::{Delimiter, Group, Span, TokenStream, TokenTree};
+use proc_macro2::{Delimiter, Group, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
 use std::collections::VecDeque;
 use std::mem;
 use syn::parse::{ParseStream, Parse};
@@ -10,7 +10,7 @@ use syn::{self, Token, Error};
 use quote::quote;
 
 use super::common;
-use crate::specifications::common::{ForAllVars, SpecEntailmentVars, TriggerSet, Trigger};
+use crate::specifications::common::{ForAllVars, SpecEntailmentVars, TriggerSet, Trigger, VariantPattern};
 use syn::spanned::Spanned;
 
 pub type AssertionWithoutId = common::Assertion<(), syn::Expr, Arg>;
@@ -154,6 +154,21 @@ impl ParserStream {
             false
         }
     }
+    /// Check whether the input starts with an enum variant test `matches!(..)`
+    /// that is followed by a Prusti operator or nothing at all. Does not set
+    /// the span.
+    fn peek_variant_test(&self) -> bool {
+        matches!(self.tokens.get(0), Some(TokenTree::Ident(ident)) if ident == "matches")
+            && self.peek_operator_with_offset("!", 1)
+            && matches!(
+                self.tokens.get(2),
+                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis
+            )
+            && (self.tokens.len() == 3
+                || self.peek_operator_with_offset("==>", 3)
+                || self.peek_operator_with_offset("&&", 3)
+                || self.peek_operator_with_offset(",", 3))
+    }
     /// Check if the input starts with the operator and if yes, consume it
     /// and set the span to it.
     fn check_and_consume_operator(&mut self, operator: &str) -> bool {
@@ -252,7 +267,7 @@ impl ParserStream {
     }
 }
 
-/// The representation of an argument to `forall` (for example `a: i32`)
+/// The representation of an argument to `forall` or `exists` (for example `a: i32`)
 #[derive(Debug, Clone)]
 pub struct Arg {
     pub name: syn::Ident,
@@ -271,7 +286,7 @@ impl Parse for Arg {
     }
 }
 
-/// The representation of all arguments to `forall`
+/// The representation of all arguments to `forall` or `exists`
 /// (for example `a: i32, b: i32, c: i32`)
 #[derive(Debug)]
 struct ForAllArgs {
@@ -301,6 +316,49 @@ impl Parse for SpecEntArgs {
     }
 }
 
+/// The arguments of an enum variant test `matches!(expr, Pattern)`
+struct VariantTestArgs {
+    expr: syn::Expr,
+    pattern: syn::Pat,
+}
+
+impl Parse for VariantTestArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let expr = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let pattern = input.parse()?;
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+        }
+        Ok(Self { expr, pattern })
+    }
+}
+
+/// Checks whether the pattern only tests the variant of a value, i.e. it is
+/// a path or an identifier, or a tuple struct or struct pattern whose fields
+/// are all wildcards.
+fn is_variant_pattern(pattern: &syn::Pat) -> bool {
+    let is_wildcard = |pat: &syn::Pat| matches!(pat, syn::Pat::Wild(_) | syn::Pat::Rest(_));
+    match pattern {
+        syn::Pat::Path(_) => true,
+        syn::Pat::Ident(pat) => {
+            pat.by_ref.is_none() && pat.mutability.is_none() && pat.subpat.is_none()
+        }
+        syn::Pat::TupleStruct(pat) => pat.pat.elems.iter().all(is_wildcard),
+        syn::Pat::Struct(pat) => pat.fields
+            .iter()
+            .all(|field| field.colon_token.is_some() && is_wildcard(&field.pat)),
+        _ => false,
+    }
+}
+
+/// The kind of a quantifier (`forall` or `exists`).
+#[derive(Debug, Clone, Copy)]
+enum QuantifierKind {
+    ForAll,
+    Exists,
+}
+
 /// The structure to parse Prusti assertions.
 ///
 /// Check common::AssertionKind to see all types of Prusti assertions.
@@ -333,7 +391,9 @@ pub struct Parser {
 
 impl Parser {
     pub fn from_token_stream(tokens: TokenStream) -> Self {
-        let input = ParserStream::from_token_stream(tokens);
+        let input = ParserStream::from_token_stream(
+            parenthesize_cast_types(split_tuple_indices(tokens))
+        );
         Self {
             input,
             conjuncts: Vec::new(),
@@ -426,12 +486,12 @@ impl Parser {
             kind: box common::AssertionKind::Implies(lhs.unwrap(), rhs.unwrap())
         });
     }
-    fn resolve_forall(&mut self) -> syn::Result<()> {
+    fn resolve_quantifier(&mut self, kind: QuantifierKind) -> syn::Result<()> {
         if self.expected_operator {
             return Err(self.error_expected_operator());
         }
 
-        // check whether there is a parenthesized block after forall
+        // check whether there is a parenthesized block after the quantifier keyword
         if let Some(group) = self.input.check_and_consume_parenthesized_block() {
 
             // construct a ParserStream off of the parenthesized block for further parsing
@@ -507,16 +567,16 @@ impl Parser {
                 trigger_set = TriggerSet(vec_of_triggers);
             }
 
+            let vars = ForAllVars {
+                spec_id: common::SpecificationId::dummy(),
+                id: (),
+                vars
+            };
             let conjunct = AssertionWithoutId {
-                kind: box common::AssertionKind::ForAll(
-                    ForAllVars {
-                        spec_id: common::SpecificationId::dummy(),
-                        id: (),
-                        vars
-                    },
-                    trigger_set,
-                    body,
-                )
+                kind: box match kind {
+                    QuantifierKind::ForAll => common::AssertionKind::ForAll(vars, trigger_set, body),
+                    QuantifierKind::Exists => common::AssertionKind::Exists(vars, trigger_set, body),
+                }
             };
 
             self.conjuncts.push(conjunct);
@@ -627,6 +687,45 @@ impl Parser {
             return Err(self.error_expected_bracket());
         }
     }
+    fn resolve_variant_test(&mut self) -> syn::Result<()> {
+        // consume `matches`, `!`, and the parenthesized arguments
+        let tokens: Vec<TokenTree> = (0..3).map(|_| self.input.pop().unwrap()).collect();
+        let args = match &tokens[2] {
+            TokenTree::Group(group) => syn::parse2::<VariantTestArgs>(group.stream()),
+            _ => unreachable!(),
+        };
+        match args {
+            Ok(args) if is_variant_pattern(&args.pattern) => {
+                let test = syn::parse2(tokens.into_iter().collect())?;
+                let conjunct = AssertionWithoutId {
+                    kind: box common::AssertionKind::Matches(
+                        ExpressionWithoutId {
+                            spec_id: common::SpecificationId::dummy(),
+                            id: (),
+                            expr: args.expr,
+                        },
+                        VariantPattern {
+                            spec_id: common::SpecificationId::dummy(),
+                            id: (),
+                            test,
+                            variant_index: None,
+                        },
+                    )
+                };
+
+                self.conjuncts.push(conjunct);
+                self.previous_expression_resolved = true;
+                self.expected_only_operator = true;
+            }
+            _ => {
+                // patterns with bindings or guards are left to the Rust
+                // macro, so we parse the test as a Rust expression
+                self.expr.extend(tokens);
+            }
+        }
+        self.expected_operator = true;
+        Ok(())
+    }
     fn resolve_parenthesized_block(&mut self, group: Group) -> syn::Result<()>{
         // handling a parenthesized block
         if self.expected_only_operator {
@@ -692,7 +791,21 @@ impl Parser {
                 return self.resolve_implies();
             }
             else if self.input.check_and_consume_keyword("forall") {
-                if let Err(err) = self.resolve_forall() {
+                if let Err(err) = self.resolve_quantifier(QuantifierKind::ForAll) {
+                    return Err(err);
+                }
+            }
+            // `exists` is only a keyword at the start of an expression, so that
+            // method calls such as `path.exists()` are still parsed as Rust
+            else if !self.expected_operator && self.input.check_and_consume_keyword("exists") {
+                if let Err(err) = self.resolve_quantifier(QuantifierKind::Exists) {
+                    return Err(err);
+                }
+            }
+            // `matches!(expr, Pattern)` is only a variant test if it forms a
+            // whole conjunct; otherwise it is an ordinary Rust macro call
+            else if !self.expected_operator && self.input.peek_variant_test() {
+                if let Err(err) = self.resolve_variant_test() {
                     return Err(err);
                 }
             }
@@ -737,7 +850,9 @@ impl Parser {
             }
             return Err(err);
         }
-        maybe_expr
+        let mut expr = maybe_expr?;
+        check_labelled_old_calls(&mut expr)?;
+        Ok(expr)
     }
     fn extract_rust_expression(&mut self) -> syn::Result<ExpressionWithoutId> {
         let expr = self.expr.clone();
@@ -855,3 +970,158 @@ impl Parser {
         syn::Error::new(self.input.span, "a quantifier must have at least one argument")
     }
 }
+
+/// Returns the name of a label given to `label!(..)` or `labelled_old(..)`,
+/// which has to be a valid identifier.
+pub fn parse_label_name(label: &syn::LitStr) -> syn::Result<String> {
+    let name = label.value();
+    let mut chars = name.chars();
+    let is_identifier = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_identifier {
+        Ok(name)
+    } else {
+        Err(Error::new(label.span(), format!("`{}` is not a valid label name", name)))
+    }
+}
+
+/// Checks that the `labelled_old(..)` calls of an expression name their label
+/// with a string literal.
+/// Split the float literals that are used as nested tuple indices, so that
+/// `result.1.0` is re-emitted as the field accesses `result.1` and `.0` instead
+/// of the tokens `result`, `.` and `1.0`. The ranges (e.g. `0.5..1.5`) are
+/// left untouched.
+fn split_tuple_indices(tokens: TokenStream) -> TokenStream {
+    let mut result: Vec<TokenTree> = vec![];
+    for token in tokens {
+        match token {
+            TokenTree::Group(group) => {
+                let mut new_group = Group::new(
+                    group.delimiter(),
+                    split_tuple_indices(group.stream()),
+                );
+                new_group.set_span(group.span());
+                result.push(TokenTree::Group(new_group));
+            }
+            TokenTree::Literal(literal) if is_field_access(&result) => {
+                let text = literal.to_string();
+                let indices: Vec<_> = text.split('.').collect();
+                if indices.len() == 2
+                    && indices.iter().all(|index| {
+                        !index.is_empty() && index.chars().all(|c| c.is_ascii_digit())
+                    })
+                {
+                    for (i, index) in indices.iter().enumerate() {
+                        if i > 0 {
+                            let mut dot = Punct::new('.', Spacing::Alone);
+                            dot.set_span(literal.span());
+                            result.push(TokenTree::Punct(dot));
+                        }
+                        let mut new_literal = Literal::usize_unsuffixed(index.parse().unwrap());
+                        new_literal.set_span(literal.span());
+                        result.push(TokenTree::Literal(new_literal));
+                    }
+                } else {
+                    result.push(TokenTree::Literal(literal));
+                }
+            }
+            token => result.push(token),
+        }
+    }
+    result.into_iter().collect()
+}
+
+/// Wrap in parentheses the primitive target type of the casts that are
+/// followed by `<`, so that `x as i64 < y` is re-emitted as `x as (i64) < y`.
+/// Otherwise, Rust parses the `<` as the start of the generic arguments of the
+/// type. The parentheses get the span of the macro call, so that the
+/// `unused_parens` lint does not warn about them.
+fn parenthesize_cast_types(tokens: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut result: Vec<TokenTree> = vec![];
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Group(group) => {
+                let mut new_group = Group::new(
+                    group.delimiter(),
+                    parenthesize_cast_types(group.stream()),
+                );
+                new_group.set_span(group.span());
+                result.push(TokenTree::Group(new_group));
+            }
+            TokenTree::Ident(ident) if is_cast(&result)
+                && is_numeric_type(ident)
+                && is_less_than(tokens.get(i + 1)) =>
+            {
+                let ty = TokenTree::Ident(ident.clone());
+                result.push(TokenTree::Group(
+                    Group::new(Delimiter::Parenthesis, ty.into())
+                ));
+            }
+            token => result.push(token.clone()),
+        }
+    }
+    result.into_iter().collect()
+}
+
+/// Check whether the tokens end with the keyword `as`.
+fn is_cast(tokens: &[TokenTree]) -> bool {
+    matches!(tokens.last(), Some(TokenTree::Ident(ident)) if ident == "as")
+}
+
+fn is_numeric_type(ident: &proc_macro2::Ident) -> bool {
+    const NUMERIC_TYPES: &[&str] = &[
+        "i8", "i16", "i32", "i64", "i128", "isize",
+        "u8", "u16", "u32", "u64", "u128", "usize",
+        "f32", "f64", "char",
+    ];
+    NUMERIC_TYPES.iter().any(|ty| ident == *ty)
+}
+
+fn is_less_than(token: Option<&TokenTree>) -> bool {
+    matches!(token, Some(TokenTree::Punct(punct)) if punct.as_char() == '<')
+}
+
+/// Check whether the tokens end with a single `.` (i.e. not with `..`).
+fn is_field_access(tokens: &[TokenTree]) -> bool {
+    let is_dot = |token: Option<&TokenTree>| matches!(
+        token,
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '.'
+    );
+    let len = tokens.len();
+    len >= 2
+        && is_dot(tokens.get(len - 1))
+        && !is_dot(tokens.get(len - 2))
+}
+
+fn check_labelled_old_calls(expr: &mut syn::Expr) -> syn::Result<()> {
+    struct LabelledOldChecker {
+        error: Option<Error>,
+    }
+    impl syn::visit_mut::VisitMut for LabelledOldChecker {
+        fn visit_expr_call_mut(&mut self, call: &mut syn::ExprCall) {
+            if let syn::Expr::Path(syn::ExprPath { path, .. }) = &*call.func {
+                let is_labelled_old = path.segments.last()
+                    .map_or(false, |segment| segment.ident == "labelled_old");
+                if is_labelled_old && self.error.is_none() {
+                    let result = match call.args.first() {
+                        Some(syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(label), .. }))
+                            if call.args.len() == 2 => parse_label_name(label).map(|_| ()),
+                        _ => Err(Error::new(
+                            call.span(),
+                            "expected `labelled_old(\"<label>\", <expression>)`",
+                        )),
+                    };
+                    self.error = result.err();
+                }
+            }
+            syn::visit_mut::visit_expr_call_mut(self, call);
+        }
+    }
+    let mut checker = LabelledOldChecker { error: None };
+    syn::visit_mut::VisitMut::visit_expr_mut(&mut checker, expr);
+    match checker.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
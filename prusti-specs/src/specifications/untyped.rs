@@ -29,6 +29,8 @@ pub type Expression = common::Expression<ExpressionId, syn::Expr>;
 pub type TriggerSet = common::TriggerSet<ExpressionId, syn::Expr>;
 /// A pledge that has not types associated with it.
 pub type Pledge = common::Pledge<ExpressionId, syn::Expr, Arg>;
+/// A variant pattern that has not types associated with it.
+pub type VariantPattern = common::VariantPattern<ExpressionId, syn::Expr>;
 
 /// An abstraction over all kinds of function items.
 pub enum AnyFnItem {
@@ -58,6 +60,14 @@ impl syn::parse::Parse for AnyFnItem {
 }
 
 impl AnyFnItem {
+    pub fn attrs(&self) -> &[syn::Attribute] {
+        match self {
+            AnyFnItem::Fn(item) => &item.attrs,
+            AnyFnItem::TraitMethod(item) => &item.attrs,
+            AnyFnItem::ImplMethod(item) => &item.attrs,
+        }
+    }
+
     pub fn attrs_mut(&mut self) -> &mut Vec<syn::Attribute> {
         match self {
             AnyFnItem::Fn(item) => &mut item.attrs,
@@ -103,6 +113,39 @@ impl Assertion {
         let assertion = parser.extract_assertion()?;
         Ok(assertion.assign_id(spec_id, id_generator))
     }
+
+    /// Parse the termination measure of a `#[decreases(..)]` attribute. Both
+    /// `decreases(a, b)` and `decreases((a, b))` denote a lexicographic measure.
+    pub(crate) fn parse_measure(
+        tokens: TokenStream,
+        spec_id: SpecificationId,
+        id_generator: &mut ExpressionIdGenerator,
+    ) -> syn::Result<Self> {
+        let span = tokens.span();
+        let parser = syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated;
+        let mut exprs: Vec<syn::Expr> = syn::parse::Parser::parse2(parser, tokens)?
+            .into_iter()
+            .collect();
+        if exprs.len() == 1 {
+            if let syn::Expr::Tuple(syn::ExprTuple { elems, .. }) = &exprs[0] {
+                exprs = elems.iter().cloned().collect();
+            }
+        }
+        if exprs.is_empty() {
+            return Err(syn::Error::new(
+                span,
+                "the `#[decreases]` attribute expects a termination measure",
+            ));
+        }
+        let components = exprs
+            .into_iter()
+            .map(|expr| common::Expression { spec_id: SpecificationId::dummy(), id: (), expr })
+            .collect();
+        let measure: common::Assertion<(), syn::Expr, Arg> = common::Assertion {
+            kind: box common::AssertionKind::Measure(components),
+        };
+        Ok(measure.assign_id(spec_id, id_generator))
+    }
 }
 
 impl Parse for common::Expression<(), syn::Expr> {
@@ -233,6 +276,21 @@ impl AssignExpressionId<SpecEntailmentVars<ExpressionId, Arg>> for common::SpecE
     }
 }
 
+impl AssignExpressionId<VariantPattern> for common::VariantPattern<(), syn::Expr> {
+    fn assign_id(
+        self,
+        spec_id: SpecificationId,
+        id_generator: &mut ExpressionIdGenerator,
+    ) -> VariantPattern {
+        VariantPattern {
+            spec_id,
+            id: id_generator.generate(),
+            test: self.test,
+            variant_index: self.variant_index,
+        }
+    }
+}
+
 impl AssignExpressionId<TriggerSet> for common::TriggerSet<(), syn::Expr> {
     fn assign_id(
         self,
@@ -277,6 +335,11 @@ impl AssignExpressionId<AssertionKind> for common::AssertionKind<(), syn::Expr,
                 triggers.assign_id(spec_id, id_generator),
                 body.assign_id(spec_id, id_generator)
             ),
+            Exists(vars, triggers, body) => Exists(
+                vars.assign_id(spec_id, id_generator),
+                triggers.assign_id(spec_id, id_generator),
+                body.assign_id(spec_id, id_generator)
+            ),
             SpecEntailment {closure, arg_binders, pres, posts} => SpecEntailment {
                 closure: closure.assign_id(spec_id, id_generator),
                 arg_binders: arg_binders.assign_id(spec_id, id_generator),
@@ -289,6 +352,15 @@ impl AssignExpressionId<AssertionKind> for common::AssertionKind<(), syn::Expr,
                          Assertion { kind: assertion.kind.assign_id(spec_id, id_generator) })
                      .collect(),
             },
+            Matches(expr, pattern) => Matches(
+                expr.assign_id(spec_id, id_generator),
+                pattern.assign_id(spec_id, id_generator)
+            ),
+            Measure(components) => Measure(
+                components.into_iter()
+                    .map(|component| component.assign_id(spec_id, id_generator))
+                    .collect()
+            ),
             x => unimplemented!("{:?}", x),
         }
     }
@@ -378,7 +450,8 @@ impl EncodeTypeCheck for Assertion {
                 lhs.encode_type_check(tokens);
                 rhs.encode_type_check(tokens);
             }
-            AssertionKind::ForAll(vars, triggers, body) => {
+            AssertionKind::ForAll(vars, triggers, body)
+            | AssertionKind::Exists(vars, triggers, body) => {
                 let vec_of_vars = &vars.vars;
                 let span = Span::call_site();
                 let identifier = format!("{}_{}", vars.spec_id, vars.id);
@@ -445,6 +518,43 @@ impl EncodeTypeCheck for Assertion {
                 };
                 tokens.extend(typeck_call);
             }
+            AssertionKind::Matches(expression, pattern) => {
+                // the matched expression is not a boolean expression; places
+                // are borrowed so that they are not moved into the closure
+                let span = expression.expr.span();
+                let expr = &expression.expr;
+                let identifier = format!("{}_{}", expression.spec_id, expression.id);
+                let matched = if is_place_expr(expr) {
+                    quote_spanned! { span => &(#expr) }
+                } else {
+                    quote_spanned! { span => #expr }
+                };
+                let typeck_call = quote_spanned! { span =>
+                    #[prusti::spec_only]
+                    #[prusti::expr_id = #identifier]
+                    || {
+                        #matched
+                    };
+                };
+                tokens.extend(typeck_call);
+                pattern.encode_type_check(tokens);
+            }
+            AssertionKind::Measure(components) => {
+                // the components of a measure are integers, not boolean expressions
+                for component in components {
+                    let span = component.expr.span();
+                    let expr = &component.expr;
+                    let identifier = format!("{}_{}", component.spec_id, component.id);
+                    let typeck_call = quote_spanned! { span =>
+                        #[prusti::spec_only]
+                        #[prusti::expr_id = #identifier]
+                        || {
+                            #expr
+                        };
+                    };
+                    tokens.extend(typeck_call);
+                }
+            }
             x => {
                 unimplemented!("{:?}", x);
             }
@@ -467,3 +577,32 @@ impl EncodeTypeCheck for Expression {
         tokens.extend(typeck_call);
     }
 }
+
+impl EncodeTypeCheck for VariantPattern {
+    fn encode_type_check(&self, tokens: &mut TokenStream) {
+        let span = self.test.span();
+        let test = &self.test;
+        let identifier = format!("{}_{}", self.spec_id, self.id);
+        let typeck_call = quote_spanned! { span =>
+            #[prusti::spec_only]
+            #[prusti::expr_id = #identifier]
+            || -> bool {
+                #test
+            };
+        };
+        tokens.extend(typeck_call);
+    }
+}
+
+/// Checks whether the expression is a place expression (a variable, field,
+/// index, or dereference), which can be borrowed rather than moved.
+fn is_place_expr(expr: &syn::Expr) -> bool {
+    match expr {
+        syn::Expr::Path(_) => true,
+        syn::Expr::Field(syn::ExprField { base, .. })
+        | syn::Expr::Index(syn::ExprIndex { expr: base, .. })
+        | syn::Expr::Paren(syn::ExprParen { expr: base, .. }) => is_place_expr(base),
+        syn::Expr::Unary(syn::ExprUnary { op: syn::UnOp::Deref(_), .. }) => true,
+        _ => false,
+    }
+}
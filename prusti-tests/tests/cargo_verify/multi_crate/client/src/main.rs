@@ -0,0 +1,7 @@
+use verified_lib::increment;
+
+fn main() {
+    // Only provable with the postcondition exported by `verified_lib`
+    let x = increment(41);
+    assert!(x == 42);
+}
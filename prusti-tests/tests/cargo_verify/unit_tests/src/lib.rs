@@ -0,0 +1,23 @@
+use prusti_contracts::*;
+
+#[requires(x < 1000)]
+#[ensures(result == 2 * x)]
+pub fn double(x: u32) -> u32 {
+    x + x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[ensures(result == 5)]
+    fn double_two() -> u32 {
+        double(2)
+    }
+
+    #[test]
+    fn test_double() {
+        assert!(double(3) == 6);
+        let _ = double_two();
+    }
+}
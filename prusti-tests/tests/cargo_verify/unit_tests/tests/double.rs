@@ -0,0 +1,6 @@
+use unit_tests::double;
+
+#[test]
+fn test_double() {
+    assert!(double(10) == 20);
+}
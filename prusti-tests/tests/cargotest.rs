@@ -4,7 +4,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use cargo_test_support::{cargo_test, project, symlink_supported};
+use cargo_test_support::{cargo_test, project, symlink_supported, Project};
 use std::path::{Path, PathBuf};
 use std::fs;
 
@@ -80,6 +80,226 @@ To learn more, run the command again with --verbose.
         .run();
 }
 
+#[cargo_test]
+fn deterministic_viper_program() {
+    let p = project()
+        .file("src/main.rs", r#"
+            struct Point { x: i32, y: i32 }
+            enum Shape { Circle(Point, u32), Rectangle(Point, Point), Empty }
+            fn area(shape: &Shape) -> u32 {
+                match shape {
+                    Shape::Circle(_, r) => if *r < 100 { 3 * r * r } else { 0 },
+                    Shape::Rectangle(a, b) => if a.x < b.x && a.y < b.y { 1 } else { 0 },
+                    Shape::Empty => 0,
+                }
+            }
+            fn first<T>(pair: (T, T)) -> T { pair.0 }
+            fn main() {
+                let shape = Shape::Circle(Point { x: 1, y: 2 }, 3);
+                let _ = area(&shape);
+                let x = first((1, 2));
+                assert!(x == x);
+            }
+        "#)
+        .build();
+    for log_dir in &["log_a", "log_b"] {
+        p.process("cargo").arg("clean").run();
+        p.process(cargo_prusti_path())
+            .env("PRUSTI_DUMP_VIPER_PROGRAM", "true")
+            .env("PRUSTI_LOG_DIR", log_dir)
+            .run();
+    }
+    let read_program = |log_dir: &str| {
+        let path = p.root().join(log_dir).join("viper_program").join("main.rs.vpr");
+        fs::read_to_string(&path)
+            .expect(&format!("Failed to read the Viper program {}", path.display()))
+    };
+    assert_eq!(read_program("log_a"), read_program("log_b"));
+}
+
+#[cargo_test]
+fn verification_cache() {
+    let p = project()
+        .file("src/main.rs", r#"
+            fn double(x: u32) -> u32 { if x < 1000 { 2 * x } else { 0 } }
+            fn main() { assert!(double(0) == double(0)); }
+        "#)
+        .build();
+    p.process(cargo_prusti_path()).run();
+    let cache_path = p.root().join("target").join("prusti").join("verification.cache");
+    let cache = fs::read_to_string(&cache_path)
+        .expect(&format!("Failed to read the verification cache {}", cache_path.display()));
+    assert!(cache.starts_with("prusti-verification-cache 1\n"));
+    assert!(cache.lines().count() > 1);
+
+    // A changed procedure has to be verified again
+    p.change_file("src/main.rs", r#"
+            fn double(x: u32) -> u32 { if x < 1000 { 2 * x } else { 0 } }
+            fn main() { assert!(double(0) == 1); }
+        "#);
+    p.process(cargo_prusti_path())
+        .with_status(101)
+        .with_stderr_contains("[ERROR] [Prusti: verification error] the asserted expression might not hold")
+        .run();
+}
+
+#[cargo_test]
+fn diff_mode() {
+    let p = project()
+        .file("src/main.rs", r#"
+            mod utils;
+            fn main() { utils::check(); }
+        "#)
+        .file("src/utils.rs", r#"
+            pub fn check() { assert!(true); }
+        "#)
+        .build();
+    // Without a previous run, everything is verified
+    p.process(cargo_prusti_path()).arg("--diff").run();
+
+    // A changed file has to be verified again
+    p.change_file("src/utils.rs", r#"
+            pub fn check() { assert!(false); }
+        "#);
+    p.process(cargo_prusti_path())
+        .arg("--diff")
+        .with_status(101)
+        .with_stderr_contains("[ERROR] [Prusti: verification error] the asserted expression might not hold")
+        .run();
+
+    // The caller in the unchanged file is not verified again
+    p.change_file("src/utils.rs", r#"
+            pub fn check() { assert!(true); }
+        "#);
+    p.process(cargo_prusti_path())
+        .arg("--diff")
+        .with_stderr_contains("Prusti: skipped the verification of 1 items not affected by the changes")
+        .run();
+
+    p.process(cargo_prusti_path())
+        .arg("--diff")
+        .with_stderr_contains("Prusti: no files changed since the last run")
+        .run();
+}
+
+#[cargo_test]
+fn diff_mode_keeps_callee_contracts() {
+    let p = project()
+        .file("src/main.rs", r#"
+            mod utils;
+            fn main() { let x = utils::zero(); assert!(x == x); }
+        "#)
+        .file("src/utils.rs", r#"
+            pub fn zero() -> u32 { 0 }
+        "#)
+        .build();
+    p.process(cargo_prusti_path()).arg("--diff").run();
+
+    // Only the callee is verified again, the caller keeps using its contract
+    p.change_file("src/utils.rs", r#"
+            pub fn zero() -> u32 { let x = 1; x - 1 }
+        "#);
+    p.process(cargo_prusti_path())
+        .arg("--diff")
+        .with_stderr_contains("Prusti: skipped the verification of 1 items not affected by the changes")
+        .run();
+}
+
+#[cargo_test]
+fn json_report() {
+    let p = project()
+        .file(
+            "src/main.rs",
+            "fn ok() { assert!(true); }\n\
+            fn fail() { assert!(false); }\n\
+            fn main() { ok(); fail(); }\n",
+        )
+        .build();
+    let report_path = p.root().join("report.json");
+    p.process(cargo_prusti_path())
+        .env("PRUSTI_JSON_REPORT", &report_path)
+        .with_status(101)
+        .run();
+    let report = fs::read_to_string(&report_path)
+        .expect(&format!("Failed to read the JSON report {}", report_path.display()));
+    let report: serde_json::Value = serde_json::from_str(&report)
+        .expect("The JSON report is not valid JSON");
+    assert_eq!(report["version"], 1);
+    assert_eq!(report["crate_name"], "foo");
+    assert!(report["backend"]["name"].is_string());
+    assert!(report["backend"]["verification_time_ms"].is_u64());
+    let items = report["items"].as_array().expect("The report has no items");
+    assert_eq!(items.len(), 3);
+    let item = |name: &str| {
+        items.iter()
+            .find(|item| item["name"].as_str().unwrap().ends_with(name))
+            .expect(&format!("The report has no item {}", name))
+    };
+    for name in &["ok", "main"] {
+        assert_eq!(item(name)["result"], "verified");
+        assert_eq!(item(name)["error_count"], 0);
+        assert!(item(name)["encoding_time_ms"].is_u64());
+    }
+    let fail = item("fail");
+    assert_eq!(fail["result"], "failed");
+    assert_eq!(fail["error_count"], 1);
+    let error = &fail["errors"][0];
+    assert!(error["message"].as_str().unwrap().contains("the asserted expression might not hold"));
+    let span = &error["spans"][0];
+    assert!(span["file"].as_str().unwrap().ends_with("main.rs"));
+    assert_eq!(span["line"], 2);
+    assert_eq!(span["column"], 13);
+}
+
+#[cargo_test]
+fn exported_specs_mismatch() {
+    let p = project()
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["lib", "app"]
+        "#)
+        .file("lib/Cargo.toml", r#"
+            [package]
+            name = "lib"
+            version = "0.1.0"
+        "#)
+        .file("lib/src/lib.rs", "pub fn zero() -> u32 { 0 }")
+        .file("app/Cargo.toml", r#"
+            [package]
+            name = "app"
+            version = "0.1.0"
+
+            [dependencies]
+            lib = { path = "../lib" }
+        "#)
+        .file("app/src/main.rs", "fn main() { assert!(lib::zero() == lib::zero()); }")
+        .build();
+    p.process(cargo_prusti_path()).run();
+
+    // The specifications of the library are exported next to its metadata
+    let deps_path = p.root().join("target").join("debug").join("deps");
+    let specs_path = fs::read_dir(&deps_path)
+        .expect(&format!("Failed to read directory {}", deps_path.display()))
+        .map(|entry| entry.expect("Failed to read directory entry").path())
+        .find(|path| {
+            let file_name = path.file_name().unwrap().to_string_lossy();
+            file_name.starts_with("liblib-") && file_name.ends_with(".prusti-specs")
+        })
+        .expect("The specifications of the library have not been exported");
+
+    // Specifications exported by another version of Prusti are rejected
+    fs::write(&specs_path, r#"{"format_version":0}"#)
+        .expect(&format!("Failed to write {}", specs_path.display()));
+    p.change_file("app/src/main.rs", "fn main() { assert!(lib::zero() == 0 || true); }");
+    p.process(cargo_prusti_path())
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] [Prusti: invalid specification] the specifications of the crate `lib` \
+            were exported by an incompatible version of Prusti (format version 0, expected 1)"
+        )
+        .run();
+}
+
 /// Test `cargo-prusti` on one of the crates in `test/cargo_verify`.
 ///
 /// Special files and folders in the root of the test crate:
@@ -93,6 +313,26 @@ To learn more, run the command again with --verbose.
 /// For more details on the special syntax allowed in the `output.*` files, check the documentation
 /// of `cargo_test_support`: <https://doc.crates.io/contrib/tests/writing.html>.
 fn test_local_project<T: Into<PathBuf>>(project_name: T) {
+    let (project, project_path) = build_local_project(project_name);
+
+    // Set the expected exit status, stdout and stderr
+    let mut test_builder = project.process(cargo_prusti_path());
+    let opt_expected_stdout = fs::read_to_string(project_path.join("output.stdout")).ok();
+    let opt_expected_stderr = fs::read_to_string(project_path.join("output.stderr")).ok();
+    if let Some(ref expected_stdout) = opt_expected_stdout {
+        test_builder.with_stdout(expected_stdout);
+    }
+    if let Some(ref expected_stderr) = opt_expected_stderr {
+        test_builder.with_status(101).with_stderr(expected_stderr);
+    }
+
+    // Run the test
+    test_builder.run();
+}
+
+/// Prepare one of the crates in `test/cargo_verify` as described in `test_local_project`,
+/// returning the test project and the path of the original crate.
+fn build_local_project<T: Into<PathBuf>>(project_name: T) -> (Project, PathBuf) {
     let mut project_builder = project().no_manifest();
     let relative_project_path = Path::new("tests/cargo_verify").join(project_name.into());
     let project_path = fs::canonicalize(&relative_project_path).expect(
@@ -138,19 +378,7 @@ fn test_local_project<T: Into<PathBuf>>(project_name: T) {
     let project = project_builder.build();
     project.process("cargo").arg("build").run();
 
-    // Set the expected exit status, stdout and stderr
-    let mut test_builder = project.process(cargo_prusti_path());
-    let opt_expected_stdout = fs::read_to_string(project_path.join("output.stdout")).ok();
-    let opt_expected_stderr = fs::read_to_string(project_path.join("output.stderr")).ok();
-    if let Some(ref expected_stdout) = opt_expected_stdout {
-        test_builder.with_stdout(expected_stdout);
-    }
-    if let Some(ref expected_stderr) = opt_expected_stderr {
-        test_builder.with_status(101).with_stderr(expected_stderr);
-    }
-
-    // Run the test
-    test_builder.run();
+    (project, project_path)
 }
 
 #[cargo_test]
@@ -164,4 +392,24 @@ fn test_failing_crate() {
     test_local_project("failing_crate");
 }
 
+#[cargo_test]
+fn test_multi_crate() {
+    test_local_project("multi_crate");
+}
+
+#[cargo_test]
+fn test_unit_tests() {
+    let (project, _) = build_local_project("unit_tests");
+
+    // By default the unit tests are not verified
+    project.process(cargo_prusti_path()).run();
+
+    project.process(cargo_prusti_path())
+        .arg("--tests")
+        .with_status(101)
+        .with_stderr_contains("[ERROR] [Prusti: verification error] postcondition might not hold.")
+        .with_stderr_contains(" --> src/lib.rs:[..]")
+        .run();
+}
+
 // TODO: automatically create a test for each folder in `test/cargo_verify`.
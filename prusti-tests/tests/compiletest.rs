@@ -152,6 +152,24 @@ fn run_verification_core_proof(group_name: &str, filter: &Option<String>) {
     run_verification(group_name, filter);
 }
 
+/// The test directories of the `verify` group that are also verified with
+/// Carbon, to check that the encoding avoids what Carbon does not support.
+const CARBON_TEST_DIRS: &[&str] = &["quick/", "quantifiers/"];
+
+fn run_verification_carbon(group_name: &str, filter: &Option<String>) {
+    let _temporary_env_vars = (
+        TemporaryEnvVar::set("PRUSTI_VIPER_BACKEND", "Carbon"),
+    );
+
+    if filter.is_some() {
+        run_verification(group_name, filter);
+    } else {
+        for dir in CARBON_TEST_DIRS {
+            run_verification(group_name, &Some(dir.to_string()));
+        }
+    }
+}
+
 fn test_runner(_tests: &[&()]) {
     // Spawn server process as child (so it stays around until main function terminates)
     let server_address = ServerSideService::spawn_off_thread();
@@ -172,6 +190,10 @@ fn test_runner(_tests: &[&()]) {
     println!("[verify]");
     run_verification("verify", &filter);
 
+    // Test a subset of the verifier tests with the Carbon backend.
+    println!("[verify_carbon]");
+    run_verification_carbon("verify", &filter);
+
     // Test the verifier with overflow checks enabled.
     println!("[verify_overflow]");
     run_verification_overflow("verify_overflow", &filter);
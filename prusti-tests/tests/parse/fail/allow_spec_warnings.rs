@@ -0,0 +1,9 @@
+use prusti_contracts::*;
+
+#[allow_spec_warnings(unbounded_arithmetics)] //~ ERROR unknown specification warning `unbounded_arithmetics`
+#[ensures(result > 0)]
+fn wrong_warning() -> i32 {
+    1
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use prusti_contracts::*;
+
+struct State {
+    counter: u32,
+}
+
+#[assigns(s.counter)] //~ ERROR the assigned place must be rooted at a `&mut` parameter, but `s` is not one
+fn shared(s: &State) {}
+
+#[assigns(t.counter)] //~ ERROR the assigned place must be rooted at a `&mut` parameter, but `t` is not one
+fn unknown(s: &mut State) {}
+
+#[assigns(s.counter + 1)] //~ ERROR only a parameter or a (nested) field of a parameter can be assigned
+fn not_a_place(s: &mut State) {}
+
+fn main() {}
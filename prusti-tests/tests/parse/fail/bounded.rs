@@ -0,0 +1,9 @@
+use prusti_contracts::*;
+
+#[bounded(0)] //~ ERROR the loop bound of `#[bounded(..)]` has to be positive
+fn zero() {}
+
+#[bounded(n)] //~ ERROR the `#[bounded(..)]` attribute expects an integer literal
+fn not_a_literal(n: u32) {}
+
+fn main() {}
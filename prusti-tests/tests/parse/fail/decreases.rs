@@ -0,0 +1,14 @@
+use prusti_contracts::*;
+
+#[decreases(n)]
+#[decreases(n + 1)] //~ ERROR a function can have at most one `#[decreases]` attribute
+fn twice(n: u32) -> u32 {
+    n
+}
+
+#[decreases()] //~ ERROR the `#[decreases]` attribute expects a termination measure
+fn empty(n: u32) -> u32 {
+    n
+}
+
+fn main() {}
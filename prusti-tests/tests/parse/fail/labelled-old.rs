@@ -0,0 +1,11 @@
+use prusti_contracts::*;
+
+fn invalid_label() {
+    label!("not a label"); //~ ERROR `not a label` is not a valid label name
+}
+
+fn non_literal_label(name: &str, x: u32) {
+    prusti_assert!(labelled_old(name, x) == x); //~ ERROR expected `labelled_old("<label>", <expression>)`
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+// compile-flags: -Pprint_desugared_specs=true -Pno_verify=true -Phide_uuids=true
+// normalize-stdout-test: "[a-z0-9]{32}" -> "$(NUM_UUID)"
+// normalize-stdout-test: "[a-z0-9]{8}-[a-z0-9]{4}-[a-z0-9]{4}-[a-z0-9]{4}-[a-z0-9]{12}" -> "$(UUID)"
+
+use prusti_contracts::*;
+
+#[requires(true)]
+fn test1() {}
+
+#[ensures(true)]
+fn test2() {}
+
+mod nested {
+    use prusti_contracts::*;
+
+    #[ensures(true)]
+    pub fn test3() {}
+}
+
+#[ensures(true)]
+fn main() {}
@@ -0,0 +1,40 @@
+// compile-flags: -Pno_verify=true
+
+// This test checks that comparisons that are trivially true because arithmetic in
+// specifications does not overflow are reported when overflow checks are disabled.
+
+#![allow(dead_code, unused_variables)]
+
+use prusti_contracts::*;
+
+#[requires(x + 1 > x)]
+fn precondition(x: u32) {}
+
+#[ensures(result != result - 1)]
+fn postcondition(x: i32) -> i32 {
+    x
+}
+
+#[requires(forall(|k: usize| k < k + 2))]
+fn quantifier() {}
+
+fn loop_invariant(n: u32) {
+    let mut i = 0;
+    while i < n {
+        body_invariant!(i + 1 > i);
+        i += 1;
+    }
+}
+
+#[allow_spec_warnings(unbounded_arithmetic)]
+#[ensures(result < result + 1)]
+fn allowed() -> u64 {
+    42
+}
+
+#[ensures(result == x + 1)]
+fn not_compared_with_itself(x: u32) -> u32 {
+    x + 1
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[ensures(*a == *b)] //~ ERROR postcondition might not hold
+fn arrays_not_equal(a: &[i32; 4], b: &[i32; 4]) {}
+
+#[requires(a.len() == b.len())]
+#[ensures(a == b)] //~ ERROR postcondition might not hold
+fn slices_not_equal(a: &[i32], b: &[i32]) {}
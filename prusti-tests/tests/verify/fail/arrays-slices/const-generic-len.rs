@@ -0,0 +1,19 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[requires(N > 0)]
+fn first<const N: usize>(a: [i32; N]) -> i32 {
+    a[0]
+}
+
+fn first_without_precondition<const N: usize>(a: [i32; N]) -> i32 {
+    a[0] //~ ERROR the array or slice index may be out of bounds
+}
+
+fn caller() {
+    let a = [1, 2, 3];
+    first(a);
+    let b: [i32; 0] = [];
+    first(b); //~ ERROR precondition might not hold
+}
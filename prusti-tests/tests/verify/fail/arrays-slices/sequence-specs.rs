@@ -0,0 +1,18 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[ensures(result == xs[2])] //~ ERROR postcondition might not hold
+fn third(xs: &[i32]) -> i32 {
+    if xs.len() > 2 { xs[2] } else { 0 }
+}
+
+#[pure]
+fn get(xs: &[i32], i: usize) -> i32 {
+    xs[i] //~ ERROR assertion might fail with "index out of bounds
+}
+
+#[ensures(forall(|i: usize| i < xs.len() ==> result[i] == xs[i]))] //~ ERROR postcondition might not hold
+fn copy_wrong(xs: &[i32; 4]) -> [i32; 4] {
+    [0; 4]
+}
@@ -0,0 +1,32 @@
+use prusti_contracts::*;
+
+#[bitvector]
+fn shift_by(x: u32, amount: i32) -> u32 {
+    x << amount //~ ERROR shift left with overflow
+}
+
+#[bitvector]
+#[requires(amount <= 32)]
+fn shift_right_by(x: u32, amount: u32) -> u32 {
+    x >> amount //~ ERROR shift right with overflow
+}
+
+#[bitvector]
+#[ensures(result < 16)] //~ ERROR postcondition might not hold
+fn low_byte(x: u32) -> u32 {
+    x & 0xff
+}
+
+#[pure]
+#[bitvector]
+fn times_sixteen(x: u32) -> u32 {
+    x << 4
+}
+
+fn main() {
+    assert!(times_sixteen(3) == 49); //~ ERROR the asserted expression might not hold
+}
+
+fn without_attribute(x: u32) -> u32 {
+    x & 0xff //~ ERROR bitwise operations on non-boolean types are only supported in functions with the `#[bitvector]` attribute
+}
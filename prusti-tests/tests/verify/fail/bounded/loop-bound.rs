@@ -0,0 +1,45 @@
+use prusti_contracts::*;
+
+#[bounded(3)]
+#[ensures(result <= 2)] //~ ERROR postcondition might not hold
+fn count(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+#[bounded(2)]
+fn second_iteration(n: u32) {
+    let mut i = 0;
+    while i < n {
+        assert!(i < 1); //~ ERROR the asserted expression might not hold
+        i += 1;
+    }
+}
+
+// The executions that do exactly 3 iterations are verified
+#[bounded(3)]
+#[requires(n == 3)]
+#[ensures(result == 2)] //~ ERROR postcondition might not hold
+fn reach_bound(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+// Without the bound, nothing is known about `i` after the loop
+#[ensures(result <= 3)] //~ ERROR postcondition might not hold
+#[allow_spec_warnings(missing_loop_invariant)]
+fn unbounded_count(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+fn main() {}
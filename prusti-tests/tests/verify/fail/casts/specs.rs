@@ -0,0 +1,15 @@
+use prusti_contracts::*;
+
+#[requires(x as i64 * y as i64 <= 1000i64)]
+#[ensures(result as i64 == x as i64 * y as i64)]
+fn mul(x: i32, y: i32) -> i32 {
+    x * y
+}
+
+#[requires(x as i64 < 300i64)]
+#[ensures(result as u8 as u32 == x)] //~ ERROR postcondition might not hold
+fn truncate(x: u32) -> u32 {
+    x
+}
+
+fn main() {}
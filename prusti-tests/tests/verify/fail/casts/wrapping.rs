@@ -0,0 +1,18 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[ensures(result as u32 == x)] //~ ERROR postcondition might not hold
+fn narrowing(x: u32) -> u8 {
+    x as u8
+}
+
+fn truncation_is_not_a_noop() {
+    let x: u32 = 300;
+    assert!(x as u8 as u32 == 300); //~ ERROR the asserted expression might not hold
+}
+
+#[ensures(result >= 0)] //~ ERROR postcondition might not hold
+fn sign_flip(x: u32) -> i32 {
+    x as i32
+}
@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+
+#[ensures(result == x)]
+#[cfg_attr(prusti, requires(x > 0))]
+fn positive(x: u32) -> u32 {
+    x
+}
+
+macro_rules! generate_increment {
+    ($name:ident) => {
+        #[requires(x < 100)]
+        #[ensures(result == x + 1)] //~ ERROR postcondition might not hold
+        fn $name(x: u32) -> u32 {
+            x
+        }
+    };
+}
+
+generate_increment!(increment);
+
+fn main() {
+    positive(0); //~ ERROR precondition might not hold
+    increment(1);
+}
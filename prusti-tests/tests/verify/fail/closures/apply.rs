@@ -0,0 +1,48 @@
+use prusti_contracts::*;
+
+#[requires(f |= |x: i32| [
+    requires(x >= 0),
+    ensures(result == x + 1)
+])]
+fn negative<F: Fn(i32) -> i32>(f: F) -> i32 {
+    f(-1) //~ ERROR precondition might not hold
+}
+
+#[requires(f |= |x: i32| [
+    requires(x >= 0),
+    ensures(result == x + 1)
+])]
+#[requires(x >= 0 && x < 100)]
+#[ensures(result == x)] //~ ERROR postcondition might not hold
+fn apply<F: Fn(i32) -> i32>(f: F, x: i32) -> i32 {
+    f(x)
+}
+
+#[requires(x >= 0 && x < 100)]
+fn unspecified<F: Fn(i32) -> i32>(f: F, x: i32) -> i32 {
+    f(x) //~ ERROR calls to a closure of type `F` are supported only if
+}
+
+#[requires(f |= |x: i32| [
+    requires(x >= 0),
+    ensures(result == x + 1)
+])]
+fn ignore_result<F: FnMut(i32) -> i32>(mut f: F) {
+    f(1);
+}
+
+#[requires(f |= |v: Vec<i32>| [ //~ ERROR are not supported as arguments or results of specification entailments
+    ensures(result == v.len())
+])]
+fn unsupported_arguments<F: Fn(Vec<i32>) -> usize>(f: F, v: Vec<i32>) -> usize {
+    f(v)
+}
+
+fn main() {
+    let wrong = closure!(
+        requires(x >= 0),
+        ensures(result == x + 1), //~ ERROR postcondition might not hold
+        |x: i32| -> i32 { x + 2 }
+    );
+    ignore_result(wrong);
+}
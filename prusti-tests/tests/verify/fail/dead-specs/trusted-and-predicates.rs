@@ -0,0 +1,30 @@
+// compile-flags: -Pdeny_dead_specs=true
+use prusti_contracts::*;
+
+#[predicate]
+fn is_positive(x: i32) -> bool {
+    x > 0
+}
+
+#[predicate]
+fn is_negative(x: i32) -> bool { //~ ERROR is never used
+    x < 0
+}
+
+#[trusted]
+#[requires(is_positive(x))]
+#[ensures(result > x)]
+fn increment(x: i32) -> i32 {
+    x + 1
+}
+
+#[trusted]
+#[ensures(result > x)]
+fn old_increment(x: i32) -> i32 { //~ ERROR is never used, because the function is trusted and never called
+    x + 1
+}
+
+fn main() {
+    let y = increment(1);
+    assert!(y > 1);
+}
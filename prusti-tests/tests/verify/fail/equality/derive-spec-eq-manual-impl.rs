@@ -0,0 +1,14 @@
+use prusti_contracts::*;
+
+#[derive_spec_eq]
+struct Angle { //~ ERROR the `#[derive_spec_eq]` attribute requires a derived `PartialEq` implementation
+    degrees: u32,
+}
+
+impl PartialEq for Angle {
+    fn eq(&self, other: &Self) -> bool {
+        self.degrees % 360 == other.degrees % 360
+    }
+}
+
+fn main() {}
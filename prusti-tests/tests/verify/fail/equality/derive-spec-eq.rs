@@ -0,0 +1,25 @@
+use prusti_contracts::*;
+
+#[derive_spec_eq]
+#[derive(Clone, Copy, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[ensures(result == b)] //~ ERROR postcondition might not hold
+fn pick(a: Point, b: Point) -> Point {
+    if a == b {
+        b
+    } else {
+        a
+    }
+}
+
+fn test_different() {
+    let a = Point { x: 1, y: 2 };
+    let b = Point { x: 1, y: 3 };
+    assert!(a == b); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
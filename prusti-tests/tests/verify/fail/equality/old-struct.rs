@@ -0,0 +1,37 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Dot(Point),
+    Empty,
+}
+
+#[requires(p.y < 1000)]
+#[ensures(*p == old(*p))] //~ ERROR postcondition might not hold
+fn move_up(p: &mut Point) {
+    p.y += 1;
+}
+
+#[requires(p.y < 1000)]
+#[ensures(p.y == old(*p).y)] //~ ERROR postcondition might not hold
+fn move_up_2(p: &mut Point) {
+    p.y += 1;
+}
+
+#[ensures(*shape == old(*shape))] //~ ERROR postcondition might not hold
+fn clear(shape: &mut Shape) {
+    *shape = Shape::Empty;
+}
+
+#[ensures(match old(*shape) { Shape::Dot(p) => p.x == 0, Shape::Empty => true })] //~ ERROR postcondition might not hold
+fn check_old_variant(shape: &mut Shape) {
+    *shape = Shape::Dot(Point { x: 0, y: 0 });
+}
+
+fn main() {}
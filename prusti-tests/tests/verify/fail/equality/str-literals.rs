@@ -0,0 +1,18 @@
+use prusti_contracts::*;
+
+#[pure]
+fn primary_color() -> &'static str {
+    "red"
+}
+
+#[requires(color == "red")]
+#[ensures(color == "blue")] //~ ERROR postcondition might not hold
+fn wrong_literal(color: &str) {}
+
+#[ensures(color != "red")] //~ ERROR postcondition might not hold
+fn unknown_string(color: &str) {}
+
+#[ensures(primary_color().len() == 4)] //~ ERROR postcondition might not hold
+fn wrong_length() {}
+
+fn main() {}
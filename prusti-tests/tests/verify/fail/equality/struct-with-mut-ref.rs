@@ -0,0 +1,19 @@
+use prusti_contracts::*;
+
+#[derive(PartialEq, Eq)]
+struct A<'a> {
+    i: &'a mut i32,
+}
+
+#[derive(PartialEq, Eq)]
+struct B<'a> {
+    a: A<'a>,
+}
+
+#[requires(_x == _y)] //~ ERROR field `i` is a mutable reference
+fn test_eq(_x: &A, _y: &A) {}
+
+#[requires(_x != _y)] //~ ERROR field `a.i` is a mutable reference
+fn test_ne(_x: &B, _y: &B) {}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use prusti_contracts::*;
+
+#[extern_spec]
+mod std {
+    mod cmp {
+        use prusti_contracts::*;
+
+        #[pure]
+        #[ensures(result >= a && result >= b)]
+        #[ensures(result == a || result == b)]
+        pub fn max(a: i32, b: i32) -> i32;
+    }
+}
+
+#[ensures(result == a)] //~ ERROR postcondition might not hold
+fn first(a: i32, b: i32) -> i32 {
+    std::cmp::max(a, b)
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use prusti_contracts::*;
+
+#[extern_spec]
+impl<U> std::option::Option<U> {
+    /// The generic parameter is named `T` in `Option`
+    pub fn unwrap_or(self, default: U) -> U; //~ ERROR the generic parameters of the external specification do not match
+}
+
+#[extern_spec]
+impl<T> std::option::Option<T> {
+    /// `V` is not a generic parameter of `map`
+    pub fn map<U, F, V>(self, f: F) -> Option<U> //~ ERROR the generic parameters of the external specification do not match
+        where F: FnOnce(T) -> U;
+}
+
+fn main() {}
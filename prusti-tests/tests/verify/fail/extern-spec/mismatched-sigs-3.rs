@@ -0,0 +1,11 @@
+use prusti_contracts::*;
+
+#[extern_spec]
+impl<T> std::vec::Vec<T> {
+    /// The argument of `len` is a shared reference, which the call accepts
+    /// after coercing the mutable reference
+    #[pure]
+    fn len(&mut self) -> usize; //~ ERROR the signature of the external specification does not match
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+// compile-flags: -Penable_local_snapshot_purification=true
+use prusti_contracts::*;
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[ensures(result.x == x)]
+fn point(x: i32, y: i32) -> Point {
+    Point { x, y }
+}
+
+fn read_unconstrained_field() {
+    let p = point(1, 2);
+    assert!(p.x == 1);
+    assert!(p.y == 2); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+use prusti_contracts::*;
+
+#[ensures(result == a || result == b)]
+fn max<T: Ord>(a: T, b: T) -> T {
+    if a >= b {
+        a
+    } else {
+        b
+    }
+}
+
+#[ensures(result == a)] //~ ERROR postcondition might not hold
+fn first<T: Ord>(a: T, b: T) -> T {
+    max(a, b)
+}
+
+fn client() {
+    let m = max(3, 5);
+    assert!(m == 5); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
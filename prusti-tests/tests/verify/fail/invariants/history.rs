@@ -0,0 +1,27 @@
+use prusti_contracts::*;
+
+#[history_invariant(old(self.value()) <= self.value())] //~ ERROR postcondition might not hold.
+pub struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[pure]
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    pub fn increment(&mut self) {
+        if self.value < u32::MAX {
+            self.value += 1;
+        }
+    }
+
+    pub fn decrement(&mut self) {
+        if self.value > 0 {
+            self.value -= 1;
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+use prusti_contracts::*;
+
+#[invariant(self.value <= 100)]
+pub struct Percent {
+    value: u8,
+}
+
+impl Percent {
+    pub fn new(value: u8) -> Self {
+        Percent { value } //~ ERROR type invariants might not hold at the end of the method.
+    }
+
+    pub fn double(self) -> Percent {
+        Percent { value: self.value / 2 * 2 }
+    }
+
+    pub fn increment(self) -> Percent {
+        Percent { value: self.value + 1 } //~ ERROR type invariants might not hold at the end of the method.
+    }
+
+    #[no_invariant]
+    pub fn set_unchecked(&mut self, value: u8) { //~ ERROR the `#[no_invariant]` attribute can only be attached to private functions
+        self.value = value;
+    }
+}
+
+fn main() {}
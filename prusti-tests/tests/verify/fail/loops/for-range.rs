@@ -0,0 +1,17 @@
+use prusti_contracts::*;
+
+fn wrong_invariant(n: u32) {
+    let mut sum = 0;
+    for i in 0..n {
+        body_invariant!(sum == i); //~ ERROR loop invariant might not hold
+        sum += i;
+    }
+}
+
+fn wrong_bound(n: usize) {
+    for i in 0..n {
+        assert!(i + 1 < n); //~ ERROR the asserted expression might not hold
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use prusti_contracts::*;
+
+fn off_by_one() {
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(i < 10);
+        body_invariant!(i == loop_iterations() + 1); //~ ERROR loop invariant might not hold in the first loop iteration
+        i += 1;
+    }
+}
+
+#[ensures(loop_iterations() == 0)] //~ ERROR loop_iterations() can only be used in loop invariants
+fn outside_loop() {}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use prusti_contracts::*;
+
+fn count_up(n: u32) {
+    let mut counter = n;
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(i < 10);
+        body_invariant!(counter == old_loop(counter)); //~ ERROR loop invariant might not hold after a loop iteration
+        counter += 1;
+        i += 1;
+    }
+}
+
+#[ensures(old_loop(*x) == *x)] //~ ERROR old_loop(..) and unchanged(..) expressions can only be used in loop invariants
+fn outside_loop(x: &mut u32) {}
+
+fn main() {}
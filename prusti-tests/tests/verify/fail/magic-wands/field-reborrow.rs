@@ -0,0 +1,21 @@
+use prusti_contracts::*;
+
+struct Pair {
+    left: u32,
+    right: u32,
+}
+
+#[after_expiry(pair.left == before_expiry(*result))]
+fn borrow_left_and_reset(pair: &mut Pair) -> &mut u32 {
+    pair.right = 0;
+    &mut pair.left
+}
+
+#[requires(pair.right == 5)]
+#[ensures(pair.right == 5)] //~ ERROR postcondition might not hold
+fn set_left(pair: &mut Pair) {
+    let left = borrow_left_and_reset(pair);
+    *left = 3;
+}
+
+fn main() {}
@@ -0,0 +1,26 @@
+use prusti_contracts::*;
+use std::mem;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+struct Pair {
+    first: Point,
+    second: Point,
+}
+
+#[ensures(pair.first == old(pair.first))] //~ ERROR postcondition might not hold
+fn swap_fields(pair: &mut Pair) {
+    mem::swap(&mut pair.first, &mut pair.second);
+}
+
+fn replace_integer() {
+    let mut a = 3;
+    let old_a = mem::replace(&mut a, 4);
+    assert!(old_a == 4); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
@@ -4,7 +4,7 @@
 fn div_rem(x: usize, d: usize) -> (usize, usize)
 {
     (
-        x / d,  //~ ERROR assertion might fail with "attempt to divide by zero"
+        x / d,  //~ ERROR statement might panic: the divisor might be zero
         x % d
     )
 }
@@ -1,4 +1,4 @@
 fn main() {
     let y = 0;
-    let z = 1 / y;  //~ ERROR assertion might fail with "attempt to divide by zero"
+    let z = 1 / y;  //~ ERROR statement might panic: the divisor might be zero
 }
@@ -0,0 +1,27 @@
+// compile-flags: -Pcheck_panics=false
+
+use prusti_contracts::*;
+
+#[panic_free]
+fn get(v: &[u32], idx: usize) -> u32 {
+    v[idx] //~ ERROR the array or slice index may be out of bounds
+}
+
+#[panic_free]
+fn unwrap_some(x: Option<u32>) -> u32 {
+    x.unwrap() //~ ERROR statement might panic: unwrap on None
+}
+
+#[panic_free]
+fn expect_ok(r: Result<u32, ()>) -> u32 {
+    r.expect("a value") //~ ERROR statement might panic: unwrap on Err
+}
+
+#[panic_free]
+fn explicit_panic(x: u32) {
+    if x > 10 {
+        panic!(); //~ ERROR panic!(..) statement might be reachable
+    }
+}
+
+fn main() {}
@@ -0,0 +1,33 @@
+use prusti_contracts::*;
+
+#[pure]
+fn fib(n: usize) -> usize {
+    match n {
+        0 => 0,
+        1 => 1,
+        n => fib(n - 1) + fib(n - 2),
+    }
+}
+
+#[pure]
+#[fuel(3)]
+fn shallow_fib(n: usize) -> usize {
+    match n {
+        0 => 0,
+        1 => 1,
+        n => shallow_fib(n - 1) + shallow_fib(n - 2),
+    }
+}
+
+// The default fuel only unfolds the definition twice.
+#[ensures(fib(10) == 55)] //~ ERROR postcondition might not hold
+fn ten_unfoldings() {}
+
+#[ensures(shallow_fib(10) == 55)] //~ ERROR postcondition might not hold
+fn ten_unfoldings_with_little_fuel() {}
+
+// A function using the definition less than the fuel still verifies.
+#[ensures(shallow_fib(3) == 2)]
+fn three_unfoldings() {}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use prusti_contracts::*;
+
+trait Counter {
+    fn count(&self) -> usize;
+}
+
+struct Zero {
+    value: usize,
+}
+
+impl Counter for Zero {
+    #[pure]
+    fn count(&self) -> usize {
+        self.value
+    }
+}
+
+#[ensures(result.count() == 1)] //~ ERROR postcondition might not hold
+fn make() -> impl Counter {
+    Zero { value: 0 }
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+use std::ops::Deref;
+
+pub struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[pure]
+    pub fn get(&self) -> u32 {
+        self.value
+    }
+}
+
+pub struct Handle {
+    target: Counter,
+}
+
+impl Deref for Handle {
+    type Target = Counter;
+
+    fn deref(&self) -> &Counter {
+        &self.target
+    }
+}
+
+#[requires(h.get() > 0)] //~ ERROR dereferencing `Handle` in pure code uses the impure function "deref"
+fn test(h: &Handle) {}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use prusti_contracts::*;
+
+struct Memo {
+    value: u32,
+    memo: u32,
+}
+
+impl Memo {
+    #[pure]
+    fn double(&mut self) -> u32 {
+        self.memo = self.value / 2; //~ ERROR pure function cannot modify the target of a mutable reference argument
+        self.memo
+    }
+
+    #[pure]
+    fn reset(&mut self) -> u32 {
+        let memo = &mut self.memo;
+        *memo = 0; //~ ERROR pure function cannot modify the target of a mutable reference argument
+        self.value
+    }
+}
+
+fn main() {}
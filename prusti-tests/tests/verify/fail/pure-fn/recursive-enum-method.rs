@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+
+enum List {
+    Nil,
+    Cons(i32, Box<List>),
+}
+
+impl List {
+    #[pure]
+    #[ensures(result >= 0)]
+    fn len(&self) -> usize {
+        match self {
+            List::Nil => 0,
+            List::Cons(_, tail) => 1 + tail.len(),
+        }
+    }
+}
+
+fn client() {
+    let list = List::Cons(1, Box::new(List::Cons(2, Box::new(List::Cons(3, Box::new(List::Nil))))));
+    assert!(list.len() == 2); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
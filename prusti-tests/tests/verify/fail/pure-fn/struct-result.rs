@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Clone, Copy)]
+union Bits {
+    signed: i32,
+    unsigned: u32,
+}
+
+#[pure]
+fn origin() -> Point {
+    Point { x: 0, y: 0 }
+}
+
+#[pure]
+fn bits() -> Bits { //~ ERROR return type `Bits` of pure function cannot be encoded as a snapshot
+    Bits { unsigned: 0 }
+}
+
+#[ensures(result == origin())]
+fn make_point() -> Point {
+    Point { x: 0, y: 1 } //~ ERROR postcondition might not hold
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[pure]
+fn a(i: u32) -> bool { i < 1000 }
+
+#[pure]
+fn b(i: u32) -> bool { i < 10 }
+
+#[pure]
+fn c(i: u32) -> bool { i < 1000 }
+
+#[ensures(forall(|i: u32| i < 100 ==>
+    a(i)
+    && b(i) //~ ERROR postcondition might not hold
+    && c(i)
+))]
+fn second_conjunct() {}
+
+#[ensures(forall(|i: u32| a(i) ==>
+    b(i) //~ ERROR postcondition might not hold
+    && c(i)
+))]
+fn first_conjunct_after_implication() {}
+
+#[ensures(forall(|i: u32| i < 100 ==>
+    a(i)
+    && (i > 5 ==> c(i) && b(i)) //~ ERROR postcondition might not hold
+))]
+fn nested_implication() {}
@@ -0,0 +1,11 @@
+use prusti_contracts::*;
+
+#[ensures(exists(|y: i32| y == result && y != result))] //~ ERROR postcondition might not hold
+fn test1(x: i32) -> i32 {
+    x
+}
+
+#[ensures(exists(|y: u32| y < x))] //~ ERROR postcondition might not hold
+fn test2(x: u32) {}
+
+fn main() {}
@@ -0,0 +1,24 @@
+// compile-flags: -Pverification_timeout_per_method_secs=5
+use prusti_contracts::*;
+
+fn main() {}
+
+#[pure]
+#[trusted]
+fn f(x: i32) -> i32 {
+    unimplemented!()
+}
+
+// Each instantiation of the quantifier yields a new trigger term `f(x + 1)`.
+#[requires(forall(|x: i32| f(x) < f(x + 1), triggers=[(f(x),)]))]
+#[ensures(f(0) < 0)]
+fn matching_loop() {} //~ ERROR verification timed out after 5 seconds
+
+// The other methods are still verified.
+fn failing(x: i32) {
+    assert!(x > 0); //~ ERROR the asserted expression might not hold
+}
+
+fn verified(x: i32) {
+    assert!(x == x);
+}
@@ -0,0 +1,17 @@
+use prusti_contracts::*;
+
+struct Pointer {
+    ptr: *const i32,
+}
+
+struct Wrapper<'a> {
+    val: &'a i32,
+}
+
+#[ensures(forall(|p: Pointer| true))] //~ ERROR cannot be used in quantifiers
+fn test1() {}
+
+#[ensures(forall(|w: Wrapper| *w.val == *w.val))] //~ ERROR cannot be used in quantifiers
+fn test2() {}
+
+fn main() {}
@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+struct State {
+    counter: u32,
+    limit: u32,
+}
+
+#[assigns(s.counter)] //~ ERROR postcondition might not hold
+#[requires(s.counter < 100)]
+fn increment(s: &mut State) {
+    s.counter += 1;
+    // Not allowed by the `assigns` clause
+    s.limit = 0;
+}
+
+#[assigns(s.counter)]
+#[requires(s.counter < 100)]
+fn increment_only(s: &mut State) {
+    s.counter += 1;
+}
+
+#[requires(s.counter < 100)]
+fn client(s: &mut State) {
+    let limit = s.limit;
+    increment_only(s);
+    assert!(s.limit == limit);
+    assert!(s.counter == 0); //~ ERROR the asserted expression might not hold
+}
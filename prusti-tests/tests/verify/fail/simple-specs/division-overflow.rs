@@ -0,0 +1,26 @@
+use prusti_contracts::*;
+
+// The overflow of `MIN / -1` and `MIN % -1` panics even when overflow checks are disabled
+
+#[requires(d != 0)]
+fn div(x: i32, d: i32) -> i32 {
+    x / d //~ ERROR statement might panic: the division might overflow
+}
+
+#[requires(d != 0)]
+fn rem(x: i8, d: i8) -> i8 {
+    x % d //~ ERROR statement might panic: the division might overflow
+}
+
+#[pure]
+#[requires(d != 0)]
+fn quotient(x: i32, d: i32) -> i32 {
+    x / d //~ ERROR statement in pure function might panic: the division might overflow
+}
+
+#[requires(d != 0 && (x != i32::MIN || d != -1))]
+fn checked_div(x: i32, d: i32) -> i32 {
+    x / d
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+/// The quotient is rounded towards zero, not towards negative infinity
+#[ensures(result == -4)] //~ ERROR postcondition might not hold
+fn floor_div() -> i32 {
+    let x = -7;
+    let d = 2;
+    x / d
+}
+
+/// The remainder has the sign of the dividend
+#[ensures(result == 1)] //~ ERROR postcondition might not hold
+fn euclidean_rem() -> i32 {
+    let x = -7;
+    let d = 2;
+    x % d
+}
+
+#[pure]
+#[requires(x >= 0)]
+fn quotient(x: i32, d: i32) -> i32 {
+    x / d //~ ERROR statement in pure function might panic: the divisor might be zero
+}
+
+#[requires(x >= 0)]
+fn remainder(x: i32, d: i32) -> i32 {
+    x % d //~ ERROR statement might panic: the divisor might be zero
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+
+#[pure]
+fn square(x: i32) -> i32 {
+    x * x
+}
+
+fn assert_forall() {
+    prusti_assert!(forall(|y: i32| (0 <= y && y <= 100) ==> square(y) > 0)); //~ ERROR the asserted specification might not hold
+}
+
+#[requires(*x < 100)]
+fn assert_old(x: &mut i32) {
+    *x += 1;
+    prusti_assert!(*x == old(*x)); //~ ERROR the asserted specification might not hold
+}
+
+#[ensures(result > 0)] //~ ERROR postcondition might not hold
+fn missing_assumption(x: i32) -> i32 {
+    prusti_assert!(x == x);
+    x
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use prusti_contracts::*;
+
+fn read_ghost_variable() -> u32 {
+    ghost! {
+        let g: u32 = 5;
+    }
+    g + 1 //~ ERROR ghost variables can only be used in ghost code and in specifications
+}
+
+fn wrong_ghost_assertion(x: u32) {
+    ghost! {
+        let copy = x;
+    }
+    prusti_assert!(copy == x + 1); //~ ERROR the asserted specification might not hold
+}
+
+fn main() {}
@@ -0,0 +1,32 @@
+use prusti_contracts::*;
+
+#[requires(*x < 100)]
+fn two_phases(x: &mut i32) {
+    *x += 1;
+    label!("phase1");
+    *x += 1;
+    prusti_assert!(*x == labelled_old("phase1", *x)); //~ ERROR the asserted specification might not hold
+}
+
+fn label_in_branch(b: bool) {
+    let mut a = 0;
+    if b {
+        label!("branch");
+        a += 1;
+    }
+    prusti_assert!(a >= labelled_old("branch", a)); //~ ERROR the label `branch` is not defined on every path reaching this expression
+}
+
+fn label_after_use() {
+    let a = 0;
+    prusti_assert!(labelled_old("later", a) == 0); //~ ERROR the label `later` is not defined on every path reaching this expression
+    label!("later");
+}
+
+#[ensures(labelled_old("phase", result) == 0)] //~ ERROR labelled_old(..) expressions can only be used in loop invariants and in ghost statements
+fn in_postcondition() -> u32 {
+    label!("phase");
+    0
+}
+
+fn main() {}
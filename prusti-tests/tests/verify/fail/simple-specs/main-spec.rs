@@ -0,0 +1,11 @@
+use prusti_contracts::*;
+
+mod nested {
+    use prusti_contracts::*;
+
+    #[ensures(false)] //~ ERROR postcondition might not hold.
+    pub fn test() {}
+}
+
+#[ensures(false)] //~ ERROR postcondition might not hold.
+fn main() {}
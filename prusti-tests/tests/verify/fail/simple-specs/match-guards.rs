@@ -0,0 +1,21 @@
+use prusti_contracts::*;
+
+#[ensures(match x { Some(v) => v != -1 || result == 3, None => true })] //~ ERROR postcondition might not hold
+fn classify(x: Option<i32>) -> i32 {
+    match x {
+        Some(v) if v > 0 => 1,
+        Some(-1) | Some(-2) => 2,
+        Some(_) => 3,
+        None => 0,
+    }
+}
+
+#[ensures(x == 3 ==> result)] //~ ERROR postcondition might not hold
+fn is_small(x: u32) -> bool {
+    match x {
+        1 | 2 | 5 => true,
+        _ => false,
+    }
+}
+
+fn main() {}
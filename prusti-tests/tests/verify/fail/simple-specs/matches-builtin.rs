@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy)]
+pub enum Level {
+    Low = 3,
+    Mid = 7,
+    High,
+}
+
+#[ensures(matches!(result, Level::High))] //~ ERROR postcondition might not hold
+fn level(high: bool) -> Level {
+    if high { Level::High } else { Level::Mid }
+}
+
+#[ensures(matches!(result, None))] //~ ERROR postcondition might not hold
+fn some(x: u32) -> Option<u32> {
+    Some(x)
+}
+
+#[ensures(matches!(result, Err(_)))] //~ ERROR postcondition might not hold
+fn check(x: u32) -> Result<u32, bool> {
+    if x < 10 { Ok(x) } else { Err(false) }
+}
+
+#[requires(levels.len() > 1)]
+#[requires(forall(|i: usize| i < levels.len() ==> matches!(levels[i], Level::Low)))]
+#[ensures(matches!(levels[1], Level::Mid))] //~ ERROR postcondition might not hold
+fn all_low(levels: &[Level]) {}
+
+fn main() {}
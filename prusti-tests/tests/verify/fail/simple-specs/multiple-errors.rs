@@ -0,0 +1,17 @@
+use prusti_contracts::*;
+
+fn two_failing_asserts(x: i32, y: i32) {
+    assert!(x == 0); //~ ERROR the asserted expression might not hold
+    assert!(y == 0); //~ ERROR the asserted expression might not hold
+}
+
+#[requires(x > 0)]
+fn failing_assert_and_call(x: i32) {
+    assert!(x > 1); //~ ERROR the asserted expression might not hold
+    callee(x - 1); //~ ERROR precondition might not hold
+}
+
+#[requires(x > 0)]
+fn callee(x: i32) {}
+
+fn main() {}
@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+#[ensures(result.unwrap() == 0)] //~ ERROR unwrap() might be called on a `None` or `Err` value
+fn maybe_none(b: bool) -> Option<i32> {
+    if b {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+#[ensures(result.is_some())] //~ ERROR postcondition might not hold
+fn wrong_variant(x: i32) -> Option<i32> {
+    if x > 0 {
+        Some(x)
+    } else {
+        None
+    }
+}
+
+#[requires(x.is_some())]
+#[ensures(result == x.unwrap_or(0) + 1)] //~ ERROR postcondition might not hold
+fn wrong_value(x: Option<i32>) -> i32 {
+    match x {
+        Some(value) => value,
+        None => 0,
+    }
+}
+
+fn main() {}
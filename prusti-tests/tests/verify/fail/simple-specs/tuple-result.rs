@@ -0,0 +1,13 @@
+use prusti_contracts::*;
+
+#[ensures(result.0 <= result.1)]
+fn sort2(a: i32, b: i32) -> (i32, i32) {
+    (a, b) //~ ERROR postcondition might not hold
+}
+
+#[ensures(result.1.0 == x)]
+fn nest(x: u32) -> (u32, (u32, u32)) {
+    (x, (0, x)) //~ ERROR postcondition might not hold
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use prusti_contracts::*;
+
+#[requires(n >= 0)]
+#[decreases(n)]
+fn missing_loop_measure(n: i32) -> i32 {
+    let mut i = 0;
+    while i < n { //~ ERROR loops in a function with a termination measure need
+        body_invariant!(0 <= i && i < n);
+        i += 1;
+    }
+    i
+}
+
+fn main() {}
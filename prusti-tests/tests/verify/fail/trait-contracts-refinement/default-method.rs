@@ -0,0 +1,34 @@
+use prusti_contracts::*;
+
+trait Percentage {
+    #[ensures(result <= 100)] //~ ERROR postcondition might not hold
+    fn get(&self) -> u8 {
+        42
+    }
+
+    #[requires(arg <= 100)]
+    fn set(&mut self, arg: u8) {
+        assert!(arg <= 100);
+    }
+}
+
+struct Inheriting;
+
+impl Percentage for Inheriting {}
+
+struct Overriding;
+
+impl Percentage for Overriding {
+    fn get(&self) -> u8 {
+        101
+    }
+
+    fn set(&mut self, arg: u8) {
+        assert!(arg < 100); //~ ERROR the asserted expression might not hold
+    }
+}
+
+fn main() {
+    let mut inheriting = Inheriting;
+    inheriting.set(101); //~ ERROR precondition might not hold
+}
@@ -0,0 +1,40 @@
+use prusti_contracts::*;
+
+struct Data {
+    locked: bool,
+    value: u32,
+}
+
+struct Guard<'a> {
+    data: &'a mut Data,
+}
+
+#[refine_trait_spec]
+impl<'a> Drop for Guard<'a> {
+    #[requires(self.data.locked)]
+    #[ensures(!self.data.locked)]
+    #[ensures(self.data.value == old(self.data.value))] //~ ERROR postcondition might not hold
+    fn drop(&mut self) {
+        self.data.value = 0;
+        self.data.locked = false;
+    }
+}
+
+fn test_unlocked_guard() {
+    let mut data = Data { locked: false, value: 0 };
+    {
+        let _guard = Guard { data: &mut data };
+    } //~ ERROR precondition might not hold.
+}
+
+fn test_wrong_value() {
+    let mut data = Data { locked: true, value: 0 };
+    {
+        let guard = Guard { data: &mut data };
+        guard.data.value = 5;
+    }
+    assert!(!data.locked);
+    assert!(data.value == 6); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
@@ -0,0 +1,38 @@
+use prusti_contracts::*;
+
+trait Counter {
+    #[requires(0 <= x && x < 100)]
+    #[ensures(result >= x)]
+    fn step(&self, x: i32) -> i32;
+}
+
+struct Strict;
+
+#[refine_trait_spec]
+impl Counter for Strict {
+    #[requires(10 <= x && x < 100)] //~ ERROR specification of impl does not refine trait
+    #[ensures(result == x)]
+    fn step(&self, x: i32) -> i32 {
+        x
+    }
+}
+
+trait Bumper {
+    #[requires(0 <= x && x < 100)]
+    #[ensures(*value >= old(*value))] //~ ERROR specification of impl does not refine trait
+    fn bump(&self, value: &mut i32, x: i32);
+}
+
+struct Decrement;
+
+#[refine_trait_spec]
+impl Bumper for Decrement {
+    #[requires(*value > i32::MIN)]
+    #[ensures(*value == old(*value) - 1)]
+    #[trusted]
+    fn bump(&self, value: &mut i32, x: i32) {
+        *value -= 1;
+    }
+}
+
+fn main() {}
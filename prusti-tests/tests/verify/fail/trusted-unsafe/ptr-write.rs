@@ -0,0 +1,14 @@
+use prusti_contracts::*;
+use std::ptr;
+
+#[trusted_unsafe]
+#[ensures(result == 0)] //~ ERROR postcondition might not hold
+fn overwritten(a: u32) -> u32 {
+    let mut x = 0;
+    unsafe {
+        ptr::write(&mut x as *mut u32, a);
+    }
+    x
+}
+
+fn main() {}
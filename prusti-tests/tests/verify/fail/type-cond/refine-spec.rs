@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+struct NonCopy(i32);
+
+#[ensures(result >= 1)]
+#[refine_spec(where T: Copy, [ensures(result == 2)])] //~ ERROR postcondition might not hold
+fn copies<T>(_value: &T) -> i32 {
+    1
+}
+
+#[trusted]
+#[refine_spec(where T: Copy, [requires(n >= 0)])]
+fn repeat<T>(_value: &T, n: i32) {}
+
+fn copy_client() {
+    repeat(&5, -1); //~ ERROR precondition might not hold
+}
+
+fn non_copy_client() {
+    let value = NonCopy(5);
+    let n = copies(&value);
+    assert!(n == 2); //~ ERROR the asserted expression might not hold
+}
+
+fn generic_client<T>(value: &T) {
+    let n = copies(value);
+    assert!(n == 2); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use prusti_contracts::*;
+
+trait Limit {
+    const LIMIT: u32;
+}
+
+#[requires(x < T::LIMIT)] //~ ERROR the value of the constant `Limit::LIMIT` depends on generic parameters and cannot be evaluated
+fn below<T: Limit>(x: u32) {}
+
+fn main() {}
@@ -0,0 +1,23 @@
+#![feature(asm)]
+
+use prusti_contracts::*;
+
+fn nop() {
+    unsafe {
+        asm!("nop"); //~ ERROR inline assembly is not supported
+    }
+}
+
+#[requires(x < 100)]
+#[ensures(result == x + 1)]
+fn increment(x: u64) -> u64 {
+    x + 1
+}
+
+fn still_verified() {
+    let x = increment(41);
+    assert!(x == 42);
+    assert!(x == 43); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+fn index_empty() -> i32 {
+    let v: Vec<i32> = Vec::new();
+    v[0] //~ ERROR precondition might not hold
+}
+
+#[ensures(v.len() == old(v.len()))] //~ ERROR postcondition might not hold
+fn push_changes_len(v: &mut Vec<i32>) {
+    v.push(1);
+}
+
+#[requires(v.len() > 1)]
+#[ensures(result.unwrap() == old(v[0]))] //~ ERROR postcondition might not hold
+fn pop_returns_last(v: &mut Vec<i32>) -> Option<i32> {
+    v.pop()
+}
+
+#[pure]
+fn get_unchecked(v: &Vec<i32>, i: usize) -> i32 {
+    v[i] //~ ERROR the vector index may be out of bounds
+}
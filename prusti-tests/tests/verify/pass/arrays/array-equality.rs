@@ -0,0 +1,32 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[pure]
+fn identity(a: &[i32; 4]) -> [i32; 4] {
+    *a
+}
+
+#[requires(*a == *b)]
+#[ensures(*a == *b)]
+fn array_eq_preserved(a: &[i32; 4], b: &[i32; 4]) {}
+
+#[requires(a == b)]
+#[ensures(a == b)]
+fn slice_eq_preserved(a: &[i32], b: &[i32]) {}
+
+#[ensures(identity(a) == *a)]
+fn pure_returning_array(a: &[i32; 4]) {}
+
+#[requires(*a == *b)]
+fn array_eq_implies_elements_eq(a: &[i32; 4], b: &[i32; 4]) {
+    assert!(a[0] == b[0]);
+    assert!(a[3] == b[3]);
+}
+
+#[requires(a == b)]
+#[requires(a.len() > 1)]
+fn slice_eq_implies_elements_eq(a: &[i32], b: &[i32]) {
+    assert!(a.len() == b.len());
+    assert!(a[1] == b[1]);
+}
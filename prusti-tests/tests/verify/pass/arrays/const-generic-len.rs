@@ -0,0 +1,27 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[requires(N > 0)]
+fn first<const N: usize>(a: [i32; N]) -> i32 {
+    a[0]
+}
+
+#[requires(N > 1)]
+fn second<const N: usize>(a: [i32; N]) -> i32 {
+    first(a);
+    a[1]
+}
+
+#[ensures(result == N)]
+fn length<const N: usize>(a: [i32; N]) -> usize {
+    a.len()
+}
+
+fn caller() {
+    let a = [1, 2, 3];
+    first(a);
+    second(a);
+    let len = length(a);
+    assert!(len == 3);
+}
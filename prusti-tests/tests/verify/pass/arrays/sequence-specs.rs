@@ -0,0 +1,44 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[ensures(result.len() == xs.len())]
+#[ensures(forall(|i: usize| i < xs.len() ==> result[i] == xs[i]))]
+fn copy(xs: &[i32; 4]) -> [i32; 4] {
+    *xs
+}
+
+#[requires(xs.len() > 2)]
+#[ensures(result == xs[2])]
+fn third(xs: &[i32]) -> i32 {
+    xs[2]
+}
+
+#[pure]
+#[requires(i < xs.len())]
+fn get(xs: &[i32], i: usize) -> i32 {
+    xs[i]
+}
+
+#[pure]
+fn first_or_zero(xs: &[i32; 3]) -> i32 {
+    if xs.len() > 0 { xs[0] } else { 0 }
+}
+
+#[requires(xs.len() > 0)]
+#[ensures(result == get(xs, 0))]
+fn first(xs: &[i32]) -> i32 {
+    xs[0]
+}
+
+#[ensures(result == first_or_zero(xs))]
+fn first_of_three(xs: &[i32; 3]) -> i32 {
+    xs[0]
+}
+
+#[requires(forall(|i: usize| i < xs.len() ==> xs[i] >= 0))]
+#[requires(xs.len() == 2)]
+#[ensures(result >= 0)]
+fn second(xs: &[i32]) -> i32 {
+    xs[1]
+}
@@ -0,0 +1,34 @@
+use prusti_contracts::*;
+
+#[bitvector]
+#[ensures(result < 256)]
+fn low_byte(x: u32) -> u32 {
+    x & 0xff
+}
+
+#[bitvector]
+#[ensures(x & 0xff < 256)]
+#[ensures(x & 0xf <= 15)]
+fn mask_in_spec(x: u64) {}
+
+#[bitvector]
+#[ensures(result <= 255)]
+fn combine(x: u8, y: u8) -> u8 {
+    (x | y) ^ (x & 0x0f)
+}
+
+#[bitvector]
+fn masked_loop(mut x: u32) {
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(x & 0xff < 256);
+        x = x & 0xff;
+        assert!(x < 256);
+        i += 1;
+    }
+}
+
+fn main() {
+    let b = low_byte(1000);
+    assert!(b < 256);
+}
@@ -0,0 +1,31 @@
+use prusti_contracts::*;
+
+#[bitvector]
+#[ensures(result < 16)]
+fn high_nibble(x: u8) -> u8 {
+    x >> 4
+}
+
+#[bitvector]
+#[requires(0 <= amount && amount < 64)]
+fn shift_by(x: u64, amount: i32) -> u64 {
+    (x << amount) >> amount
+}
+
+#[pure]
+#[bitvector]
+fn times_sixteen(x: u32) -> u32 {
+    x << 4
+}
+
+#[pure]
+#[bitvector]
+fn half(x: i32) -> i32 {
+    x >> 1
+}
+
+fn main() {
+    assert!(times_sixteen(3) == 48);
+    assert!(half(-3) == -2);
+    assert!(high_nibble(255) < 16);
+}
@@ -0,0 +1,65 @@
+use prusti_contracts::*;
+
+// The postcondition only holds for the executions with at most 3 iterations
+#[bounded(3)]
+#[ensures(result <= 3)]
+fn count(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+#[bounded(2)]
+#[ensures(result <= 4)]
+fn count_nested(n: u32, m: u32) -> u32 {
+    let mut count = 0;
+    let mut i = 0;
+    while i < n {
+        let mut j = 0;
+        while j < m {
+            count += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+#[bounded(4)]
+fn find(v: &[i32; 4], x: i32) -> Option<usize> {
+    let mut i = 0;
+    while i < 4 {
+        if v[i] == x {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+#[bounded(3)]
+#[requires(n <= 3)]
+#[ensures(result == n)]
+fn exact(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+// The bound is reached exactly: the guard is evaluated once more after the last iteration
+#[bounded(3)]
+#[requires(n == 3)]
+#[ensures(result == 3)]
+fn reach_bound(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+fn main() {}
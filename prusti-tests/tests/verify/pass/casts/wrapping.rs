@@ -0,0 +1,36 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[ensures(result == x as i64)]
+fn widening(x: i32) -> i64 {
+    x as i64
+}
+
+#[requires(x <= 255)]
+#[ensures(result as u32 == x)]
+fn narrowing_in_range(x: u32) -> u8 {
+    x as u8
+}
+
+fn narrowing_truncates() {
+    let x: u32 = 300;
+    assert!(x as u8 == 44);
+    let y: i32 = 200;
+    assert!(y as i8 == -56);
+}
+
+fn sign_flip() {
+    let x: i32 = -1;
+    assert!(x as u32 == 4294967295);
+    let y: u32 = 4294967295;
+    assert!(y as i32 == -1);
+    let z: i8 = -128;
+    assert!(z as u8 == 128);
+}
+
+#[pure]
+#[ensures(result == 1)]
+fn pure_truncation() -> u8 {
+    257u32 as u8
+}
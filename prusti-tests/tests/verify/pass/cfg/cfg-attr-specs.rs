@@ -0,0 +1,47 @@
+use prusti_contracts::*;
+
+#[cfg(prusti)]
+#[pure]
+fn verified() -> bool {
+    true
+}
+
+#[cfg(not(prusti))]
+fn verified() -> bool {
+    false
+}
+
+// The driver does not enable the `prusti` feature, so this function does not exist
+#[cfg(feature = "prusti")]
+#[pure]
+fn never() -> bool {
+    false
+}
+
+#[ensures(result == x)]
+#[cfg_attr(prusti, requires(x > 0))]
+#[cfg_attr(not(prusti), requires(false))]
+#[cfg_attr(any(prusti, feature = "prusti"), ensures(result > 0))]
+#[cfg_attr(all(prusti, feature = "prusti"), requires(never()))]
+fn positive(x: u32) -> u32 {
+    x
+}
+
+#[cfg_attr(prusti, ensures(result))]
+fn check_verified() -> bool {
+    verified()
+}
+
+#[ensures(result == x)]
+#[cfg_attr(prusti, cfg_attr(any(prusti, test), ensures(result < 10)))]
+#[requires(x < 10)]
+fn small(x: u32) -> u32 {
+    x
+}
+
+fn main() {
+    let x = positive(3);
+    assert!(x > 0);
+    assert!(check_verified());
+    assert!(small(4) < 10);
+}
@@ -0,0 +1,41 @@
+use prusti_contracts::*;
+
+macro_rules! generate_increment {
+    ($name:ident, $ty:ty, $max:expr) => {
+        #[requires(x < $max)]
+        #[ensures(result == x + 1)]
+        fn $name(x: $ty) -> $ty {
+            x + 1
+        }
+    };
+}
+
+macro_rules! generate_getter {
+    ($name:ident, $field:ident) => {
+        #[pure]
+        #[cfg_attr(prusti, ensures(result == self.$field))]
+        fn $name(&self) -> u32 {
+            self.$field
+        }
+    };
+}
+
+generate_increment!(increment_u8, u8, 255);
+generate_increment!(increment_u32, u32, 1000);
+
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+impl Point {
+    generate_getter!(get_x, x);
+    generate_getter!(get_y, y);
+}
+
+fn main() {
+    assert!(increment_u8(3) == 4);
+    assert!(increment_u32(999) == 1000);
+    let p = Point { x: 1, y: 2 };
+    assert!(p.get_x() + p.get_y() == 3);
+}
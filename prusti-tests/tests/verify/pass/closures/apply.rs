@@ -0,0 +1,73 @@
+use prusti_contracts::*;
+
+#[requires(f |= |x: i32| [
+    requires(x >= 0),
+    ensures(result == x + 1)
+])]
+#[requires(x >= 0 && x < 100)]
+#[ensures(result == x + 1)]
+fn apply<F: Fn(i32) -> i32>(f: F, x: i32) -> i32 {
+    f(x)
+}
+
+#[requires(f |= |x: i32| [
+    requires(x >= 0 && x < 100),
+    ensures(result == x + 1)
+])]
+#[ensures(result == 2)]
+fn apply_twice<F: FnMut(i32) -> i32>(mut f: F) -> i32 {
+    let y = f(0);
+    f(y)
+}
+
+#[requires(f |= |a: i32, b: i32| [
+    requires(0 <= a && a <= b && b < 100),
+    ensures(result <= b - a)
+])]
+#[requires(0 <= a && a <= b && b < 100)]
+#[ensures(result <= b)]
+fn apply_once<F: FnOnce(i32, i32) -> i32>(f: F, a: i32, b: i32) -> i32 {
+    f(a, b)
+}
+
+#[requires(f |= |b: bool| [
+    ensures(result == !b)
+])]
+#[ensures(result)]
+fn negate_false<F: Fn(bool) -> bool>(f: F) -> bool {
+    f(false)
+}
+
+#[requires(f |= |x: i32| [
+    requires(x >= 0 && x < 100),
+    ensures(result >= x)
+])]
+#[requires(x >= 0 && x < 100)]
+#[ensures(result >= x)]
+fn apply_ge<F: Fn(i32) -> i32>(f: F, x: i32) -> i32 {
+    f(x)
+}
+
+fn main() {
+    let inc = closure!(
+        requires(x >= 0),
+        ensures(result == x + 1),
+        |x: i32| -> i32 { x + 1 }
+    );
+    apply(inc, 3);
+
+    let not = closure!(
+        ensures(result == !b),
+        |b: bool| -> bool { !b }
+    );
+    negate_false(not);
+
+    // The captured state is part of the closure value passed to `apply_ge`
+    let offset = 1;
+    let add = closure!(
+        requires(x >= 0 && x < 100),
+        ensures(result >= x),
+        |x: i32| -> i32 { if offset > 0 { x + offset } else { x } }
+    );
+    apply_ge(add, 3);
+}
@@ -0,0 +1,50 @@
+// compile-flags: -Pdeny_dead_specs=true
+use prusti_contracts::*;
+
+struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[predicate]
+    fn is_small(&self) -> bool {
+        self.value < 100
+    }
+
+    #[requires(self.is_small())]
+    #[ensures(self.value == old(self.value) + 1)]
+    fn increment(&mut self) {
+        self.value += 1;
+    }
+}
+
+trait Reset {
+    fn reset(&mut self);
+}
+
+impl Reset for Counter {
+    /// Trait methods are called through the trait
+    #[trusted]
+    #[ensures(self.value == 0)]
+    fn reset(&mut self) {
+        self.value = 0;
+    }
+}
+
+/// Only used in a specification
+#[pure]
+#[trusted]
+#[ensures(result == 100)]
+fn limit() -> u32 {
+    100
+}
+
+#[requires(x < limit())]
+fn check(x: u32) {}
+
+fn main() {
+    let mut counter = Counter { value: 0 };
+    counter.increment();
+    counter.reset();
+    check(counter.value);
+}
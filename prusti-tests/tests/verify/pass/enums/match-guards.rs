@@ -0,0 +1,39 @@
+use prusti_contracts::*;
+
+#[ensures(match x {
+    Some(v) => if v > 0 { result == 1 } else if v == -1 || v == -2 { result == 2 } else { result == 3 },
+    None => result == 0,
+})]
+fn classify(x: Option<i32>) -> i32 {
+    match x {
+        Some(v) if v > 0 => 1,
+        Some(-1) | Some(-2) => 2,
+        Some(_) => 3,
+        None => 0,
+    }
+}
+
+#[ensures((x == 1 || x == 2 || x == 5) == result)]
+fn is_small(x: u32) -> bool {
+    match x {
+        1 | 2 | 5 => true,
+        _ => false,
+    }
+}
+
+#[ensures(result == if x > y { x } else { y })]
+fn max_guarded(x: i32, y: i32) -> i32 {
+    let pair = (x, y);
+    match pair {
+        (a, b) if a > b => a,
+        (_, b) => b,
+    }
+}
+
+fn main() {
+    assert!(classify(Some(5)) == 1);
+    assert!(classify(Some(-2)) == 2);
+    assert!(classify(Some(0)) == 3);
+    assert!(classify(None) == 0);
+    assert!(is_small(5));
+}
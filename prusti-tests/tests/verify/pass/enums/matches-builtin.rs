@@ -0,0 +1,65 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy)]
+pub enum Level {
+    Low = 3,
+    Mid = 7,
+    High,
+}
+
+#[derive(Clone, Copy)]
+pub enum Shape {
+    Circle { radius: u32 },
+    Square(u32),
+    Empty,
+}
+
+#[ensures(matches!(result, Some(_)))]
+fn some(x: u32) -> Option<u32> {
+    Some(x)
+}
+
+#[ensures(x < 10 ==> matches!(result, Ok(_)))]
+#[ensures(x >= 10 ==> matches!(result, Err(..)))]
+fn check(x: u32) -> Result<u32, bool> {
+    if x < 10 { Ok(x) } else { Err(false) }
+}
+
+#[ensures(high ==> matches!(result, Level::High))]
+#[ensures(!high ==> matches!(result, Level::Mid))]
+fn level(high: bool) -> Level {
+    if high { Level::High } else { Level::Mid }
+}
+
+#[pure]
+#[ensures(matches!(result, Shape::Circle { radius: _ }))]
+fn circle(radius: u32) -> Shape {
+    Shape::Circle { radius }
+}
+
+#[ensures(matches!(circle(3), Shape::Circle { .. }))]
+#[ensures(matches!(result, Shape::Square(_)))]
+fn square(side: u32) -> Shape {
+    Shape::Square(side)
+}
+
+#[ensures(matches!(old(*opt), None) ==> matches!(*opt, None))]
+fn keep(opt: &mut Option<u32>) {
+    if let Some(x) = opt {
+        *x = 0;
+    }
+}
+
+#[requires(levels.len() > 1)]
+#[requires(forall(|i: usize| i < levels.len() ==> matches!(levels[i], Level::Low)))]
+#[ensures(matches!(levels[1], Level::Low))]
+fn all_low(levels: &[Level]) {}
+
+// Tests that are not variant tests are encoded as ordinary Rust expressions.
+#[ensures(matches!(result, Some(1) | Some(2)))]
+#[ensures(matches!(result, Some(_)) == true)]
+fn one() -> Option<u32> {
+    Some(1)
+}
+
+fn main() {}
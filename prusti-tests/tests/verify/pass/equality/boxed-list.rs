@@ -0,0 +1,49 @@
+use prusti_contracts::*;
+
+#[derive(PartialEq, Eq)]
+struct Node {
+    value: i32,
+    next: Option<Box<Node>>,
+}
+
+#[pure]
+#[ensures(result >= 1)]
+fn len(node: &Node) -> usize {
+    match node.next {
+        None => 1,
+        Some(ref next) => 1 + len(next),
+    }
+}
+
+#[ensures(result.value == value)]
+#[ensures(len(&result) == 1)]
+fn singleton(value: i32) -> Box<Node> {
+    Box::new(Node { value, next: None })
+}
+
+#[ensures(result.value == value)]
+#[ensures(len(&result) == len(&old(next)) + 1)]
+fn push(value: i32, next: Box<Node>) -> Box<Node> {
+    Box::new(Node { value, next: Some(next) })
+}
+
+#[requires(a == b)]
+#[ensures(a.value == b.value)]
+#[ensures(len(&a) == len(&b))]
+fn boxed_eq(a: &Box<Node>, b: &Box<Node>) {}
+
+#[trusted]
+#[ensures(result == *node)]
+fn clone_box(node: &Box<Node>) -> Box<Node> {
+    unimplemented!()
+}
+
+fn client() {
+    let a = push(1, singleton(2));
+    let b = clone_box(&a);
+    assert!(a == b);
+    assert!(len(&b) == 2);
+    boxed_eq(&a, &b);
+}
+
+fn main() {}
@@ -0,0 +1,61 @@
+use prusti_contracts::*;
+
+#[derive_spec_eq]
+#[derive(Clone, Copy, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive_spec_eq]
+#[derive(PartialEq)]
+enum Shape {
+    Dot(Point),
+    Segment(Point, Point),
+}
+
+#[pure]
+fn get_x(p: &Point) -> i32 {
+    p.x
+}
+
+#[ensures(result == a)]
+fn pick(a: Point, b: Point) -> Point {
+    if a == b {
+        b
+    } else {
+        a
+    }
+}
+
+#[ensures(result ==> get_x(a) == get_x(b))]
+fn same_x(a: &Point, b: &Point) -> bool {
+    if a == b {
+        assert!(a.y == b.y);
+        true
+    } else {
+        a.x == b.x
+    }
+}
+
+#[ensures(result == (a == b))]
+fn compare(a: &Point, b: &Point) -> bool {
+    a == b
+}
+
+#[requires(a.x == b.x && a.y == b.y)]
+fn fields_equal(a: Point, b: Point) {
+    assert!(a == b);
+    assert!(!(a != b));
+}
+
+fn test_shapes() {
+    let p = Point { x: 1, y: 2 };
+    let dot = Shape::Dot(p);
+    let other = Shape::Dot(Point { x: 1, y: 2 });
+    assert!(dot == other);
+    let segment = Shape::Segment(p, p);
+    assert!(dot != segment);
+}
+
+fn main() {}
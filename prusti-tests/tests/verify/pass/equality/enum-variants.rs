@@ -0,0 +1,31 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Light {
+    Off,
+    On(u32),
+}
+
+#[pure]
+fn is_on(light: &Light) -> bool {
+    match light {
+        Light::On(_) => true,
+        Light::Off => false,
+    }
+}
+
+// A value that is not the first variant must be the second one
+#[requires(*light != Light::Off)]
+#[ensures(is_on(light))]
+fn not_off_is_on(light: &Light) {}
+
+#[requires(!is_on(light))]
+#[ensures(*light == Light::Off)]
+fn not_on_is_off(light: &Light) {}
+
+// The constructors are injective and build distinct values
+#[ensures(Light::On(a) == Light::On(b) ==> a == b)]
+#[ensures(Light::On(a) != Light::Off)]
+fn constructors(a: u32, b: u32) {}
+
+fn main() {}
@@ -0,0 +1,62 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    start: Point,
+    end: Point,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Dot(Point),
+    Line(Segment),
+}
+
+#[pure]
+fn get_x(p: &Point) -> i32 {
+    p.x
+}
+
+#[ensures(*p == old(*p))]
+fn keep(p: &mut Point) {}
+
+#[requires(p.y < 1000)]
+#[ensures(p.x == old(*p).x)]
+#[ensures(p.y == old(*p).y + 1)]
+fn move_up(p: &mut Point) {
+    p.y += 1;
+}
+
+#[requires(s.end.y < 1000)]
+#[ensures(s.start == old(s.start))]
+#[ensures(get_x(&s.end) == get_x(&old(s.end)))]
+fn move_end_up(s: &mut Segment) {
+    move_up(&mut s.end);
+}
+
+#[ensures(match old(*shape) {
+    Shape::Dot(p) => match *shape {
+        Shape::Line(s) => s.start == p && s.end == p,
+        Shape::Dot(_) => false,
+    },
+    Shape::Line(_) => *shape == old(*shape),
+})]
+fn to_line(shape: &mut Shape) {
+    if let Shape::Dot(p) = *shape {
+        *shape = Shape::Line(Segment { start: p, end: p });
+    }
+}
+
+#[requires(match *shape { Shape::Dot(_) => true, Shape::Line(_) => false })]
+#[ensures(*shape != old(*shape))]
+fn change_variant(shape: &mut Shape) {
+    *shape = Shape::Line(Segment { start: Point { x: 0, y: 0 }, end: Point { x: 0, y: 0 } });
+}
+
+fn main() {}
@@ -0,0 +1,33 @@
+use prusti_contracts::*;
+
+#[pure]
+fn primary_color() -> &'static str {
+    "red"
+}
+
+#[pure]
+fn is_primary(color: &str) -> bool {
+    color == primary_color()
+}
+
+#[requires(color == "red")]
+#[ensures(color != "blue")]
+#[ensures(color.len() == 3)]
+fn compare_parameter(color: &str) {}
+
+#[requires(color == "red" || color == "green")]
+#[ensures(color != "blue")]
+#[ensures(color != "")]
+fn two_literals(color: &str) {}
+
+#[ensures(primary_color() == "red")]
+#[ensures(primary_color() != "green")]
+#[ensures(primary_color().len() == 3)]
+fn through_pure_function() {}
+
+#[requires(is_primary(color))]
+#[ensures(color == "red")]
+#[ensures(color != "green")]
+fn transitivity(color: &str) {}
+
+fn main() {}
@@ -2,16 +2,31 @@ use prusti_contracts::*;
 
 #[derive(PartialEq, Eq)]
 struct A<'a> {
-    i: &'a mut i32,
+    i: &'a i32,
+}
+
+#[derive(PartialEq, Eq)]
+struct Config<'a> {
+    name: &'a u32,
+    level: u32,
 }
 
 #[requires(_x == _y)]
 fn test_eq(_x: &A, _y: &A) {}
 
-fn main() {
-    let mut a = 3;
-    let mut b = 3;
-    let x = A { i: &mut a };
-    let y = A { i: &mut b };
-    test_eq(&x, &y);
+#[requires(*x == *y)]
+#[ensures(*x.i == *y.i)]
+fn test_deref(x: &A, y: &A) {}
+
+#[ensures(result == *input)]
+fn copy<'a>(input: &Config<'a>) -> Config<'a> {
+    Config { name: input.name, level: input.level }
 }
+
+fn client(c: &Config) {
+    let d = copy(c);
+    assert!(*d.name == *c.name);
+    assert!(d.level == c.level);
+}
+
+fn main() {}
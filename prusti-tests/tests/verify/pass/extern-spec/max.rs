@@ -0,0 +1,31 @@
+use prusti_contracts::*;
+
+#[extern_spec]
+mod std {
+    mod cmp {
+        use prusti_contracts::*;
+
+        #[pure]
+        #[ensures(result >= a && result >= b)]
+        #[ensures(result == a || result == b)]
+        pub fn max(a: i32, b: i32) -> i32;
+    }
+}
+
+#[ensures(result >= 0)]
+#[ensures(result == x || result == -x)]
+fn abs(x: i32) -> i32 {
+    std::cmp::max(x, -x)
+}
+
+#[requires(a < b)]
+#[ensures(result == b)]
+fn larger(a: i32, b: i32) -> i32 {
+    std::cmp::max(a, b)
+}
+
+fn main() {
+    let m = std::cmp::max(3, 7);
+    assert!(m == 7);
+    assert!(abs(-5) == 5);
+}
@@ -0,0 +1,32 @@
+use prusti_contracts::*;
+
+#[extern_spec]
+impl<T> std::option::Option<T> {
+    #[ensures(self.is_none() ==> result == default)]
+    pub fn unwrap_or(self, default: T) -> T
+        where T: PartialEq;
+
+    /// A weak specification that only relates the variants
+    #[ensures(self.is_some() == result.is_some())]
+    pub fn map<U, F>(self, f: F) -> Option<U>
+        where F: FnOnce(T) -> U;
+}
+
+#[requires(x.is_none())]
+#[ensures(result == 0)]
+fn value_or_zero(x: Option<i32>) -> i32 {
+    x.unwrap_or(0)
+}
+
+#[requires(x.is_some())]
+#[ensures(result.is_some())]
+fn increment(x: Option<u32>) -> Option<u32> {
+    x.map(|v| v.wrapping_add(1))
+}
+
+fn main() {
+    let a = value_or_zero(None);
+    assert!(a == 0);
+    let b = increment(Some(3));
+    assert!(b.is_some());
+}
@@ -0,0 +1,49 @@
+// compile-flags: -Penable_local_snapshot_purification=true
+use prusti_contracts::*;
+
+#[derive(Clone, Copy)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+struct Segment {
+    start: Point,
+    end: Point,
+}
+
+#[ensures(result.x == x && result.y == y)]
+fn point(x: i32, y: i32) -> Point {
+    Point { x, y }
+}
+
+#[ensures(result.start.x == 0 && result.end.y == length)]
+fn vertical(length: i32) -> Segment {
+    Segment { start: point(0, 0), end: point(0, length) }
+}
+
+fn read_fields() {
+    let p = point(1, 2);
+    let sum = p.x + p.y;
+    assert!(sum == 3);
+}
+
+fn read_nested_fields(length: i32) {
+    let s = vertical(length);
+    let x = s.start.x;
+    let y = s.end.y;
+    assert!(x == 0);
+    assert!(y == length);
+}
+
+fn read_in_loop(n: i32) {
+    let p = point(n, n);
+    let mut i = 0;
+    while i < p.x {
+        body_invariant!(i < p.x);
+        i += 1;
+    }
+    assert!(i >= p.y);
+}
+
+fn main() {}
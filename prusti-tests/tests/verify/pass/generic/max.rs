@@ -0,0 +1,28 @@
+use prusti_contracts::*;
+
+#[ensures(result == a || result == b)]
+fn max<T: Ord>(a: T, b: T) -> T {
+    if a >= b {
+        a
+    } else {
+        b
+    }
+}
+
+#[ensures(result == a || result == b || result == c)]
+fn max3<T: Ord>(a: T, b: T, c: T) -> T {
+    max(max(a, b), c)
+}
+
+#[requires(a == b)]
+#[ensures(b == a)]
+fn symmetric<T: Eq>(a: &T, b: &T) {}
+
+fn client() {
+    let m = max(3, 5);
+    assert!(m == 3 || m == 5);
+    let n: i32 = max3(1, 2, 3);
+    assert!(n >= 1 && n <= 3);
+}
+
+fn main() {}
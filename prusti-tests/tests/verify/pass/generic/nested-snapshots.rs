@@ -0,0 +1,57 @@
+use prusti_contracts::*;
+
+// Each distinct type gets a single snapshot domain, even when it is nested
+// many times in the types of the arguments.
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Pair<A, B> {
+    first: A,
+    second: B,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+type Nested = Pair<Pair<Pair<i32, u8>, Pair<i32, u8>>, Pair<Pair<i32, u8>, Pair<i32, u8>>>;
+
+type Choice = Either<Either<Nested, (Nested, Nested)>, Either<(Nested, Nested), Nested>>;
+
+enum List<T> {
+    Nil,
+    Cons(T, Box<List<T>>),
+}
+
+#[pure]
+fn same_nested(a: Nested, b: Nested) -> bool {
+    a == b
+}
+
+#[pure]
+fn same_choice(a: Choice, b: Choice) -> bool {
+    a == b
+}
+
+#[pure]
+fn len(l: &List<Pair<Nested, Choice>>) -> usize {
+    match l {
+        List::Nil => 0,
+        List::Cons(_, tail) => 1 + len(tail),
+    }
+}
+
+#[ensures(same_nested(a, a))]
+fn test1(a: Nested) {}
+
+#[requires(same_choice(a, b))]
+#[ensures(same_choice(b, a))]
+fn test2(a: Choice, b: Choice) {}
+
+#[ensures(len(&result) == 0)]
+fn test3() -> List<Pair<Nested, Choice>> {
+    List::Nil
+}
+
+fn main() {}
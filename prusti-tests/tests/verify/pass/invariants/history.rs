@@ -0,0 +1,40 @@
+use prusti_contracts::*;
+
+#[history_invariant(old(self.value()) <= self.value())]
+pub struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[pure]
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    pub fn increment(&mut self) {
+        if self.value < u32::MAX {
+            self.value += 1;
+        }
+    }
+
+    #[requires(self.value() <= u32::MAX - amount)]
+    pub fn add(&mut self, amount: u32) {
+        self.value += amount;
+    }
+
+    #[trusted]
+    pub fn reset_if_full(&mut self) {
+        if self.value == u32::MAX {
+            self.value = 0;
+        }
+    }
+}
+
+fn client(counter: &mut Counter) {
+    let before = counter.value();
+    counter.increment();
+    counter.reset_if_full();
+    assert!(before <= counter.value());
+}
+
+fn main() {}
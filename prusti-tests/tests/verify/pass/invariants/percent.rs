@@ -0,0 +1,40 @@
+use prusti_contracts::*;
+
+#[invariant(self.value <= 100)]
+pub struct Percent {
+    value: u8,
+}
+
+impl Percent {
+    #[requires(value <= 100)]
+    pub fn new(value: u8) -> Self {
+        Percent { value }
+    }
+
+    #[pure]
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn complement(self) -> Percent {
+        // No overflow, the invariant guarantees that `self.value <= 100`
+        Percent { value: 100 - self.value }
+    }
+
+    #[no_invariant]
+    fn set_unchecked(&mut self, value: u8) {
+        self.value = value;
+    }
+
+    pub fn set(&mut self, value: u8) {
+        if value <= 100 {
+            self.set_unchecked(value);
+        }
+    }
+}
+
+fn main() {
+    let p = Percent::new(30);
+    let q = p.complement();
+    assert!(q.value() <= 100);
+}
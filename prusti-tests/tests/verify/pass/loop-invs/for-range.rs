@@ -0,0 +1,31 @@
+use prusti_contracts::*;
+
+#[ensures(result * 2 + n == n * n)]
+fn sum(n: u32) -> u32 {
+    let mut sum = 0;
+    for i in 0..n {
+        body_invariant!(sum * 2 + i == i * i);
+        sum += i;
+    }
+    sum
+}
+
+#[requires(a <= b)]
+#[ensures(result == b - a)]
+fn count(a: i32, b: i32) -> i32 {
+    let mut count = 0;
+    for i in a..b {
+        body_invariant!(a <= i && i < b);
+        body_invariant!(count == i - a);
+        count += 1;
+    }
+    count
+}
+
+fn bounds(n: usize) {
+    for i in 0..n {
+        assert!(i < n);
+    }
+}
+
+fn main() {}
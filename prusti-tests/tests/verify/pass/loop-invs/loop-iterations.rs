@@ -0,0 +1,38 @@
+use prusti_contracts::*;
+
+#[requires(k <= 100)]
+#[ensures(result == 10 * k)]
+fn repeat_add(k: usize) -> usize {
+    let mut sum = 0;
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(i < 10);
+        body_invariant!(i == loop_iterations());
+        body_invariant!(sum == loop_iterations() * k);
+        sum += k;
+        i += 1;
+    }
+    sum
+}
+
+fn nested() {
+    let mut total = 0;
+    let mut i = 0;
+    while i < 5 {
+        body_invariant!(i < 5);
+        body_invariant!(i == loop_iterations());
+        body_invariant!(total == 3 * loop_iterations());
+        let mut j = 0;
+        while j < 3 {
+            body_invariant!(j < 3);
+            body_invariant!(j == loop_iterations());
+            body_invariant!(total == 3 * i + loop_iterations());
+            total += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    assert!(total == 15);
+}
+
+fn main() {}
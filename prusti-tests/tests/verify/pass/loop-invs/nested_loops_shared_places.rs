@@ -0,0 +1,43 @@
+use prusti_contracts::*;
+
+struct Matrix {
+    rows: usize,
+    data: [i32; 4],
+}
+
+#[requires(m.rows < 10)]
+fn reset(m: &mut Matrix) {
+    let mut i = 0;
+    while i < m.rows {
+        body_invariant!(i < m.rows);
+        let mut j = 0;
+        while j < 4 {
+            body_invariant!(j < 4);
+            m.data[j] = 0;
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+#[requires(m.rows < 10)]
+fn sum(m: &mut Matrix) -> i32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < m.rows {
+        body_invariant!(i < m.rows);
+        let mut j = 0;
+        while j < 4 {
+            body_invariant!(j < 4);
+            if m.data[j] > 0 && m.data[j] < 100 && total < 1000 {
+                total += m.data[j];
+            }
+            j += 1;
+        }
+        m.data[0] = 0;
+        i += 1;
+    }
+    total
+}
+
+fn main() {}
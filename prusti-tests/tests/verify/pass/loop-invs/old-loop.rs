@@ -0,0 +1,47 @@
+use prusti_contracts::*;
+
+fn count_up(n: u32) {
+    let mut counter = n;
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(i < 10);
+        body_invariant!(counter == old_loop(counter) + i);
+        counter += 1;
+        i += 1;
+    }
+    assert!(counter == n + 10);
+}
+
+#[requires(*x < 1000)]
+#[ensures(*x == old(*x) + 15)]
+fn add(x: &mut u32) {
+    *x += 5;
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(i < 10);
+        body_invariant!(*x == old(*x) + 5 + i);
+        body_invariant!(*x == old_loop(*x) + i);
+        *x += 1;
+        i += 1;
+    }
+}
+
+fn nested() {
+    let mut counter = 0;
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(i < 10);
+        body_invariant!(counter == old_loop(counter) + 10 * i);
+        let mut j = 0;
+        while j < 10 {
+            body_invariant!(j < 10);
+            body_invariant!(counter == old_loop(counter) + j);
+            counter += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    assert!(counter == 100);
+}
+
+fn main() {}
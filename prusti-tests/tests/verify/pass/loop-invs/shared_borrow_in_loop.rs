@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+
+#[pure]
+fn first(v: &[i32; 4]) -> i32 {
+    v[0]
+}
+
+fn read(v: &[i32; 4]) -> i32 {
+    v[0]
+}
+
+#[ensures(first(v) == old(first(v)))]
+fn sum(v: &[i32; 4]) -> i32 {
+    let mut total = 0;
+    let mut counter = 0;
+    while counter < 4 {
+        body_invariant!(counter < 4);
+        total += read(v);
+        counter += 1;
+    }
+    total
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+use prusti_contracts::*;
+
+#[requires(n > 0)]
+#[ensures(result == 2 * n)]
+fn double(n: u32) -> u32 {
+    let mut i = 0;
+    let mut sum = 0;
+    while i < n {
+        body_invariant!(i < n);
+        body_invariant!(sum == 2 * i);
+        sum += 2;
+        i += 1;
+    }
+    sum
+}
+
+fn infinite(mut x: u32) {
+    loop {
+        body_invariant!(x >= 1 || x == 0);
+        body_invariant!(true);
+        if x > 10 {
+            break;
+        }
+        x += 1;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,40 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy)]
+struct Settings {
+    step: u32,
+    limit: u32,
+}
+
+#[requires(s.step <= 10)]
+#[ensures(s.step == old(s.step))]
+#[ensures(s.limit == old(s.limit))]
+#[ensures(result == 10 * s.step)]
+fn repeat(s: &mut Settings) -> u32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(i < 10);
+        body_invariant!(unchanged(*s));
+        body_invariant!(total == i * s.step);
+        total += s.step;
+        i += 1;
+    }
+    total
+}
+
+#[requires(s.step <= 10 && s.limit <= 1000)]
+#[ensures(s.limit == old(s.limit) + 10 * s.step)]
+#[ensures(s.step == old(s.step))]
+fn raise_limit(s: &mut Settings) {
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(i < 10);
+        body_invariant!(unchanged(s.step));
+        body_invariant!(s.limit == old_loop(s.limit) + i * s.step);
+        s.limit += s.step;
+        i += 1;
+    }
+}
+
+fn main() {}
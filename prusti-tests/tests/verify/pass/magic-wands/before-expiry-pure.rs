@@ -0,0 +1,59 @@
+use prusti_contracts::*;
+
+pub struct Cell {
+    value: i32,
+}
+
+impl Cell {
+    #[pure]
+    pub fn get(&self) -> i32 {
+        self.value
+    }
+
+    #[ensures(self.get() == value)]
+    pub fn set(&mut self, value: i32) {
+        self.value = value;
+    }
+}
+
+pub struct VecWrapper {
+    v: Vec<Cell>,
+}
+
+impl VecWrapper {
+    #[trusted]
+    #[pure]
+    pub fn len(&self) -> usize {
+        self.v.len()
+    }
+
+    #[trusted]
+    #[pure]
+    #[requires(index < self.len())]
+    pub fn lookup(&self, index: usize) -> i32 {
+        self.v[index].get()
+    }
+
+    #[trusted]
+    #[requires(index < self.len())]
+    #[after_expiry(
+        self.len() == old(self.len()) &&
+        self.lookup(index) == before_expiry(result.get()) &&
+        forall(|i: usize| (i < self.len() && i != index) ==>
+            self.lookup(i) == old(self.lookup(i)))
+    )]
+    pub fn index_mut(&mut self, index: usize) -> &mut Cell {
+        &mut self.v[index]
+    }
+}
+
+#[requires(index < v.len())]
+#[ensures(v.len() == old(v.len()))]
+#[ensures(v.lookup(index) == value)]
+#[ensures(forall(|i: usize| (i < v.len() && i != index) ==> v.lookup(i) == old(v.lookup(i))))]
+fn update(v: &mut VecWrapper, index: usize, value: i32) {
+    let cell = v.index_mut(index);
+    cell.set(value);
+}
+
+fn main() {}
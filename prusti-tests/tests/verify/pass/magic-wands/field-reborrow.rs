@@ -0,0 +1,53 @@
+use prusti_contracts::*;
+
+struct Pair {
+    left: u32,
+    right: u32,
+}
+
+#[after_expiry(pair.left == before_expiry(*result))]
+fn borrow_left(pair: &mut Pair) -> &mut u32 {
+    &mut pair.left
+}
+
+impl Pair {
+    #[after_expiry(self.right == before_expiry(*result))]
+    fn right_mut(&mut self) -> &mut u32 {
+        &mut self.right
+    }
+}
+
+struct Outer {
+    pair: Pair,
+    count: u32,
+}
+
+#[after_expiry(outer.pair.left == before_expiry(*result))]
+fn borrow_nested_left(outer: &mut Outer) -> &mut u32 {
+    &mut outer.pair.left
+}
+
+#[requires(pair.right == 5)]
+#[ensures(pair.left == 3)]
+#[ensures(pair.right == 5)]
+fn set_left(pair: &mut Pair) {
+    let left = borrow_left(pair);
+    *left = 3;
+}
+
+#[ensures(pair.left == old(pair.left))]
+#[ensures(pair.right == 7)]
+fn set_right(pair: &mut Pair) {
+    let right = pair.right_mut();
+    *right = 7;
+}
+
+#[ensures(outer.pair.left == 1)]
+#[ensures(outer.pair.right == old(outer.pair.right))]
+#[ensures(outer.count == old(outer.count))]
+fn set_nested_left(outer: &mut Outer) {
+    let left = borrow_nested_left(outer);
+    *left = 1;
+}
+
+fn main() {}
@@ -0,0 +1,64 @@
+use prusti_contracts::*;
+use std::mem;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+struct Pair {
+    first: Point,
+    second: Point,
+}
+
+#[ensures(pair.first == old(pair.second))]
+#[ensures(pair.second == old(pair.first))]
+fn swap_fields(pair: &mut Pair) {
+    mem::swap(&mut pair.first, &mut pair.second);
+}
+
+fn swap_integers() {
+    let mut a = 1;
+    let mut b = 2;
+    mem::swap(&mut a, &mut b);
+    assert!(a == 2 && b == 1);
+}
+
+#[pure]
+fn sum(p: &Point) -> i32 {
+    p.x + p.y
+}
+
+#[ensures(p.x == 0 && p.y == 0)]
+#[ensures(result == old(sum(p)))]
+fn reset(p: &mut Point) -> i32 {
+    let old_point = mem::replace(p, Point { x: 0, y: 0 });
+    sum(&old_point)
+}
+
+fn replace_integer() {
+    let mut a = 3;
+    let old_a = core::mem::replace(&mut a, 4);
+    assert!(old_a == 3 && a == 4);
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Counter {
+    value: u32,
+}
+
+impl Default for Counter {
+    #[pure]
+    fn default() -> Self {
+        Counter { value: 0 }
+    }
+}
+
+#[ensures(counter.value == 0)]
+#[ensures(result.value == old(counter.value))]
+fn take_counter(counter: &mut Counter) -> Counter {
+    mem::take(counter)
+}
+
+fn main() {}
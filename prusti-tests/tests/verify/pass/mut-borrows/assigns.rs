@@ -0,0 +1,64 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+struct Inner {
+    x: u32,
+    y: u32,
+}
+
+struct State {
+    counter: u32,
+    limit: u32,
+    inner: Inner,
+}
+
+#[assigns(s.counter)]
+#[requires(s.counter < 100)]
+#[ensures(s.counter == old(s.counter) + 1)]
+fn increment(s: &mut State) {
+    s.counter += 1;
+}
+
+#[assigns(s.inner.x)]
+fn reset_x(s: &mut State, other: &mut u32) {
+    s.inner.x = 0;
+}
+
+#[assigns(a, b.x)]
+fn swap_into(a: &mut u32, b: &mut Inner) {
+    let tmp = *a;
+    *a = b.x;
+    b.x = tmp;
+}
+
+impl State {
+    #[assigns(self.limit)]
+    fn set_limit(&mut self, limit: u32) {
+        self.limit = limit;
+    }
+}
+
+#[requires(s.counter < 100)]
+fn client(s: &mut State) {
+    let limit = s.limit;
+    let y = s.inner.y;
+    increment(s);
+    // The fields that are not assigned are unchanged
+    assert!(s.limit == limit);
+    assert!(s.inner.y == y);
+
+    let counter = s.counter;
+    let mut other = 5;
+    reset_x(s, &mut other);
+    assert!(s.counter == counter);
+    assert!(s.inner.y == y);
+    assert!(other == 5);
+
+    let mut a = 1;
+    swap_into(&mut a, &mut s.inner);
+    assert!(s.inner.y == y);
+
+    s.set_limit(10);
+    assert!(s.counter == counter);
+}
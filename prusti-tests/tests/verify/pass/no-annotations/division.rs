@@ -0,0 +1,25 @@
+fn main() {
+    let (pos, neg) = (7, -7);
+    let (two, minus_two) = (2, -2);
+
+    assert!(pos / two == 3);
+    assert!(pos / minus_two == -3);
+    assert!(neg / two == -3);
+    assert!(neg / minus_two == 3);
+
+    // The sign of the remainder is the one of the dividend
+    assert!(pos % two == 1);
+    assert!(pos % minus_two == 1);
+    assert!(neg % two == -1);
+    assert!(neg % minus_two == -1);
+
+    let (six, minus_six) = (6, -6);
+    assert!(minus_six / two == -3);
+    assert!(minus_six / minus_two == 3);
+    assert!(six / minus_two == -3);
+    assert!(minus_six % two == 0);
+
+    let (seven, three) = (7u32, 3u32);
+    assert!(seven / three == 2);
+    assert!(seven % three == 1);
+}
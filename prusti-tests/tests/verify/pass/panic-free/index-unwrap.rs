@@ -0,0 +1,49 @@
+// compile-flags: -Pcheck_panics=false
+
+use prusti_contracts::*;
+
+#[pure]
+fn is_some(x: &Option<u32>) -> bool {
+    matches!(x, Some(_))
+}
+
+#[panic_free]
+#[requires(idx < v.len())]
+fn get(v: &[u32], idx: usize) -> u32 {
+    v[idx]
+}
+
+#[panic_free]
+#[requires(is_some(&x))]
+fn unwrap_some(x: Option<u32>) -> u32 {
+    x.unwrap()
+}
+
+#[panic_free]
+#[requires(is_some(&x))]
+fn expect_some(x: Option<u32>) -> u32 {
+    x.expect("a value")
+}
+
+#[panic_free]
+fn unwrap_ok() -> u32 {
+    let r: Result<u32, ()> = Ok(5);
+    r.unwrap()
+}
+
+#[panic_free]
+#[requires(idx < v.len())]
+fn first_or_zero(v: &[u32], idx: usize, x: Option<u32>) -> u32 {
+    if is_some(&x) {
+        get(v, idx) + x.unwrap() % 2
+    } else {
+        get(v, idx) % 2
+    }
+}
+
+// Without `#[panic_free]` the unchecked panics are allowed by `check_panics=false`
+fn unchecked(x: Option<u32>, v: &[u32]) -> u32 {
+    v[0] + x.unwrap() % 2
+}
+
+fn main() {}
@@ -0,0 +1,48 @@
+use prusti_contracts::*;
+
+pub struct VecWrapperI32 {
+    v: Vec<i32>
+}
+
+impl VecWrapperI32 {
+    #[trusted]
+    #[pure]
+    pub fn len(&self) -> usize {
+        self.v.len()
+    }
+
+    #[trusted]
+    #[pure]
+    #[requires(index < self.len())]
+    pub fn lookup(&self, index: usize) -> i32 {
+        self.v[index]
+    }
+
+    #[trusted]
+    #[requires(index <= self.len())]
+    #[ensures(self.len() == old(self.len()) + 1)]
+    #[ensures(self.lookup(index) == value)]
+    #[ensures(forall(|i: usize| i < index ==> self.lookup(i) == old(self.lookup(i))))]
+    #[ensures(forall(|i: usize| index < i && i < self.len() ==>
+                    self.lookup(i) == old(self.lookup(i - 1))))]
+    pub fn insert(&mut self, index: usize, value: i32) {
+        self.v.insert(index, value);
+    }
+}
+
+#[predicate]
+fn sorted(v: &VecWrapperI32) -> bool {
+    forall(|i: usize, j: usize| i < j && j < v.len() ==> v.lookup(i) <= v.lookup(j))
+}
+
+#[requires(sorted(v))]
+#[requires(index <= v.len())]
+#[requires(index == 0 || v.lookup(index - 1) <= value)]
+#[requires(index == v.len() || value <= v.lookup(index))]
+#[ensures(sorted(v))]
+#[ensures(v.len() == old(v.len()) + 1)]
+fn insert_sorted(v: &mut VecWrapperI32, index: usize, value: i32) {
+    v.insert(index, value);
+}
+
+fn main() {}
@@ -0,0 +1,80 @@
+use prusti_contracts::*;
+use std::ops::Deref;
+
+pub struct VecWrapperI32 {
+    v: Vec<i32>
+}
+
+impl VecWrapperI32 {
+    #[trusted]
+    #[pure]
+    pub fn len(&self) -> usize {
+        self.v.len()
+    }
+
+    #[trusted]
+    #[pure]
+    #[requires(index < self.len())]
+    pub fn lookup(&self, index: usize) -> i32 {
+        self.v[index]
+    }
+}
+
+/// A smart pointer to a vector.
+pub struct Handle {
+    target: VecWrapperI32,
+}
+
+impl Deref for Handle {
+    type Target = VecWrapperI32;
+
+    #[pure]
+    fn deref(&self) -> &VecWrapperI32 {
+        &self.target
+    }
+}
+
+/// A smart pointer to a smart pointer, to chain the dereferences.
+pub struct SharedHandle {
+    handle: Handle,
+}
+
+impl Deref for SharedHandle {
+    type Target = Handle;
+
+    #[pure]
+    #[trusted]
+    #[ensures(result.len() == self.handle.len())]
+    fn deref(&self) -> &Handle {
+        &self.handle
+    }
+}
+
+pub struct Buffer {
+    inner: Handle,
+}
+
+impl Buffer {
+    #[pure]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[requires(0 < self.inner.len())]
+    #[ensures(result == self.inner.lookup(0))]
+    pub fn first(&self) -> i32 {
+        self.inner.target.lookup(0)
+    }
+}
+
+#[requires(0 < b.len())]
+#[ensures(result == b.inner.lookup(0))]
+fn first(b: &Buffer) -> i32 {
+    b.first()
+}
+
+#[requires(h.len() == 3)]
+#[ensures(h.handle.len() == 3)]
+fn chained(h: &SharedHandle) {}
+
+fn main() {}
@@ -0,0 +1,31 @@
+use prusti_contracts::*;
+
+#[pure]
+fn fib(n: usize) -> usize {
+    match n {
+        0 => 0,
+        1 => 1,
+        n => fib(n - 1) + fib(n - 2),
+    }
+}
+
+#[pure]
+#[fuel(10)]
+fn deep_fib(n: usize) -> usize {
+    match n {
+        0 => 0,
+        1 => 1,
+        n => deep_fib(n - 1) + deep_fib(n - 2),
+    }
+}
+
+// Needs two unfoldings of `fib(n + 2)`, which the default fuel provides.
+#[requires(n >= 1)]
+#[ensures(fib(n + 2) == 2 * fib(n) + fib(n - 1))]
+fn two_unfoldings(n: usize) {}
+
+// Needs ten unfoldings of `deep_fib(10)`.
+#[ensures(deep_fib(10) == 55)]
+fn ten_unfoldings() {}
+
+fn main() {}
@@ -0,0 +1,33 @@
+use prusti_contracts::*;
+
+trait Counter {
+    fn count(&self) -> usize;
+}
+
+struct Zero {
+    value: usize,
+}
+
+impl Counter for Zero {
+    #[pure]
+    fn count(&self) -> usize {
+        self.value
+    }
+}
+
+#[ensures(result.count() == 0)]
+fn make() -> impl Counter {
+    Zero { value: 0 }
+}
+
+#[requires(value > 0)]
+#[ensures(result.count() == value)]
+#[ensures(result.count() > 0)]
+fn make_with(value: usize) -> impl Counter {
+    Zero { value }
+}
+
+fn main() {
+    let _zero = make();
+    let _one = make_with(1);
+}
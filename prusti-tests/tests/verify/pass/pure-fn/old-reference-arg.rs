@@ -0,0 +1,55 @@
+use prusti_contracts::*;
+
+struct VecWrapper {
+    v: Vec<i32>,
+}
+
+impl VecWrapper {
+    #[trusted]
+    #[pure]
+    fn len(&self) -> usize {
+        self.v.len()
+    }
+
+    #[trusted]
+    #[requires(self.len() < usize::MAX)]
+    #[ensures(self.len() == old(self).len() + 1)]
+    fn push(&mut self, value: i32) {
+        self.v.push(value);
+    }
+}
+
+#[pure]
+fn length(w: &VecWrapper) -> usize {
+    w.len()
+}
+
+#[requires(length(w) < 100)]
+#[ensures(length(w) == length(old(w)) + 1)]
+fn push_one(w: &mut VecWrapper) {
+    w.push(1);
+}
+
+#[requires(length(w) < 99)]
+#[ensures(length(old(w)) + 2 == length(w))]
+fn push_two(w: &mut VecWrapper) {
+    push_one(w);
+    push_one(w);
+}
+
+struct Counter {
+    n: u32,
+}
+
+#[pure]
+fn count(c: &Counter) -> u32 {
+    c.n
+}
+
+#[requires(count(c) < 100)]
+#[ensures(count(c) == count(old(c)) + 1)]
+fn increment(c: &mut Counter) {
+    c.n += 1;
+}
+
+fn main() {}
@@ -0,0 +1,35 @@
+use prusti_contracts::*;
+
+struct CachedLen {
+    len: usize,
+    cached: bool,
+}
+
+impl CachedLen {
+    /// Only reads the cached length, even though the receiver is mutable.
+    #[pure]
+    fn get_len(&mut self) -> usize {
+        if self.cached {
+            self.len
+        } else {
+            0
+        }
+    }
+
+    #[requires(self.cached && self.len < 100)]
+    #[ensures(self.get_len() == old(self.get_len()) + 1)]
+    #[ensures(self.cached)]
+    fn grow(&mut self) {
+        self.len += 1;
+    }
+}
+
+fn main() {
+    let mut c = CachedLen { len: 3, cached: true };
+    let len = c.get_len();
+    assert!(len == 3);
+    // The call does not modify the receiver
+    assert!(c.len == 3 && c.cached);
+    c.grow();
+    assert!(c.get_len() == 4);
+}
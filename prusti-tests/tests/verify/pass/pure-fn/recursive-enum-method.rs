@@ -0,0 +1,40 @@
+use prusti_contracts::*;
+
+enum List {
+    Nil,
+    Cons(i32, Box<List>),
+}
+
+impl List {
+    #[pure]
+    #[ensures(result >= 0)]
+    fn len(&self) -> usize {
+        match self {
+            List::Nil => 0,
+            List::Cons(_, tail) => 1 + tail.len(),
+        }
+    }
+
+    #[pure]
+    fn sum(&self) -> i32 {
+        match self {
+            List::Nil => 0,
+            List::Cons(value, tail) => *value + tail.sum(),
+        }
+    }
+}
+
+fn client() {
+    let list = List::Cons(1, Box::new(List::Cons(2, Box::new(List::Cons(3, Box::new(List::Nil))))));
+    assert!(list.len() == 3);
+    assert!(list.sum() == 6);
+}
+
+#[requires(list.len() == 2)]
+fn prepend(list: List) -> List {
+    let longer = List::Cons(0, Box::new(list));
+    assert!(longer.len() == 3);
+    longer
+}
+
+fn main() {}
@@ -0,0 +1,64 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Stay,
+    Step(i32),
+}
+
+#[pure]
+fn origin() -> Point {
+    Point { x: 0, y: 0 }
+}
+
+#[pure]
+#[requires(p.x < 1000)]
+fn shift(p: Point) -> Point {
+    Point { x: p.x + 1, y: p.y }
+}
+
+#[pure]
+fn direction(moving: bool) -> Direction {
+    if moving {
+        Direction::Step(3)
+    } else {
+        Direction::Stay
+    }
+}
+
+#[pure]
+fn pair(a: u32, b: u32) -> (u32, u32) {
+    (a, b)
+}
+
+#[ensures(result == origin())]
+fn make_origin() -> Point {
+    Point { x: 0, y: 0 }
+}
+
+#[ensures(shift(origin()).x == 1)]
+#[ensures(shift(origin()).y == origin().y)]
+fn test_fields() {}
+
+#[ensures(match direction(true) {
+    Direction::Step(n) => n == 3,
+    Direction::Stay => false,
+})]
+#[ensures(match direction(false) {
+    Direction::Stay => true,
+    Direction::Step(_) => false,
+})]
+#[ensures(direction(true) == direction(true))]
+fn test_enum() {}
+
+#[ensures(pair(1, 2).0 == 1 && pair(1, 2).1 == 2)]
+#[ensures(pair(1, 2) == pair(1, 2))]
+fn test_tuple() {}
+
+fn main() {}
@@ -0,0 +1,29 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy)]
+struct Pixel {
+    red: u8,
+    shade: i8,
+    offset: isize,
+    len: usize,
+}
+
+struct Image {
+    first: Pixel,
+    count: u16,
+}
+
+#[ensures(forall(|p: Pixel| p.red <= 255))]
+fn unsigned_field() {}
+
+#[ensures(forall(|p: Pixel| -128 <= p.shade && p.shade <= 127))]
+fn signed_field() {}
+
+#[ensures(forall(|p: Pixel| 0 <= p.len && p.len <= usize::MAX))]
+#[ensures(forall(|p: Pixel| isize::MIN <= p.offset && p.offset <= isize::MAX))]
+fn pointer_sized_fields() {}
+
+#[ensures(forall(|i: Image| (i.first.red as u32) + (i.count as u32) < 65791))]
+fn nested_fields() {}
+
+fn main() {}
@@ -0,0 +1,22 @@
+use prusti_contracts::*;
+
+// The triggers read fields of references, so fold-unfold wraps them in
+// `unfolding` expressions, which are removed when verifying with Carbon.
+
+struct Counter {
+    value: i32,
+}
+
+#[pure]
+fn offset(c: &Counter, i: i32) -> i32 {
+    c.value + i
+}
+
+#[requires(forall(|i: i32| 0 <= i ==> offset(c, i) >= c.value, triggers=[(offset(c, i),)]))]
+#[ensures(offset(c, 1) >= c.value)]
+fn test1(c: &Counter) {}
+
+#[ensures(forall(|i: i32| offset(c, i) == c.value + i, triggers=[(offset(c, i),)]))]
+fn test2(c: &Counter) {}
+
+fn main() {}
@@ -0,0 +1,43 @@
+use prusti_contracts::*;
+
+// The quantifiers have no triggers, so they are inferred from the calls of pure
+// functions in their bodies. The calls whose arguments compute on the quantified
+// variables, such as `v.lookup(i + 1)`, are not used as triggers, because every
+// instantiation would create a new term that matches them.
+
+pub struct VecWrapperI32 {
+    v: Vec<i32>
+}
+
+impl VecWrapperI32 {
+    #[trusted]
+    #[pure]
+    pub fn len(&self) -> usize {
+        self.v.len()
+    }
+
+    #[trusted]
+    #[pure]
+    #[requires(index < self.len())]
+    pub fn lookup(&self, index: usize) -> i32 {
+        self.v[index]
+    }
+}
+
+#[requires(3 <= v.len())]
+#[requires(forall(|i: usize| i + 1 < v.len() ==> v.lookup(i) <= v.lookup(i + 1)))]
+#[ensures(v.lookup(0) <= v.lookup(2))]
+fn increasing(v: &VecWrapperI32) {}
+
+#[requires(forall(|i: usize| i < v.len() ==> v.lookup(i) >= 0))]
+#[requires(0 < v.len())]
+#[ensures(result >= 0)]
+fn first(v: &VecWrapperI32) -> i32 {
+    v.lookup(0)
+}
+
+#[requires(0 < v.len() && v.lookup(0) == 0)]
+#[ensures(exists(|i: usize| i < v.len() && v.lookup(i) == 0))]
+fn has_zero(v: &VecWrapperI32) {}
+
+fn main() {}
@@ -0,0 +1,33 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+struct Segment {
+    start: Point,
+    end: Point,
+}
+
+#[pure]
+fn is_vertical(s: Segment) -> bool {
+    s.start.x == s.end.x
+}
+
+#[ensures(forall(|p: Point| p.x + p.y == p.y + p.x))]
+fn test1() {}
+
+#[ensures(forall(|s: Segment| s.start.x == s.end.x ==> is_vertical(s)))]
+fn test2() {}
+
+#[ensures(forall(|t: (i32, bool)| t.1 ==> t.0 == t.0))]
+fn test3() {}
+
+#[ensures(exists(|p: Point| p.x == result && p.y == result))]
+fn test4(x: i32) -> i32 {
+    x
+}
+
+fn main() {}
@@ -0,0 +1,80 @@
+use prusti_contracts::*;
+
+mod limits {
+    pub const LIMIT: usize = 16;
+    pub const ENABLED: bool = true;
+    pub const SEPARATOR: char = ',';
+}
+
+use limits::LIMIT;
+
+enum Kind {
+    A = 1,
+    B = 2,
+}
+
+const KIND_B: isize = Kind::B as isize;
+
+struct Buffer {
+    len: usize,
+}
+
+impl Buffer {
+    const MAX_LEN: usize = 8;
+
+    /// The invariant of the buffer, bounded by the associated constant
+    #[pure]
+    fn invariant(&self) -> bool {
+        self.len <= Self::MAX_LEN
+    }
+
+    #[requires(self.invariant() && self.len < Self::MAX_LEN)]
+    #[ensures(self.invariant())]
+    #[ensures(self.len == old(self.len) + 1)]
+    fn push(&mut self) {
+        self.len += 1;
+    }
+}
+
+trait Counter {
+    const MAX: u32;
+
+    fn get(&self) -> u32;
+}
+
+struct Constant;
+
+#[refine_trait_spec]
+impl Counter for Constant {
+    const MAX: u32 = 10;
+
+    #[ensures(result <= Self::MAX)]
+    fn get(&self) -> u32 {
+        5
+    }
+}
+
+#[requires(x < LIMIT)]
+#[ensures(result <= limits::LIMIT)]
+fn inc(x: usize) -> usize {
+    x + 1
+}
+
+#[requires(limits::ENABLED && c == limits::SEPARATOR)]
+fn separator(c: char) {}
+
+#[ensures(result == 2)]
+fn kind() -> isize {
+    KIND_B
+}
+
+fn test() {
+    let mut buffer = Buffer { len: 0 };
+    buffer.push();
+    assert!(buffer.len == 1);
+    assert!(inc(15) == 16);
+    separator(',');
+    assert!(Constant.get() <= 10);
+}
+
+fn main() {}
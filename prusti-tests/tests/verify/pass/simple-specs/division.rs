@@ -0,0 +1,41 @@
+use prusti_contracts::*;
+
+#[pure]
+#[requires(d != 0 && (x != i32::MIN || d != -1))]
+fn quotient(x: i32, d: i32) -> i32 {
+    x / d
+}
+
+#[pure]
+#[requires(d != 0 && (x != i32::MIN || d != -1))]
+fn remainder(x: i32, d: i32) -> i32 {
+    x % d
+}
+
+#[requires(d != 0 && (x != i32::MIN || d != -1))]
+#[ensures(result.0 == quotient(x, d) && result.1 == remainder(x, d))]
+#[ensures(x == result.0 * d + result.1)]
+#[ensures(x >= 0 ==> result.1 >= 0)]
+#[ensures(x <= 0 ==> result.1 <= 0)]
+fn div_rem(x: i32, d: i32) -> (i32, i32) {
+    (x / d, x % d)
+}
+
+#[ensures(quotient(7, 2) == 3 && remainder(7, 2) == 1)]
+#[ensures(quotient(7, -2) == -3 && remainder(7, -2) == 1)]
+#[ensures(quotient(-7, 2) == -3 && remainder(-7, 2) == -1)]
+#[ensures(quotient(-7, -2) == 3 && remainder(-7, -2) == -1)]
+fn signs() {}
+
+#[ensures(7 / 2 == 3 && 7 % 2 == 1)]
+#[ensures(7 / -2 == -3 && 7 % -2 == 1)]
+#[ensures(-7 / 2 == -3 && -7 % 2 == -1)]
+#[ensures(-7 / -2 == 3 && -7 % -2 == -1)]
+fn signs_in_spec() {}
+
+fn main() {
+    let (q, r) = div_rem(-7, 2);
+    assert!(q == -3 && r == -1);
+    let (q, r) = div_rem(7, -2);
+    assert!(q == -3 && r == 1);
+}
@@ -0,0 +1,17 @@
+use prusti_contracts::*;
+
+#[ensures(exists(|y: i32| y == result))]
+fn test1(x: i32) -> i32 {
+    x
+}
+
+#[ensures(forall(|a: i32| exists(|b: i32| a == b)))]
+fn test2() {}
+
+#[pure]
+fn is_witness(_v: i32) -> bool { true }
+
+#[requires(exists(|v: i32| v == x, triggers=[(is_witness(v),)]))]
+fn test3(x: i32) {}
+
+fn main() {}
@@ -0,0 +1,42 @@
+use prusti_contracts::*;
+
+#[pure]
+fn square(x: i32) -> i32 {
+    x * x
+}
+
+#[requires(0 <= x && x <= 100)]
+fn assert_forall(x: i32) {
+    prusti_assert!(forall(|y: i32| (0 <= y && y <= 100) ==> square(y) >= 0));
+    prusti_assert!(square(x) >= 0);
+}
+
+#[requires(*x < 100)]
+fn assert_old(x: &mut i32) {
+    *x += 1;
+    prusti_assert!(*x == old(*x) + 1);
+}
+
+fn assert_in_loop() {
+    let mut i = 0;
+    while i < 10 {
+        prusti_assert!(i < 10);
+        body_invariant!(0 <= i && i < 10);
+        i += 1;
+        prusti_assert!(i <= 10);
+    }
+}
+
+#[ensures(result > 0)]
+fn assume_postcondition(x: i32) -> i32 {
+    prusti_assume!(x > 0);
+    x
+}
+
+#[ensures(result == 42)]
+fn assume_false() -> i32 {
+    prusti_assume!(false);
+    0
+}
+
+fn main() {}
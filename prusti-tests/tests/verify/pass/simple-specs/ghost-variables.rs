@@ -0,0 +1,36 @@
+use prusti_contracts::*;
+
+#[requires(n <= 1000)]
+#[ensures(result == 3 * n)]
+fn running_sum(n: u32) -> u32 {
+    let mut result = 0;
+    let mut i = 0;
+    ghost! {
+        let mut ghost_sum: u32 = 0;
+    }
+    while i < n {
+        body_invariant!(i < n && ghost_sum == 3 * i && result == ghost_sum);
+        result += 3;
+        ghost! {
+            ghost_sum += 3;
+        }
+        i += 1;
+    }
+    prusti_assert!(result == ghost_sum);
+    result
+}
+
+#[requires(x < 100)]
+fn ghost_old_value(x: &mut u32) {
+    ghost! {
+        let before = *x;
+    }
+    *x += 1;
+    prusti_assert!(*x == before + 1);
+}
+
+fn empty_ghost_block() {
+    ghost! {}
+}
+
+fn main() {}
@@ -0,0 +1,36 @@
+use prusti_contracts::*;
+
+#[requires(*x < 100)]
+fn two_phases(x: &mut i32) {
+    *x += 1;
+    label!("phase1");
+    *x += 1;
+    prusti_assert!(*x == labelled_old("phase1", *x) + 1);
+    prusti_assert!(*x == old(*x) + 2);
+}
+
+fn several_labels() {
+    let mut a = 0;
+    label!("start");
+    a += 1;
+    label!("middle");
+    a += 1;
+    prusti_assert!(labelled_old("start", a) == 0);
+    prusti_assert!(labelled_old("middle", a) == 1);
+    prusti_assert!(a == labelled_old("middle", a) + labelled_old("middle", a));
+}
+
+fn label_before_loop() {
+    let mut sum = 5;
+    label!("before_loop");
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(0 <= i && i < 10);
+        body_invariant!(sum >= labelled_old("before_loop", sum));
+        sum += 1;
+        i += 1;
+    }
+    prusti_assert!(sum >= labelled_old("before_loop", sum));
+}
+
+fn main() {}
@@ -0,0 +1,37 @@
+use prusti_contracts::*;
+
+#[requires(true)]
+#[ensures(false)]
+fn diverge() -> ! {
+    loop {}
+}
+
+#[ensures(result <= 10)]
+fn clamp(x: u32) -> u32 {
+    if x > 10 {
+        diverge()
+    } else {
+        x
+    }
+}
+
+mod qualified {
+    // The specifications do not need the names of `prusti_contracts` in scope
+    #[prusti_contracts::ensures(false)]
+    pub fn diverge() -> ! {
+        loop {}
+    }
+}
+
+mod imported {
+    use prusti_contracts::ensures;
+
+    #[ensures(false)]
+    pub fn diverge() -> ! {
+        loop {}
+    }
+}
+
+#[requires(true)]
+#[ensures(true)]
+fn main() {}
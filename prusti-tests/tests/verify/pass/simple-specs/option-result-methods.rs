@@ -0,0 +1,59 @@
+use prusti_contracts::*;
+
+#[requires(x.is_some())]
+#[ensures(result == x.unwrap())]
+fn get(x: Option<i32>) -> i32 {
+    match x {
+        Some(value) => value,
+        None => unreachable!(),
+    }
+}
+
+#[ensures(x >= 0 ==> result.is_some())]
+#[ensures(x < 0 ==> result.is_none())]
+#[ensures(result.unwrap_or(0) >= 0)]
+fn non_negative(x: i32) -> Option<i32> {
+    if x >= 0 {
+        Some(x)
+    } else {
+        None
+    }
+}
+
+#[requires(x.is_some())]
+#[ensures(result == *x.unwrap())]
+fn read(x: Option<&i32>) -> i32 {
+    match x {
+        Some(value) => *value,
+        None => unreachable!(),
+    }
+}
+
+#[requires(x.is_some() && x.unwrap().is_some())]
+#[ensures(result == x.unwrap().unwrap())]
+fn flatten(x: Option<Option<i32>>) -> i32 {
+    match x {
+        Some(Some(value)) => value,
+        _ => unreachable!(),
+    }
+}
+
+// The snapshot of the error type `E` is abstract
+#[ensures(result.is_err() == (x < 0))]
+#[ensures(result.is_ok() ==> result.unwrap_or(-1) == x)]
+fn check<E>(x: i32, err: E) -> Result<i32, E> {
+    if x < 0 {
+        Err(err)
+    } else {
+        Ok(x)
+    }
+}
+
+fn client() {
+    let v = 5;
+    assert!(read(Some(&v)) == 5);
+    assert!(get(non_negative(3)) == 3);
+    assert!(flatten(Some(Some(7))) == 7);
+}
+
+fn main() {}
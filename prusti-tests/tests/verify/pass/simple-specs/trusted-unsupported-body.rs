@@ -0,0 +1,82 @@
+#![feature(asm)]
+
+use prusti_contracts::*;
+
+/// The body uses inline assembly, which is not supported, but the procedure
+/// is trusted, so only its contract is encoded.
+#[trusted]
+#[requires(x < 100)]
+#[ensures(result == x + 1)]
+fn increment(x: u64) -> u64 {
+    let mut y = x;
+    unsafe {
+        asm!("add {0}, 1", inout(reg) y);
+    }
+    y
+}
+
+/// The body uses raw pointer arithmetic, which is not supported.
+#[trusted]
+#[requires(index < slice.len())]
+#[ensures(result == slice[index])]
+fn read_raw(slice: &[u32], index: usize) -> u32 {
+    unsafe {
+        let ptr = slice.as_ptr().add(index);
+        *ptr.offset(0)
+    }
+}
+
+/// Trusted pure functions are encoded as bodyless functions.
+#[trusted]
+#[pure]
+#[requires(index < slice.len())]
+fn read_raw_pure(slice: &[u32], index: usize) -> u32 {
+    let value;
+    unsafe {
+        asm!("nop");
+        value = *slice.as_ptr().add(index);
+    }
+    value
+}
+
+trait Reader {
+    #[requires(index < slice.len())]
+    fn read(&self, slice: &[u32], index: usize) -> u32;
+}
+
+struct RawReader;
+
+impl Reader for RawReader {
+    /// The body of a trusted trait method implementation is not analysed either.
+    #[trusted]
+    fn read(&self, slice: &[u32], index: usize) -> u32 {
+        unsafe {
+            asm!("nop");
+            *slice.as_ptr().add(index)
+        }
+    }
+}
+
+#[ensures(result == 42)]
+fn client_increment() -> u64 {
+    increment(41)
+}
+
+#[requires(slice.len() > 1)]
+#[ensures(result == slice[1])]
+fn client_read_raw(slice: &[u32]) -> u32 {
+    read_raw(slice, 1)
+}
+
+#[requires(slice.len() > 0)]
+#[ensures(result == read_raw_pure(slice, 0))]
+fn client_read_raw_pure(slice: &[u32]) -> u32 {
+    read_raw_pure(slice, 0)
+}
+
+#[requires(slice.len() > 2)]
+fn client_reader(slice: &[u32]) -> u32 {
+    RawReader.read(slice, 2)
+}
+
+fn main() {}
@@ -0,0 +1,47 @@
+use prusti_contracts::*;
+
+#[ensures(result.0 <= result.1)]
+#[ensures((result.0 == a && result.1 == b) || (result.0 == b && result.1 == a))]
+fn sort2(a: i32, b: i32) -> (i32, i32) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[ensures(result.0 == x)]
+#[ensures(result.1.0 == x && result.1.1 == y)]
+fn nest(x: u32, y: bool) -> (u32, (u32, bool)) {
+    (x, (x, y))
+}
+
+#[ensures({ let (low, high) = result; low <= high })]
+fn sorted_pair(a: i32, b: i32) -> (i32, i32) {
+    sort2(a, b)
+}
+
+#[pure]
+#[ensures(result.0 <= result.1)]
+fn min_max(a: u32, b: u32) -> (u32, u32) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[pure]
+#[ensures(result.1.0 == x)]
+fn nest_pure(x: u32) -> (u32, (u32, u32)) {
+    (x, (x, x))
+}
+
+fn main() {
+    let (low, high) = sort2(3, 1);
+    assert!(low == 1 && high == 3);
+    let nested = nest(5, true);
+    assert!(nested.1.0 == 5 && nested.1.1);
+    assert!(min_max(2, 1).0 == 1);
+    assert!(nest_pure(4).1.0 == 4);
+}
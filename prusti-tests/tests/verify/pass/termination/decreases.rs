@@ -0,0 +1,73 @@
+use prusti_contracts::*;
+
+enum List {
+    Nil,
+    Cons(i32, Box<List>),
+}
+
+#[pure]
+#[ensures(result >= 0)]
+fn len(list: &List) -> i32 {
+    match list {
+        List::Nil => 0,
+        List::Cons(_, tail) => 1 + len(tail),
+    }
+}
+
+/// Structural recursion over the list.
+#[pure]
+#[decreases(len(list))]
+fn sum(list: &List) -> i32 {
+    match list {
+        List::Nil => 0,
+        List::Cons(value, tail) => *value + sum(tail),
+    }
+}
+
+#[pure]
+#[requires(n >= 0)]
+#[decreases(n)]
+fn triangle(n: i32) -> i32 {
+    if n == 0 { 0 } else { n + triangle(n - 1) }
+}
+
+#[pure]
+#[requires(n >= 0)]
+#[decreases(n)]
+fn is_even(n: i32) -> bool {
+    if n == 0 { true } else { is_odd(n - 1) }
+}
+
+#[pure]
+#[requires(n >= 0)]
+#[decreases(n)]
+fn is_odd(n: i32) -> bool {
+    if n == 0 { false } else { is_even(n - 1) }
+}
+
+/// A lexicographic measure: either `m` decreases, or `m` stays the same and `n` decreases.
+#[requires(m >= 0 && n >= 0)]
+#[decreases((m, n))]
+fn count_pairs(m: i32, n: i32) -> i32 {
+    if m == 0 {
+        0
+    } else if n == 0 {
+        count_pairs(m - 1, m - 1)
+    } else {
+        1 + count_pairs(m, n - 1)
+    }
+}
+
+#[requires(n >= 0)]
+#[decreases(n)]
+fn count_loop(n: i32) -> i32 {
+    let mut i = 0;
+    while i < n {
+        body_invariant!(0 <= i && i < n);
+        body_invariant!(decreases(n - i));
+        i += 1;
+    }
+    i
+}
+
+fn main() {}
@@ -0,0 +1,78 @@
+use prusti_contracts::*;
+
+trait Stream {
+    type Item;
+
+    fn next(&mut self) -> Self::Item;
+
+    fn first() -> Self::Item;
+}
+
+struct Counter {
+    count: u32,
+}
+
+#[refine_trait_spec]
+impl Stream for Counter {
+    type Item = u32;
+
+    #[requires(self.count < 100)]
+    #[ensures(self.count == old(self.count) + 1)]
+    #[ensures(result == old(self.count))]
+    fn next(&mut self) -> Self::Item {
+        let current = self.count;
+        self.count += 1;
+        current
+    }
+
+    #[ensures(result == Self::first_value())]
+    fn first() -> Self::Item {
+        0
+    }
+}
+
+impl Counter {
+    #[pure]
+    fn first_value() -> <Self as Stream>::Item {
+        0
+    }
+}
+
+struct Repeat<T: Copy> {
+    value: T,
+    taken: u32,
+}
+
+#[refine_trait_spec]
+impl<T: Copy> Stream for Repeat<T> {
+    type Item = T;
+
+    #[requires(self.taken < 100)]
+    #[ensures(self.taken == old(self.taken) + 1)]
+    fn next(&mut self) -> Self::Item {
+        self.taken += 1;
+        self.value
+    }
+
+    #[trusted]
+    fn first() -> Self::Item {
+        unimplemented!()
+    }
+}
+
+fn test_counter() {
+    let mut counter = Counter { count: 3 };
+    let value = counter.next();
+    assert!(value == 3);
+    assert!(counter.count == 4);
+    assert!(Counter::first() == 0);
+}
+
+fn test_repeat() {
+    let mut repeat = Repeat { value: 7u8, taken: 0 };
+    let value: u8 = repeat.next();
+    assert!(repeat.taken == 1);
+    assert!(value == value);
+}
+
+fn main() {}
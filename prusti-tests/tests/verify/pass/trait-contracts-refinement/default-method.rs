@@ -0,0 +1,47 @@
+use prusti_contracts::*;
+
+trait Percentage {
+    #[ensures(result <= 100)]
+    fn get(&self) -> u8 {
+        42
+    }
+
+    #[requires(arg <= 100)]
+    fn set(&mut self, arg: u8) {
+        assert!(arg <= 100);
+    }
+}
+
+struct Inheriting;
+
+impl Percentage for Inheriting {}
+
+struct Overriding;
+
+impl Percentage for Overriding {
+    fn get(&self) -> u8 {
+        100
+    }
+
+    fn set(&mut self, arg: u8) {
+        assert!(arg != 101);
+    }
+}
+
+fn generic<T: Percentage>(t: &mut T) {
+    let value = t.get();
+    assert!(value <= 100);
+    t.set(value);
+}
+
+fn main() {
+    let mut inheriting = Inheriting;
+    assert!(inheriting.get() <= 100);
+    inheriting.set(100);
+    generic(&mut inheriting);
+
+    let mut overriding = Overriding;
+    assert!(overriding.get() <= 100);
+    overriding.set(100);
+    generic(&mut overriding);
+}
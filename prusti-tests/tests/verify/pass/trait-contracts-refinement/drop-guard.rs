@@ -0,0 +1,55 @@
+use prusti_contracts::*;
+
+struct Data {
+    locked: bool,
+    value: u32,
+}
+
+struct Guard<'a> {
+    data: &'a mut Data,
+}
+
+#[refine_trait_spec]
+impl<'a> Drop for Guard<'a> {
+    #[requires(self.data.locked)]
+    #[ensures(!self.data.locked)]
+    #[ensures(self.data.value == old(self.data.value))]
+    fn drop(&mut self) {
+        self.data.locked = false;
+    }
+}
+
+struct Plain {
+    value: u32,
+}
+
+fn test_guard() {
+    let mut data = Data { locked: false, value: 0 };
+    {
+        data.locked = true;
+        let guard = Guard { data: &mut data };
+        guard.data.value = 5;
+    }
+    assert!(!data.locked);
+    assert!(data.value == 5);
+}
+
+fn test_replace_guard() {
+    let mut first = Data { locked: true, value: 1 };
+    let mut second = Data { locked: true, value: 2 };
+    let mut guard = Guard { data: &mut first };
+    guard = Guard { data: &mut second };
+    assert!(guard.data.value == 2);
+}
+
+fn test_no_drop_impl() {
+    let plain = Plain { value: 3 };
+    let other = 4;
+    {
+        let _inner = Plain { value: 5 };
+    }
+    assert!(plain.value == 3);
+    assert!(other == 4);
+}
+
+fn main() {}
@@ -0,0 +1,41 @@
+use prusti_contracts::*;
+
+trait Counter {
+    #[requires(0 <= x && x < 100)]
+    #[ensures(result >= x)]
+    fn step(&self, x: i32) -> i32;
+
+    #[requires(0 <= x && x < 100)]
+    #[ensures(*value >= old(*value))]
+    fn bump(&self, value: &mut i32, x: i32);
+}
+
+struct Increment;
+
+#[refine_trait_spec]
+impl Counter for Increment {
+    #[requires(x < 1000)]
+    #[ensures(result == x + 1)]
+    fn step(&self, x: i32) -> i32 {
+        x + 1
+    }
+
+    #[requires(x >= 0)]
+    #[ensures(*value == old(*value))]
+    fn bump(&self, value: &mut i32, x: i32) {}
+}
+
+struct Identity;
+
+#[refine_trait_spec]
+impl Counter for Identity {
+    #[ensures(result == x)]
+    #[trusted]
+    fn step(&self, x: i32) -> i32 {
+        x
+    }
+
+    fn bump(&self, value: &mut i32, x: i32) {}
+}
+
+fn main() {}
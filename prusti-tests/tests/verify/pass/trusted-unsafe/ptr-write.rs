@@ -0,0 +1,29 @@
+use prusti_contracts::*;
+use std::ptr;
+
+#[trusted_unsafe]
+#[ensures(result < 10)]
+fn last_digit(a: u32) -> u32 {
+    let mut x = 0;
+    unsafe {
+        ptr::write(&mut x as *mut u32, a);
+    }
+    x % 10
+}
+
+#[trusted_unsafe]
+#[ensures(result == b)]
+fn untouched(a: u32, b: u32) -> u32 {
+    let mut x = 0;
+    let y = b;
+    unsafe {
+        ptr::write(&mut x as *mut u32, a);
+    }
+    if x == y {
+        x
+    } else {
+        y
+    }
+}
+
+fn main() {}
@@ -0,0 +1,37 @@
+use prusti_contracts::*;
+
+struct NonCopy(i32);
+
+#[ensures(result >= 1)]
+#[refine_spec(where T: Copy, [ensures(result == 2)])]
+fn copies<T>(_value: &T) -> i32 {
+    2
+}
+
+#[trusted]
+#[refine_spec(where T: Copy, [requires(n >= 0), ensures(result == n)])]
+fn repeat<T>(_value: &T, n: i32) -> i32 {
+    n
+}
+
+fn copy_client() {
+    let n = copies(&5);
+    assert!(n == 2);
+    let m = repeat(&true, 3);
+    assert!(m == 3);
+}
+
+fn non_copy_client() {
+    let value = NonCopy(5);
+    let n = copies(&value);
+    assert!(n >= 1);
+    // The precondition does not apply to types that are not `Copy`
+    repeat(&value, -1);
+}
+
+fn generic_copy_client<T: Copy>(value: &T) {
+    let n = copies(value);
+    assert!(n == 2);
+}
+
+fn main() {}
@@ -0,0 +1,64 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[ensures(result.len() == n)]
+#[ensures(forall(|i: usize| i < result.len() ==> result[i] == i as u32 * 2))]
+fn evens(n: usize) -> Vec<u32> {
+    let mut v = Vec::new();
+    let mut k = 0;
+    while k < n {
+        body_invariant!(k < n);
+        body_invariant!(v.len() == k);
+        body_invariant!(forall(|i: usize| i < v.len() ==> v[i] == i as u32 * 2));
+        v.push(k as u32 * 2);
+        k += 1;
+    }
+    v
+}
+
+#[requires(v.len() > 0)]
+#[ensures(v.len() == old(v.len()) - 1)]
+#[ensures(result.is_some() && result.unwrap() == old(v[v.len() - 1]))]
+#[ensures(forall(|i: usize| i < v.len() ==> v[i] == old(v[i])))]
+fn pop_last(v: &mut Vec<i32>) -> Option<i32> {
+    v.pop()
+}
+
+#[ensures(result.is_none())]
+fn pop_empty() -> Option<i32> {
+    let mut v: Vec<i32> = Vec::new();
+    v.pop()
+}
+
+#[requires(i < v.len())]
+#[ensures(result == v[i])]
+fn get(v: &Vec<i32>, i: usize) -> i32 {
+    v[i]
+}
+
+#[pure]
+#[requires(i < v.len())]
+fn get_pure(v: &Vec<i32>, i: usize) -> i32 {
+    v[i]
+}
+
+#[requires(i < v.len())]
+#[ensures(v.len() == old(v.len()))]
+#[ensures(v[i] == value)]
+#[ensures(forall(|j: usize| j < v.len() && j != i ==> v[j] == old(v[j])))]
+fn set(v: &mut Vec<i32>, i: usize, value: i32) {
+    v[i] = value;
+}
+
+#[requires(v.len() == 2)]
+fn use_set(v: &mut Vec<i32>) {
+    set(v, 0, 5);
+    assert!(get_pure(v, 0) == 5);
+    assert!(v.len() == 2);
+}
+
+#[ensures(v.len() == old(v.len()) + 1)]
+fn push_generic<T>(v: &mut Vec<T>, value: T) {
+    v.push(value);
+}
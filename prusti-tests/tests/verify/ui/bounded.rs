@@ -0,0 +1,13 @@
+use prusti_contracts::*;
+
+#[bounded(3)]
+#[ensures(result <= 3)]
+fn count(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+fn main() {}
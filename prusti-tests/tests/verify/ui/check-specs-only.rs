@@ -0,0 +1,18 @@
+// compile-flags: -Pcheck_specs_only=true
+
+use prusti_contracts::*;
+
+#[requires(x > 0 && x < 0)]
+fn unsatisfiable(x: i32) -> i32 {
+    x
+}
+
+#[requires(x > 0)]
+#[ensures(result > 0)]
+fn satisfiable(x: i32) -> i32 {
+    // The bodies are not verified
+    assert!(false);
+    x
+}
+
+fn main() {}
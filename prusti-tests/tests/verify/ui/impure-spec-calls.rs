@@ -0,0 +1,38 @@
+#![allow(dead_code, unused_variables)]
+use prusti_contracts::*;
+use std::ops::Add;
+
+#[derive(Clone, Copy)]
+struct Money(u32);
+
+impl Money {
+    fn value(&self) -> u32 {
+        self.0
+    }
+
+    fn reset(&mut self) -> u32 {
+        self.0 = 0;
+        0
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+#[requires(m.value() > 0)]
+fn method_call(m: Money) {}
+
+#[requires((a + b).0 > 0)]
+fn operator_overload(a: Money, b: Money) {}
+
+#[requires(f(0) == 0)]
+fn closure_call<F: Fn(i32) -> i32>(f: F) {}
+
+#[requires(m.reset() == 0)]
+fn mutating_call(m: &mut Money) {}
+
+fn main() {}
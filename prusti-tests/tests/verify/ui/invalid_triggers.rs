@@ -0,0 +1,21 @@
+use prusti_contracts::*;
+
+#[pure]
+fn count(n: usize) -> usize {
+    if n == 0 {
+        0
+    } else {
+        count(n-1) + 1
+    }
+}
+
+#[requires(forall(|n: usize| count(n) < count(n + 1), triggers=[(count(n + 1),)]))]
+pub fn test1() {}
+
+#[requires(forall(|n: usize| count(n) > 0, triggers=[(count(2 * n + 1),)]))]
+pub fn test2() {}
+
+#[requires(forall(|n: usize| count(n) == n, triggers=[(count(n), count(0))]))]
+pub fn test3() {}
+
+fn main() {}
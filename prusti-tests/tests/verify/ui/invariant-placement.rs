@@ -0,0 +1,26 @@
+use prusti_contracts::*;
+
+fn outside_loop(x: u32) {
+    body_invariant!(x > 0);
+}
+
+fn separated_invariants(n: u32) {
+    let mut i = 0;
+    while i < n {
+        body_invariant!(i < n);
+        i += 1;
+        body_invariant!(i <= n);
+    }
+}
+
+fn conditional_invariant(n: u32) {
+    let mut i = 0;
+    while i < n {
+        if i > 2 {
+            body_invariant!(i < n);
+        }
+        i += 1;
+    }
+}
+
+fn main() {}
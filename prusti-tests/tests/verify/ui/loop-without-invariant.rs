@@ -0,0 +1,31 @@
+use prusti_contracts::*;
+
+fn count(n: u32) -> u32 {
+    let mut i = 0;
+    let mut steps = 0;
+    while i < n {
+        i += 1;
+        steps = i;
+    }
+    steps
+}
+
+#[allow_spec_warnings(missing_loop_invariant)]
+fn count_allowed(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+fn count_with_invariant(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        body_invariant!(i < n);
+        i += 1;
+    }
+    i
+}
+
+fn main() {}
@@ -34,7 +34,29 @@ fn precond_or_correctly() -> bool {
     true
 }
 
+#[predicate]
+fn pos(x: i32) -> bool {
+    x > 0
+}
+
+// a predicate defined in terms of two other predicates
+#[predicate]
+fn both() -> bool {
+    true_p() && forall_identity()
+}
+
+// a quantifier wrapping a predicate call
+#[predicate]
+fn all_pos(n: i32) -> bool {
+    forall(|x: i32| x >= n ==> pos(x))
+}
+
+#[requires(both())]
+#[requires(all_pos(n))]
+fn test_nested(n: i32) {}
+
 fn main() {
     test_identity();
     precond_or_correctly();
+    test_nested(1);
 }
@@ -0,0 +1,20 @@
+#![allow(dead_code)]
+
+use prusti_contracts::*;
+
+#[predicate]
+fn ping(b: bool) -> bool {
+    pong(!b)
+}
+
+#[predicate]
+fn pong(b: bool) -> bool {
+    ping(!b)
+}
+
+#[predicate]
+fn diverges() -> bool {
+    diverges()
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use prusti_contracts::*;
+
+#[requires(d != 0)]
+fn div(x: i32, d: i32) -> i32 {
+    x / d //~ ERROR statement might panic: the division might overflow
+}
+
+#[requires(d != 0)]
+fn rem(x: i8, d: i8) -> i8 {
+    x % d //~ ERROR statement might panic: the division might overflow
+}
+
+#[requires(d != 0 && (x != i32::MIN || d != -1))]
+fn checked_div(x: i32, d: i32) -> i32 {
+    x / d
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use prusti_contracts::*;
+
+#[ensures(result as i64 == a as i64 + b as i64)] //~ ERROR postcondition might not hold
+pub fn saturating(a: i32, b: i32) -> i32 {
+    a.saturating_add(b)
+}
+
+#[ensures(result.is_some())] //~ ERROR postcondition might not hold
+pub fn checked_is_some(a: u8) -> Option<u8> {
+    a.checked_add(1)
+}
+
+pub fn wrapping() {
+    let x: u8 = 250;
+    assert!(x.wrapping_add(10) == 255); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
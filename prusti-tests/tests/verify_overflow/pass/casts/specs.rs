@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+
+// The specifications cast to a wider type, so that they cannot overflow.
+
+#[requires(x as i64 * y as i64 <= 1000i64)]
+#[requires(x as i64 * y as i64 >= -1000i64)]
+#[ensures(result as i64 == x as i64 * y as i64)]
+fn mul(x: i32, y: i32) -> i32 {
+    x * y
+}
+
+#[requires(x as u64 + y as u64 < u32::MAX as u64)]
+#[ensures(result as u64 == x as u64 + y as u64)]
+fn add(x: u32, y: u32) -> u32 {
+    x + y
+}
+
+#[requires(0u8 < x)]
+#[ensures(result as u16 <= 255u16 * 2u16)]
+fn double(x: u8) -> u16 {
+    x as u16 * 2
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use prusti_contracts::*;
+
+#[ensures(result == x as i64)]
+pub fn widening(x: i32) -> i64 {
+    x as i64
+}
+
+#[requires(x <= 255)]
+#[ensures(result as u32 == x)]
+pub fn narrowing_in_range(x: u32) -> u8 {
+    x as u8
+}
+
+#[requires(0 <= x)]
+#[ensures(result as i32 == x)]
+pub fn sign_flip_in_range(x: i32) -> u32 {
+    x as u32
+}
+
+fn main() {}
@@ -0,0 +1,62 @@
+use prusti_contracts::*;
+
+#[ensures(a as i64 + b as i64 > std::i32::MAX as i64 ==> result == std::i32::MAX)]
+#[ensures(a as i64 + b as i64 < std::i32::MIN as i64 ==> result == std::i32::MIN)]
+#[ensures(
+    std::i32::MIN as i64 <= a as i64 + b as i64 && a as i64 + b as i64 <= std::i32::MAX as i64
+    ==> result as i64 == a as i64 + b as i64
+)]
+pub fn add_or_saturate(a: i32, b: i32) -> i32 {
+    match a.checked_add(b) {
+        Some(sum) => sum,
+        None => if b > 0 { std::i32::MAX } else { std::i32::MIN },
+    }
+}
+
+pub fn saturating(a: i32, b: i32) -> i32 {
+    let result = a.saturating_add(b);
+    assert!(result == add_or_saturate(a, b));
+    result
+}
+
+pub fn wrapping() {
+    let x: u8 = 250;
+    assert!(x.wrapping_add(10) == 4);
+    let y: i8 = -128;
+    assert!(y.wrapping_sub(1) == 127);
+    let z: u32 = 2147483648;
+    assert!(z.wrapping_mul(2) == 0);
+}
+
+pub fn overflowing() {
+    let x: u8 = 250;
+    let (sum, overflow) = x.overflowing_add(10);
+    assert!(sum == 4 && overflow);
+    let (sum, overflow) = x.overflowing_add(5);
+    assert!(sum == 255 && !overflow);
+}
+
+#[ensures(result.is_none())]
+pub fn checked_underflow() -> Option<u32> {
+    let x: u32 = 0;
+    x.checked_sub(1)
+}
+
+#[requires(x < 100)]
+#[ensures(result.is_some() && result.unwrap() == x + 1)]
+pub fn checked_in_range(x: u32) -> Option<u32> {
+    x.checked_add(1)
+}
+
+pub fn sum_in_range(values: &[u32]) -> u32 {
+    let mut total: u32 = 0;
+    let mut i = 0;
+    while i < values.len() {
+        body_invariant!(i < values.len());
+        total = total.saturating_add(values[i]);
+        i += 1;
+    }
+    total
+}
+
+fn main() {}
@@ -5,10 +5,7 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use rustc_middle::ty;
-use std::{
-    collections::HashMap,
-    convert::TryInto,
-};
+use std::collections::HashMap;
 use crate::encoder::{
     Encoder,
     errors::EncodingResult,
@@ -23,6 +20,31 @@ const LOOKUP_PURE_NAME: &str = "lookup_pure";
 const SLICE_LEN_NAME: &str = "Slice$len";
 
 
+/// The length of an array type.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ArrayLen {
+    /// A length known during the encoding, e.g. 3
+    Fixed(usize),
+    /// A length given by a const generic parameter, e.g. `N`, as the ghost variable encoding it
+    Param(vir::LocalVar),
+}
+
+impl ArrayLen {
+    pub fn to_expr(&self) -> vir::Expr {
+        match self {
+            ArrayLen::Fixed(len) => vir::Expr::from(*len),
+            ArrayLen::Param(var) => vir::Expr::local(var.clone()),
+        }
+    }
+
+    pub fn as_fixed(&self) -> Option<usize> {
+        match self {
+            ArrayLen::Fixed(len) => Some(*len),
+            ArrayLen::Param(_) => None,
+        }
+    }
+}
+
 /// The result of `ArrayEncoder::encode_array_types`. Contains types, type predicates and length of the given array type.
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct EncodedArrayTypes<'tcx> {
@@ -36,8 +58,8 @@ pub struct EncodedArrayTypes<'tcx> {
     pub elem_value_ty: vir::Type,
     /// The non-encoded element type as passed by rustc
     pub elem_ty_rs: ty::Ty<'tcx>,
-    /// The length of the array, e.g. 3
-    pub array_len: usize,
+    /// The length of the array, e.g. 3 or a const generic parameter `N`
+    pub array_len: ArrayLen,
 }
 
 impl<'tcx> EncodedArrayTypes<'tcx> {
@@ -196,15 +218,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ArrayTypesEncoder<'tcx> {
         let elem_ty = encoder.encode_type(elem_ty_rs)?;
         let elem_value_ty = encoder.encode_snapshot_type(elem_ty_rs)?;
 
-        let array_len = encoder.const_eval_intlike(&len.val)?
-            .to_u64().unwrap().try_into().unwrap();
+        let array_len = encoder.encode_array_len(len)?;
 
         // we don't need them here, but want to trigger encoding the definitions
         let _ = encoder.encode_builtin_function_use(
             BuiltinFunctionKind::ArrayLookupPure {
                 array_ty_pred: array_pred.clone(),
                 elem_ty_pred: elem_pred,
-                array_len,
+                array_len: array_len.as_fixed(),
                 return_ty: elem_value_ty.clone(),
             }
         );
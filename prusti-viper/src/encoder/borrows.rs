@@ -12,6 +12,7 @@ use prusti_interface::data::ProcedureDefId;
 // };
 use rustc_hir::{self as hir, Mutability};
 use rustc_middle::{mir, ty::FnSig, ty::subst::SubstsRef};
+use rustc_middle::mir::visit::{NonMutatingUseContext, PlaceContext, Visitor};
 use rustc_index::vec::Idx;
 use rustc_middle::ty::{self, Ty, TyCtxt, TyKind, TypeckResults};
 // use rustc_data_structures::indexed_vec::Idx;
@@ -33,6 +34,10 @@ where
     pub region: Option<ty::BoundRegionKind>,
     pub blocking_paths: Vec<(P, Mutability)>,
     pub blocked_paths: Vec<(P, Mutability)>,
+    /// Places below the blocked paths that are neither reborrowed by the
+    /// blocking paths nor modified by the procedure. They keep their initial
+    /// value when the borrow expires.
+    pub framed_paths: Vec<P>,
     //blocked_lifetimes: Vec<String>, TODO: Get this info from the constraints graph.
 }
 
@@ -42,6 +47,7 @@ impl<P: fmt::Debug> BorrowInfo<P> {
             region,
             blocking_paths: Vec::new(),
             blocked_paths: Vec::new(),
+            framed_paths: Vec::new(),
         }
     }
 }
@@ -62,6 +68,9 @@ impl<P: fmt::Debug> fmt::Display for BorrowInfo<P> {
         for path in self.blocked_paths.iter() {
             writeln!(f, "  {:?}", path)?;
         }
+        for path in self.framed_paths.iter() {
+            writeln!(f, "  unchanged {:?}", path)?;
+        }
         writeln!(f, "}}")
     }
 }
@@ -123,6 +132,14 @@ impl<'tcx, L: fmt::Debug, P: fmt::Debug> ProcedureContractGeneric<'tcx, L, P> {
             unreachable!("Unexpected: {:?}", self.specification)
         }
     }
+
+    pub fn decreases(&self) -> Option<&typed::Assertion<'tcx>> {
+        if let typed::SpecificationSet::Procedure(spec) = &self.specification {
+            spec.decreases.as_ref()
+        } else {
+            unreachable!("Unexpected: {:?}", self.specification)
+        }
+    }
 }
 
 /// Procedure contract as it is defined in MIR.
@@ -177,6 +194,7 @@ impl<'tcx> ProcedureContractMirDef<'tcx> {
                     .iter()
                     .map(|(p, m)| (p.into(), *m))
                     .collect(),
+                framed_paths: info.framed_paths.iter().map(|p| p.into()).collect(),
             })
             .collect();
         ProcedureContract {
@@ -205,13 +223,15 @@ impl<'tcx> ProcedureContractMirDef<'tcx> {
         for (from, to) in self.args.iter().zip(args) {
             substitutions.insert(*from, *to);
         }
-        let substitute = |(place, mutability): &(_, Mutability)| {
+        let substitute_place = |place: &mir::Place<'tcx>| {
             let root = &get_place_root(place);
-            let substitute_place = places::Place::SubstitutedPlace {
+            places::Place::SubstitutedPlace {
                 substituted_root: *substitutions.get(root).unwrap(),
                 place: place.clone(),
-            };
-            (substitute_place, *mutability)
+            }
+        };
+        let substitute = |(place, mutability): &(_, Mutability)| {
+            (substitute_place(place), *mutability)
         };
         let borrow_infos = self
             .borrow_infos
@@ -220,6 +240,7 @@ impl<'tcx> ProcedureContractMirDef<'tcx> {
                 region: info.region,
                 blocking_paths: info.blocking_paths.iter().map(&substitute).collect(),
                 blocked_paths: info.blocked_paths.iter().map(&substitute).collect(),
+                framed_paths: info.framed_paths.iter().map(&substitute_place).collect(),
             })
             .collect();
         let returned_refs = self.returned_refs.iter().map(&substitute).collect();
@@ -496,3 +517,186 @@ where
     trace!("[compute_borrow_infos] exit result={}", contract);
     Ok(contract)
 }
+
+/// Computes the `framed_paths` of the magic wands of a procedure from its body.
+///
+/// If the body returns a mutable reborrow of a strict subplace of a reference
+/// argument (for example, `&mut pair.left`) and does not modify the argument
+/// otherwise, the sibling fields along the path to the subplace (for example,
+/// `pair.right`) keep their initial value until the returned borrow expires.
+pub fn compute_framed_paths<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    mir: &mir::Body<'tcx>,
+    contract: &mut ProcedureContractMirDef<'tcx>,
+) {
+    for borrow_info in contract.borrow_infos.iter_mut() {
+        let returns_mut_ref = matches!(
+            &borrow_info.blocking_paths[..],
+            [(place, Mutability::Mut)]
+                if place.local == mir::RETURN_PLACE && place.projection[..] == [mir::ProjectionElem::Deref]
+        );
+        let arg = match &borrow_info.blocked_paths[..] {
+            [(place, Mutability::Mut)] if place.projection[..] == [mir::ProjectionElem::Deref] => {
+                place.local
+            }
+            _ => continue,
+        };
+        if !returns_mut_ref {
+            continue;
+        }
+        let reborrow = match find_returned_reborrow(tcx, mir) {
+            Some(reborrow) => reborrow,
+            None => continue,
+        };
+        if reborrow.place.local != arg || reborrow.place.projection.len() < 2 {
+            continue;
+        }
+        let field_path = match &reborrow.place.projection[..] {
+            [mir::ProjectionElem::Deref, fields @ ..] => fields,
+            _ => continue,
+        };
+        if field_path.iter().any(|elem| !matches!(elem, mir::ProjectionElem::Field(..))) {
+            continue;
+        }
+        let mut visitor = MutationVisitor {
+            locals: reborrow.locals,
+            exempted: reborrow.locations,
+            is_mutated: false,
+        };
+        visitor.locals.push(arg);
+        visitor.visit_body(mir);
+        if visitor.is_mutated {
+            continue;
+        }
+        let mut parent = tcx.mk_place_deref(arg.into());
+        for elem in field_path {
+            let reborrowed_field = match elem {
+                mir::ProjectionElem::Field(field, _) => *field,
+                _ => unreachable!(),
+            };
+            let siblings: Vec<Ty<'tcx>> = match parent.ty(mir, tcx).ty.kind() {
+                TyKind::Adt(adt_def, substs) if adt_def.is_struct() => {
+                    adt_def.non_enum_variant().fields.iter()
+                        .map(|field| field.ty(tcx, substs))
+                        .collect()
+                }
+                TyKind::Tuple(parts) => parts.types().collect(),
+                _ => break,
+            };
+            for (index, ty) in siblings.into_iter().enumerate() {
+                let field = mir::Field::new(index);
+                if field != reborrowed_field {
+                    borrow_info.framed_paths.push(tcx.mk_place_field(parent, field, ty));
+                }
+            }
+            parent = tcx.mk_place_elem(parent, *elem);
+        }
+    }
+}
+
+/// The place reborrowed by the returned reference, together with the
+/// temporaries and statements through which it flows to the return place.
+struct ReturnedReborrow<'tcx> {
+    place: mir::Place<'tcx>,
+    locals: Vec<mir::Local>,
+    locations: Vec<mir::Location>,
+}
+
+/// Follows the unique assignments of `&mut` borrows and moves backwards from
+/// the return place, until a place based on an argument is borrowed.
+fn find_returned_reborrow<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    mir: &mir::Body<'tcx>,
+) -> Option<ReturnedReborrow<'tcx>> {
+    // The returned reference points to `(*current).suffix`.
+    let mut current = mir::RETURN_PLACE;
+    let mut suffix: Vec<mir::PlaceElem<'tcx>> = vec![];
+    let mut locals = vec![];
+    let mut locations = vec![];
+    loop {
+        if locals.contains(&current) {
+            return None;
+        }
+        let (rvalue, location) = find_unique_assignment(mir, current)?;
+        locals.push(current);
+        locations.push(location);
+        current = match rvalue {
+            mir::Rvalue::Ref(_, mir::BorrowKind::Mut { .. }, borrowed) => {
+                let is_arg = (1..=mir.arg_count).contains(&borrowed.local.index());
+                match borrowed.projection.split_first() {
+                    Some((mir::ProjectionElem::Deref, rest)) if !is_arg => {
+                        suffix.splice(0..0, rest.iter().cloned());
+                        borrowed.local
+                    }
+                    _ => {
+                        let mut projection = borrowed.projection.to_vec();
+                        projection.extend(suffix);
+                        return Some(ReturnedReborrow {
+                            place: mir::Place {
+                                local: borrowed.local,
+                                projection: tcx.intern_place_elems(&projection),
+                            },
+                            locals,
+                            locations,
+                        });
+                    }
+                }
+            }
+            mir::Rvalue::Use(mir::Operand::Move(place))
+            | mir::Rvalue::Use(mir::Operand::Copy(place)) => place.as_local()?,
+            _ => return None,
+        };
+    }
+}
+
+/// Returns the right-hand side of the only assignment to `local`, if `local`
+/// is not defined in any other way.
+fn find_unique_assignment<'a, 'tcx>(
+    mir: &'a mir::Body<'tcx>,
+    local: mir::Local,
+) -> Option<(&'a mir::Rvalue<'tcx>, mir::Location)> {
+    let mut assignment = None;
+    for (block, data) in mir.basic_blocks().iter_enumerated() {
+        for (statement_index, statement) in data.statements.iter().enumerate() {
+            if let mir::StatementKind::Assign(box (lhs, rvalue)) = &statement.kind {
+                if lhs.local == local {
+                    if assignment.is_some() || !lhs.projection.is_empty() {
+                        return None;
+                    }
+                    assignment = Some((rvalue, mir::Location { block, statement_index }));
+                }
+            }
+        }
+        if let mir::TerminatorKind::Call { destination: Some((place, _)), .. } = &data.terminator().kind {
+            if place.local == local {
+                return None;
+            }
+        }
+    }
+    assignment
+}
+
+/// Checks whether any of the given locals, or the places they point to, are
+/// modified or moved outside of the exempted locations.
+struct MutationVisitor {
+    locals: Vec<mir::Local>,
+    exempted: Vec<mir::Location>,
+    is_mutated: bool,
+}
+
+impl<'tcx> Visitor<'tcx> for MutationVisitor {
+    fn visit_place(
+        &mut self,
+        place: &mir::Place<'tcx>,
+        context: PlaceContext,
+        location: mir::Location,
+    ) {
+        if !self.locals.contains(&place.local) || self.exempted.contains(&location) {
+            return;
+        }
+        let is_move = matches!(context, PlaceContext::NonMutatingUse(NonMutatingUseContext::Move));
+        if context.is_mutating_use() || is_move {
+            self.is_mutated = true;
+        }
+    }
+}
@@ -21,11 +21,11 @@ pub enum BuiltinFunctionKind {
     Unreachable(vir::Type),
     /// type
     Undefined(vir::Type),
-    /// array lookup pure function
+    /// array lookup pure function; the length is `None` if it is a const generic parameter
     ArrayLookupPure {
         array_ty_pred: String,
         elem_ty_pred: String,
-        array_len: usize,
+        array_len: Option<usize>,
         return_ty: vir::Type,
     },
     /// lookup_pure function for slices
@@ -39,6 +39,32 @@ pub enum BuiltinFunctionKind {
         slice_ty_pred: String,
         elem_ty_pred: String,
     },
+    /// lookup_pure function for vectors
+    VecLookupPure {
+        vec_ty_pred: String,
+        elem_ty_pred: String,
+        return_ty: vir::Type,
+    },
+    /// abstract length function for vectors
+    VecLen {
+        vec_ty_pred: String,
+        elem_ty_pred: String,
+    },
+    /// bitwise operation on integers of the given bit width
+    BitwiseOp {
+        op: BitwiseOpKind,
+        width: u64,
+        signed: bool,
+    },
+    /// `2^exponent`, used to encode shifts by a non-constant amount
+    PowerOfTwo,
+}
+
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum BitwiseOpKind {
+    And,
+    Or,
+    Xor,
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
@@ -95,8 +121,19 @@ impl BuiltinEncoder {
             BuiltinFunctionKind::Undefined(vir::Type::Domain(_)) => "builtin$undef_doman".to_string(),
             BuiltinFunctionKind::Undefined(vir::Type::Snapshot(_)) => "builtin$undef_snap".to_string(),
             BuiltinFunctionKind::ArrayLookupPure { .. }
-            | BuiltinFunctionKind::SliceLookupPure { .. } => "lookup_pure".to_string(),
+            | BuiltinFunctionKind::SliceLookupPure { .. }
+            | BuiltinFunctionKind::VecLookupPure { .. } => "lookup_pure".to_string(),
             BuiltinFunctionKind::SliceLen { .. } => "Slice$len".to_string(),
+            BuiltinFunctionKind::VecLen { .. } => "Vec$len".to_string(),
+            BuiltinFunctionKind::BitwiseOp { op, width, signed } => {
+                let op_name = match op {
+                    BitwiseOpKind::And => "and",
+                    BitwiseOpKind::Or => "or",
+                    BitwiseOpKind::Xor => "xor",
+                };
+                format!("builtin$bv_{}${}{}", op_name, if *signed { "i" } else { "u" }, width)
+            }
+            BuiltinFunctionKind::PowerOfTwo => "builtin$pow2".to_string(),
         }
     }
 
@@ -124,25 +161,31 @@ impl BuiltinEncoder {
                 let self_var = vir::LocalVar::new_typed_ref("self", array_ty_pred.clone());
                 let idx_var = vir_local!{ idx: Int };
 
+                let mut pres = vec![
+                    // acc(self, read$())
+                    vir::Expr::predicate_access_predicate(
+                        array_ty_pred,
+                        vir::Expr::local(self_var.clone()),
+                        vir::PermAmount::Read,
+                    ),
+                ];
+                // A const generic length is not in scope of the function, so the
+                // bounds of the index are only checked at the accesses.
+                if let Some(array_len) = array_len {
+                    // idx < {len}
+                    pres.push(vir!([vir::Expr::local(idx_var.clone())]  < [vir::Expr::from(array_len)]));
+                }
+
                 vir::Function {
                     name: fn_name,
                     formal_args: vec![
                         // self,
-                        self_var.clone(),
+                        self_var,
                         // idx,
-                        idx_var.clone(),
+                        idx_var,
                     ],
                     return_type: return_ty,
-                    pres: vec![
-                        // acc(self, read$())
-                        vir::Expr::predicate_access_predicate(
-                            array_ty_pred,
-                            vir::Expr::local(self_var),
-                            vir::PermAmount::Read,
-                        ),
-                        // idx < {len}
-                        vir!([vir::Expr::local(idx_var)]  < [vir::Expr::from(array_len)]),
-                    ],
+                    pres,
                     posts: vec![],
                     body: None,
                 }
@@ -151,67 +194,226 @@ impl BuiltinEncoder {
                 let slice_len = self.encode_builtin_function_name(
                     &BuiltinFunctionKind::SliceLen { slice_ty_pred: slice_ty_pred.clone(), elem_ty_pred }
                 );
-                let self_var = vir::LocalVar::new_typed_ref("self", slice_ty_pred.clone());
-                let idx_var = vir_local!{ idx: Int };
-
-                let slice_len_call = vir::Expr::func_app(
-                    slice_len,
-                    vec![
-                        vir::Expr::local(self_var.clone()),
-                    ],
-                    vec![
-                        self_var.clone(),
-                    ],
-                    vir::Type::Int,
-                    vir::Position::default(),
+                self.encode_sequence_lookup_pure_def(fn_name, slice_ty_pred, slice_len, return_ty)
+            },
+            BuiltinFunctionKind::SliceLen { slice_ty_pred, .. } => {
+                self.encode_sequence_len_def(fn_name, slice_ty_pred)
+            },
+            BuiltinFunctionKind::VecLookupPure { vec_ty_pred, elem_ty_pred, return_ty } => {
+                let vec_len = self.encode_builtin_function_name(
+                    &BuiltinFunctionKind::VecLen { vec_ty_pred: vec_ty_pred.clone(), elem_ty_pred }
                 );
-
+                self.encode_sequence_lookup_pure_def(fn_name, vec_ty_pred, vec_len, return_ty)
+            },
+            BuiltinFunctionKind::VecLen { vec_ty_pred, .. } => {
+                self.encode_sequence_len_def(fn_name, vec_ty_pred)
+            },
+            BuiltinFunctionKind::BitwiseOp { op, width, signed } => {
+                let left = vir_local!{ left: Int };
+                let right = vir_local!{ right: Int };
+                let posts = if signed {
+                    self.encode_signed_bitwise_op_posts(op, width, left.clone().into(), right.clone().into())
+                } else {
+                    self.encode_unsigned_bitwise_op_posts(op, width, left.clone().into(), right.clone().into())
+                };
                 vir::Function {
                     name: fn_name,
-                    formal_args: vec![
-                        self_var.clone(),
-                        idx_var.clone(),
-                    ],
-                    return_type: return_ty,
-                    pres: vec![
-                        // acc(self, read$())
-                        vir::Expr::predicate_access_predicate(
-                            slice_ty_pred,
-                            vir::Expr::local(self_var),
-                            vir::PermAmount::Read,
-                        ),
-                        // idx < Slice${ty}$len(self)
-                        vir!{ [vir::Expr::local(idx_var)] < [slice_len_call] },
-                    ],
-                    posts: vec![],
+                    formal_args: vec![left, right],
+                    return_type: vir::Type::Int,
+                    pres: vec![],
+                    posts,
                     body: None,
                 }
             },
-            BuiltinFunctionKind::SliceLen { slice_ty_pred, .. } => {
-                let self_var = vir::LocalVar::new_typed_ref("self", slice_ty_pred.clone());
-
+            BuiltinFunctionKind::PowerOfTwo => {
+                let exponent = vir_local!{ exponent: Int };
+                // exponent <= 0 ? 1 : 2 * pow2(exponent - 1)
+                let recursive_call = vir::Expr::func_app(
+                    fn_name.clone(),
+                    vec![vir::Expr::sub(exponent.clone().into(), 1.into())],
+                    vec![exponent.clone()],
+                    vir::Type::Int,
+                    vir::Position::default(),
+                );
                 vir::Function {
                     name: fn_name,
-                    formal_args: vec![
-                        self_var.clone(),
-                    ],
+                    formal_args: vec![exponent.clone()],
                     return_type: vir::Type::Int,
-                    pres: vec![
-                        vir::Expr::predicate_access_predicate(
-                            slice_ty_pred,
-                            vir::Expr::local(self_var),
-                            vir::PermAmount::Read,
-                        ),
-                    ],
+                    pres: vec![],
                     posts: vec![
-                        vir!{ [vir::Expr::from(vir_local!{ __result: Int })] >= [vir::Expr::from(0)] }
+                        vir!{ [vir::Expr::from(vir_local!{ __result: Int })] >= [vir::Expr::from(1)] }
                     ],
-                    body: None,
+                    body: Some(vir::Expr::ite(
+                        vir::Expr::le_cmp(exponent.into(), 0.into()),
+                        1.into(),
+                        vir::Expr::mul(2.into(), recursive_call),
+                    )),
                 }
             },
         }
     }
 
+    /// Encodes the `lookup_pure` function of a slice or vector, whose length
+    /// is given by the function named `len_name`.
+    fn encode_sequence_lookup_pure_def(
+        &self,
+        fn_name: String,
+        ty_pred: String,
+        len_name: String,
+        return_ty: vir::Type,
+    ) -> vir::Function {
+        let self_var = vir::LocalVar::new_typed_ref("self", ty_pred.clone());
+        let idx_var = vir_local!{ idx: Int };
+
+        let len_call = vir::Expr::func_app(
+            len_name,
+            vec![
+                vir::Expr::local(self_var.clone()),
+            ],
+            vec![
+                self_var.clone(),
+            ],
+            vir::Type::Int,
+            vir::Position::default(),
+        );
+
+        vir::Function {
+            name: fn_name,
+            formal_args: vec![
+                self_var.clone(),
+                idx_var.clone(),
+            ],
+            return_type: return_ty,
+            pres: vec![
+                // acc(self, read$())
+                vir::Expr::predicate_access_predicate(
+                    ty_pred,
+                    vir::Expr::local(self_var),
+                    vir::PermAmount::Read,
+                ),
+                // idx < len(self)
+                vir!{ [vir::Expr::local(idx_var)] < [len_call] },
+            ],
+            posts: vec![],
+            body: None,
+        }
+    }
+
+    /// Encodes the abstract length function of a slice or vector.
+    fn encode_sequence_len_def(&self, fn_name: String, ty_pred: String) -> vir::Function {
+        let self_var = vir::LocalVar::new_typed_ref("self", ty_pred.clone());
+
+        vir::Function {
+            name: fn_name,
+            formal_args: vec![
+                self_var.clone(),
+            ],
+            return_type: vir::Type::Int,
+            pres: vec![
+                vir::Expr::predicate_access_predicate(
+                    ty_pred,
+                    vir::Expr::local(self_var),
+                    vir::PermAmount::Read,
+                ),
+            ],
+            posts: vec![
+                vir!{ [vir::Expr::from(vir_local!{ __result: Int })] >= [vir::Expr::from(0)] }
+            ],
+            body: None,
+        }
+    }
+
+    /// The bitwise operations on unsigned integers are applied to the
+    /// operands modulo `2^width`, so that their postconditions hold for any
+    /// integer arguments.
+    fn encode_unsigned_bitwise_op_posts(
+        &self,
+        op: BitwiseOpKind,
+        width: u64,
+        left: vir::Expr,
+        right: vir::Expr,
+    ) -> Vec<vir::Expr> {
+        let result: vir::Expr = vir_local!{ __result: Int }.into();
+        let modulus = encode_power_of_two(width);
+        let left = vir::Expr::modulo(left, modulus.clone());
+        let right = vir::Expr::modulo(right, modulus.clone());
+        let mut posts = vec![
+            vir::Expr::le_cmp(0.into(), result.clone()),
+            vir::Expr::lt_cmp(result.clone(), modulus),
+        ];
+        match op {
+            BitwiseOpKind::And => {
+                posts.push(vir::Expr::le_cmp(result.clone(), left));
+                posts.push(vir::Expr::le_cmp(result, right));
+            }
+            BitwiseOpKind::Or => {
+                posts.push(vir::Expr::ge_cmp(result.clone(), left));
+                posts.push(vir::Expr::ge_cmp(result, right));
+            }
+            BitwiseOpKind::Xor => {
+                posts.push(vir::Expr::implies(
+                    vir::Expr::eq_cmp(left.clone(), right),
+                    vir::Expr::eq_cmp(result, 0.into()),
+                ));
+            }
+        }
+        posts
+    }
+
+    /// The bitwise operations on signed integers only have postconditions
+    /// for operands in the range of the type.
+    fn encode_signed_bitwise_op_posts(
+        &self,
+        op: BitwiseOpKind,
+        width: u64,
+        left: vir::Expr,
+        right: vir::Expr,
+    ) -> Vec<vir::Expr> {
+        let result: vir::Expr = vir_local!{ __result: Int }.into();
+        let bound = encode_power_of_two(width - 1);
+        let in_range = |expr: vir::Expr| vir::Expr::and(
+            vir::Expr::le_cmp(vir::Expr::minus(bound.clone()), expr.clone()),
+            vir::Expr::lt_cmp(expr, bound.clone()),
+        );
+        let operands_in_range = vir::Expr::and(in_range(left.clone()), in_range(right.clone()));
+        let mut facts = vec![in_range(result.clone())];
+        match op {
+            BitwiseOpKind::And => {
+                // masking with a non-negative value gives a non-negative value
+                for operand in &[left, right] {
+                    facts.push(vir::Expr::implies(
+                        vir::Expr::ge_cmp(operand.clone(), 0.into()),
+                        vir::Expr::and(
+                            vir::Expr::le_cmp(0.into(), result.clone()),
+                            vir::Expr::le_cmp(result.clone(), operand.clone()),
+                        ),
+                    ));
+                }
+            }
+            BitwiseOpKind::Or => {
+                facts.push(vir::Expr::implies(
+                    vir::Expr::and(
+                        vir::Expr::ge_cmp(left.clone(), 0.into()),
+                        vir::Expr::ge_cmp(right.clone(), 0.into()),
+                    ),
+                    vir::Expr::and(
+                        vir::Expr::ge_cmp(result.clone(), left),
+                        vir::Expr::ge_cmp(result, right),
+                    ),
+                ));
+            }
+            BitwiseOpKind::Xor => {
+                facts.push(vir::Expr::implies(
+                    vir::Expr::eq_cmp(left, right),
+                    vir::Expr::eq_cmp(result, 0.into()),
+                ));
+            }
+        }
+        facts.into_iter()
+            .map(|fact| vir::Expr::implies(operands_in_range.clone(), fact))
+            .collect()
+    }
+
     pub fn encode_builtin_domain(&self, kind: BuiltinDomainKind) -> vir::Domain {
         match kind {
             BuiltinDomainKind::Nat => self.encode_nat_builtin_domain(),
@@ -306,3 +508,18 @@ impl BuiltinEncoder {
         }
     }
 }
+
+/// Encode the constant `2^exponent`, for exponents up to 128.
+pub fn encode_power_of_two(exponent: u64) -> vir::Expr {
+    if exponent < 63 {
+        (1i64 << exponent).into()
+    } else if exponent < 128 {
+        (1u128 << exponent).into()
+    } else {
+        assert_eq!(exponent, 128);
+        vir::Expr::Const(
+            vir::Const::BigInt("340282366920938463463374607431768211456".to_string()),
+            vir::Position::default(),
+        )
+    }
+}
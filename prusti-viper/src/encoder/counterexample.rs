@@ -0,0 +1,362 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Translation of the counterexamples reported by the verifier back to Rust.
+//!
+//! When counterexamples are enabled, the procedure encoder stores the
+//! snapshot of each argument in a ghost variable right after inhaling the
+//! precondition. The model reported by Silicon maps these variables to their
+//! values, which are then printed as Rust values, e.g.
+//! `counterexample: x = 3, p = Point { x: 0, y: -1 }`. Values that cannot be
+//! reconstructed from the model are omitted.
+
+use rustc_hir::def::CtorKind;
+use rustc_middle::ty;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use crate::encoder::Encoder;
+
+/// A Rust variable whose value is reported in counterexamples.
+#[derive(Debug, Clone)]
+pub struct CounterexampleVar<'tcx> {
+    /// The name of the Viper ghost variable holding the snapshot.
+    pub viper_name: String,
+    /// The name of the Rust variable.
+    pub name: String,
+    pub ty: ty::Ty<'tcx>,
+}
+
+/// A value of the model reported by the verifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ModelValue {
+    /// A constant, e.g. `3`, `true` or an opaque `Snap$Point!val!0`.
+    Constant(String),
+    /// An application, e.g. `(- 1)` or `(cons$0$__$TY$__... 3 4)`.
+    Application(String, Vec<ModelValue>),
+    /// The interpretation of a function: the results for some arguments, and
+    /// the result for all other arguments.
+    Map(Vec<(Vec<ModelValue>, ModelValue)>, Option<Box<ModelValue>>),
+}
+
+/// A model reported by the verifier, mapping the names of Viper variables
+/// (and possibly functions) to their values.
+struct Model {
+    entries: HashMap<String, ModelValue>,
+}
+
+impl Model {
+    /// Parses the textual representation of a model, which consists of
+    /// entries of the form `name -> value`. Parsing stops at the first entry
+    /// that is not understood, keeping the entries parsed so far.
+    fn parse(text: &str) -> Self {
+        let mut parser = ModelParser {
+            tokens: tokenize(text),
+            pos: 0,
+        };
+        let mut entries = HashMap::new();
+        while let Some(name) = parser.next() {
+            if parser.next().as_deref() != Some("->") {
+                break;
+            }
+            match parser.parse_value() {
+                Some(value) => entries.insert(name, value),
+                None => break,
+            };
+        }
+        Model { entries }
+    }
+
+    /// Returns the result of applying the function with the given identifier
+    /// to `arg`, if the model contains the interpretation of the function.
+    fn apply(&self, function: &str, arg: &ModelValue) -> Option<&ModelValue> {
+        match self.entries.get(function)? {
+            ModelValue::Map(options, default) => options.iter()
+                .find(|(args, _)| args.len() == 1 && &args[0] == arg)
+                .map(|(_, result)| result)
+                .or_else(|| default.as_deref()),
+            _ => None,
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut current = String::new();
+    for c in text.chars() {
+        if c.is_whitespace() || "(){}".contains(c) {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+struct ModelParser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl ModelParser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|token| token.as_str())
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_value(&mut self) -> Option<ModelValue> {
+        match self.next()?.as_str() {
+            "(" => {
+                let name = self.next()?;
+                let mut args = vec![];
+                while self.peek()? != ")" {
+                    args.push(self.parse_value()?);
+                }
+                self.next();
+                Some(ModelValue::Application(name, args))
+            }
+            "{" => {
+                let mut options = vec![];
+                let mut default = None;
+                while self.peek()? != "}" {
+                    let mut args = vec![];
+                    while self.peek()? != "->" {
+                        args.push(self.parse_value()?);
+                    }
+                    self.next();
+                    let result = self.parse_value()?;
+                    if args == [ModelValue::Constant("else".to_string())] {
+                        default = Some(box result);
+                    } else {
+                        options.push((args, result));
+                    }
+                }
+                self.next();
+                Some(ModelValue::Map(options, default))
+            }
+            ")" | "}" | "->" => None,
+            token => Some(ModelValue::Constant(token.to_string())),
+        }
+    }
+}
+
+/// Translates the model of a counterexample to a list of Rust-level
+/// assignments `name = value`, one for each of the given variables that
+/// appears in the model. Returns [None] if no value could be reconstructed.
+pub fn translate_counterexample<'v, 'tcx: 'v>(
+    encoder: &Encoder<'v, 'tcx>,
+    vars: &[CounterexampleVar<'tcx>],
+    model: &str,
+) -> Option<String> {
+    let model = Model::parse(model);
+    let translator = ValueTranslator {
+        encoder,
+        model: &model,
+    };
+    let assignments: Vec<String> = vars.iter()
+        .filter_map(|var| {
+            let value = model.entries.get(&var.viper_name)?;
+            let value = translator.translate(var.ty, value)?;
+            Some(format!("{} = {}", var.name, value))
+        })
+        .collect();
+    if assignments.is_empty() {
+        None
+    } else {
+        Some(assignments.join(", "))
+    }
+}
+
+struct ValueTranslator<'p, 'v: 'p, 'tcx: 'v> {
+    encoder: &'p Encoder<'v, 'tcx>,
+    model: &'p Model,
+}
+
+impl<'p, 'v: 'p, 'tcx: 'v> ValueTranslator<'p, 'v, 'tcx> {
+    /// Prints the value of the snapshot of a variable of type [ty].
+    fn translate(&self, ty: ty::Ty<'tcx>, value: &ModelValue) -> Option<String> {
+        let tcx = self.encoder.env().tcx();
+        match ty.kind() {
+            _ if ty.is_box() => self.translate(ty.boxed_ty(), value),
+            ty::TyKind::Ref(_, sub_ty, _) => self.translate(sub_ty, value),
+            ty::TyKind::Bool => match value {
+                ModelValue::Constant(c) if c == "true" || c == "false" => Some(c.to_string()),
+                _ => None,
+            },
+            ty::TyKind::Int(_)
+            | ty::TyKind::Uint(_) => self.translate_int(value).map(|i| i.to_string()),
+            ty::TyKind::Char => self.translate_int(value)
+                .and_then(|i| std::char::from_u32(i.try_into().ok()?))
+                .map(|c| format!("{:?}", c)),
+            ty::TyKind::Tuple(substs) => {
+                if substs.is_empty() {
+                    return Some("()".to_string());
+                }
+                let field_names = (0..substs.len())
+                    .map(|field_num| format!("tuple_{}", field_num))
+                    .collect();
+                let (_, _, args) = self.translate_variant(ty, value, vec![field_names])?;
+                let fields = substs.iter()
+                    .zip(args.iter())
+                    .map(|(field_ty, arg)| self.translate(field_ty.expect_ty(), arg))
+                    .collect::<Option<Vec<_>>>()?;
+                if fields.len() == 1 {
+                    Some(format!("({},)", fields[0]))
+                } else {
+                    Some(format!("({})", fields.join(", ")))
+                }
+            }
+            ty::TyKind::Adt(adt_def, substs) if adt_def.is_struct() || adt_def.is_enum() => {
+                let adt_name = tcx.item_name(adt_def.did).to_ident_string();
+                if adt_def.variants.is_empty() {
+                    return None;
+                }
+                // Data-less structs are encoded as the unit snapshot.
+                if adt_def.is_struct() && adt_def.non_enum_variant().fields.is_empty() {
+                    return Some(adt_name);
+                }
+                let field_names = adt_def.variants.iter()
+                    .map(|variant| variant.fields.iter()
+                        .map(|field| format!("f${}", field.ident))
+                        .collect())
+                    .collect();
+                let (variant_idx, variant_name, args) =
+                    self.translate_variant(ty, value, field_names)?;
+                let variant = adt_def.variants.iter().nth(variant_idx)?;
+                let name = match variant_name {
+                    Some(variant_name) => format!("{}::{}", adt_name, variant_name),
+                    None => adt_name,
+                };
+                let fields = variant.fields.iter()
+                    .zip(args.iter())
+                    .map(|(field, arg)| {
+                        self.translate(field.ty(tcx, substs), arg)
+                            .map(|value| (field.ident.to_string(), value))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(match variant.ctor_kind {
+                    CtorKind::Const => name,
+                    CtorKind::Fn => format!(
+                        "{}({})",
+                        name,
+                        fields.into_iter().map(|(_, value)| value).collect::<Vec<_>>().join(", "),
+                    ),
+                    CtorKind::Fictive => format!(
+                        "{} {{ {} }}",
+                        name,
+                        fields.into_iter()
+                            .map(|(field, value)| format!("{}: {}", field, value))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn translate_int(&self, value: &ModelValue) -> Option<i128> {
+        match value {
+            ModelValue::Constant(c) => c.parse().ok(),
+            ModelValue::Application(op, args) if op == "-" && args.len() == 1 => {
+                self.translate_int(&args[0]).map(|i| -i)
+            }
+            _ => None,
+        }
+    }
+
+    /// Finds the variant of the snapshot [value] of type [ty], its name (for
+    /// enums), and the values of its fields. [field_names] lists the names of the fields of each
+    /// variant, as used in the snapshot encoding.
+    ///
+    /// The value is either an application of a snapshot constructor, or an
+    /// opaque value whose fields are looked up in the interpretations of the
+    /// field access functions. The latter is only possible for types with a
+    /// single variant, since the discriminant is not reconstructed.
+    fn translate_variant(
+        &self,
+        ty: ty::Ty<'tcx>,
+        value: &ModelValue,
+        field_names: Vec<Vec<String>>,
+    ) -> Option<(usize, Option<String>, Vec<ModelValue>)> {
+        let variants = self.encoder.get_snapshot_variant_functions(ty).ok()??;
+        let (name, args) = match value {
+            ModelValue::Application(name, args) => (name, &args[..]),
+            ModelValue::Constant(name) => (name, &[][..]),
+            ModelValue::Map(..) => return None,
+        };
+        if let Some(variant_idx) = variants.iter().position(|v| &v.constructor == name) {
+            return if args.len() == field_names[variant_idx].len() {
+                Some((variant_idx, variants[variant_idx].name.clone(), args.to_vec()))
+            } else {
+                None
+            };
+        }
+        if variants.len() == 1 {
+            let fields = field_names[0].iter()
+                .map(|field| {
+                    let function = variants[0].fields.get(field)?;
+                    self.model.apply(function, value).cloned()
+                })
+                .collect::<Option<Vec<_>>>()?;
+            return Some((0, variants[0].name.clone(), fields));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constant(value: &str) -> ModelValue {
+        ModelValue::Constant(value.to_string())
+    }
+
+    #[test]
+    fn test_parse_constants_and_applications() {
+        let model = Model::parse("cex$0 -> 3\ncex$1 -> (- 1)\ncex$2 -> (cons$0$ 4 true)");
+        assert_eq!(model.entries.len(), 3);
+        assert_eq!(model.entries["cex$0"], constant("3"));
+        assert_eq!(
+            model.entries["cex$1"],
+            ModelValue::Application("-".to_string(), vec![constant("1")]),
+        );
+        assert_eq!(
+            model.entries["cex$2"],
+            ModelValue::Application("cons$0$".to_string(), vec![constant("4"), constant("true")]),
+        );
+    }
+
+    #[test]
+    fn test_parse_function_interpretations() {
+        let model = Model::parse(
+            "cex$0 -> Snap$Point!val!0\nf$x -> {\n    Snap$Point!val!0 -> 5\n    else -> 7\n}"
+        );
+        assert_eq!(model.apply("f$x", &constant("Snap$Point!val!0")), Some(&constant("5")));
+        assert_eq!(model.apply("f$x", &constant("Snap$Point!val!1")), Some(&constant("7")));
+        assert_eq!(model.apply("f$y", &constant("Snap$Point!val!0")), None);
+    }
+
+    #[test]
+    fn test_parse_stops_at_malformed_entries() {
+        let model = Model::parse("cex$0 -> 3\ncex$1 -> (cons$0$ 4");
+        assert_eq!(model.entries.len(), 1);
+        assert_eq!(model.entries["cex$0"], constant("3"));
+    }
+}
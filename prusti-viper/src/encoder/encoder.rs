@@ -5,12 +5,16 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use ::log::{info, debug, trace};
-use crate::encoder::borrows::{compute_procedure_contract, ProcedureContract, ProcedureContractMirDef};
+use crate::encoder::borrows::{
+    compute_framed_paths, compute_procedure_contract, ProcedureContract, ProcedureContractMirDef,
+};
 use crate::encoder::builtin_encoder::BuiltinEncoder;
 use crate::encoder::builtin_encoder::BuiltinFunctionKind;
 use crate::encoder::builtin_encoder::BuiltinMethodKind;
 use crate::encoder::builtin_encoder::BuiltinDomainKind;
-use crate::encoder::errors::{ErrorCtxt, ErrorManager, SpannedEncodingError, EncodingError, WithSpan};
+use crate::encoder::errors::{
+    ErrorCtxt, ErrorManager, SpannedEncodingError, EncodingError, EncodingErrorKind, WithSpan,
+};
 use crate::encoder::foldunfold;
 use crate::encoder::places;
 use crate::encoder::procedure_encoder::ProcedureEncoder;
@@ -28,6 +32,7 @@ use prusti_common::config;
 use prusti_common::report::log;
 use prusti_interface::data::ProcedureDefId;
 use prusti_interface::environment::Environment;
+use prusti_interface::specs::checker::collect_expressions;
 use prusti_interface::specs::typed;
 use prusti_interface::specs::typed::SpecificationId;
 use prusti_interface::utils::{has_spec_only_attr, read_prusti_attrs};
@@ -39,26 +44,30 @@ use rustc_hir::def_id::DefId;
 use rustc_middle::mir;
 // use rustc::mir::interpret::GlobalId;
 use rustc_middle::ty;
-use std::cell::{RefCell, RefMut};
+use std::cell::{Ref, RefCell, RefMut};
 use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use std::mem;
+use std::time::{Duration, Instant};
 // use viper;
 use crate::encoder::stub_procedure_encoder::StubProcedureEncoder;
 use std::ops::AddAssign;
 use std::convert::TryInto;
+use viper::VerificationBackend;
 use std::borrow::Borrow;
 use crate::encoder::specs_closures_collector::SpecsClosuresCollector;
-use rustc_span::MultiSpan;
+use rustc_span::{MultiSpan, Span};
 use crate::encoder::name_interner::NameInterner;
 use crate::encoder::utils::transpose;
 use crate::encoder::errors::EncodingResult;
 use crate::encoder::errors::SpannedEncodingResult;
 use crate::encoder::mirror_function_encoder;
 use crate::encoder::mirror_function_encoder::MirrorEncoder;
-use crate::encoder::snapshot::encoder::SnapshotEncoder;
+use crate::encoder::snapshot::encoder::{SnapshotEncoder, SnapshotVariantFunctions};
+use crate::encoder::counterexample::{self, CounterexampleVar};
 use crate::encoder::purifier;
-use crate::encoder::array_encoder::{ArrayTypesEncoder, EncodedArrayTypes, EncodedSliceTypes};
+use crate::encoder::viper_dump;
+use crate::encoder::array_encoder::{ArrayLen, ArrayTypesEncoder, EncodedArrayTypes, EncodedSliceTypes};
 
 #[must_use]
 pub struct CleanupTyMapStack<'a, 'tcx> {
@@ -71,6 +80,16 @@ impl<'a, 'tcx> Drop for CleanupTyMapStack<'a, 'tcx> {
     }
 }
 
+pub struct CleanupConstMapStack<'a, 'tcx> {
+    constmap_stack: &'a std::cell::RefCell<Vec<HashMap<ty::ParamConst, &'tcx ty::Const<'tcx>>>>,
+}
+
+impl<'a, 'tcx> Drop for CleanupConstMapStack<'a, 'tcx> {
+    fn drop(&mut self) {
+        self.constmap_stack.borrow_mut().pop();
+    }
+}
+
 pub struct Encoder<'v, 'tcx: 'v> {
     env: &'v Environment<'tcx>,
     def_spec: &'v typed::DefSpecificationMap<'tcx>,
@@ -82,9 +101,20 @@ pub struct Encoder<'v, 'tcx: 'v> {
     builtin_methods: RefCell<HashMap<BuiltinMethodKind, vir::BodylessMethod>>,
     builtin_functions: RefCell<HashMap<BuiltinFunctionKind, vir::Function>>,
     procedures: RefCell<HashMap<ProcedureDefId, vir::CfgMethod>>,
+    /// Methods that check that the specification of a trait method implementation refines
+    /// the specification of the trait method
+    refinement_checks: RefCell<HashMap<ProcedureDefId, Option<vir::CfgMethod>>>,
+    /// Methods that check whether the precondition of a procedure is satisfiable, encoded
+    /// instead of the procedure when only checking the specifications
+    precondition_checks: RefCell<HashMap<ProcedureDefId, vir::CfgMethod>>,
     pure_function_bodies: RefCell<HashMap<(ProcedureDefId, String), vir::Expr>>,
     pure_functions: RefCell<HashMap<(ProcedureDefId, String), vir::Function>>,
     failed_pure_functions: RefCell<HashSet<(ProcedureDefId, String)>>,
+    /// The spans of the bodies of the quantifiers for which no trigger could be
+    /// inferred, so that each of them is reported only once.
+    untriggered_quantifiers: RefCell<HashSet<Span>>,
+    /// The loops without invariant that have already been reported.
+    loops_without_invariant: RefCell<HashSet<Span>>,
     /// Stub pure functions. Generated when an impure Rust function is invoked
     /// where a pure function is required.
     stub_pure_functions: RefCell<HashMap<(ProcedureDefId, String), vir::Function>>,
@@ -96,10 +126,13 @@ pub struct Encoder<'v, 'tcx: 'v> {
     type_predicates: RefCell<HashMap<String, vir::Predicate>>,
     type_invariants: RefCell<HashMap<String, vir::Function>>,
     type_tags: RefCell<HashMap<String, vir::Function>>,
+    /// The hypotheses that trait bounds depending on type parameters hold,
+    /// used to encode type-conditional specifications.
+    type_cond_hypotheses: RefCell<HashMap<ty::TraitRef<'tcx>, vir::Function>>,
     type_discriminant_funcs: RefCell<HashMap<String, vir::Function>>,
     type_cast_functions: RefCell<HashMap<(ty::Ty<'tcx>, ty::Ty<'tcx>), vir::Function>>,
     fields: RefCell<HashMap<String, vir::Field>>,
-    snapshot_encoder: RefCell<SnapshotEncoder>,
+    snapshot_encoder: RefCell<SnapshotEncoder<'tcx>>,
     mirror_encoder: RefCell<MirrorEncoder>,
     array_types_encoder: RefCell<ArrayTypesEncoder<'tcx>>,
     closures_collector: RefCell<SpecsClosuresCollector<'tcx>>,
@@ -107,10 +140,32 @@ pub struct Encoder<'v, 'tcx: 'v> {
     vir_program_before_foldunfold_writer: RefCell<Box<dyn Write>>,
     vir_program_before_viper_writer: RefCell<Box<dyn Write>>,
     pub typaram_repl: RefCell<Vec<HashMap<ty::Ty<'tcx>, ty::Ty<'tcx>>>>,
+    /// The substitutions of const generic parameters, like `typaram_repl` for types.
+    const_param_repl: RefCell<Vec<HashMap<ty::ParamConst, &'tcx ty::Const<'tcx>>>>,
     encoding_errors_counter: RefCell<usize>,
     name_interner: RefCell<NameInterner>,
+    /// Ghost variables whose values are reported in counterexamples.
+    counterexample_vars: RefCell<Vec<CounterexampleVar<'tcx>>>,
     /// The procedure that is currently being encoded.
-    pub current_proc: RefCell<Option<ProcedureDefId>>
+    pub current_proc: RefCell<Option<ProcedureDefId>>,
+    /// The encoding errors and encoding time of each procedure, used for the
+    /// JSON report (see `config::json_report`).
+    procedure_encoding_stats: RefCell<HashMap<ProcedureDefId, ProcedureEncodingStats>>,
+    /// The features of Viper supported by the backend selected with
+    /// `config::viper_backend`.
+    backend_capabilities: vir::BackendCapabilities,
+}
+
+/// The outcome of encoding a procedure, as reported in the JSON report.
+#[derive(Default)]
+pub struct ProcedureEncodingStats {
+    /// Wall-clock time spent encoding the procedure.
+    pub duration: Duration,
+    /// The encoding errors (not the warnings) reported for the procedure.
+    pub errors: Vec<PrustiError>,
+    /// Whether the procedure uses an unsupported feature, even if this is
+    /// reported as a warning (see `config::skip_unsupported_features`).
+    pub unsupported: bool,
 }
 
 impl<'v, 'tcx> Encoder<'v, 'tcx> {
@@ -145,9 +200,13 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             builtin_methods: RefCell::new(HashMap::new()),
             builtin_functions: RefCell::new(HashMap::new()),
             procedures: RefCell::new(HashMap::new()),
+            refinement_checks: RefCell::new(HashMap::new()),
+            precondition_checks: RefCell::new(HashMap::new()),
             pure_function_bodies: RefCell::new(HashMap::new()),
             pure_functions: RefCell::new(HashMap::new()),
             failed_pure_functions: RefCell::new(HashSet::new()),
+            untriggered_quantifiers: RefCell::new(HashSet::new()),
+            loops_without_invariant: RefCell::new(HashSet::new()),
             stub_pure_functions: RefCell::new(HashMap::new()),
             spec_functions: RefCell::new(HashMap::new()),
             type_predicate_names: RefCell::new(HashMap::new()),
@@ -157,6 +216,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             type_predicates: RefCell::new(HashMap::new()),
             type_invariants: RefCell::new(HashMap::new()),
             type_tags: RefCell::new(HashMap::new()),
+            type_cond_hypotheses: RefCell::new(HashMap::new()),
             type_discriminant_funcs: RefCell::new(HashMap::new()),
             type_cast_functions: RefCell::new(HashMap::new()),
             fields: RefCell::new(HashMap::new()),
@@ -165,12 +225,18 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             vir_program_before_foldunfold_writer,
             vir_program_before_viper_writer,
             typaram_repl: RefCell::new(Vec::new()),
+            const_param_repl: RefCell::new(Vec::new()),
             snapshot_encoder: RefCell::new(SnapshotEncoder::new()),
             mirror_encoder: RefCell::new(MirrorEncoder::new()),
             array_types_encoder: RefCell::new(ArrayTypesEncoder::new()),
             encoding_errors_counter: RefCell::new(0),
             name_interner: RefCell::new(NameInterner::new()),
+            counterexample_vars: RefCell::new(vec![]),
             current_proc: RefCell::new(None),
+            procedure_encoding_stats: RefCell::new(HashMap::new()),
+            backend_capabilities: vir::BackendCapabilities::of(
+                VerificationBackend::from_str(&config::viper_backend())
+            ),
         }
     }
 
@@ -183,6 +249,15 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         CleanupTyMapStack { tymap_stack: &self.typaram_repl }
     }
 
+    pub fn push_temp_constmap<'a>(
+        &'a self,
+        constmap: HashMap<ty::ParamConst, &'tcx ty::Const<'tcx>>,
+    ) -> CleanupConstMapStack<'a, 'tcx> {
+        self.const_param_repl.borrow_mut().push(constmap);
+
+        CleanupConstMapStack { constmap_stack: &self.const_param_repl }
+    }
+
     pub fn log_vir_program_before_foldunfold<S: ToString>(&self, program: S) {
         let mut writer = self.vir_program_before_foldunfold_writer.borrow_mut();
         writer
@@ -229,6 +304,44 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         self.error_manager.borrow_mut()
     }
 
+    pub fn backend_capabilities(&self) -> &vir::BackendCapabilities {
+        &self.backend_capabilities
+    }
+
+    /// Registers a ghost variable holding the snapshot of the Rust variable
+    /// `name`, to be reported in counterexamples. Returns the name of the
+    /// Viper variable.
+    pub fn register_counterexample_var(&self, name: String, ty: ty::Ty<'tcx>) -> String {
+        let mut counterexample_vars = self.counterexample_vars.borrow_mut();
+        let viper_name = format!("cex${}", counterexample_vars.len());
+        counterexample_vars.push(CounterexampleVar {
+            viper_name: viper_name.clone(),
+            name,
+            ty,
+        });
+        viper_name
+    }
+
+    /// Translates the model of a counterexample reported by the verifier to
+    /// the values of the Rust variables.
+    pub fn translate_counterexample(&self, model: &str) -> Option<String> {
+        counterexample::translate_counterexample(
+            self,
+            &self.counterexample_vars.borrow(),
+            model,
+        )
+    }
+
+    /// The names of the Viper functions that encode Rust pure functions.
+    pub fn get_pure_function_names(&self) -> HashSet<String> {
+        self.pure_functions.borrow().values()
+            .map(|function| function.name.clone())
+            .collect()
+    }
+
+    /// Assembles the encoded Viper program. All items are sorted by their
+    /// identifier, so that the same input always results in the same
+    /// program, regardless of the order in which the items were encoded.
     pub fn get_viper_program(&self) -> vir::Program {
         vir::Program {
             domains: self.get_used_viper_domains(),
@@ -242,17 +355,103 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
 
     pub(in crate::encoder) fn register_encoding_error(&self, encoding_error: SpannedEncodingError) {
         debug!("Encoding error: {:?}", encoding_error);
+        let unsupported = matches!(encoding_error.kind(), EncodingErrorKind::Unsupported(_));
         let prusti_error: PrustiError = encoding_error.into();
         if prusti_error.is_error() {
             self.encoding_errors_counter.borrow_mut().add_assign(1);
         }
+        if let Some(proc_def_id) = *self.current_proc.borrow() {
+            let mut stats = self.procedure_encoding_stats.borrow_mut();
+            let proc_stats = stats.entry(proc_def_id).or_default();
+            if prusti_error.is_error() {
+                proc_stats.errors.push(prusti_error.clone());
+            }
+            proc_stats.unsupported |= unsupported;
+        }
         prusti_error.emit(self.env);
     }
 
+    /// Records that the Viper items `names` were generated to encode the
+    /// snapshot of `ty`, on behalf of the procedure being encoded. Verification
+    /// errors in these items are reported as failures to encode the type.
+    pub(in crate::encoder) fn register_generated_snapshot_items(
+        &self,
+        names: Vec<String>,
+        ty: ty::Ty<'tcx>,
+    ) {
+        let span = match *self.current_proc.borrow() {
+            Some(proc_def_id) => MultiSpan::from_span(self.env.get_item_span(proc_def_id)),
+            None => MultiSpan::new(),
+        };
+        let ty_name = format!("{}", ty);
+        let mut error_manager = self.error_manager();
+        for name in names {
+            error_manager.register_generated_item(name, ty_name.clone(), span.clone());
+        }
+    }
+
+    /// Warns that no trigger could be inferred for the quantifier whose body is at
+    /// `span`. The warning is reported once, even if the specification that
+    /// contains the quantifier is encoded several times.
+    pub(in crate::encoder) fn warn_untriggered_quantifier(&self, span: Span) {
+        if self.untriggered_quantifiers.borrow_mut().insert(span) {
+            PrustiError::warning(
+                "no trigger could be inferred for this quantifier",
+                MultiSpan::from_span(span),
+            ).set_help(
+                "specify the triggers with `triggers=[(..)]`; otherwise the verifier chooses \
+                them on its own, and might not be able to use the quantifier"
+            ).emit(self.env);
+        }
+    }
+
+    /// Warns that the loop at `span` has no invariant, although it modifies the
+    /// user variables `var_names`. At most `MAX_REPORTED_HAVOCKED_VARS` variables
+    /// are listed. The warning is reported once per loop.
+    pub(in crate::encoder) fn warn_loop_without_invariant(&self, span: Span, var_names: &[String]) {
+        const MAX_REPORTED_HAVOCKED_VARS: usize = 5;
+        if !self.loops_without_invariant.borrow_mut().insert(span) {
+            return;
+        }
+        let mut vars = var_names.iter()
+            .take(MAX_REPORTED_HAVOCKED_VARS)
+            .map(|name| format!("`{}`", name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if var_names.len() > MAX_REPORTED_HAVOCKED_VARS {
+            vars.push_str(&format!(" and {} more", var_names.len() - MAX_REPORTED_HAVOCKED_VARS));
+        }
+        PrustiError::warning(
+            format!(
+                "this loop has no invariant, so nothing is known about the values of the \
+                variables it modifies: {}",
+                vars
+            ),
+            MultiSpan::from_span(span),
+        ).set_help(
+            "add a `body_invariant!(..)` describing the values of these variables, or suppress \
+            this warning with `#[allow_spec_warnings(missing_loop_invariant)]`"
+        ).emit(self.env);
+    }
+
     pub fn count_encoding_errors(&self) -> usize {
         *self.encoding_errors_counter.borrow()
     }
 
+    /// Returns the encoding errors and encoding time of the procedure, if it
+    /// has been encoded.
+    pub fn get_procedure_encoding_stats(
+        &self,
+        def_id: ProcedureDefId,
+    ) -> Option<Ref<ProcedureEncodingStats>> {
+        let stats = self.procedure_encoding_stats.borrow();
+        if stats.contains_key(&def_id) {
+            Some(Ref::map(stats, |stats| &stats[&def_id]))
+        } else {
+            None
+        }
+    }
+
     pub fn get_used_viper_domains(&self) -> Vec<vir::Domain> {
         let mut domains = vec![];
         domains.extend(self.snapshot_encoder.borrow().get_viper_domains());
@@ -264,6 +463,10 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             domains.push(builtin_encoder.encode_builtin_domain(BuiltinDomainKind::Primitive));
         }
 
+        for domain in domains.iter_mut() {
+            domain.functions.sort_by_key(|f| f.get_identifier());
+            domain.axioms.sort_by(|a, b| a.name.cmp(&b.name));
+        }
         domains.sort_by_key(|d| d.get_identifier());
         domains
     }
@@ -291,6 +494,9 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         for function in self.type_tags.borrow().values() {
             functions.push(function.clone());
         }
+        for function in self.type_cond_hypotheses.borrow().values() {
+            functions.push(function.clone());
+        }
         for function in self.type_discriminant_funcs.borrow().values() {
             functions.push(function.clone());
         }
@@ -326,11 +532,37 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
     }
 
     fn get_used_builtin_methods(&self) -> Vec<vir::BodylessMethod> {
-        self.builtin_methods.borrow().values().cloned().collect()
+        let mut methods: Vec<_> = self.builtin_methods.borrow().values().cloned().collect();
+        methods.sort_by(|a, b| a.name.cmp(&b.name));
+        methods
     }
 
     fn get_used_viper_methods(&self) -> Vec<vir::CfgMethod> {
-        self.procedures.borrow().values().cloned().collect()
+        let mut methods: Vec<_> = self.procedures.borrow().values().cloned()
+            .chain(self.refinement_checks.borrow().values().flatten().cloned())
+            .chain(self.precondition_checks.borrow().values().cloned())
+            .collect();
+        methods.sort_by_key(|m| m.name());
+        methods
+    }
+
+    /// Returns the names of the Viper methods encoded for the procedure
+    /// `def_id`: the method verifying its body (or the check of its
+    /// precondition) and the check that its specification refines the one of
+    /// the implemented trait method.
+    pub fn get_procedure_method_names(&self, def_id: ProcedureDefId) -> Vec<String> {
+        self.procedures.borrow().get(&def_id)
+            .into_iter()
+            .chain(self.precondition_checks.borrow().get(&def_id))
+            .chain(self.refinement_checks.borrow().get(&def_id).into_iter().flatten())
+            .map(|method| method.name())
+            .collect()
+    }
+
+    /// Returns the name of the Viper method that checks whether the precondition of the
+    /// procedure `def_id` is satisfiable, if it has been encoded.
+    pub fn get_precondition_check_name(&self, def_id: ProcedureDefId) -> Option<String> {
+        self.precondition_checks.borrow().get(&def_id).map(|method| method.name())
     }
 
     pub fn get_single_closure_instantiation(
@@ -353,8 +585,87 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
     /// Get the loop invariant attached to a function with a
     /// `prusti::loop_body_invariant_spec` attribute.
     pub fn get_loop_specs(&self, def_id: DefId) -> Option<typed::LoopSpecification<'tcx>> {
+        match self.def_spec.get(&def_id)? {
+            typed::SpecificationSet::Loop(spec) => Some(spec.clone()),
+            _ => None,
+        }
+    }
+
+    /// Get the invariants attached to a struct or enum with `#[invariant(..)]` or
+    /// `#[history_invariant(..)]`.
+    pub fn get_struct_specs(&self, def_id: DefId) -> Vec<typed::Specification<'tcx>> {
+        match self.def_spec.get(&def_id) {
+            Some(typed::SpecificationSet::Struct(invariants)) => invariants.clone(),
+            _ => vec![],
+        }
+    }
+
+    /// Get the history invariants that a method has to preserve, if the method takes
+    /// `&mut self` and its `Self` type has a `#[history_invariant(..)]`.
+    pub fn get_history_invariants(&self, proc_def_id: DefId) -> Vec<typed::Assertion<'tcx>> {
+        let tcx = self.env().tcx();
+        let impl_def_id = match tcx.impl_of_method(proc_def_id) {
+            Some(impl_def_id) => impl_def_id,
+            None => return vec![],
+        };
+        if !tcx.associated_item(proc_def_id).fn_has_self_parameter {
+            return vec![];
+        }
+        let self_ty = tcx.type_of(impl_def_id);
+        let receiver_ty = tcx.fn_sig(proc_def_id).skip_binder().inputs()[0];
+        match (self_ty.kind(), receiver_ty.kind()) {
+            (
+                ty::TyKind::Adt(adt_def, _),
+                ty::TyKind::Ref(_, inner_ty, mir::Mutability::Mut),
+            ) if *inner_ty == self_ty => {
+                self.get_struct_specs(adt_def.did)
+                    .into_iter()
+                    .filter(|spec| spec.typ == typed::SpecType::HistoryInvariant)
+                    .map(|spec| spec.assertion)
+                    .collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Get the specification of a `prusti_assert!(..)` or `prusti_assume!(..)`
+    /// ghost statement, which is attached to a closure.
+    pub fn get_ghost_statement_specs(&self, def_id: DefId) -> Option<typed::Specification<'tcx>> {
         let spec = self.def_spec.get(&def_id)?;
-        Some(spec.expect_loop().clone())
+        Some(spec.expect_ghost().clone())
+    }
+
+    /// The `result` of a postcondition of a function returning `impl Trait`
+    /// has a type parameter as type in the specification items. Map these
+    /// type parameters to the hidden concrete type returned by the function,
+    /// so that the postconditions can use the pure methods of that type.
+    pub fn get_opaque_result_tymap(
+        &self,
+        proc_def_id: ProcedureDefId,
+        postconditions: &[typed::Assertion<'tcx>],
+    ) -> HashMap<ty::Ty<'tcx>, ty::Ty<'tcx>> {
+        use rustc_middle::ty::fold::TypeFoldable;
+        let tcx = self.env().tcx();
+        let mut tymap = HashMap::new();
+        let return_ty = tcx.fn_sig(proc_def_id).skip_binder().output();
+        if !return_ty.has_opaque_types() {
+            return tymap;
+        }
+        let revealed_return_ty = self.env().reveal_opaque_types(return_ty);
+        let mut expressions = vec![];
+        for assertion in postconditions {
+            collect_expressions(assertion, &mut expressions);
+        }
+        for expression in expressions {
+            let spec_item_def_id = tcx.closure_base_def_id(expression.expr);
+            let spec_item_sig = tcx.fn_sig(spec_item_def_id);
+            if let Some(&result_ty) = spec_item_sig.skip_binder().inputs().last() {
+                if let ty::TyKind::Param(_) = result_ty.kind() {
+                    tymap.insert(result_ty, revealed_return_ty);
+                }
+            }
+        }
+        tymap
     }
 
     /// Get the specifications attached to the `def_id` function.
@@ -374,11 +685,42 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
     fn get_procedure_contract(&self, proc_def_id: ProcedureDefId)
         -> EncodingResult<ProcedureContractMirDef<'tcx>>
     {
-        let spec = typed::SpecificationSet::Procedure(
-            self.get_procedure_specs(proc_def_id)
-                .unwrap_or_else(|| typed::ProcedureSpecification::empty())
-        );
-        compute_procedure_contract(proc_def_id, self.env().tcx(), spec, None)
+        let mut spec = self.get_procedure_specs(proc_def_id)
+            .unwrap_or_else(|| typed::ProcedureSpecification::empty());
+        // A method implementing a trait method inherits the parts of the specification of the
+        // trait method that it does not refine, like at its call sites. This way, the body of
+        // an implementation without specification is checked against the inherited contract.
+        if let Some(trait_method_def_id) = self.env().find_trait_method(proc_def_id) {
+            if let Some(trait_spec) = self.get_procedure_specs(trait_method_def_id) {
+                spec = trait_spec.refine(&spec);
+            }
+        }
+        let spec = typed::SpecificationSet::Procedure(spec);
+        let mut contract = compute_procedure_contract(proc_def_id, self.env().tcx(), spec, None)?;
+        self.compute_framed_paths(proc_def_id, &mut contract);
+        Ok(contract)
+    }
+
+    /// Computes the places that keep their value while the result of the
+    /// procedure is borrowed. This is only done for procedures whose body is
+    /// verified, and that are not called through a trait.
+    fn compute_framed_paths(
+        &self,
+        proc_def_id: ProcedureDefId,
+        contract: &mut ProcedureContractMirDef<'tcx>,
+    ) {
+        let tcx = self.env().tcx();
+        if !proc_def_id.is_local()
+            || !tcx.is_mir_available(proc_def_id)
+            || tcx.is_closure(proc_def_id)
+            || tcx.trait_of_item(proc_def_id).is_some()
+            || self.is_trusted(proc_def_id)
+            || self.is_pure(proc_def_id)
+        {
+            return;
+        }
+        let mir = self.env().local_mir(proc_def_id.expect_local());
+        compute_framed_paths(tcx, &mir, contract);
     }
 
     /// Extract scalar value, invoking const evaluation if necessary.
@@ -393,9 +735,27 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             ty::ConstKind::Unevaluated(ct) => {
                 let tcx = self.env().tcx();
                 let param_env = tcx.param_env(ct.def.did);
-                tcx.const_eval_resolve(param_env, *ct, None)
-                    .ok()
-                    .and_then(|const_value| const_value.try_to_scalar())
+                match tcx.const_eval_resolve(param_env, *ct, None) {
+                    Ok(const_value) => const_value.try_to_scalar(),
+                    Err(mir::interpret::ErrorHandled::TooGeneric) => {
+                        return Err(EncodingError::unsupported(format!(
+                            "the value of the constant `{}` depends on generic parameters \
+                            and cannot be evaluated",
+                            tcx.def_path_str(ct.def.did),
+                        )));
+                    }
+                    Err(_) => None,
+                }
+            }
+            ty::ConstKind::Param(param) => {
+                let resolved = self.resolve_const_param(*param);
+                if let ty::ConstKind::Param(_) = resolved {
+                    return Err(EncodingError::unsupported(format!(
+                        "the value of the generic constant parameter `{}` cannot be evaluated",
+                        param.name,
+                    )));
+                }
+                return self.const_eval_intlike(&resolved);
             }
             _ => unimplemented!("{:?}", value),
         };
@@ -409,6 +769,46 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         }
     }
 
+    /// Extract the content of a string literal of type `&str`, or of a
+    /// reference to it (e.g. a promoted `&&str`), invoking const evaluation if
+    /// necessary.
+    pub fn const_eval_str(
+        &self,
+        ty: ty::Ty<'tcx>,
+        value: &ty::ConstKind<'tcx>,
+    ) -> EncodingResult<String> {
+        let tcx = self.env().tcx();
+        let opt_const_value = match value {
+            ty::ConstKind::Value(const_value) => Some(*const_value),
+            ty::ConstKind::Unevaluated(ct) => {
+                let param_env = tcx.param_env(ct.def.did);
+                tcx.const_eval_resolve(param_env, *ct, None).ok()
+            }
+            _ => None,
+        };
+
+        let opt_literal = match (ty.kind(), opt_const_value) {
+            (
+                ty::TyKind::Ref(_, inner_ty, _),
+                Some(mir::interpret::ConstValue::Slice { data, start, end }),
+            ) if inner_ty.is_str() => {
+                let bytes = data.inspect_with_uninit_and_ptr_outside_interpreter(start..end);
+                std::str::from_utf8(bytes).ok().map(|literal| literal.to_string())
+            }
+            (ty::TyKind::Ref(_, inner_ty, _), Some(const_value)) if !inner_ty.is_str() => {
+                let deref_const = tcx.deref_const(
+                    ty::ParamEnv::reveal_all().and(ty::Const::from_value(tcx, const_value, ty))
+                );
+                return self.const_eval_str(deref_const.ty, &deref_const.val);
+            }
+            _ => None,
+        };
+
+        opt_literal.ok_or_else(|| EncodingError::unsupported(
+            format!("unsupported string constant value: {:?}", value)
+        ))
+    }
+
     pub fn get_procedure_contract_for_def(
         &self,
         proc_def_id: ProcedureDefId,
@@ -474,12 +874,13 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         // merge specifications
         let final_spec = trait_spec.refine(&impl_spec);
 
-        let contract = compute_procedure_contract(
+        let mut contract = compute_procedure_contract(
             proc_def_id,
             self.env().tcx(),
             typed::SpecificationSet::Procedure(final_spec),
             Some(&tymap[0])
         )?;
+        self.compute_framed_paths(proc_def_id, &mut contract);
         Ok(contract.to_call_site_contract(args, target))
     }
 
@@ -487,10 +888,13 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
     /// a primitive types.
     /// For composed data structures, the base expression is returned.
     pub fn encode_value_expr(&self, base: vir::Expr, ty: ty::Ty<'tcx>) -> EncodingResult<vir::Expr> {
+        let ty = self.env().reveal_opaque_types(ty);
         match ty.kind() {
             ty::TyKind::Adt(_, _)
-            | ty::TyKind::Tuple(_) => {
-                Ok(base) // don't use a field for tuples and ADTs
+            | ty::TyKind::Tuple(_)
+            | ty::TyKind::Array(..)
+            | ty::TyKind::Slice(..) => {
+                Ok(base) // don't use a field for tuples, ADTs and sequences
             }
             _ => {
                 let value_field = self.encode_value_field(ty)?;
@@ -737,6 +1141,15 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             if config::enable_purification_optimization() {
                 purifier::purify_method(&self, &mut method);
             }
+            if config::enable_local_snapshot_purification() {
+                // The unpurified method is kept, the failure is reported
+                if let Err(error) = purifier::purify_local_snapshots(&self, &mut method) {
+                    self.register_encoding_error(error.with_span(procedure.get_span()));
+                }
+            }
+            if config::dump_viper_per_fn() {
+                viper_dump::dump_method(self, &method);
+            }
 
             self.procedures.borrow_mut().insert(def_id, method);
         }
@@ -756,6 +1169,41 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         Ok(())
     }
 
+    /// Encodes the check that the specification of the trait method implementation `def_id`
+    /// refines the specification of the implemented trait method, if any.
+    pub fn encode_refinement_check(&self, def_id: ProcedureDefId) -> SpannedEncodingResult<()> {
+        debug!("encode_refinement_check({:?})", def_id);
+        if !self.refinement_checks.borrow().contains_key(&def_id) {
+            // Only trait method implementations need to be checked; avoid
+            // analysing the bodies of all other (possibly unsupported) procedures.
+            if self.env.find_trait_method(def_id).is_none() {
+                self.refinement_checks.borrow_mut().insert(def_id, None);
+                return Ok(());
+            }
+            let procedure = self.env.get_procedure(def_id);
+            let proc_encoder = ProcedureEncoder::new(self, &procedure)?;
+            let method = proc_encoder.encode_refinement_check()?;
+            if let Some(ref method) = method {
+                self.log_vir_program_before_viper(method.to_string());
+            }
+            self.refinement_checks.borrow_mut().insert(def_id, method);
+        }
+        Ok(())
+    }
+
+    /// Encodes the check whether the precondition of the procedure `def_id` is satisfiable.
+    pub fn encode_precondition_check(&self, def_id: ProcedureDefId) -> SpannedEncodingResult<()> {
+        debug!("encode_precondition_check({:?})", def_id);
+        if !self.precondition_checks.borrow().contains_key(&def_id) {
+            let procedure = self.env.get_procedure(def_id);
+            let proc_encoder = ProcedureEncoder::new(self, &procedure)?;
+            let method = proc_encoder.encode_precondition_check()?;
+            self.log_vir_program_before_viper(method.to_string());
+            self.precondition_checks.borrow_mut().insert(def_id, method);
+        }
+        Ok(())
+    }
+
     /// Encodes the specification functions for the function/closure def_id.
     pub fn encode_spec_funcs(&self, def_id: ProcedureDefId)
         -> SpannedEncodingResult<Vec<vir::Function>>
@@ -781,6 +1229,32 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         type_encoder.encode_bounds(var)
     }
 
+    /// Returns the minimum and the maximum value of the given integer or `char` type.
+    pub fn get_integer_bounds(&self, ty: ty::Ty<'tcx>) -> Option<(vir::Expr, vir::Expr)> {
+        let type_encoder = TypeEncoder::new(self, ty);
+        type_encoder.get_integer_bounds()
+    }
+
+    /// Encodes the value of the integer type `ty` that is congruent to `value` modulo `2^bits`,
+    /// i.e. the result of wrapping `value` around the bounds of the type. This is also the
+    /// two's-complement conversion of a value whose sign is not representable.
+    pub fn encode_wrapped_value(&self, value: vir::Expr, ty: ty::Ty<'tcx>) -> vir::Expr {
+        let (lower, upper) = self.get_integer_bounds(ty).unwrap();
+        let modulus = vir::Expr::add(vir::Expr::sub(upper.clone(), lower.clone()), 1.into());
+        let wrapped = vir::Expr::add(
+            vir::Expr::modulo(vir::Expr::sub(value.clone(), lower.clone()), modulus),
+            lower.clone(),
+        );
+        vir::Expr::ite(
+            vir::Expr::and(
+                vir::Expr::le_cmp(lower, value.clone()),
+                vir::Expr::le_cmp(value.clone(), upper),
+            ),
+            value,
+            wrapped,
+        )
+    }
+
     /// See `spec_encoder::encode_spec_assertion` for a description of the arguments.
     pub fn encode_assertion(
         &self,
@@ -824,6 +1298,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
     pub fn encode_type_predicate_use(&self, ty: ty::Ty<'tcx>)
         -> EncodingResult<String>
     {
+        let ty = self.env().reveal_opaque_types(ty);
         if !self.type_predicate_names.borrow().contains_key(ty.kind()) {
             let type_encoder = TypeEncoder::new(self, ty);
             let name = type_encoder.encode_predicate_use()?;
@@ -859,24 +1334,64 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
     }
 
     /// Checks whether the given type implements structural equality
-    /// by either being a primitive type or by deriving the Eq trait.
+    /// by either being a primitive type, by deriving the Eq trait, by
+    /// deriving `PartialEq` and being annotated with `#[derive_spec_eq]`,
+    /// or by being an array or slice of such types.
     pub fn has_structural_eq_impl(&self, ty: ty::Ty<'tcx>) -> bool {
+        self.has_structural_eq_impl_rec(ty, &mut vec![])
+    }
+
+    /// Like `has_structural_eq_impl`, where `visited` contains the `#[derive_spec_eq]`
+    /// types whose fields are being checked, which are assumed to be structural.
+    fn has_structural_eq_impl_rec(&self, ty: ty::Ty<'tcx>, visited: &mut Vec<DefId>) -> bool {
         let ty = ty.peel_refs();
         match ty.kind() {
+            // boxes are compared by the value they point to
+            _ if ty.is_box() => self.has_structural_eq_impl_rec(ty.boxed_ty(), visited),
             ty::TyKind::Bool
             | ty::TyKind::Int(_)
             | ty::TyKind::Uint(_)
             | ty::TyKind::Char
+            | ty::TyKind::Str
             | ty::TyKind::Tuple(_)
             | ty::TyKind::Never
             | ty::TyKind::Param(_) => true,
-            ty::TyKind::Adt(_, _) => {
+            ty::TyKind::Adt(adt_def, substs) => {
                 self.env().tcx().has_structural_eq_impls(ty)
+                    || self.has_derived_spec_eq(adt_def, substs, visited)
             }
+            ty::TyKind::Array(elem_ty, _)
+            | ty::TyKind::Slice(elem_ty) => self.has_structural_eq_impl_rec(elem_ty, visited),
             _ => false,
         }
     }
 
+    /// Checks whether the given ADT is annotated with `#[derive_spec_eq]` and derives
+    /// `PartialEq`, and the fields of all its variants implement structural equality.
+    /// The derived `PartialEq` then compares the values like the snapshot equality.
+    fn has_derived_spec_eq(
+        &self,
+        adt_def: &'tcx ty::AdtDef,
+        substs: ty::subst::SubstsRef<'tcx>,
+        visited: &mut Vec<DefId>,
+    ) -> bool {
+        if visited.contains(&adt_def.did) {
+            return true;
+        }
+        if !self.env().has_prusti_attribute(adt_def.did, "derive_spec_eq")
+            || !self.env().has_derived_partial_eq(adt_def.did)
+        {
+            return false;
+        }
+        let tcx = self.env().tcx();
+        visited.push(adt_def.did);
+        let fields_are_structural = adt_def.all_fields().all(|field| {
+            self.has_structural_eq_impl_rec(field.ty(tcx, substs), visited)
+        });
+        visited.pop();
+        fields_are_structural
+    }
+
     pub fn encode_snapshot_type(&self, ty: ty::Ty<'tcx>)
         -> EncodingResult<vir::Type>
     {
@@ -886,17 +1401,100 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
     pub fn encode_snapshot_constructor(
         &self,
         ty: ty::Ty<'tcx>,
+        variant_index: usize,
         args: Vec<vir::Expr>,
     )
         -> EncodingResult<vir::Expr>
     {
-        self.snapshot_encoder.borrow_mut().encode_constructor(self, ty, args)
+        self.snapshot_encoder.borrow_mut().encode_constructor(self, ty, variant_index, args)
+    }
+
+    pub fn has_recursive_snapshot(&self, ty: ty::Ty<'tcx>) -> EncodingResult<bool> {
+        self.snapshot_encoder.borrow_mut().is_recursive(self, ty)
+    }
+
+    pub fn has_abstract_snapshot(&self, ty: ty::Ty<'tcx>) -> EncodingResult<bool> {
+        self.snapshot_encoder.borrow_mut().is_abstract(self, ty)
+    }
+
+    pub fn get_snapshot_variant_functions(&self, ty: ty::Ty<'tcx>)
+        -> EncodingResult<Option<Vec<SnapshotVariantFunctions>>>
+    {
+        self.snapshot_encoder.borrow_mut().get_variant_functions(self, ty)
+    }
+
+    pub fn encode_snapshot_sequence_len(
+        &self,
+        ty: ty::Ty<'tcx>,
+        expr: vir::Expr,
+    )
+        -> EncodingResult<vir::Expr>
+    {
+        self.snapshot_encoder.borrow_mut().encode_sequence_len(self, ty, expr)
+    }
+
+    pub fn encode_snapshot_sequence_read(
+        &self,
+        ty: ty::Ty<'tcx>,
+        expr: vir::Expr,
+        idx: vir::Expr,
+    )
+        -> EncodingResult<vir::Expr>
+    {
+        self.snapshot_encoder.borrow_mut().encode_sequence_read(self, ty, expr, idx)
+    }
+
+    /// Encodes the length in bytes of the given string slice. [expr] can be
+    /// Ref-based or a snapshot already.
+    pub fn encode_snapshot_str_len(&self, expr: vir::Expr) -> EncodingResult<vir::Expr> {
+        self.snapshot_encoder.borrow_mut().encode_str_len(self, expr)
+    }
+
+    /// Encodes a string literal as a constructor of the snapshot domain of
+    /// `str`.
+    pub fn encode_snapshot_str_literal(&self, literal: &str) -> EncodingResult<vir::Expr> {
+        self.snapshot_encoder.borrow_mut().encode_str_literal(self, literal)
+    }
+
+    pub fn encode_snapshot_sequence_unsize(
+        &self,
+        slice_ty: ty::Ty<'tcx>,
+        array_ty: ty::Ty<'tcx>,
+        slice_expr: vir::Expr,
+        array_expr: vir::Expr,
+        expr: vir::Expr,
+    )
+        -> EncodingResult<vir::Expr>
+    {
+        self.snapshot_encoder.borrow_mut().encode_sequence_unsize(
+            self,
+            slice_ty,
+            array_ty,
+            slice_expr,
+            array_expr,
+            expr,
+        )
     }
 
     pub fn supports_snapshot_equality(&self, ty: ty::Ty<'tcx>) -> EncodingResult<bool> {
         self.snapshot_encoder.borrow_mut().supports_equality(self, ty)
     }
 
+    /// Encodes `lhs == rhs` for two instances of the given type as an
+    /// equality of their snapshots.
+    pub fn encode_snapshot_equality(
+        &self,
+        ty: ty::Ty<'tcx>,
+        lhs: vir::Expr,
+        rhs: vir::Expr,
+    ) -> EncodingResult<vir::Expr> {
+        self.snapshot_encoder.borrow_mut().encode_equality(self, ty, lhs, rhs)
+    }
+
+    pub fn check_snapshot_equality_fields(&self, ty: ty::Ty<'tcx>) -> EncodingResult<()> {
+        self.snapshot_encoder.borrow_mut().check_equality_fields(self, ty)
+    }
+
     pub fn is_quantifiable(&self, ty: ty::Ty<'tcx>) -> EncodingResult<bool> {
         self.snapshot_encoder.borrow_mut().is_quantifiable(self, ty)
     }
@@ -964,6 +1562,12 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         value: &ty::ConstKind<'tcx>
     ) -> EncodingResult<vir::Expr> {
         trace!("encode_const_expr {:?}", value);
+        if let ty::ConstKind::Param(param) = value {
+            if let ty::ConstKind::Param(param) = self.resolve_const_param(*param) {
+                // A const generic parameter of the encoded procedure
+                return Ok(vir::Expr::local(self.encode_const_param_var(param)));
+            }
+        }
         let scalar_value = self.const_eval_intlike(value)?;
 
         let expr = match ty.kind() {
@@ -995,6 +1599,37 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         Ok(expr)
     }
 
+    /// Encodes a const generic parameter, e.g. `N`, as a ghost integer variable of the
+    /// procedures that are generic over it.
+    pub fn encode_const_param_var(&self, param: ty::ParamConst) -> vir::LocalVar {
+        vir::LocalVar::new(format!("const${}", param.name), vir::Type::Int)
+    }
+
+    /// Replaces a const generic parameter using the substitutions of the enclosing calls.
+    pub fn resolve_const_param(&self, param: ty::ParamConst) -> ty::ConstKind<'tcx> {
+        let mut resolved = ty::ConstKind::Param(param);
+        for constmap in self.const_param_repl.borrow().iter().rev() {
+            if let ty::ConstKind::Param(param) = resolved {
+                if let Some(subst) = constmap.get(&param) {
+                    resolved = subst.val;
+                }
+            }
+        }
+        resolved
+    }
+
+    /// Encodes the length of an array type, which is either a constant or a
+    /// const generic parameter (e.g. `N` in `[T; N]`).
+    pub fn encode_array_len(&self, len: &ty::Const<'tcx>) -> EncodingResult<ArrayLen> {
+        match len.val {
+            ty::ConstKind::Param(param) => Ok(ArrayLen::Param(self.encode_const_param_var(param))),
+            _ => Ok(ArrayLen::Fixed(
+                self.const_eval_intlike(&len.val)?
+                    .to_machine_usize(&self.env().tcx()).unwrap() as usize
+            )),
+        }
+    }
+
     pub fn encode_int_cast(&self, value: u128, ty: ty::Ty<'tcx>) -> vir::Expr {
         trace!("encode_int_cast {:?} as {:?}", value, ty);
 
@@ -1059,6 +1694,62 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         )
     }
 
+    /// Encodes the trait bounds of the type condition `type_cond_def_id` for the current
+    /// instantiation of the generics. A bound that holds is encoded as `true`, and a bound on
+    /// concrete types that does not hold as `false`. A bound that depends on type parameters
+    /// is encoded as a hypothesis, i.e. an uninterpreted boolean function.
+    pub fn encode_type_cond(&self, type_cond_def_id: DefId) -> EncodingResult<vir::Expr> {
+        use rustc_middle::ty::fold::TypeFoldable;
+        let tcx = self.env.tcx();
+        // The bounds of the type parameters of the procedure that is being encoded
+        let param_env_def_id = self.current_proc.borrow().unwrap_or(type_cond_def_id);
+        let mut hypotheses = vec![];
+        for predicate in tcx.predicates_of(type_cond_def_id).instantiate_identity(tcx).predicates {
+            let trait_ref = match predicate.kind().skip_binder() {
+                ty::PredicateKind::Trait(trait_predicate, _) => trait_predicate.trait_ref,
+                ty::PredicateKind::RegionOutlives(_)
+                | ty::PredicateKind::TypeOutlives(_) => continue,
+                _ => return Err(EncodingError::unsupported(format!(
+                    "the type condition `{}` is not a trait bound",
+                    predicate,
+                ))),
+            };
+            let substs = tcx.mk_substs(trait_ref.substs.iter().map(|arg| match arg.unpack() {
+                ty::subst::GenericArgKind::Type(ty) => self.resolve_typaram(ty).into(),
+                _ => arg,
+            }));
+            let trait_ref = tcx.erase_regions(ty::TraitRef::new(trait_ref.def_id, substs));
+            if self.env.trait_bound_holds(trait_ref, param_env_def_id) {
+                continue;
+            }
+            if !trait_ref.needs_subst() {
+                return Ok(false.into());
+            }
+            hypotheses.push(self.encode_type_cond_hypothesis(trait_ref));
+        }
+        Ok(hypotheses.into_iter().conjoin())
+    }
+
+    fn encode_type_cond_hypothesis(&self, trait_ref: ty::TraitRef<'tcx>) -> vir::Expr {
+        let mut type_cond_hypotheses = self.type_cond_hypotheses.borrow_mut();
+        let index = type_cond_hypotheses.len();
+        let function = type_cond_hypotheses.entry(trait_ref).or_insert_with(|| vir::Function {
+            name: format!("type_cond${}", index),
+            formal_args: vec![],
+            return_type: vir::Type::Bool,
+            pres: vec![],
+            posts: vec![],
+            body: None,
+        });
+        vir::Expr::func_app(
+            function.name.clone(),
+            vec![],
+            vec![],
+            vir::Type::Bool,
+            vir::Position::default(),
+        )
+    }
+
     /// Encode the body of the given procedure as a pure expression.
     pub fn encode_pure_expression(&self, proc_def_id: ProcedureDefId)
         -> SpannedEncodingResult<vir::Expr>
@@ -1067,11 +1758,11 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         let substs_key = self.type_substitution_key().with_span(mir_span)?;
         let key = (proc_def_id, substs_key);
         if !self.pure_function_bodies.borrow().contains_key(&key) {
-            let procedure = self.env.get_procedure(proc_def_id);
+            let mir = self.env.mir(proc_def_id);
             let pure_function_encoder = PureFunctionEncoder::new(
                 self,
                 proc_def_id,
-                procedure.get_mir(),
+                &mir,
                 true,
             );
             let body = pure_function_encoder.encode_body()?;
@@ -1115,10 +1806,13 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             // failed set. If the encoding is successful, we remove it again.
             self.failed_pure_functions.borrow_mut().insert(key.clone());
 
+            // Only the MIR body is needed here. Building a `Procedure` would
+            // analyse the control flow of the body, which might use features
+            // that we do not support even if the function is trusted.
             let wrapper_def_id = self.get_wrapper_def_id(proc_def_id);
-            let procedure = self.env.get_procedure(wrapper_def_id);
+            let mir = self.env.mir(wrapper_def_id);
             let pure_function_encoder =
-                PureFunctionEncoder::new(self, proc_def_id, procedure.get_mir(), false);
+                PureFunctionEncoder::new(self, proc_def_id, &mir, false);
             let (mut function, needs_patching) = if let Some(predicate_body) = self.get_predicate_body(proc_def_id) {
                 (pure_function_encoder.encode_predicate_function(predicate_body)?, false)
             } else if self.is_trusted(proc_def_id) {
@@ -1128,17 +1822,21 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             };
 
             if needs_patching {
+                let fuel = self.get_pure_function_fuel(proc_def_id);
                 self.mirror_encoder
                     .borrow_mut()
-                    .encode_mirrors(proc_def_id, &mut function);
+                    .encode_mirrors(proc_def_id, &mut function, &self.snapshot_encoder.borrow(), fuel);
             }
 
             function = self.snapshot_encoder
                 .borrow_mut()
                 .patch_snapshots_function(self, function)
-                .with_span(procedure.get_span())?;
+                .with_span(mir.span)?;
 
             self.log_vir_program_before_viper(function.to_string());
+            if config::dump_viper_per_fn() {
+                viper_dump::dump_function(self, &function);
+            }
             self.failed_pure_functions.borrow_mut().remove(&key);
             self.pure_functions.borrow_mut().insert(key, function);
         }
@@ -1147,6 +1845,24 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         Ok(())
     }
 
+    /// The number of times that the definition of a recursive pure function
+    /// can be unfolded from each of its calls, given by `#[fuel(n)]` or by the
+    /// `default_fuel` configuration flag.
+    fn get_pure_function_fuel(&self, proc_def_id: ProcedureDefId) -> u32 {
+        read_prusti_attrs("fuel", self.env.tcx().get_attrs(proc_def_id))
+            .pop()
+            .and_then(|fuel| fuel.parse().ok())
+            .unwrap_or_else(config::default_fuel)
+    }
+
+    /// The number of iterations to which the loops of a procedure are unrolled,
+    /// if the procedure is only verified for bounded executions with `#[bounded(k)]`.
+    pub fn get_loop_bound(&self, proc_def_id: ProcedureDefId) -> Option<u32> {
+        read_prusti_attrs("bounded", self.env.tcx().get_attrs(proc_def_id))
+            .pop()
+            .and_then(|bound| bound.parse().ok())
+    }
+
     pub fn get_item_name(&self, proc_def_id: ProcedureDefId) -> String {
         self.env.get_item_name(proc_def_id)
     }
@@ -1161,7 +1877,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         proc_def_id: ProcedureDefId,
     ) -> SpannedEncodingResult<(String, vir::Type)> {
         let wrapper_def_id = self.get_wrapper_def_id(proc_def_id);
-        let procedure = self.env.get_procedure(wrapper_def_id);
+        let mir = self.env.mir(wrapper_def_id);
 
         assert!(
             self.is_pure(proc_def_id),
@@ -1170,7 +1886,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         );
 
         let pure_function_encoder =
-            PureFunctionEncoder::new(self, proc_def_id, procedure.get_mir(), false);
+            PureFunctionEncoder::new(self, proc_def_id, &mir, false);
 
         self.queue_pure_function_encoding(proc_def_id);
 
@@ -1226,6 +1942,8 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         while !self.encoding_queue.borrow().is_empty() {
             let (proc_def_id, substs) = self.encoding_queue.borrow_mut().pop().unwrap();
             self.current_proc.replace(Some(proc_def_id.clone()));
+            self.error_manager().set_current_procedure(Some(proc_def_id));
+            let start = Instant::now();
 
             let proc_name = self.env.get_absolute_item_name(proc_def_id);
             let proc_def_path = self.env.get_item_def_path(proc_def_id);
@@ -1236,26 +1954,47 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
                 proc_name, proc_span, proc_def_path
             );
             let is_pure_function = self.is_pure(proc_def_id);
-            if is_pure_function {
+            if self.is_trusted(proc_def_id) && !is_pure_function {
+                // The body of a trusted procedure is not analysed at all, because its callers
+                // only rely on its contract
+                debug!(
+                    "Trusted procedure will not be encoded or verified: {:?}",
+                    proc_def_id
+                );
+            } else if config::check_specs_only() {
+                // Only the specifications are checked, the bodies are not encoded
+                if !is_pure_function {
+                    if let Err(error) = self.encode_refinement_check(proc_def_id) {
+                        self.register_encoding_error(error);
+                        debug!("Error encoding refinement check: {:?}", proc_def_id);
+                    }
+                    if let Err(error) = self.encode_precondition_check(proc_def_id) {
+                        self.register_encoding_error(error);
+                        debug!("Error encoding precondition check: {:?}", proc_def_id);
+                    }
+                }
+            } else if is_pure_function {
                 if let Err(error) = self.encode_pure_function_def(proc_def_id, substs) {
                     self.register_encoding_error(error);
                     debug!("Error encoding pure function: {:?}", proc_def_id);
                 }
             } else {
                 assert!(substs.is_empty());
-                if self.is_trusted(proc_def_id) {
-                    debug!(
-                        "Trusted procedure will not be encoded or verified: {:?}",
-                        proc_def_id
-                    );
-                } else {
-                    if let Err(error) = self.encode_procedure(proc_def_id) {
-                        self.register_encoding_error(error);
-                        debug!("Error encoding function: {:?}", proc_def_id);
-                    }
+                if let Err(error) = self.encode_refinement_check(proc_def_id) {
+                    self.register_encoding_error(error);
+                    debug!("Error encoding refinement check: {:?}", proc_def_id);
+                }
+                if let Err(error) = self.encode_procedure(proc_def_id) {
+                    self.register_encoding_error(error);
+                    debug!("Error encoding function: {:?}", proc_def_id);
                 }
             }
 
+            self.procedure_encoding_stats.borrow_mut()
+                .entry(proc_def_id)
+                .or_default()
+                .duration += start.elapsed();
+            self.error_manager().set_current_procedure(None);
             self.current_proc.replace(None);
         }
     }
@@ -1272,6 +2011,16 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         result
     }
 
+    /// Whether the integers of a function are encoded with their bit width.
+    /// Closures and specification items use the encoding of their function,
+    /// which has the `#[bitvector]` attribute.
+    pub fn uses_bitvector_encoding(&self, def_id: DefId) -> bool {
+        let item_def_id = self.env.tcx().closure_base_def_id(def_id);
+        let result = self.env.has_prusti_attribute(item_def_id, "bitvector");
+        trace!("uses_bitvector_encoding {:?} = {}", def_id, result);
+        result
+    }
+
     pub fn get_predicate_body(&self, def_id: ProcedureDefId) -> Option<&typed::Assertion<'tcx>> {
         let result = self.def_spec.get(&def_id).map_or(None, |spec| spec.expect_procedure().predicate_body.as_ref());
         trace!("get_predicate_body {:?} = {:?}", def_id, result);
@@ -1296,6 +2045,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         struct Resolver<'tcx> {
             tcx: ty::TyCtxt<'tcx>,
             tymap: HashMap<ty::Ty<'tcx>, ty::Ty<'tcx>>,
+            constmap: HashMap<ty::ParamConst, ty::ConstKind<'tcx>>,
         }
         impl<'tcx> TypeFolder<'tcx> for Resolver<'tcx> {
             fn tcx(&self) -> ty::TyCtxt<'tcx> {
@@ -1305,12 +2055,37 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
                 let rep = self.tymap.get(&ty).unwrap_or(&ty);
                 rep.super_fold_with(self)
             }
+            fn fold_const(&mut self, value: &'tcx ty::Const<'tcx>) -> &'tcx ty::Const<'tcx> {
+                match value.val {
+                    ty::ConstKind::Param(param) if self.constmap.contains_key(&param) => {
+                        let resolved = self.constmap[&param];
+                        self.tcx.mk_const(ty::Const { val: resolved, ty: value.ty })
+                    }
+                    _ => value.super_fold_with(self),
+                }
+            }
         }
-        ty.fold_with(&mut Resolver {
+        let resolved_ty = ty.fold_with(&mut Resolver {
             tcx: self.env().tcx(),
             // TODO: creating each time a current_tymap might be slow. This can be optimized.
             tymap: self.current_tymap(),
-        })
+            constmap: self.current_constmap(),
+        });
+        // Once the type parameters are known, the associated types of generic
+        // impls can be resolved.
+        self.env().normalize_projections(self.env().reveal_opaque_types(resolved_ty))
+    }
+
+    /// Resolves each const generic parameter that has a substitution in the
+    /// stack of const maps.
+    fn current_constmap(&self) -> HashMap<ty::ParamConst, ty::ConstKind<'tcx>> {
+        let mut map = HashMap::new();
+        for constmap in self.const_param_repl.borrow().iter() {
+            for &param in constmap.keys() {
+                map.insert(param, self.resolve_const_param(param));
+            }
+        }
+        map
     }
 
     /// Merges the stack of type maps into a single map.
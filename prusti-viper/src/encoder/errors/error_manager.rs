@@ -8,6 +8,7 @@ use prusti_common::vir::Position;
 use std::collections::HashMap;
 use rustc_span::source_map::SourceMap;
 use rustc_span::MultiSpan;
+use rustc_hir::def_id::DefId;
 use viper::VerificationError;
 use prusti_interface::PrustiError;
 use log::debug;
@@ -27,6 +28,15 @@ pub enum PanicCause {
     Unreachable,
     /// Caused by an unimplemented!()
     Unimplemented,
+    /// Caused by an `unwrap()` or `expect(..)` on a `None` value
+    UnwrapNone,
+    /// Caused by an `unwrap()` or `expect(..)` on an `Err` value
+    UnwrapErr,
+    /// Caused by a division or a remainder with a divisor of zero
+    DivisionByZero,
+    /// Caused by a division or a remainder of the minimum value of a signed integer
+    /// type by `-1`
+    DivisionOverflow,
 }
 
 /// In case of verification error, this enum will contain additional information
@@ -84,12 +94,31 @@ pub enum ErrorCtxt {
     DivergingCallInPureFunction,
     /// A Viper pure function call with `false` precondition that encodes a Rust panic in a pure function
     PanicInPureFunction(PanicCause),
-    /// A Viper `assert e1 ==> e2` that encodes a weakening of the precondition
-    /// of a method implementation of a trait
+    /// A Viper pure function call with `false` precondition that is reached when `unwrap()` is
+    /// called on a `None` or `Err` value in pure code
+    PureUnwrapOfNoValue,
+    /// A Viper pure function call with `false` precondition that is reached when a vector is
+    /// indexed out of bounds in pure code
+    PureVecIndexOutOfBounds,
+    /// A Viper pure function call with `false` precondition that is reached when the termination
+    /// measure does not decrease at a recursive call in a pure function
+    TerminationMeasureInPureFunction,
+    /// A Viper `assert` that checks that the termination measure decreases at a recursive call
+    AssertTerminationMeasure,
+    /// A Viper `assert` that checks that the termination measure of a loop decreases in each
+    /// iteration
+    AssertLoopTerminationMeasure,
+    /// A Viper `assert expr` that encodes a `prusti_assert!(expr)` ghost statement
+    AssertGhostStatement,
+    /// A Viper `assert` of the precondition of a method implementation of a trait, in the
+    /// refinement check that assumes the precondition of the trait method (whose span is given)
     AssertMethodPreconditionWeakening(MultiSpan),
-    /// A Viper `assert e1 ==> e2` that encodes a strengthening of the precondition
-    /// of a method implementation of a trait.
+    /// A Viper `assert` of the postcondition of a trait method, in the refinement check that
+    /// assumes the postcondition of the method implementation (whose span is given)
     AssertMethodPostconditionStrengthening(MultiSpan),
+    /// A Viper `assert false` after assuming the precondition of a method, whose failure shows
+    /// that the precondition is satisfiable (see `config::check_specs_only`)
+    AssertPreconditionUnsatisfiable,
     /// A cast like `usize as u32`.
     TypeCast,
     /// A Viper `assert false` that encodes an unsupported feature
@@ -102,6 +131,13 @@ pub struct ErrorManager<'tcx> {
     codemap: &'tcx SourceMap,
     source_span: HashMap<u64, MultiSpan>,
     error_contexts: HashMap<u64, ErrorCtxt>,
+    /// The procedure that was being encoded when each position was registered.
+    procedures: HashMap<u64, DefId>,
+    current_procedure: Option<DefId>,
+    /// Maps the names of the generated snapshot functions and domain functions
+    /// to the Rust type they encode and to the span of the item that
+    /// requested the encoding.
+    generated_items: HashMap<String, (String, MultiSpan)>,
     next_pos_id: u64,
 }
 
@@ -112,6 +148,9 @@ impl<'tcx> ErrorManager<'tcx>
             codemap,
             source_span: HashMap::new(),
             error_contexts: HashMap::new(),
+            procedures: HashMap::new(),
+            current_procedure: None,
+            generated_items: HashMap::new(),
             next_pos_id: 1,
         }
     }
@@ -147,9 +186,41 @@ impl<'tcx> ErrorManager<'tcx>
             Position::new(0, 0, pos_id)
         };
         self.source_span.insert(pos_id, span);
+        if let Some(def_id) = self.current_procedure {
+            self.procedures.insert(pos_id, def_id);
+        }
         pos
     }
 
+    /// Sets the procedure to which the positions registered from now on belong.
+    pub fn set_current_procedure(&mut self, def_id: Option<DefId>) {
+        self.current_procedure = def_id;
+    }
+
+    /// Records that the Viper item `name` was generated to encode the Rust type
+    /// `ty_name`, on behalf of the item at `span`. Only the first request is kept.
+    pub fn register_generated_item(&mut self, name: String, ty_name: String, span: MultiSpan) {
+        self.generated_items.entry(name).or_insert((ty_name, span));
+    }
+
+    /// Returns the procedure whose encoding contains the position of the
+    /// verification error, if known.
+    pub fn get_procedure(&self, ver_error: &VerificationError) -> Option<DefId> {
+        let pos_id: u64 = ver_error.pos_id.as_ref()?.parse().ok()?;
+        self.procedures.get(&pos_id).cloned()
+    }
+
+    /// Returns the context of the verification error, if known.
+    pub fn get_error_ctxt(&self, ver_error: &VerificationError) -> Option<&ErrorCtxt> {
+        let pos_id: u64 = ver_error.pos_id.as_ref()?.parse().ok()?;
+        self.error_contexts.get(&pos_id)
+    }
+
+    /// Returns the span registered for the position, if any.
+    pub fn get_span(&self, pos: &Position) -> Option<&MultiSpan> {
+        self.source_span.get(&pos.id())
+    }
+
     pub fn register_error(&mut self, pos: &Position, error_ctxt: ErrorCtxt) {
         debug!("Register error at: {:?}", pos.id());
         self.error_contexts.insert(pos.id(), error_ctxt);
@@ -157,6 +228,18 @@ impl<'tcx> ErrorManager<'tcx>
 
     pub fn translate_verification_error(&self, ver_error: &VerificationError) -> PrustiError {
         debug!("Verification error: {:?}", ver_error);
+        let opt_generated_item = ver_error.offending_function.as_ref()
+            .and_then(|name| self.generated_items.get(name));
+        if let Some((ty_name, span)) = opt_generated_item {
+            return PrustiError::internal(
+                format!("failed to encode the type `{}`", ty_name),
+                span.clone()
+            ).set_help(format!(
+                "This is a bug in Prusti. Please report it, together with the \
+                verification error: [{}] {}",
+                ver_error.full_id, ver_error.message
+            ));
+        }
         let opt_pos_id: Option<u64> = match ver_error.pos_id {
             Some(ref viper_pos_id) => {
                 match viper_pos_id.parse() {
@@ -271,6 +354,26 @@ impl<'tcx> ErrorManager<'tcx>
                     .set_failing_assertion(opt_cause_span)
             }
 
+            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::UnwrapNone)) => {
+                PrustiError::verification("statement might panic: unwrap on None", error_span)
+                    .set_failing_assertion(opt_cause_span)
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::UnwrapErr)) => {
+                PrustiError::verification("statement might panic: unwrap on Err", error_span)
+                    .set_failing_assertion(opt_cause_span)
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::DivisionByZero)) => {
+                PrustiError::verification("statement might panic: the divisor might be zero", error_span)
+                    .set_failing_assertion(opt_cause_span)
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::DivisionOverflow)) => {
+                PrustiError::verification("statement might panic: the division might overflow", error_span)
+                    .set_failing_assertion(opt_cause_span)
+            }
+
             ("assert.failed:assertion.false", ErrorCtxt::AssertTerminator(ref message)) => {
                 PrustiError::verification(format!("assertion might fail with \"{}\"", message), error_span)
                     .set_failing_assertion(opt_cause_span)
@@ -407,6 +510,68 @@ impl<'tcx> ErrorManager<'tcx>
                 ).push_primary_span(opt_cause_span)
             }
 
+            (
+                "application.precondition:assertion.false",
+                ErrorCtxt::PanicInPureFunction(PanicCause::DivisionByZero),
+            ) => {
+                PrustiError::verification(
+                    "statement in pure function might panic: the divisor might be zero",
+                    error_span
+                ).push_primary_span(opt_cause_span)
+            }
+
+            (
+                "application.precondition:assertion.false",
+                ErrorCtxt::PanicInPureFunction(PanicCause::DivisionOverflow),
+            ) => {
+                PrustiError::verification(
+                    "statement in pure function might panic: the division might overflow",
+                    error_span
+                ).push_primary_span(opt_cause_span)
+            }
+
+            (
+                "application.precondition:assertion.false",
+                ErrorCtxt::PureUnwrapOfNoValue,
+            ) => {
+                PrustiError::verification(
+                    "unwrap() might be called on a `None` or `Err` value",
+                    error_span
+                ).push_primary_span(opt_cause_span)
+            }
+
+            (
+                "application.precondition:assertion.false",
+                ErrorCtxt::PureVecIndexOutOfBounds,
+            ) => {
+                PrustiError::verification(
+                    "the vector index may be out of bounds",
+                    error_span
+                ).push_primary_span(opt_cause_span)
+            }
+
+            (
+                "application.precondition:assertion.false",
+                ErrorCtxt::TerminationMeasureInPureFunction,
+            ) | ("assert.failed:assertion.false", ErrorCtxt::AssertTerminationMeasure) => {
+                PrustiError::verification(
+                    "the termination measure might not decrease at this recursive call",
+                    error_span
+                ).push_primary_span(opt_cause_span)
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::AssertGhostStatement) => {
+                PrustiError::verification("the asserted specification might not hold", error_span)
+                    .push_primary_span(opt_cause_span)
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::AssertLoopTerminationMeasure) => {
+                PrustiError::verification(
+                    "the termination measure of the loop might not decrease after a loop iteration",
+                    error_span
+                ).push_primary_span(opt_cause_span)
+            }
+
             ("postcondition.violated:assertion.false", ErrorCtxt::PureFunctionDefinition) |
             ("postcondition.violated:assertion.false", ErrorCtxt::PureFunctionCall) |
             ("postcondition.violated:assertion.false", ErrorCtxt::GenericExpression) => {
@@ -461,16 +626,14 @@ impl<'tcx> ErrorManager<'tcx>
                 ).set_failing_assertion(opt_cause_span)
             }
 
-            ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPreconditionWeakening(impl_span)) => {
-                PrustiError::verification(format!("the method's precondition may not be a valid weakening of the trait's precondition."), error_span)
-                    //.push_primary_span(opt_cause_span)
-                    .push_primary_span(Some(&impl_span))
+            ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPreconditionWeakening(trait_span)) => {
+                PrustiError::verification("specification of impl does not refine trait", error_span)
+                    .push_primary_span(Some(&trait_span))
                     .set_help("The trait's precondition should imply the implemented method's precondition.")
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPostconditionStrengthening(impl_span)) => {
-                PrustiError::verification(format!("the method's postcondition may not be a valid strengthening of the trait's postcondition."), error_span)
-                    //.push_primary_span(opt_cause_span)
+                PrustiError::verification("specification of impl does not refine trait", error_span)
                     .push_primary_span(Some(&impl_span))
                     .set_help("The implemented method's postcondition should imply the trait's postcondition.")
             }
@@ -527,3 +690,54 @@ impl<'tcx> ErrorManager<'tcx>
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_span::source_map::FilePathMapping;
+
+    fn verification_error(pos: &Position, offending_function: Option<&str>) -> VerificationError {
+        VerificationError::new(
+            "application.precondition:assertion.false".to_string(),
+            Some(pos.id().to_string()),
+            None,
+            "Precondition of function snap$__$TY$__Foo might not hold.".to_string(),
+            None,
+            offending_function.map(|name| name.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_error_in_generated_item() {
+        let codemap = SourceMap::new(FilePathMapping::empty());
+        let mut error_manager = ErrorManager::new(&codemap);
+        error_manager.register_generated_item(
+            "snap$__$TY$__Foo".to_string(),
+            "Foo".to_string(),
+            MultiSpan::new(),
+        );
+        let pos = error_manager.register(MultiSpan::new(), ErrorCtxt::PureFunctionCall);
+        let error = verification_error(&pos, Some("snap$__$TY$__Foo"));
+        assert_eq!(
+            error_manager.translate_verification_error(&error).message(),
+            "[Prusti internal error] failed to encode the type `Foo`"
+        );
+    }
+
+    #[test]
+    fn test_error_in_application_of_generated_item() {
+        let codemap = SourceMap::new(FilePathMapping::empty());
+        let mut error_manager = ErrorManager::new(&codemap);
+        error_manager.register_generated_item(
+            "snap$__$TY$__Foo".to_string(),
+            "Foo".to_string(),
+            MultiSpan::new(),
+        );
+        let pos = error_manager.register(MultiSpan::new(), ErrorCtxt::PureFunctionCall);
+        let error = verification_error(&pos, None);
+        assert_eq!(
+            error_manager.translate_verification_error(&error).message(),
+            "[Prusti: verification error] precondition of pure function call might not hold."
+        );
+    }
+}
@@ -14,13 +14,15 @@ use crate::encoder::errors::EncodingErrorKind;
 pub struct SpannedEncodingError {
     pub(super) error: EncodingErrorKind,
     span: MultiSpan,
+    help: Option<String>,
+    note: Option<(String, MultiSpan)>,
 }
 
 pub type SpannedEncodingResult<T> = Result<T, SpannedEncodingError>;
 
 impl From<SpannedEncodingError> for PrustiError {
     fn from(other: SpannedEncodingError) -> Self {
-        match other.error {
+        let error = match other.error {
             EncodingErrorKind::Unsupported(msg) => {
                 PrustiError::unsupported(msg, other.span)
             }
@@ -30,6 +32,14 @@ impl From<SpannedEncodingError> for PrustiError {
             EncodingErrorKind::Internal(msg) => {
                 PrustiError::internal(msg, other.span)
             }
+        };
+        let error = match other.help {
+            Some(help) => error.set_help(help),
+            None => error,
+        };
+        match other.note {
+            Some((note, note_span)) => error.set_note(note, note_span),
+            None => error,
         }
     }
 }
@@ -39,6 +49,8 @@ impl SpannedEncodingError {
         SpannedEncodingError {
             error,
             span: span.into(),
+            help: None,
+            note: None,
         }
     }
 
@@ -66,6 +78,18 @@ impl SpannedEncodingError {
         )
     }
 
+    /// Suggest how to fix the error
+    pub fn set_help<M: ToString>(mut self, message: M) -> Self {
+        self.help = Some(message.to_string());
+        self
+    }
+
+    /// Point at a related source code location (e.g. a move of the value)
+    pub fn set_note<M: ToString, S: Into<MultiSpan>>(mut self, message: M, span: S) -> Self {
+        self.note = Some((message.to_string(), span.into()));
+        self
+    }
+
     pub fn kind(&self) -> &EncodingErrorKind {
         &self.error
     }
@@ -74,7 +74,8 @@ impl ExprFootprintGetter for vir::Expr {
                 &right.get_footprint(predicates),
             ),
 
-            vir::Expr::ForAll(vars, _triggers, box body, _) => {
+            vir::Expr::ForAll(vars, _triggers, box body, _)
+            | vir::Expr::Exists(vars, _triggers, box body, _) => {
                 assert!(vars.iter().all(|var| !var.typ.is_ref()));
                 let vars_places: HashSet<Perm> = vars
                     .iter()
@@ -8,7 +8,9 @@ use prusti_interface::environment::mir_analyses::initialization::{
     compute_definitely_initialized, DefinitelyInitializedAnalysisResult,
 };
 use prusti_interface::environment::place_set::PlaceSet;
-use prusti_interface::environment::{BasicBlockIndex, PermissionForest, ProcedureLoops, Procedure};
+use prusti_interface::environment::{
+    BasicBlockIndex, PermissionForest, PermissionForestError, ProcedureLoops, Procedure,
+};
 use prusti_interface::utils;
 use rustc_middle::{mir, ty};
 use log::{trace, debug};
@@ -72,7 +74,7 @@ impl<'p, 'tcx: 'p> LoopEncoder<'p, 'tcx> {
         &self,
         bb: BasicBlockIndex,
         bb_inv: BasicBlockIndex
-    ) -> PermissionForest<'p, 'tcx> {
+    ) -> Result<PermissionForest<'p, 'tcx>, PermissionForestError<'tcx>> {
         assert!(self.is_loop_head(bb));
 
         // 1.  Let ``A1`` be a set of pairs ``(p, t)`` where ``p`` is a prefix
@@ -96,7 +98,7 @@ impl<'p, 'tcx: 'p> LoopEncoder<'p, 'tcx> {
         //         bodies without unreachable elements instead of predicates.
 
         // Paths accessed inside the loop body.
-        let (write_leaves, mut_borrow_leaves, read_leaves) =
+        let (write_leaves, mut_borrow_leaves, read_leaves, shared_borrow_leaves) =
             self.loops().compute_read_and_write_leaves(
                 bb,
                 self.mir(),
@@ -113,12 +115,38 @@ impl<'p, 'tcx: 'p> LoopEncoder<'p, 'tcx> {
         for place in &write_leaves {
             all_places.insert(&place, self.mir(), self.tcx)
         }
+        for place in &shared_borrow_leaves {
+            all_places.insert(&place, self.mir(), self.tcx)
+        }
 
         // Construct the permission forest.
-        let forest =
-            PermissionForest::new(self.procedure.get_mir(), self.tcx, &write_leaves, &mut_borrow_leaves, &read_leaves, &all_places);
+        PermissionForest::new(
+            self.procedure.get_mir(),
+            self.tcx,
+            &write_leaves,
+            &mut_borrow_leaves,
+            &read_leaves,
+            &shared_borrow_leaves,
+            &all_places,
+        )
+    }
 
-        forest
+    /// Returns the places that are modified or mutably borrowed in the loop
+    /// body, and whose values are therefore havocked at the loop head.
+    pub fn compute_havocked_places(
+        &self,
+        bb: BasicBlockIndex,
+        bb_inv: BasicBlockIndex
+    ) -> Vec<mir::Place<'tcx>> {
+        assert!(self.is_loop_head(bb));
+        let (mut write_leaves, mut_borrow_leaves, _, _) =
+            self.loops().compute_read_and_write_leaves(
+                bb,
+                self.mir(),
+                Some(self.initialization.get_before_block(bb_inv)),
+            );
+        write_leaves.extend(mut_borrow_leaves);
+        write_leaves
     }
 
     /// Is the ``place`` definitely initialised at the beginning of ``bbi``?
@@ -154,6 +182,7 @@ impl<'p, 'tcx: 'p> LoopEncoder<'p, 'tcx> {
                     && self.mir()[bb].terminator().successors().any(|&succ_bb| {
                         self.procedure.is_reachable_block(succ_bb)
                             && self.procedure.is_spec_block(succ_bb)
+                            && !self.procedure.is_ghost_statement_block(succ_bb)
                     })
             })
             .cloned()
@@ -7,13 +7,13 @@
 mod downcast_detector;
 mod place_encoding;
 
-use crate::encoder::builtin_encoder::BuiltinFunctionKind;
+use crate::encoder::builtin_encoder::{BuiltinFunctionKind, BitwiseOpKind, encode_power_of_two};
 use crate::encoder::errors::{
     ErrorCtxt, PanicCause, SpannedEncodingError, EncodingError, WithSpan,
     SpannedEncodingResult, EncodingResult
 };
 use crate::encoder::Encoder;
-use prusti_common::vir;
+use prusti_common::{vir, vir_local};
 use prusti_common::config;
 use rustc_target::abi;
 use rustc_hir::def_id::DefId;
@@ -32,6 +32,15 @@ pub use place_encoding::{PlaceEncoding, ExprOrArrayBase};
 
 pub static PRECONDITION_LABEL: &'static str = "pre";
 pub static WAND_LHS_LABEL: &'static str = "lhs";
+/// Placeholder label of `old_loop(..)` expressions; replaced with the label
+/// emitted at the entry of the loop whose invariant is being encoded.
+pub static LOOP_ENTRY_LABEL: &'static str = "loop_entry";
+/// Placeholder variable of `loop_iterations()` expressions; replaced with the
+/// iteration counter of the loop whose invariant is being encoded.
+pub static LOOP_ITERATIONS_VAR: &'static str = "loop_iterations";
+/// Prefix of the labels of `label!(..)` statements, which keeps them apart
+/// from the labels generated by the encoding.
+pub static USER_LABEL_PREFIX: &'static str = "user$";
 
 pub trait PlaceEncoder<'v, 'tcx: 'v> {
 
@@ -501,7 +510,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
             mir::BinOp::Le => vir::Expr::le_cmp(left, right),
             mir::BinOp::Add => vir::Expr::add(left, right),
             mir::BinOp::Sub => vir::Expr::sub(left, right),
-            mir::BinOp::Rem => vir::Expr::rem(left, right),
+            // The division and the remainder of unsigned integers are the ones of Viper
+            mir::BinOp::Rem if ty.is_signed() => vir::Expr::rem(left, right),
+            mir::BinOp::Rem => vir::Expr::modulo(left, right),
+            mir::BinOp::Div if ty.is_signed() => vir::Expr::rust_div(left, right),
             mir::BinOp::Div => vir::Expr::div(left, right),
             mir::BinOp::Mul => vir::Expr::mul(left, right),
             mir::BinOp::BitAnd if is_bool => vir::Expr::and(left, right),
@@ -509,9 +521,17 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
             mir::BinOp::BitXor if is_bool => vir::Expr::xor(left, right),
             mir::BinOp::BitAnd |
             mir::BinOp::BitOr |
+            mir::BinOp::BitXor |
+            mir::BinOp::Shl |
+            mir::BinOp::Shr if ty.is_integral() && self.uses_bitvector_encoding() => {
+                self.encode_bitvector_op_expr(op, left, right, ty)?
+            }
+            mir::BinOp::BitAnd |
+            mir::BinOp::BitOr |
             mir::BinOp::BitXor => {
                 return Err(EncodingError::unsupported(
-                    "bitwise operations on non-boolean types are not supported"
+                    "bitwise operations on non-boolean types are only supported in \
+                    functions with the `#[bitvector]` attribute"
                 ))
             }
             unsupported_op => {
@@ -523,6 +543,115 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
         })
     }
 
+    /// Whether the integers of the encoded function are encoded with their bit
+    /// width, as requested by the `#[bitvector]` attribute.
+    fn uses_bitvector_encoding(&self) -> bool {
+        self.encoder.uses_bitvector_encoding(self.def_id)
+    }
+
+    /// Returns the bit width of an integer type and whether it is signed.
+    fn get_integer_bit_width(&self, ty: ty::Ty<'tcx>) -> EncodingResult<(u64, bool)> {
+        let pointer_width = self.encoder.env().tcx().data_layout.pointer_size.bits();
+        match ty.kind() {
+            ty::TyKind::Int(int_ty) => Ok((int_ty.bit_width().unwrap_or(pointer_width), true)),
+            ty::TyKind::Uint(uint_ty) => Ok((uint_ty.bit_width().unwrap_or(pointer_width), false)),
+            _ => Err(EncodingError::unsupported(format!(
+                "bitwise operations on type '{:?}' are not supported",
+                ty,
+            ))),
+        }
+    }
+
+    /// Encode a bitwise operation or a shift on integers of type `ty`.
+    ///
+    /// The values stay encoded as unbounded integers, so no conversion is
+    /// needed when calling functions that do not use the bit-width aware
+    /// encoding. Shifts are encoded exactly using multiplication, division
+    /// and modulo by powers of two, while `&`, `|` and `^` are encoded as
+    /// calls of functions whose postconditions describe their results.
+    fn encode_bitvector_op_expr(
+        &self,
+        op: mir::BinOp,
+        left: vir::Expr,
+        right: vir::Expr,
+        ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<vir::Expr> {
+        let (width, signed) = self.get_integer_bit_width(ty)?;
+        let bitwise_op = match op {
+            mir::BinOp::BitAnd => BitwiseOpKind::And,
+            mir::BinOp::BitOr => BitwiseOpKind::Or,
+            mir::BinOp::BitXor => BitwiseOpKind::Xor,
+            mir::BinOp::Shl | mir::BinOp::Shr => {
+                return Ok(self.encode_shift_expr(op, left, right, width, signed));
+            }
+            _ => unreachable!("{:?}", op),
+        };
+        let function_name = self.encoder.encode_builtin_function_use(
+            BuiltinFunctionKind::BitwiseOp { op: bitwise_op, width, signed }
+        );
+        Ok(vir::Expr::func_app(
+            function_name,
+            vec![left, right],
+            vec![vir_local!{ left: Int }, vir_local!{ right: Int }],
+            vir::Type::Int,
+            vir::Position::default(),
+        ))
+    }
+
+    /// Encode `left << right` or `left >> right`, assuming that `right` is
+    /// smaller than the bit width (this is checked by `encode_bin_op_check`).
+    fn encode_shift_expr(
+        &self,
+        op: mir::BinOp,
+        left: vir::Expr,
+        right: vir::Expr,
+        width: u64,
+        signed: bool,
+    ) -> vir::Expr {
+        let factor = match right {
+            vir::Expr::Const(vir::Const::Int(amount), _) if 0 <= amount && (amount as u64) < width => {
+                encode_power_of_two(amount as u64)
+            }
+            _ => {
+                let function_name = self.encoder.encode_builtin_function_use(
+                    BuiltinFunctionKind::PowerOfTwo
+                );
+                vir::Expr::func_app(
+                    function_name,
+                    vec![right],
+                    vec![vir_local!{ exponent: Int }],
+                    vir::Type::Int,
+                    vir::Position::default(),
+                )
+            }
+        };
+        match op {
+            // The division rounds towards negative infinity, like an arithmetic shift
+            mir::BinOp::Shr if signed => vir::Expr::div(left, factor),
+            mir::BinOp::Shr => vir::Expr::div(
+                vir::Expr::modulo(left, encode_power_of_two(width)),
+                factor,
+            ),
+            mir::BinOp::Shl => {
+                // The bits shifted out of the type are dropped
+                let shifted = vir::Expr::modulo(
+                    vir::Expr::mul(left, factor),
+                    encode_power_of_two(width),
+                );
+                if signed {
+                    vir::Expr::ite(
+                        vir::Expr::ge_cmp(shifted.clone(), encode_power_of_two(width - 1)),
+                        vir::Expr::sub(shifted.clone(), encode_power_of_two(width)),
+                        shifted,
+                    )
+                } else {
+                    shifted
+                }
+            }
+            _ => unreachable!("{:?}", op),
+        }
+    }
+
     pub fn encode_unary_op_expr(&self, op: mir::UnOp, expr: vir::Expr) -> vir::Expr {
         match op {
             mir::UnOp::Not => vir::Expr::not(expr),
@@ -538,7 +667,16 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
         right: vir::Expr,
         ty: ty::Ty<'tcx>,
     ) -> EncodingResult<vir::Expr> {
-        if !op.is_checkable() || !config::check_overflows() {
+        if matches!(op, mir::BinOp::Shl | mir::BinOp::Shr)
+            && ty.is_integral() && self.uses_bitvector_encoding()
+        {
+            // Shifting by a negative amount or by at least the bit width overflows
+            let (width, _) = self.get_integer_bit_width(ty)?;
+            Ok(vir::Expr::or(
+                vir::Expr::lt_cmp(right.clone(), 0.into()),
+                vir::Expr::ge_cmp(right, width.into()),
+            ))
+        } else if !op.is_checkable() || !config::check_overflows() {
             Ok(false.into())
         } else {
             let result = self.encode_bin_op_expr(op, left, right, ty)?;
@@ -566,10 +704,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
                         vir::Expr::lt_cmp(result.clone(), std::u128::MIN.into()),
                         vir::Expr::gt_cmp(result, std::u128::MAX.into()),
                     ),
-                    ty::TyKind::Uint(ty::UintTy::Usize) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::usize::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::usize::MAX.into()),
-                    ),
                     // Signed
                     ty::TyKind::Int(ty::IntTy::I8) => vir::Expr::or(
                         vir::Expr::lt_cmp(result.clone(), std::i8::MIN.into()),
@@ -591,10 +725,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
                         vir::Expr::lt_cmp(result.clone(), std::i128::MIN.into()),
                         vir::Expr::gt_cmp(result, std::i128::MAX.into()),
                     ),
-                    ty::TyKind::Int(ty::IntTy::Isize) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::isize::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::isize::MAX.into()),
-                    ),
+                    // Pointer-sized, whose bounds depend on the target
+                    ty::TyKind::Uint(ty::UintTy::Usize)
+                    | ty::TyKind::Int(ty::IntTy::Isize) => {
+                        let (lower, upper) = self.encoder.get_integer_bounds(ty).unwrap();
+                        vir::Expr::or(
+                            vir::Expr::lt_cmp(result.clone(), lower),
+                            vir::Expr::gt_cmp(result, upper),
+                        )
+                    }
 
                     _ => {
                         return Err(EncodingError::unsupported(format!(
@@ -700,8 +839,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
                         pos,
                     ));
                 } else {
-                    // Don't check the cast
-                    encoded_operand
+                    // Encode the wrapping semantics of the cast, which also
+                    // covers the two's-complement conversion of a change of sign
+                    self.encoder.encode_wrapped_value(encoded_operand, dst_ty)
                 }
             }
 
@@ -19,7 +19,10 @@ impl MirSuccessor {
             MirSuccessor::GotoSwitch(guarded_targets, default) => {
                 let mut targets = vec![*default];
                 for (_, target) in guarded_targets {
-                    targets.push(*target);
+                    // The same block can be the target of several guards
+                    if !targets.contains(target) {
+                        targets.push(*target);
+                    }
                 }
                 targets
             }
@@ -10,13 +10,18 @@ use std::collections::HashSet;
 use prusti_common::vir::{self, ExprIterator, WithIdentifier};
 use prusti_interface::environment::borrowck::facts::Loan;
 use crate::encoder::encoder::Encoder;
+use crate::encoder::snapshot::encoder::SnapshotEncoder;
 use std::collections::HashMap;
+use std::iter;
 
 const MIRROR_DOMAIN_NAME: &str = "MirrorDomain";
+const FUEL_DOMAIN_NAME: &str = "Fuel";
 
 pub struct MirrorEncoder {
     domain: vir::Domain,
     encoded: HashSet<DefId>,
+    /// Whether some mirror takes a fuel argument, which requires the fuel domain.
+    uses_fuel: bool,
 }
 
 // mirror_caller_functions: RefCell<Vec<vir::Function>>
@@ -31,27 +36,41 @@ impl MirrorEncoder {
                 type_vars: vec![],
             },
             encoded: HashSet::new(),
+            uses_fuel: false,
         }
     }
 
     /// Returns a list of Viper domains needed by the encoded mirrors.
     pub fn get_viper_domains(&self) -> Vec<vir::Domain> {
-        if self.encoded.is_empty() {
-            vec![]
-        } else {
-            vec![self.domain.clone()]
+        let mut domains = vec![];
+        if !self.encoded.is_empty() {
+            domains.push(self.domain.clone());
+        }
+        if self.uses_fuel {
+            domains.push(vir::Domain {
+                name: FUEL_DOMAIN_NAME.to_string(),
+                functions: vec![fuel_zero_func(), fuel_succ_func()],
+                axioms: vec![],
+                type_vars: vec![],
+            });
         }
+        domains
     }
 
     pub fn get_viper_functions(&self) -> Vec<vir::Function> {
         vec![]
     }
 
+    /// Encodes the mirrors of a pure function. `fuel` is the number of times
+    /// that the definition of a recursive function can be unfolded starting
+    /// from each of its calls.
     pub fn encode_mirrors(
         &mut self,
         // encoder: &Encoder,
         def_id: DefId,
         function: &mut vir::Function,
+        snapshot_encoder: &SnapshotEncoder,
+        fuel: u32,
     ) {
         // don't encode a mirror for the same DefId multiple times
         if self.encoded.contains(&def_id) {
@@ -59,15 +78,16 @@ impl MirrorEncoder {
         }
         self.encoded.insert(def_id);
 
-        self.encode_mirror_simple(def_id, function);
-        self.encode_mirror_axiomatized(def_id, function);
+        let mirror_func = self.encode_mirror_simple(def_id, function);
+        self.encode_mirror_axiomatized(def_id, function, mirror_func, snapshot_encoder);
+        self.encode_mirror_with_fuel(def_id, function, fuel);
     }
 
     fn encode_mirror_simple(
         &mut self,
         _def_id: DefId,
         function: &mut vir::Function,
-    ) {
+    ) -> vir::DomainFunc {
         // create mirror function
         let mirror_func = vir::DomainFunc {
             name: format!("mirror_simple${}", function.name),
@@ -97,15 +117,333 @@ impl MirrorEncoder {
         ));
 
         // add mirror function to mirror domain
-        self.domain.functions.push(mirror_func);
+        self.domain.functions.push(mirror_func.clone());
+        mirror_func
     }
 
-    // TODO: ...
+    /// Axiomatizes the mirror of a function that is recursive on the
+    /// structure of one of its snapshot arguments. For each variant of that
+    /// argument, the axiom
+    /// ```plain
+    /// forall fields..., other_args... :: {mirror(cons(fields...), other_args...)}
+    ///   pres[arg := cons(fields...)] ==>
+    ///     mirror(cons(fields...), other_args...) == body[arg := cons(fields...), f := mirror]
+    /// ```
+    /// unrolls the function on snapshots that are built with a constructor,
+    /// which allows evaluating the function on concrete values. Since the
+    /// recursive calls take fields of the argument, each instantiation
+    /// removes a constructor and the unrolling terminates.
     fn encode_mirror_axiomatized(
         &mut self,
         _def_id: DefId,
-        _function: &mut vir::Function,
-    ) {}
+        function: &vir::Function,
+        mirror_func: vir::DomainFunc,
+        snapshot_encoder: &SnapshotEncoder,
+    ) {
+        let body = match function.body {
+            Some(ref body) => body,
+            None => return,
+        };
+
+        // Domain axioms can only use domain functions and the mirror itself
+        let mut checker = AxiomatizableChecker {
+            function_name: &function.name,
+            recursive_calls: vec![],
+            is_axiomatizable: true,
+        };
+        for expr in function.pres.iter().chain(iter::once(body)) {
+            vir::ExprWalker::walk(&mut checker, expr);
+        }
+        if !checker.is_axiomatizable || checker.recursive_calls.is_empty() {
+            return;
+        }
+
+        // Find an argument that decreases structurally at every recursive call
+        let decreasing_arg = function.formal_args.iter().enumerate().find(|(index, arg)| {
+            let arg_expr = vir::Expr::local((*arg).clone());
+            snapshot_encoder.get_constructors(&arg.typ).is_some()
+                && checker.recursive_calls.iter().all(|call_args| {
+                    is_strict_subterm(snapshot_encoder, &call_args[*index], &arg_expr)
+                })
+        });
+        let (arg_index, arg) = match decreasing_arg {
+            Some((arg_index, arg)) => (arg_index, arg.clone()),
+            None => return,
+        };
+
+        let constructors = snapshot_encoder.get_constructors(&arg.typ).unwrap();
+        for (variant_index, constructor) in constructors.into_iter().enumerate() {
+            let fields: Vec<vir::LocalVar> = constructor.formal_args.iter()
+                .map(|field| vir::LocalVar::new(
+                    format!("__mirror_field{}", field.name),
+                    field.typ.clone(),
+                ))
+                .collect();
+            let constructor_app = constructor.apply(
+                fields.iter().cloned().map(vir::Expr::local).collect()
+            );
+            let instantiate = |expr: vir::Expr| {
+                let expr = expr.replace_place(&vir::Expr::local(arg.clone()), &constructor_app);
+                vir::ExprFolder::fold(&mut MirrorCallFolder {
+                    function_name: &function.name,
+                    mirror_func: &mirror_func,
+                    fuel: None,
+                }, expr)
+            };
+
+            let mut vars = fields.clone();
+            let mut mirror_args = vec![];
+            for (index, formal_arg) in function.formal_args.iter().enumerate() {
+                if index == arg_index {
+                    mirror_args.push(constructor_app.clone());
+                } else {
+                    vars.push(formal_arg.clone());
+                    mirror_args.push(vir::Expr::local(formal_arg.clone()));
+                }
+            }
+            let mirror_app = mirror_func.apply(mirror_args);
+            let definition = vir::Expr::implies(
+                function.pres.iter().cloned().map(|pre| instantiate(pre)).conjoin(),
+                vir::Expr::eq_cmp(mirror_app.clone(), instantiate(body.clone())),
+            );
+            let expr = if vars.is_empty() {
+                definition
+            } else {
+                vir::Expr::forall(
+                    vars,
+                    vec![vir::Trigger::new(vec![mirror_app])],
+                    definition,
+                )
+            };
+            self.domain.axioms.push(vir::DomainAxiom {
+                name: format!("{}${}$definition", mirror_func.name, variant_index),
+                expr,
+                domain_name: MIRROR_DOMAIN_NAME.to_string(),
+            });
+        }
+    }
+
+    /// Axiomatizes the mirror of a recursive function with an additional fuel
+    /// argument, with the axioms
+    /// ```plain
+    /// forall fuel, args... :: {mirror(succ(fuel), args...)}
+    ///   pres ==> mirror(succ(fuel), args...) == body[f := mirror(fuel, ..)]
+    /// forall fuel, args... :: {mirror(succ(fuel), args...)}
+    ///   mirror(succ(fuel), args...) == mirror(fuel, args...)
+    /// ```
+    /// and adds to the function the postcondition
+    /// `result == mirror(succ^n(zero), args...)`, where `n` is `fuel`.
+    /// Since the axioms only trigger on a mirror whose fuel is a successor,
+    /// and each instantiation removes a successor, the definition is unfolded
+    /// at most `n` times from each call of the function. This bounds the
+    /// instantiations of the axioms, unlike an unrestricted definitional axiom
+    /// that triggers on the recursive calls of its own body.
+    fn encode_mirror_with_fuel(
+        &mut self,
+        _def_id: DefId,
+        function: &mut vir::Function,
+        fuel: u32,
+    ) {
+        if fuel == 0 {
+            return;
+        }
+        let body = match function.body {
+            Some(ref body) => body.clone(),
+            None => return,
+        };
+
+        // Domain axioms can only use domain functions and the mirror itself
+        let mut checker = AxiomatizableChecker {
+            function_name: &function.name,
+            recursive_calls: vec![],
+            is_axiomatizable: true,
+        };
+        for expr in function.pres.iter().chain(iter::once(&body)) {
+            vir::ExprWalker::walk(&mut checker, expr);
+        }
+        if !checker.is_axiomatizable || checker.recursive_calls.is_empty() {
+            return;
+        }
+
+        let fuel_var = vir::LocalVar::new("__fuel", fuel_type());
+        let mirror_func = vir::DomainFunc {
+            name: format!("mirror_fuel${}", function.name),
+            formal_args: iter::once(fuel_var.clone())
+                .chain(function.formal_args.iter().cloned())
+                .collect(),
+            return_type: function.return_type.clone(),
+            unique: false,
+            domain_name: MIRROR_DOMAIN_NAME.to_string(),
+        };
+        let args: Vec<vir::Expr> = function.formal_args.iter()
+            .cloned()
+            .map(vir::Expr::local)
+            .collect();
+        let apply_with_fuel = |fuel: vir::Expr| {
+            mirror_func.apply(iter::once(fuel).chain(args.iter().cloned()).collect())
+        };
+        let fuel_expr = vir::Expr::local(fuel_var.clone());
+        let mirror_app = apply_with_fuel(fuel_expr.clone());
+        let mirror_succ_app = apply_with_fuel(fuel_succ_func().apply(vec![fuel_expr.clone()]));
+        let instantiate = |expr: vir::Expr| {
+            vir::ExprFolder::fold(&mut MirrorCallFolder {
+                function_name: &function.name,
+                mirror_func: &mirror_func,
+                fuel: Some(fuel_expr.clone()),
+            }, expr)
+        };
+        let vars: Vec<_> = mirror_func.formal_args.clone();
+        let trigger = vir::Trigger::new(vec![mirror_succ_app.clone()]);
+        let definition = vir::Expr::implies(
+            function.pres.iter().cloned().map(|pre| instantiate(pre)).conjoin(),
+            vir::Expr::eq_cmp(mirror_succ_app.clone(), instantiate(body)),
+        );
+        self.domain.axioms.push(vir::DomainAxiom {
+            name: format!("{}$definition", mirror_func.name),
+            expr: vir::Expr::forall(vars.clone(), vec![trigger.clone()], definition),
+            domain_name: MIRROR_DOMAIN_NAME.to_string(),
+        });
+        // The value does not depend on the fuel. Like the definition of the
+        // function itself, this relies on the termination of the function.
+        self.domain.axioms.push(vir::DomainAxiom {
+            name: format!("{}$fuel", mirror_func.name),
+            expr: vir::Expr::forall(
+                vars,
+                vec![trigger],
+                vir::Expr::eq_cmp(mirror_succ_app, mirror_app),
+            ),
+            domain_name: MIRROR_DOMAIN_NAME.to_string(),
+        });
+
+        // add postcondition to the original function
+        // [result == mirror(succ^fuel(zero), args), true]
+        let initial_fuel = (0..fuel).fold(
+            fuel_zero_func().apply(vec![]),
+            |fuel, _| fuel_succ_func().apply(vec![fuel]),
+        );
+        function.posts.push(vir::Expr::InhaleExhale(
+            box vir::Expr::eq_cmp(
+                vir::Expr::local(
+                    vir::LocalVar::new("__result", function.return_type.clone()),
+                ),
+                apply_with_fuel(initial_fuel),
+            ),
+            box true.into(),
+            vir::Position::default(),
+        ));
+
+        self.domain.functions.push(mirror_func.clone());
+        self.uses_fuel = true;
+    }
+}
+
+fn fuel_type() -> vir::Type {
+    vir::Type::Domain(FUEL_DOMAIN_NAME.to_string())
+}
+
+fn fuel_zero_func() -> vir::DomainFunc {
+    vir::DomainFunc {
+        name: "fuel$zero".to_string(),
+        formal_args: vec![],
+        return_type: fuel_type(),
+        unique: false,
+        domain_name: FUEL_DOMAIN_NAME.to_string(),
+    }
+}
+
+fn fuel_succ_func() -> vir::DomainFunc {
+    vir::DomainFunc {
+        name: "fuel$succ".to_string(),
+        formal_args: vec![vir::LocalVar::new("fuel", fuel_type())],
+        return_type: fuel_type(),
+        unique: false,
+        domain_name: FUEL_DOMAIN_NAME.to_string(),
+    }
+}
+
+/// Checks that an expression only uses constructs that can appear in a domain
+/// axiom, and collects the arguments of the recursive calls.
+struct AxiomatizableChecker<'a> {
+    function_name: &'a str,
+    recursive_calls: Vec<Vec<vir::Expr>>,
+    is_axiomatizable: bool,
+}
+
+impl<'a> vir::ExprWalker for AxiomatizableChecker<'a> {
+    fn walk(&mut self, expr: &vir::Expr) {
+        match expr {
+            vir::Expr::Local(..)
+            | vir::Expr::Const(..)
+            | vir::Expr::UnaryOp(..)
+            | vir::Expr::BinOp(..)
+            | vir::Expr::Cond(..)
+            | vir::Expr::LetExpr(..)
+            | vir::Expr::DomainFuncApp(..)
+            | vir::Expr::FuncApp(..) => vir::default_walk_expr(self, expr),
+            _ => self.is_axiomatizable = false,
+        }
+    }
+
+    fn walk_func_app(
+        &mut self,
+        name: &str,
+        args: &Vec<vir::Expr>,
+        _formal_args: &Vec<vir::LocalVar>,
+        _return_type: &vir::Type,
+        _pos: &vir::Position,
+    ) {
+        if name == self.function_name {
+            self.recursive_calls.push(args.clone());
+            for arg in args {
+                self.walk(arg);
+            }
+        } else {
+            self.is_axiomatizable = false;
+        }
+    }
+}
+
+/// Replaces the recursive calls of a function with calls of its mirror,
+/// passing `fuel` as first argument if the mirror takes a fuel.
+struct MirrorCallFolder<'a> {
+    function_name: &'a str,
+    mirror_func: &'a vir::DomainFunc,
+    fuel: Option<vir::Expr>,
+}
+
+impl<'a> vir::ExprFolder for MirrorCallFolder<'a> {
+    fn fold_func_app(
+        &mut self,
+        name: String,
+        args: Vec<vir::Expr>,
+        formal_args: Vec<vir::LocalVar>,
+        return_type: vir::Type,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        let args: Vec<_> = args.into_iter().map(|arg| self.fold(arg)).collect();
+        if name == self.function_name {
+            let args = self.fuel.iter().cloned().chain(args.into_iter()).collect();
+            self.mirror_func.apply(args)
+        } else {
+            vir::Expr::FuncApp(name, args, formal_args, return_type, pos)
+        }
+    }
+}
+
+/// Whether `expr` reads a (possibly nested) field of the snapshot `base`.
+fn is_strict_subterm(
+    snapshot_encoder: &SnapshotEncoder,
+    expr: &vir::Expr,
+    base: &vir::Expr,
+) -> bool {
+    match expr {
+        vir::Expr::DomainFuncApp(func, args, _)
+            if args.len() == 1 && snapshot_encoder.is_field_access_func(func) =>
+        {
+            &args[0] == base || is_strict_subterm(snapshot_encoder, &args[0], base)
+        }
+        _ => false,
+    }
 }
 
 // ------------------------------
@@ -8,9 +8,11 @@ pub use self::encoder::Encoder;
 
 mod borrows;
 mod builtin_encoder;
+mod counterexample;
 mod specs_closures_collector;
 mod encoder;
 mod errors;
+pub use errors::ErrorCtxt;
 mod foldunfold;
 mod initialisation;
 mod loop_encoder;
@@ -32,3 +34,4 @@ mod snapshot;
 mod mirror_function_encoder;
 mod purifier;
 mod array_encoder;
+mod viper_dump;
@@ -14,9 +14,14 @@ use crate::encoder::foldunfold;
 use crate::encoder::initialisation::InitInfo;
 use crate::encoder::loop_encoder::{LoopEncoder, LoopEncoderError};
 use crate::encoder::mir_encoder::{MirEncoder, FakeMirEncoder, PlaceEncoder, PlaceEncoding, ExprOrArrayBase};
-use crate::encoder::mir_encoder::PRECONDITION_LABEL;
+use crate::encoder::mir_encoder::{
+    PRECONDITION_LABEL, LOOP_ENTRY_LABEL, LOOP_ITERATIONS_VAR, USER_LABEL_PREFIX, WAND_LHS_LABEL,
+};
 use crate::encoder::mir_successor::MirSuccessor;
 use crate::encoder::places::{Local, LocalVariableManager, Place};
+use crate::encoder::spec_encoder::{
+    encode_spec_entailment_call, encode_spec_measure, encode_measure_decrease,
+};
 use crate::encoder::Encoder;
 use prusti_common::{
     config,
@@ -27,7 +32,7 @@ use prusti_common::{
     vir::{
         borrows::Borrow,
         collect_assigned_vars,
-        fixes::fix_ghost_vars,
+        fixes::{fix_ghost_vars, fix_triggers},
         CfgBlockIndex, Expr, ExprIterator, Successor, Type,
     },
 };
@@ -39,7 +44,9 @@ use prusti_interface::{
             LoanPlaces, PoloniusInfo, PoloniusInfoError, ReborrowingDAG, ReborrowingDAGNode,
             ReborrowingKind, ReborrowingZombity,
         },
-        BasicBlockIndex, PermissionKind, Procedure,
+        mir_analyses::reaching_moves::compute_reaching_moves,
+        BasicBlockIndex, BuiltinIntMethod, BuiltinVecMethod, Environment, PermissionForestError, PermissionKind,
+        Procedure, UnsafeRegions,
     },
 };
 use prusti_interface::utils;
@@ -68,6 +75,10 @@ use crate::encoder::errors::EncodingErrorKind;
 use crate::encoder::snapshot;
 use std::convert::TryInto;
 
+const LOOP_INVARIANT_PLACEMENT_HELP: &str =
+    "put all the `body_invariant!(..)` of a loop at the beginning of its body, \
+    outside of any conditional branch";
+
 pub struct ProcedureEncoder<'p, 'v: 'p, 'tcx: 'v> {
     encoder: &'p Encoder<'v, 'tcx>,
     proc_def_id: ProcedureDefId,
@@ -79,6 +90,9 @@ pub struct ProcedureEncoder<'p, 'v: 'p, 'tcx: 'v> {
     auxiliary_local_vars: HashMap<String, vir::Type>,
     mir_encoder: MirEncoder<'p, 'v, 'tcx>,
     check_panics: bool,
+    /// Whether the procedure has to be proven free of panics, including the panics of the
+    /// library methods that it calls (see `encode_unwrap_check`).
+    panic_free: bool,
     check_foldunfold_state: bool,
     polonius_info: Option<PoloniusInfo<'p, 'tcx>>,
     procedure_contract: Option<ProcedureContract<'tcx>>,
@@ -108,6 +122,18 @@ pub struct ProcedureEncoder<'p, 'v: 'p, 'tcx: 'v> {
     old_ghost_vars: HashMap<String, vir::Type>,
     /// For each loop head, the block at whose end the loop invariant holds
     cached_loop_invariant_block: HashMap<BasicBlockIndex, BasicBlockIndex>,
+    /// For each loop head, the label of the state just before the loop is entered, as used by
+    /// `old_loop(..)` expressions in the loop invariant.
+    loop_entry_labels: HashMap<BasicBlockIndex, String>,
+    /// For each loop head, the variable counting the iterations of the loop, as used by
+    /// `loop_iterations()` expressions in the loop invariant.
+    loop_iteration_counters: HashMap<BasicBlockIndex, vir::LocalVar>,
+    /// The `unsafe` blocks that are trusted, if the procedure has the `#[trusted_unsafe]`
+    /// attribute.
+    unsafe_regions: Option<UnsafeRegions<'tcx>>,
+    /// The number of iterations to which loops are unrolled, if the procedure is only
+    /// verified for bounded executions with `#[bounded(k)]` (see `encode_bounded_loop`).
+    loop_bound: Option<u32>,
 }
 
 impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
@@ -123,6 +149,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         let mir_encoder = MirEncoder::new(encoder, mir, def_id);
         let init_info = InitInfo::new(mir, tcx, def_id, &mir_encoder)
             .with_span(procedure.get_span())?;
+        let panic_free = config::panic_free()
+            || encoder.env().has_prusti_attribute(def_id, "panic_free");
+        let unsafe_regions = if encoder.env().has_prusti_attribute(def_id, "trusted_unsafe") {
+            Some(UnsafeRegions::new(mir))
+        } else {
+            None
+        };
+        let loop_bound = encoder.get_loop_bound(def_id);
 
         let cfg_method = vir::CfgMethod::new(
             // method name
@@ -147,7 +181,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             loop_encoder: LoopEncoder::new(procedure, tcx),
             auxiliary_local_vars: HashMap::new(),
             mir_encoder,
-            check_panics: config::check_panics(),
+            check_panics: config::check_panics() || panic_free,
+            panic_free,
             check_foldunfold_state: config::check_foldunfold_state(),
             polonius_info: None,
             procedure_contract: None,
@@ -162,6 +197,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             old_to_ghost_var: HashMap::new(),
             old_ghost_vars: HashMap::new(),
             cached_loop_invariant_block: HashMap::new(),
+            loop_entry_labels: HashMap::new(),
+            loop_iteration_counters: HashMap::new(),
+            unsafe_regions,
+            loop_bound,
         })
     }
 
@@ -221,6 +260,79 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
     }
 
+    fn translate_permission_forest_error(
+        &self,
+        error: PermissionForestError<'tcx>,
+        loop_head: BasicBlockIndex,
+    ) -> SpannedEncodingError {
+        match error {
+            PermissionForestError::WriteToSharedBorrowedPlace { place, borrowed_place } => {
+                SpannedEncodingError::internal(
+                    format!(
+                        "the loop writes to {:?}, which overlaps with the shared-borrowed place {:?}",
+                        place,
+                        borrowed_place,
+                    ),
+                    self.get_loop_span(loop_head),
+                )
+            }
+            PermissionForestError::UnexpectedBorrowedChild { borrowed_place, place } => {
+                SpannedEncodingError::internal(
+                    format!(
+                        "the place {:?} is not reachable from the borrowed place {:?}",
+                        place,
+                        borrowed_place,
+                    ),
+                    self.get_loop_span(loop_head),
+                )
+            }
+        }
+    }
+
+    /// Translates an error of the fold-unfold algorithm. If it failed to obtain a permission of
+    /// a local variable whose value may have been moved out, the error points at the moves.
+    fn translate_fold_unfold_error(
+        &self,
+        foldunfold_error: foldunfold::FoldUnfoldError,
+        span: Span,
+    ) -> SpannedEncodingError {
+        let base_local = match foldunfold_error {
+            foldunfold::FoldUnfoldError::FailedToObtain(ref perm) => {
+                let base = perm.get_place().get_base();
+                base.name.strip_prefix('_')
+                    .and_then(|index| index.parse::<usize>().ok())
+                    .filter(|&index| index < self.mir.local_decls.len())
+                    .map(mir::Local::from_usize)
+            }
+            _ => None,
+        };
+        let error = SpannedEncodingError::internal(
+            format!(
+                "generating fold-unfold Viper statements failed ({:?})",
+                foldunfold_error
+            ),
+            span,
+        );
+        if let Some(local) = base_local {
+            let place: mir::Place<'tcx> = local.into();
+            let tcx = self.encoder.env().tcx();
+            let reaching_moves = compute_reaching_moves(self.mir, tcx, &[place]);
+            let mut move_spans: Vec<Span> = self.mir.basic_blocks().indices()
+                .flat_map(|bb| reaching_moves.get_moves_before_terminator(bb, &place))
+                .map(|location| self.mir.source_info(location).span)
+                .collect();
+            move_spans.sort();
+            move_spans.dedup();
+            if !move_spans.is_empty() {
+                return error.set_note(
+                    "value may have been moved here",
+                    MultiSpan::from_spans(move_spans),
+                );
+            }
+        }
+        error
+    }
+
     fn polonius_info(&self) -> &PoloniusInfo<'p, 'tcx> {
         self.polonius_info.as_ref().unwrap()
     }
@@ -237,6 +349,16 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         trace!("Encode procedure {}", self.cfg_method.name());
         let mir_span = self.mir.span;
 
+        // Only the private functions of the module that defines a type may break its invariant
+        if self.encoder.env().has_prusti_attribute(self.proc_def_id, "no_invariant")
+            && self.encoder.env().tcx().visibility(self.proc_def_id) == ty::Visibility::Public
+        {
+            return Err(SpannedEncodingError::incorrect(
+                "the `#[no_invariant]` attribute can only be attached to private functions",
+                self.encoder.env().get_item_span(self.proc_def_id),
+            ));
+        }
+
         // Retrieve the contract
         self.procedure_contract = Some(
             self.encoder
@@ -244,91 +366,39 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 .with_span(mir_span)?
         );
 
-        // Prepare assertions to check specification refinement
-        let mut precondition_weakening: Option<typed::Assertion> = None;
-        let mut postcondition_strengthening: Option<typed::Assertion> = None;
-        debug!("procedure_contract: {:?}", self.procedure_contract());
-        //trace!("def_id of proc: {:?}", &self.proc_def_id);
-        let impl_def_id = self.encoder.env().tcx().impl_of_method(self.proc_def_id);
-    //     //trace!("def_id of impl: {:?}", &impl_def_id);
-        if let Some(id) = impl_def_id {
-            let def_id_trait = self.encoder.env().tcx().trait_id_of_impl(id);
-            trace!("def_id of trait: {:?}", &def_id_trait);
-            // Trait implementation method refinement
-            // Choosing alternative C as discussed in
-            // https://ethz.ch/content/dam/ethz/special-interest/infk/chair-program-method/pm/documents/Education/Theses/Matthias_Erdin_MA_report.pdf
-            // pp 19-23
-            if let Some(id) = def_id_trait {
-                let proc_name = self
-                    .encoder
-                    .env()
-                    .tcx()
-                    .item_name(self.proc_def_id);
-                    // .as_symbol();
-                if let Some(assoc_item) = self.encoder.env().get_assoc_item(id, proc_name) {
-                    // TODO use the impl's specs if there are any (separately replace pre/post!)
-                    let procedure_trait_contract = self
-                        .encoder
-                        .get_procedure_contract_for_def(assoc_item.def_id)
-                        .with_span(mir_span)?;
-                    let typed::ProcedureSpecification {
-                        pres: proc_pre_specs,
-                        posts: proc_post_specs,
-                        pledges: proc_pledge_specs,
-                        ..
-                    } = self.mut_contract().specification.expect_mut_procedure();
+        // The contract of a `Drop::drop` method is applied at the implicit drops of the type
+        let tcx = self.encoder.env().tcx();
+        let is_drop_impl = tcx.impl_of_method(self.proc_def_id)
+            .and_then(|impl_def_id| tcx.trait_id_of_impl(impl_def_id))
+            .map_or(false, |trait_def_id| Some(trait_def_id) == tcx.lang_items().drop_trait());
+        if is_drop_impl {
+            self.check_drop_contract(self.procedure_contract())?;
+        }
 
-                    if proc_pre_specs.is_empty() {
-                        proc_pre_specs
-                            .extend_from_slice(procedure_trait_contract.functional_precondition())
-                    } else {
-                        let proc_pre = typed::Assertion {
-                            kind: box typed::AssertionKind::And(
-                                proc_pre_specs.clone()
-                            ),
-                        };
-                        let proc_trait_pre = typed::Assertion {
-                            kind: box typed::AssertionKind::And(
-                                procedure_trait_contract
-                                    .functional_precondition()
-                                    .iter()
-                                    .cloned()
-                                    .collect(),
-                            ),
-                        };
-                        precondition_weakening = Some(typed::Assertion {
-                            kind: box typed::AssertionKind::Implies(proc_trait_pre, proc_pre),
-                        });
-                    }
+        // Inherit the specification of the implemented trait method, if the method does not
+        // have its own. Otherwise, the refinement is checked by `encode_refinement_check`.
+        debug!("procedure_contract: {:?}", self.procedure_contract());
+        if let Some(trait_method_def_id) = self.encoder.env().find_trait_method(self.proc_def_id) {
+            let procedure_trait_contract = self
+                .encoder
+                .get_procedure_contract_for_def(trait_method_def_id)
+                .with_span(mir_span)?;
+            let typed::ProcedureSpecification {
+                pres: proc_pre_specs,
+                posts: proc_post_specs,
+                pledges: proc_pledge_specs,
+                ..
+            } = self.mut_contract().specification.expect_mut_procedure();
 
-                    if proc_post_specs.is_empty() && proc_pledge_specs.is_empty() {
-                        proc_post_specs
-                            .extend_from_slice(procedure_trait_contract.functional_postcondition());
-                        proc_pledge_specs
-                            .extend_from_slice(procedure_trait_contract.pledges());
-                    } else {
-                        if !proc_pledge_specs.is_empty() {
-                            unimplemented!("Refining specifications with pledges is not supported");
-                        }
-                        let proc_post = typed::Assertion {
-                            kind: box typed::AssertionKind::And(
-                                proc_post_specs.clone()
-                            ),
-                        };
-                        let proc_trait_post = typed::Assertion {
-                            kind: box typed::AssertionKind::And(
-                                procedure_trait_contract
-                                    .functional_postcondition()
-                                    .iter()
-                                    .cloned()
-                                    .collect(),
-                            ),
-                        };
-                        postcondition_strengthening = Some(typed::Assertion {
-                            kind: box typed::AssertionKind::Implies(proc_post, proc_trait_post),
-                        });
-                    }
-                }
+            if proc_pre_specs.is_empty() {
+                proc_pre_specs
+                    .extend_from_slice(procedure_trait_contract.functional_precondition())
+            }
+            if proc_post_specs.is_empty() && proc_pledge_specs.is_empty() {
+                proc_post_specs
+                    .extend_from_slice(procedure_trait_contract.functional_postcondition());
+                proc_pledge_specs
+                    .extend_from_slice(procedure_trait_contract.pledges());
             }
         }
 
@@ -350,7 +420,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         return Err(SpannedEncodingError::incorrect(
                             "the loop invariant cannot be in a conditional branch of the loop",
                             self.get_loop_span(loop_head),
-                        ));
+                        ).set_help(LOOP_INVARIANT_PLACEMENT_HELP));
                     }
                     Ok(loop_inv_bbi) => {
                         self.cached_loop_invariant_block.insert(bbi, loop_inv_bbi);
@@ -358,6 +428,26 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 }
             }
         }
+        self.check_loop_invariant_placement()?;
+
+        // Each `label!(..)` statement has to name a different program point
+        let mut ghost_label_names = HashSet::new();
+        for (label, _, span) in self.procedure.get_ghost_labels() {
+            if !ghost_label_names.insert(label.clone()) {
+                return Err(SpannedEncodingError::incorrect(
+                    format!("the label `{}` is defined more than once", label),
+                    span,
+                ));
+            }
+        }
+
+        // The value of ghost variables must not flow into the real code
+        if let Some(span) = self.procedure.find_ghost_variable_use() {
+            return Err(SpannedEncodingError::incorrect(
+                "ghost variables can only be used in ghost code and in specifications",
+                span,
+            ));
+        }
 
         // Load Polonius info
         self.polonius_info = Some(
@@ -428,10 +518,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         );
 
         // Encode preconditions
-        self.encode_preconditions(start_cfg_block, precondition_weakening)?;
+        self.encode_preconditions(start_cfg_block)?;
 
         // Encode postcondition
-        self.encode_postconditions(return_cfg_block, postcondition_strengthening)?;
+        self.encode_postconditions(return_cfg_block)?;
 
         let local_vars: Vec<_> = self
             .locals
@@ -484,19 +574,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             &self.cfg_blocks_map,
             method_pos,
         )
-        .map_err(|foldunfold_error| {
-            SpannedEncodingError::internal(
-                format!(
-                    "generating fold-unfold Viper statements failed ({:?})",
-                    foldunfold_error
-                ),
-                mir_span,
-            )
-        })?;
+        .map_err(|foldunfold_error| self.translate_fold_unfold_error(foldunfold_error, mir_span))?;
 
         // Fix variable declarations.
         let final_method = fix_ghost_vars(method_with_fold_unfold);
 
+        // Fix the triggers that the backend does not support.
+        let final_method = fix_triggers(final_method, self.encoder.backend_capabilities());
+
         // Dump final CFG
         if config::dump_debug_info() {
             prusti_common::report::log::report_with_writer(
@@ -509,6 +594,288 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(final_method)
     }
 
+    /// Encode a method that checks that the specification of a method implementing a trait
+    /// method refines the specification of the trait method. That is, the precondition of
+    /// the trait method must imply the precondition of the implementation, and the
+    /// postcondition of the implementation must imply the postcondition of the trait method.
+    ///
+    /// Returns `None` if the procedure does not implement a trait method or if it inherits
+    /// the whole specification of the trait method.
+    pub fn encode_refinement_check(mut self) -> SpannedEncodingResult<Option<vir::CfgMethod>> {
+        let mir_span = self.mir.span;
+        let trait_method_def_id = match self.encoder.env().find_trait_method(self.proc_def_id) {
+            Some(def_id) => def_id,
+            None => return Ok(None),
+        };
+        let contract = self.encoder
+            .get_procedure_contract_for_def(self.proc_def_id)
+            .with_span(mir_span)?;
+        let trait_contract = self.encoder
+            .get_procedure_contract_for_def(trait_method_def_id)
+            .with_span(mir_span)?;
+        // The contract of the implementation contains the inherited parts of the specification
+        // of the trait method, which do not need to be checked
+        let impl_spec = self.encoder.get_procedure_specs(self.proc_def_id)
+            .unwrap_or_else(typed::ProcedureSpecification::empty);
+        let check_pre = !impl_spec.pres.is_empty();
+        let check_post = !impl_spec.posts.is_empty() || !impl_spec.pledges.is_empty();
+        if !check_pre && !check_post {
+            return Ok(None);
+        }
+        if check_post && !(contract.pledges().is_empty() && trait_contract.pledges().is_empty()) {
+            return Err(SpannedEncodingError::unsupported(
+                "refining specifications with pledges is not supported",
+                mir_span,
+            ));
+        }
+        trace!("Encode refinement check for procedure {}", self.cfg_method.name());
+
+        // The specification of the trait method, stated on the arguments of the implementation
+        let mut trait_spec_contract = contract.clone();
+        trait_spec_contract.specification = trait_contract.specification.clone();
+        let tcx = self.encoder.env().tcx();
+        let get_spans = |assertions: &[typed::Assertion<'tcx>], def_id: ProcedureDefId| {
+            let spans: Vec<_> = assertions
+                .iter()
+                .flat_map(|assertion| typed::Spanned::get_spans(assertion, &self.mir, tcx))
+                .collect();
+            if spans.is_empty() {
+                MultiSpan::from_span(tcx.def_span(def_id))
+            } else {
+                MultiSpan::from_spans(spans)
+            }
+        };
+        let impl_pre_span = get_spans(contract.functional_precondition(), self.proc_def_id);
+        let trait_pre_span = get_spans(trait_contract.functional_precondition(), trait_method_def_id);
+        let impl_post_span = get_spans(contract.functional_postcondition(), self.proc_def_id);
+        let trait_post_span = get_spans(trait_contract.functional_postcondition(), trait_method_def_id);
+        self.procedure_contract = Some(contract);
+
+        self.cfg_method = vir::CfgMethod::new(
+            // method name
+            format!("{}$refinement", self.encoder.encode_item_name(self.proc_def_id)),
+            // formal args
+            self.mir.arg_count,
+            // formal returns
+            vec![],
+            // local vars
+            vec![],
+            // reserved labels
+            vec![],
+        );
+        let contract = self.procedure_contract().clone();
+        let encoded_return = self.encode_prusti_local(contract.returned_value);
+        self.cfg_method.add_formal_return(&encoded_return.name, encoded_return.typ);
+        for &arg in &contract.args {
+            let encoded_arg = self.encode_prusti_local(arg);
+            self.cfg_method.add_local_var(&encoded_arg.name, encoded_arg.typ);
+        }
+
+        let const_params_spec = self.encode_const_params();
+
+        let mut stmts = vec![
+            vir::Stmt::comment("========== refinement check =========="),
+            vir::Stmt::comment(format!("Def path: {:?}", self.procedure.get_def_path())),
+            vir::Stmt::comment(format!("Span: {:?}", self.procedure.get_span())),
+            vir::Stmt::Inhale(const_params_spec),
+        ];
+
+        // Assume the precondition of the trait method, check the precondition of the
+        // implementation
+        let (type_spec, mandatory_type_spec, invs_spec, func_spec) =
+            self.encode_precondition_expr(&contract)?;
+        let (_, _, _, trait_func_spec) = self.encode_precondition_expr(&trait_spec_contract)?;
+        stmts.push(vir::Stmt::Inhale(type_spec.clone()));
+        stmts.push(vir::Stmt::Inhale(mandatory_type_spec.into_iter().conjoin()));
+        stmts.push(vir::Stmt::Inhale(invs_spec));
+        stmts.push(vir::Stmt::Inhale(trait_func_spec));
+        if check_pre {
+            let pos = self.encoder.error_manager().register(
+                impl_pre_span,
+                ErrorCtxt::AssertMethodPreconditionWeakening(trait_pre_span),
+            );
+            stmts.push(vir::Stmt::Assert(func_spec.clone(), pos));
+        }
+        stmts.push(vir::Stmt::Inhale(func_spec));
+        stmts.push(vir::Stmt::Label(PRECONDITION_LABEL.to_string()));
+
+        // Assume the postcondition of the implementation, check the postcondition of the
+        // trait method
+        if check_post {
+            let post_label = self.cfg_method.get_fresh_label_name();
+            let (
+                post_type_spec,
+                return_type_spec,
+                post_invs_spec,
+                post_func_spec,
+                _,
+                read_transfer,
+            ) = self.encode_postcondition_expr(
+                None,
+                &contract,
+                PRECONDITION_LABEL,
+                &post_label,
+                None,
+                false,
+                None,
+                false,
+            )?;
+            let (_, _, _, trait_post_func_spec, _, _) = self.encode_postcondition_expr(
+                None,
+                &trait_spec_contract,
+                PRECONDITION_LABEL,
+                &post_label,
+                None,
+                false,
+                None,
+                false,
+            )?;
+            let exhale_pos = self.encoder.error_manager()
+                .register(mir_span, ErrorCtxt::Unexpected);
+            stmts.push(vir::Stmt::Exhale(type_spec.remove_read_permissions(), exhale_pos));
+            stmts.push(vir::Stmt::Inhale(post_type_spec.remove_read_permissions()));
+            if let Some(access) = return_type_spec {
+                stmts.push(vir::Stmt::Inhale(access));
+            }
+            for (from_place, to_place) in read_transfer {
+                stmts.push(vir::Stmt::TransferPerm(from_place, to_place, true));
+            }
+            stmts.push(vir::Stmt::Inhale(post_invs_spec));
+            stmts.push(vir::Stmt::Inhale(post_func_spec));
+            stmts.push(vir::Stmt::Label(post_label));
+            let pos = self.encoder.error_manager().register(
+                trait_post_span,
+                ErrorCtxt::AssertMethodPostconditionStrengthening(impl_post_span),
+            );
+            stmts.push(vir::Stmt::Assert(trait_post_func_spec, pos));
+        }
+
+        let check_cfg_block = self.cfg_method.add_block("check", stmts);
+        self.cfg_method.set_successor(check_cfg_block, Successor::Return);
+
+        self.encoder
+            .log_vir_program_before_foldunfold(self.cfg_method.to_string());
+
+        // Patch snapshots
+        self.cfg_method = self.encoder.patch_snapshots_method(self.cfg_method)
+            .with_span(mir_span)?;
+
+        // Add fold/unfold
+        let method_pos = self
+            .encoder
+            .error_manager()
+            .register(mir_span, ErrorCtxt::Unexpected);
+        let method_with_fold_unfold = foldunfold::add_fold_unfold(
+            self.encoder,
+            self.cfg_method,
+            &HashMap::new(),
+            &HashMap::new(),
+            method_pos,
+        )
+        .map_err(|foldunfold_error| {
+            SpannedEncodingError::internal(
+                format!(
+                    "generating fold-unfold Viper statements failed ({:?})",
+                    foldunfold_error
+                ),
+                mir_span,
+            )
+        })?;
+
+        Ok(Some(fix_triggers(
+            fix_ghost_vars(method_with_fold_unfold),
+            self.encoder.backend_capabilities(),
+        )))
+    }
+
+    /// Encodes a method that assumes the precondition of the procedure and asserts `false`,
+    /// without encoding its body. If the method verifies, the precondition is unsatisfiable.
+    /// Used when only checking the specifications (see `config::check_specs_only`).
+    pub fn encode_precondition_check(mut self) -> SpannedEncodingResult<vir::CfgMethod> {
+        let mir_span = self.mir.span;
+        let contract = self.encoder
+            .get_procedure_contract_for_def(self.proc_def_id)
+            .with_span(mir_span)?;
+        trace!("Encode precondition check for procedure {}", self.cfg_method.name());
+        self.procedure_contract = Some(contract);
+
+        self.cfg_method = vir::CfgMethod::new(
+            // method name
+            format!("{}$precondition_check", self.encoder.encode_item_name(self.proc_def_id)),
+            // formal args
+            self.mir.arg_count,
+            // formal returns
+            vec![],
+            // local vars
+            vec![],
+            // reserved labels
+            vec![],
+        );
+        let contract = self.procedure_contract().clone();
+        let encoded_return = self.encode_prusti_local(contract.returned_value);
+        self.cfg_method.add_formal_return(&encoded_return.name, encoded_return.typ);
+        for &arg in &contract.args {
+            let encoded_arg = self.encode_prusti_local(arg);
+            self.cfg_method.add_local_var(&encoded_arg.name, encoded_arg.typ);
+        }
+
+        let const_params_spec = self.encode_const_params();
+
+        let (type_spec, mandatory_type_spec, invs_spec, func_spec) =
+            self.encode_precondition_expr(&contract)?;
+        let pos = self.encoder.error_manager().register(
+            self.encoder.env().get_item_span(self.proc_def_id),
+            ErrorCtxt::AssertPreconditionUnsatisfiable,
+        );
+        let stmts = vec![
+            vir::Stmt::comment("========== precondition check =========="),
+            vir::Stmt::comment(format!("Def path: {:?}", self.procedure.get_def_path())),
+            vir::Stmt::comment(format!("Span: {:?}", self.procedure.get_span())),
+            vir::Stmt::Inhale(const_params_spec),
+            vir::Stmt::Inhale(type_spec),
+            vir::Stmt::Inhale(mandatory_type_spec.into_iter().conjoin()),
+            vir::Stmt::Inhale(invs_spec),
+            vir::Stmt::Inhale(func_spec),
+            vir::Stmt::Assert(false.into(), pos),
+        ];
+        let check_cfg_block = self.cfg_method.add_block("check", stmts);
+        self.cfg_method.set_successor(check_cfg_block, Successor::Return);
+
+        self.encoder
+            .log_vir_program_before_foldunfold(self.cfg_method.to_string());
+
+        // Patch snapshots
+        self.cfg_method = self.encoder.patch_snapshots_method(self.cfg_method)
+            .with_span(mir_span)?;
+
+        // Add fold/unfold
+        let method_pos = self
+            .encoder
+            .error_manager()
+            .register(mir_span, ErrorCtxt::Unexpected);
+        let method_with_fold_unfold = foldunfold::add_fold_unfold(
+            self.encoder,
+            self.cfg_method,
+            &HashMap::new(),
+            &HashMap::new(),
+            method_pos,
+        )
+        .map_err(|foldunfold_error| {
+            SpannedEncodingError::internal(
+                format!(
+                    "generating fold-unfold Viper statements failed ({:?})",
+                    foldunfold_error
+                ),
+                mir_span,
+            )
+        })?;
+
+        Ok(fix_triggers(
+            fix_ghost_vars(method_with_fold_unfold),
+            self.encoder.backend_capabilities(),
+        ))
+    }
+
     /// Encodes a topologically ordered group of blocks.
     ///
     /// Returns:
@@ -520,6 +887,26 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         ordered_group_blocks: &[BasicBlockIndex],
         group_loop_depth: usize,
         return_block: CfgBlockIndex,
+    ) -> SpannedEncodingResult<(Option<CfgBlockIndex>, Vec<(CfgBlockIndex, BasicBlockIndex)>)> {
+        self.encode_blocks_group_with_back_edges(
+            label_prefix,
+            ordered_group_blocks,
+            group_loop_depth,
+            return_block,
+            None,
+        )
+    }
+
+    /// Like `encode_blocks_group`, but the edges to `opt_loop_head` are left unresolved
+    /// even if the loop head is in the group. This is used to encode one iteration of
+    /// an unrolled loop, whose back edges lead to the next iteration.
+    fn encode_blocks_group_with_back_edges(
+        &mut self,
+        label_prefix: &str,
+        ordered_group_blocks: &[BasicBlockIndex],
+        group_loop_depth: usize,
+        return_block: CfgBlockIndex,
+        opt_loop_head: Option<BasicBlockIndex>,
     ) -> SpannedEncodingResult<(Option<CfgBlockIndex>, Vec<(CfgBlockIndex, BasicBlockIndex)>)> {
         // Encode the CFG blocks
         let mut bb_map: HashMap<_, _> = HashMap::new();
@@ -560,8 +947,13 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             );
             bb_map[bb]
         });
-        let still_unresolved_edges =
-            self.encode_unresolved_edges(unresolved_edges, |bb| bb_map.get(&bb).cloned())?;
+        let still_unresolved_edges = self.encode_unresolved_edges(unresolved_edges, |bb| {
+            if Some(bb) == opt_loop_head {
+                None
+            } else {
+                bb_map.get(&bb).cloned()
+            }
+        })?;
         Ok((group_head, still_unresolved_edges))
     }
 
@@ -616,6 +1008,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         loop_head: BasicBlockIndex,
         return_block: CfgBlockIndex,
     ) -> SpannedEncodingResult<(CfgBlockIndex, Vec<(CfgBlockIndex, BasicBlockIndex)>)> {
+        if let Some(loop_bound) = self.loop_bound {
+            return self.encode_bounded_loop(label_prefix, loop_head, return_block, loop_bound);
+        }
         let loop_info = self.loop_encoder.loops();
         debug_assert!(loop_info.is_loop_head(loop_head));
         trace!("encode_loop: {:?}", loop_head);
@@ -633,36 +1028,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             .collect();
 
         // Identify important blocks
-        let loop_exit_blocks = loop_info.get_loop_exit_blocks(loop_head);
-        let loop_exit_blocks_set: HashSet<_> = loop_exit_blocks.iter().cloned().collect();
         let before_invariant_block: BasicBlockIndex = self.cached_loop_invariant_block[&loop_head];
-        let before_inv_block_pos = loop_body
-            .iter()
-            .position(|&bb| bb == before_invariant_block)
-            .unwrap();
-        let after_inv_block_pos = 1 + before_inv_block_pos;
-        let exit_blocks_before_inv: Vec<_> = loop_body[0..after_inv_block_pos]
-            .iter()
-            .filter(|&bb| loop_exit_blocks_set.contains(bb))
-            .cloned()
-            .collect();
-        // HEURISTIC: pick the last exit block before the invariant.
-        // An infinite loop will have no exit blocks, so we have to use an Option here
-        let opt_loop_guard_switch = exit_blocks_before_inv.last().cloned();
-        let after_guard_block_pos = opt_loop_guard_switch
-            .and_then(|loop_guard_switch| {
-                loop_body
-                    .iter()
-                    .position(|&bb| bb == loop_guard_switch)
-                    .map(|x| x + 1)
-            })
-            .unwrap_or(0);
+        let after_inv_block_pos = self.get_after_invariant_block_pos(loop_head, &loop_body);
+        let after_guard_block_pos = self.get_after_guard_block_pos(loop_head, &loop_body);
         let after_guard_block = loop_body[after_guard_block_pos];
         let after_inv_block = loop_body[after_inv_block_pos];
 
         debug!("loop_head: {:?}", loop_head);
         debug!("loop_body: {:?}", loop_body);
-        debug!("opt_loop_guard_switch: {:?}", opt_loop_guard_switch);
         debug!("before_invariant_block: {:?}", before_invariant_block);
         debug!("after_guard_block: {:?}", after_guard_block);
         debug!("after_inv_block: {:?}", after_inv_block);
@@ -671,16 +1044,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 "{:?} is conditional branch in loop {:?}",
                 before_invariant_block, loop_head
             );
-            let loop_head_span = self.mir_encoder.get_span_of_basic_block(loop_head);
             return Err(SpannedEncodingError::incorrect(
                 "the loop invariant cannot be in a conditional branch of the loop",
-                loop_body
-                    .iter()
-                    .map(|&bb| self.mir_encoder.get_span_of_basic_block(bb))
-                    .filter(|&span| span.contains(loop_head_span))
-                    .min()
-                    .unwrap(),
-            ));
+                self.get_loop_span(loop_head, &loop_body),
+            ).set_help(LOOP_INVARIANT_PLACEMENT_HELP));
+        }
+
+        if self.get_loop_specifications(loop_head).is_empty() {
+            self.warn_loop_without_invariant(loop_head, before_invariant_block, &loop_body);
         }
 
         // Split the blocks such that:
@@ -696,6 +1067,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         let mut heads = vec![];
 
         // Build the "start" CFG block (*start* - G - B1 - invariant - B2 - G - B1 - end)
+        // The label marks the state before the loop, which is used by `old_loop(..)` expressions.
+        let loop_entry_label = format!("{}_entry", loop_label_prefix);
+        self.loop_entry_labels.insert(loop_head, loop_entry_label.clone());
+        // The counter of `loop_iterations()` expressions, declared only if the invariant uses it
+        let iteration_counter = vir::LocalVar::new(
+            format!("{}_iterations", loop_label_prefix),
+            vir::Type::Int,
+        );
+        self.loop_iteration_counters.insert(loop_head, iteration_counter.clone());
         let start_block = self.cfg_method.add_block(
             &format!("{}_start", loop_label_prefix),
             vec![vir::Stmt::comment(format!(
@@ -703,6 +1083,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 loop_label_prefix
             ))],
         );
+        self.cfg_method.add_stmt(start_block, vir::Stmt::Label(loop_entry_label));
         heads.push(Some(start_block));
 
         // Encode the first G group (start - *G* - B1 - invariant - B2 - G - B1 - end)
@@ -743,11 +1124,32 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         heads.push(Some(inv_pre_block));
         self.cfg_method
             .set_successor(inv_pre_block, vir::Successor::Goto(inv_post_block));
+        let counts_iterations;
         {
             let stmts =
                 self.encode_loop_invariant_exhale_stmts(loop_head, before_invariant_block, false)?;
+            counts_iterations = stmts_mention_local_var(&stmts, &iteration_counter.name);
             self.cfg_method.add_stmts(inv_pre_block, stmts);
         }
+        // The counter is zero when the loop is entered, and it is havocked with the other
+        // variables assigned in the loop.
+        if counts_iterations {
+            self.cfg_method.add_local_var(&iteration_counter.name, vir::Type::Int);
+            self.cfg_method.add_stmt(
+                start_block,
+                vir::Stmt::Assign(
+                    iteration_counter.clone().into(),
+                    0.into(),
+                    vir::AssignKind::Copy,
+                ),
+            );
+            self.cfg_method.add_stmt(
+                inv_post_block,
+                vir::Stmt::Inhale(
+                    vir::Expr::ge_cmp(iteration_counter.clone().into(), 0.into()),
+                ),
+            );
+        }
         // We'll add later more statements at the end of inv_pre_block, to havoc local variables
         {
             let stmts =
@@ -755,6 +1157,25 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             self.cfg_method.add_stmts(inv_post_block, stmts);
         }
 
+        // The termination measure of the loop has to decrease in each iteration. Loops in
+        // procedures that have a termination measure need to have one as well.
+        let loop_measure = self.encode_loop_measure(loop_head, before_invariant_block)?;
+        if loop_measure.is_none() && self.procedure_contract().decreases().is_some() {
+            let loop_head_span = self.mir_encoder.get_span_of_basic_block(loop_head);
+            return Err(SpannedEncodingError::incorrect(
+                "loops in a function with a termination measure need a \
+                `body_invariant!(decreases(..))` termination measure",
+                loop_head_span,
+            ));
+        }
+        let loop_iteration_label = format!("{}_iteration", loop_label_prefix);
+        if loop_measure.is_some() {
+            self.cfg_method.add_stmt(
+                inv_post_block,
+                vir::Stmt::Label(loop_iteration_label.clone()),
+            );
+        }
+
         // Encode the last B2 group (start - G - B1 - invariant - *B2* - G - B1 - end)
         let (last_b2_head, last_b2_edges) = self.encode_blocks_group(
             &format!("{}_group3_", loop_label_prefix),
@@ -792,6 +1213,31 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 loop_label_prefix
             ))],
         );
+        // Each back edge of the loop completes one more iteration
+        if counts_iterations {
+            self.cfg_method.add_stmt(
+                end_body_block,
+                vir::Stmt::Assign(
+                    iteration_counter.clone().into(),
+                    vir::Expr::add(iteration_counter.clone().into(), 1.into()),
+                    vir::AssignKind::Copy,
+                ),
+            );
+        }
+        if let Some((measure, measure_span)) = loop_measure {
+            let old_measure: Vec<_> = measure
+                .iter()
+                .map(|component| vir::Expr::labelled_old(&loop_iteration_label, component.clone()))
+                .collect();
+            let pos = self
+                .encoder
+                .error_manager()
+                .register(measure_span, ErrorCtxt::AssertLoopTerminationMeasure);
+            self.cfg_method.add_stmt(
+                end_body_block,
+                vir::Stmt::Assert(encode_measure_decrease(&measure, &old_measure), pos),
+            );
+        }
         {
             let stmts = self.encode_loop_invariant_exhale_stmts(
                 loop_head,
@@ -895,6 +1341,219 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok((start_block, still_unresolved_edges))
 }
 
+    /// Encodes a loop of a procedure that is only verified for bounded executions, by
+    /// unrolling it `loop_bound` times. The loop invariants are not used.
+    ///
+    /// Returns:
+    /// * The first CFG block of the encoding
+    /// * A vector of unresolved CFG edges
+    ///
+    /// The encoding transforms
+    /// ```text
+    /// while { g = G; g } { B }
+    /// ```
+    /// into
+    /// ```text
+    /// g = G
+    /// if (g) {
+    ///   B
+    ///   g = G
+    ///   if (g) {
+    ///     // ... `B` is encoded `loop_bound` times in total
+    ///     B
+    ///     g = G
+    ///     if (g) {
+    ///       assume false
+    ///     }
+    ///   }
+    /// }
+    /// ```
+    /// such that the executions that do more iterations are not verified. The guard is
+    /// evaluated once more after the last iteration, so the executions that do exactly
+    /// `loop_bound` iterations are verified.
+    fn encode_bounded_loop(
+        &mut self,
+        label_prefix: &str,
+        loop_head: BasicBlockIndex,
+        return_block: CfgBlockIndex,
+        loop_bound: u32,
+    ) -> SpannedEncodingResult<(CfgBlockIndex, Vec<(CfgBlockIndex, BasicBlockIndex)>)> {
+        let loop_info = self.loop_encoder.loops();
+        debug_assert!(loop_info.is_loop_head(loop_head));
+        trace!("encode_bounded_loop: {:?}", loop_head);
+        let loop_label_prefix = format!("{}loop{}", label_prefix, loop_head.index());
+        let loop_depth = loop_info.get_loop_head_depth(loop_head);
+
+        // The loop head is the first block of the body
+        let loop_body: Vec<BasicBlockIndex> = loop_info
+            .get_loop_body(loop_head)
+            .iter()
+            .filter(
+                |&&bb| self.procedure.is_reachable_block(bb) && !self.procedure.is_spec_block(bb)
+            )
+            .cloned()
+            .collect();
+        debug_assert_eq!(loop_body[0], loop_head);
+
+        let start_block = self.cfg_method.add_block(
+            &format!("{}_start", loop_label_prefix),
+            vec![vir::Stmt::comment(format!(
+                "========== {}_start ==========",
+                loop_label_prefix
+            ))],
+        );
+
+        // Encode the iterations, each one starting where the previous one goes back to
+        // the loop head. The first one starts after the "start" block.
+        let mut still_unresolved_edges = vec![];
+        let mut back_edges = vec![start_block];
+        for iteration in 0..loop_bound {
+            let (iteration_head, iteration_edges) = self.encode_blocks_group_with_back_edges(
+                &format!("{}_unroll{}_", loop_label_prefix, iteration),
+                &loop_body,
+                loop_depth,
+                return_block,
+                Some(loop_head),
+            )?;
+            let iteration_head = iteration_head.unwrap();
+            for &back_edge in &back_edges {
+                self.cfg_method
+                    .set_successor(back_edge, vir::Successor::Goto(iteration_head));
+            }
+            back_edges.clear();
+            for (curr_block, target) in iteration_edges {
+                if target == loop_head {
+                    back_edges.push(curr_block);
+                } else {
+                    still_unresolved_edges.push((curr_block, target));
+                }
+            }
+        }
+
+        // Kill the executions that do more iterations than the bound
+        let bound_block = self.cfg_method.add_block(
+            &format!("{}_bound", loop_label_prefix),
+            vec![
+                vir::Stmt::comment(format!("========== {}_bound ==========", loop_label_prefix)),
+                vir::Stmt::comment(format!("The loop bound {} is exceeded", loop_bound)),
+                vir::Stmt::Inhale(false.into()),
+            ],
+        );
+        self.cfg_method
+            .set_successor(bound_block, vir::Successor::Return);
+
+        // Evaluate the guard once more after the last iteration. The executions that
+        // leave the loop continue after it, the ones that stay in the loop are killed.
+        let after_guard_block_pos = self.get_after_guard_block_pos(loop_head, &loop_body);
+        let (last_guard_head, last_guard_edges) = self.encode_blocks_group_with_back_edges(
+            &format!("{}_unroll{}_", loop_label_prefix, loop_bound),
+            &loop_body[0..after_guard_block_pos],
+            loop_depth,
+            return_block,
+            Some(loop_head),
+        )?;
+        let last_guard_head = last_guard_head.unwrap_or(bound_block);
+        for back_edge in back_edges {
+            self.cfg_method
+                .set_successor(back_edge, vir::Successor::Goto(last_guard_head));
+        }
+        let loop_info = self.loop_encoder.loops();
+        for (curr_block, target) in last_guard_edges {
+            if loop_info.get_loop_body(loop_head).contains(&target) {
+                self.cfg_method
+                    .set_successor(curr_block, vir::Successor::Goto(bound_block));
+            } else {
+                still_unresolved_edges.push((curr_block, target));
+            }
+        }
+
+        Ok((start_block, still_unresolved_edges))
+    }
+
+    /// The position in `loop_body` of the first block after the one at which the loop
+    /// invariant is (or would be) placed.
+    fn get_after_invariant_block_pos(
+        &self,
+        loop_head: BasicBlockIndex,
+        loop_body: &[BasicBlockIndex],
+    ) -> usize {
+        let before_invariant_block = self.cached_loop_invariant_block[&loop_head];
+        1 + loop_body
+            .iter()
+            .position(|&bb| bb == before_invariant_block)
+            .unwrap()
+    }
+
+    /// The position in `loop_body` of the first block after the evaluation of the loop
+    /// guard, i.e. the blocks `loop_body[0..pos]` evaluate the guard.
+    fn get_after_guard_block_pos(
+        &self,
+        loop_head: BasicBlockIndex,
+        loop_body: &[BasicBlockIndex],
+    ) -> usize {
+        let loop_info = self.loop_encoder.loops();
+        let loop_exit_blocks: HashSet<_> = loop_info
+            .get_loop_exit_blocks(loop_head)
+            .iter()
+            .cloned()
+            .collect();
+        let after_inv_block_pos = self.get_after_invariant_block_pos(loop_head, loop_body);
+        // HEURISTIC: pick the last exit block before the invariant.
+        // An infinite loop will have no exit blocks, so the guard can be empty.
+        loop_body[0..after_inv_block_pos]
+            .iter()
+            .rposition(|bb| loop_exit_blocks.contains(bb))
+            .map(|loop_guard_switch_pos| loop_guard_switch_pos + 1)
+            .unwrap_or(0)
+    }
+
+    /// The span of the whole loop statement, i.e. the smallest span of the loop
+    /// body that contains the span of the loop head.
+    fn get_loop_span(&self, loop_head: BasicBlockIndex, loop_body: &[BasicBlockIndex]) -> Span {
+        let loop_head_span = self.mir_encoder.get_span_of_basic_block(loop_head);
+        loop_body
+            .iter()
+            .map(|&bb| self.mir_encoder.get_span_of_basic_block(bb))
+            .filter(|&span| span.contains(loop_head_span))
+            .min()
+            .unwrap()
+    }
+
+    /// Warns about a loop without `body_invariant!(..)` that modifies user
+    /// variables, because nothing is known about their values after the
+    /// havocking at the loop head. The warning can be suppressed with
+    /// `#[allow_spec_warnings(missing_loop_invariant)]`.
+    fn warn_loop_without_invariant(
+        &self,
+        loop_head: BasicBlockIndex,
+        before_invariant_block: BasicBlockIndex,
+        loop_body: &[BasicBlockIndex],
+    ) {
+        let allowed_warnings = utils::read_prusti_attrs(
+            "allow_spec_warning",
+            self.encoder.env().tcx().get_attrs(self.proc_def_id),
+        );
+        if allowed_warnings.iter().any(|warning| warning == "missing_loop_invariant") {
+            return;
+        }
+        let mut var_names = vec![];
+        let havocked_places = self.loop_encoder
+            .compute_havocked_places(loop_head, before_invariant_block);
+        for place in havocked_places {
+            if let Some(name) = self.procedure.get_var_name(place.local) {
+                if !var_names.contains(&name) {
+                    var_names.push(name);
+                }
+            }
+        }
+        if !var_names.is_empty() {
+            self.encoder.warn_loop_without_invariant(
+                self.get_loop_span(loop_head, loop_body),
+                &var_names,
+            );
+        }
+    }
+
     /// Encode a block.
     ///
     /// Returns:
@@ -1037,9 +1696,101 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(opt_mir_successor.unwrap())
     }
 
+    /// Encode a MIR statement or terminator. The ones inside trusted `unsafe` blocks are
+    /// skipped, and the places that they modify are havocked when leaving the block.
+    fn encode_statement_at(
+        &mut self,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<(Vec<vir::Stmt>, Option<MirSuccessor>)> {
+        let (is_trusted, is_exit) = match self.unsafe_regions {
+            Some(ref regions) => (regions.contains(location), regions.is_exit(location)),
+            None => (false, false),
+        };
+        if is_trusted {
+            return self.encode_trusted_statement_at(location);
+        }
+        let mut stmts = if is_exit {
+            self.encode_havoc_of_unsafe_regions(location)?
+        } else {
+            vec![]
+        };
+        let (encoded_stmts, opt_succ) = self.encode_statement_or_unsupported_at(location)?;
+        stmts.extend(encoded_stmts);
+        Ok((stmts, opt_succ))
+    }
+
+    /// Encode a MIR statement or terminator inside a trusted `unsafe` block, which only
+    /// preserves the control flow.
+    fn encode_trusted_statement_at(
+        &mut self,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<(Vec<vir::Stmt>, Option<MirSuccessor>)> {
+        debug!("Skip trusted location {:?}", location);
+        let bb_data = &self.mir[location.block];
+        if location.statement_index < bb_data.statements.len() {
+            let mir_stmt = &bb_data.statements[location.statement_index];
+            let stmts = vec![vir::Stmt::comment(format!("[mir] {:?} (trusted)", mir_stmt))];
+            return Ok((stmts, None));
+        }
+        let term = bb_data.terminator();
+        let successor = match term.kind {
+            TerminatorKind::Goto { target }
+            | TerminatorKind::Drop { target, .. }
+            | TerminatorKind::DropAndReplace { target, .. }
+            | TerminatorKind::Assert { target, .. } => MirSuccessor::Goto(target),
+            TerminatorKind::FalseEdge { real_target, .. }
+            | TerminatorKind::FalseUnwind { real_target, .. } => MirSuccessor::Goto(real_target),
+            TerminatorKind::Call { ref destination, .. } => match destination {
+                Some((_, target)) => MirSuccessor::Goto(*target),
+                None => MirSuccessor::Kill,
+            },
+            TerminatorKind::SwitchInt { .. } => {
+                return Err(SpannedEncodingError::unsupported(
+                    "branching inside of a trusted unsafe block is not supported",
+                    term.source_info.span,
+                ));
+            }
+            _ => return self.encode_statement_or_unsupported_at(location),
+        };
+        let stmts = vec![vir::Stmt::comment(format!("[mir] {:?} (trusted)", term.kind))];
+        Ok((stmts, Some(successor)))
+    }
+
+    /// Havoc the places that may be modified by the trusted `unsafe` blocks, at a location
+    /// right after one of the blocks.
+    fn encode_havoc_of_unsafe_regions(
+        &mut self,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let span = self.mir_encoder.get_span_of_location(location);
+        let write_set = self.unsafe_regions.as_ref().unwrap().write_set().to_vec();
+        let mut stmts = vec![vir::Stmt::comment(
+            "Havoc the places modified by the trusted unsafe blocks"
+        )];
+        for place in &write_set {
+            let (place_encoding, _, _) = self.mir_encoder.encode_place(place).with_span(span)?;
+            let (encoded_place, pre_stmts) = self.postprocess_place_encoding(
+                place_encoding,
+                ArrayAccessKind::Mutable(None, location),
+            ).with_span(span)?;
+            stmts.extend(pre_stmts);
+            if place.projection.is_empty() {
+                stmts.extend(self.encode_havoc_and_allocation(&encoded_place));
+            } else {
+                // exhale and re-inhale to havoc
+                let perm = self.mir_encoder
+                    .encode_place_predicate_permission(encoded_place, vir::PermAmount::Write)
+                    .unwrap();
+                stmts.push(vir!{ exhale [perm] });
+                stmts.push(vir!{ inhale [perm] });
+            }
+        }
+        Ok(stmts)
+    }
+
     /// Encode a MIR statement or terminator, encoding an `assert false` in case
     /// of usage of unsupported features.
-    fn encode_statement_at(
+    fn encode_statement_or_unsupported_at(
         &mut self,
         location: mir::Location,
     ) -> SpannedEncodingResult<(Vec<vir::Stmt>, Option<MirSuccessor>)> {
@@ -1079,6 +1830,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         match stmts_succ_res {
             Ok(stmts_succ) => Ok(stmts_succ),
             Err(err) => {
+                if self.loop_bound.is_some() {
+                    if let EncodingErrorKind::Unsupported(msg) = err.kind() {
+                        let msg = msg.to_string();
+                        if let Some((stmts, opt_succ)) = self.encode_unsupported_as_havoc(location, &msg) {
+                            downcast_stmts.extend(stmts);
+                            return Ok((downcast_stmts, opt_succ));
+                        }
+                    }
+                }
                 let unsupported_msg = match err.kind() {
                     EncodingErrorKind::Unsupported(msg)
                         if config::allow_unreachable_unsupported_code() => {
@@ -1114,6 +1874,48 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
     }
 
+    /// Encode an unsupported assignment or call of a procedure that is only verified for
+    /// bounded executions, by havocking the place that it modifies. Returns `None` if the
+    /// statement is of another kind, or if the place cannot be encoded either.
+    fn encode_unsupported_as_havoc(
+        &mut self,
+        location: mir::Location,
+        unsupported_msg: &str,
+    ) -> Option<(Vec<vir::Stmt>, Option<MirSuccessor>)> {
+        let bb_data = &self.mir[location.block];
+        let (place, opt_succ) = if location.statement_index < bb_data.statements.len() {
+            match bb_data.statements[location.statement_index].kind {
+                mir::StatementKind::Assign(box (place, _)) => (place, None),
+                _ => return None,
+            }
+        } else {
+            match bb_data.terminator().kind {
+                TerminatorKind::Call { destination: Some((place, target)), .. } => {
+                    (place, Some(MirSuccessor::Goto(target)))
+                }
+                _ => return None,
+            }
+        };
+        let (encoded_place, pre_stmts, _, _) = self
+            .encode_place(&place, ArrayAccessKind::Mutable(None, location))
+            .ok()?;
+        let mut stmts = vec![vir::Stmt::comment(format!(
+            "Unsupported feature: {}; havoc {:?} instead (bounded verification)",
+            unsupported_msg, place
+        ))];
+        stmts.extend(pre_stmts);
+        if self.init_info.is_vir_place_accessible(&encoded_place, location) {
+            // exhale and re-inhale to havoc
+            let perm = self.mir_encoder
+                .encode_place_predicate_permission(encoded_place, vir::PermAmount::Write)?;
+            stmts.push(vir!{ exhale [perm] });
+            stmts.push(vir!{ inhale [perm] });
+        } else {
+            stmts.extend(self.encode_havoc_and_allocation(&encoded_place));
+        }
+        Some((stmts, opt_succ))
+    }
+
     /// Note: it's better to call `encode_statement_at` instead of this method.
     fn encode_statement(
         &mut self,
@@ -1136,6 +1938,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             | mir::StatementKind::Coverage(..)
             | mir::StatementKind::Nop => vec![],
 
+            // The shallow borrows of the guards of `match` expressions are only used by
+            // `FakeRead` statements
+            mir::StatementKind::Assign(
+                box (_, mir::Rvalue::Ref(_, mir::BorrowKind::Shallow, _))
+            ) => vec![],
+
             mir::StatementKind::Assign(box (ref lhs, ref rhs)) => {
                 // Array access on the LHS should always be mutable (idx is always calculated
                 // before, and just a separate local variable here)
@@ -1162,7 +1970,19 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     )?
                 }
             }
-            ref x => unimplemented!("{:?}", x),
+            mir::StatementKind::LlvmInlineAsm(..) => {
+                return Err(SpannedEncodingError::unsupported(
+                    "inline assembly is not supported",
+                    span,
+                ));
+            }
+
+            ref x => {
+                return Err(SpannedEncodingError::unsupported(
+                    format!("the MIR statement '{:?}' is not supported", x),
+                    span,
+                ));
+            }
         };
         stmts.extend(encoding_stmts);
         Ok(self.set_stmts_default_pos(stmts, stmt.source_info.span))
@@ -1312,8 +2132,25 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     )).with_span(span);
                 }
             }
+            &mir::Rvalue::AddressOf(..) => {
+                return Err(SpannedEncodingError::unsupported(
+                    "creating raw pointers is not supported",
+                    span,
+                ));
+            }
+
+            &mir::Rvalue::ThreadLocalRef(..) => {
+                return Err(SpannedEncodingError::unsupported(
+                    "accessing thread-local statics is not supported",
+                    span,
+                ));
+            }
+
             ref rhs => {
-                unimplemented!("encoding of '{:?}'", rhs);
+                return Err(SpannedEncodingError::unsupported(
+                    format!("the MIR rvalue '{:?}' is not supported", rhs),
+                    span,
+                ));
             }
         })
     }
@@ -1391,8 +2228,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             mir::Rvalue::Ref(_, mir_borrow_kind, ref rhs_place) => {
                 let is_mut = match mir_borrow_kind {
                     mir::BorrowKind::Shared => false,
-                    mir::BorrowKind::Shallow => unimplemented!(),
-                    mir::BorrowKind::Unique => unimplemented!(),
+                    mir::BorrowKind::Shallow | mir::BorrowKind::Unique => {
+                        return Err(EncodingError::unsupported(format!(
+                            "{:?} borrows are not supported",
+                            mir_borrow_kind,
+                        )));
+                    }
                     mir::BorrowKind::Mut { .. } => true,
                 };
                 let array_encode_kind = if is_mut { ArrayAccessKind::Mutable(None, loan_places.location) } else { ArrayAccessKind::Shared };
@@ -1737,6 +2578,25 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             }
         }
 
+        // The `before_expiry(..)` expressions of the pledge are evaluated in the
+        // state in which the reference expires. Outside of a package statement,
+        // label that state, so that arbitrary pure expressions (e.g. calls of
+        // pure functions on the reference) can be evaluated in it, as any other
+        // old expression.
+        let rhs = if is_in_package_stmt {
+            rhs
+        } else {
+            let expiry_label = self.cfg_method.get_fresh_label_name();
+            stmts.push(vir::Stmt::Label(expiry_label.clone()));
+            rhs.map_labels(|label| {
+                if label == WAND_LHS_LABEL {
+                    Some(expiry_label.clone())
+                } else {
+                    Some(label)
+                }
+            })
+        };
+
         let pos = self.encoder.error_manager().register(
             //self.mir.span,
             // TODO change to where the loan expires?
@@ -1784,6 +2644,19 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             zombie_loans
         );
         let mut stmts: Vec<vir::Stmt> = vec![];
+        // The loans created inside trusted `unsafe` blocks have not been encoded
+        let (loans, zombie_loans) = if let Some(ref regions) = self.unsafe_regions {
+            let polonius_info = self.polonius_info();
+            let is_encoded = |loan: &facts::Loan| {
+                !regions.contains(polonius_info.get_loan_location(loan))
+            };
+            (
+                loans.into_iter().filter(|loan| is_encoded(loan)).collect(),
+                zombie_loans.iter().cloned().filter(|loan| is_encoded(loan)).collect(),
+            )
+        } else {
+            (loans, zombie_loans.to_vec())
+        };
         if loans.len() > 0 {
             let vir_reborrowing_dag =
                 self.construct_vir_reborrowing_dag(&loans, &zombie_loans, location, end_location, is_in_package_stmt)?;
@@ -1907,7 +2780,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
                         ref x => unreachable!("{:?}", x),
                     };
-                    cfg_targets.push((viper_guard, target))
+                    // Or-patterns branch to the same block for several values
+                    if let Some(cfg_target) = cfg_targets.iter_mut().find(|(_, t)| *t == target) {
+                        cfg_target.0 = vir::Expr::or(cfg_target.0.clone(), viper_guard);
+                    } else {
+                        cfg_targets.push((viper_guard, target))
+                    }
                 }
                 let mut default_target = targets.otherwise();
                 let mut kill_default_target = false;
@@ -1924,11 +2802,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
                 // Is the target a specification block?
                 if self.procedure.is_spec_block(default_target) {
-                    stmts.push(vir::Stmt::comment(format!(
-                        "Ignore default target {:?}, as it is only used by Prusti to type-check \
-                        a loop invariant.",
-                        default_target
-                    )));
+                    if self.procedure.is_ghost_statement_block(default_target) {
+                        stmts.extend(self.encode_ghost_statements(location.block, default_target)?);
+                    } else {
+                        stmts.push(vir::Stmt::comment(format!(
+                            "Ignore default target {:?}, as it is only used by Prusti to type-check \
+                            a loop invariant.",
+                            default_target
+                        )));
+                    }
                     kill_default_target = true;
                 };
 
@@ -1983,7 +2865,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 (stmts, MirSuccessor::Kill)
             }
 
-            TerminatorKind::Drop { target, .. } => (stmts, MirSuccessor::Goto(target)),
+            TerminatorKind::Drop { target, ref place, .. } => {
+                stmts.extend(self.encode_drop(place, location, span)?);
+                (stmts, MirSuccessor::Goto(target))
+            }
 
             TerminatorKind::FalseEdge { real_target, .. } => {
                 (stmts, MirSuccessor::Goto(real_target))
@@ -1999,6 +2884,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 ref value,
                 ..
             } => {
+                stmts.extend(self.encode_drop(lhs, location, span)?);
                 let (encoded_lhs, pre_stmts, _, _) = self.encode_place(lhs, ArrayAccessKind::Mutable(None, location))
                     .with_span(span)?;
                 stmts.extend(pre_stmts);
@@ -2045,17 +2931,29 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
                     // FIXME: this is a hack to support generics. See issue #187.
                     let mut tymap = HashMap::new();
+                    let mut constmap = HashMap::new();
 
                     for (kind1, kind2) in own_substs.iter().zip(substs.iter()) {
-                        if let (
-                            ty::subst::GenericArgKind::Type(ty1),
-                            ty::subst::GenericArgKind::Type(ty2),
-                        ) = (kind1.unpack(), kind2.unpack())
-                        {
-                            tymap.insert(ty1, ty2);
+                        match (kind1.unpack(), kind2.unpack()) {
+                            (
+                                ty::subst::GenericArgKind::Type(ty1),
+                                ty::subst::GenericArgKind::Type(ty2),
+                            ) => {
+                                tymap.insert(ty1, ty2);
+                            }
+                            (
+                                ty::subst::GenericArgKind::Const(const1),
+                                ty::subst::GenericArgKind::Const(const2),
+                            ) => {
+                                if let ty::ConstKind::Param(param) = const1.val {
+                                    constmap.insert(param, const2);
+                                }
+                            }
+                            _ => {}
                         }
                     }
                     let _cleanup_token = self.encoder.push_temp_tymap(tymap);
+                    let _const_cleanup_token = self.encoder.push_temp_constmap(constmap);
 
                     match full_func_proc_name {
                         "std::rt::begin_panic"
@@ -2170,7 +3068,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                             );
                         }
 
-                        "std::ops::Fn::call" => {
+                        "std::ops::Fn::call" |
+                        "std::ops::FnMut::call_mut" |
+                        "std::ops::FnOnce::call_once" => {
                             let cl_type: ty::Ty = substs[0].expect_ty();
                             match cl_type.kind() {
                                 ty::TyKind::Closure(cl_def_id, _) => {
@@ -2185,6 +3085,17 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                                     )?);
                                 }
 
+                                ty::TyKind::Param(_) => {
+                                    debug!("Encoding call to closure argument of type {:?}", cl_type);
+                                    stmts.extend(self.encode_closure_argument_call(
+                                        location,
+                                        term.source_info.span,
+                                        args,
+                                        destination,
+                                        cl_type,
+                                    )?);
+                                }
+
                                 _ => {
                                     return Err(SpannedEncodingError::unsupported(
                                         format!("only calls to closures are supported. The term is a {:?}, not a closure.", cl_type.kind()),
@@ -2206,23 +3117,88 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                             );
                         }
 
-                        "std::iter::Iterator::next" |
-                        "core::iter::Iterator::next" => {
-                            return Err(SpannedEncodingError::unsupported(
-                                "iterators are not fully supported yet".to_string(),
-                                term.source_info.span,
-                            ));
+                        _ if self.encoder.env().is_std_item(def_id, &["mem", "swap"])
+                            || self.encoder.env().is_std_item(def_id, &["mem", "replace"])
+                            || self.encoder.env().is_std_item(def_id, &["mem", "take"])
+                        => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_mem_function_call(
+                                    def_id,
+                                    location,
+                                    term.source_info.span,
+                                    args,
+                                    destination,
+                                )?
+                            );
                         }
 
-                        _ => {
-                            let is_pure_function = self.encoder.is_pure(def_id);
-                            if is_pure_function {
-                                let (function_name, _) = self.encoder
-                                    .encode_pure_function_use(def_id)
-                                    .with_span(term.source_info.span)?;
-                                debug!("Encoding pure function call '{}'", function_name);
-                                assert!(destination.is_some());
-
+                        _ if self.encoder.env().get_builtin_int_method(def_id).is_some() => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_int_method_call(
+                                    def_id,
+                                    self.encoder.env().get_builtin_int_method(def_id).unwrap(),
+                                    location,
+                                    term.source_info.span,
+                                    args,
+                                    destination,
+                                )?
+                            );
+                        }
+
+                        "std::iter::IntoIterator::into_iter" |
+                        "core::iter::IntoIterator::into_iter"
+                            if args.len() == 1 && self.encoder.env().get_integer_range_elem_type(
+                                self.mir_encoder.get_operand_ty(&args[0])
+                            ).is_some()
+                        => {
+                            // A range is its own iterator
+                            debug!("Encoding call of IntoIterator::into_iter on a range");
+                            let (ref target_place, _) = destination.as_ref().unwrap();
+                            let (dst, pre_stmts, _, _) = self.encode_place(
+                                target_place,
+                                ArrayAccessKind::Shared,
+                            ).with_span(span)?;
+                            stmts.extend(pre_stmts);
+                            stmts.extend(self.encode_assign_operand(&dst, &args[0], location)?);
+                        }
+
+                        "std::iter::Iterator::next" |
+                        "core::iter::Iterator::next"
+                            if args.len() == 1 && self.encoder.env().get_integer_range_elem_type(
+                                self.mir_encoder.get_operand_ty(&args[0]).peel_refs()
+                            ).is_some()
+                        => {
+                            debug!("Encoding call of Iterator::next on a range");
+                            stmts.extend(
+                                self.encode_range_next_call(
+                                    def_id,
+                                    location,
+                                    term.source_info.span,
+                                    args,
+                                    destination,
+                                )?
+                            );
+                        }
+
+                        "std::iter::Iterator::next" |
+                        "core::iter::Iterator::next" => {
+                            return Err(SpannedEncodingError::unsupported(
+                                "iterators are not fully supported yet".to_string(),
+                                term.source_info.span,
+                            ));
+                        }
+
+                        _ => {
+                            let is_pure_function = self.encoder.is_pure(def_id);
+                            if is_pure_function {
+                                let (function_name, _) = self.encoder
+                                    .encode_pure_function_use(def_id)
+                                    .with_span(term.source_info.span)?;
+                                debug!("Encoding pure function call '{}'", function_name);
+                                assert!(destination.is_some());
+
                                 let mut arg_exprs = vec![];
                                 for operand in args.iter() {
                                     let arg_expr = self.mir_encoder.encode_operand_expr(operand);
@@ -2239,6 +3215,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                                     )?
                                 );
                             } else {
+                                if self.panic_free {
+                                    stmts.extend(
+                                        self.encode_unwrap_check(
+                                            def_id,
+                                            args,
+                                            term.source_info.span,
+                                        )?
+                                    );
+                                }
                                 stmts.extend(
                                     self.encode_impure_function_call(
                                         location,
@@ -2263,14 +3248,18 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         (stmts, MirSuccessor::Kill)
                     }
                 } else {
-                    // Other kind of calls?
-                    unimplemented!();
+                    return Err(SpannedEncodingError::unsupported(
+                        format!("calling a constant of type '{:?}' is not supported", ty),
+                        term.source_info.span,
+                    ));
                 }
             }
 
             TerminatorKind::Call { .. } => {
-                // Other kind of calls?
-                unimplemented!();
+                return Err(SpannedEncodingError::unsupported(
+                    "calling function pointers or closures through a reference is not supported",
+                    term.source_info.span,
+                ));
             }
 
             TerminatorKind::Assert {
@@ -2300,15 +3289,26 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 };
 
                 // Check or assume the assertion
-                let (assert_msg, error_ctxt) = if let mir::AssertKind::BoundsCheck { .. } = msg {
-                    let mut s = String::new();
-                    msg.fmt_assert_args(&mut s).unwrap();
-                    (s, ErrorCtxt::BoundsCheckAssert)
-                } else {
-                    let assert_msg = msg.description().to_string();
-                    (assert_msg.clone(), ErrorCtxt::AssertTerminator(assert_msg))
+                let (assert_msg, error_ctxt) = match msg {
+                    mir::AssertKind::BoundsCheck { .. } => {
+                        let mut s = String::new();
+                        msg.fmt_assert_args(&mut s).unwrap();
+                        (s, ErrorCtxt::BoundsCheckAssert)
+                    }
+                    mir::AssertKind::DivisionByZero(_) | mir::AssertKind::RemainderByZero(_) => (
+                        msg.description().to_string(),
+                        ErrorCtxt::Panic(PanicCause::DivisionByZero),
+                    ),
+                    mir::AssertKind::Overflow(mir::BinOp::Div, ..)
+                    | mir::AssertKind::Overflow(mir::BinOp::Rem, ..) => (
+                        msg.description().to_string(),
+                        ErrorCtxt::Panic(PanicCause::DivisionOverflow),
+                    ),
+                    _ => {
+                        let assert_msg = msg.description().to_string();
+                        (assert_msg.clone(), ErrorCtxt::AssertTerminator(assert_msg))
+                    }
                 };
-
                 stmts.push(vir::Stmt::comment(format!("Rust assertion: {}", assert_msg)));
                 if self.check_panics {
                     stmts.push(vir::Stmt::Assert(
@@ -2326,10 +3326,21 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 (stmts, MirSuccessor::Goto(target))
             }
 
-            TerminatorKind::Resume
-            | TerminatorKind::Yield { .. }
-            | TerminatorKind::GeneratorDrop
-            | TerminatorKind::InlineAsm { .. } => unimplemented!("{:?}", term.kind),
+            TerminatorKind::Yield { .. } | TerminatorKind::GeneratorDrop => {
+                return Err(SpannedEncodingError::unsupported(
+                    "generators are not supported",
+                    term.source_info.span,
+                ));
+            }
+
+            TerminatorKind::InlineAsm { .. } => {
+                return Err(SpannedEncodingError::unsupported(
+                    "inline assembly is not supported",
+                    term.source_info.span,
+                ));
+            }
+
+            TerminatorKind::Resume => unreachable!("{:?}", term.kind),
         };
         Ok(result)
     }
@@ -2402,15 +3413,11 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             let rhs = self.mir_encoder.encode_operand_expr(&args[1])
                 .with_span(call_site_span)?;
 
+            let equality = self.encoder.encode_snapshot_equality(arg_ty, lhs, rhs)
+                .with_span(call_site_span)?;
             let expr = match bin_op {
-                vir::BinOpKind::EqCmp => vir::Expr::eq_cmp(
-                    vir::Expr::snap_app(lhs),
-                    vir::Expr::snap_app(rhs),
-                ),
-                vir::BinOpKind::NeCmp => vir::Expr::ne_cmp(
-                    vir::Expr::snap_app(lhs),
-                    vir::Expr::snap_app(rhs),
-                ),
+                vir::BinOpKind::EqCmp => equality,
+                vir::BinOpKind::NeCmp => vir::Expr::not(equality),
                 _ => unreachable!()
             };
 
@@ -2442,6 +3449,463 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
     }
 
+    /// Encode a call of a `checked_*`, `wrapping_*`, `saturating_*` or
+    /// `overflowing_*` method of an integer type like a call of a pure
+    /// function, whose result is computed from the mathematical result of the
+    /// operation according to the bounds of the type.
+    fn encode_int_method_call(
+        &mut self,
+        called_def_id: ProcedureDefId,
+        method: BuiltinIntMethod,
+        location: mir::Location,
+        call_site_span: Span,
+        args: &[mir::Operand<'tcx>],
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let tcx = self.encoder.env().tcx();
+        let int_ty = self.mir_encoder.get_operand_ty(&args[0]);
+        let lhs = self.mir_encoder.encode_operand_expr(&args[0])
+            .with_span(call_site_span)?;
+        let rhs = self.mir_encoder.encode_operand_expr(&args[1])
+            .with_span(call_site_span)?;
+        let op = match method {
+            BuiltinIntMethod::Checked(op)
+            | BuiltinIntMethod::Wrapping(op)
+            | BuiltinIntMethod::Saturating(op)
+            | BuiltinIntMethod::Overflowing(op) => op,
+        };
+        let value = match op {
+            mir::BinOp::Add => vir::Expr::add(lhs, rhs),
+            mir::BinOp::Sub => vir::Expr::sub(lhs, rhs),
+            mir::BinOp::Mul => vir::Expr::mul(lhs, rhs),
+            _ => unreachable!("{:?}", op),
+        };
+        let (lower, upper) = self.encoder.get_integer_bounds(int_ty).unwrap();
+        let in_range = vir::Expr::and(
+            vir::Expr::le_cmp(lower.clone(), value.clone()),
+            vir::Expr::le_cmp(value.clone(), upper.clone()),
+        );
+        let wrapped = self.encoder.encode_wrapped_value(value.clone(), int_ty);
+
+        let (target_place, mut stmts) = self.encode_pure_function_call_lhs_place(destination);
+        let return_ty = tcx.fn_sig(called_def_id).skip_binder().output();
+        let result = match method {
+            BuiltinIntMethod::Wrapping(_) => wrapped,
+            BuiltinIntMethod::Saturating(_) => vir::Expr::ite(
+                vir::Expr::lt_cmp(value.clone(), lower.clone()),
+                lower,
+                vir::Expr::ite(vir::Expr::gt_cmp(value.clone(), upper.clone()), upper, value),
+            ),
+            BuiltinIntMethod::Checked(_) => {
+                let variant_index = |name: rustc_span::Symbol| match return_ty.kind() {
+                    ty::TyKind::Adt(adt_def, _) => adt_def.variants.iter_enumerated()
+                        .find(|(_, variant_def)| variant_def.ident.name == name)
+                        .map(|(variant_index, _)| variant_index.index())
+                        .unwrap(),
+                    _ => unreachable!("{:?}", return_ty),
+                };
+                vir::Expr::ite(
+                    in_range,
+                    self.encoder.encode_snapshot_constructor(
+                        return_ty,
+                        variant_index(rustc_span::sym::Some),
+                        vec![value],
+                    ).with_span(call_site_span)?,
+                    self.encoder.encode_snapshot_constructor(
+                        return_ty,
+                        variant_index(rustc_span::sym::None),
+                        vec![],
+                    ).with_span(call_site_span)?,
+                )
+            }
+            BuiltinIntMethod::Overflowing(_) => self.encoder.encode_snapshot_constructor(
+                return_ty,
+                0,
+                vec![wrapped, vir::Expr::not(in_range)],
+            ).with_span(call_site_span)?,
+        };
+        let target_value = if return_ty.is_integral() {
+            self.encoder.encode_value_expr(target_place, return_ty)
+                .with_span(call_site_span)?
+        } else {
+            vir::Expr::snap_app(target_place)
+        };
+        let inhaled_expr = self.encoder.patch_snapshots(vir::Expr::eq_cmp(target_value, result))
+            .with_span(call_site_span)?;
+
+        let (call_stmts, _) = self.encode_pure_function_call_site(
+            location,
+            destination,
+            inhaled_expr,
+        );
+        stmts.extend(call_stmts);
+        Ok(stmts)
+    }
+
+    /// Encode a call of `mem::swap`, `mem::replace` or `mem::take` like a call
+    /// of a function without specification, followed by the inhale of the
+    /// equalities that describe how the values are moved between the places.
+    /// The equalities are only encoded if the type of the values supports
+    /// snapshot equality, otherwise the targets of the arguments are havocked.
+    fn encode_mem_function_call(
+        &mut self,
+        called_def_id: ProcedureDefId,
+        location: mir::Location,
+        call_site_span: Span,
+        args: &[mir::Operand<'tcx>],
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let mut stmts = self.encode_impure_function_call(
+            location,
+            call_site_span,
+            args,
+            destination,
+            called_def_id,
+            None,
+        )?;
+
+        let ty = match self.mir_encoder.get_operand_ty(&args[0]).kind() {
+            ty::TyKind::Ref(_, ty, _) => *ty,
+            _ => unreachable!(),
+        };
+        if !self.encoder.supports_snapshot_equality(ty).with_span(call_site_span)? {
+            return Ok(stmts);
+        }
+        let dest = match self.encode_mem_function_target(&args[0], call_site_span)? {
+            Some(dest) => dest,
+            None => return Ok(stmts),
+        };
+
+        // The permissions of the targets of the arguments are returned in the
+        // pre state of the call, see `wrap_arguments_into_old`.
+        let pre_label = self.label_after_location[&location].clone();
+        let value_before = |place: vir::Expr| {
+            vir::Expr::labelled_old(&pre_label, vir::Expr::snap_app(place))
+        };
+        let value_after = |place: vir::Expr| {
+            vir::Expr::snap_app(vir::Expr::labelled_old(&pre_label, place))
+        };
+
+        let mut equalities = vec![];
+        let function_name = self.encoder.env().tcx().item_name(called_def_id);
+        match &*function_name.as_str() {
+            "swap" => {
+                let other = match self.encode_mem_function_target(&args[1], call_site_span)? {
+                    Some(other) => other,
+                    None => return Ok(stmts),
+                };
+                equalities.push(vir::Expr::eq_cmp(
+                    value_after(dest.clone()),
+                    value_before(other.clone()),
+                ));
+                equalities.push(vir::Expr::eq_cmp(value_after(other), value_before(dest.clone())));
+            }
+            "replace" => {
+                let src_value = match args[1].place().and_then(|place| place.as_local()) {
+                    Some(local) => value_before(self.encode_prusti_local(local.into()).into()),
+                    None if ty.is_primitive() => {
+                        self.mir_encoder.encode_operand_expr(&args[1]).with_span(call_site_span)?
+                    }
+                    None => return Ok(stmts),
+                };
+                equalities.push(vir::Expr::eq_cmp(value_after(dest.clone()), src_value));
+            }
+            "take" => {
+                // The new value is known if `T::default()` is pure
+                let tcx = self.encoder.env().tcx();
+                let default_method = tcx.get_diagnostic_item(rustc_span::sym::Default)
+                    .and_then(|default_trait| {
+                        self.encoder.env().get_trait_method_decl_for_type(
+                            ty,
+                            default_trait,
+                            rustc_span::Symbol::intern("default"),
+                        ).into_iter().next()
+                    })
+                    .map(|assoc_item| assoc_item.def_id)
+                    .filter(|def_id| self.encoder.is_pure(*def_id));
+                if let Some(default_def_id) = default_method {
+                    let (function_name, return_type) = self.encoder
+                        .encode_pure_function_use(default_def_id)?;
+                    equalities.push(vir::Expr::eq_cmp(
+                        value_after(dest.clone()),
+                        vir::Expr::func_app(
+                            function_name,
+                            vec![],
+                            vec![],
+                            return_type,
+                            vir::Position::default(),
+                        ),
+                    ));
+                }
+            }
+            _ => unreachable!("{:?}", function_name),
+        }
+
+        // `replace` and `take` return the old value of the destination
+        if function_name.as_str() != "swap" {
+            if let Some((ref target_place, _)) = destination {
+                let (encoded_target, pre_stmts, _, _) = self
+                    .encode_place(target_place, ArrayAccessKind::Shared)
+                    .with_span(call_site_span)?;
+                stmts.extend(pre_stmts);
+                equalities.push(vir::Expr::eq_cmp(
+                    vir::Expr::snap_app(encoded_target),
+                    value_before(dest),
+                ));
+            }
+        }
+
+        let effect = self.encoder.patch_snapshots(equalities.into_iter().conjoin())
+            .with_span(call_site_span)?;
+        stmts.push(vir::Stmt::Inhale(effect));
+        Ok(stmts)
+    }
+
+    /// Encode the target of a reference passed to `mem::swap`, `mem::replace`
+    /// or `mem::take`, if the reference is stored in a local variable.
+    fn encode_mem_function_target(
+        &self,
+        arg: &mir::Operand<'tcx>,
+        span: Span,
+    ) -> SpannedEncodingResult<Option<vir::Expr>> {
+        let local = match arg.place().and_then(|place| place.as_local()) {
+            Some(local) => local,
+            None => return Ok(None),
+        };
+        let encoded_arg: vir::Expr = self.encode_prusti_local(local.into()).into();
+        let arg_ty = self.mir_encoder.get_operand_ty(arg);
+        let (encoded_target, ..) = self.mir_encoder.encode_deref(encoded_arg, arg_ty)
+            .with_span(span)?;
+        Ok(Some(encoded_target))
+    }
+
+    /// Encode a call of `Iterator::next` on a range of integers like a call
+    /// of a function without specification, followed by the inhale of the
+    /// effect of the call: if the range is not empty, its start is returned
+    /// and incremented, otherwise `None` is returned.
+    fn encode_range_next_call(
+        &mut self,
+        called_def_id: ProcedureDefId,
+        location: mir::Location,
+        call_site_span: Span,
+        args: &[mir::Operand<'tcx>],
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let mut stmts = self.encode_impure_function_call(
+            location,
+            call_site_span,
+            args,
+            destination,
+            called_def_id,
+            None,
+        )?;
+
+        let range_ty = self.mir_encoder.get_operand_ty(&args[0]).peel_refs();
+        let elem_ty = self.encoder.env().get_integer_range_elem_type(range_ty).unwrap();
+        let range = match self.encode_mem_function_target(&args[0], call_site_span)? {
+            Some(range) => range,
+            None => return Ok(stmts),
+        };
+        let (target_place, return_ty) = match destination {
+            Some((ref target_place, _)) => {
+                let (encoded_target, pre_stmts, return_ty, _) = self
+                    .encode_place(target_place, ArrayAccessKind::Shared)
+                    .with_span(call_site_span)?;
+                stmts.extend(pre_stmts);
+                (encoded_target, return_ty)
+            }
+            None => return Ok(stmts),
+        };
+
+        // The permission of the range is returned in the pre state of the
+        // call, see `wrap_arguments_into_old`.
+        let pre_label = self.label_after_location[&location].clone();
+        let start_field = self.encoder.encode_struct_field("start", elem_ty)
+            .with_span(call_site_span)?;
+        let end_field = self.encoder.encode_struct_field("end", elem_ty)
+            .with_span(call_site_span)?;
+        let value_before = |field: &vir::Field| -> EncodingResult<vir::Expr> {
+            Ok(vir::Expr::labelled_old(
+                &pre_label,
+                self.encoder.encode_value_expr(range.clone().field(field.clone()), elem_ty)?,
+            ))
+        };
+        let value_after = |field: &vir::Field| -> EncodingResult<vir::Expr> {
+            self.encoder.encode_value_expr(
+                vir::Expr::labelled_old(&pre_label, range.clone()).field(field.clone()),
+                elem_ty,
+            )
+        };
+        let start_before = value_before(&start_field).with_span(call_site_span)?;
+        let start_after = value_after(&start_field).with_span(call_site_span)?;
+        let end_before = value_before(&end_field).with_span(call_site_span)?;
+        let end_after = value_after(&end_field).with_span(call_site_span)?;
+
+        let variant_index = |name: rustc_span::Symbol| match return_ty.kind() {
+            ty::TyKind::Adt(adt_def, _) => adt_def.variants.iter_enumerated()
+                .find(|(_, variant_def)| variant_def.ident.name == name)
+                .map(|(variant_index, _)| variant_index.index())
+                .unwrap(),
+            _ => unreachable!("{:?}", return_ty),
+        };
+        let some_value = self.encoder.encode_snapshot_constructor(
+            return_ty,
+            variant_index(rustc_span::sym::Some),
+            vec![start_before.clone()],
+        ).with_span(call_site_span)?;
+        let none_value = self.encoder.encode_snapshot_constructor(
+            return_ty,
+            variant_index(rustc_span::sym::None),
+            vec![],
+        ).with_span(call_site_span)?;
+        let is_not_empty = vir::Expr::lt_cmp(start_before.clone(), end_before.clone());
+        let effect = vir::Expr::and(
+            vir::Expr::eq_cmp(end_after, end_before),
+            vir::Expr::ite(
+                is_not_empty,
+                vir::Expr::and(
+                    vir::Expr::eq_cmp(
+                        start_after.clone(),
+                        vir::Expr::add(start_before.clone(), 1.into()),
+                    ),
+                    vir::Expr::eq_cmp(vir::Expr::snap_app(target_place.clone()), some_value),
+                ),
+                vir::Expr::and(
+                    vir::Expr::eq_cmp(start_after, start_before),
+                    vir::Expr::eq_cmp(vir::Expr::snap_app(target_place), none_value),
+                ),
+            ),
+        );
+        let effect = self.encoder.patch_snapshots(effect).with_span(call_site_span)?;
+        stmts.push(vir::Stmt::Inhale(effect));
+        Ok(stmts)
+    }
+
+    /// Returns the places of the ranges that are iterated by the loop with
+    /// head `loop_head`, together with the user variables that are bound to
+    /// the element of the current iteration before the invariant is reached.
+    fn get_range_loop_iterations(
+        &self,
+        loop_head: BasicBlockIndex,
+        loop_inv_block: BasicBlockIndex,
+    ) -> Vec<(mir::Local, ty::Ty<'tcx>, Vec<mir::Local>)> {
+        let tcx = self.encoder.env().tcx();
+        let loop_body = self.loop_encoder.loops().get_loop_body(loop_head);
+        let blocks_before_inv = match loop_body.iter().position(|&bb| bb == loop_inv_block) {
+            Some(pos) => &loop_body[..=pos],
+            None => return vec![],
+        };
+        let statements = || blocks_before_inv.iter()
+            .flat_map(|&bb| self.mir.basic_blocks()[bb].statements.iter());
+        let mut iterations = vec![];
+        for &bb in blocks_before_inv {
+            let (func, args, destination) = match self.mir.basic_blocks()[bb].terminator().kind {
+                TerminatorKind::Call { ref func, ref args, destination: Some((place, _)), .. } => {
+                    (func, args, place)
+                }
+                _ => continue,
+            };
+            let called_def_id = match func.constant().map(|constant| constant.literal.ty().kind()) {
+                Some(ty::TyKind::FnDef(def_id, _)) => *def_id,
+                _ => continue,
+            };
+            if !matches!(
+                &*tcx.def_path_str(called_def_id),
+                "std::iter::Iterator::next" | "core::iter::Iterator::next"
+            ) {
+                continue;
+            }
+            let range_ty = args[0].ty(self.mir, tcx).peel_refs();
+            let elem_ty = match self.encoder.env().get_integer_range_elem_type(range_ty) {
+                Some(elem_ty) => elem_ty,
+                None => continue,
+            };
+            // The reference to the range is created by `&mut range` right before the call
+            let ref_local = match args[0].place().and_then(|place| place.as_local()) {
+                Some(local) => local,
+                None => continue,
+            };
+            let range_local = statements().find_map(|stmt| match stmt.kind {
+                mir::StatementKind::Assign(box (
+                    lhs,
+                    mir::Rvalue::Ref(_, mir::BorrowKind::Mut { .. }, borrowed),
+                )) if lhs.as_local() == Some(ref_local) => borrowed.as_local(),
+                _ => None,
+            });
+            let range_local = match range_local {
+                Some(local) => local,
+                None => continue,
+            };
+            // Follow the copies of the value in `Some(..)` to the variable of the pattern
+            let mut elem_locals: Vec<mir::Local> = vec![];
+            for stmt in statements() {
+                if let mir::StatementKind::Assign(box (lhs, mir::Rvalue::Use(ref operand))) =
+                    stmt.kind
+                {
+                    let rhs = match operand.place() {
+                        Some(rhs) => rhs,
+                        None => continue,
+                    };
+                    let is_elem = if rhs.local == destination.local {
+                        matches!(
+                            &rhs.projection[..],
+                            [mir::ProjectionElem::Downcast(..), mir::ProjectionElem::Field(..)]
+                        )
+                    } else {
+                        rhs.as_local().map_or(false, |local| elem_locals.contains(&local))
+                    };
+                    if let (true, Some(local)) = (is_elem, lhs.as_local()) {
+                        elem_locals.push(local);
+                    }
+                }
+            }
+            elem_locals.retain(|&local| self.mir.local_decls[local].is_user_variable());
+            iterations.push((range_local, elem_ty, elem_locals));
+        }
+        iterations
+    }
+
+    /// Encode the invariant that Prusti knows about the ranges iterated by a
+    /// `for` loop: the end of the range does not change, the start only grows
+    /// and is the successor of the element of the current iteration.
+    fn encode_range_loop_invariant(
+        &self,
+        loop_head: BasicBlockIndex,
+        loop_inv_block: BasicBlockIndex,
+    ) -> EncodingResult<Vec<vir::Expr>> {
+        let loop_entry_label = &self.loop_entry_labels[&loop_head];
+        let mut invariant = vec![];
+        for (range_local, elem_ty, elem_locals) in
+            self.get_range_loop_iterations(loop_head, loop_inv_block)
+        {
+            let range: vir::Expr = self.encode_prusti_local(range_local.into()).into();
+            let start_field = self.encoder.encode_struct_field("start", elem_ty)?;
+            let end_field = self.encoder.encode_struct_field("end", elem_ty)?;
+            let start = self.encoder.encode_value_expr(range.clone().field(start_field), elem_ty)?;
+            let end = self.encoder.encode_value_expr(range.field(end_field), elem_ty)?;
+            invariant.push(vir::Expr::eq_cmp(
+                end.clone(),
+                vir::Expr::labelled_old(loop_entry_label, end.clone()),
+            ));
+            invariant.push(vir::Expr::le_cmp(
+                vir::Expr::labelled_old(loop_entry_label, start.clone()),
+                start.clone(),
+            ));
+            invariant.push(vir::Expr::le_cmp(start.clone(), end));
+            for elem_local in elem_locals {
+                let elem = self.encoder.encode_value_expr(
+                    self.encode_prusti_local(elem_local.into()).into(),
+                    elem_ty,
+                )?;
+                invariant.push(vir::Expr::eq_cmp(
+                    vir::Expr::add(elem, 1.into()),
+                    start.clone(),
+                ));
+            }
+        }
+        Ok(invariant)
+    }
+
     /// Encode an edge of the MIR graph
     fn encode_edge_block(
         &mut self,
@@ -2479,6 +3943,211 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
     }
 
+    /// Check that the receiver of a call of `Option::unwrap`, `Result::unwrap` or of their
+    /// `expect` variants has the variant for which the call does not panic. Returns no
+    /// statements for the calls of other procedures.
+    fn encode_unwrap_check(
+        &mut self,
+        called_def_id: ProcedureDefId,
+        mir_args: &[mir::Operand<'tcx>],
+        call_site_span: rustc_span::Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let tcx = self.encoder.env().tcx();
+        let full_func_proc_name = tcx.def_path_str(called_def_id);
+        let (variant_name, panic_cause) = match full_func_proc_name.as_str() {
+            "std::option::Option::<T>::unwrap"
+            | "std::option::Option::<T>::expect"
+            | "core::option::Option::<T>::unwrap"
+            | "core::option::Option::<T>::expect" => ("Some", PanicCause::UnwrapNone),
+            "std::result::Result::<T, E>::unwrap"
+            | "std::result::Result::<T, E>::expect"
+            | "core::result::Result::<T, E>::unwrap"
+            | "core::result::Result::<T, E>::expect" => ("Ok", PanicCause::UnwrapErr),
+            _ => return Ok(vec![]),
+        };
+        let adt_def = match self.mir_encoder.get_operand_ty(&mir_args[0]).kind() {
+            ty::TyKind::Adt(adt_def, _) => adt_def,
+            _ => return Ok(vec![]),
+        };
+        let receiver = match self.mir_encoder.encode_operand_place(&mir_args[0])
+            .with_span(call_site_span)?
+        {
+            Some(place) => place,
+            // Constants are known to the verifier only through their snapshot
+            None => return Ok(vec![]),
+        };
+        let (variant_index, _) = adt_def.variants.iter_enumerated()
+            .find(|(_, variant)| &*variant.ident.as_str() == variant_name)
+            .unwrap();
+        let discr_value: vir::Expr = adt_def.discriminant_for_variant(tcx, variant_index)
+            .val
+            .into();
+        let discriminant = self.encoder.encode_discriminant_func_app(receiver, adt_def);
+        let pos = self.encoder.error_manager().register(
+            call_site_span,
+            ErrorCtxt::Panic(panic_cause),
+        );
+        Ok(vec![
+            vir::Stmt::comment(format!(
+                "Rust panic - {} without a {} value",
+                full_func_proc_name,
+                variant_name,
+            )),
+            vir::Stmt::Assert(vir::Expr::eq_cmp(discriminant, discr_value), pos),
+        ])
+    }
+
+    /// Encode the implicit drop of `place`. If the type of the place has a local `Drop` impl,
+    /// the precondition of its `drop` method is checked, the dropped place is havocked and the
+    /// postcondition is assumed. Other places of the procedure are not affected. Dropping a
+    /// place of a type without a `Drop` impl, or a place that is not initialized, is a no-op.
+    fn encode_drop(
+        &mut self,
+        place: &mir::Place<'tcx>,
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let tcx = self.encoder.env().tcx();
+        let ty = place.ty(self.mir, tcx).ty;
+        let (adt_def, substs) = match ty.kind() {
+            ty::TyKind::Adt(adt_def, substs) => (adt_def, substs),
+            _ => return Ok(vec![]),
+        };
+        let drop_def_id = match adt_def.destructor(tcx) {
+            Some(destructor) if destructor.did.is_local() => destructor.did,
+            _ => return Ok(vec![]),
+        };
+        let (encoded_place, pre_stmts, _, _) = self.encode_place(place, ArrayAccessKind::Shared)
+            .with_span(span)?;
+        if !self.init_info.is_vir_place_accessible(&encoded_place, location) {
+            return Ok(vec![]);
+        }
+        let mut stmts = pre_stmts;
+        stmts.push(vir::Stmt::comment(format!("Drop of {:?}", place)));
+
+        // The `drop` method has the generics of the `Drop` impl, which are those of the type
+        let own_substs = ty::List::identity_for_item(tcx, drop_def_id);
+        let mut tymap = HashMap::new();
+        let mut constmap = HashMap::new();
+        for (kind1, kind2) in own_substs.iter().zip(substs.iter()) {
+            match (kind1.unpack(), kind2.unpack()) {
+                (
+                    ty::subst::GenericArgKind::Type(ty1),
+                    ty::subst::GenericArgKind::Type(ty2),
+                ) => {
+                    tymap.insert(ty1, ty2);
+                }
+                (
+                    ty::subst::GenericArgKind::Const(const1),
+                    ty::subst::GenericArgKind::Const(const2),
+                ) => {
+                    if let ty::ConstKind::Param(param) = const1.val {
+                        constmap.insert(param, const2);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let _cleanup_token = self.encoder.push_temp_tymap(tymap);
+        let _const_cleanup_token = self.encoder.push_temp_constmap(constmap);
+
+        // The receiver of `drop` is a mutable reference to the dropped place
+        let self_ty = tcx.mk_mut_ref(tcx.lifetimes.re_erased, ty);
+        let self_arg = self.locals.get_fresh(self_ty);
+        let target = self.locals.get_fresh(tcx.mk_unit());
+        let contract = self.encoder.get_procedure_contract_for_call(
+            None,
+            drop_def_id,
+            &vec![self_arg],
+            target,
+        ).with_span(span)?;
+        self.check_drop_contract(&contract)?;
+        let encoded_self_local = self.encode_prusti_local(self_arg);
+        let encoded_self_arg: vir::Expr = encoded_self_local.clone().into();
+        let encoded_target: vir::Expr = self.encode_prusti_local(target).into();
+        let deref_field = self.encoder.encode_dereference_field(self_ty).with_span(span)?;
+        let self_deref = encoded_self_arg.clone().field(deref_field);
+
+        // Store a label for the pre state
+        let pre_label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::Label(pre_label.clone()));
+
+        // The assertions of the contract are stated on the dropped place, which replaces `*self`
+        let on_dropped_place = |assertion: &typed::Assertion<'tcx>, encoded_assertion: vir::Expr| {
+            let encoded_assertion = encoded_assertion.replace_place(&self_deref, &encoded_place);
+            if expr_mentions_local_var(&encoded_assertion, &encoded_self_local.name) {
+                let assertion_span = typed::Spanned::get_spans(assertion, &self.mir, tcx)
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(|| tcx.def_span(drop_def_id));
+                return Err(SpannedEncodingError::incorrect(
+                    "the contract of `Drop::drop` can only be about the dropped value `*self`",
+                    assertion_span,
+                ));
+            }
+            Ok(encoded_assertion)
+        };
+
+        // Check the precondition
+        let pos = self
+            .encoder
+            .error_manager()
+            .register(span, ErrorCtxt::ExhaleMethodPrecondition);
+        for assertion in contract.functional_precondition() {
+            let encoded_assertion = self.encoder.encode_assertion(
+                assertion,
+                &self.mir,
+                None,
+                &[encoded_self_arg.clone()],
+                None,
+                false,
+                None,
+                ErrorCtxt::GenericExpression,
+            )?;
+            stmts.push(vir::Stmt::Assert(on_dropped_place(assertion, encoded_assertion)?, pos));
+        }
+
+        // Havoc the dropped place
+        let place_perm = self.mir_encoder
+            .encode_place_predicate_permission(encoded_place.clone(), vir::PermAmount::Write)
+            .ok_or_else(|| SpannedEncodingError::unsupported(
+                format!("the drop of a place of type `{:?}` is not supported", ty),
+                span,
+            ))?;
+        stmts.push(vir::Stmt::Exhale(place_perm.clone(), pos));
+        stmts.push(vir::Stmt::Inhale(place_perm));
+
+        // Assume the postcondition
+        for assertion in contract.functional_postcondition() {
+            let encoded_assertion = self.encoder.encode_assertion(
+                assertion,
+                &self.mir,
+                Some(&pre_label),
+                &[encoded_self_arg.clone()],
+                Some(&encoded_target),
+                false,
+                None,
+                ErrorCtxt::GenericExpression,
+            )?;
+            stmts.push(vir::Stmt::Inhale(on_dropped_place(assertion, encoded_assertion)?));
+        }
+
+        Ok(stmts)
+    }
+
+    /// Checks that the contract of a `Drop::drop` method consists only of preconditions and
+    /// postconditions, which can be applied at the implicit drops of the type.
+    fn check_drop_contract(&self, contract: &ProcedureContract<'tcx>) -> SpannedEncodingResult<()> {
+        if !contract.pledges().is_empty() {
+            return Err(SpannedEncodingError::incorrect(
+                "the contract of `Drop::drop` can only have `#[requires(..)]` and \
+                `#[ensures(..)]` about `self`",
+                self.encoder.env().tcx().def_span(contract.def_id),
+            ));
+        }
+        Ok(())
+    }
+
     fn encode_impure_function_call(
         &mut self,
         location: mir::Location,
@@ -2702,15 +4371,30 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             stmts.extend(self.encode_havoc_and_allocation(constant_arg));
         }
 
+        // Check that the termination measure decreases at a recursive call
+        if let Some((new_measure, old_measure)) = self.encode_termination_measures(
+            called_def_id,
+            &procedure_contract,
+            call_site_span,
+        )? {
+            let new_measure: Vec<_> = new_measure.into_iter().map(&replace_fake_exprs).collect();
+            let pos = self
+                .encoder
+                .error_manager()
+                .register(call_site_span, ErrorCtxt::AssertTerminationMeasure);
+            stmts.push(vir::Stmt::Assert(
+                encode_measure_decrease(&new_measure, &old_measure),
+                pos,
+            ));
+        }
+
         // Encode precondition.
         let (
             pre_type_spec,
             pre_mandatory_type_spec,
             pre_invs_spec,
             pre_func_spec,
-            _, // We don't care about verifying that the weakening is valid,
-               // since it isn't the task of the caller
-        ) = self.encode_precondition_expr(&procedure_contract, None)?;
+        ) = self.encode_precondition_expr(&procedure_contract)?;
         let pos = self
             .encoder
             .error_manager()
@@ -2772,12 +4456,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             post_func_spec,
             magic_wands,
             read_transfer,
-            _, // We don't care about verifying that the strengthening is valid,
-               // since it isn't the task of the caller
         ) = self.encode_postcondition_expr(
             Some(location),
             &procedure_contract,
-            None,
             &pre_label,
             &post_label,
             Some((location, &fake_exprs)),
@@ -2899,28 +4580,147 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         let (target_value, mut stmts) = self.encode_pure_function_call_lhs_value(destination)
             .with_span(call_site_span)?;
 
-        let inhaled_expr = if return_type.is_domain() {
-            let (target_place, pre_stmts) = self.encode_pure_function_call_lhs_place(destination);
-            stmts.extend(pre_stmts);
-            vir::Expr::eq_cmp(
-                vir::Expr::snap_app(target_place),
-                func_call,
-            )
-        } else {
-            vir::Expr::eq_cmp(target_value.into(), func_call)
-        };
+        let inhaled_expr = if return_type.is_domain() {
+            let (target_place, pre_stmts) = self.encode_pure_function_call_lhs_place(destination);
+            stmts.extend(pre_stmts);
+            vir::Expr::eq_cmp(
+                vir::Expr::snap_app(target_place),
+                func_call,
+            )
+        } else {
+            vir::Expr::eq_cmp(target_value.into(), func_call)
+        };
+
+        let (call_stmts, label) = self.encode_pure_function_call_site(
+            location,
+            destination,
+            inhaled_expr
+        );
+        stmts.extend(call_stmts);
+
+        self.encode_transfer_args_permissions(location, args,  &mut stmts, label, false)?;
+        Ok(stmts)
+    }
+
+    /// Encode a call of a closure that is an argument of the procedure, using the
+    /// specification entailment `f |= |args| [..]` of the closure type `cl_ty` in the
+    /// precondition of the procedure.
+    fn encode_closure_argument_call(
+        &mut self,
+        location: mir::Location,
+        call_site_span: Span,
+        args: &[mir::Operand<'tcx>],
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        cl_ty: ty::Ty<'tcx>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let entailment = self.find_spec_entailment(cl_ty).ok_or_else(||
+            SpannedEncodingError::unsupported(
+                format!(
+                    "calls to a closure of type `{}` are supported only if the precondition \
+                    contains a specification entailment for it",
+                    cl_ty,
+                ),
+                call_site_span,
+            )
+        )?;
+        if destination.is_none() {
+            return Err(SpannedEncodingError::unsupported(
+                format!("calls to a closure of type `{}` that do not return are not supported", cl_ty),
+                call_site_span,
+            ));
+        }
+        // The closure and the tuple of its arguments
+        let arg_tuple_operand = match args {
+            [_, arg_tuple_operand] => arg_tuple_operand,
+            _ => return Err(SpannedEncodingError::unsupported(
+                format!("calls to a closure with {} operands instead of 2 are not supported", args.len()),
+                call_site_span,
+            )),
+        };
+
+        // The arguments of the closure are passed in a tuple
+        let arg_tuple_ty = self.mir_encoder.get_operand_ty(arg_tuple_operand);
+        let arg_tys = match arg_tuple_ty.kind() {
+            ty::TyKind::Tuple(substs) => substs,
+            _ => return Err(SpannedEncodingError::unsupported(
+                format!("the arguments of the closure are passed as a `{}`, not as a tuple", arg_tuple_ty),
+                call_site_span,
+            )),
+        };
+        let arg_tuple = self.mir_encoder.encode_operand_place(arg_tuple_operand)
+            .with_span(call_site_span)?
+            .ok_or_else(|| SpannedEncodingError::unsupported(
+                "the arguments of a call to a closure must be passed in a place",
+                call_site_span,
+            ))?;
+        let mut call_args = vec![];
+        for (field_num, ty) in arg_tys.iter().enumerate() {
+            let arg_ty = ty.expect_ty();
+            let value_field = self
+                .encoder
+                .encode_raw_ref_field(format!("tuple_{}", field_num), arg_ty)
+                .with_span(call_site_span)?;
+            call_args.push(
+                self.encoder.encode_value_expr(arg_tuple.clone().field(value_field), arg_ty)
+                    .with_span(call_site_span)?
+            );
+        }
+
+        let encoded_args: Vec<vir::Expr> = self.procedure_contract()
+            .args
+            .iter()
+            .map(|local| self.encode_prusti_local(*local).into())
+            .collect();
+        let (target_value, mut stmts) = self.encode_pure_function_call_lhs_value(destination)
+            .with_span(call_site_span)?;
+        let (pre, post) = encode_spec_entailment_call(
+            self.encoder,
+            &entailment,
+            &encoded_args,
+            &call_args,
+            &target_value,
+        )?;
+
+        let pos = self
+            .encoder
+            .error_manager()
+            .register(call_site_span, ErrorCtxt::ExhaleMethodPrecondition);
+        stmts.push(vir::Stmt::Assert(pre.set_default_pos(pos), pos));
 
         let (call_stmts, label) = self.encode_pure_function_call_site(
             location,
             destination,
-            inhaled_expr
+            post,
         );
         stmts.extend(call_stmts);
 
-        self.encode_transfer_args_permissions(location, args,  &mut stmts, label, false)?;
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label, false)?;
         Ok(stmts)
     }
 
+    /// Find the specification entailment for the closure type `cl_ty` in the precondition
+    /// of the procedure.
+    fn find_spec_entailment(&self, cl_ty: ty::Ty<'tcx>) -> Option<typed::Assertion<'tcx>> {
+        fn find<'tcx>(
+            env: &Environment<'tcx>,
+            assertion: &typed::Assertion<'tcx>,
+            cl_ty: ty::Ty<'tcx>,
+        ) -> Option<typed::Assertion<'tcx>> {
+            match assertion.kind {
+                box typed::AssertionKind::SpecEntailment { ref closure, .. }
+                    if env.mir(closure.expr).return_ty() == cl_ty => Some(assertion.clone()),
+                box typed::AssertionKind::And(ref assertions) => assertions
+                    .iter()
+                    .find_map(|assertion| find(env, assertion, cl_ty)),
+                _ => None,
+            }
+        }
+        self.procedure_contract()
+            .functional_precondition()
+            .iter()
+            .find_map(|assertion| find(self.encoder.env(), assertion, cl_ty))
+    }
+
     fn encode_pure_function_call_lhs_value(
         &mut self,
         destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
@@ -3092,6 +4892,64 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         })
     }
 
+    /// Encode the termination measure of a procedure called with `called_contract` and the
+    /// termination measure of the encoded procedure in its pre state. Returns `None` if the
+    /// encoded procedure has no termination measure or if the call is not recursive.
+    fn encode_termination_measures(
+        &self,
+        called_def_id: ProcedureDefId,
+        called_contract: &ProcedureContract<'tcx>,
+        call_site_span: Span,
+    ) -> SpannedEncodingResult<Option<(Vec<vir::Expr>, Vec<vir::Expr>)>> {
+        let measure = match self.procedure_contract().decreases() {
+            Some(measure) => measure,
+            None => return Ok(None),
+        };
+        if !self.encoder.env().is_recursive_call(self.proc_def_id, called_def_id) {
+            return Ok(None);
+        }
+        let called_measure = called_contract.decreases().ok_or_else(||
+            SpannedEncodingError::incorrect(
+                format!(
+                    "the mutually recursive function `{}` has no termination measure",
+                    self.encoder.env().get_item_name(called_def_id),
+                ),
+                call_site_span,
+            )
+        )?;
+        let encode_args = |contract: &ProcedureContract<'tcx>| -> Vec<vir::Expr> {
+            contract.args
+                .iter()
+                .map(|local| self.encode_prusti_local(*local).into())
+                .collect()
+        };
+        let old_measure = encode_spec_measure(
+            self.encoder,
+            measure,
+            &encode_args(self.procedure_contract()),
+            false,
+            None,
+        )?
+            .into_iter()
+            .map(|component| vir::Expr::labelled_old(PRECONDITION_LABEL, component))
+            .collect::<Vec<_>>();
+        let new_measure = encode_spec_measure(
+            self.encoder,
+            called_measure,
+            &encode_args(called_contract),
+            false,
+            None,
+        )?;
+        if old_measure.len() != new_measure.len() {
+            return Err(SpannedEncodingError::incorrect(
+                "the termination measures of mutually recursive functions must have the same \
+                number of components",
+                call_site_span,
+            ));
+        }
+        Ok(Some((new_measure, old_measure)))
+    }
+
     /// Encode the precondition with three expressions:
     /// - one for the type encoding
     /// - one for the type invariants
@@ -3099,13 +4957,11 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
     fn encode_precondition_expr(
         &self,
         contract: &ProcedureContract<'tcx>,
-        precondition_weakening: Option<typed::Assertion<'tcx>>,
     ) -> SpannedEncodingResult<(
         vir::Expr,
         Vec<vir::Expr>,
         vir::Expr,
         vir::Expr,
-        Option<vir::Expr>,
     )> {
         let borrow_infos = &contract.borrow_infos;
         let maybe_blocked_paths = if !borrow_infos.is_empty() {
@@ -3177,6 +5033,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             )?;
             func_spec.push(value);
         }
+        if let Some(value) = self.encode_builtin_vec_precondition(contract, &encoded_args)? {
+            func_spec.push(value);
+        }
         let precondition_spans = MultiSpan::from_spans(
             func_precondition
                 .iter()
@@ -3189,40 +5048,28 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         );
 
         let mut invs_spec: Vec<vir::Expr> = vec![];
-        for arg in contract.args.iter() {
-            // FIXME: this is somewhat hacky to avoid consistency errors with raw_ref args. this
-            // assumes that invariants for raw_ref types are always empty.
-            let ty = self.locals.get_type(*arg);
-            if !ty.is_unsafe_ptr() {
-                invs_spec.push(
-                    self.encoder.encode_invariant_func_app(
-                        ty,
-                        self.encode_prusti_local(*arg).into(),
-                    ).with_span(precondition_spans.clone())?
-                );
+        // Functions with `#[no_invariant]` may be called with values that break the invariants
+        if !self.encoder.env().has_prusti_attribute(contract.def_id, "no_invariant") {
+            for arg in contract.args.iter() {
+                // FIXME: this is somewhat hacky to avoid consistency errors with raw_ref args. this
+                // assumes that invariants for raw_ref types are always empty.
+                let ty = self.locals.get_type(*arg);
+                if !ty.is_unsafe_ptr() {
+                    invs_spec.push(
+                        self.encoder.encode_invariant_func_app(
+                            ty,
+                            self.encode_prusti_local(*arg).into(),
+                        ).with_span(precondition_spans.clone())?
+                    );
+                }
             }
         }
 
-        let precondition_weakening = precondition_weakening.map(|pw| {
-            self.encoder.encode_assertion(
-                &pw,
-                &self.mir,
-                None,
-                &encoded_args,
-                None,
-                false,
-                None,
-                ErrorCtxt::AssertMethodPreconditionWeakening(
-                    precondition_spans.clone()
-                ),
-            )
-        }).map_or(Ok(None), |v| v.map(Some))?;
         Ok((
             type_spec.into_iter().conjoin(),
             mandatory_type_spec,
             invs_spec.into_iter().conjoin(),
             func_spec.into_iter().conjoin(),
-            precondition_weakening,
         ))
     }
 
@@ -3230,15 +5077,16 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
     fn encode_preconditions(
         &mut self,
         start_cfg_block: CfgBlockIndex,
-        precondition_weakening: Option<typed::Assertion<'tcx>>,
     ) -> SpannedEncodingResult<()> {
         self.cfg_method
             .add_stmt(start_cfg_block, vir::Stmt::comment("Preconditions:"));
-        let (type_spec, mandatory_type_spec, invs_spec, func_spec, weakening_spec) =
-            self.encode_precondition_expr(
-                self.procedure_contract(),
-                precondition_weakening
-            )?;
+        let const_params_spec = self.encode_const_params();
+        self.cfg_method.add_stmt(
+            start_cfg_block,
+            vir::Stmt::Inhale(const_params_spec),
+        );
+        let (type_spec, mandatory_type_spec, invs_spec, func_spec) =
+            self.encode_precondition_expr(self.procedure_contract())?;
         self.cfg_method.add_stmt(
             start_cfg_block,
             vir::Stmt::Inhale(type_spec),
@@ -3253,15 +5101,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             start_cfg_block,
             vir::Stmt::Inhale(invs_spec),
         );
-        // Weakening assertion must be put before inhaling the precondition, otherwise the weakening
-        // soundness check becomes trivially satisfied.
-        if let Some(weakening_spec) = weakening_spec {
-            let pos = weakening_spec.pos();
-            self.cfg_method.add_stmt(
-                start_cfg_block,
-                vir::Stmt::Assert(weakening_spec, pos),
-            );
-        }
         self.cfg_method.add_stmt(
             start_cfg_block,
             vir::Stmt::Inhale(func_spec),
@@ -3270,9 +5109,67 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             start_cfg_block,
             vir::Stmt::Label(PRECONDITION_LABEL.to_string()),
         );
+        if config::counterexample() {
+            self.encode_counterexample_vars(start_cfg_block);
+        }
         Ok(())
     }
 
+    /// Declares the const generic parameters of the procedure (e.g. `N` in
+    /// `fn first<const N: usize>`) as ghost variables, and returns the bounds
+    /// of their types.
+    fn encode_const_params(&mut self) -> vir::Expr {
+        let tcx = self.encoder.env().tcx();
+        let mut bounds = vec![];
+        for arg in ty::List::identity_for_item(tcx, self.proc_def_id).iter() {
+            if let ty::subst::GenericArgKind::Const(value) = arg.unpack() {
+                if let ty::ConstKind::Param(param) = value.val {
+                    let encoded_param = self.encoder.encode_const_param_var(param);
+                    self.cfg_method.add_local_var(&encoded_param.name, encoded_param.typ.clone());
+                    bounds.extend(
+                        self.encoder.encode_type_bounds(&encoded_param.into(), value.ty)
+                    );
+                }
+            }
+        }
+        bounds.into_iter().conjoin()
+    }
+
+    /// Stores the snapshots of the arguments in ghost variables, so that the
+    /// initial values of the arguments can be reported in counterexamples.
+    fn encode_counterexample_vars(&mut self, start_cfg_block: CfgBlockIndex) {
+        let mir = self.mir;
+        for var_debug_info in &mir.var_debug_info {
+            let local = match var_debug_info.value {
+                mir::VarDebugInfoContents::Place(place) => match place.as_local() {
+                    Some(local) if mir.args_iter().any(|arg| arg == local) => local,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            let ty = self.mir_encoder.get_local_ty(local);
+            // Arguments whose snapshot cannot be encoded are not reported.
+            let snapshot_type = match self.encoder.encode_snapshot_type(ty) {
+                Ok(snapshot_type) => snapshot_type,
+                Err(_) => continue,
+            };
+            let name = self.encoder.register_counterexample_var(
+                var_debug_info.name.to_ident_string(),
+                ty,
+            );
+            self.cfg_method.add_local_var(&name, snapshot_type.clone());
+            let encoded_arg: vir::Expr = self.encode_prusti_local(local.into()).into();
+            self.cfg_method.add_stmt(
+                start_cfg_block,
+                vir::Stmt::Assign(
+                    vir::LocalVar::new(name, snapshot_type).into(),
+                    vir::Expr::snap_app(encoded_arg),
+                    vir::AssignKind::Copy,
+                ),
+            );
+        }
+    }
+
     /// Encode the magic wand used in the postcondition with its
     /// functional specification. Returns (lhs, rhs).
     fn encode_postcondition_magic_wand(
@@ -3397,6 +5294,35 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 lhs.push(assertion_lhs);
                 rhs.push(assertion_rhs);
             }
+            for place in &borrow_info.framed_paths {
+                // TODO: Use a better span
+                let (place_expr, place_ty, _) = self.encode_generic_place(
+                    contract.def_id, location, place
+                ).with_span(self.mir.span)?;
+                let value = if place_ty.is_primitive() {
+                    self.encoder.encode_value_expr(place_expr, place_ty)?
+                } else if self.encoder.supports_snapshot_equality(place_ty)? {
+                    vir::Expr::snap_app(place_expr)
+                } else {
+                    continue;
+                };
+                let framing = vir::Expr::eq_cmp(value.clone(), value.old(pre_label));
+                rhs.push(self.wrap_arguments_into_old(
+                    framing,
+                    pre_label,
+                    contract,
+                    &encoded_args
+                )?);
+            }
+            if let Some(pledge) = self.encode_builtin_vec_pledge(
+                contract,
+                pre_label,
+                post_label,
+                &encoded_args,
+                &encoded_return,
+            )? {
+                rhs.push(pledge);
+            }
             let lhs = lhs
                 .into_iter()
                 .conjoin();
@@ -3409,42 +5335,456 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
     }
 
-    /// Wrap function arguments used in the postcondition into ``old``:
-    ///
-    /// +   For references wrap the base ``_1.var_ref``.
-    /// +   For non-references wrap the entire place into old.
-    fn wrap_arguments_into_old(
+    /// Wrap function arguments used in the postcondition into ``old``:
+    ///
+    /// +   For references wrap the base ``_1.var_ref``.
+    /// +   For non-references wrap the entire place into old.
+    fn wrap_arguments_into_old(
+        &self,
+        mut assertion: vir::Expr,
+        pre_label: &str,
+        contract: &ProcedureContract<'tcx>,
+        encoded_args: &[vir::Expr],
+    ) -> SpannedEncodingResult<vir::Expr> {
+        // Snapshots of references only refer to `_1.val_ref` once they are patched.
+        assertion = self.encoder.patch_snapshots(assertion)
+            .with_span(self.mir.span)?;
+        for (encoded_arg, &arg) in encoded_args.iter().zip(&contract.args) {
+            let ty = self.locals.get_type(arg);
+            if self.mir_encoder.is_reference(ty) {
+                // If the argument is a reference, we wrap _1.val_ref into old.
+                let arg_span = self.mir_encoder.get_local_span(arg.into());
+                let (encoded_deref, ..) = self
+                    .mir_encoder
+                    .encode_deref(encoded_arg.clone(), ty)
+                    .with_span(arg_span)?;
+                let original_expr = encoded_deref;
+                let old_expr = vir::Expr::labelled_old(pre_label, original_expr.clone());
+                assertion = assertion.replace_place(&original_expr, &old_expr);
+            } else {
+                // If the argument is not a reference, we wrap entire path into old.
+                assertion = assertion.fold_places(|place| {
+                    let base: vir::Expr = place.get_base().into();
+                    if encoded_arg == &base {
+                        place.old(pre_label)
+                    } else {
+                        place
+                    }
+                });
+            }
+        }
+        Ok(assertion.remove_redundant_old())
+    }
+
+    /// Returns the method of `Vec` whose contract is `contract`, if the method has a built-in
+    /// contract that models the vector as the sequence of its elements.
+    fn get_builtin_vec_method(&self, contract: &ProcedureContract<'tcx>) -> Option<BuiltinVecMethod> {
+        let arg_tys: Vec<_> = contract.args.iter()
+            .map(|arg| self.locals.get_type(*arg))
+            .collect();
+        self.encoder.env().get_builtin_vec_method(contract.def_id, &arg_tys)
+    }
+
+    /// Returns the type and the place of the vector that the built-in contract of `method`
+    /// talks about: the returned vector of `Vec::new`, the target of the receiver otherwise.
+    fn encode_builtin_vec_place(
+        &self,
+        method: BuiltinVecMethod,
+        contract: &ProcedureContract<'tcx>,
+        encoded_args: &[vir::Expr],
+        encoded_return: &vir::Expr,
+    ) -> SpannedEncodingResult<(ty::Ty<'tcx>, vir::Expr)> {
+        if method == BuiltinVecMethod::New {
+            return Ok((self.locals.get_type(contract.returned_value), encoded_return.clone()));
+        }
+        let ref_ty = self.locals.get_type(contract.args[0]);
+        let (encoded_deref, vec_ty, _) = self.mir_encoder
+            .encode_deref(encoded_args[0].clone(), ref_ty)
+            .with_span(self.mir_encoder.get_local_span(contract.args[0].into()))?;
+        Ok((vec_ty, encoded_deref))
+    }
+
+    /// Encode the `usize` index passed to `Index::index` or `IndexMut::index_mut` of `Vec`.
+    fn encode_builtin_vec_index(
+        &self,
+        contract: &ProcedureContract<'tcx>,
+        encoded_args: &[vir::Expr],
+    ) -> SpannedEncodingResult<vir::Expr> {
+        self.encoder.encode_value_expr(
+            encoded_args[1].clone(),
+            self.locals.get_type(contract.args[1]),
+        ).with_span(self.mir_encoder.get_local_span(contract.args[1].into()))
+    }
+
+    /// Encode the precondition of the built-in contract of a method of `Vec`: the index
+    /// passed to `Index::index` and `IndexMut::index_mut` must be in bounds.
+    fn encode_builtin_vec_precondition(
+        &self,
+        contract: &ProcedureContract<'tcx>,
+        encoded_args: &[vir::Expr],
+    ) -> SpannedEncodingResult<Option<vir::Expr>> {
+        let method = match self.get_builtin_vec_method(contract) {
+            Some(method @ BuiltinVecMethod::Index) |
+            Some(method @ BuiltinVecMethod::IndexMut) => method,
+            _ => return Ok(None),
+        };
+        let encoded_return: vir::Expr = self.encode_prusti_local(contract.returned_value).into();
+        let (vec_ty, vec) = self.encode_builtin_vec_place(
+            method,
+            contract,
+            encoded_args,
+            &encoded_return,
+        )?;
+        let idx = self.encode_builtin_vec_index(contract, encoded_args)?;
+        let len = self.encoder.encode_snapshot_sequence_len(vec_ty, vec)
+            .with_span(self.mir.span)?;
+        let in_bounds = vir::Expr::and(
+            vir::Expr::le_cmp(0.into(), idx.clone()),
+            vir::Expr::lt_cmp(idx, len),
+        );
+        Ok(Some(self.encoder.patch_snapshots(in_bounds).with_span(self.mir.span)?))
+    }
+
+    /// Encode the postcondition of the built-in contract of a method of `Vec`, which relates
+    /// the sequence snapshots of the vector before and after the call. The elements are only
+    /// described if their snapshots support equality; otherwise only the length is.
+    fn encode_builtin_vec_postcondition(
+        &self,
+        contract: &ProcedureContract<'tcx>,
+        pre_label: &str,
+        encoded_args: &[vir::Expr],
+        encoded_return: &vir::Expr,
+    ) -> SpannedEncodingResult<Option<vir::Expr>> {
+        let method = match self.get_builtin_vec_method(contract) {
+            Some(method) => method,
+            None => return Ok(None),
+        };
+        let span = self.mir.span;
+        let (vec_ty, vec) = self.encode_builtin_vec_place(
+            method,
+            contract,
+            encoded_args,
+            encoded_return,
+        )?;
+        let elem_ty = self.encoder.env().get_vec_elem_type(vec_ty).unwrap();
+        let elem_equality = self.encoder.supports_snapshot_equality(elem_ty).with_span(span)?;
+        let len = self.encoder.encode_snapshot_sequence_len(vec_ty, vec.clone())
+            .with_span(span)?;
+        let old_len = len.clone().old(pre_label);
+        let read = |idx: vir::Expr| {
+            self.encoder.encode_snapshot_sequence_read(vec_ty, vec.clone(), idx)
+                .with_span(span)
+        };
+        // The elements with an index smaller than `bound` are unchanged
+        let unchanged_prefix = |bound: vir::Expr| -> SpannedEncodingResult<vir::Expr> {
+            let i = vir::LocalVar::new("i", vir::Type::Int);
+            let read_i = read(i.clone().into())?;
+            Ok(vir::Expr::forall(
+                vec![i.clone()],
+                vec![vir::Trigger::new(vec![read_i.clone()])],
+                vir::Expr::implies(
+                    vir::Expr::and(
+                        vir::Expr::le_cmp(0.into(), i.clone().into()),
+                        vir::Expr::lt_cmp(i.into(), bound),
+                    ),
+                    vir::Expr::eq_cmp(read_i.clone(), read_i.old(pre_label)),
+                ),
+            ))
+        };
+        // The target of the returned reference is the element at the given index
+        let result_deref = || -> SpannedEncodingResult<vir::Expr> {
+            let (encoded_deref, ..) = self.mir_encoder
+                .encode_deref(
+                    encoded_return.clone(),
+                    self.locals.get_type(contract.returned_value),
+                )
+                .with_span(span)?;
+            Ok(vir::Expr::snap_app(encoded_deref))
+        };
+
+        let mut conjuncts = vec![];
+        match method {
+            BuiltinVecMethod::New => {
+                conjuncts.push(vir::Expr::eq_cmp(len, 0.into()));
+            }
+            BuiltinVecMethod::Len => {
+                let result = self.encoder.encode_value_expr(
+                    encoded_return.clone(),
+                    self.locals.get_type(contract.returned_value),
+                ).with_span(span)?;
+                conjuncts.push(vir::Expr::eq_cmp(result, len));
+            }
+            BuiltinVecMethod::Push => {
+                conjuncts.push(vir::Expr::eq_cmp(
+                    len,
+                    vir::Expr::add(old_len.clone(), 1.into()),
+                ));
+                if elem_equality {
+                    conjuncts.push(unchanged_prefix(old_len.clone())?);
+                    conjuncts.push(vir::Expr::eq_cmp(
+                        read(old_len)?,
+                        vir::Expr::snap_app(encoded_args[1].clone()).old(pre_label),
+                    ));
+                }
+            }
+            BuiltinVecMethod::Pop => {
+                let option_ty = self.locals.get_type(contract.returned_value);
+                let option_equality = elem_equality && self.encoder
+                    .supports_snapshot_equality(option_ty)
+                    .with_span(span)?;
+                let variant_index = |name: rustc_span::Symbol| match option_ty.kind() {
+                    ty::TyKind::Adt(adt_def, _) => adt_def.variants.iter_enumerated()
+                        .find(|(_, variant_def)| variant_def.ident.name == name)
+                        .map(|(variant_index, _)| variant_index.index())
+                        .unwrap(),
+                    _ => unreachable!("{:?}", option_ty),
+                };
+                let mut when_empty = vec![vir::Expr::eq_cmp(len.clone(), 0.into())];
+                let last = vir::Expr::sub(len.clone(), 1.into()).old(pre_label);
+                let mut when_non_empty = vec![vir::Expr::eq_cmp(len.clone(), last.clone())];
+                if elem_equality {
+                    when_non_empty.push(unchanged_prefix(len)?);
+                }
+                if option_equality {
+                    let result = vir::Expr::snap_app(encoded_return.clone());
+                    when_empty.push(vir::Expr::eq_cmp(
+                        result.clone(),
+                        self.encoder.encode_snapshot_constructor(
+                            option_ty,
+                            variant_index(rustc_span::sym::None),
+                            vec![],
+                        ).with_span(span)?,
+                    ));
+                    when_non_empty.push(vir::Expr::eq_cmp(
+                        result,
+                        self.encoder.encode_snapshot_constructor(
+                            option_ty,
+                            variant_index(rustc_span::sym::Some),
+                            vec![read(last)?.old(pre_label)],
+                        ).with_span(span)?,
+                    ));
+                }
+                conjuncts.push(vir::Expr::implies(
+                    vir::Expr::eq_cmp(old_len.clone(), 0.into()),
+                    when_empty.into_iter().conjoin(),
+                ));
+                conjuncts.push(vir::Expr::implies(
+                    vir::Expr::gt_cmp(old_len, 0.into()),
+                    when_non_empty.into_iter().conjoin(),
+                ));
+            }
+            BuiltinVecMethod::Index => {
+                if elem_equality {
+                    let idx = self.encode_builtin_vec_index(contract, encoded_args)?;
+                    conjuncts.push(vir::Expr::eq_cmp(result_deref()?, read(idx)?));
+                }
+            }
+            BuiltinVecMethod::IndexMut => {
+                if elem_equality {
+                    let idx = self.encode_builtin_vec_index(contract, encoded_args)?;
+                    conjuncts.push(vir::Expr::eq_cmp(
+                        result_deref()?,
+                        read(idx)?.old(pre_label),
+                    ));
+                }
+            }
+        }
+        let assertion = self.wrap_arguments_into_old(
+            conjuncts.into_iter().conjoin(),
+            pre_label,
+            contract,
+            encoded_args,
+        )?;
+        Ok(Some(assertion))
+    }
+
+    /// Encode the assertion that holds when the reference returned by `IndexMut::index_mut` of
+    /// `Vec` expires: the element at the index has the last value of the reference, while the
+    /// length and the other elements are unchanged.
+    fn encode_builtin_vec_pledge(
+        &self,
+        contract: &ProcedureContract<'tcx>,
+        pre_label: &str,
+        post_label: &str,
+        encoded_args: &[vir::Expr],
+        encoded_return: &vir::Expr,
+    ) -> SpannedEncodingResult<Option<vir::Expr>> {
+        let method = match self.get_builtin_vec_method(contract) {
+            Some(method @ BuiltinVecMethod::IndexMut) => method,
+            _ => return Ok(None),
+        };
+        let span = self.mir.span;
+        let (vec_ty, vec) = self.encode_builtin_vec_place(
+            method,
+            contract,
+            encoded_args,
+            encoded_return,
+        )?;
+        let elem_ty = self.encoder.env().get_vec_elem_type(vec_ty).unwrap();
+        let len = self.encoder.encode_snapshot_sequence_len(vec_ty, vec.clone())
+            .with_span(span)?;
+        let mut conjuncts = vec![vir::Expr::eq_cmp(len.clone(), len.clone().old(pre_label))];
+        if self.encoder.supports_snapshot_equality(elem_ty).with_span(span)? {
+            let idx = self.encode_builtin_vec_index(contract, encoded_args)?;
+            let read = |idx: vir::Expr| {
+                self.encoder.encode_snapshot_sequence_read(vec_ty, vec.clone(), idx)
+                    .with_span(span)
+            };
+            let i = vir::LocalVar::new("i", vir::Type::Int);
+            let read_i = read(i.clone().into())?;
+            conjuncts.push(vir::Expr::forall(
+                vec![i.clone()],
+                vec![vir::Trigger::new(vec![read_i.clone()])],
+                vir::Expr::implies(
+                    vir::Expr::and(
+                        vir::Expr::and(
+                            vir::Expr::le_cmp(0.into(), i.clone().into()),
+                            vir::Expr::lt_cmp(i.clone().into(), len),
+                        ),
+                        vir::Expr::ne_cmp(i.into(), idx.clone()),
+                    ),
+                    vir::Expr::eq_cmp(read_i.clone(), read_i.old(pre_label)),
+                ),
+            ));
+            let (result_deref, ..) = self.mir_encoder
+                .encode_deref(
+                    encoded_return.clone(),
+                    self.locals.get_type(contract.returned_value),
+                )
+                .with_span(span)?;
+            conjuncts.push(vir::Expr::eq_cmp(
+                read(idx)?,
+                vir::Expr::snap_app(result_deref).old(WAND_LHS_LABEL),
+            ));
+        }
+        let mut assertion = self.wrap_arguments_into_old(
+            conjuncts.into_iter().conjoin(),
+            pre_label,
+            contract,
+            encoded_args,
+        )?;
+        // Like in pledges, the target of the returned reference is the one after the call
+        let (encoded_deref, ..) = self.mir_encoder
+            .encode_deref(
+                encoded_return.clone(),
+                self.locals.get_type(contract.returned_value),
+            )
+            .with_span(span)?;
+        let old_deref = vir::Expr::labelled_old(post_label, encoded_deref.clone());
+        assertion = assertion.replace_place(&encoded_deref, &old_deref);
+        Ok(Some(assertion.remove_redundant_old()))
+    }
+
+    /// Encode the frame condition of a function with `#[assigns(..)]` attributes: the places
+    /// behind its `&mut` arguments that are not assigned have the same value as before the
+    /// call. If only some fields of a struct are assigned, the other fields are unchanged.
+    fn encode_assigns_postcondition(
+        &self,
+        contract: &ProcedureContract<'tcx>,
+        pre_label: &str,
+        encoded_args: &[vir::Expr],
+    ) -> SpannedEncodingResult<Option<vir::Expr>> {
+        let tcx = self.encoder.env().tcx();
+        let assigned = utils::read_prusti_attrs_with_spans("assigns", tcx.get_attrs(contract.def_id));
+        if assigned.is_empty() {
+            return Ok(None);
+        }
+        let assigned_paths: Vec<(Vec<&str>, Span)> = assigned.iter()
+            .map(|(path, span)| (path.split('.').collect(), *span))
+            .collect();
+        let arg_names = tcx.fn_arg_names(contract.def_id);
+        let mut conjuncts = vec![];
+        for ((&arg, encoded_arg), arg_name) in contract.args.iter().zip(encoded_args).zip(arg_names) {
+            let arg_ty = self.locals.get_type(arg);
+            if !matches!(arg_ty.kind(), ty::TyKind::Ref(_, _, Mutability::Mut)) {
+                continue;
+            }
+            let arg_name = arg_name.name.to_ident_string();
+            let paths: Vec<_> = assigned_paths.iter()
+                .filter(|(path, _)| path[0] == arg_name)
+                .map(|(path, span)| (&path[1..], *span))
+                .collect();
+            let (encoded_deref, target_ty, _) = self.mir_encoder
+                .encode_deref(encoded_arg.clone(), arg_ty)
+                .with_span(self.mir.span)?;
+            self.encode_unassigned_places(
+                encoded_deref,
+                target_ty,
+                &paths,
+                pre_label,
+                &mut conjuncts,
+            )?;
+        }
+        let assigns_span = MultiSpan::from_spans(
+            assigned.iter().map(|(_, span)| *span).collect()
+        );
+        let pos = self.encoder.error_manager().register_span(assigns_span);
+        let assertion = self.wrap_arguments_into_old(
+            conjuncts.into_iter().conjoin(),
+            pre_label,
+            contract,
+            encoded_args,
+        )?;
+        Ok(Some(assertion.set_default_pos(pos)))
+    }
+
+    /// Collects the equalities stating that the parts of `place` that are not covered by the
+    /// assigned `paths` (relative to `place`) are unchanged. Parts whose snapshots do not
+    /// support equality are described field by field, if they are structs, or not at all.
+    fn encode_unassigned_places(
         &self,
-        mut assertion: vir::Expr,
+        place: vir::Expr,
+        ty: ty::Ty<'tcx>,
+        paths: &[(&[&str], Span)],
         pre_label: &str,
-        contract: &ProcedureContract<'tcx>,
-        encoded_args: &[vir::Expr],
-    ) -> SpannedEncodingResult<vir::Expr> {
-        for (encoded_arg, &arg) in encoded_args.iter().zip(&contract.args) {
-            let ty = self.locals.get_type(arg);
-            if self.mir_encoder.is_reference(ty) {
-                // If the argument is a reference, we wrap _1.val_ref into old.
-                let arg_span = self.mir_encoder.get_local_span(arg.into());
-                let (encoded_deref, ..) = self
-                    .mir_encoder
-                    .encode_deref(encoded_arg.clone(), ty)
-                    .with_span(arg_span)?;
-                let original_expr = encoded_deref;
-                let old_expr = vir::Expr::labelled_old(pre_label, original_expr.clone());
-                assertion = assertion.replace_place(&original_expr, &old_expr);
-            } else {
-                // If the argument is not a reference, we wrap entire path into old.
-                assertion = assertion.fold_places(|place| {
-                    let base: vir::Expr = place.get_base().into();
-                    if encoded_arg == &base {
-                        place.old(pre_label)
-                    } else {
-                        place
-                    }
-                });
+        conjuncts: &mut Vec<vir::Expr>,
+    ) -> SpannedEncodingResult<()> {
+        if paths.iter().any(|(path, _)| path.is_empty()) {
+            // The whole place may be modified
+            return Ok(());
+        }
+        let span = paths.first().map_or(self.mir.span, |(_, span)| *span);
+        if paths.is_empty() && self.encoder.supports_snapshot_equality(ty).with_span(span)? {
+            let snapshot = vir::Expr::snap_app(place);
+            conjuncts.push(vir::Expr::eq_cmp(snapshot.clone(), snapshot.old(pre_label)));
+            return Ok(());
+        }
+        let (adt_def, substs) = match ty.kind() {
+            ty::TyKind::Adt(adt_def, substs) if adt_def.is_struct() => (adt_def, substs),
+            _ if paths.is_empty() => return Ok(()),
+            _ => return Err(SpannedEncodingError::incorrect(
+                format!("the fields of type `{}` cannot be assigned separately", ty),
+                span,
+            )),
+        };
+        let tcx = self.encoder.env().tcx();
+        let fields = &adt_def.non_enum_variant().fields;
+        for (path, span) in paths {
+            if !fields.iter().any(|field| field.ident.as_str() == path[0]) {
+                return Err(SpannedEncodingError::incorrect(
+                    format!("type `{}` has no field `{}`", ty, path[0]),
+                    *span,
+                ));
             }
         }
-        Ok(assertion.remove_redundant_old())
+        for field in fields {
+            let field_name = field.ident.as_str();
+            let field_ty = field.ty(tcx, substs);
+            let field_paths: Vec<_> = paths.iter()
+                .filter(|(path, _)| path[0] == &*field_name)
+                .map(|(path, span)| (&path[1..], *span))
+                .collect();
+            let encoded_field = self.encoder.encode_struct_field(&field_name, field_ty)
+                .with_span(span)?;
+            self.encode_unassigned_places(
+                place.clone().field(encoded_field),
+                field_ty,
+                &field_paths,
+                pre_label,
+                conjuncts,
+            )?;
+        }
+        Ok(())
     }
 
     /// Encode the postcondition with three expressions:
@@ -3459,7 +5799,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         &mut self,
         location: Option<mir::Location>,
         contract: &ProcedureContract<'tcx>,
-        postcondition_strengthening: Option<typed::Assertion<'tcx>>,
         pre_label: &str,
         post_label: &str,
         magic_wand_store_info: Option<(mir::Location, &HashMap<vir::Expr, vir::Expr>)>,
@@ -3473,7 +5812,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         vir::Expr,                   // Functional specification.
         Vec<vir::Expr>,              // Magic wands.
         Vec<(vir::Expr, vir::Expr)>, // Read permissions that need to be transferred to a new place.
-        Option<vir::Expr>, // Specification strengthening, in case of trait method implementation.
     )> {
         let mut type_spec = vec![];
         let mut invs_spec = vec![];
@@ -3566,6 +5904,11 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         let mut func_spec = vec![];
         let mut func_spec_spans = vec![];
         let func_postcondition = contract.functional_postcondition();
+        let opaque_result_tymap = self.encoder.get_opaque_result_tymap(
+            contract.def_id,
+            func_postcondition,
+        );
+        let _cleanup_token = self.encoder.push_temp_tymap(opaque_result_tymap);
         for typed_assertion in func_postcondition {
             let mut assertion = self.encoder.encode_assertion(
                 &typed_assertion,
@@ -3586,45 +5929,59 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             )?;
             func_spec.push(assertion);
         }
+        // Methods that take `&mut self` have to preserve the history invariants of `Self`,
+        // which refer to the receiver as their only argument.
+        for typed_assertion in self.encoder.get_history_invariants(contract.def_id) {
+            let mut assertion = self.encoder.encode_assertion(
+                &typed_assertion,
+                &self.mir,
+                Some(pre_label),
+                &encoded_args[..1],
+                None,
+                false,
+                None,
+                ErrorCtxt::GenericExpression,
+            )?;
+            func_spec_spans.extend(typed::Spanned::get_spans(&typed_assertion, &self.mir, self.encoder.env().tcx()));
+            assertion = self.wrap_arguments_into_old(
+                assertion,
+                pre_label,
+                contract,
+                &encoded_args
+            )?;
+            func_spec.push(assertion);
+        }
+        if let Some(assertion) = self.encode_builtin_vec_postcondition(
+            contract,
+            pre_label,
+            &encoded_args,
+            &encoded_return,
+        )? {
+            func_spec.push(assertion);
+        }
+        if let Some(assertion) = self.encode_assigns_postcondition(
+            contract,
+            pre_label,
+            &encoded_args,
+        )? {
+            func_spec.push(assertion);
+        }
         let postcondition_span = MultiSpan::from_spans(func_spec_spans);
         let func_spec_pos = self.encoder.error_manager()
             .register_span(postcondition_span.clone());
 
-        // Encode invariant for return value
-        invs_spec.push(
-            self.encoder.encode_invariant_func_app(
-                self.locals.get_type(contract.returned_value),
-                encoded_return.clone(),
-            ).with_span(postcondition_span.clone())?
-        );
-
-        // Encode possible strengthening, in case of trait method implementation
-        let strengthening_spec: Option<Expr> = postcondition_strengthening
-            .map(|ps|
-                self.encoder.encode_assertion(
-                    &ps,
-                    &self.mir,
-                    Some(pre_label),
-                    &encoded_args,
-                    Some(&encoded_return),
-                    false,
-                    None,
-                    ErrorCtxt::AssertMethodPostconditionStrengthening(
-                        postcondition_span.clone()
-                    ),
-                )
-            )
-            .map_or(Ok(None), |r| r.map(Some))
-            .and_then(|opt_assertion|
-                opt_assertion.map(|assertion|
-                    self.wrap_arguments_into_old(
-                        assertion,
-                        pre_label,
-                        contract,
-                        &encoded_args
-                    )
-                ).map_or(Ok(None), |r| r.map(Some))
-            )?;
+        // Encode invariant for return value. Functions with `#[no_invariant]` may return values
+        // that break the invariants.
+        if self.encoder.env().has_prusti_attribute(contract.def_id, "no_invariant") {
+            invs_spec.clear();
+        } else {
+            invs_spec.push(
+                self.encoder.encode_invariant_func_app(
+                    self.locals.get_type(contract.returned_value),
+                    encoded_return.clone(),
+                ).with_span(postcondition_span.clone())?
+            );
+        }
 
         let full_func_spec = func_spec.into_iter()
             .conjoin()
@@ -3637,7 +5994,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             full_func_spec,
             magic_wands,
             read_transfer,
-            strengthening_spec,
         ))
     }
 
@@ -3838,7 +6194,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
     fn encode_postconditions(
         &mut self,
         return_cfg_block: CfgBlockIndex,
-        postcondition_strengthening: Option<typed::Assertion<'tcx>>,
     ) -> SpannedEncodingResult<()> {
         // This clone is only due to borrow checker restrictions
         let contract = self.procedure_contract().clone();
@@ -3855,11 +6210,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             func_spec,
             magic_wands,
             _,
-            strengthening_spec
         ) = self.encode_postcondition_expr(
             None,
             &contract,
-            postcondition_strengthening,
             PRECONDITION_LABEL,
             &postcondition_label,
             None,
@@ -3987,21 +6340,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         self.cfg_method
             .add_stmt(return_cfg_block, obtain_return_stmt);
 
-        // Assert possible strengthening
-        self.cfg_method.add_stmt(
-            return_cfg_block,
-            vir::Stmt::comment("Assert possible strengthening"),
-        );
-        if let Some(strengthening_spec) = strengthening_spec {
-            let patched_strengthening_spec =
-                self.replace_old_places_with_ghost_vars(None, strengthening_spec);
-            let pos = patched_strengthening_spec.pos();
-            self.cfg_method.add_stmt(
-                return_cfg_block,
-                vir::Stmt::Assert(patched_strengthening_spec, pos),
-            );
-        }
-
         // Assert functional specification of postcondition
         self.cfg_method.add_stmt(
             return_cfg_block,
@@ -4138,7 +6476,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         );
         let permissions_forest = self
             .loop_encoder
-            .compute_loop_invariant(loop_head, loop_inv);
+            .compute_loop_invariant(loop_head, loop_inv)
+            .map_err(|error| self.translate_permission_forest_error(error, loop_head))?;
         debug!("permissions_forest: {:?}", permissions_forest);
         let loops = self.loop_encoder.get_enclosing_loop_heads(loop_head);
         let enclosing_permission_forest = if loops.len() > 1 {
@@ -4147,7 +6486,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             Some(self.loop_encoder.compute_loop_invariant(
                 enclosing_loop_head,
                 self.cached_loop_invariant_block[&enclosing_loop_head],
-            ))
+            ).map_err(|error| self.translate_permission_forest_error(error, enclosing_loop_head))?)
         } else {
             None
         };
@@ -4158,8 +6497,19 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         let mut array_pred_perms = HashMap::new();
 
         for tree in permissions_forest.get_trees().iter() {
-            for (kind, mir_place) in tree.get_permissions().into_iter() {
-                if kind.is_none() {
+            // The permissions that are given by the invariant of the enclosing
+            // loop are already held at the loop head, so we exhale and inhale
+            // only the remaining ones.
+            let tree_permissions = match enclosing_permission_forest
+                .as_ref()
+                .and_then(|forest| forest.get_tree(tree.get_root_place()))
+            {
+                Some(enclosing_tree) => tree.difference(enclosing_tree),
+                None => tree.get_permissions(),
+            };
+            for (kind, mir_place) in tree_permissions.into_iter() {
+                // The marker of a borrowed tree gives no permission.
+                if kind.is_none() || kind == PermissionKind::Borrowed {
                     continue;
                 }
                 // we want to check if array or other place expr, so we call the mir_encoder
@@ -4300,6 +6650,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     // Give no permission to this node and the entire subtree. This
                     // must be a leaf node.
                     PermissionKind::None => unreachable!(),
+                    // Skipped above, it only marks the root of a borrowed tree.
+                    PermissionKind::Borrowed => unreachable!(),
                 };
             }
         }
@@ -4352,12 +6704,11 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         res
     }
 
-    /// Encode the functional specification of a loop
-    fn encode_loop_invariant_specs(
+    /// Get the specifications given by the `body_invariant!(..)` of a loop.
+    fn get_loop_specifications(
         &self,
         loop_head: BasicBlockIndex,
-        loop_inv_block: BasicBlockIndex,
-    ) -> SpannedEncodingResult<(Vec<vir::Expr>, MultiSpan)> {
+    ) -> Vec<typed::LoopSpecification<'tcx>> {
         let spec_blocks = self.get_loop_spec_blocks(loop_head);
         trace!(
             "loop head {:?} has spec blocks {:?}",
@@ -4374,10 +6725,160 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     _,
                     mir::Rvalue::Aggregate(box mir::AggregateKind::Closure(cl_def_id, _), _),
                 )) = stmt.kind {
-                    specs.extend(self.encoder.get_loop_specs(cl_def_id).unwrap().invariant);
+                    // Ghost statements in the loop body are not part of the loop specification.
+                    if let Some(spec) = self.encoder.get_loop_specs(cl_def_id) {
+                        specs.push(spec);
+                    }
+                }
+            }
+        }
+        specs
+    }
+
+    /// Encode the `prusti_assert!(..)`, `prusti_assume!(..)` and `label!(..)` ghost statements
+    /// that are type-checked in the specification blocks starting at `spec_block`, which is
+    /// entered from `bbi`.
+    fn encode_ghost_statements(
+        &self,
+        bbi: BasicBlockIndex,
+        spec_block: BasicBlockIndex,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let encoded_args: Vec<vir::Expr> = self
+            .mir
+            .args_iter()
+            .map(|local| self.mir_encoder.encode_local(local).map(|l| l.into()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut stmts = vec![];
+        for (cl_def_id, span) in self.procedure.get_ghost_statements(spec_block) {
+            if let Some(label) = self.procedure.get_ghost_label(cl_def_id) {
+                stmts.push(vir::Stmt::comment(format!("label! at {:?}", span)));
+                stmts.push(vir::Stmt::Label(format!("{}{}", USER_LABEL_PREFIX, label)));
+                continue;
+            }
+            if self.procedure.is_ghost_block_marker(cl_def_id) {
+                // The ghost code itself is encoded like the real code
+                continue;
+            }
+            let spec = self.encoder.get_ghost_statement_specs(cl_def_id).unwrap();
+            // The state at the beginning of the specification block is the one in which the
+            // ghost statement is evaluated.
+            let encoded_spec = self.encoder.encode_assertion(
+                &spec.assertion,
+                &self.mir,
+                Some(PRECONDITION_LABEL),
+                &encoded_args,
+                None,
+                false,
+                Some(spec_block),
+                ErrorCtxt::GenericExpression,
+            )?;
+            self.check_user_labels(&encoded_spec, bbi, span.into())?;
+            match spec.typ {
+                typed::SpecType::GhostAssertion => {
+                    stmts.push(vir::Stmt::comment(format!("prusti_assert! at {:?}", span)));
+                    let pos = self.encoder.error_manager().register(
+                        span,
+                        ErrorCtxt::AssertGhostStatement,
+                    );
+                    stmts.push(vir::Stmt::Assert(encoded_spec, pos));
+                }
+                typed::SpecType::GhostAssumption => {
+                    stmts.push(vir::Stmt::comment(format!("prusti_assume! at {:?}", span)));
+                    stmts.push(vir::Stmt::Inhale(encoded_spec));
+                }
+                ref x => unreachable!("{:?}", x),
+            }
+        }
+        Ok(stmts)
+    }
+
+    /// Check that the labels of the `labelled_old(..)` expressions in `expr` are defined by
+    /// `label!(..)` statements that are executed on every path to `bbi`.
+    fn check_user_labels(
+        &self,
+        expr: &vir::Expr,
+        bbi: BasicBlockIndex,
+        span: MultiSpan,
+    ) -> SpannedEncodingResult<()> {
+        struct UserLabelCollector {
+            labels: Vec<String>,
+        }
+        impl vir::ExprWalker for UserLabelCollector {
+            fn walk_labelled_old(&mut self, label: &str, body: &vir::Expr, _pos: &vir::Position) {
+                if let Some(name) = label.strip_prefix(USER_LABEL_PREFIX) {
+                    self.labels.push(name.to_string());
                 }
+                self.walk(body);
             }
         }
+        let mut collector = UserLabelCollector { labels: vec![] };
+        vir::ExprWalker::walk(&mut collector, expr);
+        if collector.labels.is_empty() {
+            return Ok(());
+        }
+        let ghost_labels = self.procedure.get_ghost_labels();
+        for label in collector.labels {
+            let is_defined = ghost_labels.iter().any(|(name, label_bbi, _)| {
+                name == &label && label_bbi != &bbi && self.procedure.dominates(*label_bbi, bbi)
+            });
+            if !is_defined {
+                return Err(SpannedEncodingError::incorrect(
+                    format!(
+                        "the label `{}` is not defined on every path reaching this expression",
+                        label,
+                    ),
+                    span,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode the termination measure of a loop, given by `body_invariant!(decreases(..))`.
+    fn encode_loop_measure(
+        &self,
+        loop_head: BasicBlockIndex,
+        loop_inv_block: BasicBlockIndex,
+    ) -> SpannedEncodingResult<Option<(Vec<vir::Expr>, MultiSpan)>> {
+        let measure = match self.get_loop_specifications(loop_head)
+            .into_iter()
+            .find_map(|spec| spec.variant)
+        {
+            Some(measure) => measure,
+            None => return Ok(None),
+        };
+        let encoded_args: Vec<vir::Expr> = self
+            .mir
+            .args_iter()
+            .map(|local| self.mir_encoder.encode_local(local).map(|l| l.into()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let encoded_measure = encode_spec_measure(
+            self.encoder,
+            &measure,
+            &encoded_args,
+            false,
+            Some(loop_inv_block),
+        )?;
+        let measure_spans = typed::Spanned::get_spans(&measure, &self.mir, self.encoder.env().tcx());
+        if encoded_measure.iter().any(|expr| expr_mentions_local_var(expr, LOOP_ITERATIONS_VAR)) {
+            return Err(SpannedEncodingError::incorrect(
+                "loop_iterations() can only be used in loop invariants",
+                MultiSpan::from_spans(measure_spans),
+            ));
+        }
+        Ok(Some((encoded_measure, MultiSpan::from_spans(measure_spans))))
+    }
+
+    /// Encode the functional specification of a loop
+    fn encode_loop_invariant_specs(
+        &self,
+        loop_head: BasicBlockIndex,
+        loop_inv_block: BasicBlockIndex,
+    ) -> SpannedEncodingResult<(Vec<vir::Expr>, MultiSpan)> {
+        let specs: Vec<_> = self.get_loop_specifications(loop_head)
+            .into_iter()
+            .flat_map(|spec| spec.invariant)
+            .collect();
         trace!("specs: {:?}", specs);
 
         let mut encoded_specs = vec![];
@@ -4388,6 +6889,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 .args_iter()
                 .map(|local| self.mir_encoder.encode_local(local).map(|l| l.into()))
                 .collect::<Result<Vec<_>, _>>()?;
+            let loop_entry_label = &self.loop_entry_labels[&loop_head];
+            let iteration_counter = &self.loop_iteration_counters[&loop_head];
             for assertion in &specs {
                 // TODO: Mmm... are these parameters correct?
                 let encoded_spec = self.encoder.encode_assertion(
@@ -4399,8 +6902,21 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     false,
                     Some(loop_inv_block),
                     ErrorCtxt::GenericExpression,
-                )?;
+                )?.map_old_expr_label(|label| {
+                    if label == LOOP_ENTRY_LABEL {
+                        loop_entry_label.clone()
+                    } else {
+                        label
+                    }
+                });
+                let encoded_spec = replace_loop_iterations(encoded_spec, iteration_counter);
                 let spec_spans = typed::Spanned::get_spans(assertion, &self.mir, self.encoder.env().tcx());
+                // The labels have to be defined before the loop is entered
+                self.check_user_labels(
+                    &encoded_spec,
+                    loop_head,
+                    MultiSpan::from_spans(spec_spans.clone()),
+                )?;
                 let spec_pos = self
                     .encoder
                     .error_manager()
@@ -4411,6 +6927,20 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             trace!("encoded_specs: {:?}", encoded_specs);
         }
 
+        // The invariant of the ranges iterated by a `for` loop is added to the user's one
+        let loop_head_span = self.mir_encoder.get_span_of_basic_block(loop_head);
+        let range_invariant = self.encode_range_loop_invariant(loop_head, loop_inv_block)
+            .with_span(loop_head_span)?;
+        if !range_invariant.is_empty() {
+            let range_invariant_pos = self
+                .encoder
+                .error_manager()
+                .register(loop_head_span, ErrorCtxt::GenericExpression);
+            encoded_specs.extend(
+                range_invariant.into_iter().map(|expr| expr.set_default_pos(range_invariant_pos))
+            );
+        }
+
         Ok((encoded_specs, MultiSpan::from_spans(encoded_spec_spans)))
     }
 
@@ -4709,7 +7239,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         // inhale infos about array contents back
         let i_var: vir::Expr = vir_local!{ i: Int }.into();
         let zero_le_i = vir!{ [vir::Expr::from(0)] <= [ i_var ] };
-        let i_lt_len = vir!{ [ i_var ] < [ vir::Expr::from(at.array_len) ] };
+        let i_lt_len = vir!{ [ i_var ] < [ at.array_len.to_expr() ] };
         let i_ne_idx = vir!{ [ i_var ] != [ old(idx_val_int.clone()) ] };
         let idx_conditions = vir!{ [zero_le_i] && ([i_lt_len] && [i_ne_idx]) };
         let lookup_array_i = at.encode_lookup_pure_call(encoded_array.clone(), i_var);
@@ -4832,7 +7362,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     // implement proper support for handling constants of
                     // non-primitive types.
                     if !elements.is_empty() {
-                        unimplemented!("Only ZSTs are currently supported, got: {:?}", elements);
+                        return Err(SpannedEncodingError::unsupported(
+                            format!("constants of type '{:?}' are not supported", ty),
+                            self.mir_encoder.get_span_of_location(location),
+                        ));
                     }
                     // Since we have a ZST, we do not need to do anything to
                     // encode it.
@@ -5107,7 +7640,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 // Leave `box_content` uninitialized
                 Ok(stmts)
             }
-            mir::NullOp::SizeOf => unimplemented!(),
+            mir::NullOp::SizeOf => Err(SpannedEncodingError::unsupported(
+                "computing the size of a type is not supported",
+                self.mir_encoder.get_span_of_location(location),
+            )),
         }
     }
 
@@ -5302,21 +7838,35 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
         let slice_len_call = slice_types.encode_slice_len_call(slice_expr.clone());
 
-        stmts.push(vir::Stmt::Inhale(vir!{ [slice_len_call] == [vir::Expr::from(array_types.array_len)] }));
+        stmts.push(vir::Stmt::Inhale(vir!{ [slice_len_call] == [array_types.array_len.to_expr()] }));
 
-        for idx in 0..array_types.array_len {
-            let array_lookup_call = array_types.encode_lookup_pure_call(
-                rhs_expr.clone(),
-                vir::Expr::from(idx),
-            );
+        if let Some(array_len) = array_types.array_len.as_fixed() {
+            for idx in 0..array_len {
+                let array_lookup_call = array_types.encode_lookup_pure_call(
+                    rhs_expr.clone(),
+                    vir::Expr::from(idx),
+                );
 
-            let slice_lookup_call = slice_types.encode_lookup_pure_call(
-                slice_expr.clone(),
-                vir::Expr::from(idx),
-            );
+                let slice_lookup_call = slice_types.encode_lookup_pure_call(
+                    slice_expr.clone(),
+                    vir::Expr::from(idx),
+                );
 
+                stmts.push(vir::Stmt::Inhale(
+                    vir!{ [array_lookup_call] == [slice_lookup_call] }
+                ));
+            }
+        } else {
+            // The length is a const generic parameter, so relate the elements with a quantifier
+            let i_var: vir::Expr = vir_local!{ i: Int }.into();
+            let array_lookup_call = array_types.encode_lookup_pure_call(rhs_expr, i_var.clone());
+            let slice_lookup_call = slice_types.encode_lookup_pure_call(slice_expr, i_var.clone());
+            let zero_le_i = vir!{ [vir::Expr::from(0)] <= [ i_var ] };
+            let i_lt_len = vir!{ [ i_var ] < [ array_types.array_len.to_expr() ] };
+            let lookups_equal = vir!{ [array_lookup_call] == [slice_lookup_call] };
+            let forall_body = vir!{ ([zero_le_i] && [i_lt_len]) ==> [lookups_equal] };
             stmts.push(vir::Stmt::Inhale(
-                vir!{ [array_lookup_call] == [slice_lookup_call] }
+                vir!{ forall i: Int :: { [array_lookup_call] } [ forall_body ] }
             ));
         }
 
@@ -5355,7 +7905,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 stmts.extend(
                     self.encode_copy_value_assign(
                         encoded_lhs,
-                        array_types.array_len.into(),
+                        array_types.array_len.to_expr(),
                         dst_ty,
                         location,
                     )?
@@ -5590,17 +8140,11 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             ty::TyKind::Adt(_, _)
             | ty::TyKind::Tuple(_)
             | ty::TyKind::Param(_)
-            | ty::TyKind::Array(_, _) => {
+            | ty::TyKind::Array(_, _)
+            | ty::TyKind::Closure(_, _) => {
                 self.encode_copy_snapshot_value(src, dst)?
             }
 
-            ty::TyKind::Closure(_, _) => {
-                // TODO: (can a closure be copy-assigned?)
-                // encode a closure deep copy or at least a stub
-                debug!("warning: ty::TyKind::Closure not implemented yet");
-                Vec::new()
-            }
-            
             _ => {
                 return Err(SpannedEncodingError::unsupported(
                     format!("copy operation for an unsupported type {:?}", self_ty.kind()),
@@ -5712,19 +8256,48 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         location,
                     )?);
                 }
+
+                // Relate the snapshot of a value of a recursive type to the snapshots of its
+                // fields. This makes the snapshot of a value that is built step by step a
+                // nesting of constructors, on which recursive pure functions can be evaluated.
+                if !adt_def.is_union() && self.encoder.has_recursive_snapshot(ty).with_span(span)? {
+                    let tcx = self.encoder.env().tcx();
+                    let mut field_snapshots = vec![];
+                    for field in &variant_def.fields {
+                        let encoded_field = self.encoder
+                            .encode_struct_field(&field.ident.as_str(), field.ty(tcx, subst))
+                            .with_span(span)?;
+                        field_snapshots.push(vir::Expr::snap_app(dst_base.clone().field(encoded_field)));
+                    }
+                    let snapshot = self.encoder
+                        .encode_snapshot_constructor(ty, variant_index.index(), field_snapshots)
+                        .with_span(span)?;
+                    stmts.push(vir::Stmt::Inhale(
+                        vir::Expr::eq_cmp(vir::Expr::snap_app(dst.clone()), snapshot),
+                    ));
+                }
             }
 
-            mir::AggregateKind::Closure(def_id, _substs) => {
+            mir::AggregateKind::Closure(def_id, substs) => {
                 debug_assert!(!self.encoder.is_spec_closure(def_id), "spec closure: {:?}", def_id);
-                // TODO: assign to closure; this case should only handle assign
-                // of the same closure type (== instances of the same syntactic
-                // closure)
-                // closure state encoding should first be implemented in
-                // type_encoder
-                // this case might also need to assert history invariants?
-                //
-                // for now we generate nothing to at least allow
-                // let f = closure!(...);
+                // The state of the closure is made of the captured places, see
+                // `TypeEncoder::encode_predicate_def`.
+                // TODO: this case might also need to assert history invariants?
+                for (field_num, (operand, field_ty)) in operands.iter()
+                    .zip(substs.as_closure().upvar_tys())
+                    .enumerate()
+                {
+                    let field_name = format!("closure_{}", field_num);
+                    let encoded_field = self
+                        .encoder
+                        .encode_raw_ref_field(field_name, field_ty)
+                        .with_span(span)?;
+                    stmts.extend(self.encode_assign_operand(
+                        &dst.clone().field(encoded_field),
+                        operand,
+                        location,
+                    )?);
+                }
             }
 
             mir::AggregateKind::Array(..) => {
@@ -5772,6 +8345,62 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         &self.label_after_location[&location]
     }
 
+    /// Check that each `body_invariant!(..)` is in the body of a loop, and that the invariants
+    /// of a loop are next to each other. The invariants are conjoined and hold at the position
+    /// of the first one, so user code between them would be checked against the wrong state.
+    fn check_loop_invariant_placement(&self) -> SpannedEncodingResult<()> {
+        let mut loop_entries: HashMap<BasicBlockIndex, Vec<(BasicBlockIndex, Span)>> =
+            HashMap::new();
+        for (bbi, span) in self.procedure.get_loop_invariant_entries() {
+            if let Some(loop_head) = self.loop_encoder.get_loop_head(bbi) {
+                loop_entries.entry(loop_head).or_default().push((bbi, span));
+            } else {
+                return Err(SpannedEncodingError::incorrect(
+                    "`body_invariant!(..)` can only be used in the body of a loop",
+                    span,
+                ).set_help("use `prusti_assert!(..)` to check an assertion outside of loops"));
+            }
+        }
+
+        for (loop_head, entries) in loop_entries {
+            let loop_inv_bbi = self.cached_loop_invariant_block[&loop_head];
+            // The blocks that can be reached from the first invariant without executing
+            // user code, i.e. code that does not come from the expansion of a macro.
+            let mut adjacent_blocks = HashSet::new();
+            let mut to_visit = vec![loop_inv_bbi];
+            while let Some(curr) = to_visit.pop() {
+                if !adjacent_blocks.insert(curr) {
+                    continue;
+                }
+                for &succ in self.procedure.successors(curr) {
+                    let bb_data = &self.mir[succ];
+                    let is_generated = bb_data.statements.iter()
+                        .all(|stmt| match stmt.kind {
+                            mir::StatementKind::StorageLive(..)
+                            | mir::StatementKind::StorageDead(..)
+                            | mir::StatementKind::Nop => true,
+                            _ => stmt.source_info.span.from_expansion(),
+                        });
+                    if !self.procedure.is_spec_block(succ)
+                        && self.loop_encoder.get_loop_head(succ) == Some(loop_head)
+                        && is_generated
+                    {
+                        to_visit.push(succ);
+                    }
+                }
+            }
+            let misplaced_entry = entries.iter()
+                .find(|(bbi, _)| !adjacent_blocks.contains(bbi));
+            if let Some(&(_, span)) = misplaced_entry {
+                return Err(SpannedEncodingError::incorrect(
+                    "the invariants of a loop must be next to each other",
+                    span,
+                ).set_help(LOOP_INVARIANT_PLACEMENT_HELP));
+            }
+        }
+        Ok(())
+    }
+
     fn get_loop_span(&self, loop_head: mir::BasicBlock) -> Span {
         let loop_info = self.loop_encoder.loops();
         debug_assert!(loop_info.is_loop_head(loop_head));
@@ -5868,7 +8497,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
         // exhale preconditions
         stmts.push(vir::Stmt::Assert(
-            vir!{ [idx_val_int] < [ vir::Expr::from(at.array_len) ] },
+            vir!{ [idx_val_int] < [ at.array_len.to_expr() ] },
             vir::Position::default(),
         ));
 
@@ -5905,7 +8534,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         let i_var: vir::Expr = vir_local!{ i: Int }.into();
 
         let zero_le_i = vir!{ [ vir::Expr::from(0) ] <= [ i_var ] };
-        let i_lt_len = vir!{ [ i_var ] < [ vir::Expr::from(at.array_len) ] };
+        let i_lt_len = vir!{ [ i_var ] < [ at.array_len.to_expr() ] };
         let i_ne_idx = vir!{ [ i_var ] != [ old(idx_val_int.clone()) ] };
         let idx_conditions = vir!{ [zero_le_i] && ([i_lt_len] && [i_ne_idx]) };
         let lookup_array_i = at.encode_lookup_pure_call(encoded_base_expr.clone(), i_var);
@@ -5981,3 +8610,57 @@ enum ArrayAccessKind {
 fn convert_loans_to_borrows(loans: &[facts::Loan]) -> Vec<Borrow> {
     loans.iter().map(|l| l.into()).collect()
 }
+
+/// Replace the placeholder of `loop_iterations()` expressions with the iteration counter of the
+/// loop.
+fn replace_loop_iterations(expr: vir::Expr, counter: &vir::LocalVar) -> vir::Expr {
+    struct LoopIterationsReplacer<'a> {
+        counter: &'a vir::LocalVar,
+    }
+    impl<'a> vir::ExprFolder for LoopIterationsReplacer<'a> {
+        fn fold_local(&mut self, var: vir::LocalVar, pos: vir::Position) -> vir::Expr {
+            if var.name == LOOP_ITERATIONS_VAR {
+                vir::Expr::Local(self.counter.clone(), pos)
+            } else {
+                vir::Expr::Local(var, pos)
+            }
+        }
+    }
+    vir::ExprFolder::fold(&mut LoopIterationsReplacer { counter }, expr)
+}
+
+/// Finds the uses of the local variable `name`.
+struct LocalVarFinder<'a> {
+    name: &'a str,
+    found: bool,
+}
+
+impl<'a> vir::ExprWalker for LocalVarFinder<'a> {
+    fn walk_local_var(&mut self, var: &vir::LocalVar) {
+        self.found |= var.name == self.name;
+    }
+}
+
+impl<'a> vir::StmtWalker for LocalVarFinder<'a> {
+    fn walk_expr(&mut self, expr: &vir::Expr) {
+        vir::ExprWalker::walk(self, expr);
+    }
+
+    fn walk_local_var(&mut self, var: &vir::LocalVar) {
+        self.found |= var.name == self.name;
+    }
+}
+
+fn expr_mentions_local_var(expr: &vir::Expr, name: &str) -> bool {
+    let mut finder = LocalVarFinder { name, found: false };
+    vir::ExprWalker::walk(&mut finder, expr);
+    finder.found
+}
+
+fn stmts_mention_local_var(stmts: &[vir::Stmt], name: &str) -> bool {
+    let mut finder = LocalVarFinder { name, found: false };
+    for stmt in stmts {
+        vir::StmtWalker::walk(&mut finder, stmt);
+    }
+    finder.found
+}
@@ -10,15 +10,22 @@ use crate::encoder::builtin_encoder::BuiltinFunctionKind;
 use crate::encoder::errors::{SpannedEncodingError, ErrorCtxt, WithSpan, PanicCause};
 use crate::encoder::foldunfold;
 use crate::encoder::mir_encoder::{MirEncoder, PlaceEncoder};
-use crate::encoder::mir_encoder::{PRECONDITION_LABEL, WAND_LHS_LABEL};
+use crate::encoder::mir_encoder::{
+    PRECONDITION_LABEL, WAND_LHS_LABEL, LOOP_ENTRY_LABEL, LOOP_ITERATIONS_VAR, USER_LABEL_PREFIX,
+};
 use crate::encoder::mir_interpreter::{
     run_backward_interpretation, BackwardMirInterpreter, MultiExprBackwardInterpreterState,
 };
 use crate::encoder::snapshot;
+use crate::encoder::spec_encoder::{encode_spec_measure, encode_measure_decrease};
 use crate::encoder::Encoder;
 use prusti_common::{vir, vir_local};
 use prusti_common::vir::ExprIterator;
+use prusti_common::vir::fixes::fix_function_triggers;
 use prusti_common::config;
+use prusti_interface::environment::{
+    BuiltinPureMethod, BuiltinVecMethod, find_writes_through_mut_ref_args,
+};
 use prusti_interface::specs::typed;
 use rustc_hir as hir;
 use rustc_hir::def_id::DefId;
@@ -80,6 +87,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionEncoder<'p, 'v, 'tcx> {
     pub fn encode_function(&self) -> SpannedEncodingResult<vir::Function> {
         let function_name = self.encode_function_name();
         debug!("Encode pure function {}", function_name);
+        // Check the return type before encoding the body, so that unsupported
+        // return types are reported as such
+        let return_type = self.encode_function_return_type()?;
+        self.check_no_writes_through_arguments()?;
         let mut state = run_backward_interpretation(self.mir, &self.interpreter)?
             .expect(&format!("Procedure {:?} contains a loop", self.proc_def_id));
 
@@ -106,7 +117,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionEncoder<'p, 'v, 'tcx> {
         );
 
         // if the function returns a snapshot, we take a snapshot of the body
-        if self.encode_function_return_type()?.is_snapshot() {
+        if return_type.is_snapshot() {
             let ty = self.encoder.resolve_typaram(self.mir.return_ty());
             let return_span = self.get_local_span(mir::RETURN_PLACE);
 
@@ -266,7 +277,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionEncoder<'p, 'v, 'tcx> {
             .with_span(self.mir.span)?;
 
         // Add folding/unfolding
-        foldunfold::add_folding_unfolding_to_function(
+        let function = foldunfold::add_folding_unfolding_to_function(
             function,
             self.encoder.get_used_viper_predicates_map(),
         )
@@ -278,7 +289,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionEncoder<'p, 'v, 'tcx> {
                 ),
                 self.mir.span,
             )
-        })
+        })?;
+
+        Ok(fix_function_triggers(function, self.encoder.backend_capabilities()))
     }
 
     /// Encode the precondition with two expressions:
@@ -392,6 +405,22 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionEncoder<'p, 'v, 'tcx> {
         self.interpreter.mir_encoder().get_local_span(local)
     }
 
+    /// The calls of a pure function do not modify the state of the caller, so
+    /// the targets of the mutable reference arguments (e.g. `&mut self`) can
+    /// only be read by the body.
+    fn check_no_writes_through_arguments(&self) -> SpannedEncodingResult<()> {
+        if let Some(&location) = find_writes_through_mut_ref_args(self.mir).first() {
+            let span = self.mir.source_info(location).span;
+            return Err(SpannedEncodingError::incorrect(
+                "pure function cannot modify the target of a mutable reference argument",
+                span,
+            ).set_help(
+                "remove the #[pure] attribute, or change the function to only read the target of the reference",
+            ));
+        }
+        Ok(())
+    }
+
     pub fn encode_function_name(&self) -> String {
         self.encoder.encode_item_name(self.proc_def_id)
     }
@@ -400,15 +429,38 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionEncoder<'p, 'v, 'tcx> {
         let ty = self.encoder.resolve_typaram(self.mir.return_ty());
         let return_span = self.get_local_span(mir::RETURN_PLACE);
 
+        // A pure `deref` method returns a reference to its target, which is encoded
+        // as the snapshot of the target, so that it can be chained with the calls
+        // that read through the reference
+        let target_ty = match ty.kind() {
+            ty::TyKind::Ref(_, target_ty, hir::Mutability::Not)
+                if self.encoder.env().is_deref_method(self.proc_def_id) => target_ty,
+            _ => ty,
+        };
+
         // Return an error for unsupported return types
         let tcx = self.encoder.env().tcx();
-        if !is_supported_type_of_pure_expression(tcx, ty) {
+        if !is_supported_type_of_pure_expression(tcx, target_ty) {
             return Err(SpannedEncodingError::incorrect(
                 "invalid return type of pure function",
                 return_span,
             ));
         }
 
+        // Values of types with an abstract snapshot cannot be constructed, so
+        // only type parameters can be returned as such
+        if !matches!(target_ty.kind(), ty::TyKind::Param(_))
+            && self.encoder.has_abstract_snapshot(target_ty).with_span(return_span)?
+        {
+            return Err(SpannedEncodingError::unsupported(
+                format!(
+                    "return type `{}` of pure function cannot be encoded as a snapshot",
+                    target_ty,
+                ),
+                return_span,
+            ));
+        }
+
         let return_local = mir::Place::return_place().as_local().unwrap();
         let span = self.interpreter.mir_encoder().get_local_span(return_local);
         self.encoder.encode_snapshot_type(ty).with_span(span)
@@ -418,6 +470,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionEncoder<'p, 'v, 'tcx> {
 pub(super) struct PureFunctionBackwardInterpreter<'p, 'v: 'p, 'tcx: 'v> {
     encoder: &'p Encoder<'v, 'tcx>,
     mir: &'p mir::Body<'tcx>,
+    def_id: DefId,
     mir_encoder: MirEncoder<'p, 'v, 'tcx>,
     /// True if the encoder is currently encoding an assertion and not a pure function body. This
     /// flag is used to distinguish when assert terminators should be translated into `false` and
@@ -439,6 +492,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionBackwardInterpreter<'p, 'v, 'tcx> {
         PureFunctionBackwardInterpreter {
             encoder,
             mir,
+            def_id,
             mir_encoder: MirEncoder::new(encoder, mir, def_id),
             is_encoding_assertion,
         }
@@ -448,6 +502,61 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionBackwardInterpreter<'p, 'v, 'tcx> {
         &self.mir_encoder
     }
 
+    /// Encode the check that the termination measure of the encoded function decreases at a call
+    /// of `called_def_id` with the arguments `encoded_args`. Returns `None` if the encoded
+    /// function has no termination measure or if the call is not recursive.
+    fn encode_termination_check(
+        &self,
+        called_def_id: DefId,
+        encoded_args: &[vir::Expr],
+        span: Span,
+    ) -> SpannedEncodingResult<Option<vir::Expr>> {
+        if self.is_encoding_assertion {
+            return Ok(None);
+        }
+        let measure = match self.encoder.get_procedure_specs(self.def_id)
+            .and_then(|spec| spec.decreases)
+        {
+            Some(measure) => measure,
+            None => return Ok(None),
+        };
+        if !self.encoder.env().is_recursive_call(self.def_id, called_def_id) {
+            return Ok(None);
+        }
+        let called_measure = self.encoder.get_procedure_specs(called_def_id)
+            .and_then(|spec| spec.decreases)
+            .ok_or_else(|| SpannedEncodingError::incorrect(
+                format!(
+                    "the mutually recursive function `{}` has no termination measure",
+                    self.encoder.env().get_item_name(called_def_id),
+                ),
+                span,
+            ))?;
+
+        // The measure of the encoded function is evaluated on its formal arguments
+        let formal_args: Vec<vir::Expr> = self.mir
+            .args_iter()
+            .map(|local| {
+                let ty = self.mir_encoder.get_local_ty(local);
+                self.encoder.encode_snapshot_type(ty).map(|typ| {
+                    vir::LocalVar::new(self.mir_encoder.encode_local_var_name(local), typ).into()
+                })
+            })
+            .collect::<Result<_, _>>()
+            .with_span(span)?;
+        let old_measure = encode_spec_measure(self.encoder, &measure, &formal_args, true, None)?;
+        let new_measure =
+            encode_spec_measure(self.encoder, &called_measure, encoded_args, true, None)?;
+        if old_measure.len() != new_measure.len() {
+            return Err(SpannedEncodingError::incorrect(
+                "the termination measures of mutually recursive functions must have the same \
+                number of components",
+                span,
+            ));
+        }
+        Ok(Some(encode_measure_decrease(&new_measure, &old_measure)))
+    }
+
     /// Wrap all expressions contained in the state with downcast information to be used by the
     /// fold-unfold pass.
     fn apply_downcasts(&self, state: &mut MultiExprBackwardInterpreterState, location: mir::Location)
@@ -499,6 +608,172 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionBackwardInterpreter<'p, 'v, 'tcx> {
         // TODO: actual encoding of e.g. array access here
         Ok((encoded_place.try_into_expr()?, ty, variant_idx))
     }
+
+    /// Encodes the element of an array or slice that is read by the given
+    /// place, if the place ends with an index projection. The result is the
+    /// snapshot of the element; the bounds check is done by the MIR.
+    fn encode_sequence_read(
+        &self,
+        local: mir::Local,
+        projection: &[mir::PlaceElem<'tcx>],
+    ) -> EncodingResult<Option<vir::Expr>> {
+        let (idx, base_projection) = match projection.split_last() {
+            Some((mir::ProjectionElem::Index(idx), base_projection)) => (idx, base_projection),
+            _ => return Ok(None),
+        };
+        let tcx = self.encoder.env().tcx();
+        let base_ty = mir::Place::ty_from(local, base_projection, self.mir, tcx).ty;
+        let encoded_base = match self.encode_sequence_read(local, base_projection)? {
+            Some(encoded_read) => encoded_read,
+            None => self.encode_projection(local, base_projection)?.0,
+        };
+        let encoded_idx = self.encoder.encode_value_expr(
+            self.mir_encoder.encode_local(*idx)?.into(),
+            self.mir_encoder.get_local_ty(*idx),
+        )?;
+        self.encoder.encode_snapshot_sequence_read(base_ty, encoded_base, encoded_idx)
+            .map(Some)
+    }
+
+    /// Like [MirEncoder::encode_operand_expr], but also supports reading
+    /// array and slice elements, and string literals.
+    fn encode_operand_expr(&self, operand: &mir::Operand<'tcx>) -> EncodingResult<vir::Expr> {
+        if let mir::Operand::Copy(ref place) | mir::Operand::Move(ref place) = operand {
+            if let Some(encoded_read) = self.encode_sequence_read(place.local, place.projection)? {
+                return Ok(encoded_read);
+            }
+        }
+        if let Some(literal) = self.eval_str_literal(operand) {
+            return self.encoder.encode_snapshot_str_literal(&literal?);
+        }
+        self.mir_encoder.encode_operand_expr(operand)
+    }
+
+    /// Returns the content of the operand if it is a string literal, or a
+    /// (promoted) reference to it.
+    fn eval_str_literal(&self, operand: &mir::Operand<'tcx>) -> Option<EncodingResult<String>> {
+        match operand {
+            mir::Operand::Constant(box mir::Constant { literal, .. })
+                if literal.ty().peel_refs().is_str() =>
+            {
+                let value = match literal {
+                    mir::ConstantKind::Ty(ty::Const { val, .. }) => *val,
+                    mir::ConstantKind::Val(val, _) => ty::ConstKind::Value(*val),
+                };
+                Some(self.encoder.const_eval_str(literal.ty(), &value))
+            }
+            _ => None,
+        }
+    }
+
+    /// Encode a call of a method of `Option` or `Result` that has a built-in pure encoding.
+    /// The methods are defined in terms of the discriminant of the enumeration and of the field
+    /// of the variant with index `value_variant`, which become the discriminant and field access
+    /// functions of the snapshot domain when the argument is a snapshot.
+    fn encode_builtin_pure_method(
+        &self,
+        method: BuiltinPureMethod,
+        value_variant: usize,
+        args: &[mir::Operand<'tcx>],
+        encoded_args: &[vir::Expr],
+        span: Span,
+    ) -> SpannedEncodingResult<vir::Expr> {
+        let tcx = self.encoder.env().tcx();
+        // `is_some(&self)` and similar methods take the enumeration by reference
+        let arg_ty = self.mir_encoder.get_operand_ty(&args[0]);
+        let (enum_ty, enum_expr) = match arg_ty.kind() {
+            ty::TyKind::Ref(_, target_ty, _) => {
+                let deref_field = self.encoder.encode_dereference_field(target_ty)
+                    .with_span(span)?;
+                (*target_ty, encoded_args[0].clone().field(deref_field))
+            }
+            _ => (arg_ty, encoded_args[0].clone()),
+        };
+        let (adt_def, substs) = match enum_ty.kind() {
+            ty::TyKind::Adt(adt_def, substs) => (adt_def, substs),
+            _ => unreachable!("{:?}", enum_ty),
+        };
+        let variant_idx = rustc_target::abi::VariantIdx::from_usize(value_variant);
+        let discriminant = adt_def.discriminant_for_variant(tcx, variant_idx).val as i128;
+        let is_value = vir::Expr::eq_cmp(
+            enum_expr.clone().field(self.encoder.encode_discriminant_field()),
+            discriminant.into(),
+        );
+        let encode_value = || -> SpannedEncodingResult<vir::Expr> {
+            let variant_def = &adt_def.variants[variant_idx];
+            let field = &variant_def.fields[0];
+            let field_ty = field.ty(tcx, substs);
+            let encoded_field = self.encoder.encode_struct_field(&field.ident.as_str(), field_ty)
+                .with_span(span)?;
+            let value_place = enum_expr.clone()
+                .variant(&variant_def.ident.as_str())
+                .field(encoded_field);
+            self.encoder.encode_value_expr(value_place, field_ty).with_span(span)
+        };
+        Ok(match method {
+            BuiltinPureMethod::IsValue => is_value,
+            BuiltinPureMethod::IsNotValue => vir::Expr::not(is_value),
+            BuiltinPureMethod::Unwrap => {
+                let value = encode_value()?;
+                let return_type = value.get_type().clone();
+                let pos = self.encoder.error_manager().register(span, ErrorCtxt::PureUnwrapOfNoValue);
+                let function_name = self.encoder.encode_builtin_function_use(
+                    BuiltinFunctionKind::Unreachable(return_type.clone()),
+                );
+                vir::Expr::ite(
+                    is_value,
+                    value,
+                    vir::Expr::func_app(function_name, vec![], vec![], return_type, pos),
+                )
+            }
+            BuiltinPureMethod::UnwrapOr => {
+                vir::Expr::ite(is_value, encode_value()?, encoded_args[1].clone())
+            }
+        })
+    }
+
+    /// Encode a call of `Vec::len` or an indexing of a `Vec` in terms of the sequence snapshot
+    /// of the vector. The index must be in bounds, like in the built-in contract of the
+    /// indexing that is used in impure code.
+    fn encode_builtin_vec_method(
+        &self,
+        method: BuiltinVecMethod,
+        args: &[mir::Operand<'tcx>],
+        encoded_args: &[vir::Expr],
+        span: Span,
+    ) -> SpannedEncodingResult<vir::Expr> {
+        // `len(&self)` and `index(&self, idx)` take the vector by reference
+        let vec_ty = self.mir_encoder.get_operand_ty(&args[0]);
+        let len = self.encoder.encode_snapshot_sequence_len(vec_ty, encoded_args[0].clone())
+            .with_span(span)?;
+        match method {
+            BuiltinVecMethod::Len => Ok(len),
+            BuiltinVecMethod::Index => {
+                let idx = encoded_args[1].clone();
+                let elem_ty = self.encoder.env().get_vec_elem_type(vec_ty.peel_refs()).unwrap();
+                let value = self.encoder.encode_snapshot_sequence_read(
+                    vec_ty,
+                    encoded_args[0].clone(),
+                    idx.clone(),
+                ).with_span(span)?;
+                let return_type = self.encoder.encode_snapshot_type(elem_ty).with_span(span)?;
+                let pos = self.encoder.error_manager()
+                    .register(span, ErrorCtxt::PureVecIndexOutOfBounds);
+                let function_name = self.encoder.encode_builtin_function_use(
+                    BuiltinFunctionKind::Unreachable(return_type.clone()),
+                );
+                Ok(vir::Expr::ite(
+                    vir::Expr::and(
+                        vir::Expr::le_cmp(0.into(), idx.clone()),
+                        vir::Expr::lt_cmp(idx, len),
+                    ),
+                    value,
+                    vir::Expr::func_app(function_name, vec![], vec![], return_type, pos),
+                ))
+            }
+            _ => unreachable!("{:?}", method),
+        }
+    }
 }
 
 impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
@@ -612,7 +887,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                     targets
                 );
                 let mut cfg_targets: Vec<(vir::Expr, mir::BasicBlock)> = vec![];
-                let discr_val = self.mir_encoder.encode_operand_expr(discr)
+                let discr_val = self.encode_operand_expr(discr)
                     .with_span(span)?;
                 for (value, target) in targets.iter() {
                     // Convert int to bool, if required
@@ -712,45 +987,143 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
 
                     // FIXME: this is a hack to support generics. See issue #187.
                     let mut tymap = HashMap::new();
+                    let mut constmap = HashMap::new();
                     for (kind1, kind2) in own_substs.iter().zip(substs.iter()) {
-                        if let (
-                            ty::subst::GenericArgKind::Type(ty1),
-                            ty::subst::GenericArgKind::Type(ty2),
-                        ) = (kind1.unpack(), kind2.unpack())
-                        {
-                            tymap.insert(ty1, ty2);
+                        match (kind1.unpack(), kind2.unpack()) {
+                            (
+                                ty::subst::GenericArgKind::Type(ty1),
+                                ty::subst::GenericArgKind::Type(ty2),
+                            ) => {
+                                tymap.insert(ty1, ty2);
+                            }
+                            (
+                                ty::subst::GenericArgKind::Const(const1),
+                                ty::subst::GenericArgKind::Const(const2),
+                            ) => {
+                                if let ty::ConstKind::Param(param) = const1.val {
+                                    constmap.insert(param, const2);
+                                }
+                            }
+                            _ => {}
                         }
                     }
                     let _cleanup_token = self.encoder.push_temp_tymap(tymap);
+                    let _const_cleanup_token = self.encoder.push_temp_constmap(constmap);
 
                     let state = if destination.is_some() {
                         let (ref lhs_place, target_block) = destination.as_ref().unwrap();
                         let (encoded_lhs, ty, _) = self.encode_place(lhs_place)
                             .with_span(span)?;
                         let lhs_value = self.encoder.encode_value_expr(encoded_lhs.clone(), ty).with_span(span)?;
-                        let encoded_args: Vec<vir::Expr> = args
+                        // The label of `labelled_old(..)` is not part of the expression
+                        let value_args = if full_func_proc_name == "prusti_contracts::labelled_old" {
+                            &args[1..]
+                        } else {
+                            &args[..]
+                        };
+                        let encoded_args: Vec<vir::Expr> = value_args
                             .iter()
-                            .map(|arg| self.mir_encoder.encode_operand_expr(arg))
+                            .map(|arg| self.encode_operand_expr(arg))
                             .collect::<Result<_, _>>()
                             .with_span(span)?;
+                        let arg_tys: Vec<_> = args.iter()
+                            .map(|arg| self.mir_encoder.get_operand_ty(arg))
+                            .collect();
+                        let builtin_vec_method = self.encoder.env()
+                            .get_builtin_vec_method(def_id, &arg_tys);
 
                         match full_func_proc_name {
-                            "prusti_contracts::old" => {
-                                trace!("Encoding old expression {:?}", args[0]);
-                                assert_eq!(args.len(), 1);
-
-                                // Return an error for unsupported old(..) types
+                            "prusti_contracts::old" |
+                            "prusti_contracts::old_loop" |
+                            "prusti_contracts::labelled_old" => {
+                                trace!("Encoding old expression {:?}", value_args[0]);
+                                assert_eq!(encoded_args.len(), 1);
+
+                                // Return an error for unsupported old(..) types. References
+                                // are accepted, so that pure functions can be called on the
+                                // old state of a reference argument.
                                 let tcx = self.encoder.env().tcx();
-                                if !is_supported_type_of_pure_expression(tcx, ty) {
+                                if !ty.is_ref() && !is_supported_type_of_pure_expression(tcx, ty) {
                                     return Err(SpannedEncodingError::incorrect(
                                         "the type of the old expression is invalid",
                                         term.source_info.span,
                                     ));
                                 }
 
+                                let label = match full_func_proc_name {
+                                    "prusti_contracts::old" => PRECONDITION_LABEL.to_string(),
+                                    "prusti_contracts::old_loop" => LOOP_ENTRY_LABEL.to_string(),
+                                    _ => {
+                                        let label_name = self.eval_str_literal(&args[0])
+                                            .ok_or_else(|| SpannedEncodingError::incorrect(
+                                                "the label of labelled_old(..) must be a string literal",
+                                                term.source_info.span,
+                                            ))?
+                                            .with_span(span)?;
+                                        format!("{}{}", USER_LABEL_PREFIX, label_name)
+                                    }
+                                };
+                                // Take the snapshot of structures (and of the targets of
+                                // references) in the old state, so that their fields are read
+                                // in that state as well
+                                let old_arg = if ty.is_primitive() {
+                                    encoded_args[0].clone()
+                                } else {
+                                    vir::Expr::snap_app(encoded_args[0].clone())
+                                };
                                 let encoded_rhs = self
                                     .mir_encoder
-                                    .encode_old_expr(encoded_args[0].clone(), PRECONDITION_LABEL);
+                                    .encode_old_expr(old_arg, &label);
+                                let mut state = states[&target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
+
+                            "prusti_contracts::unchanged" => {
+                                trace!("Encoding unchanged expression {:?}", args[0]);
+                                assert_eq!(args.len(), 1);
+
+                                let arg_ty = self.mir_encoder.get_operand_ty(&args[0]);
+                                let tcx = self.encoder.env().tcx();
+                                if !is_supported_type_of_pure_expression(tcx, arg_ty) {
+                                    return Err(SpannedEncodingError::incorrect(
+                                        "the type of the unchanged expression is invalid",
+                                        term.source_info.span,
+                                    ));
+                                }
+
+                                // Compare the current value with the value at the entry of the
+                                // loop, i.e. `arg == old_loop(arg)`
+                                let arg = if arg_ty.is_primitive() {
+                                    encoded_args[0].clone()
+                                } else {
+                                    self.encoder.check_snapshot_equality_fields(arg_ty)
+                                        .with_span(term.source_info.span)?;
+                                    vir::Expr::snap_app(encoded_args[0].clone())
+                                };
+                                let encoded_rhs = vir::Expr::eq_cmp(
+                                    arg.clone(),
+                                    self.mir_encoder.encode_old_expr(arg, LOOP_ENTRY_LABEL),
+                                );
+                                let mut state = states[&target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
+
+                            "prusti_contracts::loop_iterations" => {
+                                trace!("Encoding loop_iterations expression");
+                                assert!(args.is_empty());
+                                if !self.is_encoding_assertion {
+                                    return Err(SpannedEncodingError::incorrect(
+                                        "loop_iterations() can only be used in loop invariants",
+                                        term.source_info.span,
+                                    ));
+                                }
+                                // Resolved by the procedure encoder to the iteration
+                                // counter of the loop whose invariant is being encoded
+                                let encoded_rhs = vir::Expr::local(
+                                    vir::LocalVar::new(LOOP_ITERATIONS_VAR, vir::Type::Int),
+                                );
                                 let mut state = states[&target_block].clone();
                                 state.substitute_value(&lhs_value, encoded_rhs);
                                 state
@@ -767,15 +1140,42 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                 state
                             }
 
+                            "core::slice::<impl [T]>::len" => {
+                                trace!("Encoding slice length {:?}", args[0]);
+                                assert_eq!(args.len(), 1);
+                                let encoded_rhs = self.encoder.encode_snapshot_sequence_len(
+                                    self.mir_encoder.get_operand_ty(&args[0]),
+                                    encoded_args[0].clone(),
+                                ).with_span(span)?;
+                                let mut state = states[&target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
+
+                            "core::str::<impl str>::len" => {
+                                trace!("Encoding string length {:?}", args[0]);
+                                assert_eq!(args.len(), 1);
+                                let encoded_rhs = self.encoder.encode_snapshot_str_len(
+                                    encoded_args[0].clone(),
+                                ).with_span(span)?;
+                                let mut state = states[&target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
+
                             "std::cmp::PartialEq::eq"
                             if self.encoder.has_structural_eq_impl(
                                 self.mir_encoder.get_operand_ty(&args[0])
                             ) => {
                                 assert_eq!(args.len(), 2);
-                                let encoded_rhs = vir::Expr::eq_cmp(
-                                    vir::Expr::snap_app(encoded_args[0].clone()),
-                                    vir::Expr::snap_app(encoded_args[1].clone()),
-                                );
+                                self.encoder.check_snapshot_equality_fields(
+                                    self.mir_encoder.get_operand_ty(&args[0])
+                                ).with_span(term.source_info.span)?;
+                                let encoded_rhs = self.encoder.encode_snapshot_equality(
+                                    self.mir_encoder.get_operand_ty(&args[0]),
+                                    encoded_args[0].clone(),
+                                    encoded_args[1].clone(),
+                                ).with_span(term.source_info.span)?;
                                 let mut state = states[&target_block].clone();
                                 state.substitute_value(&lhs_value, encoded_rhs);
                                 state
@@ -786,21 +1186,92 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                 self.mir_encoder.get_operand_ty(&args[0])
                             ) => {
                                 assert_eq!(args.len(), 2);
-                                let encoded_rhs = vir::Expr::ne_cmp(
-                                    vir::Expr::snap_app(encoded_args[0].clone()),
-                                    vir::Expr::snap_app(encoded_args[1].clone()),
+                                self.encoder.check_snapshot_equality_fields(
+                                    self.mir_encoder.get_operand_ty(&args[0])
+                                ).with_span(term.source_info.span)?;
+                                let encoded_rhs = vir::Expr::not(
+                                    self.encoder.encode_snapshot_equality(
+                                        self.mir_encoder.get_operand_ty(&args[0]),
+                                        encoded_args[0].clone(),
+                                        encoded_args[1].clone(),
+                                    ).with_span(term.source_info.span)?
                                 );
                                 let mut state = states[&target_block].clone();
                                 state.substitute_value(&lhs_value, encoded_rhs);
                                 state
                             }
 
+                            _ if self.encoder.env().get_builtin_pure_method(def_id).is_some() => {
+                                let (method, value_variant) = self.encoder.env()
+                                    .get_builtin_pure_method(def_id)
+                                    .unwrap();
+                                let encoded_rhs = self.encode_builtin_pure_method(
+                                    method,
+                                    value_variant,
+                                    args,
+                                    &encoded_args,
+                                    term.source_info.span,
+                                )?;
+                                let mut state = states[&target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
+
+                            _ if matches!(
+                                builtin_vec_method,
+                                Some(BuiltinVecMethod::Len) | Some(BuiltinVecMethod::Index)
+                            ) => {
+                                let encoded_rhs = self.encode_builtin_vec_method(
+                                    builtin_vec_method.unwrap(),
+                                    args,
+                                    &encoded_args,
+                                    term.source_info.span,
+                                )?;
+                                let mut state = states[&target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
+
                             // simple function call
                             _ => {
+                                // A call of a trait method whose receiver type is
+                                // known (e.g. the hidden type of an `impl Trait`)
+                                // uses the pure method of the implementation.
+                                let def_id = if self.encoder.is_pure(def_id) {
+                                    def_id
+                                } else {
+                                    let tcx = self.encoder.env().tcx();
+                                    let resolved_substs = tcx.mk_substs(substs.iter().map(|arg| {
+                                        match arg.unpack() {
+                                            ty::subst::GenericArgKind::Type(ty) => {
+                                                self.encoder.resolve_typaram(ty).into()
+                                            }
+                                            _ => arg,
+                                        }
+                                    }));
+                                    self.encoder.env()
+                                        .resolve_trait_method_call(def_id, resolved_substs)
+                                        .filter(|impl_def_id| self.encoder.is_pure(*impl_def_id))
+                                        .unwrap_or(def_id)
+                                };
                                 let is_pure_function = self.encoder.is_pure(def_id);
                                 let (function_name, return_type) = if is_pure_function {
                                     self.encoder.encode_pure_function_use(def_id)
                                         .with_span(term.source_info.span)?
+                                } else if self.encoder.env().is_deref_method(def_id) {
+                                    // The call of an implicit dereference, e.g. of `w` in `w.len()`
+                                    return Err(SpannedEncodingError::incorrect(
+                                        format!(
+                                            "use of impure function {:?} to dereference `{}` in pure \
+                                            code is not allowed",
+                                            func_proc_name,
+                                            self.mir_encoder.get_operand_ty(&args[0]).peel_refs(),
+                                        ),
+                                        term.source_info.span,
+                                    ).set_help(
+                                        "consider marking the `deref` method of the implementation \
+                                        of `Deref` as `#[pure]`"
+                                    ));
                                 } else {
                                     return Err(SpannedEncodingError::incorrect(
                                         format!(
@@ -822,17 +1293,44 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                     .collect::<Result<_, _>>()
                                     .with_span(term.source_info.span)?;
 
+                                let termination_check = self.encode_termination_check(
+                                    def_id,
+                                    &encoded_args,
+                                    term.source_info.span,
+                                )?;
                                 let pos = self
                                     .encoder
                                     .error_manager()
                                     .register(term.source_info.span, ErrorCtxt::PureFunctionCall);
-                                let encoded_rhs = vir::Expr::func_app(
+                                let mut encoded_rhs = vir::Expr::func_app(
                                     function_name,
                                     encoded_args,
                                     formal_args,
-                                    return_type,
+                                    return_type.clone(),
                                     pos,
                                 );
+                                // A recursive call that does not decrease the termination
+                                // measure is encoded as unreachable
+                                if let Some(termination_check) = termination_check {
+                                    let pos = self.encoder.error_manager().register(
+                                        term.source_info.span,
+                                        ErrorCtxt::TerminationMeasureInPureFunction,
+                                    );
+                                    let function_name = self.encoder.encode_builtin_function_use(
+                                        BuiltinFunctionKind::Unreachable(return_type.clone()),
+                                    );
+                                    encoded_rhs = vir::Expr::ite(
+                                        termination_check,
+                                        encoded_rhs,
+                                        vir::Expr::func_app(
+                                            function_name,
+                                            vec![],
+                                            vec![],
+                                            return_type,
+                                            pos,
+                                        ),
+                                    );
+                                }
                                 let mut state = states[&target_block].clone();
                                 state.substitute_value(&lhs_value, encoded_rhs);
                                 state
@@ -886,7 +1384,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                 ref msg,
                 ..
             } => {
-                let cond_val = self.mir_encoder.encode_operand_expr(cond)
+                let cond_val = self.encode_operand_expr(cond)
                     .with_span(span)?;
                 let viper_guard = if expected {
                     cond_val
@@ -894,17 +1392,25 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                     vir::Expr::not(cond_val)
                 };
 
-                let assert_msg = if let mir::AssertKind::BoundsCheck { .. } = msg {
-                    let mut s = String::new();
-                    msg.fmt_assert_args(&mut s).unwrap();
-                    s
-                } else {
-                    msg.description().to_string()
+                let error_ctxt = match msg {
+                    mir::AssertKind::BoundsCheck { .. } => {
+                        let mut s = String::new();
+                        msg.fmt_assert_args(&mut s).unwrap();
+                        ErrorCtxt::PureFunctionAssertTerminator(s)
+                    }
+                    mir::AssertKind::DivisionByZero(_) | mir::AssertKind::RemainderByZero(_) => {
+                        ErrorCtxt::PanicInPureFunction(PanicCause::DivisionByZero)
+                    }
+                    mir::AssertKind::Overflow(mir::BinOp::Div, ..)
+                    | mir::AssertKind::Overflow(mir::BinOp::Rem, ..) => {
+                        ErrorCtxt::PanicInPureFunction(PanicCause::DivisionOverflow)
+                    }
+                    _ => ErrorCtxt::PureFunctionAssertTerminator(msg.description().to_string()),
                 };
 
                 let pos = self.encoder.error_manager().register(
                     term.source_info.span,
-                    ErrorCtxt::PureFunctionAssertTerminator(assert_msg),
+                    error_ctxt,
                 );
 
                 MultiExprBackwardInterpreterState::new(
@@ -912,13 +1418,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                         .exprs()
                         .iter()
                         .map(|expr| {
-                            let failure_result = if self.is_encoding_assertion {
+                            let failure_result = if self.is_encoding_assertion
+                                && self.mir.return_ty().is_bool()
+                            {
                                 // We are encoding an assertion, so all failures should be
                                 // equivalent to false.
                                 Ok(false.into())
                             } else {
-                                // We are encoding a pure function, so all failures should
-                                // be unreachable.
+                                // We are encoding a pure function or the matched expression
+                                // of a variant test, so all failures should be unreachable.
                                 unreachable_expr(pos).with_span(term.source_info.span)
                             };
                             failure_result.map(
@@ -992,6 +1500,20 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                 };
 
                 match rhs {
+                    &mir::Rvalue::Use(mir::Operand::Copy(ref place))
+                    | &mir::Rvalue::Use(mir::Operand::Move(ref place))
+                        if matches!(place.projection.last(), Some(mir::ProjectionElem::Index(_))) => {
+                        let encoded_read = self.encode_sequence_read(place.local, place.projection)
+                            .with_span(span)?
+                            .unwrap();
+                        if let Some(lhs_value_place) = &opt_lhs_value_place {
+                            state.substitute_value(lhs_value_place, encoded_read);
+                        } else {
+                            // The element is a snapshot
+                            state.substitute_place(&encoded_lhs, encoded_read);
+                        }
+                    }
+
                     &mir::Rvalue::Use(ref operand) => {
                         let opt_encoded_rhs = self.mir_encoder.encode_operand_place(operand)
                             .with_span(span)?;
@@ -1005,8 +1527,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                 // Substitute a place of a value with an expression
                                 if let Some(lhs_value_place) = &opt_lhs_value_place {
                                     // opt_lhs_value_place can be none in trigger generation code.
-                                    let rhs_expr = self.mir_encoder
-                                        .encode_operand_expr(operand)
+                                    let rhs_expr = self.encode_operand_expr(operand)
                                         .with_span(span)?;
                                     state.substitute_value(lhs_value_place, rhs_expr);
                                 }
@@ -1037,13 +1558,18 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                     match encoded_operand {
                                         Some(encoded_rhs) => {
                                             // Substitute a place
-                                            field_exprs.push(encoded_rhs.clone());
+                                            field_exprs.push(
+                                                self.encoder.encode_value_expr(
+                                                    encoded_rhs.clone(),
+                                                    field_ty.expect_ty(),
+                                                ).with_span(span)?
+                                            );
                                             state.substitute_place(&field_place, encoded_rhs);
                                         }
                                         None => {
                                             // Substitute a place of a value with an expression
                                             let rhs_expr =
-                                                self.mir_encoder.encode_operand_expr(operand)
+                                                self.encode_operand_expr(operand)
                                                     .with_span(span)?;
                                             field_exprs.push(rhs_expr.clone());
                                             state.substitute_value(
@@ -1055,12 +1581,19 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                 }
                                 let snapshot = self.encoder.encode_snapshot_constructor(
                                     ty,
+                                    0,
                                     field_exprs,
                                 ).with_span(span)?;
                                 state.substitute_place(&encoded_lhs, snapshot);
                             }
 
                             &mir::AggregateKind::Adt(adt_def, variant_index, subst, _, _) => {
+                                if adt_def.is_union() {
+                                    return Err(SpannedEncodingError::unsupported(
+                                        "unions cannot be constructed in pure code",
+                                        span,
+                                    ));
+                                }
                                 let num_variants = adt_def.variants.len();
                                 let variant_def = &adt_def.variants[variant_index];
                                 let mut encoded_lhs_variant = encoded_lhs.clone();
@@ -1073,6 +1606,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                     encoded_lhs_variant =
                                         encoded_lhs_variant.variant(&variant_def.ident.as_str());
                                 }
+                                let mut field_exprs = vec![];
                                 for (field_index, field) in variant_def.fields.iter().enumerate() {
                                     let operand = &operands[field_index];
                                     let field_name = &field.ident.as_str();
@@ -1090,13 +1624,20 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                     match encoded_operand {
                                         Some(encoded_rhs) => {
                                             // Substitute a place
+                                            field_exprs.push(
+                                                self.encoder.encode_value_expr(
+                                                    encoded_rhs.clone(),
+                                                    field_ty,
+                                                ).with_span(span)?
+                                            );
                                             state.substitute_place(&field_place, encoded_rhs);
                                         }
                                         None => {
                                             // Substitute a place of a value with an expression
                                             let rhs_expr =
-                                                self.mir_encoder.encode_operand_expr(operand)
+                                                self.encode_operand_expr(operand)
                                                     .with_span(span)?;
+                                            field_exprs.push(rhs_expr.clone());
                                             state.substitute_value(
                                                 &self.encoder.encode_value_expr(field_place, field_ty).with_span(span)?,
                                                 rhs_expr,
@@ -1104,6 +1645,16 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                         }
                                     }
                                 }
+                                // Data-less structs are encoded as the unit snapshot, which
+                                // has no constructor
+                                if num_variants != 1 || !variant_def.fields.is_empty() {
+                                    let snapshot = self.encoder.encode_snapshot_constructor(
+                                        ty,
+                                        variant_index.index(),
+                                        field_exprs,
+                                    ).with_span(span)?;
+                                    state.substitute_place(&encoded_lhs, snapshot);
+                                }
                             }
 
                             ref x => unimplemented!("{:?}", x),
@@ -1111,9 +1662,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                     }
 
                     &mir::Rvalue::BinaryOp(op, box(ref left, ref right)) => {
-                        let encoded_left = self.mir_encoder.encode_operand_expr(left)
+                        let encoded_left = self.encode_operand_expr(left)
                             .with_span(span)?;
-                        let encoded_right = self.mir_encoder.encode_operand_expr(right)
+                        let encoded_right = self.encode_operand_expr(right)
                             .with_span(span)?;
                         let encoded_value = self.mir_encoder.encode_bin_op_expr(
                             op,
@@ -1133,9 +1684,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                             unreachable!()
                         };
 
-                        let encoded_left = self.mir_encoder.encode_operand_expr(left)
+                        let encoded_left = self.encode_operand_expr(left)
                             .with_span(span)?;
-                        let encoded_right = self.mir_encoder.encode_operand_expr(right)
+                        let encoded_right = self.encode_operand_expr(right)
                             .with_span(span)?;
 
                         let encoded_value = self.mir_encoder.encode_bin_op_expr(
@@ -1182,7 +1733,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                     }
 
                     &mir::Rvalue::UnaryOp(op, ref operand) => {
-                        let encoded_val = self.mir_encoder.encode_operand_expr(operand)
+                        let encoded_val = self.encode_operand_expr(operand)
                             .with_span(span)?;
                         let encoded_value = self.mir_encoder.encode_unary_op_expr(op, encoded_val);
 
@@ -1231,6 +1782,16 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                         }
                     }
 
+                    &mir::Rvalue::Ref(_, mir::BorrowKind::Shared, ref place)
+                        if matches!(place.projection.last(), Some(mir::ProjectionElem::Index(_))) => {
+                        // The element is a snapshot, which also stands for the
+                        // target of a shared reference to it
+                        let encoded_read = self.encode_sequence_read(place.local, place.projection)
+                            .with_span(span)?
+                            .unwrap();
+                        state.substitute_value(&opt_lhs_value_place.unwrap(), encoded_read);
+                    }
+
                     &mir::Rvalue::Ref(_, mir::BorrowKind::Unique, ref place)
                     | &mir::Rvalue::Ref(_, mir::BorrowKind::Mut { .. }, ref place)
                     | &mir::Rvalue::Ref(_, mir::BorrowKind::Shared, ref place) => {
@@ -1260,6 +1821,52 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                         state.substitute_value(&opt_lhs_value_place.unwrap(), encoded_val);
                     }
 
+                    &mir::Rvalue::Len(ref place) => {
+                        let (encoded_place, place_ty, _) = self.encode_place(place)
+                            .with_span(span)?;
+                        let encoded_len = self.encoder
+                            .encode_snapshot_sequence_len(place_ty, encoded_place)
+                            .with_span(span)?;
+
+                        // Substitute a place of a value with an expression
+                        state.substitute_value(&opt_lhs_value_place.unwrap(), encoded_len);
+                    }
+
+                    &mir::Rvalue::Cast(
+                        mir::CastKind::Pointer(ty::adjustment::PointerCast::Unsize),
+                        ref operand,
+                        dst_ty,
+                    ) => {
+                        let src_ty = self.mir_encoder.get_operand_ty(operand);
+                        match (src_ty.peel_refs().kind(), dst_ty.peel_refs().kind()) {
+                            (ty::TyKind::Array(..), ty::TyKind::Slice(..)) => {}
+                            _ => return Err(SpannedEncodingError::unsupported(
+                                "unsizing a pointer or reference value is not supported",
+                                span,
+                            )),
+                        }
+                        let lhs_value_place = opt_lhs_value_place.unwrap();
+                        let encoded_array = self.encode_operand_expr(operand)
+                            .with_span(span)?;
+                        for expr in state.exprs_mut() {
+                            let base = mem::replace(expr, true.into());
+                            *expr = self.encoder.encode_snapshot_sequence_unsize(
+                                dst_ty.peel_refs(),
+                                src_ty.peel_refs(),
+                                lhs_value_place.clone(),
+                                encoded_array.clone(),
+                                base,
+                            ).with_span(span)?;
+                        }
+                        if state.use_place(&encoded_lhs) {
+                            return Err(SpannedEncodingError::unsupported(
+                                "slices of arrays can only be used to read their length \
+                                or elements in pure code",
+                                span,
+                            ));
+                        }
+                    }
+
                     ref rhs => {
                         unimplemented!("encoding of '{:?}'", rhs);
                     }
@@ -1293,6 +1900,11 @@ fn is_supported_type_of_pure_expression<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<
                     .all(|t| is_supported_type_of_pure_expression(tcx, t))
         }
 
+        ty::TyKind::Array(elem_ty, _) => is_supported_type_of_pure_expression(tcx, elem_ty),
+
+        // Shared string slices are encoded as the snapshot of the string
+        ty::TyKind::Ref(_, inner_ty, hir::Mutability::Not) => inner_ty.is_str(),
+
         _ => false,
     }
 }
@@ -7,6 +7,8 @@ use prusti_common::vir::{self, ExprWalker, ExprFolder, StmtWalker, StmtFolder};
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use log::{debug, trace};
 use crate::encoder::Encoder;
+use crate::encoder::errors::{EncodingError, EncodingResult};
+use rustc_middle::ty;
 
 /// Replaces shared references to pure Viper variables.
 pub fn purify_method(
@@ -395,4 +397,518 @@ impl ExprFolder for Purifier<'_, '_, '_> {
             pos
         )
     }
-}
\ No newline at end of file
+}
+
+/// Replaces local variables of struct or tuple type that are never mutated,
+/// borrowed, or passed to a method by pure snapshot variables. Permissions to
+/// such variables are removed, their `fold` and `unfold` statements are
+/// dropped, and reads of their fields become snapshot field functions.
+///
+/// On error, `method` is left unchanged.
+pub fn purify_local_snapshots(
+    encoder: &Encoder,
+    method: &mut vir::CfgMethod
+) -> EncodingResult<()> {
+    let mut candidates = HashSet::new();
+    debug!("method: {}", method.name());
+    for var in &method.local_vars {
+        match &var.typ {
+            // We cannot purify the return value.
+            vir::Type::TypedRef(ref typ) if var.name != "_0" && !typ.starts_with("ref$") => {
+                if is_purifiable_type(encoder, typ) {
+                    trace!("  local snapshot candidate: {}: {}", var.name, var.typ);
+                    candidates.insert(var.name.clone());
+                }
+            }
+            _ => {}
+        };
+    }
+    if candidates.is_empty() {
+        return Ok(());
+    }
+
+    let mut analysis = EscapeAnalysis {
+        candidates: &candidates,
+        escaping: HashSet::new(),
+    };
+    method.walk_statements(|stmt| {
+        StmtWalker::walk(&mut analysis, stmt);
+    });
+    method.walk_successors(|successor| {
+        if let vir::Successor::GotoSwitch(conditional_targets, _) = successor {
+            for (guard, _) in conditional_targets {
+                analysis.escaping.extend(collect_variables(guard));
+            }
+        }
+    });
+    let escaping = analysis.escaping;
+    candidates.retain(|var| !escaping.contains(var));
+    debug!(
+        "Local snapshots in method {} to be purified {:?}",
+        method.name(),
+        candidates
+    );
+    if candidates.is_empty() {
+        return Ok(());
+    }
+
+    let mut purifier = SnapshotPurifier {
+        encoder,
+        vars: candidates,
+        fresh_variables: Vec::new(),
+        error: None,
+    };
+    let purified_stmts: Vec<Vec<vir::Stmt>> = method.basic_blocks
+        .iter()
+        .map(|block| {
+            block.stmts
+                .iter()
+                .cloned()
+                .map(|stmt| StmtFolder::fold(&mut purifier, stmt))
+                .collect()
+        })
+        .collect();
+    if let Some(error) = purifier.error {
+        return Err(error);
+    }
+    for (block, stmts) in method.basic_blocks.iter_mut().zip(purified_stmts) {
+        block.stmts = stmts;
+    }
+    for var in &mut method.local_vars {
+        if purifier.vars.contains(&var.name) {
+            let typ = std::mem::replace(&mut var.typ, vir::Type::Bool);
+            var.typ = translate_type(encoder, typ);
+        }
+    }
+    method.local_vars.extend(purifier.fresh_variables);
+    Ok(())
+}
+
+/// Only single-variant types can be represented by a snapshot whose fields
+/// are accessed without a downcast.
+fn is_purifiable_type(encoder: &Encoder, predicate_name: &str) -> bool {
+    let ty = match encoder.decode_type_predicate(predicate_name) {
+        Ok(ty) => ty,
+        Err(_) => return false,
+    };
+    let is_struct = match ty.kind() {
+        ty::TyKind::Adt(adt_def, _) => adt_def.is_struct(),
+        ty::TyKind::Tuple(substs) => !substs.is_empty(),
+        _ => false,
+    };
+    is_struct && matches!(encoder.encode_snapshot_type(ty), Ok(vir::Type::Snapshot(_)))
+}
+
+/// Checks whether `place` is a (possibly nested) field of one of the
+/// candidates that does not go through a reference or a box.
+fn is_candidate_place(candidates: &HashSet<String>, place: &vir::Expr) -> bool {
+    let (base, components) = place.explode_place();
+    match base {
+        vir::Expr::Local(local_var, _) if candidates.contains(&local_var.name) => {
+            components.iter().all(|component| match component {
+                vir::PlaceComponent::Field(field, _) => field.name != "val_ref",
+                vir::PlaceComponent::Variant(..) => false,
+            })
+        }
+        _ => false,
+    }
+}
+
+/// Checks whether `place` reads a primitive value stored in a candidate.
+fn is_candidate_value_read(candidates: &HashSet<String>, place: &vir::Expr) -> bool {
+    match place {
+        vir::Expr::Field(_, vir::Field { name, .. }, _)
+                if name == "val_int" || name == "val_bool" => {
+            is_candidate_place(candidates, place)
+        }
+        _ => false,
+    }
+}
+
+/// This is an ExprWalker and StmtWalker that collects the candidates of
+/// `purify_local_snapshots` that have to stay on the heap. Everything that is
+/// not known to be a pure read of a candidate makes the candidate escape.
+struct EscapeAnalysis<'a> {
+    candidates: &'a HashSet<String>,
+    escaping: HashSet<String>,
+}
+
+impl EscapeAnalysis<'_> {
+    fn escape(&mut self, expr: &vir::Expr) {
+        self.escaping.extend(collect_variables(expr));
+    }
+}
+
+impl ExprWalker for EscapeAnalysis<'_> {
+    fn walk_local(&mut self, local_var: &vir::LocalVar, _pos: &vir::Position) {
+        // A candidate that is used as a reference.
+        self.escaping.insert(local_var.name.clone());
+    }
+    fn walk_field(&mut self, receiver: &vir::Expr, field: &vir::Field, pos: &vir::Position) {
+        let place = vir::Expr::Field(box receiver.clone(), field.clone(), *pos);
+        if !is_candidate_value_read(self.candidates, &place) {
+            ExprWalker::walk(self, receiver);
+        }
+    }
+    fn walk_variant(&mut self, base: &vir::Expr, _variant: &vir::Field, _pos: &vir::Position) {
+        self.escape(base);
+    }
+    fn walk_addr_of(&mut self, receiver: &vir::Expr, _typ: &vir::Type, _pos: &vir::Position) {
+        self.escape(receiver);
+    }
+    fn walk_labelled_old(&mut self, _label: &str, body: &vir::Expr, _pos: &vir::Position) {
+        // A purified variable has the same value in all states.
+        self.escape(body);
+    }
+    fn walk_magic_wand(
+        &mut self,
+        lhs: &vir::Expr,
+        rhs: &vir::Expr,
+        _borrow: &Option<vir::borrows::Borrow>,
+        _pos: &vir::Position
+    ) {
+        self.escape(lhs);
+        self.escape(rhs);
+    }
+    fn walk_predicate_access_predicate(
+        &mut self,
+        _name: &str,
+        arg: &vir::Expr,
+        _perm_amount: vir::PermAmount,
+        _pos: &vir::Position
+    ) {
+        if !is_candidate_place(self.candidates, arg) {
+            ExprWalker::walk(self, arg);
+        }
+    }
+    fn walk_field_access_predicate(
+        &mut self,
+        receiver: &vir::Expr,
+        _perm_amount: vir::PermAmount,
+        _pos: &vir::Position
+    ) {
+        if !is_candidate_place(self.candidates, receiver) {
+            ExprWalker::walk(self, receiver);
+        }
+    }
+    fn walk_unfolding(
+        &mut self,
+        _name: &str,
+        args: &Vec<vir::Expr>,
+        body: &vir::Expr,
+        _perm: vir::PermAmount,
+        _variant: &vir::MaybeEnumVariantIndex,
+        _pos: &vir::Position
+    ) {
+        match args.as_slice() {
+            [arg] if is_candidate_place(self.candidates, arg) => {}
+            _ => {
+                for arg in args {
+                    ExprWalker::walk(self, arg);
+                }
+            }
+        }
+        ExprWalker::walk(self, body);
+    }
+    fn walk_func_app(
+        &mut self,
+        name: &str,
+        args: &Vec<vir::Expr>,
+        _formal_args: &Vec<vir::LocalVar>,
+        _return_type: &vir::Type,
+        _pos: &vir::Position
+    ) {
+        match args.as_slice() {
+            [arg] if name.starts_with("snap$") && is_candidate_place(self.candidates, arg) => {}
+            _ => {
+                for arg in args {
+                    ExprWalker::walk(self, arg);
+                }
+            }
+        }
+    }
+}
+
+impl StmtWalker for EscapeAnalysis<'_> {
+    fn walk_expr(&mut self, expr: &vir::Expr) {
+        ExprWalker::walk(self, expr);
+    }
+    fn walk_method_call(&mut self, _method_name: &str, args: &Vec<vir::Expr>, targets: &Vec<vir::LocalVar>) {
+        for arg in args {
+            self.walk_expr(arg);
+        }
+        // Only a call with a single result can be replaced by a havoc.
+        if targets.len() > 1 {
+            for target in targets {
+                self.escaping.insert(target.name.clone());
+            }
+        }
+    }
+    fn walk_assign(&mut self, target: &vir::Expr, source: &vir::Expr, _kind: &vir::AssignKind) {
+        // Candidates are never written to.
+        self.escape(target);
+        self.walk_expr(source);
+    }
+    fn walk_fold(
+        &mut self,
+        _predicate_name: &str,
+        args: &Vec<vir::Expr>,
+        _perm: &vir::PermAmount,
+        _variant: &vir::MaybeEnumVariantIndex,
+        _pos: &vir::Position
+    ) {
+        match args.as_slice() {
+            [arg] if is_candidate_place(self.candidates, arg) => {}
+            _ => args.iter().for_each(|arg| self.walk_expr(arg)),
+        }
+    }
+    fn walk_unfold(
+        &mut self,
+        _predicate_name: &str,
+        args: &Vec<vir::Expr>,
+        _perm: &vir::PermAmount,
+        _variant: &vir::MaybeEnumVariantIndex,
+    ) {
+        match args.as_slice() {
+            [arg] if is_candidate_place(self.candidates, arg) => {}
+            _ => args.iter().for_each(|arg| self.walk_expr(arg)),
+        }
+    }
+    fn walk_obtain(&mut self, expr: &vir::Expr, _pos: &vir::Position) {
+        self.escape(expr);
+    }
+    fn walk_transfer_perm(&mut self, from: &vir::Expr, to: &vir::Expr, _unchecked: &bool) {
+        self.escape(from);
+        self.escape(to);
+    }
+    fn walk_package_magic_wand(
+        &mut self,
+        wand: &vir::Expr,
+        body: &Vec<vir::Stmt>,
+        _label: &str,
+        _vars: &[vir::LocalVar],
+        _pos: &vir::Position,
+    ) {
+        self.escape(wand);
+        for stmt in body {
+            StmtWalker::walk(self, stmt);
+        }
+    }
+    fn walk_apply_magic_wand(&mut self, wand: &vir::Expr, _pos: &vir::Position) {
+        self.escape(wand);
+    }
+    fn walk_downcast(&mut self, expr: &vir::Expr, _field: &vir::Field) {
+        self.escape(expr);
+    }
+}
+
+struct SnapshotPurifier<'p, 'v: 'p, 'tcx: 'v> {
+    encoder: &'p Encoder<'v, 'tcx>,
+    vars: HashSet<String>,
+    fresh_variables: Vec<vir::LocalVar>,
+    /// The first error raised while patching the snapshots of a purified place.
+    error: Option<EncodingError>,
+}
+
+impl<'p, 'v: 'p, 'tcx: 'v> SnapshotPurifier<'p, 'v, 'tcx> {
+    fn fresh_variable(&mut self, typ: &vir::Type) -> vir::LocalVar {
+        let name = format!("snap_havoc${}", self.fresh_variables.len());
+        let var = vir::LocalVar {
+            name,
+            typ: translate_type(self.encoder, typ.clone()),
+        };
+        self.fresh_variables.push(var.clone());
+        var
+    }
+
+    fn is_candidate_place(&self, place: &vir::Expr) -> bool {
+        is_candidate_place(&self.vars, place)
+    }
+
+    /// Translates a place rooted at a purified variable into the corresponding
+    /// snapshot expression. If the snapshots cannot be patched, the error is
+    /// recorded and the result must be discarded.
+    fn purify_place(&mut self, place: vir::Expr) -> vir::Expr {
+        let (base, components) = place.explode_place();
+        let base = match base {
+            vir::Expr::Local(local_var, pos) => vir::Expr::Local(
+                vir::LocalVar {
+                    name: local_var.name,
+                    typ: translate_type(self.encoder, local_var.typ),
+                },
+                pos,
+            ),
+            _ => unreachable!(),
+        };
+        let purified_place = base.reconstruct_place(components);
+        match self.encoder.patch_snapshots(purified_place.clone()) {
+            Ok(patched_place) => patched_place,
+            Err(error) => {
+                self.error.get_or_insert(error);
+                purified_place
+            }
+        }
+    }
+}
+
+impl StmtFolder for SnapshotPurifier<'_, '_, '_> {
+    fn fold_expr(&mut self, expr: vir::Expr) -> vir::Expr {
+        ExprFolder::fold(self, expr)
+    }
+    fn fold_method_call(
+        &mut self,
+        name: String,
+        args: Vec<vir::Expr>,
+        targets: Vec<vir::LocalVar>
+    ) -> vir::Stmt {
+        match targets.as_slice() {
+            [local_var] if self.vars.contains(&local_var.name) => {
+                return vir::Stmt::Assign(
+                    vir::LocalVar {
+                        name: local_var.name.clone(),
+                        typ: translate_type(self.encoder, local_var.typ.clone()),
+                    }.into(),
+                    self.fresh_variable(&local_var.typ).into(),
+                    vir::AssignKind::Ghost
+                );
+            }
+            _ => {}
+        }
+        vir::Stmt::MethodCall(
+            name,
+            args.into_iter().map(|e| self.fold_expr(e)).collect(),
+            targets
+        )
+    }
+    fn fold_fold(
+        &mut self,
+        predicate_name: String,
+        args: Vec<vir::Expr>,
+        perm_amount: vir::PermAmount,
+        variant: vir::MaybeEnumVariantIndex,
+        pos: vir::Position
+    ) -> vir::Stmt {
+        match args.as_slice() {
+            [arg] if self.is_candidate_place(arg) => {
+                return vir::Stmt::comment(format!("purified fold {}({})", predicate_name, arg));
+            }
+            _ => {}
+        }
+        vir::Stmt::Fold(
+            predicate_name,
+            args.into_iter().map(|e| self.fold_expr(e)).collect(),
+            perm_amount,
+            variant,
+            pos,
+        )
+    }
+    fn fold_unfold(
+        &mut self,
+        predicate_name: String,
+        args: Vec<vir::Expr>,
+        perm_amount: vir::PermAmount,
+        variant: vir::MaybeEnumVariantIndex,
+    ) -> vir::Stmt {
+        match args.as_slice() {
+            [arg] if self.is_candidate_place(arg) => {
+                return vir::Stmt::comment(format!("purified unfold {}({})", predicate_name, arg));
+            }
+            _ => {}
+        }
+        vir::Stmt::Unfold(
+            predicate_name,
+            args.into_iter().map(|e| self.fold_expr(e)).collect(),
+            perm_amount,
+            variant,
+        )
+    }
+}
+
+impl ExprFolder for SnapshotPurifier<'_, '_, '_> {
+    fn fold_field_access_predicate(
+        &mut self,
+        receiver: Box<vir::Expr>,
+        perm_amount: vir::PermAmount,
+        pos: vir::Position
+    ) -> vir::Expr {
+        if self.is_candidate_place(&receiver) {
+            return true.into();
+        }
+        vir::Expr::FieldAccessPredicate(self.fold_boxed(receiver), perm_amount, pos)
+    }
+    fn fold_predicate_access_predicate(
+        &mut self,
+        name: String,
+        arg: Box<vir::Expr>,
+        perm_amount: vir::PermAmount,
+        pos: vir::Position
+    ) -> vir::Expr {
+        if self.is_candidate_place(&arg) {
+            return true.into();
+        }
+        vir::Expr::PredicateAccessPredicate(name, self.fold_boxed(arg), perm_amount, pos)
+    }
+    fn fold_unfolding(
+        &mut self,
+        name: String,
+        args: Vec<vir::Expr>,
+        expr: Box<vir::Expr>,
+        perm: vir::PermAmount,
+        variant: vir::MaybeEnumVariantIndex,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        match args.as_slice() {
+            [arg] if self.is_candidate_place(arg) => return ExprFolder::fold(self, *expr),
+            _ => {}
+        }
+        vir::Expr::Unfolding(
+            name,
+            args.into_iter().map(|e| ExprFolder::fold(self, e)).collect(),
+            self.fold_boxed(expr),
+            perm,
+            variant,
+            pos,
+        )
+    }
+    fn fold_field(
+        &mut self,
+        receiver: Box<vir::Expr>,
+        field: vir::Field,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        let place = vir::Expr::Field(receiver, field, pos);
+        if is_candidate_value_read(&self.vars, &place) {
+            return self.purify_place(place);
+        }
+        match place {
+            vir::Expr::Field(receiver, field, pos) => {
+                vir::Expr::Field(self.fold_boxed(receiver), field, pos)
+            }
+            _ => unreachable!(),
+        }
+    }
+    fn fold_func_app(
+        &mut self,
+        name: String,
+        args: Vec<vir::Expr>,
+        formal_args: Vec<vir::LocalVar>,
+        return_type: vir::Type,
+        pos: vir::Position
+    ) -> vir::Expr {
+        if name.starts_with("snap$") {
+            match args.as_slice() {
+                [arg] if self.is_candidate_place(arg) => {
+                    return self.purify_place(arg.clone());
+                }
+                _ => {}
+            }
+        }
+        vir::Expr::FuncApp(
+            name,
+            args.into_iter().map(|e| ExprFolder::fold(self, e)).collect(),
+            formal_args,
+            return_type,
+            pos
+        )
+    }
+}
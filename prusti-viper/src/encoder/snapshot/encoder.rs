@@ -5,17 +5,20 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use ::log::debug;
+use rustc_hir::Mutability;
 use rustc_middle::ty;
 use rustc_middle::ty::layout::IntegerExt;
 use rustc_target::abi::Integer;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
 use prusti_common::vir::{
-    self, Expr, FallibleExprFolder, FallibleStmtFolder, Type, PermAmount,
-    EnumVariantIndex, ExprIterator,
+    self, Expr, ExprFolder, FallibleExprFolder, FallibleStmtFolder, Type, PermAmount,
+    EnumVariantIndex, ExprIterator, WithIdentifier,
 };
 use crate::encoder::foldunfold;
 use crate::encoder::utils::range_extract;
 use crate::encoder::Encoder;
+use crate::encoder::builtin_encoder::BuiltinFunctionKind;
 use crate::encoder::errors::{EncodingError, EncodingResult, SpannedEncodingResult};
 use crate::encoder::snapshot::{Snapshot, patcher::SnapshotPatcher};
 
@@ -26,7 +29,7 @@ const SNAP_FUNC_NAME: &str = "snap$";
 
 /// Encodes MIR types into snapshots, and keeps track of which types have
 /// already been encoded.
-pub struct SnapshotEncoder {
+pub struct SnapshotEncoder<'tcx> {
     /// Types that are currently being encoded. Stores the VIR type that will
     /// be the result of the snapshot operation: either a primitive VIR type,
     /// or an instance of [Type::Snapshot].
@@ -36,12 +39,28 @@ pub struct SnapshotEncoder {
     /// look up the resulting type from this hashmap.
     in_progress: HashMap<PredicateName, Type>,
 
-    /// Maps predicate names to encoded snapshots.
-    encoded: HashMap<PredicateName, Snapshot>,
+    /// Maps predicate names to encoded snapshots. Ordered by name, so that
+    /// the emitted domains and functions do not depend on hashing.
+    encoded: BTreeMap<PredicateName, Snapshot>,
+
+    /// Maps the types that were already encoded, after resolving their type
+    /// parameters and stripping their references and boxes, to the type of
+    /// their snapshots. Looking up a type here avoids both the computation
+    /// of its predicate name and the copy of its snapshot, which is
+    /// significant for nested generic types whose fields are encoded over
+    /// and over.
+    encoded_types: HashMap<ty::Ty<'tcx>, Type>,
+
+    /// The time spent encoding snapshots, reported in the debug output.
+    encoding_duration: Duration,
 
     /// Whether the unit domain was used in encoding or not.
     unit_used: bool,
     unit_domain: vir::Domain,
+
+    /// String literals encoded so far. The index of a literal in this vector
+    /// is used to name its constructor in the snapshot domain of `str`.
+    str_literals: Vec<String>,
 }
 
 /// Snapshot encoding flattens references and boxes. This function removes any
@@ -82,11 +101,68 @@ fn strip_refs_and_boxes_expr<'p, 'v: 'p, 'tcx: 'v>(
     }
 }
 
-impl SnapshotEncoder {
+/// Looks for a (possibly nested) field of the given type which holds a
+/// mutable reference, and returns its path. Shared references are compared by
+/// the value they point to, but comparing the target of a mutable reference
+/// is not supported.
+fn find_mut_ref_field<'p, 'v: 'p, 'tcx: 'v>(
+    encoder: &'p Encoder<'v, 'tcx>,
+    ty: ty::Ty<'tcx>,
+    visited: &mut Vec<ty::Ty<'tcx>>,
+) -> Option<String> {
+    let tcx = encoder.env().tcx();
+    let ty = encoder.resolve_typaram(strip_refs_and_boxes(ty));
+    if visited.contains(&ty) {
+        return None;
+    }
+    visited.push(ty);
+    let fields: Vec<(String, ty::Ty<'tcx>)> = match ty.kind() {
+        ty::TyKind::Tuple(substs) => substs.iter()
+            .enumerate()
+            .map(|(field_num, field_ty)| (field_num.to_string(), field_ty.expect_ty()))
+            .collect(),
+        ty::TyKind::Adt(..) if encoder.env().get_vec_elem_type(ty).is_some() => {
+            vec![("elem".to_string(), encoder.env().get_vec_elem_type(ty).unwrap())]
+        }
+        ty::TyKind::Adt(adt_def, subst) => adt_def.all_fields()
+            .map(|field| (field.ident.to_string(), field.ty(tcx, subst)))
+            .collect(),
+        ty::TyKind::Closure(_, substs) => substs.as_closure().upvar_tys()
+            .enumerate()
+            .map(|(field_num, field_ty)| (format!("closure_{}", field_num), field_ty))
+            .collect(),
+        _ => return None,
+    };
+    for (field_name, field_ty) in fields {
+        if let ty::TyKind::Ref(_, _, Mutability::Mut) = field_ty.kind() {
+            return Some(field_name);
+        }
+        if let Some(path) = find_mut_ref_field(encoder, field_ty, visited) {
+            return Some(format!("{}.{}", field_name, path));
+        }
+    }
+    None
+}
+
+/// Returns the constructor of the string literal with the given index in the
+/// snapshot domain of `str`.
+fn str_literal_func(domain: &vir::Domain, snapshot_type: Type, index: usize) -> vir::DomainFunc {
+    vir::DomainFunc {
+        name: format!("{}$lit${}", domain.name, index),
+        formal_args: vec![],
+        return_type: snapshot_type,
+        unique: false,
+        domain_name: domain.name.to_string(),
+    }
+}
+
+impl<'tcx> SnapshotEncoder<'tcx> {
     pub fn new() -> Self {
         Self {
             in_progress: HashMap::new(),
-            encoded: HashMap::new(),
+            encoded: BTreeMap::new(),
+            encoded_types: HashMap::new(),
+            encoding_duration: Duration::default(),
             unit_used: false,
             unit_domain: vir::Domain {
                 name: UNIT_DOMAIN_NAME.to_string(),
@@ -100,6 +176,7 @@ impl SnapshotEncoder {
                 axioms: vec![],
                 type_vars: vec![],
             },
+            str_literals: vec![],
         }
     }
 
@@ -108,7 +185,10 @@ impl SnapshotEncoder {
         let mut funcs = vec![];
         for snapshot in self.encoded.values() {
             match snapshot {
-                Snapshot::Complex { snap_func, .. } => funcs.push(snap_func.clone()),
+                Snapshot::Struct { snap_func, .. } => funcs.push(snap_func.clone()),
+                Snapshot::Enum { snap_func, .. } => funcs.push(snap_func.clone()),
+                Snapshot::Sequence { snap_func, .. } => funcs.push(snap_func.clone()),
+                Snapshot::Str { snap_func, .. } => funcs.push(snap_func.clone()),
                 Snapshot::Abstract { snap_func, .. } => funcs.push(snap_func.clone()),
                 _ => {},
             }
@@ -117,11 +197,50 @@ impl SnapshotEncoder {
     }
 
     /// Returns a list of Viper domains needed by the encoded snapshots.
+    ///
+    /// The snapshot domain of `str` gets a constructor for each string
+    /// literal encoded so far, with an axiom fixing its length and its index:
+    /// ```plain
+    /// len(lit$N()) == <length of the literal> && literal_index(lit$N()) == N
+    /// ```
+    /// Since the indices are distinct, so are the snapshots of distinct
+    /// literals.
     pub fn get_viper_domains(&self) -> Vec<vir::Domain> {
+        debug!(
+            "Encoded the snapshots of {} types ({} distinct) in {:?}",
+            self.encoded_types.len(),
+            self.encoded.len(),
+            self.encoding_duration,
+        );
         let mut domains = vec![];
         for snapshot in self.encoded.values() {
             match snapshot {
-                Snapshot::Complex { domain, .. } => domains.push(domain.clone()),
+                Snapshot::Struct { domain, .. } => domains.push(domain.clone()),
+                Snapshot::Enum { domain, .. } => domains.push(domain.clone()),
+                Snapshot::Sequence { domain, .. } => domains.push(domain.clone()),
+                Snapshot::Str { domain, len_func, literal_index_func, .. } => {
+                    let mut domain = domain.clone();
+                    for (index, literal) in self.str_literals.iter().enumerate() {
+                        let literal_func = str_literal_func(&domain, snapshot.get_type(), index);
+                        let literal_expr = literal_func.apply(vec![]);
+                        domain.axioms.push(vir::DomainAxiom {
+                            name: format!("{}$axiom", literal_func.name),
+                            expr: Expr::and(
+                                Expr::eq_cmp(
+                                    len_func.apply(vec![literal_expr.clone()]),
+                                    literal.len().into(),
+                                ),
+                                Expr::eq_cmp(
+                                    literal_index_func.apply(vec![literal_expr]),
+                                    index.into(),
+                                ),
+                            ),
+                            domain_name: domain.name.to_string(),
+                        });
+                        domain.functions.push(literal_func);
+                    }
+                    domains.push(domain);
+                }
                 Snapshot::Abstract { domain, .. } => domains.push(domain.clone()),
                 _ => {},
             }
@@ -133,7 +252,7 @@ impl SnapshotEncoder {
     }
 
     /// Patches snapshots in a method.
-    pub fn patch_snapshots_method<'p, 'v: 'p, 'tcx: 'v>(
+    pub fn patch_snapshots_method<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         method: vir::CfgMethod,
@@ -147,7 +266,7 @@ impl SnapshotEncoder {
     }
 
     /// Patches snapshots in a function.
-    pub fn patch_snapshots_function<'p, 'v: 'p, 'tcx: 'v>(
+    pub fn patch_snapshots_function<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         mut function: vir::Function,
@@ -170,7 +289,7 @@ impl SnapshotEncoder {
     }
 
     /// Patches snapshots in an expression.
-    pub fn patch_snapshots_expr<'p, 'v: 'p, 'tcx: 'v>(
+    pub fn patch_snapshots_expr<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         expr: Expr,
@@ -185,7 +304,7 @@ impl SnapshotEncoder {
 
     /// Converts a SnapApp(expr) into an appropriate expression based on the
     /// kind of this snapshot.
-    pub(super) fn snap_app<'p, 'v: 'p, 'tcx: 'v>(
+    pub(super) fn snap_app<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         expr: Expr,
@@ -208,6 +327,7 @@ impl SnapshotEncoder {
                     ty::TyKind::Tuple(substs) if substs.is_empty() => self.snap_unit(),
                     ty::TyKind::Adt(adt_def, _) if adt_def.variants.is_empty() => self.snap_unit(),
                     ty::TyKind::Adt(adt_def, _) if adt_def.variants.len() == 1 && adt_def.variants[rustc_target::abi::VariantIdx::from_u32(0)].fields.is_empty() => self.snap_unit(),
+                    ty::TyKind::Closure(_, substs) if substs.as_closure().upvar_tys().next().is_none() => self.snap_unit(),
 
                     // Param(_) | Adt(_) | Tuple(_) and unsupported types
                     _ => {
@@ -231,7 +351,7 @@ impl SnapshotEncoder {
 
     /// Calls the [snap] function on the given expression. This should only
     /// ever be used when [expr] has been snapshot-encoded and the snapshot has
-    /// a snap function (i.e. it is Complex or Abstract).
+    /// a snap function (e.g. it is a Struct, an Enum or Abstract).
     fn snap_app_expr(
         &self,
         expr: Expr,
@@ -250,7 +370,7 @@ impl SnapshotEncoder {
 
     /// Converts variant + field access on a snapshot to a domain function call.+
     /// This is used when accessing data of an enum variant.
-    pub(super) fn snap_variant_field<'p, 'v: 'p, 'tcx: 'v>(
+    pub(super) fn snap_variant_field<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         expr: Expr,
@@ -261,29 +381,32 @@ impl SnapshotEncoder {
         assert!(variant.name.starts_with("enum_"));
         let variant_name = &variant.name[5..];
         let snapshot = self.decode_snapshot(encoder, expr.get_type())?;
-        match snapshot {
-            Snapshot::Complex { variants, variant_names, .. } => {
+        let fields = match snapshot {
+            Snapshot::Enum { ref variants, ref variant_names, .. } => {
                 let variant_idx = variant_names.get(variant_name)
                     .ok_or_else(|| EncodingError::internal(
                         format!("no such variant: {}", variant_name),
                     ))?;
-                variants[*variant_idx].1.get(&field.name)
-                    .map(|func| func.apply(vec![expr.clone()]))
-                    .ok_or_else(|| EncodingError::internal(format!(
-                        "cannot snap_variant_field {}/{} of {:?}",
-                        variant_name,
-                        field.name,
-                        expr,
-                    )))
-            },
-            _ => Err(EncodingError::internal(
-                format!("invalid snapshot field (not Complex): {:?}", expr),
+                &variants[*variant_idx].1
+            }
+            // The only variant of an enum
+            Snapshot::Struct { ref fields, .. } => fields,
+            _ => return Err(EncodingError::internal(
+                format!("invalid snapshot field (not Enum): {:?}", expr),
             )),
-        }
+        };
+        fields.get(&field.name)
+            .map(|func| func.apply(vec![expr.clone()]))
+            .ok_or_else(|| EncodingError::internal(format!(
+                "cannot snap_variant_field {}/{} of {:?}",
+                variant_name,
+                field.name,
+                expr,
+            )))
     }
 
     /// Converts field access on a snapshot to a domain function call.
-    pub(super) fn snap_field<'p, 'v: 'p, 'tcx: 'v>(
+    pub(super) fn snap_field<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         expr: Expr,
@@ -291,23 +414,23 @@ impl SnapshotEncoder {
     ) -> EncodingResult<Expr> {
         let snapshot = self.decode_snapshot(encoder, expr.get_type())?;
         match (field.name.as_str(), snapshot) {
-            ("discriminant", Snapshot::Complex { discriminant_func, .. }) => Ok(
+            ("discriminant", Snapshot::Enum { discriminant_func, .. }) => Ok(
                 discriminant_func.apply(vec![expr]),
             ),
-            (_, Snapshot::Complex { variants, .. }) => variants[0].1
+            (_, Snapshot::Struct { fields, .. }) => fields
                 .get(&field.name)
                 .map(|func| func.apply(vec![expr.clone()]))
                 .ok_or_else(|| EncodingError::internal(
                     format!("cannot snap_field {} of {:?}", field.name, expr),
                 )),
             _ => Err(EncodingError::internal(
-                format!("invalid snapshot field (not Complex): {:?}", expr),
+                format!("invalid snapshot field (not Struct): {:?}", expr),
             )),
         }
     }
 
     /// Decodes a VIR type back into a full Snapshot.
-    fn decode_snapshot<'p, 'v: 'p, 'tcx: 'v>(
+    fn decode_snapshot<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         ty: &vir::Type,
@@ -330,31 +453,130 @@ impl SnapshotEncoder {
         self.unit_domain.functions[0].apply(vec![])
     }
 
+    /// Returns [true] iff the snapshot of the given type is Abstract, i.e.
+    /// its values cannot be constructed or inspected.
+    pub fn is_abstract<'p, 'v: 'p>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<bool> {
+        self.encode_snapshot(encoder, ty)
+            .map(|snapshot| matches!(snapshot, Snapshot::Abstract { .. }))
+    }
+
     /// Returns [true] iff we can encode equality between two instances of the
     /// given type as a direct equality between snapshots of the instances.
-    pub fn supports_equality<'p, 'v: 'p, 'tcx: 'v>(
+    pub fn supports_equality<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         ty: ty::Ty<'tcx>,
     ) -> EncodingResult<bool> {
+        if find_mut_ref_field(encoder, ty, &mut vec![]).is_some() {
+            return Ok(false);
+        }
         self.encode_snapshot(encoder, ty)
             .map(|snapshot| snapshot.supports_equality())
     }
 
+    /// Reports an error naming the offending field if two instances of the
+    /// given type cannot be compared by snapshot equality because the type
+    /// contains a mutable reference.
+    pub fn check_equality_fields<'p, 'v: 'p>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<()> {
+        if let Some(field) = find_mut_ref_field(encoder, ty, &mut vec![]) {
+            return Err(EncodingError::unsupported(format!(
+                "equality of values of type `{}` is not supported, because \
+                field `{}` is a mutable reference",
+                strip_refs_and_boxes(ty),
+                field,
+            )));
+        }
+        Ok(())
+    }
+
+    /// Encodes the equality of two instances of the given type, which should
+    /// support snapshot equality. Abstract snapshots are compared with their
+    /// equality function, all other snapshots by a direct equality.
+    pub fn encode_equality<'p, 'v: 'p>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        ty: ty::Ty<'tcx>,
+        lhs: Expr,
+        rhs: Expr,
+    ) -> EncodingResult<Expr> {
+        Ok(match self.encode_snapshot(encoder, ty)? {
+            Snapshot::Abstract { equality_func, .. } => equality_func.apply(vec![
+                Expr::snap_app(lhs),
+                Expr::snap_app(rhs),
+            ]),
+            _ => Expr::eq_cmp(Expr::snap_app(lhs), Expr::snap_app(rhs)),
+        })
+    }
+
     /// Returns [true] iff the given type can be used as a quantified variable
-    /// in a user-facing [forall].
-    pub fn is_quantifiable<'p, 'v: 'p, 'tcx: 'v>(
+    /// in a user-facing [forall] or [exists]. Tuples and ADTs are
+    /// quantifiable if all of their fields are, arrays, slices and vectors if
+    /// their elements are. Boxes are transparent, but fields behind references are
+    /// not quantifiable. Recursive types are not quantifiable either, since
+    /// their snapshot domain may contain values with no Rust counterpart.
+    pub fn is_quantifiable<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         ty: ty::Ty<'tcx>,
     ) -> EncodingResult<bool> {
-        self.encode_snapshot(encoder, ty)
-            .map(|snapshot| snapshot.is_quantifiable())
+        self.is_quantifiable_internal(encoder, ty, &mut vec![])
+    }
+
+    fn is_quantifiable_internal<'p, 'v: 'p>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        ty: ty::Ty<'tcx>,
+        visited: &mut Vec<ty::Ty<'tcx>>,
+    ) -> EncodingResult<bool> {
+        let snapshot = self.encode_snapshot(encoder, ty)?;
+        if !snapshot.is_quantifiable() {
+            return Ok(false);
+        }
+        if let Snapshot::Struct { .. } | Snapshot::Enum { .. } | Snapshot::Sequence { .. } = snapshot {
+            let tcx = encoder.env().tcx();
+            let ty = encoder.resolve_typaram(strip_refs_and_boxes(ty));
+            if visited.contains(&ty) {
+                return Ok(false);
+            }
+            visited.push(ty);
+            let field_tys: Vec<ty::Ty<'tcx>> = match ty.kind() {
+                ty::TyKind::Tuple(substs) => substs.iter()
+                    .map(|field_ty| field_ty.expect_ty())
+                    .collect(),
+                ty::TyKind::Adt(..) if encoder.env().get_vec_elem_type(ty).is_some() => {
+                    vec![encoder.env().get_vec_elem_type(ty).unwrap()]
+                }
+                ty::TyKind::Adt(adt_def, subst) => adt_def.all_fields()
+                    .map(|field| field.ty(tcx, subst))
+                    .collect(),
+                ty::TyKind::Array(elem_ty, _)
+                | ty::TyKind::Slice(elem_ty) => vec![elem_ty],
+                _ => return Ok(false),
+            };
+            for field_ty in field_tys {
+                if matches!(field_ty.kind(), ty::TyKind::Ref(..)) {
+                    return Ok(false);
+                }
+                if !self.is_quantifiable_internal(encoder, field_ty, visited)? {
+                    return Ok(false);
+                }
+            }
+            visited.pop();
+        }
+        Ok(true)
     }
 
     /// Encodes the postcondition asserting that the discriminant of the
     /// snapshot matches the result of the (Ref-based) discriminant function.
-    pub fn encode_discriminant_post<'p, 'v: 'p, 'tcx: 'v>(
+    pub fn encode_discriminant_post<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         expr_self: Expr,
@@ -362,45 +584,275 @@ impl SnapshotEncoder {
     ) -> EncodingResult<Expr> {
         let snapshot = self.decode_snapshot(encoder, expr_self.get_type())?;
         match snapshot {
-            Snapshot::Complex { ref discriminant_func, .. } => Ok(vir::Expr::eq_cmp(
+            Snapshot::Enum { ref discriminant_func, .. } => Ok(vir::Expr::eq_cmp(
                 discriminant_func.apply(vec![
                     self.snap_app_expr(expr_self, snapshot.get_type()),
                 ]),
                 expr_result,
             )),
             _ => Err(EncodingError::internal(
-                format!("invalid discriminant post (not Complex): {:?}", expr_self),
+                format!("invalid discriminant post (not Enum): {:?}", expr_self),
             )),
         }
     }
 
-    /// Encodes a snapshot constructor directly. Can only be used on ADTs with
-    /// a single variant.
-    pub fn encode_constructor<'p, 'v: 'p, 'tcx: 'v>(
+    /// Encodes a snapshot constructor of the given variant directly. The
+    /// variant index is always 0 for tuples and structs.
+    pub fn encode_constructor<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         ty: ty::Ty<'tcx>,
+        variant_index: usize,
         args: Vec<vir::Expr>,
     ) -> EncodingResult<vir::Expr> {
         let snapshot = self.encode_snapshot(encoder, ty)?;
-        match snapshot {
-            Snapshot::Complex { ref variants, .. } => {
-                assert_eq!(variants.len(), 1);
-                Ok(variants[0].0.apply(args))
+        let variants = snapshot.get_variants();
+        if variants.is_empty() {
+            return Err(EncodingError::internal(
+                format!("invalid constructor (not Struct or Enum): {}", ty),
+            ));
+        }
+        assert!(variant_index < variants.len());
+        Ok(variants[variant_index].0.apply(args))
+    }
+
+    /// Returns the Viper identifiers of the constructor and of the field
+    /// access functions of each variant of the snapshot of the given type.
+    /// Returns [None] if the snapshot is not a Struct or an Enum.
+    pub fn get_variant_functions<'p, 'v: 'p>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<Option<Vec<SnapshotVariantFunctions>>> {
+        let snapshot = self.encode_snapshot(encoder, ty)?;
+        let variants = snapshot.get_variants();
+        if variants.is_empty() {
+            return Ok(None);
+        }
+        let variant_name = |variant_idx: usize| match snapshot {
+            Snapshot::Enum { ref variant_names, .. } => variant_names.iter()
+                .find(|(_, idx)| **idx == variant_idx)
+                .map(|(name, _)| name.to_string()),
+            _ => None,
+        };
+        Ok(Some(
+            variants.iter()
+                .enumerate()
+                .map(|(variant_idx, (constructor, fields))| SnapshotVariantFunctions {
+                    name: variant_name(variant_idx),
+                    constructor: constructor.get_identifier(),
+                    fields: fields.iter()
+                        .map(|(name, func)| (name.to_string(), func.get_identifier()))
+                        .collect(),
+                })
+                .collect()
+        ))
+    }
+
+    /// Returns the constructors of the variants of an already encoded Struct
+    /// or Enum snapshot type. Returns [None] for other types.
+    pub fn get_constructors(&self, typ: &Type) -> Option<Vec<vir::DomainFunc>> {
+        match typ {
+            Type::Snapshot(ref predicate_name) => match self.encoded.get(predicate_name) {
+                Some(snapshot @ Snapshot::Struct { .. })
+                | Some(snapshot @ Snapshot::Enum { .. }) => Some(
+                    snapshot.get_variants().into_iter()
+                        .map(|(constructor, _)| constructor.clone())
+                        .collect()
+                ),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns [true] iff the snapshot of the given type is a Struct or an Enum and
+    /// (possibly indirectly) contains a field of the same snapshot type.
+    pub fn is_recursive<'p, 'v: 'p>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<bool> {
+        let typ = self.encode_type(encoder, ty)?;
+        let mut visited = vec![];
+        let mut to_visit = self.get_field_types(&typ);
+        while let Some(field_typ) = to_visit.pop() {
+            if field_typ == typ {
+                return Ok(true);
+            }
+            if !visited.contains(&field_typ) {
+                to_visit.extend(self.get_field_types(&field_typ));
+                visited.push(field_typ);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns the types of the fields of all variants of an already encoded
+    /// Struct or Enum snapshot type.
+    fn get_field_types(&self, typ: &Type) -> Vec<Type> {
+        match typ {
+            Type::Snapshot(ref predicate_name) => match self.encoded.get(predicate_name) {
+                Some(snapshot) => snapshot.get_variants().into_iter()
+                    .flat_map(|(_, fields)| fields.values())
+                    .map(|field_func| field_func.return_type.clone())
+                    .collect(),
+                None => vec![],
             },
+            _ => vec![],
+        }
+    }
+
+    /// Whether the domain function accesses a field of a Struct or Enum snapshot.
+    pub fn is_field_access_func(&self, func: &vir::DomainFunc) -> bool {
+        self.encoded.values().any(|snapshot| snapshot.get_variants().into_iter()
+            .any(|(_, fields)| fields.values().any(|field_func| field_func == func)))
+    }
+
+    /// Encodes the length of the given array, slice or vector. [expr] can be
+    /// Ref-based or a snapshot already.
+    pub fn encode_sequence_len<'p, 'v: 'p>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        ty: ty::Ty<'tcx>,
+        expr: vir::Expr,
+    ) -> EncodingResult<vir::Expr> {
+        let snapshot = self.encode_snapshot(encoder, ty)?;
+        match snapshot {
+            Snapshot::Sequence { ref len_func, .. } => Ok(
+                len_func.apply(vec![Expr::snap_app(expr)]),
+            ),
+            _ => Err(EncodingError::internal(
+                format!("invalid sequence length (not Sequence): {}", ty),
+            )),
+        }
+    }
+
+    /// Encodes a read of the element at index [idx] of the given array,
+    /// slice or vector. The result is the snapshot of the element.
+    pub fn encode_sequence_read<'p, 'v: 'p>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        ty: ty::Ty<'tcx>,
+        expr: vir::Expr,
+        idx: vir::Expr,
+    ) -> EncodingResult<vir::Expr> {
+        let snapshot = self.encode_snapshot(encoder, ty)?;
+        match snapshot {
+            Snapshot::Sequence { ref read_func, .. } => Ok(
+                read_func.apply(vec![Expr::snap_app(expr), idx]),
+            ),
             _ => Err(EncodingError::internal(
-                format!("invalid constructor (not Complex): {}", ty),
+                format!("invalid sequence read (not Sequence): {}", ty),
             )),
         }
     }
 
+    /// Encodes the length in bytes of a string slice. [expr] can be Ref-based
+    /// or a snapshot already.
+    pub fn encode_str_len<'p, 'v: 'p>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        expr: vir::Expr,
+    ) -> EncodingResult<vir::Expr> {
+        let str_ty = encoder.env().tcx().types.str_;
+        match self.encode_snapshot(encoder, str_ty)? {
+            Snapshot::Str { ref len_func, .. } => Ok(
+                len_func.apply(vec![Expr::snap_app(expr)]),
+            ),
+            _ => Err(EncodingError::internal("invalid string length (not Str)")),
+        }
+    }
+
+    /// Encodes a string literal as the application of its constructor in the
+    /// snapshot domain of `str`.
+    pub fn encode_str_literal<'p, 'v: 'p>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        literal: &str,
+    ) -> EncodingResult<vir::Expr> {
+        let str_ty = encoder.env().tcx().types.str_;
+        let snapshot = self.encode_snapshot(encoder, str_ty)?;
+        let domain = match snapshot {
+            Snapshot::Str { ref domain, .. } => domain,
+            _ => return Err(EncodingError::internal("invalid string literal (not Str)")),
+        };
+        let index = match self.str_literals.iter().position(|l| l == literal) {
+            Some(index) => index,
+            None => {
+                self.str_literals.push(literal.to_string());
+                self.str_literals.len() - 1
+            }
+        };
+        Ok(str_literal_func(domain, snapshot.get_type(), index).apply(vec![]))
+    }
+
+    /// Rewrites the lengths and reads of the slice [slice_expr] in [expr] to
+    /// the lengths and reads of the array [array_expr], from which the slice
+    /// was obtained by an unsizing cast.
+    pub fn encode_sequence_unsize<'p, 'v: 'p>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        slice_ty: ty::Ty<'tcx>,
+        array_ty: ty::Ty<'tcx>,
+        slice_expr: vir::Expr,
+        array_expr: vir::Expr,
+        expr: vir::Expr,
+    ) -> EncodingResult<vir::Expr> {
+        struct UnsizeFixer {
+            slice_arg: vir::Expr,
+            array_arg: vir::Expr,
+            slice_funcs: (vir::DomainFunc, vir::DomainFunc),
+            array_funcs: (vir::DomainFunc, vir::DomainFunc),
+        }
+        impl ExprFolder for UnsizeFixer {
+            fn fold_domain_func_app(
+                &mut self,
+                func: vir::DomainFunc,
+                args: Vec<vir::Expr>,
+                pos: vir::Position,
+            ) -> vir::Expr {
+                let mut args: Vec<_> = args.into_iter()
+                    .map(|arg| self.fold(arg))
+                    .collect();
+                if args.first() == Some(&self.slice_arg) {
+                    if func == self.slice_funcs.0 {
+                        return self.array_funcs.0.apply(vec![self.array_arg.clone()]);
+                    }
+                    if func == self.slice_funcs.1 {
+                        args[0] = self.array_arg.clone();
+                        return self.array_funcs.1.apply(args);
+                    }
+                }
+                vir::Expr::DomainFuncApp(func, args, pos)
+            }
+        }
+        let funcs = |snapshot| match snapshot {
+            Snapshot::Sequence { len_func, read_func, .. } => Ok((len_func, read_func)),
+            _ => Err(EncodingError::internal(
+                format!("invalid unsizing (not Sequence): {} to {}", array_ty, slice_ty),
+            )),
+        };
+        let mut fixer = UnsizeFixer {
+            slice_arg: Expr::snap_app(slice_expr),
+            array_arg: Expr::snap_app(array_expr),
+            slice_funcs: funcs(self.encode_snapshot(encoder, slice_ty)?)?,
+            array_funcs: funcs(self.encode_snapshot(encoder, array_ty)?)?,
+        };
+        Ok(fixer.fold(expr))
+    }
+
     /// Encodes the snapshot of the given type and returns a VIR type
     /// representing that snapshot.
-    pub fn encode_type<'p, 'v: 'p, 'tcx: 'v>(
+    pub fn encode_type<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         ty: ty::Ty<'tcx>,
     ) -> EncodingResult<Type> {
+        let resolved_ty = encoder.resolve_typaram(strip_refs_and_boxes(ty));
+        if let Some(typ) = self.encoded_types.get(&resolved_ty) {
+            return Ok(typ.clone());
+        }
         self.encode_snapshot(encoder, ty)
             .map(|snapshot| snapshot.get_type())
     }
@@ -412,7 +864,23 @@ impl SnapshotEncoder {
     /// * Caching to avoid duplicate encodings.
     /// * Preventing infinite loops when encoding recursive types.
     /// * Triggering the real encoding [encode_snapshot_internal].
-    fn encode_snapshot<'p, 'v: 'p, 'tcx: 'v>(
+    /// * Measuring the time spent encoding snapshots.
+    fn encode_snapshot<'p, 'v: 'p>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<Snapshot> {
+        // only measure the outermost encoding, which includes the nested ones
+        if !self.in_progress.is_empty() {
+            return self.encode_snapshot_cached(encoder, ty);
+        }
+        let start = Instant::now();
+        let result = self.encode_snapshot_cached(encoder, ty);
+        self.encoding_duration += start.elapsed();
+        result
+    }
+
+    fn encode_snapshot_cached<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         ty: ty::Ty<'tcx>,
@@ -441,6 +909,7 @@ impl SnapshotEncoder {
             ty::TyKind::Tuple(substs) if substs.is_empty() => self.snap_unit().get_type().clone(),
             ty::TyKind::Adt(adt_def, _) if adt_def.variants.is_empty() => self.snap_unit().get_type().clone(),
             ty::TyKind::Adt(adt_def, _) if adt_def.variants.len() == 1 && adt_def.variants[rustc_target::abi::VariantIdx::from_u32(0)].fields.is_empty() => self.snap_unit().get_type().clone(),
+            ty::TyKind::Closure(_, substs) if substs.as_closure().upvar_tys().next().is_none() => self.snap_unit().get_type().clone(),
 
             // Param(_) | Adt(_) | Tuple(_) and unsupported types
             _ => Type::Snapshot(predicate_name.to_string()),
@@ -462,12 +931,14 @@ impl SnapshotEncoder {
 
         // remember resulting snapshot
         assert_eq!(snapshot_type, snapshot.get_type());
+        encoder.register_generated_snapshot_items(snapshot.get_generated_item_names(), ty);
         self.encoded.insert(predicate_name.to_string(), snapshot.clone());
+        self.encoded_types.insert(ty, snapshot_type);
 
         Ok(snapshot)
     }
 
-    fn encode_snapshot_internal<'p, 'v: 'p, 'tcx: 'v>(
+    fn encode_snapshot_internal<'p, 'v: 'p>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
         ty: ty::Ty<'tcx>,
@@ -496,8 +967,31 @@ impl SnapshotEncoder {
             ty::TyKind::Tuple(substs) if substs.is_empty() => Ok(Snapshot::Unit),
             ty::TyKind::Adt(adt_def, _) if adt_def.variants.is_empty() => Ok(Snapshot::Unit),
             ty::TyKind::Adt(adt_def, _) if adt_def.variants.len() == 1 && adt_def.variants[rustc_target::abi::VariantIdx::from_u32(0)].fields.is_empty() => Ok(Snapshot::Unit),
+            ty::TyKind::Closure(_, substs) if substs.as_closure().upvar_tys().next().is_none() => Ok(Snapshot::Unit),
+
+            // TODO: never type
 
-            // TODO: closures, never type
+            // The state of a closure is the tuple of its captured places
+            ty::TyKind::Closure(_, substs) => {
+                let mut fields = vec![];
+                for (field_num, field_ty) in substs.as_closure().upvar_tys().enumerate() {
+                    let field_name = format!("closure_{}", field_num);
+                    fields.push(SnapshotField {
+                        name: field_name.to_string(),
+                        access: self.snap_app(encoder, Expr::field(
+                            arg_expr.clone(),
+                            encoder.encode_raw_ref_field(field_name.to_string(), field_ty)?,
+                        ))?,
+                        mir_type: field_ty,
+                        typ: self.encode_type(encoder, field_ty)?,
+                    });
+                }
+                self.encode_complex(encoder, vec![SnapshotVariant {
+                    discriminant: -1,
+                    fields,
+                    name: None,
+                }], predicate_name)
+            }
 
             ty::TyKind::Tuple(substs) => {
                 let mut fields = vec![];
@@ -520,6 +1014,42 @@ impl SnapshotEncoder {
                     name: None,
                 }], predicate_name)
             }
+            ty::TyKind::Adt(..) if encoder.env().get_vec_elem_type(ty).is_some() => {
+                let elem_ty = encoder.env().get_vec_elem_type(ty).unwrap();
+                let elem_ty_pred = encoder.encode_type_predicate_use(elem_ty)?;
+                let lookup_func_name = encoder.encode_builtin_function_use(
+                    BuiltinFunctionKind::VecLookupPure {
+                        vec_ty_pred: predicate_name.to_string(),
+                        elem_ty_pred: elem_ty_pred.clone(),
+                        return_ty: self.encode_type(encoder, elem_ty)?,
+                    }
+                );
+                let len_func_name = encoder.encode_builtin_function_use(
+                    BuiltinFunctionKind::VecLen {
+                        vec_ty_pred: predicate_name.to_string(),
+                        elem_ty_pred,
+                    }
+                );
+                let arg_self = vir::LocalVar::new(
+                    "self",
+                    Type::TypedRef(predicate_name.to_string()),
+                );
+                let vec_len = Expr::func_app(
+                    len_func_name,
+                    vec![Expr::local(arg_self.clone())],
+                    vec![arg_self],
+                    Type::Int,
+                    vir::Position::default(),
+                );
+                self.encode_sequence(
+                    encoder,
+                    elem_ty,
+                    lookup_func_name,
+                    Some(vec_len),
+                    None,
+                    predicate_name,
+                )
+            }
             ty::TyKind::Adt(adt_def, subst) if adt_def.is_struct() => {
                 let mut fields = vec![];
                 for field in adt_def.all_fields() { // or adt_def.variants[0].fields ?
@@ -586,18 +1116,135 @@ impl SnapshotEncoder {
                 self.encode_complex(encoder, variants, predicate_name)
             }
 
+            ty::TyKind::Array(elem_ty, size) => {
+                let array_len = encoder.encode_array_len(size)?.as_fixed();
+                let elem_snap_ty = self.encode_type(encoder, elem_ty)?;
+                let lookup_func_name = encoder.encode_builtin_function_use(
+                    BuiltinFunctionKind::ArrayLookupPure {
+                        array_ty_pred: predicate_name.to_string(),
+                        elem_ty_pred: encoder.encode_type_predicate_use(elem_ty)?,
+                        array_len,
+                        return_ty: elem_snap_ty,
+                    }
+                );
+                self.encode_sequence(
+                    encoder,
+                    elem_ty,
+                    lookup_func_name,
+                    array_len.map(Expr::from),
+                    array_len,
+                    predicate_name,
+                )
+            }
+            ty::TyKind::Slice(elem_ty) => {
+                let elem_ty_pred = encoder.encode_type_predicate_use(elem_ty)?;
+                let lookup_func_name = encoder.encode_builtin_function_use(
+                    BuiltinFunctionKind::SliceLookupPure {
+                        slice_ty_pred: predicate_name.to_string(),
+                        elem_ty_pred: elem_ty_pred.clone(),
+                        return_ty: self.encode_type(encoder, elem_ty)?,
+                    }
+                );
+                let len_func_name = encoder.encode_builtin_function_use(
+                    BuiltinFunctionKind::SliceLen {
+                        slice_ty_pred: predicate_name.to_string(),
+                        elem_ty_pred,
+                    }
+                );
+                let arg_self = vir::LocalVar::new(
+                    "self",
+                    Type::TypedRef(predicate_name.to_string()),
+                );
+                let slice_len = Expr::func_app(
+                    len_func_name,
+                    vec![Expr::local(arg_self.clone())],
+                    vec![arg_self],
+                    Type::Int,
+                    vir::Position::default(),
+                );
+                self.encode_sequence(
+                    encoder,
+                    elem_ty,
+                    lookup_func_name,
+                    Some(slice_len),
+                    None,
+                    predicate_name,
+                )
+            }
+
+            ty::TyKind::Str => self.encode_str(predicate_name),
+
             // Param(_) and unsupported types
             _ => self.encode_abstract(predicate_name),
         }
     }
 
-    fn encode_abstract<'p, 'v: 'p, 'tcx: 'v>(
+    /// Encodes the snapshot of a type whose values cannot be constructed or
+    /// inspected. Its domain has an uninterpreted equality function `eq$`,
+    /// which is only axiomatised to be an equivalence relation:
+    /// ```plain
+    /// forall x :: {eq(x, x)} eq(x, x)
+    /// forall x, y :: {eq(x, y)} eq(x, y) == eq(y, x)
+    /// forall x, y, z :: {eq(x, y), eq(y, z)} eq(x, y) && eq(y, z) ==> eq(x, z)
+    /// ```
+    /// Type parameters are encoded this way when verifying a generic
+    /// function. At a monomorphic call site the contract of the callee is
+    /// encoded with the type parameters substituted, so there the equality
+    /// becomes the snapshot equality of the concrete type.
+    fn encode_abstract<'p, 'v: 'p>(
         &self,
         predicate_name: &str,
     ) -> EncodingResult<Snapshot> {
         let domain_name = format!("Snap${}", predicate_name);
         let snapshot_type = Type::Snapshot(predicate_name.to_string());
 
+        // encode equality function and its axioms
+        let dom_local = |name: &str| vir::LocalVar::new(name, snapshot_type.clone());
+        let (x, y, z) = (dom_local("_x"), dom_local("_y"), dom_local("_z"));
+        let equality_func = vir::DomainFunc {
+            name: "eq$".to_string(),
+            formal_args: vec![x.clone(), y.clone()],
+            return_type: Type::Bool,
+            unique: false,
+            domain_name: domain_name.to_string(),
+        };
+        let eq = |l: &vir::LocalVar, r: &vir::LocalVar| equality_func.apply(vec![
+            Expr::local(l.clone()),
+            Expr::local(r.clone()),
+        ]);
+        let axioms = vec![
+            vir::DomainAxiom {
+                name: format!("{}$eq$reflexivity", domain_name),
+                expr: Expr::forall(
+                    vec![x.clone()],
+                    vec![vir::Trigger::new(vec![eq(&x, &x)])],
+                    eq(&x, &x),
+                ),
+                domain_name: domain_name.to_string(),
+            },
+            vir::DomainAxiom {
+                name: format!("{}$eq$symmetry", domain_name),
+                expr: Expr::forall(
+                    vec![x.clone(), y.clone()],
+                    vec![vir::Trigger::new(vec![eq(&x, &y)])],
+                    Expr::eq_cmp(eq(&x, &y), eq(&y, &x)),
+                ),
+                domain_name: domain_name.to_string(),
+            },
+            vir::DomainAxiom {
+                name: format!("{}$eq$transitivity", domain_name),
+                expr: Expr::forall(
+                    vec![x.clone(), y.clone(), z.clone()],
+                    vec![vir::Trigger::new(vec![eq(&x, &y), eq(&y, &z)])],
+                    Expr::implies(
+                        Expr::and(eq(&x, &y), eq(&y, &z)),
+                        eq(&x, &z),
+                    ),
+                ),
+                domain_name: domain_name.to_string(),
+            },
+        ];
+
         let arg_ref_local = vir::LocalVar::new(
             "self",
             Type::TypedRef(predicate_name.to_string()),
@@ -622,19 +1269,317 @@ impl SnapshotEncoder {
             predicate_name: predicate_name.to_string(),
             domain: vir::Domain {
                 name: domain_name,
-                functions: vec![],
-                axioms: vec![],
+                functions: vec![equality_func.clone()],
+                axioms,
+                type_vars: vec![],
+            },
+            snap_func,
+            equality_func,
+        })
+    }
+
+    /// Encodes the snapshot of string slices. The predicate of `str` is
+    /// abstract, so the snapshot of a string that is not a literal is only
+    /// known to have a non-negative length. The constructors of the literals
+    /// are added to the domain by [get_viper_domains], once all the literals
+    /// of the program have been encoded.
+    fn encode_str(&self, predicate_name: &str) -> EncodingResult<Snapshot> {
+        let domain_name = format!("Snap${}", predicate_name);
+        let snapshot_type = Type::Snapshot(predicate_name.to_string());
+
+        let arg_dom_local = vir::LocalVar::new("self", snapshot_type.clone());
+        let arg_dom_expr = Expr::local(arg_dom_local.clone());
+        let len_func = vir::DomainFunc {
+            name: format!("{}$len", domain_name),
+            formal_args: vec![arg_dom_local.clone()],
+            return_type: Type::Int,
+            unique: false,
+            domain_name: domain_name.to_string(),
+        };
+        let literal_index_func = vir::DomainFunc {
+            name: format!("{}$literal_index", domain_name),
+            formal_args: vec![arg_dom_local.clone()],
+            return_type: Type::Int,
+            unique: false,
+            domain_name: domain_name.to_string(),
+        };
+
+        // encode length axiom:
+        // ```plain
+        // forall self :: {len(self)} len(self) >= 0
+        // ```
+        let len_call = len_func.apply(vec![arg_dom_expr]);
+        let len_axiom = vir::DomainAxiom {
+            name: format!("{}$len_axiom", domain_name),
+            expr: Expr::forall(
+                vec![arg_dom_local],
+                vec![vir::Trigger::new(vec![len_call.clone()])],
+                Expr::ge_cmp(len_call, 0.into()),
+            ),
+            domain_name: domain_name.to_string(),
+        };
+
+        let arg_ref_local = vir::LocalVar::new(
+            "self",
+            Type::TypedRef(predicate_name.to_string()),
+        );
+
+        // encode snap function
+        let snap_func = vir::Function {
+            name: SNAP_FUNC_NAME.to_string(),
+            formal_args: vec![arg_ref_local.clone()],
+            return_type: snapshot_type,
+            pres: vec![Expr::predicate_access_predicate(
+                predicate_name,
+                Expr::local(arg_ref_local),
+                PermAmount::Read,
+            )],
+            posts: vec![],
+            body: None,
+        };
+
+        Ok(Snapshot::Str {
+            predicate_name: predicate_name.to_string(),
+            domain: vir::Domain {
+                name: domain_name,
+                functions: vec![len_func.clone(), literal_index_func.clone()],
+                axioms: vec![len_axiom],
+                type_vars: vec![],
+            },
+            snap_func,
+            len_func,
+            literal_index_func,
+        })
+    }
+
+    /// Encodes the snapshot for a fixed-size array, a slice or a vector. The snapshot
+    /// domain has a length function and a function to read the element at a
+    /// given index, together with an extensionality axiom: two snapshots are
+    /// equal iff they have the same length and equal elements.
+    ///
+    /// [lookup_func_name] is the name of the (Ref-based) `lookup_pure`
+    /// function of the sequence, [ref_len] is its length in terms of
+    /// the Ref-based `self` argument, and [fixed_len] is the statically known
+    /// length for arrays. Both are `None` for arrays whose length is a const
+    /// generic parameter. The returned snapshot will be of the
+    /// [Snapshot::Sequence] variant.
+    fn encode_sequence<'p, 'v: 'p>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        elem_ty: ty::Ty<'tcx>,
+        lookup_func_name: String,
+        ref_len: Option<Expr>,
+        fixed_len: Option<usize>,
+        predicate_name: &str,
+    ) -> EncodingResult<Snapshot> {
+        let domain_name = format!("Snap${}", predicate_name);
+        let snapshot_type = Type::Snapshot(predicate_name.to_string());
+        let elem_snap_ty = self.encode_type(encoder, elem_ty)?;
+        let elem_supports_equality = self.supports_equality(encoder, elem_ty)?;
+        let mut domain_axioms = vec![];
+
+        let arg_ref_local = vir::LocalVar::new(
+            "self",
+            Type::TypedRef(predicate_name.to_string()),
+        );
+        let arg_dom_local = vir::LocalVar::new(
+            "self",
+            snapshot_type.clone(),
+        );
+        let idx_local = vir::LocalVar::new("idx", Type::Int);
+        let arg_ref_expr = Expr::local(arg_ref_local.clone());
+        let arg_dom_expr = Expr::local(arg_dom_local.clone());
+        let idx_expr = Expr::local(idx_local.clone());
+
+        let len_func = vir::DomainFunc {
+            name: format!("{}$len", domain_name),
+            formal_args: vec![arg_dom_local.clone()],
+            return_type: Type::Int,
+            unique: false,
+            domain_name: domain_name.to_string(),
+        };
+        let read_func = vir::DomainFunc {
+            name: format!("{}$read", domain_name),
+            formal_args: vec![arg_dom_local.clone(), idx_local.clone()],
+            return_type: elem_snap_ty.clone(),
+            unique: false,
+            domain_name: domain_name.to_string(),
+        };
+
+        // 0 <= idx && idx < len(snapshot)
+        let in_bounds = |snapshot: Expr, idx: Expr| Expr::and(
+            Expr::le_cmp(0.into(), idx.clone()),
+            Expr::lt_cmp(idx, len_func.apply(vec![snapshot])),
+        );
+
+        // encode length axiom:
+        // ```plain
+        // forall self :: {len(self)} len(self) == N  // arrays
+        // forall self :: {len(self)} len(self) >= 0  // slices and vectors
+        // ```
+        domain_axioms.push({
+            let len_call = len_func.apply(vec![arg_dom_expr.clone()]);
+            vir::DomainAxiom {
+                name: format!("{}$len_axiom", domain_name),
+                expr: Expr::forall(
+                    vec![arg_dom_local.clone()],
+                    vec![vir::Trigger::new(vec![len_call.clone()])],
+                    match fixed_len {
+                        Some(len) => Expr::eq_cmp(len_call, len.into()),
+                        None => Expr::ge_cmp(len_call, 0.into()),
+                    },
+                ),
+                domain_name: domain_name.to_string(),
+            }
+        });
+
+        // encode extensionality axiom:
+        // ```plain
+        // forall _l, _r :: {len(_l), len(_r)}
+        //   len(_l) == len(_r)
+        //   && (forall idx :: {read(_l, idx)} {read(_r, idx)}
+        //     0 <= idx && idx < len(_l) ==> read(_l, idx) == read(_r, idx))
+        //   ==> _l == _r
+        // ```
+        domain_axioms.push({
+            let lhs_local = vir::LocalVar::new("_l", snapshot_type.clone());
+            let rhs_local = vir::LocalVar::new("_r", snapshot_type.clone());
+            let lhs_expr = Expr::local(lhs_local.clone());
+            let rhs_expr = Expr::local(rhs_local.clone());
+            let lhs_len = len_func.apply(vec![lhs_expr.clone()]);
+            let rhs_len = len_func.apply(vec![rhs_expr.clone()]);
+            let lhs_read = read_func.apply(vec![lhs_expr.clone(), idx_expr.clone()]);
+            let rhs_read = read_func.apply(vec![rhs_expr.clone(), idx_expr.clone()]);
+
+            vir::DomainAxiom {
+                name: format!("{}$extensionality", domain_name),
+                expr: Expr::forall(
+                    vec![lhs_local, rhs_local],
+                    vec![vir::Trigger::new(vec![lhs_len.clone(), rhs_len.clone()])],
+                    Expr::implies(
+                        Expr::and(
+                            Expr::eq_cmp(lhs_len, rhs_len),
+                            Expr::forall(
+                                vec![idx_local.clone()],
+                                vec![
+                                    vir::Trigger::new(vec![lhs_read.clone()]),
+                                    vir::Trigger::new(vec![rhs_read.clone()]),
+                                ],
+                                Expr::implies(
+                                    in_bounds(lhs_expr.clone(), idx_expr.clone()),
+                                    Expr::eq_cmp(lhs_read, rhs_read),
+                                ),
+                            ),
+                        ),
+                        Expr::eq_cmp(lhs_expr, rhs_expr),
+                    ),
+                ),
+                domain_name: domain_name.to_string(),
+            }
+        });
+
+        // encode type validity axiom for elements
+        // TODO: encode type invariants rather than just integer bounds
+        match elem_ty.kind() {
+            ty::TyKind::Int(_)
+            | ty::TyKind::Uint(_)
+            | ty::TyKind::Char => domain_axioms.push({
+                let read_call = read_func.apply(vec![arg_dom_expr.clone(), idx_expr.clone()]);
+
+                vir::DomainAxiom {
+                    name: format!("{}$elem$valid", domain_name),
+                    expr: Expr::forall(
+                        vec![arg_dom_local.clone(), idx_local.clone()],
+                        vec![vir::Trigger::new(vec![read_call.clone()])],
+                        Expr::implies(
+                            in_bounds(arg_dom_expr.clone(), idx_expr.clone()),
+                            encoder.encode_type_bounds(&read_call, elem_ty)
+                                .into_iter()
+                                .conjoin(),
+                        ),
+                    ),
+                    domain_name: domain_name.to_string(),
+                }
+            }),
+            _ => {},
+        }
+
+        // encode snap function; the array predicate is abstract, so the
+        // snapshot is related to the Ref-based lookup function in the
+        // postcondition:
+        // ```plain
+        // ensures len(result) == <ref_len>  // unless the length is a const parameter
+        // ensures forall idx :: {read(result, idx)} {lookup_pure(self, idx)}
+        //   0 <= idx && idx < len(result) ==> read(result, idx) == lookup_pure(self, idx)
+        // ```
+        let snap_func = {
+            let result_expr = Expr::local(vir::LocalVar::new(
+                "__result",
+                snapshot_type.clone(),
+            ));
+            let result_read = read_func.apply(vec![result_expr.clone(), idx_expr.clone()]);
+            let lookup_call = Expr::func_app(
+                lookup_func_name,
+                vec![arg_ref_expr.clone(), idx_expr.clone()],
+                vec![arg_ref_local.clone(), idx_local.clone()],
+                elem_snap_ty,
+                vir::Position::default(),
+            );
+
+            let mut posts = vec![];
+            if let Some(ref_len) = ref_len {
+                posts.push(Expr::eq_cmp(
+                    len_func.apply(vec![result_expr.clone()]),
+                    ref_len,
+                ));
+            }
+            posts.push(Expr::forall(
+                vec![idx_local],
+                vec![
+                    vir::Trigger::new(vec![result_read.clone()]),
+                    vir::Trigger::new(vec![lookup_call.clone()]),
+                ],
+                Expr::implies(
+                    in_bounds(result_expr, idx_expr),
+                    Expr::eq_cmp(result_read, lookup_call),
+                ),
+            ));
+
+            vir::Function {
+                name: SNAP_FUNC_NAME.to_string(),
+                formal_args: vec![arg_ref_local.clone()],
+                return_type: snapshot_type.clone(),
+                pres: vec![Expr::predicate_access_predicate(
+                    predicate_name,
+                    arg_ref_expr.clone(),
+                    PermAmount::Read,
+                )],
+                posts,
+                body: None,
+            }
+        };
+
+        Ok(Snapshot::Sequence {
+            predicate_name: predicate_name.to_string(),
+            domain: vir::Domain {
+                name: domain_name,
+                functions: vec![len_func.clone(), read_func.clone()],
+                axioms: domain_axioms,
                 type_vars: vec![],
             },
             snap_func,
+            len_func,
+            read_func,
+            elem_supports_equality,
         })
     }
 
     /// Encodes the snapshot for a complex data structure (tuple, struct,
     /// enum, or closure). There must be one or more variants, at least one
     /// with one or more fields to encode. The returned snapshot will be of the
-    /// [Snapshot::Complex] variant.
-    fn encode_complex<'p, 'v: 'p, 'tcx: 'v>(
+    /// [Snapshot::Enum] variant if there are several variants, and of the
+    /// [Snapshot::Struct] variant otherwise.
+    fn encode_complex<'p, 'v: 'p>(
         &self,
         encoder: &'p Encoder<'v, 'tcx>,
         variants: Vec<SnapshotVariant<'tcx>>,
@@ -647,7 +1592,7 @@ impl SnapshotEncoder {
         let mut domain_axioms = vec![];
         let mut variant_domain_funcs = vec![];
         let mut variant_snap_bodies = vec![];
-        let mut variant_names = HashMap::new();
+        let mut variant_names = BTreeMap::new();
 
         // a local called "self", both as a Ref and as a Snapshot
         let arg_ref_local = vir::LocalVar::new(
@@ -813,7 +1758,7 @@ impl SnapshotEncoder {
                 });
             }
 
-            let mut field_access_funcs = HashMap::new();
+            let mut field_access_funcs = BTreeMap::new();
 
             for (field_idx, field) in variant.fields.iter().enumerate() {
                 // encode field access function
@@ -933,6 +1878,15 @@ impl SnapshotEncoder {
             encoder.get_used_viper_predicates_map(),
         ).unwrap();
 
+        if has_multiple_variants {
+            domain_axioms.extend(encode_enum_axioms(
+                &domain_name,
+                &discriminant_func,
+                &variants,
+                &variant_domain_funcs,
+            ));
+        }
+
         // create domain
         let domain = vir::Domain {
             name: domain_name,
@@ -941,15 +1895,110 @@ impl SnapshotEncoder {
             type_vars: vec![],
         };
 
-        Ok(Snapshot::Complex {
-            predicate_name: predicate_name.to_string(),
-            domain,
-            discriminant_func,
-            snap_func,
-            variants: variant_domain_funcs,
-            variant_names,
-        })
+        if has_multiple_variants {
+            Ok(Snapshot::Enum {
+                predicate_name: predicate_name.to_string(),
+                domain,
+                snap_func,
+                discriminant_func,
+                variants: variant_domain_funcs,
+                variant_names,
+            })
+        } else {
+            let (constructor, fields) = variant_domain_funcs.pop().unwrap();
+            Ok(Snapshot::Struct {
+                predicate_name: predicate_name.to_string(),
+                domain,
+                snap_func,
+                constructor,
+                fields,
+            })
+        }
+    }
+}
+
+/// Encodes the axioms of the snapshot domain of an enum that relate its
+/// variants to each other:
+/// * exhaustiveness: a snapshot is built by the constructor of the variant
+///   given by its discriminant, applied to its own fields:
+///   ```plain
+///   forall self :: {discriminant(self)}
+///     discriminant(self) == N ==> self == cons<N>(field<N>_0(self), ...)
+///   ```
+/// * disjointness: the constructors of distinct variants build distinct
+///   snapshots:
+///   ```plain
+///   forall _l_args..., _r_args... :: {cons<N>(_l_args...), cons<M>(_r_args...)}
+///     cons<N>(_l_args...) != cons<M>(_r_args...)
+///   ```
+/// The range of the discriminant and the discriminant of each constructor are
+/// axiomatised together with the constructors.
+fn encode_enum_axioms(
+    domain_name: &str,
+    discriminant_func: &vir::DomainFunc,
+    variants: &[SnapshotVariant<'_>],
+    variant_domain_funcs: &[(vir::DomainFunc, BTreeMap<String, vir::DomainFunc>)],
+) -> Vec<vir::DomainAxiom> {
+    let mut axioms = vec![];
+    let self_local = discriminant_func.formal_args[0].clone();
+    let self_expr = Expr::local(self_local.clone());
+    let disc_call = discriminant_func.apply(vec![self_expr.clone()]);
+    for (variant_idx, (variant, (constructor, field_funcs))) in
+        variants.iter().zip(variant_domain_funcs).enumerate()
+    {
+        let fields_of_self = variant.fields.iter()
+            .map(|field| field_funcs[&field.name].apply(vec![self_expr.clone()]))
+            .collect();
+        axioms.push(vir::DomainAxiom {
+            name: format!("{}${}$exhaustiveness", domain_name, variant_idx),
+            expr: Expr::forall(
+                vec![self_local.clone()],
+                vec![vir::Trigger::new(vec![disc_call.clone()])],
+                Expr::implies(
+                    Expr::eq_cmp(disc_call.clone(), variant.discriminant.into()),
+                    Expr::eq_cmp(self_expr.clone(), constructor.apply(fields_of_self)),
+                ),
+            ),
+            domain_name: domain_name.to_string(),
+        });
     }
+    let prefixed_args = |prefix: &str, constructor: &vir::DomainFunc| -> Vec<vir::LocalVar> {
+        constructor.formal_args.iter()
+            .map(|arg| vir::LocalVar::new(format!("{}{}", prefix, arg.name), arg.typ.clone()))
+            .collect()
+    };
+    for (lhs_idx, (lhs_constructor, _)) in variant_domain_funcs.iter().enumerate() {
+        for (rhs_idx, (rhs_constructor, _)) in variant_domain_funcs.iter().enumerate().skip(lhs_idx + 1) {
+            let lhs_args = prefixed_args("_l", lhs_constructor);
+            let rhs_args = prefixed_args("_r", rhs_constructor);
+            let lhs_call = lhs_constructor.apply(lhs_args.iter().cloned().map(Expr::local).collect());
+            let rhs_call = rhs_constructor.apply(rhs_args.iter().cloned().map(Expr::local).collect());
+            // Calls of nullary constructors do not need to be triggers
+            let mut trigger_terms = vec![];
+            if !lhs_args.is_empty() {
+                trigger_terms.push(lhs_call.clone());
+            }
+            if !rhs_args.is_empty() {
+                trigger_terms.push(rhs_call.clone());
+            }
+            let disjoint = Expr::ne_cmp(lhs_call, rhs_call);
+            let forall_vars: Vec<_> = lhs_args.into_iter().chain(rhs_args).collect();
+            axioms.push(vir::DomainAxiom {
+                name: format!("{}${}${}$disjointness", domain_name, lhs_idx, rhs_idx),
+                expr: if forall_vars.is_empty() {
+                    disjoint
+                } else {
+                    Expr::forall(
+                        forall_vars,
+                        vec![vir::Trigger::new(trigger_terms)],
+                        disjoint,
+                    )
+                },
+                domain_name: domain_name.to_string(),
+            });
+        }
+    }
+    axioms
 }
 
 struct SnapshotVariant<'tcx> {
@@ -964,3 +2013,14 @@ struct SnapshotField<'tcx> {
     mir_type: ty::Ty<'tcx>,
     typ: Type,
 }
+
+/// Viper identifiers of the domain functions of one variant of a Struct or Enum
+/// snapshot, used to read back values of that variant from a counterexample.
+pub struct SnapshotVariantFunctions {
+    /// The variant name (as used by Prusti), for enums.
+    pub name: Option<String>,
+    pub constructor: String,
+    /// Field access functions, keyed by the field name used in the snapshot
+    /// encoding (e.g. `f$x` or `tuple_0`).
+    pub fields: BTreeMap<String, String>,
+}
@@ -7,7 +7,7 @@
 use rustc_middle::ty;
 use prusti_common::vir;
 use prusti_common::vir::{Expr, Type};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 pub mod encoder;
 mod patcher;
@@ -21,26 +21,67 @@ enum Snapshot {
     /// Encodes types with no content; these need not be provided as arguments
     /// to snapshot constructors.
     Unit,
-    /// Encodes a complex type: tuples, ADTs, or closures.
-    Complex {
+    /// Encodes a type with a single variant: tuples, structs, closures, and
+    /// enums with one variant.
+    Struct {
+        predicate_name: String,
+        domain: vir::Domain,
+        snap_func: vir::Function,
+        /// The constructor, which takes the snapshots of the fields.
+        constructor: vir::DomainFunc,
+        /// The field access functions, keyed by their name.
+        fields: BTreeMap<String, vir::DomainFunc>,
+    },
+    /// Encodes an enum with two or more variants. Besides the injectivity of
+    /// the constructors, the domain axiomatises that the discriminant is one
+    /// of the variants, and that every snapshot is built by the constructor
+    /// of the variant given by its discriminant.
+    Enum {
         predicate_name: String,
         domain: vir::Domain,
-        discriminant_func: vir::DomainFunc,
         snap_func: vir::Function,
-        /// [variants] has one entry for tuples, structs, and closures.
-        /// For enums, it has as many entries as there are variants.
-        /// The first function is the constructor, the hashmap encodes the
-        /// field access functions, keyed by their name.
-        variants: Vec<(vir::DomainFunc, HashMap<String, vir::DomainFunc>)>,
+        discriminant_func: vir::DomainFunc,
+        /// One entry per variant: the constructor and the field access
+        /// functions, keyed by their name.
+        variants: Vec<(vir::DomainFunc, BTreeMap<String, vir::DomainFunc>)>,
         /// Mapping of variant names (as used by Prusti) to variant indices
-        /// in the [variants] vector. Empty for non-enums.
-        variant_names: HashMap<String, usize>,
-    }, // TODO: separate variant for enums and one-variant Complexes?
+        /// in the [variants] vector.
+        variant_names: BTreeMap<String, usize>,
+    },
+    /// Encodes fixed-size arrays, slices and vectors as a sequence domain,
+    /// with a length function and a function to read the element at an index.
+    Sequence {
+        predicate_name: String,
+        domain: vir::Domain,
+        snap_func: vir::Function,
+        /// Returns the number of elements of the snapshot.
+        len_func: vir::DomainFunc,
+        /// Returns the snapshot of the element at the given index.
+        read_func: vir::DomainFunc,
+        /// Whether the elements themselves support snapshot equality.
+        elem_supports_equality: bool,
+    },
+    /// Encodes string slices. The domain has a length function, and a
+    /// constructor for each string literal of the program; see
+    /// [encoder::SnapshotEncoder::get_viper_domains].
+    Str {
+        predicate_name: String,
+        domain: vir::Domain,
+        snap_func: vir::Function,
+        /// Returns the length in bytes of the snapshot.
+        len_func: vir::DomainFunc,
+        /// Returns the index of a literal, used to axiomatise that distinct
+        /// literals have distinct snapshots.
+        literal_index_func: vir::DomainFunc,
+    },
     /// Type cannot be encoded: type parameters, unsupported types.
     Abstract {
         predicate_name: String,
         domain: vir::Domain,
         snap_func: vir::Function,
+        /// Uninterpreted equivalence relation used to encode `==` between
+        /// instances of the type.
+        equality_func: vir::DomainFunc,
     },
 
     /// A type which will be resolved to a different snapshot kind.
@@ -53,26 +94,72 @@ impl Snapshot {
         match self {
             Self::Primitive(ty) => ty.clone(),
             Self::Unit => Type::Domain(encoder::UNIT_DOMAIN_NAME.to_string()),
-            Self::Complex { predicate_name, .. } => Type::Snapshot(predicate_name.to_string()),
+            Self::Struct { predicate_name, .. } => Type::Snapshot(predicate_name.to_string()),
+            Self::Enum { predicate_name, .. } => Type::Snapshot(predicate_name.to_string()),
+            Self::Sequence { predicate_name, .. } => Type::Snapshot(predicate_name.to_string()),
+            Self::Str { predicate_name, .. } => Type::Snapshot(predicate_name.to_string()),
             Self::Abstract { predicate_name, .. } => Type::Snapshot(predicate_name.to_string()),
             Self::Lazy(ty) => ty.clone(),
         }
     }
 
+    /// Whether a variable of this snapshot can be bound by a quantifier.
+    /// For [Struct], [Enum] and [Sequence] snapshots the fields or elements need to
+    /// be checked as well; see [encoder::SnapshotEncoder::is_quantifiable].
     pub fn is_quantifiable(&self) -> bool {
-        // TODO: allow more types?
         match self {
             Self::Primitive(_) => true,
+            Self::Unit => true,
+            Self::Struct { .. } => true,
+            Self::Enum { .. } => true,
+            Self::Sequence { .. } => true,
             _ => false,
         }
     }
 
+    /// Whether instances of this snapshot can be compared by snapshot
+    /// equality. A [Lazy] snapshot is a recursive occurrence of a type that
+    /// is being encoded, whose equality is decided by the enclosing encoding.
+    /// [Abstract] snapshots are compared with their uninterpreted equality
+    /// function.
     pub fn supports_equality(&self) -> bool {
         match self {
             Self::Primitive(_) => true,
             Self::Unit => true,
-            Self::Complex { .. } => true,
-            _ => false,
+            Self::Struct { .. } => true,
+            Self::Enum { .. } => true,
+            Self::Sequence { elem_supports_equality, .. } => *elem_supports_equality,
+            Self::Str { .. } => true,
+            Self::Abstract { .. } => true,
+            Self::Lazy(_) => true,
+        }
+    }
+
+    /// The names of the Viper function and of the domain functions generated
+    /// for this snapshot. Empty for snapshots that do not generate items.
+    pub fn get_generated_item_names(&self) -> Vec<String> {
+        let (domain, snap_func) = match self {
+            Self::Struct { domain, snap_func, .. }
+            | Self::Enum { domain, snap_func, .. }
+            | Self::Sequence { domain, snap_func, .. }
+            | Self::Str { domain, snap_func, .. }
+            | Self::Abstract { domain, snap_func, .. } => (domain, snap_func),
+            _ => return vec![],
+        };
+        std::iter::once(snap_func.name.clone())
+            .chain(domain.functions.iter().map(|func| func.name.clone()))
+            .collect()
+    }
+
+    /// The constructor and the field access functions of each variant of a
+    /// [Struct] or [Enum] snapshot. Empty for other snapshots.
+    pub fn get_variants(&self) -> Vec<(&vir::DomainFunc, &BTreeMap<String, vir::DomainFunc>)> {
+        match self {
+            Self::Struct { constructor, fields, .. } => vec![(constructor, fields)],
+            Self::Enum { variants, .. } => variants.iter()
+                .map(|(constructor, fields)| (constructor, fields))
+                .collect(),
+            _ => vec![],
         }
     }
 }
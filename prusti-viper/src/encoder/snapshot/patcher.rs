@@ -10,10 +10,93 @@ use crate::encoder::errors::{EncodingError, EncodingResult};
 use crate::encoder::snapshot::encoder::{UNIT_DOMAIN_NAME, SnapshotEncoder};
 
 pub(super) struct SnapshotPatcher<'v, 'tcx: 'v> {
-    pub(super) snapshot_encoder: &'v mut SnapshotEncoder,
+    pub(super) snapshot_encoder: &'v mut SnapshotEncoder<'tcx>,
     pub(super) encoder: &'v Encoder<'v, 'tcx>,
 }
 
+impl<'v, 'tcx: 'v> SnapshotPatcher<'v, 'tcx> {
+    /// Applies the snap function to an already patched expression. The
+    /// snapshot of a place in an old state is taken in that state, so the
+    /// snap function is applied under the `old[label]` rather than to the
+    /// old place in the current state.
+    fn snap_app(&mut self, expr: vir::Expr) -> EncodingResult<vir::Expr> {
+        match expr {
+            vir::Expr::LabelledOld(label, box expr, pos) => Ok(vir::Expr::LabelledOld(
+                label,
+                box self.snap_app(expr)?,
+                pos,
+            )),
+            expr => self.snapshot_encoder.snap_app(self.encoder, expr),
+        }
+    }
+
+    /// Patches the bound variables of a quantifier (built with
+    /// `constructor`) to their snapshot types.
+    fn fold_quantifier(
+        &mut self,
+        constructor: fn(Vec<vir::LocalVar>, Vec<vir::Trigger>, Box<vir::Expr>, vir::Position) -> vir::Expr,
+        vars: Vec<vir::LocalVar>,
+        triggers: Vec<vir::Trigger>,
+        expr: Box<vir::Expr>,
+        pos: vir::Position,
+    ) -> EncodingResult<vir::Expr> {
+        let mut expr = *expr;
+        let mut patched_vars = vec![];
+
+        // unpack triggers into Vec<Vec<Expr>>
+        let mut trigger_exprs = triggers
+            .into_iter()
+            .map(|trigger| trigger
+                .elements()
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        for var in vars {
+            match var.typ {
+                vir::Type::TypedRef(ref name) => {
+                    let ty = self.encoder.decode_type_predicate(name)?;
+                    let patched_var = vir::LocalVar::new(
+                        &var.name,
+                        self.snapshot_encoder.encode_type(self.encoder, ty)?,
+                    );
+                    patched_vars.push(patched_var.clone());
+                    let mut fixer = ForallFixer {
+                        var,
+                        patched_var,
+                    };
+                    expr = fixer.fold(expr);
+                    trigger_exprs = trigger_exprs
+                        .into_iter()
+                        .map(|trigger| trigger
+                            .into_iter()
+                            .map(|expr| fixer.fold(expr))
+                            .collect())
+                        .collect();
+                }
+                _ => patched_vars.push(var.clone()),
+            }
+        }
+        let expr = FallibleExprFolder::fallible_fold(self, expr)?;
+        Ok(constructor(
+            patched_vars,
+            trigger_exprs
+                .into_iter()
+                .map(|trigger| trigger
+                    .into_iter()
+                    .map(|expr| FallibleExprFolder::fallible_fold(self, expr))
+                    .collect::<Result<Vec<_>, _>>())
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .map(vir::Trigger::new)
+                .collect(),
+            box expr,
+            pos,
+        ))
+    }
+}
+
 impl<'v, 'tcx: 'v> FallibleExprFolder for SnapshotPatcher<'v, 'tcx> {
     type Error = EncodingError;
 
@@ -23,7 +106,7 @@ impl<'v, 'tcx: 'v> FallibleExprFolder for SnapshotPatcher<'v, 'tcx> {
         _p: vir::Position
     ) -> Result<vir::Expr, Self::Error> {
         let e = self.fallible_fold_boxed(e)?;
-        self.snapshot_encoder.snap_app(self.encoder, *e)
+        self.snap_app(*e)
     }
 
     fn fallible_fold_func_app(
@@ -41,7 +124,7 @@ impl<'v, 'tcx: 'v> FallibleExprFolder for SnapshotPatcher<'v, 'tcx> {
                 // TODO: this patches more than it should
                 // so it could cover up/muddle some type errors in the VIR
                 if *arg.get_type() != formal_arg.typ {
-                    self.snapshot_encoder.snap_app(self.encoder, arg)
+                    self.snap_app(arg)
                 } else {
                     Ok(arg)
                 }
@@ -104,60 +187,17 @@ impl<'v, 'tcx: 'v> FallibleExprFolder for SnapshotPatcher<'v, 'tcx> {
         expr: Box<vir::Expr>,
         pos: vir::Position,
     ) -> Result<vir::Expr, Self::Error> {
-        let mut expr = *expr;
-        let mut patched_vars = vec![];
-
-        // unpack triggers into Vec<Vec<Expr>>
-        let mut trigger_exprs = triggers
-            .into_iter()
-            .map(|trigger| trigger
-                .elements()
-                .iter()
-                .cloned()
-                .collect::<Vec<_>>())
-            .collect::<Vec<_>>();
+        self.fold_quantifier(vir::Expr::ForAll, vars, triggers, expr, pos)
+    }
 
-        for var in vars {
-            match var.typ {
-                vir::Type::TypedRef(ref name) => {
-                    let ty = self.encoder.decode_type_predicate(name)?;
-                    let patched_var = vir::LocalVar::new(
-                        &var.name,
-                        self.snapshot_encoder.encode_type(self.encoder, ty)?,
-                    );
-                    patched_vars.push(patched_var.clone());
-                    let mut fixer = ForallFixer {
-                        var,
-                        patched_var,
-                    };
-                    expr = fixer.fold(expr);
-                    trigger_exprs = trigger_exprs
-                        .into_iter()
-                        .map(|trigger| trigger
-                            .into_iter()
-                            .map(|expr| fixer.fold(expr))
-                            .collect())
-                        .collect();
-                }
-                _ => patched_vars.push(var.clone()),
-            }
-        }
-        let expr = FallibleExprFolder::fallible_fold(self, expr)?;
-        Ok(vir::Expr::ForAll(
-            patched_vars,
-            trigger_exprs
-                .into_iter()
-                .map(|trigger| trigger
-                    .into_iter()
-                    .map(|expr| FallibleExprFolder::fallible_fold(self, expr))
-                    .collect::<Result<Vec<_>, _>>())
-                .collect::<Result<Vec<_>, _>>()?
-                .into_iter()
-                .map(vir::Trigger::new)
-                .collect(),
-            box expr,
-            pos,
-        ))
+    fn fallible_fold_exists(
+        &mut self,
+        vars: Vec<vir::LocalVar>,
+        triggers: Vec<vir::Trigger>,
+        expr: Box<vir::Expr>,
+        pos: vir::Position,
+    ) -> Result<vir::Expr, Self::Error> {
+        self.fold_quantifier(vir::Expr::Exists, vars, triggers, expr, pos)
     }
 
     fn fallible_fold_downcast(
@@ -217,7 +257,7 @@ impl ExprFolder for ForallFixer {
         match *expr {
             vir::Expr::Local(v, pos) if v == self.var
                 => vir::Expr::Local(self.patched_var.clone(), pos),
-            _ => vir::Expr::SnapApp(expr, pos),
+            _ => vir::Expr::SnapApp(self.fold_boxed(expr), pos),
         }
     }
 
@@ -5,20 +5,24 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::encoder::errors::{
-    ErrorCtxt, SpannedEncodingResult, SpannedEncodingError, EncodingError, WithSpan
+    ErrorCtxt, SpannedEncodingResult, SpannedEncodingError, WithSpan
 };
 use crate::encoder::mir_encoder::{MirEncoder, PlaceEncoder, PlaceEncoding};
-use crate::encoder::mir_encoder::PRECONDITION_LABEL;
+use crate::encoder::mir_encoder::{
+    PRECONDITION_LABEL, LOOP_ENTRY_LABEL, LOOP_ITERATIONS_VAR, USER_LABEL_PREFIX,
+};
 use crate::encoder::mir_interpreter::{
     run_backward_interpretation_point_to_point, BackwardMirInterpreter,
     MultiExprBackwardInterpreterState,
 };
 use crate::encoder::pure_function_encoder::PureFunctionBackwardInterpreter;
 use crate::encoder::Encoder;
+use crate::encoder::type_encoder::compute_discriminant_values;
 use prusti_common::config;
 use crate::encoder::SpecFunctionKind;
 use prusti_common::vir;
 use prusti_common::vir::ExprIterator;
+use prusti_interface::specs::checker::collect_expressions;
 use prusti_interface::specs::typed;
 use rustc_hir as hir;
 use rustc_hir::def_id::DefId;
@@ -70,6 +74,84 @@ pub fn encode_spec_assertion<'v, 'tcx: 'v>(
     spec_encoder.encode_assertion(assertion)
 }
 
+/// Encode the precondition and the postcondition that the specification entailment
+/// `f |= |args| [requires(..), ensures(..)]` gives to a call of `f`.
+///
+/// Arguments:
+/// * `encoder`: a reference to the `Encoder`.
+/// * `assertion`: the specification entailment.
+/// * `target_args`: the expression to be used to encode the arguments of the procedure
+///   that has the specification entailment in its precondition.
+/// * `call_args`: the values of the arguments passed to `f`.
+/// * `call_result`: the value returned by `f`.
+pub fn encode_spec_entailment_call<'v, 'tcx: 'v>(
+    encoder: &Encoder<'v, 'tcx>,
+    assertion: &typed::Assertion<'tcx>,
+    target_args: &[vir::Expr],
+    call_args: &[vir::Expr],
+    call_result: &vir::Expr,
+) -> SpannedEncodingResult<(vir::Expr, vir::Expr)> {
+    let spec_encoder = SpecEncoder::new(
+        encoder,
+        "",
+        target_args,
+        None,
+        false,
+        None,
+    );
+    spec_encoder.encode_entailment_call(assertion, call_args, call_result)
+}
+
+/// Encode the components of the termination measure `decreases(e1, .., en)`.
+///
+/// Arguments:
+/// * `encoder`: a reference to the `Encoder`.
+/// * `measure`: the termination measure to be encoded.
+/// * `target_args`: the expression to be used to encode arguments.
+/// * `targets_are_values`: if `true`, the elements of `target_args` encode _values_ and not
+///   _memory locations_.
+/// * `measure_location`: the basic block at which the measure should be encoded. This should
+///   be `Some(..)` iff the measure is the one of a loop.
+pub fn encode_spec_measure<'v, 'tcx: 'v>(
+    encoder: &Encoder<'v, 'tcx>,
+    measure: &typed::Assertion<'tcx>,
+    target_args: &[vir::Expr],
+    targets_are_values: bool,
+    measure_location: Option<mir::BasicBlock>,
+) -> SpannedEncodingResult<Vec<vir::Expr>> {
+    let spec_encoder = SpecEncoder::new(
+        encoder,
+        "",
+        target_args,
+        None,
+        targets_are_values,
+        measure_location,
+    );
+    spec_encoder.encode_measure(measure)
+}
+
+/// Encode the condition under which the termination measure `new_measure` is smaller than
+/// `old_measure`, in the lexicographic order. The component at which the measure decreases
+/// also has to be non-negative, so that the order is well-founded.
+pub fn encode_measure_decrease(new_measure: &[vir::Expr], old_measure: &[vir::Expr]) -> vir::Expr {
+    assert_eq!(new_measure.len(), old_measure.len());
+    new_measure.iter().zip(old_measure).rev().fold(
+        false.into(),
+        |decreases_later, (new_component, old_component)| {
+            vir::Expr::or(
+                vir::Expr::and(
+                    vir::Expr::ge_cmp(new_component.clone(), 0.into()),
+                    vir::Expr::lt_cmp(new_component.clone(), old_component.clone()),
+                ),
+                vir::Expr::and(
+                    vir::Expr::eq_cmp(new_component.clone(), old_component.clone()),
+                    decreases_later,
+                ),
+            )
+        },
+    )
+}
+
 struct SpecEncoder<'p, 'v: 'p, 'tcx: 'v> {
     encoder: &'p Encoder<'v, 'tcx>,
     /// The label to encode `old(..)` expressions
@@ -121,6 +203,20 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
         vir::LocalVar::new(var_name, ty)
     }
 
+    /// Warn that no trigger could be inferred for the quantifier with the given `body`,
+    /// reported at the span that covers the expressions of the body.
+    fn warn_untriggered_quantifier(&self, body: &typed::Assertion<'tcx>) {
+        let tcx = self.encoder.env().tcx();
+        let mut expressions = vec![];
+        collect_expressions(body, &mut expressions);
+        let span = expressions.iter()
+            .map(|expression| tcx.def_span(expression.expr))
+            .reduce(|lhs, rhs| lhs.to(rhs));
+        if let Some(span) = span {
+            self.encoder.warn_untriggered_quantifier(span);
+        }
+    }
+
     fn encode_trigger(
         &self,
         trigger: &typed::Trigger,
@@ -205,30 +301,23 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
                     self.encode_assertion(rhs)?
                 )
             }
-            box typed::AssertionKind::TypeCond(ref vars, ref assertion) => {
-                let enc = |ty: ty::Ty<'tcx>| -> vir::Expr {
-                    // FIXME: this is a hack to support generics. See issue #187.
-                    let ty = self.encoder.resolve_typaram(ty);
-                    self.encoder.encode_tag_func_app(ty)
-                };
-                let typecond =
-                    vir::Expr::eq_cmp(enc(vars.vars[0].1), enc(vars.vars[1].1));
+            box typed::AssertionKind::TypeCond(ref type_cond, ref assertion) => {
+                let item_def_id = type_cond.item;
+                let encoded_type_cond = self.encoder.encode_type_cond(item_def_id)
+                    .with_span(self.encoder.env().tcx().def_span(item_def_id))?;
                 vir::Expr::implies(
-                    typecond,
+                    encoded_type_cond,
                     self.encode_assertion(assertion)?
                 )
             }
-            box typed::AssertionKind::ForAll(ref vars, ref trigger_set, ref body) => {
+            box typed::AssertionKind::ForAll(ref vars, ref trigger_set, ref body)
+            | box typed::AssertionKind::Exists(ref vars, ref trigger_set, ref body) => {
                 let mut encoded_args = Vec::new();
                 let mut bounds = Vec::new();
                 for (arg, ty) in &vars.vars {
-                    // TODO: how to get a span for the variable here?
-                    //if !self.encoder.is_quantifiable(ty).unwrap() {
-                    //    return Err(EncodingError::incorrect(
-                    //        "This type cannot be used in quantifiers.",
-                    //    ).with_span(?));
-                    //}
-
+                    // The types of the variables are checked to be quantifiable
+                    // in `translate_expr_to_closure_def_site`, where we have a
+                    // span for them.
                     let encoded_arg = self.encode_forall_arg(*arg, ty, &format!("{}_{}", vars.spec_id, vars.id));
                     if config::check_overflows() {
                         bounds.extend(self.encoder.encode_type_bounds(&encoded_arg.clone().into(), ty));
@@ -246,17 +335,64 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
                     encoded_triggers.push(encoded_trigger);
                 }
                 let encoded_body = self.encode_assertion(body)?;
-                let final_body = if bounds.is_empty() {
-                    encoded_body
+                if let box typed::AssertionKind::Exists(..) = assertion.kind {
+                    // The bounds of the bound variables restrict the witness.
+                    let final_body = bounds.into_iter().chain(std::iter::once(encoded_body)).conjoin();
+                    if encoded_triggers.is_empty() {
+                        encoded_triggers = infer_triggers(&encoded_args, &final_body);
+                        if encoded_triggers.is_empty() {
+                            self.warn_untriggered_quantifier(body);
+                        }
+                    }
+                    vir::Expr::exists(
+                        encoded_args,
+                        encoded_triggers,
+                        final_body,
+                    )
                 } else {
-                    vir::Expr::implies(bounds.into_iter().conjoin(), encoded_body)
-                };
-                vir::Expr::forall(
-                    encoded_args,
-                    encoded_triggers,
-                    final_body,
-                )
+                    // One quantifier per conjunct of the body, so that a failure is reported
+                    // at the span of the conjunct that does not hold.
+                    let bounds = if bounds.is_empty() {
+                        None
+                    } else {
+                        Some(bounds.into_iter().conjoin())
+                    };
+                    let mut is_untriggered = false;
+                    let quantifiers = split_conjuncts(encoded_body)
+                        .into_iter()
+                        .map(|(conjunct, pos)| {
+                            let final_body = match bounds {
+                                Some(ref bounds) => vir::Expr::implies(bounds.clone(), conjunct),
+                                None => conjunct,
+                            };
+                            let triggers = if encoded_triggers.is_empty() {
+                                infer_triggers(&encoded_args, &final_body)
+                            } else {
+                                encoded_triggers.clone()
+                            };
+                            is_untriggered |= triggers.is_empty();
+                            vir::Expr::forall(
+                                encoded_args.clone(),
+                                triggers,
+                                final_body,
+                            ).set_pos(pos)
+                        })
+                        .conjoin();
+                    if is_untriggered {
+                        self.warn_untriggered_quantifier(body);
+                    }
+                    quantifiers
+                }
             },
+            box typed::AssertionKind::Matches(ref expression, ref pattern) => {
+                self.encode_variant_test(expression, pattern)?
+            }
+            box typed::AssertionKind::Measure(ref components) => {
+                return Err(SpannedEncodingError::internal(
+                    "a termination measure cannot be encoded as an assertion",
+                    self.encoder.env().tcx().def_span(components[0].expr),
+                ));
+            }
             box typed::AssertionKind::SpecEntailment {
                 ref closure,
                 arg_binders: ref vars,
@@ -265,7 +401,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
             } => {
                 // TODO: refactor, simplify, or extract into a function
                 let tcx = self.encoder.env().tcx();
-                let mir = self.encoder.env().local_mir(closure.expr);
+                let mir = self.encoder.env().mir(closure.expr);
                 let result = &mir.local_decls[(0 as u32).into()];
                 let ty = result.ty;
                 if let Some(ty_repl) = self.encoder.current_tymap().get(ty) {
@@ -274,24 +410,40 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
                     match ty_repl.kind() {
                         ty::TyKind::Closure(def_id, _substs)
                         | ty::TyKind::FnDef(def_id, _substs) => {
+                            let span = tcx.def_span(closure.expr);
+                            check_entailment_types(vars, span)?;
+
                             let encoded_pres = pres.iter()
                                 .map(|x| self.encode_assertion(x))
                                 .collect::<Result<Vec<vir::Expr>, _>>()?
                                 .into_iter()
                                 .conjoin();
 
-                            // encode_forall_arg() above only works for integers.
-                            // Therefore, for the time being, check that we're working with integers:
-                            vars.args.iter().for_each(|(_arg, arg_ty)| {
-                                match arg_ty.kind() {
-                                    ty::TyKind::Int(..) | ty::TyKind::Uint(..) => {}
-                                    _ => { unimplemented!("Only integers are currently supported as closure arguments."); }
-                                }
-                            });
-                            match vars.result.1.kind() {
-                                ty::TyKind::Int(..) | ty::TyKind::Uint(..) => {}
-                                _ => { unimplemented!("Only integers are currently supported as closure return types."); }
+                            // The captured state of a closure is the first argument of its
+                            // specification functions.
+                            let mut state_args = vec![];
+                            let mut state_formal_args = vec![];
+                            if let ty::TyKind::Closure(..) = ty_repl.kind() {
+                                state_args.push(vir::Expr::snap_app(self.encode_expression(closure)?));
+                                state_formal_args.push(vir::LocalVar::new(
+                                    "_1",
+                                    self.encoder.encode_snapshot_type(*ty_repl).with_span(span)?,
+                                ));
                             }
+                            let first_arg_index = state_formal_args.len() + 1;
+                            let arg_formal_args = vars.args
+                                .iter()
+                                .enumerate()
+                                .map(|(i, (_arg, arg_ty))| {
+                                    self.encoder.encode_snapshot_type(*arg_ty)
+                                        .map(|typ| vir::LocalVar::new(format!("_{}", first_arg_index + i), typ))
+                                })
+                                .collect::<Result<Vec<_>, _>>()
+                                .with_span(span)?;
+                            let result_formal_arg = vir::LocalVar::new(
+                                "_0",
+                                self.encoder.encode_snapshot_type(vars.result.1).with_span(span)?,
+                            );
 
                             let sf_pre_name = self.encoder.encode_spec_func_name(*def_id, SpecFunctionKind::Pre);
                             let qvars_pre: Vec<_> = vars.args
@@ -305,11 +457,13 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
                                     encoded_pres.clone(),
                                     vir::Expr::FuncApp(
                                         sf_pre_name,
-                                        qvars_pre.iter()
-                                            .map(|x| vir::Expr::Local(x.clone(), vir::Position::default()))
+                                        state_args.iter()
+                                            .cloned()
+                                            .chain(qvars_pre.iter().map(|x| vir::Expr::Local(x.clone(), vir::Position::default())))
                                             .collect(),
-                                        (0 .. vars.args.len())
-                                            .map(|i| vir::LocalVar::new(format!("_{}", i), vir::Type::Int))
+                                        state_formal_args.iter()
+                                            .chain(arg_formal_args.iter())
+                                            .cloned()
                                             .collect(),
                                         vir::Type::Bool,
                                         vir::Position::default()
@@ -332,7 +486,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
                                          &format!("{}_{}", vars.spec_id, vars.post_id)))
                                 .chain(std::iter::once(
                                     self.encode_forall_arg(
-                                        result_var, tcx.mk_ty(ty::TyKind::Int(ty::IntTy::I32)),
+                                        result_var, vars.result.1,
                                         &format!("{}_{}", vars.spec_id, vars.post_id))))
                                 .collect();
 
@@ -350,11 +504,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
                                     vir::Expr::implies(
                                         vir::Expr::FuncApp(
                                             sf_post_name,
-                                            qvars_post.iter()
-                                                .map(|x| vir::Expr::Local(x.clone(), vir::Position::default()))
+                                            state_args.iter()
+                                                .cloned()
+                                                .chain(qvars_post.iter().map(|x| vir::Expr::Local(x.clone(), vir::Position::default())))
                                                 .collect(),
-                                            (0 ..= vars.args.len())
-                                                .map(|i| vir::LocalVar::new(format!("_{}", i), vir::Type::Int))
+                                            state_formal_args.iter()
+                                                .chain(arg_formal_args.iter())
+                                                .chain(std::iter::once(&result_formal_arg))
+                                                .cloned()
                                                 .collect(),
                                             vir::Type::Bool,
                                             vir::Position::default()
@@ -382,6 +539,131 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
         })
     }
 
+    /// Encode the specification entailment `assertion` at a call of the entailed closure,
+    /// replacing the variables bound by the entailment with `call_args` and `call_result`.
+    fn encode_entailment_call(
+        &self,
+        assertion: &typed::Assertion<'tcx>,
+        call_args: &[vir::Expr],
+        call_result: &vir::Expr,
+    ) -> SpannedEncodingResult<(vir::Expr, vir::Expr)> {
+        let (closure, vars, pres, posts) = match assertion.kind {
+            box typed::AssertionKind::SpecEntailment {
+                ref closure,
+                arg_binders: ref vars,
+                ref pres,
+                ref posts,
+            } => (closure, vars, pres, posts),
+            _ => unreachable!("not a specification entailment: {:?}", assertion),
+        };
+        let span = self.encoder.env().tcx().def_span(closure.expr);
+        check_entailment_types(vars, span)?;
+        if vars.args.len() != call_args.len() {
+            return Err(SpannedEncodingError::incorrect(
+                format!(
+                    "the specification entailment has {} arguments, but the closure is called with {}",
+                    vars.args.len(),
+                    call_args.len(),
+                ),
+                span,
+            ));
+        }
+
+        let pre_id = format!("{}_{}", vars.spec_id, vars.pre_id);
+        let post_id = format!("{}_{}", vars.spec_id, vars.post_id);
+        let mut pre_replacements = vec![];
+        let mut post_replacements = vec![];
+        for ((arg, arg_ty), call_arg) in vars.args.iter().zip(call_args) {
+            pre_replacements.push((
+                vir::Expr::local(self.encode_forall_arg(*arg, arg_ty, &pre_id)),
+                call_arg.clone(),
+            ));
+            post_replacements.push((
+                vir::Expr::local(self.encode_forall_arg(*arg, arg_ty, &post_id)),
+                call_arg.clone(),
+            ));
+        }
+        // The result is bound by the variable following the arguments, see above
+        let result_var = mir::Local::from_usize(vars.args.len() + 2);
+        post_replacements.push((
+            vir::Expr::local(self.encode_forall_arg(result_var, vars.result.1, &post_id)),
+            call_result.clone(),
+        ));
+
+        let encoded_pres = pres.iter()
+            .map(|x| self.encode_assertion(x))
+            .collect::<Result<Vec<vir::Expr>, _>>()?
+            .into_iter()
+            .conjoin()
+            .replace_multiple_places(&pre_replacements);
+        let encoded_posts = posts.iter()
+            .map(|x| self.encode_assertion(x))
+            .collect::<Result<Vec<vir::Expr>, _>>()?
+            .into_iter()
+            .conjoin()
+            .replace_multiple_places(&post_replacements);
+        Ok((encoded_pres, encoded_posts))
+    }
+
+    /// Encode the components of a termination measure, which must be integers.
+    fn encode_measure(&self, measure: &typed::Assertion<'tcx>)
+        -> SpannedEncodingResult<Vec<vir::Expr>>
+    {
+        let components = match measure.kind {
+            box typed::AssertionKind::Measure(ref components) => components,
+            _ => unreachable!("not a termination measure: {:?}", measure),
+        };
+        components.iter().map(|component| {
+            let ty = self.encoder.env().mir(component.expr).return_ty();
+            if !ty.is_integral() {
+                return Err(SpannedEncodingError::incorrect(
+                    format!("the termination measure must be an integer, not `{}`", ty),
+                    self.encoder.env().tcx().def_span(component.expr),
+                ));
+            }
+            self.encode_expression(component)
+        }).collect()
+    }
+
+    /// Encode the enum variant test `matches!(expr, Pattern)` as a comparison
+    /// of the discriminant of the snapshot of `expr` with the discriminant of
+    /// the tested variant.
+    fn encode_variant_test(
+        &self,
+        expression: &typed::Expression,
+        pattern: &typed::VariantPattern,
+    ) -> SpannedEncodingResult<vir::Expr> {
+        let variant_index = match pattern.variant_index {
+            Some(variant_index) => variant_index,
+            // the pattern matches every value
+            None => return Ok(true.into()),
+        };
+        let tcx = self.encoder.env().tcx();
+        let span = tcx.def_span(pattern.test);
+        let mir = self.encoder.env().mir(expression.expr);
+        // the matched expression may have been borrowed, see `EncodeTypeCheck`
+        let ty = mir.return_ty().peel_refs();
+        let adt_def = match ty.kind() {
+            ty::TyKind::Adt(adt_def, _) if adt_def.is_enum() && adt_def.variants.len() > 1 => {
+                adt_def
+            }
+            // the only variant of a struct or enum always matches
+            _ => return Ok(true.into()),
+        };
+        if self.encoder.has_abstract_snapshot(ty).with_span(span)? {
+            return Err(SpannedEncodingError::unsupported(
+                format!("variants of type `{}` cannot be tested in specifications", ty),
+                span,
+            ));
+        }
+        let discriminant = compute_discriminant_values(adt_def, tcx)[variant_index];
+        let encoded_expr = self.encode_expression(expression)?;
+        Ok(vir::Expr::eq_cmp(
+            vir::Expr::snap_app(encoded_expr).field(self.encoder.encode_discriminant_field()),
+            discriminant.into(),
+        ))
+    }
+
     /// Translate an expression `expr` from a closure identified by `def_id` to its definition site.
     ///
     /// During the translation:
@@ -493,6 +775,16 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
             for local_arg_index in inner_mir.args_iter().skip(1) {
                 let local_arg = &inner_mir.local_decls[local_arg_index];
                 assert!(!local_arg.internal);
+                let span = inner_mir_encoder.get_local_span(local_arg_index);
+                if !self.encoder.is_quantifiable(local_arg.ty).with_span(span)? {
+                    return Err(SpannedEncodingError::unsupported(
+                        format!(
+                            "values of type `{}` cannot be used in quantifiers",
+                            local_arg.ty,
+                        ),
+                        span,
+                    ));
+                }
                 let quantified_var = self.encode_forall_arg(
                     local_arg_index,
                     local_arg.ty,
@@ -504,7 +796,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
                     ty::TyKind::Int(_) |
                     ty::TyKind::Bool |
                     ty::TyKind::Char => {
-                        let span = inner_mir_encoder.get_local_span(local_arg_index);
                         let value_field = self.encoder.encode_value_field(local_arg.ty).with_span(span)?;
                         vir::Expr::local(encoded_arg).field(value_field)
                     }
@@ -577,7 +868,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
     {
         debug!("encode_expression {:?}", assertion_expr);
 
-        let mut curr_def_id = assertion_expr.expr.to_def_id();
+        let mut curr_def_id = assertion_expr.expr;
         let mut curr_expr = self.encoder.encode_pure_expression(curr_def_id)?;
 
         loop {
@@ -610,7 +901,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
 
         // At this point `curr_def_id` should be either a SPEC item (when encoding a contract) or
         // the method being verified (when encoding a loop invariant).
-        let mir = self.encoder.env().local_mir(curr_def_id.expect_local());
+        let mir = self.encoder.env().mir(curr_def_id);
         let mir_encoder = MirEncoder::new(self.encoder, &mir, curr_def_id);
 
         // Replacements to use the provided `target_args` and `target_return`
@@ -671,6 +962,52 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
             }
         });
 
+        // `old_loop(..)`, `unchanged(..)` and `loop_iterations()` expressions are resolved by the
+        // procedure encoder, for the loop whose invariant is being encoded. The labels of
+        // `labelled_old(..)` expressions are only defined in the body of the procedure.
+        if self.assertion_location.is_none() {
+            struct LabelFinder {
+                found_loop_entry: bool,
+                found_user_label: bool,
+                found_loop_iterations: bool,
+            }
+            impl vir::ExprWalker for LabelFinder {
+                fn walk_labelled_old(&mut self, label: &str, body: &vir::Expr, _pos: &vir::Position) {
+                    self.found_loop_entry |= label == LOOP_ENTRY_LABEL;
+                    self.found_user_label |= label.starts_with(USER_LABEL_PREFIX);
+                    self.walk(body);
+                }
+                fn walk_local_var(&mut self, var: &vir::LocalVar) {
+                    self.found_loop_iterations |= var.name == LOOP_ITERATIONS_VAR;
+                }
+            }
+            let mut finder = LabelFinder {
+                found_loop_entry: false,
+                found_user_label: false,
+                found_loop_iterations: false,
+            };
+            vir::ExprWalker::walk(&mut finder, &curr_expr);
+            if finder.found_loop_entry {
+                return Err(SpannedEncodingError::incorrect(
+                    "old_loop(..) and unchanged(..) expressions can only be used in loop invariants",
+                    self.encoder.env().tcx().def_span(assertion_expr.expr),
+                ));
+            }
+            if finder.found_loop_iterations {
+                return Err(SpannedEncodingError::incorrect(
+                    "loop_iterations() can only be used in loop invariants",
+                    self.encoder.env().tcx().def_span(assertion_expr.expr),
+                ));
+            }
+            if finder.found_user_label {
+                return Err(SpannedEncodingError::incorrect(
+                    "labelled_old(..) expressions can only be used in loop invariants and in \
+                    ghost statements",
+                    self.encoder.env().tcx().def_span(assertion_expr.expr),
+                ));
+            }
+        }
+
         debug!("MIR expr {:?} --> {}", assertion_expr.id, curr_expr);
         Ok(curr_expr.set_default_pos(
             self.encoder
@@ -682,6 +1019,116 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
     }
 }
 
+/// Check that the arguments and the result bound by a specification entailment have
+/// primitive types, which are the only ones whose values the encoding can bind.
+fn check_entailment_types<'tcx>(
+    vars: &typed::SpecEntailmentVars<'tcx>,
+    span: rustc_span::Span,
+) -> SpannedEncodingResult<()> {
+    let is_primitive = |ty: ty::Ty<'tcx>| matches!(
+        ty.kind(),
+        ty::TyKind::Bool | ty::TyKind::Char | ty::TyKind::Int(..) | ty::TyKind::Uint(..)
+    );
+    for ty in vars.args.iter().map(|(_, arg_ty)| *arg_ty).chain(std::iter::once(vars.result.1)) {
+        if !is_primitive(ty) {
+            return Err(SpannedEncodingError::unsupported(
+                format!(
+                    "values of type `{}` are not supported as arguments or results of \
+                    specification entailments",
+                    ty,
+                ),
+                span,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Infer the triggers of a quantifier without explicit triggers from the function calls in its
+/// body. Each call that mentions all the bounded variables, and whose arguments do not compute
+/// on them, becomes a trigger. If there is no such call, the calls that mention some of the
+/// bounded variables are used together as a single trigger, when they mention all of them.
+/// If no trigger can be inferred, the choice is left to the verifier, and the encoder warns
+/// about it (see `warn_untriggered_quantifier`).
+fn infer_triggers(bounded_vars: &[vir::LocalVar], body: &vir::Expr) -> Vec<vir::Trigger> {
+    struct CallCollector<'a> {
+        bounded_vars: &'a [vir::Expr],
+        calls: Vec<vir::Expr>,
+    }
+    impl<'a> vir::ExprWalker for CallCollector<'a> {
+        fn walk(&mut self, expr: &vir::Expr) {
+            match expr {
+                vir::Expr::FuncApp(..) | vir::Expr::DomainFuncApp(..) => {
+                    let mentions_bounded_vars = self.bounded_vars.iter().any(|var| expr.find(var));
+                    if mentions_bounded_vars && is_trigger_term(expr) && !self.calls.contains(expr) {
+                        self.calls.push(expr.clone());
+                    }
+                }
+                vir::Expr::ForAll(..) | vir::Expr::Exists(..) => {
+                    // The calls of nested quantifiers might mention their own bounded variables.
+                    return;
+                }
+                _ => {}
+            }
+            vir::default_walk_expr(self, expr);
+        }
+    }
+    /// Function calls whose arguments are places, constants or other such calls.
+    fn is_trigger_term(expr: &vir::Expr) -> bool {
+        match expr {
+            vir::Expr::FuncApp(_, args, ..) | vir::Expr::DomainFuncApp(_, args, _) =>
+                args.iter().all(is_trigger_term),
+            vir::Expr::SnapApp(arg, _) => is_trigger_term(arg),
+            vir::Expr::Const(..) => true,
+            expr => expr.is_place(),
+        }
+    }
+
+    let bounded_vars: Vec<vir::Expr> = bounded_vars.iter().map(|var| var.clone().into()).collect();
+    let mut collector = CallCollector {
+        bounded_vars: &bounded_vars,
+        calls: Vec::new(),
+    };
+    vir::ExprWalker::walk(&mut collector, body);
+    let mentions_all = |terms: &[vir::Expr]| {
+        bounded_vars.iter().all(|var| terms.iter().any(|term| term.find(var)))
+    };
+    let triggers: Vec<_> = collector.calls.iter()
+        .filter(|call| mentions_all(std::slice::from_ref(call)))
+        .map(|call| vir::Trigger::new(vec![call.clone()]))
+        .collect();
+    if !triggers.is_empty() {
+        triggers
+    } else if !collector.calls.is_empty() && mentions_all(&collector.calls) {
+        vec![vir::Trigger::new(collector.calls)]
+    } else {
+        vec![]
+    }
+}
+
+/// Split the body of a quantifier into its conjuncts, distributing implications over
+/// conjunctions: `p ==> (a && b)` results in `p ==> a` and `p ==> b`. Each conjunct is paired
+/// with the position of its innermost subexpression that has one.
+fn split_conjuncts(expr: vir::Expr) -> Vec<(vir::Expr, vir::Position)> {
+    match expr {
+        vir::Expr::BinOp(vir::BinOpKind::And, box lhs, box rhs, pos) if pos.is_default() => {
+            let mut conjuncts = split_conjuncts(lhs);
+            conjuncts.extend(split_conjuncts(rhs));
+            conjuncts
+        }
+        vir::Expr::BinOp(vir::BinOpKind::Implies, box guard, box body, pos) if pos.is_default() => {
+            split_conjuncts(body)
+                .into_iter()
+                .map(|(conjunct, pos)| (vir::Expr::implies(guard.clone(), conjunct), pos))
+                .collect()
+        }
+        expr => {
+            let pos = expr.pos();
+            vec![(expr, pos)]
+        }
+    }
+}
+
 struct StraightLineBackwardInterpreter<'p, 'v: 'p, 'tcx: 'v> {
     interpreter: PureFunctionBackwardInterpreter<'p, 'v, 'tcx>,
 }
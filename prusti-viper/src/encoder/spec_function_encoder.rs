@@ -28,7 +28,6 @@ pub struct SpecFunctionEncoder<'p, 'v: 'p, 'tcx: 'v> {
     procedure: &'p Procedure<'p, 'tcx>,
     span: Span,
     proc_def_id: ProcedureDefId,
-    is_closure: bool,
     mir: &'p mir::Body<'tcx>,
     mir_encoder: MirEncoder<'p, 'v, 'tcx>,
 }
@@ -41,7 +40,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecFunctionEncoder<'p, 'v, 'tcx> {
             procedure: procedure,
             span: procedure.get_span(),
             proc_def_id: procedure.get_id(),
-            is_closure: encoder.env().tcx().is_closure(procedure.get_id()),
             mir: procedure.get_mir(),
             mir_encoder: MirEncoder::new(encoder, procedure.get_mir(), procedure.get_id())
         }
@@ -100,9 +98,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecFunctionEncoder<'p, 'v, 'tcx> {
         Ok(vir::Function {
             name: self.encoder.encode_spec_func_name(self.procedure.get_id(),
                                                      SpecFunctionKind::Pre),
-            formal_args: encoded_args.into_iter()
-                                     .skip(if self.is_closure { 1 } else { 0 }) // FIXME: "self" is skipped, see TypeEncoder
-                                     .collect(),
+            // For closures, the first argument is the captured state
+            formal_args: encoded_args,
             return_type: vir::Type::Bool,
             pres: Vec::new(),
             posts: Vec::new(),
@@ -143,8 +140,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecFunctionEncoder<'p, 'v, 'tcx> {
         Ok(vir::Function {
             name: self.encoder.encode_spec_func_name(self.procedure.get_id(),
                                                      SpecFunctionKind::Post),
+            // For closures, the first argument is the captured state
             formal_args: encoded_args.into_iter()
-                                     .skip(if self.is_closure { 1 } else { 0 }) // FIXME: "self" is skipped, see TypeEncoder
                                      .chain(std::iter::once(encoded_return))
                                      .collect(),
             return_type: vir::Type::Bool,
@@ -8,11 +8,12 @@ use crate::encoder::foldunfold;
 use crate::encoder::utils::range_extract;
 use crate::encoder::utils::PlusOne;
 use crate::encoder::Encoder;
+use crate::encoder::spec_encoder::encode_spec_assertion;
 use prusti_common::{
     config,
     vir,
     vir_local,
-    vir::{ExprIterator, ExprFolder},
+    vir::{ExprIterator, ExprFolder, fixes::fix_function_triggers},
 };
 // use prusti_interface::specifications::*;
 // use rustc::middle::const_val::ConstVal;
@@ -30,6 +31,7 @@ use rustc_target::abi::Integer;
 use log::{debug, trace};
 use crate::encoder::errors::{EncodingError, EncodingResult};
 use crate::encoder::builtin_encoder::BuiltinFunctionKind;
+use crate::encoder::array_encoder::ArrayLen;
 use std::convert::TryInto;
 
 pub struct TypeEncoder<'p, 'v: 'p, 'tcx: 'v> {
@@ -39,6 +41,8 @@ pub struct TypeEncoder<'p, 'v: 'p, 'tcx: 'v> {
 
 impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
     pub fn new(encoder: &'p Encoder<'v, 'tcx>, ty: ty::Ty<'tcx>) -> Self {
+        // `impl Trait` types are encoded as their hidden concrete type
+        let ty = encoder.env().reveal_opaque_types(ty);
         TypeEncoder { encoder, ty }
     }
 
@@ -147,7 +151,9 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
         })
     }
 
-    fn get_integer_bounds(&self) -> Option<(vir::Expr, vir::Expr)> {
+    pub fn get_integer_bounds(&self) -> Option<(vir::Expr, vir::Expr)> {
+        // The bounds of `isize` and `usize` depend on the target, not on the host
+        let pointer_width = self.encoder.env().tcx().data_layout.pointer_size.bits();
         match self.ty.kind() {
             ty::TyKind::Int(int_ty) => {
                 let bounds = match int_ty {
@@ -156,7 +162,10 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                     ty::IntTy::I32 => (std::i32::MIN.into(), std::i32::MAX.into()),
                     ty::IntTy::I64 => (std::i64::MIN.into(), std::i64::MAX.into()),
                     ty::IntTy::I128 => (std::i128::MIN.into(), std::i128::MAX.into()),
-                    ty::IntTy::Isize => (std::isize::MIN.into(), std::isize::MAX.into()),
+                    ty::IntTy::Isize => (
+                        (-(1i128 << (pointer_width - 1))).into(),
+                        ((1i128 << (pointer_width - 1)) - 1).into(),
+                    ),
                 };
                 Some(bounds)
             }
@@ -167,7 +176,7 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                     ty::UintTy::U32 => (0.into(), std::u32::MAX.into()),
                     ty::UintTy::U64 => (0.into(), std::u64::MAX.into()),
                     ty::UintTy::U128 => (0.into(), std::u128::MAX.into()),
-                    ty::UintTy::Usize => (0.into(), std::usize::MAX.into()),
+                    ty::UintTy::Usize => (0.into(), ((1u128 << pointer_width) - 1).into()),
                 };
                 Some(bounds)
             }
@@ -241,6 +250,12 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                 vec![vir::Predicate::new_struct(typ, fields)]
             }
 
+            ty::TyKind::Adt(..) if self.encoder.env().get_vec_elem_type(self.ty).is_some() => {
+                // vectors are modelled as sequences of their elements, see
+                // `SnapshotEncoder::encode_snapshot_internal`
+                vec![vir::Predicate::new_abstract(typ)]
+            }
+
             ty::TyKind::Adt(adt_def, subst) if !adt_def.is_box() => {
                 let num_variants = adt_def.variants.len();
                 let tcx = self.encoder.env().tcx();
@@ -336,26 +351,21 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
             }
 
             ty::TyKind::Closure(_def_id, internal_substs) => {
-                let closure_substs = internal_substs.as_closure();
-                match closure_substs.tupled_upvars_ty().kind() {
-                    ty::TyKind::Tuple(_upvar_substs) => {
-                        // TODO: this should encode the state of a closure, i.e.
-                        // the "self" parameter passed into the implementation
-                        // function generated for every closure. This should
-                        // work using snapshots. For now, the "self" parameter
-                        // is skipped in encoding.
-
-                        // let field_name = "upvars".to_owned();
-                        // let field = self.encoder.encode_raw_ref_field(field_name, cl_upvars);
-                        // let pred = vir::Predicate::new_struct(typ, vec![field.clone()]);
-                        let pred = vir::Predicate::new_struct(typ.clone(), vec![]);
-                        // trace!("Encoded closure type {:?} as {:?} with field {:?}", typ, pred, field);
-                        trace!("Encoded closure type {:?} as {:?}", typ, pred);
-                        vec![pred]
-                    }
-
-                    _ => unreachable!()
-                }
+                // The state of a closure, i.e. the "self" parameter passed into the
+                // implementation function generated for every closure, has one field
+                // per captured place. The field names match the ones used to encode
+                // the accesses to the captured places in the body of the closure.
+                let fields = internal_substs.as_closure()
+                    .upvar_tys()
+                    .enumerate()
+                    .map(|(field_num, field_ty)| {
+                        let field_name = format!("closure_{}", field_num);
+                        self.encoder.encode_raw_ref_field(field_name, field_ty)
+                    })
+                    .collect::<Result<_, _>>()?;
+                let pred = vir::Predicate::new_struct(typ.clone(), fields);
+                trace!("Encoded closure type {:?} as {:?}", typ, pred);
+                vec![pred]
             }
 
             ty::TyKind::Array(..) => {
@@ -384,10 +394,21 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                 // makes generics "less fragile"
                 composed_name.push("_sep_".to_string());
             }
-            if let ty::subst::GenericArgKind::Type(ty) = kind.unpack() {
-                composed_name.push(
-                    self.encoder.encode_type_predicate_use(ty)?
-                )
+            match kind.unpack() {
+                ty::subst::GenericArgKind::Type(ty) => {
+                    composed_name.push(
+                        self.encoder.encode_type_predicate_use(ty)?
+                    )
+                }
+                ty::subst::GenericArgKind::Const(value)
+                    if matches!(value.ty.kind(), ty::TyKind::Uint(ty::UintTy::Usize)) => {
+                    let len = match self.encoder.encode_array_len(value)? {
+                        ArrayLen::Fixed(len) => len.to_string(),
+                        ArrayLen::Param(var) => var.name,
+                    };
+                    composed_name.push(len)
+                }
+                _ => {}
             }
         }
         composed_name.push("_end_".to_string()); // makes generics "less fragile"
@@ -449,10 +470,10 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
             ty::TyKind::Str => "str".to_string(),
 
             ty::TyKind::Array(elem_ty, size) => {
-                let array_len =
-                    self.encoder
-                        .const_eval_intlike(&size.val).unwrap()
-                        .to_u64().unwrap();
+                let array_len = match self.encoder.encode_array_len(size)? {
+                    ArrayLen::Fixed(len) => len.to_string(),
+                    ArrayLen::Param(var) => var.name,
+                };
                 format!(
                     "Array${}${}",
                     array_len,
@@ -534,6 +555,11 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                 ])
             }
 
+            // the predicate of a vector is abstract
+            ty::TyKind::Adt(..) if self.encoder.env().get_vec_elem_type(self.ty).is_some() => {
+                Some(vec![])
+            }
+
             ty::TyKind::Adt(ref adt_def, ref subst) if !adt_def.is_box() => {
                 if self.is_supported_struct_type(adt_def, subst) {
                     let own_substs =
@@ -557,50 +583,20 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                     let num_variants = adt_def.variants.len();
                     let tcx = self.encoder.env().tcx();
 
-                    let specs: Vec<typed::SpecificationSet> = Vec::new();
-                    // FIXME: type invariants need to be collected separately
-                    // in `SpecCollector`, and encoder should get a
-                    // `get_struct_specs` method or similar.
-                    // `get_procedure_specs` now only returns procedure specs,
-                    // so the match below for `SpecSet::Struct` would never
-                    // succeed.
-
-                    //if let Some(spec) = self.encoder.get_procedure_specs(adt_def.did) {
-                    //    specs.push(spec);
-                    //}
-
-                    // let traits = self.encoder.env().get_traits_decls_for_type(&self.ty);
-                    // for trait_id in traits {
-                    //     //if let Some(spec) = self.encoder.get_procedure_specs(trait_id) {
-                    //     //    specs.push(spec);
-                    //     //}
-                    // }
-
-                    for spec in specs.into_iter() {
-                        //let encoded_args = vec![vir::Expr::from(self_local_var.clone())];
-                        // let mut hacky_folder = HackyExprFolder {
-                        //     saelf: self_local_var.clone(),
-                        // };
-
-                        match spec {
-                            typed::SpecificationSet::Struct(items) => {
-                                for _item in items {
-                                    // let enc = encode_simple_spec_assertion(
-                                    //     self.encoder,
-                                    //     &[],
-                                    //     &item.assertion
-                                    // );
-                                    let _enc = unimplemented!(
-                                        "TODO: type invariants need to be upgraded \
-                                        to the new compiler version"
-                                    );
-                                    // OPEN TODO: hacky fix here to convert the closure var to "self"...
-                                    // let enc = hacky_folder.fold(enc);
-                                    // exprs.push(enc);
-                                }
-                            }
-                            _ => unreachable!(),
-                        }
+                    // The invariants given by `#[invariant(..)]` refer to the value as `self`
+                    let invariants = self.encoder.get_struct_specs(adt_def.did)
+                        .into_iter()
+                        .filter(|spec| spec.typ == typed::SpecType::Invariant);
+                    for invariant in invariants {
+                        exprs.push(encode_spec_assertion(
+                            self.encoder,
+                            &invariant.assertion,
+                            None,
+                            &[self_local_var.clone().into()],
+                            None,
+                            false,
+                            None,
+                        )?);
                     }
 
                     if num_variants == 0 {
@@ -680,6 +676,10 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
             function,
             self.encoder.get_used_viper_predicates_map(),
         ).unwrap(); // TODO: generate a stub function in case of error
+        let final_function = fix_function_triggers(
+            final_function,
+            self.encoder.backend_capabilities(),
+        );
         debug!(
             "[exit] encode_invariant_def({:?}):\n{}",
             self.ty, final_function
@@ -0,0 +1,106 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Dump of the encoding of single methods and functions, annotated with the
+//! Rust code that they come from. Enabled by the `dump_viper_per_fn` flag.
+
+use crate::encoder::Encoder;
+use crate::json_report::SpanReport;
+use prusti_common::report::log;
+use prusti_common::vir;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+const NAMESPACE: &str = "viper_program_per_fn";
+
+/// Dump `method` to `<source file>.<method name>.vpr`, with a `// <file>:<line>`
+/// comment before each statement that starts a new source line, and the table
+/// of the positions to `<source file>.<method name>.map.json`.
+pub fn dump_method(encoder: &Encoder, method: &vir::CfgMethod) {
+    let locations = SourceLocations::new(encoder);
+    let text = method.to_string_with_comments(|stmt| {
+        stmt_position(stmt).and_then(|pos| locations.comment(&pos))
+    });
+    write_dump(encoder, method.name(), text, locations);
+}
+
+/// Dump `function` like `dump_method` does for methods, with comments before
+/// each pre- and postcondition and before the body.
+pub fn dump_function(encoder: &Encoder, function: &vir::Function) {
+    let locations = SourceLocations::new(encoder);
+    let text = function.to_string_with_comments(|expr| locations.comment(&expr.pos()));
+    write_dump(encoder, function.name.clone(), text, locations);
+}
+
+fn write_dump(encoder: &Encoder, name: String, text: String, locations: SourceLocations) {
+    let source_filename = encoder.env().source_file_name();
+    log::report(NAMESPACE, format!("{}.{}.vpr", source_filename, name), text);
+    log::report_with_writer(
+        NAMESPACE,
+        format!("{}.{}.map.json", source_filename, name),
+        |writer| serde_json::to_writer_pretty(writer, &locations.table.into_inner()).unwrap(),
+    );
+}
+
+/// The position of a statement, taken from its expressions when the statement
+/// has no position of its own.
+fn stmt_position(stmt: &vir::Stmt) -> Option<vir::Position> {
+    let pos = match stmt {
+        vir::Stmt::Exhale(_, pos)
+        | vir::Stmt::Assert(_, pos)
+        | vir::Stmt::Fold(_, _, _, _, pos)
+        | vir::Stmt::Obtain(_, pos)
+        | vir::Stmt::PackageMagicWand(_, _, _, _, pos)
+        | vir::Stmt::ApplyMagicWand(_, pos) => *pos,
+        vir::Stmt::Inhale(expr)
+        | vir::Stmt::If(expr, _, _)
+        | vir::Stmt::Downcast(expr, _) => expr.pos(),
+        vir::Stmt::Assign(lhs, rhs, _) if rhs.pos().is_default() => lhs.pos(),
+        vir::Stmt::Assign(_, rhs, _) => rhs.pos(),
+        vir::Stmt::MethodCall(_, args, _) => args.iter()
+            .map(|arg| arg.pos())
+            .find(|pos| !pos.is_default())
+            .unwrap_or_default(),
+        _ => return None,
+    };
+    if pos.is_default() {
+        None
+    } else {
+        Some(pos)
+    }
+}
+
+/// Translates positions to comments, collecting the table of the positions.
+struct SourceLocations<'p, 'v: 'p, 'tcx: 'v> {
+    encoder: &'p Encoder<'v, 'tcx>,
+    table: RefCell<BTreeMap<u64, SpanReport>>,
+    /// The location of the last comment, used to skip comments that would
+    /// repeat it.
+    last: RefCell<Option<(String, usize)>>,
+}
+
+impl<'p, 'v: 'p, 'tcx: 'v> SourceLocations<'p, 'v, 'tcx> {
+    fn new(encoder: &'p Encoder<'v, 'tcx>) -> Self {
+        SourceLocations {
+            encoder,
+            table: RefCell::new(BTreeMap::new()),
+            last: RefCell::new(None),
+        }
+    }
+
+    fn comment(&self, pos: &vir::Position) -> Option<String> {
+        let span = self.encoder.error_manager().get_span(pos)?.primary_span()?;
+        let report = SpanReport::new(self.encoder.env().codemap(), span);
+        let location = (report.file.clone(), report.line);
+        self.table.borrow_mut().insert(pos.id(), report);
+        if self.last.borrow().as_ref() == Some(&location) {
+            return None;
+        }
+        let comment = format!("{}:{}", location.0, location.1);
+        *self.last.borrow_mut() = Some(location);
+        Some(comment)
+    }
+}
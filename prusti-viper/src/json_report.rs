@@ -0,0 +1,122 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A machine-readable report of the verification results, written to the
+//! file given by the `json_report` configuration flag.
+
+use prusti_interface::PrustiError;
+use rustc_span::source_map::SourceMap;
+use rustc_span::{FileName, Span};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+/// The version of the format of the report. It is incremented whenever a
+/// field is removed or changes meaning.
+pub const JSON_REPORT_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+pub struct JsonReport {
+    pub version: u32,
+    pub crate_name: String,
+    pub items: Vec<ItemReport>,
+    /// Errors that could not be attributed to one of the items.
+    pub other_errors: Vec<ErrorReport>,
+    pub backend: BackendReport,
+}
+
+/// The result of verifying a single function or method.
+#[derive(Serialize)]
+pub struct ItemReport {
+    pub name: String,
+    pub def_path: String,
+    pub span: SpanReport,
+    pub result: ItemResult,
+    pub error_count: usize,
+    pub errors: Vec<ErrorReport>,
+    /// Wall-clock time spent encoding the item, in milliseconds. The
+    /// encoded program is verified as a whole (see `BackendReport`).
+    pub encoding_time_ms: u128,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemResult {
+    Verified,
+    Failed,
+    Unsupported,
+    Trusted,
+    Cached,
+    /// The verification did not finish within the time limit.
+    TimedOut,
+}
+
+#[derive(Serialize)]
+pub struct ErrorReport {
+    pub message: String,
+    pub spans: Vec<SpanReport>,
+}
+
+/// A source location. Lines and columns start at 1.
+#[derive(Serialize)]
+pub struct SpanReport {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+#[derive(Serialize)]
+pub struct BackendReport {
+    pub name: String,
+    /// Wall-clock time spent verifying the Viper program, in milliseconds.
+    pub verification_time_ms: u128,
+    /// The number of quantifier instantiations, if reported by the backend.
+    /// The Viper backends do not expose it yet.
+    pub quantifier_instantiations: Option<u64>,
+}
+
+impl SpanReport {
+    pub fn new(codemap: &SourceMap, span: Span) -> Self {
+        let span = span.source_callsite();
+        let lo = codemap.lookup_char_pos(span.lo());
+        let hi = codemap.lookup_char_pos(span.hi());
+        let file = match &lo.file.name {
+            FileName::Real(real_name) => real_name.local_path()
+                .map(|path| path.display().to_string()),
+            _ => None,
+        }.unwrap_or_else(|| format!("{:?}", lo.file.name));
+        SpanReport {
+            file,
+            line: lo.line,
+            column: lo.col.0 + 1,
+            end_line: hi.line,
+            end_column: hi.col.0 + 1,
+        }
+    }
+}
+
+impl ErrorReport {
+    pub fn new(codemap: &SourceMap, error: &PrustiError) -> Self {
+        ErrorReport {
+            message: error.message().to_string(),
+            spans: error.span().primary_spans().iter()
+                .filter(|span| !span.is_dummy())
+                .map(|&span| SpanReport::new(codemap, span))
+                .collect(),
+        }
+    }
+}
+
+impl JsonReport {
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
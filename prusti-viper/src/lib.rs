@@ -27,5 +27,7 @@ extern crate rustc_attr;
 extern crate lazy_static;
 
 pub mod encoder;
+mod json_report;
 mod utils;
+mod verification_cache;
 pub mod verifier;
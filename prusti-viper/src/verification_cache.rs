@@ -0,0 +1,342 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A persistent cache of the procedures that have been verified successfully,
+//! used to avoid verifying again the procedures whose encoding did not change.
+//!
+//! The cache maps the def path of each procedure to a hash of the Viper
+//! methods encoded for it, together with all the Viper items that the methods
+//! transitively depend on. Since the contracts of the called procedures are
+//! inlined in the encoded methods, and the used functions, predicates and
+//! domains are part of the hash, a change to the specification of a callee or
+//! to a type definition invalidates the entries of the dependent procedures.
+
+use prusti_common::vir::{self, ExprWalker, StmtWalker, WithIdentifier};
+use prusti_common::vir::utils::walk_method;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use ::log::{debug, info, warn};
+
+/// The first line of a cache file. It has to be changed whenever the format
+/// of the file or the computation of the hashes changes, so that cache files
+/// written by other versions of Prusti are ignored.
+const CACHE_FILE_HEADER: &str = "prusti-verification-cache 1";
+
+pub struct VerificationCache {
+    path: PathBuf,
+    /// Hashes of the procedures that have been verified successfully, keyed
+    /// by their def path.
+    entries: BTreeMap<String, u64>,
+    /// Entries changed since the cache was loaded; [None] marks a removed
+    /// entry.
+    changes: BTreeMap<String, Option<u64>>,
+}
+
+impl VerificationCache {
+    /// Loads the cache from the given file. A missing, unreadable or stale
+    /// cache file results in an empty cache.
+    pub fn load(path: PathBuf) -> Self {
+        let entries = read_entries(&path).unwrap_or_default();
+        debug!("Loaded {} entries from the verification cache {}", entries.len(), path.display());
+        VerificationCache {
+            path,
+            entries,
+            changes: BTreeMap::new(),
+        }
+    }
+
+    /// Returns [true] iff the procedure has already been verified with the
+    /// given hash.
+    pub fn contains(&self, def_path: &str, hash: u64) -> bool {
+        self.entries.get(def_path) == Some(&hash)
+    }
+
+    pub fn insert(&mut self, def_path: String, hash: u64) {
+        self.entries.insert(def_path.clone(), hash);
+        self.changes.insert(def_path, Some(hash));
+    }
+
+    pub fn remove(&mut self, def_path: &str) {
+        if self.entries.remove(def_path).is_some() {
+            self.changes.insert(def_path.to_string(), None);
+        }
+    }
+
+    /// Writes the changed entries to the cache file. The file is read again
+    /// right before writing it, so that the entries written in the meantime
+    /// by other Prusti processes (e.g. for other crates of the same target
+    /// directory) are preserved.
+    pub fn save(&self) {
+        if self.changes.is_empty() {
+            return;
+        }
+        let mut entries = read_entries(&self.path).unwrap_or_default();
+        for (def_path, hash) in &self.changes {
+            match hash {
+                Some(hash) => entries.insert(def_path.clone(), *hash),
+                None => entries.remove(def_path),
+            };
+        }
+        let mut content = format!("{}\n", CACHE_FILE_HEADER);
+        for (def_path, hash) in &entries {
+            content.push_str(&format!("{:016x} {}\n", hash, def_path));
+        }
+        // Write to a temporary file first, so that a concurrent reader never
+        // sees a partially written cache.
+        let tmp_path = self.path.with_extension("tmp");
+        let result = self.path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&tmp_path, content))
+            .and_then(|_| fs::rename(&tmp_path, &self.path));
+        if let Err(error) = result {
+            warn!("Failed to write the verification cache {}: {}", self.path.display(), error);
+        }
+    }
+}
+
+fn read_entries(path: &PathBuf) -> Option<BTreeMap<String, u64>> {
+    let content = fs::read_to_string(path).ok()?;
+    let mut lines = content.lines();
+    if lines.next()? != CACHE_FILE_HEADER {
+        info!("Ignoring the verification cache {}, which has an unknown format", path.display());
+        return None;
+    }
+    lines
+        .map(|line| {
+            let (hash, def_path) = line.split_once(' ')?;
+            Some((def_path.to_string(), u64::from_str_radix(hash, 16).ok()?))
+        })
+        .collect()
+}
+
+/// Computes a hash of the given methods and of all the items of the program
+/// that they transitively depend on. Positions and comments are not part of
+/// the hash, so that moving a procedure in the source file does not change
+/// its hash.
+pub fn hash_methods(program: &vir::Program, methods: &[&vir::CfgMethod]) -> u64 {
+    let mut collector = DependencyCollector::default();
+    let mut hasher = DefaultHasher::new();
+    for method in methods {
+        walk_method(method, &mut collector);
+        for var in method.get_all_vars() {
+            collector.add_type(&var.typ);
+        }
+        for line in method.to_string().lines() {
+            if !line.trim_start().starts_with("//") {
+                line.hash(&mut hasher);
+            }
+        }
+    }
+    collector.add_dependencies_of(program);
+
+    for domain in &program.domains {
+        if collector.domains.contains(&domain.name) {
+            domain.to_string().hash(&mut hasher);
+        }
+    }
+    for function in &program.functions {
+        if collector.functions.contains(&function.get_identifier()) {
+            function.to_string().hash(&mut hasher);
+        }
+    }
+    for predicate in &program.viper_predicates {
+        if collector.predicates.contains(predicate.name()) {
+            predicate.to_string().hash(&mut hasher);
+        }
+    }
+    for field in &program.fields {
+        format!("{:?}", field).hash(&mut hasher);
+    }
+    for method in &program.builtin_methods {
+        method.to_string().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Collects the identifiers of the functions, and the names of the predicates
+/// and domains used by some VIR code.
+#[derive(Default)]
+struct DependencyCollector {
+    functions: BTreeSet<String>,
+    predicates: BTreeSet<String>,
+    domains: BTreeSet<String>,
+    /// Snapshot and domain types (as strings), used to find the domains
+    /// declaring them.
+    types: BTreeSet<String>,
+}
+
+impl DependencyCollector {
+    fn add_type(&mut self, typ: &vir::Type) {
+        match typ {
+            vir::Type::TypedRef(name) => {
+                self.predicates.insert(name.clone());
+            }
+            vir::Type::Snapshot(_) | vir::Type::Domain(_) => {
+                self.types.insert(typ.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.functions.len() + self.predicates.len() + self.domains.len() + self.types.len()
+    }
+
+    /// Adds the dependencies of the functions, predicates and domains that
+    /// have been collected so far, until a fixpoint is reached.
+    fn add_dependencies_of(&mut self, program: &vir::Program) {
+        loop {
+            let count = self.count();
+            for function in &program.functions {
+                if self.functions.contains(&function.get_identifier()) {
+                    for var in &function.formal_args {
+                        self.add_type(&var.typ);
+                    }
+                    self.add_type(&function.return_type);
+                    for expr in function.pres.iter()
+                        .chain(function.posts.iter())
+                        .chain(function.body.iter())
+                    {
+                        ExprWalker::walk(self, expr);
+                    }
+                }
+            }
+            for predicate in &program.viper_predicates {
+                if self.predicates.contains(predicate.name()) {
+                    match predicate {
+                        vir::Predicate::Struct(p) => {
+                            if let Some(body) = &p.body {
+                                ExprWalker::walk(self, body);
+                            }
+                        }
+                        vir::Predicate::Enum(p) => ExprWalker::walk(self, &p.body()),
+                        vir::Predicate::Bodyless(..) => {}
+                    }
+                }
+            }
+            for domain in &program.domains {
+                let declares_used_type = domain.functions.iter().any(|func| {
+                    self.types.contains(&func.return_type.to_string()) ||
+                        func.formal_args.iter().any(|arg| self.types.contains(&arg.typ.to_string()))
+                });
+                if declares_used_type || self.domains.contains(&domain.name) {
+                    self.domains.insert(domain.name.clone());
+                    for func in &domain.functions {
+                        for arg in &func.formal_args {
+                            self.add_type(&arg.typ);
+                        }
+                        self.add_type(&func.return_type);
+                    }
+                    for axiom in &domain.axioms {
+                        ExprWalker::walk(self, &axiom.expr);
+                    }
+                }
+            }
+            if self.count() == count {
+                break;
+            }
+        }
+    }
+}
+
+impl ExprWalker for DependencyCollector {
+    fn walk_local_var(&mut self, var: &vir::LocalVar) {
+        self.add_type(&var.typ);
+    }
+
+    fn walk_func_app(
+        &mut self,
+        name: &str,
+        args: &Vec<vir::Expr>,
+        formal_args: &Vec<vir::LocalVar>,
+        return_type: &vir::Type,
+        _pos: &vir::Position,
+    ) {
+        self.functions.insert(vir::compute_identifier(name, formal_args, return_type));
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+    }
+
+    fn walk_domain_func_app(
+        &mut self,
+        func: &vir::DomainFunc,
+        args: &Vec<vir::Expr>,
+        _pos: &vir::Position,
+    ) {
+        self.domains.insert(func.domain_name.clone());
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+    }
+
+    fn walk_predicate_access_predicate(
+        &mut self,
+        name: &str,
+        arg: &vir::Expr,
+        _perm_amount: vir::PermAmount,
+        _pos: &vir::Position,
+    ) {
+        self.predicates.insert(name.to_string());
+        ExprWalker::walk(self, arg);
+    }
+
+    fn walk_unfolding(
+        &mut self,
+        name: &str,
+        args: &Vec<vir::Expr>,
+        body: &vir::Expr,
+        _perm: vir::PermAmount,
+        _variant: &vir::MaybeEnumVariantIndex,
+        _pos: &vir::Position,
+    ) {
+        self.predicates.insert(name.to_string());
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+        ExprWalker::walk(self, body);
+    }
+}
+
+impl StmtWalker for DependencyCollector {
+    fn walk_expr(&mut self, expr: &vir::Expr) {
+        ExprWalker::walk(self, expr);
+    }
+
+    fn walk_local_var(&mut self, var: &vir::LocalVar) {
+        self.add_type(&var.typ);
+    }
+
+    fn walk_fold(
+        &mut self,
+        predicate_name: &str,
+        args: &Vec<vir::Expr>,
+        _perm: &vir::PermAmount,
+        _variant: &vir::MaybeEnumVariantIndex,
+        _pos: &vir::Position,
+    ) {
+        self.predicates.insert(predicate_name.to_string());
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+    }
+
+    fn walk_unfold(
+        &mut self,
+        predicate_name: &str,
+        args: &Vec<vir::Expr>,
+        _perm: &vir::PermAmount,
+        _variant: &vir::MaybeEnumVariantIndex,
+    ) {
+        self.predicates.insert(predicate_name.to_string());
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+    }
+}
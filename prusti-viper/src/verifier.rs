@@ -6,23 +6,31 @@
 
 use prusti_common::vir::{self, optimizations, ToViper, ToViperDecl};
 use prusti_common::{
-    config, report::log, verification_context::VerifierBuilder, verification_service::*, Stopwatch,
+    config, report::{log, user}, verification_context::VerifierBuilder, verification_service::*,
+    Stopwatch,
 };
 use crate::encoder::Encoder;
+use crate::encoder::ErrorCtxt;
+use crate::verification_cache::{self, VerificationCache};
+use crate::json_report::{
+    BackendReport, ErrorReport, ItemReport, ItemResult, JsonReport, SpanReport,
+    JSON_REPORT_VERSION,
+};
 // use prusti_filter::validators::Validator;
 use prusti_interface::data::VerificationResult;
-use prusti_interface::data::VerificationTask;
+use prusti_interface::data::{ProcedureDefId, VerificationTask};
 use prusti_interface::environment::Environment;
 use prusti_interface::PrustiError;
 // use prusti_interface::specifications::TypedSpecificationMap;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use viper::{self, VerificationBackend, Viper};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::fs::{create_dir_all, canonicalize};
 use std::ffi::OsString;
 use prusti_interface::specs::typed;
 use ::log::{info, debug, error};
-use prusti_server::{PrustiServerConnection, ServerSideService, VerifierRunner};
+use prusti_server::{verify_in_parallel, PrustiServerConnection, ServerSideService, VerifierRunner};
 use rustc_span::DUMMY_SP;
 
 // /// A verifier builder is an object that lives entire program's
@@ -247,10 +255,26 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
         if config::simplify_encoding() {
             stopwatch.start_next("optimizing Viper program");
             let source_file_name = self.encoder.env().source_file_name();
-            program = program.optimized(&source_file_name);
+            let pure_functions = self.encoder.get_pure_function_names();
+            program = program.optimized(&source_file_name, &pure_functions);
+        }
+
+        // The checks of the specifications are not cached, because their
+        // expected outcome is a failure.
+        let mut cache = config::cache_path()
+            .filter(|_| !config::check_specs_only())
+            .map(|path| VerificationCache::load(PathBuf::from(path)));
+        let mut verified_procedures = vec![];
+        let mut cached_procedures = vec![];
+        if let Some(cache) = &cache {
+            stopwatch.start_next("looking up the verification cache");
+            let (verified, cached) = self.skip_cached_procedures(task, cache, &mut program);
+            verified_procedures = verified;
+            cached_procedures = cached;
         }
 
         stopwatch.start_next("verifying Viper program");
+        let verification_start = Instant::now();
         let source_path = self.env.source_path();
         let program_name = source_path
             .file_name()
@@ -279,7 +303,14 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
                 program_name,
                 backend_config: Default::default(),
             };
-            service.verify(request)
+            let jobs = config::verify_parallel_jobs();
+            // The time limit applies to each request, so with a limit the
+            // methods are always verified separately.
+            if jobs > 1 || config::verification_timeout_per_method_secs() > 0 {
+                verify_in_parallel(Arc::new(service), request, jobs.max(1))
+            } else {
+                service.verify(request)
+            }
         } else {
             let mut stopwatch = Stopwatch::start("prusti-viper", "JVM startup");
             let verifier_builder = VerifierBuilder::new();
@@ -288,42 +319,316 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
                 runner.verify(program, program_name.as_str())
             })
         };
+        let verification_time = verification_start.elapsed();
 
         stopwatch.finish();
 
-        let verification_errors = match verification_result {
-            viper::VerificationResult::Success() => vec![],
-            viper::VerificationResult::Failure(errors) => errors,
+        let (mut verification_errors, timed_out_methods) = match verification_result {
+            viper::VerificationResult::Success() => (vec![], None),
+            viper::VerificationResult::Failure(errors) => (errors, None),
+            viper::VerificationResult::Timeout { errors, methods } => (errors, Some(methods)),
             viper::VerificationResult::ConsistencyErrors(errors) => {
                 debug_assert!(!errors.is_empty());
-                errors.iter().for_each(|e| {
-                    PrustiError::internal(
+                let prusti_errors: Vec<_> = errors.iter().map(|e| {
+                    (None, PrustiError::internal(
                         format!("consistency error: {}", e), DUMMY_SP.into()
-                    ).emit(self.env)
-                });
+                    ))
+                }).collect();
+                self.write_json_report(
+                    task, &cached_procedures, &[], &prusti_errors, true, verification_time
+                );
+                for (_, prusti_error) in prusti_errors {
+                    prusti_error.emit(self.env);
+                }
                 return VerificationResult::Failure;
             }
             viper::VerificationResult::JavaException(exception) => {
                 error!("Java exception: {}", exception.get_stack_trace());
-                PrustiError::internal(
+                let prusti_error = PrustiError::internal(
                     format!("{}", exception), DUMMY_SP.into()
-                ).emit(self.env);
+                );
+                self.write_json_report(
+                    task,
+                    &cached_procedures,
+                    &[],
+                    &[(None, prusti_error.clone())],
+                    true,
+                    verification_time,
+                );
+                prusti_error.emit(self.env);
                 return VerificationResult::Failure;
             }
         };
 
-        if encoding_errors_count == 0 && verification_errors.is_empty() {
-            VerificationResult::Success
-        } else {
+        // If the timed-out methods are not known (e.g. because the whole
+        // program was verified by a single request), all the verified
+        // procedures are reported as timed out.
+        let timed_out_procedures: Vec<ProcedureDefId> = match &timed_out_methods {
+            Some(methods) => task.procedures.iter()
+                .copied()
+                .filter(|&proc_id| !cached_procedures.contains(&proc_id))
+                .filter(|&proc_id| {
+                    let method_names = self.encoder.get_procedure_method_names(proc_id);
+                    !method_names.is_empty() && (methods.is_empty()
+                        || method_names.iter().any(|name| methods.contains(name)))
+                })
+                .collect(),
+            None => vec![],
+        };
+
+        if let Some(cache) = &mut cache {
+            let success = encoding_errors_count == 0
+                && verification_errors.is_empty()
+                && timed_out_methods.is_none();
+            for (def_path, hash) in verified_procedures {
+                if success {
+                    cache.insert(def_path, hash);
+                } else {
+                    cache.remove(&def_path);
+                }
+            }
+            cache.save();
+        }
+
+        // The failure of the check of a precondition shows that it is satisfiable.
+        let mut satisfiable_procedures = vec![];
+        verification_errors.retain(|verification_error| {
             let error_manager = self.encoder.error_manager();
+            match error_manager.get_error_ctxt(verification_error) {
+                Some(ErrorCtxt::AssertPreconditionUnsatisfiable) => {
+                    satisfiable_procedures.extend(error_manager.get_procedure(verification_error));
+                    false
+                }
+                _ => true,
+            }
+        });
 
-            for verification_error in verification_errors {
-                debug!("Verification error: {:?}", verification_error);
-                let prusti_error = error_manager.translate_verification_error(&verification_error);
-                debug!("Prusti error: {:?}", prusti_error);
-                prusti_error.emit(self.env);
+        // The procedure of each error is recorded for the JSON report.
+        let mut prusti_errors: Vec<(Option<ProcedureDefId>, PrustiError)> = vec![];
+        for verification_error in verification_errors {
+            debug!("Verification error: {:?}", verification_error);
+            let mut prusti_error = self.encoder.error_manager()
+                .translate_verification_error(&verification_error);
+            let proc_def_id = self.encoder.error_manager().get_procedure(&verification_error);
+            // Several Viper errors (e.g. of different optimized copies of
+            // the same assertion) can be mapped to the same Rust error.
+            if prusti_errors.iter().any(|(_, error)| error.is_duplicate_of(&prusti_error)) {
+                debug!("Duplicate Prusti error: {:?}", prusti_error);
+                continue;
             }
+            if let Some(bound) = proc_def_id.and_then(|proc_id| self.encoder.get_loop_bound(proc_id)) {
+                prusti_error = prusti_error.add_note(format!(
+                    "bounded verification only: the error was found considering only the \
+                    executions in which each loop does at most {} iterations",
+                    bound,
+                ));
+            }
+            if let Some(model) = &verification_error.counterexample {
+                if let Some(counterexample) = self.encoder.translate_counterexample(model) {
+                    prusti_error = prusti_error
+                        .add_note(format!("counterexample: {}", counterexample));
+                }
+            }
+            debug!("Prusti error: {:?}", prusti_error);
+            prusti_errors.push((proc_def_id, prusti_error));
+        }
+        for &proc_id in &timed_out_procedures {
+            prusti_errors.push((Some(proc_id), self.timeout_error(proc_id)));
+        }
+        for &proc_id in &task.procedures {
+            if self.encoder.get_precondition_check_name(proc_id).is_some()
+                && !satisfiable_procedures.contains(&proc_id)
+                && !timed_out_procedures.contains(&proc_id)
+            {
+                prusti_errors.push((Some(proc_id), self.unsatisfiable_precondition_warning(proc_id)));
+            }
+            let is_verified = !config::check_specs_only() && !self.encoder.is_trusted(proc_id);
+            if let Some(bound) = self.encoder.get_loop_bound(proc_id).filter(|_| is_verified) {
+                prusti_errors.push((Some(proc_id), self.bounded_verification_warning(proc_id, bound)));
+            }
+        }
+        self.write_json_report(
+            task,
+            &cached_procedures,
+            &timed_out_procedures,
+            &prusti_errors,
+            false,
+            verification_time,
+        );
+
+        let success = encoding_errors_count == 0
+            && prusti_errors.iter().all(|(_, error)| !error.is_error());
+        for (_, prusti_error) in prusti_errors {
+            prusti_error.emit(self.env);
+        }
+        if success {
+            VerificationResult::Success
+        } else {
             VerificationResult::Failure
         }
     }
+
+    /// Reports that the verification of the procedure did not finish within
+    /// the time limit. This is a warning if `config::allow_timeouts` is set.
+    fn timeout_error(&self, proc_id: ProcedureDefId) -> PrustiError {
+        let message = match config::verification_timeout_per_method_secs() {
+            0 => "verification timed out".to_string(),
+            timeout => format!("verification timed out after {} seconds", timeout),
+        };
+        let span = self.env.get_item_span(proc_id).into();
+        let error = if config::allow_timeouts() {
+            PrustiError::warning(message, span)
+        } else {
+            PrustiError::verification(message, span)
+        };
+        error.set_help(
+            "the result of this function is unknown; consider simplifying its \
+            specification or increasing `verification_timeout_per_method_secs`"
+        )
+    }
+
+    /// Reports that the precondition of the procedure is unsatisfiable, which
+    /// is found when only checking the specifications.
+    fn unsatisfiable_precondition_warning(&self, proc_id: ProcedureDefId) -> PrustiError {
+        PrustiError::warning(
+            "the precondition of this function is unsatisfiable",
+            self.env.tcx().def_span(proc_id).into(),
+        ).set_help(
+            "the function can never be called; check that its `#[requires(..)]` are not \
+            contradictory"
+        )
+    }
+
+    /// Reports that the procedure is only verified for the executions in which
+    /// each loop does at most `bound` iterations, as requested by `#[bounded(..)]`.
+    fn bounded_verification_warning(&self, proc_id: ProcedureDefId, bound: u32) -> PrustiError {
+        PrustiError::warning(
+            format!(
+                "bounded verification only: this function is only verified for the \
+                executions in which each loop does at most {} iterations",
+                bound,
+            ),
+            self.env.tcx().def_span(proc_id).into(),
+        ).set_help(
+            "the longer executions are not checked, and the unsupported statements are \
+            replaced by havocking the places that they modify; remove `#[bounded(..)]` \
+            and add loop invariants to verify all the executions"
+        )
+    }
+
+    /// Writes the result of each procedure to the file given by
+    /// `config::json_report`, if any. If `backend_failed`, the procedures that
+    /// were sent to the backend are reported as failed.
+    fn write_json_report(
+        &self,
+        task: &VerificationTask,
+        cached_procedures: &[ProcedureDefId],
+        timed_out_procedures: &[ProcedureDefId],
+        verification_errors: &[(Option<ProcedureDefId>, PrustiError)],
+        backend_failed: bool,
+        verification_time: Duration,
+    ) {
+        let path = if let Some(path) = config::json_report() {
+            path
+        } else {
+            return;
+        };
+        let codemap = self.env.codemap();
+        let mut items = vec![];
+        for &proc_id in &task.procedures {
+            let stats = self.encoder.get_procedure_encoding_stats(proc_id);
+            let errors: Vec<_> = stats.iter()
+                .flat_map(|stats| stats.errors.iter())
+                .chain(
+                    verification_errors.iter()
+                        .filter(|(error_proc_id, _)| *error_proc_id == Some(proc_id))
+                        .map(|(_, error)| error)
+                )
+                .filter(|error| error.is_error())
+                .map(|error| ErrorReport::new(codemap, error))
+                .collect();
+            let result = if self.encoder.is_trusted(proc_id) {
+                ItemResult::Trusted
+            } else if stats.as_ref().map_or(false, |stats| stats.unsupported) {
+                ItemResult::Unsupported
+            } else if timed_out_procedures.contains(&proc_id) {
+                ItemResult::TimedOut
+            } else if !errors.is_empty() {
+                ItemResult::Failed
+            } else if cached_procedures.contains(&proc_id) {
+                ItemResult::Cached
+            } else if backend_failed {
+                ItemResult::Failed
+            } else {
+                ItemResult::Verified
+            };
+            items.push(ItemReport {
+                name: self.env.get_absolute_item_name(proc_id),
+                def_path: self.env.get_item_def_path(proc_id),
+                span: SpanReport::new(codemap, self.env.get_item_span(proc_id)),
+                result,
+                error_count: errors.len(),
+                errors,
+                encoding_time_ms: stats.map_or(0, |stats| stats.duration.as_millis()),
+            });
+        }
+        let other_errors = verification_errors.iter()
+            .filter(|(proc_id, _)| {
+                proc_id.map_or(true, |proc_id| !task.procedures.contains(&proc_id))
+            })
+            .map(|(_, error)| ErrorReport::new(codemap, error))
+            .collect();
+        let report = JsonReport {
+            version: JSON_REPORT_VERSION,
+            crate_name: self.env.crate_name(),
+            items,
+            other_errors,
+            backend: BackendReport {
+                name: config::viper_backend(),
+                verification_time_ms: verification_time.as_millis(),
+                quantifier_instantiations: None,
+            },
+        };
+        if let Err(error) = report.write(Path::new(&path)) {
+            PrustiError::warning(
+                format!("could not write the JSON report to '{}': {}", path, error),
+                DUMMY_SP.into(),
+            ).emit(self.env);
+        }
+    }
+
+    /// Removes from the program the methods of the procedures that are
+    /// recorded in the cache as verified with the same encoding, and reports
+    /// them as verified. Returns the def paths and hashes of the procedures
+    /// that remain to be verified, and the procedures that have been skipped.
+    fn skip_cached_procedures(
+        &self,
+        task: &VerificationTask,
+        cache: &VerificationCache,
+        program: &mut vir::Program,
+    ) -> (Vec<(String, u64)>, Vec<ProcedureDefId>) {
+        let mut verified_procedures = vec![];
+        let mut cached_procedures = vec![];
+        let mut cached_methods = vec![];
+        for &proc_id in &task.procedures {
+            let method_names = self.encoder.get_procedure_method_names(proc_id);
+            if method_names.is_empty() {
+                continue;
+            }
+            let methods: Vec<_> = program.methods.iter()
+                .filter(|method| method_names.contains(&method.name()))
+                .collect();
+            let hash = verification_cache::hash_methods(program, &methods);
+            let def_path = self.env.get_item_def_path(proc_id);
+            if !config::force_full_verification() && cache.contains(&def_path, hash) {
+                user::message(format!("{}: cached: OK", self.env.get_absolute_item_name(proc_id)));
+                cached_methods.extend(method_names);
+                cached_procedures.push(proc_id);
+            } else {
+                verified_procedures.push((def_path, hash));
+            }
+        }
+        program.methods.retain(|method| !cached_methods.contains(&method.name()));
+        (verified_procedures, cached_procedures)
+    }
 }
@@ -49,6 +49,20 @@ impl rustc_driver::Callbacks for PrustiCompilerCalls {
             let mut spec_collector = specs::SpecCollector::new(tcx);
             intravisit::walk_crate(&mut spec_collector, &krate);
             let def_spec = spec_collector.build_def_specs(&env);
+            spec_checker.check_unbounded_arithmetic(tcx, &def_spec);
+            spec_checker.report_warnings(&env);
+            spec_checker.check_spec_calls(&env, &def_spec);
+            spec_checker.report_spec_call_errors(&env);
+            spec_checker.check_triggers(tcx, &def_spec);
+            spec_checker.report_trigger_errors(&env);
+            spec_checker.check_spec_eq_types(&env);
+            spec_checker.report_spec_eq_errors(&env);
+            spec_checker.check_dead_specs(&env, &def_spec);
+            spec_checker.report_dead_specs(&env);
+            compiler.session().abort_if_errors();
+            if config::export_specs() {
+                spec_collector.export_specs(&env);
+            }
             if config::print_typeckd_specs() {
                 let mut values: Vec<_> = def_spec
                     .specs
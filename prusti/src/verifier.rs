@@ -3,12 +3,46 @@
 use prusti_interface::specs::typed;
 use log::{debug, trace, warn};
 use prusti_interface::{
-    data::{VerificationResult, VerificationTask},
+    data::{ProcedureDefId, VerificationResult, VerificationTask},
     environment::Environment,
 };
 use prusti_viper::verifier::Verifier;
 use prusti_common::config;
 use prusti_common::report::user;
+use std::fs;
+use std::path::PathBuf;
+
+/// Keep only the procedures selected by the `VERIFY_ONLY_DEF_PATHS` and
+/// `VERIFY_ONLY_FILES` flags. A procedure is selected if it matches any of the
+/// two lists; if both are empty, all procedures are selected.
+///
+/// The other procedures are not verified, but their contracts are still
+/// encoded (and assumed) wherever they are called.
+fn select_procedures(
+    env: &Environment<'_>,
+    procedures: Vec<ProcedureDefId>,
+) -> (Vec<ProcedureDefId>, usize) {
+    let def_paths = config::verify_only_def_paths();
+    let files = config::verify_only_files();
+    if def_paths.is_empty() && files.is_empty() {
+        return (procedures, 0);
+    }
+    let canonicalize = |path: PathBuf| fs::canonicalize(&path).unwrap_or(path);
+    let files: Vec<PathBuf> = files.into_iter()
+        .map(|file| canonicalize(PathBuf::from(file)))
+        .collect();
+    let total = procedures.len();
+    let selected: Vec<_> = procedures.into_iter()
+        .filter(|&proc_id| {
+            def_paths.contains(&env.get_item_def_path(proc_id)) ||
+                env.get_item_source_path(proc_id)
+                    .map(|path| files.contains(&canonicalize(path)))
+                    .unwrap_or(false)
+        })
+        .collect();
+    let skipped = total - selected.len();
+    (selected, skipped)
+}
 
 pub fn verify<'tcx>(
     env: Environment<'tcx>,
@@ -21,6 +55,16 @@ pub fn verify<'tcx>(
     } else {
         debug!("Prepare verification task...");
         let annotated_procedures = env.get_annotated_procedures();
+        let (annotated_procedures, skipped_procedures) =
+            select_procedures(&env, annotated_procedures);
+        if skipped_procedures > 0 {
+            // Printed also in quiet mode, because the selection has been
+            // explicitly requested by the user (e.g. `cargo prusti --diff`).
+            eprintln!(
+                "Prusti: skipped the verification of {} items not affected by the changes",
+                skipped_procedures
+            );
+        }
         let verification_task = VerificationTask {
             procedures: annotated_procedures,
         };
@@ -90,6 +90,7 @@ fn main() {
             // Scala
             java_class!("scala.Some", vec![
                 constructor!(),
+                method!("get"),
             ]),
             java_class!("scala.None$", vec![
                 object_getter!(),
@@ -304,6 +305,7 @@ fn main() {
             ]),
             java_class!("viper.silver.ast.Function", vec![
                 constructor!(),
+                method!("name"),
             ]),
             java_class!("viper.silver.ast.GeCmp", vec![
                 constructor!(),
@@ -606,6 +608,11 @@ fn main() {
                 method!("fullId"),
                 method!("reason"),
                 method!("readableMessage", "()Ljava/lang/String;"),
+                method!("counterexample"),
+                method!("offendingNode"),
+            ]),
+            java_class!("viper.silver.verifier.Counterexample", vec![
+                method!("model"),
             ]),
             java_class!("viper.silver.verifier.ErrorReason", vec![
                 method!("id"),
@@ -615,6 +622,9 @@ fn main() {
             java_class!("viper.silver.verifier.ConsistencyError", vec![
                 constructor!(),
             ]),
+            java_class!("viper.silver.verifier.errors.PostconditionViolated", vec![
+                method!("member"),
+            ]),
         ])
         .generate(&generated_dir)
         .unwrap_or_else(|e| {
@@ -17,6 +17,13 @@ pub enum VerificationResult {
     ConsistencyErrors(Vec<String>),
     /// The verification raised a Java exception.
     JavaException(JavaException),
+    /// The verification did not finish within the time limit. The errors found
+    /// before the timeout are reported, together with the names of the methods
+    /// whose verification timed out, if they are known.
+    Timeout {
+        errors: Vec<VerificationError>,
+        methods: Vec<String>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -25,6 +32,13 @@ pub struct VerificationError {
     pub pos_id: Option<String>,
     pub reason_pos_id: Option<String>,
     pub message: String,
+    /// The model of the counterexample reported by the backend, if one was
+    /// requested.
+    pub counterexample: Option<String>,
+    /// The name of the Viper function in which the error occurred, if the
+    /// offending node is a function or a postcondition of a function. The
+    /// errors of an application of a function are not attributed to it.
+    pub offending_function: Option<String>,
 }
 
 impl VerificationError {
@@ -33,12 +47,16 @@ impl VerificationError {
         pos_id: Option<String>,
         reason_pos_id: Option<String>,
         message: String,
+        counterexample: Option<String>,
+        offending_function: Option<String>,
     ) -> Self {
         VerificationError {
             full_id,
             pos_id,
             reason_pos_id,
             message,
+            counterexample,
+            offending_function,
         }
     }
 }
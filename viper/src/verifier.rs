@@ -16,6 +16,7 @@ use std::path::PathBuf;
 use verification_backend::VerificationBackend;
 use verification_result::VerificationError;
 use verification_result::VerificationResult;
+use viper_sys::wrappers::scala;
 use viper_sys::wrappers::viper::*;
 
 pub mod state {
@@ -161,6 +162,7 @@ impl<'a> Verifier<'a, state::Started> {
 
         if is_failure {
             let mut errors: Vec<VerificationError> = vec![];
+            let mut timed_out = false;
 
             let viper_errors = self.jni.seq_to_vec(self.jni.unwrap_result(
                 silver::verifier::Failure::with(self.env).call_errors(viper_result),
@@ -177,6 +179,17 @@ impl<'a> Verifier<'a, state::Started> {
                     .jni
                     .is_instance_of(viper_error, "viper/silver/verifier/VerificationError");
 
+                // Reported when the time limit of `--timeout` is exceeded
+                let is_timeout = self
+                    .jni
+                    .is_instance_of(viper_error, "viper/silver/verifier/TimeoutOccurred");
+
+                if is_timeout {
+                    debug!("The verification timed out: {}", self.jni.to_string(viper_error));
+                    timed_out = true;
+                    continue;
+                }
+
                 if !is_verification_error {
                     let is_aborted_exceptionally = self
                         .jni
@@ -259,15 +272,72 @@ impl<'a> Verifier<'a, state::Started> {
                         None
                     };
 
+                let counterexample_option = self
+                    .jni
+                    .unwrap_result(verification_error_wrapper.call_counterexample(viper_error));
+
+                let counterexample = if self
+                    .jni
+                    .is_instance_of(counterexample_option, "scala/Some")
+                {
+                    let viper_counterexample = self.jni.unwrap_result(
+                        scala::Some::with(self.env).call_get(counterexample_option),
+                    );
+                    let model = self.jni.unwrap_result(
+                        silver::verifier::Counterexample::with(self.env)
+                            .call_model(viper_counterexample),
+                    );
+                    Some(self.jni.to_string(model))
+                } else {
+                    None
+                };
+
+                // The postcondition of a function is reported on the
+                // postcondition expression; the function is the member.
+                // Applications of a function are not attributed to it, their
+                // failures are errors of the caller.
+                let offending_node = if self
+                    .jni
+                    .is_instance_of(viper_error, "viper/silver/verifier/errors/PostconditionViolated")
+                {
+                    self.jni.unwrap_result(
+                        silver::verifier::errors::PostconditionViolated::with(self.env)
+                            .call_member(viper_error),
+                    )
+                } else {
+                    self.jni
+                        .unwrap_result(verification_error_wrapper.call_offendingNode(viper_error))
+                };
+
+                let offending_function = if self
+                    .jni
+                    .is_instance_of(offending_node, "viper/silver/ast/Function")
+                {
+                    Some(self.jni.get_string(self.jni.unwrap_result(
+                        silver::ast::Function::with(self.env).call_name(offending_node),
+                    )))
+                } else {
+                    None
+                };
+
                 errors.push(VerificationError::new(
                     error_full_id,
                     pos_id,
                     reason_pos_id,
                     message,
+                    counterexample,
+                    offending_function,
                 ))
             }
 
-            VerificationResult::Failure(errors)
+            if timed_out {
+                VerificationResult::Timeout {
+                    errors,
+                    methods: vec![],
+                }
+            } else {
+                VerificationResult::Failure(errors)
+            }
         } else {
             VerificationResult::Success()
         }
@@ -257,3 +257,119 @@ fn success_with_complex_post_condition() {
 
     assert_eq!(verification_result, VerificationResult::Success());
 }
+
+#[test]
+fn failure_with_function_postcondition_is_attributed_to_the_function() {
+    setup();
+
+    let verification_context: VerificationContext = VIPER.new_verification_context();
+    let ast = verification_context.new_ast_factory();
+
+    // function foo(x: Int): Int ensures result > 0 { x }
+    let function = ast.function(
+        "foo",
+        &[ast.local_var_decl("x", ast.int_type())],
+        ast.int_type(),
+        &[],
+        &[ast.gt_cmp(
+            ast.result_with_pos(ast.int_type(), ast.no_position()),
+            ast.int_lit(0),
+        )],
+        ast.no_position(),
+        Some(ast.local_var("x", ast.int_type())),
+    );
+
+    let program = ast.program(&[], &[], &[function], &[], &[]);
+
+    let verifier = verification_context.new_verifier(viper::VerificationBackend::Silicon, None);
+
+    let verification_result = verifier.verify(program);
+
+    if let VerificationResult::Failure(errors) = verification_result {
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].offending_function, Some("foo".to_string()));
+    } else {
+        assert!(false)
+    }
+}
+
+#[test]
+fn failure_with_function_body_is_attributed_to_the_function() {
+    setup();
+
+    let verification_context: VerificationContext = VIPER.new_verification_context();
+    let ast = verification_context.new_ast_factory();
+
+    // function foo(x: Int): Int { x \ 0 }
+    let function = ast.function(
+        "foo",
+        &[ast.local_var_decl("x", ast.int_type())],
+        ast.int_type(),
+        &[],
+        &[],
+        ast.no_position(),
+        Some(ast.div(ast.local_var("x", ast.int_type()), ast.int_lit(0))),
+    );
+
+    let program = ast.program(&[], &[], &[function], &[], &[]);
+
+    let verifier = verification_context.new_verifier(viper::VerificationBackend::Silicon, None);
+
+    let verification_result = verifier.verify(program);
+
+    if let VerificationResult::Failure(errors) = verification_result {
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].offending_function, Some("foo".to_string()));
+    } else {
+        assert!(false)
+    }
+}
+
+#[test]
+fn failure_with_function_application_is_not_attributed_to_the_function() {
+    setup();
+
+    let verification_context: VerificationContext = VIPER.new_verification_context();
+    let ast = verification_context.new_ast_factory();
+
+    // function foo(x: Int): Int requires x > 0 { x }
+    let function = ast.function(
+        "foo",
+        &[ast.local_var_decl("x", ast.int_type())],
+        ast.int_type(),
+        &[ast.gt_cmp(ast.local_var("x", ast.int_type()), ast.int_lit(0))],
+        &[],
+        ast.no_position(),
+        Some(ast.local_var("x", ast.int_type())),
+    );
+
+    // method bar() { assert foo(0) == 0 }
+    let application = ast.func_app(
+        "foo",
+        &[ast.int_lit(0)],
+        ast.int_type(),
+        ast.no_position(),
+    );
+    let assertion = ast.assert(ast.eq_cmp(application, ast.int_lit(0)), ast.no_position());
+
+    let body = ast.seqn(&[assertion], &[]);
+
+    let method = ast.method("bar", &[], &[], &[], &[], Some(body));
+
+    let program = ast.program(&[], &[], &[function], &[], &[method]);
+
+    let verifier = verification_context.new_verifier(viper::VerificationBackend::Silicon, None);
+
+    let verification_result = verifier.verify(program);
+
+    if let VerificationResult::Failure(errors) = verification_result {
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].full_id,
+            "application.precondition:assertion.false".to_string()
+        );
+        assert_eq!(errors[0].offending_function, None);
+    } else {
+        assert!(false)
+    }
+}